@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::disasm::{Decoder, FlowKind};
+use crate::image::Image;
+use crate::json;
+
+use super::cfg::{function_cfg, EdgeKind};
+use super::exceptions::frame_table;
+use super::functions::Function;
+use super::plt::plt_names;
+
+/// Where a call goes
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Callee {
+    // A function of the binary, by its start
+    Function(u64),
+    // A symbol of another module, as symbol@plt when called through the PLT and symbol when through the GOT
+    Import(String),
+    // Through a register or memory that isn't a GOT slot
+    Unresolved,
+}
+
+#[derive(Debug, Clone)]
+pub struct Call {
+    // Start of the calling function
+    pub caller: u64,
+    // Address of the call instruction
+    pub address: u64,
+    pub callee: Callee,
+    // A jump to another function rather than a call
+    pub tail: bool,
+}
+
+/// The calls between the functions of a binary and to the symbols it imports
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+    pub functions: Vec<Function>,
+    pub calls: Vec<Call>,
+}
+
+impl CallGraph {
+    /// The calls made by `functions`. Calls through a PLT stub or a GOT slot resolve to the symbol of the
+    /// relocation the dynamic linker fills the slot with.
+    pub fn build(image: &Image, decoder: &dyn Decoder, functions: Vec<Function>) -> CallGraph {
+        let starts: BTreeSet<u64> = functions.iter().map(|x| x.start).collect();
+        let stubs = plt_names(image, decoder);
+        let slots: HashMap<u64, &str> =
+            image.relocations.iter().filter_map(|x| x.symbol.as_deref().map(|name| (x.address, name))).collect();
+        let frames = frame_table(image);
+
+        let mut calls = Vec::new();
+        for function in functions.iter() {
+            let cfg = function_cfg(image, decoder, &frames, function.start, &starts);
+            for (id, block) in cfg.blocks.iter().enumerate() {
+                let terminator = match block.terminator() {
+                    Some(x) => x,
+                    None => continue,
+                };
+                let tail = match terminator.flow {
+                    FlowKind::Call => false,
+                    FlowKind::Jump => true,
+                    _ => continue,
+                };
+
+                let slot = terminator.memory_targets().into_iter().find_map(|x| slots.get(&x));
+                let callee = match (terminator.branch_target(), slot) {
+                    (Some(target), _) if starts.contains(&target) => Callee::Function(target),
+                    (Some(target), _) => match stubs.get(&target) {
+                        Some(name) => Callee::Import(name.clone()),
+                        None => Callee::Unresolved,
+                    },
+                    (None, Some(name)) => Callee::Import(name.to_string()),
+                    (None, None) => Callee::Unresolved,
+                };
+                // Jumps leaving the function are tail calls. Those staying inside and indirect jumps, such as through
+                // jump tables, are not calls.
+                if tail {
+                    let leaves = cfg
+                        .edges
+                        .iter()
+                        .any(|x| x.from == id && x.to == cfg.exit && matches!(x.kind, EdgeKind::Unconditional | EdgeKind::True));
+                    if !leaves && !matches!(callee, Callee::Import(_)) {
+                        continue;
+                    }
+                }
+                calls.push(Call { caller: function.start, address: terminator.address, callee, tail });
+            }
+        }
+        CallGraph { functions, calls }
+    }
+
+    pub fn function(&self, start: u64) -> Option<&Function> {
+        self.functions.iter().find(|x| x.start == start)
+    }
+
+    pub fn calls_from(&self, caller: u64) -> impl Iterator<Item = &Call> {
+        self.calls.iter().filter(move |x| x.caller == caller)
+    }
+
+    /// The calls made to a function
+    #[allow(unused)]
+    pub fn callers(&self, start: u64) -> Vec<&Call> {
+        self.calls.iter().filter(|x| x.callee == Callee::Function(start)).collect()
+    }
+
+    /// The functions that can be called, directly or not, from the function starting at `start`, itself included
+    pub fn reachable(&self, start: u64) -> BTreeSet<u64> {
+        let mut res = BTreeSet::new();
+        let mut work = vec![start];
+        while let Some(function) = work.pop() {
+            if !res.insert(function) {
+                continue;
+            }
+            for call in self.calls_from(function) {
+                if let Callee::Function(callee) = call.callee {
+                    work.push(callee);
+                }
+            }
+        }
+        res
+    }
+
+    fn callee_name(&self, callee: &Callee) -> String {
+        match callee {
+            Callee::Function(start) => self.function(*start).map(|x| x.display_name()).unwrap_or_else(|| format!("sub_{:x}", start)),
+            Callee::Import(name) => name.clone(),
+            Callee::Unresolved => "?".to_string(),
+        }
+    }
+
+    /// Graphviz rendering of the calls between the functions in `only`, or all of them. Imports are drawn as
+    /// ellipses, indirect calls go to a single node.
+    pub fn to_dot(&self, only: Option<&BTreeSet<u64>>) -> String {
+        let included = |x: u64| only.is_none_or(|only| only.contains(&x));
+        let escape = |x: &str| x.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut res = "digraph calls {\n    node [shape=box, fontname=\"monospace\"];\n".to_string();
+        for function in self.functions.iter().filter(|x| included(x.start)) {
+            res.push_str(&format!("    f{:x} [label=\"{}\"];\n", function.start, escape(&function.display_name())));
+        }
+
+        let mut imports: BTreeSet<&str> = BTreeSet::new();
+        let mut edges: BTreeMap<(u64, String), bool> = BTreeMap::new();
+        for call in self.calls.iter().filter(|x| included(x.caller)) {
+            let to = match &call.callee {
+                Callee::Function(start) if included(*start) => format!("f{:x}", start),
+                Callee::Function(_) => continue,
+                Callee::Import(name) => {
+                    imports.insert(name);
+                    format!("\"import {}\"", escape(name))
+                }
+                Callee::Unresolved => "unresolved".to_string(),
+            };
+            *edges.entry((call.caller, to)).or_insert(true) &= call.tail;
+        }
+        for name in imports {
+            res.push_str(&format!("    \"import {}\" [label=\"{}\", shape=ellipse, color=gray];\n", escape(name), escape(name)));
+        }
+        if edges.keys().any(|x| x.1 == "unresolved") {
+            res.push_str("    unresolved [label=\"?\", shape=diamond, style=dashed];\n");
+        }
+        for ((caller, to), tail) in edges {
+            let style = if tail { " [style=dashed]" } else { "" };
+            res.push_str(&format!("    f{:x} -> {}{};\n", caller, to, style));
+        }
+        res.push_str("}\n");
+        res
+    }
+
+    /// The functions and calls as JSON, restricted to the functions in `only` if given
+    pub fn to_json(&self, only: Option<&BTreeSet<u64>>) -> String {
+        let included = |x: u64| only.is_none_or(|only| only.contains(&x));
+        let functions: Vec<String> = self
+            .functions
+            .iter()
+            .filter(|x| included(x.start))
+            .map(|x| {
+                format!(
+                    "    {{\"name\": {}, \"start\": {}, \"end\": {}}}",
+                    json::string(&x.display_name()),
+                    json::address(x.start),
+                    json::address(x.end)
+                )
+            })
+            .collect();
+        let calls: Vec<String> = self
+            .calls
+            .iter()
+            .filter(|x| included(x.caller))
+            .map(|x| {
+                let (kind, target) = match &x.callee {
+                    Callee::Function(start) => ("function", json::address(*start)),
+                    Callee::Import(_) => ("import", "null".to_string()),
+                    Callee::Unresolved => ("unresolved", "null".to_string()),
+                };
+                format!(
+                    "    {{\"caller\": {}, \"address\": {}, \"kind\": \"{}\", \"callee\": {}, \"target\": {}, \"tail\": {}}}",
+                    json::address(x.caller),
+                    json::address(x.address),
+                    kind,
+                    match x.callee {
+                        Callee::Unresolved => "null".to_string(),
+                        _ => json::string(&self.callee_name(&x.callee)),
+                    },
+                    target,
+                    x.tail
+                )
+            })
+            .collect();
+        format!("{{\n  \"functions\": [\n{}\n  ],\n  \"calls\": [\n{}\n  ]\n}}\n", functions.join(",\n"), calls.join(",\n"))
+    }
+
+    /// One line per call, grouped by caller
+    pub fn to_text(&self, only: Option<&BTreeSet<u64>>) -> String {
+        let mut res = String::new();
+        for function in self.functions.iter().filter(|x| only.is_none_or(|only| only.contains(&x.start))) {
+            res.push_str(&format!("{} (0x{:x})\n", function.display_name(), function.start));
+            for call in self.calls_from(function.start) {
+                let kind = if call.tail { "jumps to" } else { "calls" };
+                res.push_str(&format!("    {:x}: {} {}\n", call.address, kind, self.callee_name(&call.callee)));
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{CallGraph, Callee};
+    use crate::analysis::{Function, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Relocation, Section};
+    use crate::instruction_set::InstructionSet;
+
+    fn section(index: usize, name: &str, address: u64, data: Vec<u8>, executable: bool) -> Section {
+        Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size: 0,
+            data,
+            allocated: true,
+            executable,
+            writable: !executable,
+            merged_strings: false,
+        }
+    }
+
+    fn function(start: u64, end: u64, name: &str) -> Function {
+        Function { start, end, name: Some(name.to_string()), sources: vec![Source::Symbol] }
+    }
+
+    #[test]
+    fn calls() {
+        // main: call helper; call [rip + puts@got]; call rax; jmp leaf
+        // helper: call leaf; jmp 1f; 1: ret
+        // leaf: ret
+        let mut text = vec![0xcc; 0x31];
+        text[..0x12].copy_from_slice(&[0xe8, 0x1b, 0, 0, 0, 0xff, 0x15, 0xf5, 0x1f, 0, 0, 0xff, 0xd0, 0xe9, 0x1e, 0, 0, 0]);
+        text[0x20..0x28].copy_from_slice(&[0xe8, 0x0b, 0, 0, 0, 0xeb, 0x00, 0xc3]);
+        text[0x30] = 0xc3;
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0x1000,
+            relocatable: false,
+            sections: vec![section(1, ".text", 0x1000, text, true), section(2, ".got", 0x3000, vec![0; 8], false)],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: vec![Relocation {
+                address: 0x3000,
+                kind: 6,
+                symbol: Some("puts".to_string()),
+                addend: 0,
+                section: None,
+                plt: false,
+                mangled: None,
+            }],
+        };
+        let decoder = decoder_for(&image.target).unwrap();
+        let functions = vec![function(0x1000, 0x1012, "main"), function(0x1020, 0x1028, "helper"), function(0x1030, 0x1031, "leaf")];
+        let graph = CallGraph::build(&image, &*decoder, functions);
+
+        let calls: Vec<_> = graph.calls.iter().map(|x| (x.caller, x.address, x.callee.clone(), x.tail)).collect();
+        assert_eq!(calls, vec![
+            (0x1000, 0x1000, Callee::Function(0x1020), false),
+            (0x1000, 0x1005, Callee::Import("puts".to_string()), false),
+            (0x1000, 0x100b, Callee::Unresolved, false),
+            (0x1000, 0x100d, Callee::Function(0x1030), true),
+            (0x1020, 0x1020, Callee::Function(0x1030), false),
+        ]);
+        assert_eq!(graph.callers(0x1030).iter().map(|x| x.address).collect::<Vec<_>>(), vec![0x100d, 0x1020]);
+        assert_eq!(graph.reachable(0x1000), BTreeSet::from([0x1000, 0x1020, 0x1030]));
+        assert_eq!(graph.reachable(0x1020), BTreeSet::from([0x1020, 0x1030]));
+        assert_eq!(graph.reachable(0x1030), BTreeSet::from([0x1030]));
+
+        let only = BTreeSet::from([0x1000]);
+        assert_eq!(
+            graph.to_text(Some(&only)),
+            "main (0x1000)\n    1000: calls helper\n    1005: calls puts\n    100b: calls ?\n    100d: jumps to leaf\n"
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::disasm::{Decoder, DelaySlot, FlowKind, Instruction, Syntax};
+use crate::dwarf::{CallSite, FrameTable};
+use crate::image::Image;
+
+use super::exceptions;
+use super::functions::function_instructions;
+use super::jumptables::{jump_tables, JumpTable};
+
+pub type BlockId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EdgeKind {
+    // Into the next block, which starts there because something jumps to it
+    Fallthrough,
+    // Conditional branch taken and not taken
+    True,
+    False,
+    Unconditional,
+    // Across a call, to where it returns
+    Call,
+    // To the exit block
+    Return,
+    // A jump whose target is not known statically, to the exit block
+    Indirect,
+    // A jump through a jump table, for the value switched on
+    Case(i64),
+    // To a landing pad
+    Exception,
+}
+
+impl EdgeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            EdgeKind::Fallthrough => "fallthrough",
+            EdgeKind::True => "true",
+            EdgeKind::False => "false",
+            EdgeKind::Unconditional => "jump",
+            EdgeKind::Call => "call",
+            EdgeKind::Return => "return",
+            EdgeKind::Indirect => "indirect",
+            EdgeKind::Case(_) => "case",
+            EdgeKind::Exception => "exception",
+        }
+    }
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeKind::Case(value) => write!(f, "case {}", value),
+            kind => write!(f, "{}", kind.name()),
+        }
+    }
+}
+
+/// Straight line code: only the first instruction is jumped to and only the last one branches, or the one before
+/// it when it has a delay slot
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub start: u64,
+    // Exclusive
+    pub end: u64,
+    pub instructions: Vec<Instruction>,
+}
+
+impl Block {
+    /// The instruction deciding where execution continues
+    pub fn terminator(&self) -> Option<&Instruction> {
+        match self.instructions.len() {
+            0 => None,
+            1 => self.instructions.last(),
+            n if self.instructions[n - 2].delay_slot != DelaySlot::None => Some(&self.instructions[n - 2]),
+            n => Some(&self.instructions[n - 1]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    pub from: BlockId,
+    pub to: BlockId,
+    pub kind: EdgeKind,
+}
+
+/// The control flow graph of a function
+///
+/// Blocks end at calls too, so that the call edges show where a call returns to. Jumps through recovered jump
+/// tables have an edge for each case. All returns, tail calls and other indirect jumps lead to a single exit block without instructions, which post-dominance is computed from.
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub entry: BlockId,
+    pub exit: BlockId,
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+}
+
+/// Immediate dominators, by block. The root and the blocks it doesn't reach have none.
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct DominatorTree {
+    pub root: BlockId,
+    pub idom: Vec<Option<BlockId>>,
+}
+
+impl DominatorTree {
+    /// Whether every path from the root to `b` goes through `a`. Blocks dominate themselves.
+    pub fn dominates(&self, a: BlockId, b: BlockId) -> bool {
+        let mut current = Some(b);
+        while let Some(x) = current {
+            if x == a {
+                return true;
+            }
+            current = self.idom[x];
+        }
+        false
+    }
+
+    #[allow(unused)]
+    pub fn children(&self, a: BlockId) -> Vec<BlockId> {
+        (0..self.idom.len()).filter(|x| self.idom[*x] == Some(a)).collect()
+    }
+}
+
+/// A natural loop: the blocks from which the latches can be reached without going through the header
+#[derive(Debug, Clone)]
+pub struct Loop {
+    pub header: BlockId,
+    // Blocks with a back edge to the header
+    pub latches: Vec<BlockId>,
+    pub body: BTreeSet<BlockId>,
+    // Blocks outside the loop that the body branches to
+    pub exits: BTreeSet<BlockId>,
+}
+
+/// The control flow graph of the function starting at `start`. Calls and jumps into the functions starting at
+/// `starts` are not followed, the code reached from the landing pads `frames` give for the function is included.
+pub fn function_cfg(image: &Image, decoder: &dyn Decoder, frames: &FrameTable, start: u64, starts: &BTreeSet<u64>) -> Cfg {
+    let call_sites = exceptions::call_sites(image, frames, start);
+    let landing_pads: Vec<u64> = call_sites.iter().filter_map(|x| x.landing_pad).collect();
+    let instructions = function_instructions(image, decoder, start, &landing_pads, starts);
+    let tables = jump_tables(image, &instructions);
+    Cfg::new(start, &instructions, &call_sites, &tables)
+}
+
+impl Cfg {
+    /// Splits the instructions into blocks starting at `entry`. Exceptions thrown in the ranges of the call
+    /// sites continue at their landing pads, jumps through `tables` at their cases.
+    pub fn new(entry: u64, instructions: &BTreeMap<u64, Instruction>, call_sites: &[CallSite], tables: &[JumpTable]) -> Cfg {
+        // Delay slots belong to the block of their branch
+        let slots: BTreeSet<u64> =
+            instructions.values().filter(|x| x.delay_slot != DelaySlot::None).map(|x| x.next_address()).collect();
+        let ends_block = |x: &Instruction| !matches!(x.flow, FlowKind::Sequential | FlowKind::Syscall);
+
+        let mut leaders: BTreeSet<u64> = BTreeSet::new();
+        leaders.insert(entry);
+        leaders.extend(call_sites.iter().filter_map(|x| x.landing_pad));
+        for instruction in instructions.values().filter(|x| ends_block(x)) {
+            leaders.insert(instruction.resume_address());
+            // Call targets are other functions
+            if instruction.flow == FlowKind::Jump {
+                leaders.extend(instruction.branch_target());
+            }
+        }
+        for table in tables.iter() {
+            leaders.extend(table.targets.iter().copied());
+        }
+
+        let mut blocks: Vec<Block> = Vec::new();
+        let mut open = false;
+        for instruction in instructions.values() {
+            let contiguous = blocks.last().is_some_and(|x| x.end == instruction.address);
+            let slot = slots.contains(&instruction.address);
+            if !open || !contiguous || (leaders.contains(&instruction.address) && !slot) {
+                blocks.push(Block { start: instruction.address, end: instruction.address, instructions: Vec::new() });
+            }
+            let block = blocks.last_mut().unwrap();
+            block.end = instruction.next_address();
+            block.instructions.push(instruction.clone());
+            // A branch with a delay slot ends the block after the slot
+            open = !(slot || (ends_block(instruction) && instruction.delay_slot == DelaySlot::None));
+        }
+
+        let exit = blocks.len();
+        blocks.push(Block { start: 0, end: 0, instructions: Vec::new() });
+        let by_address: BTreeMap<u64, BlockId> = blocks[..exit].iter().enumerate().map(|(i, x)| (x.start, i)).collect();
+        let entry = by_address.get(&entry).copied().unwrap_or(exit);
+
+        let mut edges = Vec::new();
+        let mut add = |from: BlockId, to: BlockId, kind: EdgeKind| {
+            let edge = Edge { from, to, kind };
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        };
+        for (id, block) in blocks[..exit].iter().enumerate() {
+            let terminator = match block.terminator() {
+                Some(x) => x,
+                None => continue,
+            };
+            let next = by_address.get(&terminator.resume_address()).copied();
+            // Known targets outside of the function are tail calls
+            let target = terminator.branch_target().map(|x| by_address.get(&x).copied().unwrap_or(exit));
+            let table = tables.iter().find(|x| x.jump == terminator.address).filter(|_| terminator.flow == FlowKind::Jump);
+            if let Some(table) = table {
+                for (value, to) in table.cases() {
+                    add(id, by_address.get(&to).copied().unwrap_or(exit), EdgeKind::Case(value));
+                }
+                if let (true, Some(next)) = (terminator.conditional, next) {
+                    add(id, next, EdgeKind::False);
+                }
+                continue;
+            }
+            match (terminator.flow, terminator.conditional) {
+                (FlowKind::Jump, false) => match target {
+                    Some(to) => add(id, to, EdgeKind::Unconditional),
+                    None => add(id, exit, EdgeKind::Indirect),
+                },
+                (FlowKind::Jump, true) => {
+                    match target {
+                        Some(to) => add(id, to, EdgeKind::True),
+                        None => add(id, exit, EdgeKind::Indirect),
+                    }
+                    if let Some(next) = next {
+                        add(id, next, EdgeKind::False);
+                    }
+                }
+                (FlowKind::Return, conditional) => {
+                    add(id, exit, EdgeKind::Return);
+                    if let (true, Some(next)) = (conditional, next) {
+                        add(id, next, EdgeKind::False);
+                    }
+                }
+                (FlowKind::Call, _) => {
+                    if let Some(next) = next {
+                        add(id, next, EdgeKind::Call);
+                    }
+                }
+                (FlowKind::Trap, _) => {}
+                (FlowKind::Sequential | FlowKind::Syscall, _) => {
+                    if let Some(next) = by_address.get(&block.end) {
+                        add(id, *next, EdgeKind::Fallthrough);
+                    }
+                }
+            }
+        }
+        for site in call_sites.iter() {
+            let pad = match site.landing_pad.and_then(|x| by_address.get(&x)) {
+                Some(x) => *x,
+                None => continue,
+            };
+            let end = site.start.wrapping_add(site.length);
+            for (id, block) in blocks[..exit].iter().enumerate() {
+                if block.start < end && site.start < block.end {
+                    add(id, pad, EdgeKind::Exception);
+                }
+            }
+        }
+
+        Cfg { entry, exit, blocks, edges }
+    }
+
+    pub fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        self.edges.iter().filter(|x| x.from == block).map(|x| x.to).collect()
+    }
+
+    pub fn predecessors(&self, block: BlockId) -> Vec<BlockId> {
+        self.edges.iter().filter(|x| x.to == block).map(|x| x.from).collect()
+    }
+
+    /// The block containing an address
+    #[allow(unused)]
+    pub fn block_at(&self, address: u64) -> Option<BlockId> {
+        self.blocks.iter().position(|x| address >= x.start && address < x.end)
+    }
+
+    pub fn dominators(&self) -> DominatorTree {
+        dominator_tree(self.blocks.len(), self.entry, |x| self.successors(x), |x| self.predecessors(x))
+    }
+
+    /// Dominators of the reversed graph, rooted at the exit block
+    pub fn post_dominators(&self) -> DominatorTree {
+        dominator_tree(self.blocks.len(), self.exit, |x| self.predecessors(x), |x| self.successors(x))
+    }
+
+    /// The natural loops, one per header, inner loops included
+    pub fn loops(&self) -> Vec<Loop> {
+        natural_loops(&self.dominators(), &self.edges, |x| self.predecessors(x), |x| self.successors(x))
+    }
+
+    /// Whether all cycles are natural loops, so that every edge going back in a depth first order goes to a
+    /// block dominating its source
+    pub fn is_reducible(&self) -> bool {
+        let dominators = self.dominators();
+        let order = reverse_postorder(self.blocks.len(), self.entry, |x| self.successors(x));
+        let mut position = vec![usize::MAX; self.blocks.len()];
+        for (i, block) in order.iter().enumerate() {
+            position[*block] = i;
+        }
+        self.edges
+            .iter()
+            .filter(|x| position[x.from] != usize::MAX && position[x.to] <= position[x.from])
+            .all(|x| dominators.dominates(x.to, x.from))
+    }
+
+    /// Graphviz rendering, with the instructions of each block and the edges colored by kind
+    pub fn to_dot(&self, name: &str, syntax: Syntax) -> String {
+        let escape = |x: &str| x.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut res = format!("digraph \"{}\" {{\n", escape(name));
+        res.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for (id, block) in self.blocks.iter().enumerate() {
+            let label = if id == self.exit {
+                "exit".to_string()
+            } else {
+                let lines: Vec<String> =
+                    block.instructions.iter().map(|x| format!("{:x}: {}\\l", x.address, escape(&x.render(syntax)))).collect();
+                lines.concat()
+            };
+            res.push_str(&format!("    b{} [label=\"{}\"];\n", id, label));
+        }
+        for edge in self.edges.iter() {
+            let style = match edge.kind {
+                EdgeKind::True => "color=green",
+                EdgeKind::False => "color=red",
+                EdgeKind::Call => "style=dashed",
+                EdgeKind::Exception => "color=orange, style=dashed",
+                EdgeKind::Return | EdgeKind::Indirect => "style=dotted",
+                EdgeKind::Case(_) => "color=purple",
+                EdgeKind::Fallthrough | EdgeKind::Unconditional => "color=blue",
+            };
+            res.push_str(&format!("    b{} -> b{} [label=\"{}\", {}];\n", edge.from, edge.to, edge.kind, style));
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+// The natural loops of the back edges among `edges`, whose targets dominate their sources
+pub(crate) fn natural_loops(
+    dominators: &DominatorTree,
+    edges: &[Edge],
+    predecessors: impl Fn(BlockId) -> Vec<BlockId>,
+    successors: impl Fn(BlockId) -> Vec<BlockId>,
+) -> Vec<Loop> {
+    let mut latches: BTreeMap<BlockId, Vec<BlockId>> = BTreeMap::new();
+    for edge in edges.iter().filter(|x| dominators.dominates(x.to, x.from)) {
+        latches.entry(edge.to).or_default().push(edge.from);
+    }
+
+    let mut res = Vec::new();
+    for (header, latches) in latches {
+        let mut body: BTreeSet<BlockId> = BTreeSet::new();
+        body.insert(header);
+        let mut work = latches.clone();
+        while let Some(block) = work.pop() {
+            if body.insert(block) {
+                work.extend(predecessors(block));
+            }
+        }
+        let exits = body.iter().flat_map(|x| successors(*x)).filter(|x| !body.contains(x)).collect();
+        res.push(Loop { header, latches, body, exits });
+    }
+    res
+}
+
+// Blocks reachable from `root` in reverse postorder
+pub(crate) fn reverse_postorder(n: usize, root: BlockId, successors: impl Fn(BlockId) -> Vec<BlockId>) -> Vec<BlockId> {
+    let mut visited = vec![false; n];
+    let mut order = Vec::new();
+    // Iterative depth first search, each entry holds the successors still to visit
+    let mut stack: Vec<(BlockId, Vec<BlockId>)> = vec![(root, successors(root))];
+    visited[root] = true;
+    while let Some((block, pending)) = stack.last_mut() {
+        match pending.pop() {
+            Some(next) if !visited[next] => {
+                visited[next] = true;
+                let next_successors = successors(next);
+                stack.push((next, next_successors));
+            }
+            Some(_) => {}
+            None => {
+                order.push(*block);
+                stack.pop();
+            }
+        }
+    }
+    order.reverse();
+    order
+}
+
+// Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm"
+pub(crate) fn dominator_tree(
+    n: usize,
+    root: BlockId,
+    successors: impl Fn(BlockId) -> Vec<BlockId>,
+    predecessors: impl Fn(BlockId) -> Vec<BlockId>,
+) -> DominatorTree {
+    let order = reverse_postorder(n, root, successors);
+    let mut position = vec![usize::MAX; n];
+    for (i, block) in order.iter().enumerate() {
+        position[*block] = i;
+    }
+
+    let mut idom: Vec<Option<BlockId>> = vec![None; n];
+    idom[root] = Some(root);
+    let intersect = |idom: &[Option<BlockId>], mut a: BlockId, mut b: BlockId| {
+        while a != b {
+            while position[a] > position[b] {
+                a = idom[a].unwrap();
+            }
+            while position[b] > position[a] {
+                b = idom[b].unwrap();
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in order.iter().skip(1).copied() {
+            let mut new = None;
+            for predecessor in predecessors(block).into_iter().filter(|x| idom[*x].is_some()) {
+                new = Some(match new {
+                    None => predecessor,
+                    Some(current) => intersect(&idom, predecessor, current),
+                });
+            }
+            if new.is_some() && idom[block] != new {
+                idom[block] = new;
+                changed = true;
+            }
+        }
+    }
+    idom[root] = None;
+    DominatorTree { root, idom }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Block, Cfg, Edge, EdgeKind};
+
+    // Blocks without instructions, 0x10 bytes apart, and the edges between them
+    fn cfg(n: usize, exit: usize, edges: &[(usize, usize)]) -> Cfg {
+        let blocks = (0..n as u64).map(|x| Block { start: x * 0x10, end: x * 0x10 + 0x10, instructions: Vec::new() }).collect();
+        let edges = edges.iter().map(|&(from, to)| Edge { from, to, kind: EdgeKind::Unconditional }).collect();
+        Cfg { entry: 0, exit, blocks, edges }
+    }
+
+    #[test]
+    fn nested_loops() {
+        // 0 -> 1 -> 2 <-> 3 -> 4 -> 1 -> 5 -> 6 (exit), and 7 jumps into the outer loop from nowhere
+        let cfg = cfg(8, 6, &[(0, 1), (1, 2), (1, 5), (2, 3), (3, 2), (3, 4), (4, 1), (5, 6), (7, 1)]);
+        let dominators = cfg.dominators();
+        assert_eq!(dominators.idom, vec![None, Some(0), Some(1), Some(2), Some(3), Some(1), Some(5), None]);
+        assert!(dominators.dominates(1, 4) && dominators.dominates(4, 4) && !dominators.dominates(2, 5));
+        assert!(!dominators.dominates(0, 7));
+        assert_eq!(dominators.children(1), vec![2, 5]);
+
+        let post_dominators = cfg.post_dominators();
+        assert_eq!(post_dominators.idom, vec![Some(1), Some(5), Some(3), Some(4), Some(1), Some(6), None, Some(1)]);
+
+        let loops = cfg.loops();
+        assert_eq!(loops.len(), 2);
+        assert_eq!((loops[0].header, &loops[0].latches), (1, &vec![4]));
+        assert_eq!(loops[0].body, BTreeSet::from([1, 2, 3, 4]));
+        assert_eq!(loops[0].exits, BTreeSet::from([5]));
+        assert_eq!((loops[1].header, &loops[1].latches), (2, &vec![3]));
+        assert_eq!(loops[1].body, BTreeSet::from([2, 3]));
+        assert_eq!(loops[1].exits, BTreeSet::from([4]));
+        assert!(cfg.is_reducible());
+        assert_eq!(cfg.block_at(0x38), Some(3));
+    }
+
+    #[test]
+    fn irreducible() {
+        // A cycle entered at both 1 and 2 has no header dominating the other block, so no natural loop
+        let cfg = cfg(4, 3, &[(0, 1), (0, 2), (1, 2), (2, 1), (2, 3)]);
+        assert_eq!(cfg.dominators().idom, vec![None, Some(0), Some(0), Some(2)]);
+        assert!(cfg.loops().is_empty());
+        assert!(!cfg.is_reducible());
+    }
+}
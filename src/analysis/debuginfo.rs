@@ -0,0 +1,26 @@
+use crate::dwarf::{DebugInfo, DebugSections, Reader};
+use crate::image::Image;
+
+/// What the DWARF sections say about the program, nothing if it has none or they can't be read. Compressed
+/// sections are not read, and object files are read without applying their relocations.
+pub fn debug_info(image: &Image) -> DebugInfo {
+    let section = |name| image.section_by_name(name).map_or(&[][..], |x| &x.data[..]);
+    let sections = DebugSections {
+        info: section(".debug_info"),
+        abbrev: section(".debug_abbrev"),
+        str: section(".debug_str"),
+        line_str: section(".debug_line_str"),
+        str_offsets: section(".debug_str_offsets"),
+        addr: section(".debug_addr"),
+        line: section(".debug_line"),
+        ranges: section(".debug_ranges"),
+        loc: section(".debug_loc"),
+        rnglists: section(".debug_rnglists"),
+        loclists: section(".debug_loclists"),
+    };
+    if sections.info.is_empty() {
+        return DebugInfo::default();
+    }
+    let reader = Reader::new(sections.info, image.target.endianness, image.target.bits / 8);
+    DebugInfo::parse(&sections, &reader).unwrap_or_default()
+}
@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disasm::{DecodeError, Decoder, DelaySlot, Instruction};
+
+/// A line of a listing
+#[derive(Debug, Clone)]
+pub enum Line {
+    Instruction(Instruction),
+    // Bytes that don't decode, as many as the decoder skips over
+    Bad { address: u64, length: usize, error: DecodeError },
+    // Bytes recursive descent never reached
+    Data { address: u64, bytes: Vec<u8> },
+}
+
+impl Line {
+    pub fn address(&self) -> u64 {
+        match self {
+            Line::Instruction(x) => x.address,
+            Line::Bad { address, .. } | Line::Data { address, .. } => *address,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        match self {
+            Line::Instruction(x) => x.length,
+            Line::Bad { length, .. } => *length,
+            Line::Data { bytes, .. } => bytes.len(),
+        }
+    }
+}
+
+/// Decodes `bytes`, located at `address`, from start to end. Data in between code is decoded as if it were code.
+pub fn linear_sweep(decoder: &dyn Decoder, bytes: &[u8], address: u64) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let current = address + offset as u64;
+        match decoder.decode(&bytes[offset..], current) {
+            Ok(instruction) => {
+                offset += instruction.length;
+                lines.push(Line::Instruction(instruction));
+            }
+            Err(error) => {
+                let length = decoder.alignment(current).clamp(1, bytes.len() - offset);
+                lines.push(Line::Bad { address: current, length, error });
+                offset += length;
+            }
+        }
+    }
+    lines
+}
+
+/// Follows control flow from `roots` through the code regions, given as (address, contents). Only what is
+/// reachable is decoded, so data in code sections stays data.
+///
+/// Calls are assumed to return. Targets of indirect jumps are unknown, so code only reached through them is
+/// missed unless it is a root.
+pub fn recursive_descent(decoder: &dyn Decoder, code: &[(u64, &[u8])], roots: &[u64]) -> BTreeMap<u64, Instruction> {
+    let decode = |address: u64| -> Option<Instruction> {
+        let (start, bytes) = code.iter().find(|(start, bytes)| address >= *start && address - start < bytes.len() as u64)?;
+        decoder.decode(&bytes[(address - start) as usize..], address).ok()
+    };
+
+    let mut instructions = BTreeMap::new();
+    let mut work: Vec<u64> = roots.to_vec();
+    let mut seen: BTreeSet<u64> = BTreeSet::new();
+    while let Some(address) = work.pop() {
+        if !seen.insert(address) {
+            continue;
+        }
+        let instruction = match decode(address) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        // The delay slot executes with the branch, but what follows it is only reached if the branch falls through
+        if instruction.delay_slot != DelaySlot::None {
+            if let Some(slot) = decode(instruction.next_address()) {
+                seen.insert(slot.address);
+                instructions.insert(slot.address, slot);
+            }
+        }
+        if let Some(target) = instruction.branch_target() {
+            work.push(target);
+        }
+        if instruction.falls_through() {
+            work.push(instruction.resume_address());
+        }
+        instructions.insert(address, instruction);
+    }
+    instructions
+}
+
+/// Lines for `bytes` at `address` from the decoded instructions, with what lies between them as data
+pub fn listing(instructions: &BTreeMap<u64, Instruction>, bytes: &[u8], address: u64) -> Vec<Line> {
+    let end = address + bytes.len() as u64;
+    let mut lines = Vec::new();
+    let mut current = address;
+    for instruction in instructions.range(address..end).map(|x| x.1) {
+        // Instructions overlapping one already listed are the result of a bad guess, such as a wrong root
+        if instruction.address < current {
+            continue;
+        }
+        if instruction.address > current {
+            let data = &bytes[(current - address) as usize..(instruction.address - address) as usize];
+            lines.push(Line::Data { address: current, bytes: data.to_vec() });
+        }
+        lines.push(Line::Instruction(instruction.clone()));
+        current = instruction.next_address();
+    }
+    if current < end {
+        lines.push(Line::Data { address: current, bytes: bytes[(current - address) as usize..].to_vec() });
+    }
+    lines
+}
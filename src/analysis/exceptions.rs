@@ -0,0 +1,51 @@
+use crate::dwarf::{self, Bases, CallSite, FrameKind, FrameTable, Reader};
+use crate::image::{Image, Section};
+
+use super::unwind::eh_frame_header;
+
+// What pointers in the exception handling sections are relative to
+fn bases(image: &Image, section: &Section) -> Bases {
+    Bases {
+        section: section.address,
+        text: image.section_by_name(".text").map(|x| x.address),
+        // i386 code refers to data relative to the GOT
+        data: image.section_by_name(".got.plt").or_else(|| image.section_by_name(".got")).map(|x| x.address),
+        function: None,
+    }
+}
+
+/// The entries of .eh_frame, none if there is no such section or it can't be read. Without section headers, the
+/// section is found through the .eh_frame_hdr of the PT_GNU_EH_FRAME segment.
+pub fn frame_table(image: &Image) -> FrameTable {
+    if let Some(section) = image.section_by_name(".eh_frame") {
+        let reader = Reader::new(&section.data, image.target.endianness, image.target.bits / 8);
+        return FrameTable::parse(&reader, FrameKind::EhFrame, &bases(image, section)).unwrap_or_default();
+    }
+    let address = match eh_frame_header(image) {
+        Some(x) => x.eh_frame,
+        None => return FrameTable::default(),
+    };
+    let data = match image.data_at(address) {
+        Some(x) => x,
+        None => return FrameTable::default(),
+    };
+    // The section runs to its terminator, or to the end of the segment
+    let reader = Reader::new(data, image.target.endianness, image.target.bits / 8);
+    let bases = Bases { section: address, text: None, data: None, function: None };
+    FrameTable::parse(&reader, FrameKind::EhFrame, &bases).unwrap_or_default()
+}
+
+/// Where exceptions thrown in the function starting at `start` land, from the LSDA its FDE points to
+pub fn call_sites(image: &Image, frames: &FrameTable, start: u64) -> Vec<CallSite> {
+    let lsda = match frames.fdes.iter().find(|x| x.start == start).and_then(|x| x.lsda) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let section = match image.section_at(lsda).filter(|x| !x.data.is_empty()) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let mut reader = Reader::new(&section.data, image.target.endianness, image.target.bits / 8);
+    reader.seek((lsda - section.address) as usize);
+    dwarf::parse_lsda(&mut reader, start, &bases(image, section)).unwrap_or_default()
+}
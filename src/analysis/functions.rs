@@ -0,0 +1,505 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disasm::{Decoder, DelaySlot, FlowKind, Instruction, Operand};
+use crate::endian::Endianness;
+use crate::image::Image;
+use crate::instruction_set::InstructionSet;
+use crate::ir::{self, Lifter};
+
+use super::exceptions;
+use super::jumptables::jump_table;
+use super::plt::PLT_SECTIONS;
+use super::unwind;
+
+/// Why an address is believed to start a function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Symbol,
+    Entry,
+    InitArray,
+    FiniArray,
+    // An FDE of .eh_frame
+    ExceptionFrame,
+    // An FDE of .debug_frame
+    DebugFrame,
+    // A function exported in the dynamic symbol table
+    Export,
+    // The target of a direct call, or of a jump back before the start of the function, which is a tail call
+    Call,
+    // Code nothing was found to reach that looks like a function prologue
+    Prologue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Source {
+    pub fn confidence(self) -> Confidence {
+        match self {
+            Source::Call => Confidence::Medium,
+            Source::Prologue => Confidence::Low,
+            _ => Confidence::High,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Symbol => "symbol",
+            Source::Entry => "entry",
+            Source::InitArray => "init_array",
+            Source::FiniArray => "fini_array",
+            Source::ExceptionFrame => "eh_frame",
+            Source::DebugFrame => "debug_frame",
+            Source::Export => "export",
+            Source::Call => "call",
+            Source::Prologue => "prologue",
+        }
+    }
+}
+
+/// A function found by [discover_functions]
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub start: u64,
+    // Exclusive
+    pub end: u64,
+    pub name: Option<String>,
+    pub sources: Vec<Source>,
+}
+
+impl Function {
+    pub fn confidence(&self) -> Confidence {
+        self.sources.iter().map(|x| x.confidence()).max().unwrap_or(Confidence::Low)
+    }
+
+    /// The name of the symbol, or one made up from the address
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("sub_{:x}", self.start),
+        }
+    }
+}
+
+/// Finds the functions of the code sections, stripped binaries included
+///
+/// Functions start at the symbols, the entry point, the pointers in .init_array and .fini_array, the FDEs of
+/// .eh_frame and the dynamic exports. Following the control flow of these gives the targets of their calls,
+/// which are functions too. Code that is still not reached and starts like a function does is a function with
+/// low confidence.
+///
+/// A function ends where its symbol or FDE says, otherwise after the last instruction reached from its start,
+/// but never beyond the start of the next function.
+pub fn discover_functions(image: &Image, decoder: &dyn Decoder) -> Vec<Function> {
+    let code = Code::new(image, decoder);
+    let mut functions: BTreeMap<u64, Function> = BTreeMap::new();
+    let add = |functions: &mut BTreeMap<u64, Function>, start: u64, end: Option<u64>, name: Option<&str>, source: Source| {
+        if !code.contains(start) {
+            return false;
+        }
+        let function = functions.entry(start).or_insert_with(|| Function { start, end: start, name: None, sources: Vec::new() });
+        let new = function.sources.is_empty();
+        if !function.sources.contains(&source) {
+            function.sources.push(source);
+            function.sources.sort();
+        }
+        if let Some(end) = end.filter(|x| *x > start) {
+            function.end = function.end.max(end);
+        }
+        if function.name.is_none() {
+            function.name = name.map(str::to_string);
+        }
+        new
+    };
+
+    // Thumb functions are referred to with the low bit set
+    let mask = match image.target.instruction_set {
+        InstructionSet::ARM => !1,
+        _ => !0,
+    };
+
+    for symbol in image.symbols.iter().filter(|x| x.function && x.section.is_some() && !x.name.is_empty()) {
+        let end = Some(symbol.address + symbol.size).filter(|_| symbol.size > 0);
+        let source = if symbol.dynamic && symbol.global { Source::Export } else { Source::Symbol };
+        add(&mut functions, symbol.address, end, Some(&symbol.name), source);
+    }
+    if !image.relocatable {
+        add(&mut functions, image.entry & mask, None, None, Source::Entry);
+    }
+    for (section, source) in [(".init_array", Source::InitArray), (".fini_array", Source::FiniArray)] {
+        for pointer in pointers(image, section) {
+            add(&mut functions, pointer & mask, None, None, source);
+        }
+    }
+    // FDEs of object files are relocated against the code sections, where they start is only known once linked
+    if !image.relocatable {
+        for fde in exceptions::frame_table(image).fdes.iter().filter(|x| x.length > 0) {
+            add(&mut functions, fde.start & mask, Some(fde.end() & mask), None, Source::ExceptionFrame);
+        }
+        for fde in unwind::debug_frame_table(image).fdes.iter().filter(|x| x.length > 0) {
+            add(&mut functions, fde.start & mask, Some(fde.end() & mask), None, Source::DebugFrame);
+        }
+    }
+
+    // Calls from what is known lead to more functions, and so does code that looks like a function but is still
+    // not reached
+    let mut covered: BTreeMap<u64, u64> = BTreeMap::new();
+    let mut visited: BTreeSet<u64> = BTreeSet::new();
+    loop {
+        let mut work: Vec<u64> = functions.keys().copied().filter(|x| !visited.contains(x)).collect();
+        while let Some(start) = work.pop() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let body = code.body(start, &[], &BTreeSet::new());
+            for instruction in body.values() {
+                covered.insert(instruction.address, instruction.next_address());
+            }
+            let tail_calls = body
+                .values()
+                .filter(|x| x.flow == FlowKind::Jump && !x.conditional && !code.relocated(x))
+                .filter_map(|x| x.branch_target())
+                .filter(|x| *x < start);
+            let calls = body.values().filter(|x| x.flow == FlowKind::Call && !code.relocated(x)).filter_map(|x| x.branch_target());
+            for target in calls.chain(tail_calls).collect::<Vec<u64>>() {
+                if add(&mut functions, target, None, None, Source::Call) {
+                    work.push(target);
+                }
+            }
+        }
+
+        let mut found = false;
+        for start in code.prologues(&covered) {
+            found |= add(&mut functions, start, None, None, Source::Prologue);
+        }
+        if !found {
+            break;
+        }
+    }
+
+    // Now that all starts are known, bodies end where other functions begin
+    let starts: BTreeSet<u64> = functions.keys().copied().collect();
+    let mut res: Vec<Function> = functions.into_values().collect();
+    for i in 0..res.len() {
+        let next = res.get(i + 1).map(|x| x.start).unwrap_or(u64::MAX);
+        let function = &mut res[i];
+        if function.end == function.start {
+            let body = code.body(function.start, &[], &starts);
+            function.end = body.values().map(|x| x.next_address()).filter(|x| *x <= next).max().unwrap_or(function.start);
+        }
+        function.end = function.end.min(code.section_end(function.start));
+    }
+    res
+}
+
+/// The instructions of the function starting at `start`, as far as they are reached from the start and from
+/// `roots`, such as landing pads. Calls and jumps to the other functions in `starts` are not followed.
+pub fn function_instructions(
+    image: &Image,
+    decoder: &dyn Decoder,
+    start: u64,
+    roots: &[u64],
+    starts: &BTreeSet<u64>,
+) -> BTreeMap<u64, Instruction> {
+    Code::new(image, decoder).body(start, roots, starts)
+}
+
+// The pointer sized entries of a section. Position independent code leaves them to relocations, which hold
+// the address in their addend.
+fn pointers(image: &Image, name: &str) -> Vec<u64> {
+    let section = match image.section_by_name(name) {
+        Some(x) => x,
+        None => return Vec::new(),
+    };
+    let size = image.target.bits as usize / 8;
+    section
+        .data
+        .chunks_exact(size)
+        .enumerate()
+        .map(|(i, bytes)| {
+            let address = section.address + (i * size) as u64;
+            let relocated = image.relocations.iter().find(|x| x.address == address && x.symbol.is_none() && x.addend != 0);
+            if let Some(relocation) = relocated {
+                return relocation.addend as u64;
+            }
+            let mut value = 0u64;
+            for (j, byte) in bytes.iter().enumerate() {
+                let shift = match image.target.endianness {
+                    Endianness::LittleEndian => j * 8,
+                    Endianness::BigEndian => (size - 1 - j) * 8,
+                };
+                value |= (*byte as u64) << shift;
+            }
+            value
+        })
+        .filter(|x| *x != 0 && *x != u64::MAX && *x != 0xffff_ffff)
+        .collect()
+}
+
+// The code sections functions can be in, which leaves out PLT stubs
+struct Code<'a> {
+    image: &'a Image,
+    sections: Vec<(u64, &'a [u8])>,
+    decoder: &'a dyn Decoder,
+    // To follow jumps through tables
+    lifter: Option<Box<dyn Lifter>>,
+    instruction_set: InstructionSet,
+    // Where relocations of object files apply to the code, whose targets aren't in the instructions yet
+    relocations: BTreeSet<u64>,
+}
+
+impl<'a> Code<'a> {
+    fn new(image: &'a Image, decoder: &'a dyn Decoder) -> Code<'a> {
+        let sections = image
+            .sections
+            .iter()
+            .filter(|x| x.executable && x.allocated && !x.data.is_empty() && !PLT_SECTIONS.contains(&x.name.as_str()))
+            .map(|x| (x.address, &x.data[..]))
+            .collect();
+        let relocations = image
+            .relocations
+            .iter()
+            .filter(|x| image.relocatable && image.sections.iter().any(|section| x.section == Some(section.index) && section.executable))
+            .map(|x| x.address)
+            .collect();
+        let lifter = ir::lifter_for(&image.target);
+        Code { image, sections, decoder, lifter, instruction_set: image.target.instruction_set, relocations }
+    }
+
+    fn relocated(&self, instruction: &Instruction) -> bool {
+        self.relocations.range(instruction.address..instruction.next_address()).next().is_some()
+    }
+
+    fn contains(&self, address: u64) -> bool {
+        self.sections.iter().any(|(start, bytes)| address >= *start && address - start < bytes.len() as u64)
+    }
+
+    fn section_end(&self, address: u64) -> u64 {
+        self.sections
+            .iter()
+            .find(|(start, bytes)| address >= *start && address - start < bytes.len() as u64)
+            .map(|(start, bytes)| start + bytes.len() as u64)
+            .unwrap_or(address)
+    }
+
+    fn decode(&self, address: u64) -> Option<Instruction> {
+        let (start, bytes) = self.sections.iter().find(|(start, bytes)| address >= *start && address - start < bytes.len() as u64)?;
+        self.decoder.decode(&bytes[(address - start) as usize..], address).ok()
+    }
+
+    // The instructions reached from `start` and `roots` without following calls. Jumps to the start of another
+    // function are tail calls, which aren't followed either. Indirect jumps are followed through their jump tables
+    // once the code leading to them is known, unless they are before the start: code reached there by falling
+    // through calls that don't return is more likely another function.
+    fn body(&self, start: u64, roots: &[u64], starts: &BTreeSet<u64>) -> BTreeMap<u64, Instruction> {
+        let mut instructions = BTreeMap::new();
+        let mut resolved = BTreeSet::new();
+        let mut work = vec![start];
+        work.extend(roots);
+        while !work.is_empty() {
+            while let Some(address) = work.pop() {
+                if instructions.contains_key(&address) || (address != start && starts.contains(&address)) {
+                    continue;
+                }
+                let instruction = match self.decode(address) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                if instruction.delay_slot != DelaySlot::None {
+                    if let Some(slot) = self.decode(instruction.next_address()) {
+                        instructions.insert(slot.address, slot);
+                    }
+                }
+                if instruction.flow == FlowKind::Jump {
+                    work.extend(instruction.branch_target().filter(|x| *x >= start || instruction.conditional));
+                }
+                if instruction.falls_through() {
+                    work.push(instruction.resume_address());
+                }
+                instructions.insert(address, instruction);
+            }
+            let jumps = instructions.values().filter(|x| x.flow == FlowKind::Jump && x.branch_target().is_none() && x.address >= start);
+            for jump in jumps {
+                if resolved.insert(jump.address) {
+                    let table = jump_table(self.image, self.lifter.as_deref(), &instructions, jump);
+                    work.extend(table.iter().flat_map(|x| x.targets.iter().copied()));
+                }
+            }
+        }
+        instructions
+    }
+
+    // Starts of functions in the code not covered yet, recognized by their prologue. Padding between functions is
+    // skipped, after that only aligned addresses are tried.
+    fn prologues(&self, covered: &BTreeMap<u64, u64>) -> Vec<u64> {
+        let alignment = match self.instruction_set {
+            InstructionSet::X86 | InstructionSet::X86_64 => 16,
+            _ => 4,
+        };
+        let mut res = Vec::new();
+        for (section_start, bytes) in self.sections.iter() {
+            let section_end = section_start + bytes.len() as u64;
+            let mut address = *section_start;
+            while address < section_end {
+                // Skip what is already known to be code
+                if let Some((_, end)) = covered.range(..=address).next_back().filter(|x| *x.1 > address) {
+                    address = *end;
+                    continue;
+                }
+                let gap_end = covered.range(address..).next().map(|x| *x.0).unwrap_or(section_end).min(section_end);
+
+                let mut candidate = address;
+                while let Some(instruction) = self.decode(candidate).filter(|x| x.next_address() <= gap_end && is_padding(x)) {
+                    candidate = instruction.next_address();
+                }
+                while candidate < gap_end {
+                    if self.is_prologue(candidate) {
+                        res.push(candidate);
+                        break;
+                    }
+                    candidate = (candidate / alignment + 1) * alignment;
+                }
+                address = gap_end;
+            }
+        }
+        res
+    }
+
+    fn is_prologue(&self, address: u64) -> bool {
+        let first = match self.decode(address) {
+            Some(x) => x,
+            None => return false,
+        };
+        let second = self.decode(first.next_address());
+        let registers = |x: &Instruction| -> Vec<&'static str> {
+            x.operands
+                .iter()
+                .filter_map(|x| match x {
+                    Operand::Register(register) => Some(register.name),
+                    _ => None,
+                })
+                .collect()
+        };
+        let negative = |x: &Instruction| x.operands.iter().any(|x| matches!(x, Operand::Immediate { value, .. } if *value < 0));
+        let stack_memory = |x: &Instruction, stack: &str| {
+            x.operands.iter().any(|x| matches!(x, Operand::Memory(mem) if mem.base.is_some_and(|x| x.name == stack)))
+        };
+        let mnemonic = first.mnemonic.as_str();
+        let operands = registers(&first);
+
+        match self.instruction_set {
+            InstructionSet::X86 | InstructionSet::X86_64 => {
+                let frame = matches!(operands[..], ["rbp"] | ["ebp"]) && mnemonic == "push";
+                let saved = matches!(operands[..], ["rbx"] | ["r12"] | ["r13"] | ["r14"] | ["r15"] | ["ebx"] | ["esi"] | ["edi"]);
+                let followed = second.is_some_and(|x| {
+                    let next = registers(&x);
+                    (x.mnemonic == "mov" && matches!(next[..], ["rbp", "rsp"] | ["ebp", "esp"]))
+                        || x.mnemonic == "push"
+                        || (x.mnemonic == "sub" && matches!(next[..], ["rsp"] | ["esp"]))
+                });
+                mnemonic.starts_with("endbr")
+                    || ((frame || (saved && mnemonic == "push")) && followed)
+                    || (mnemonic == "sub" && matches!(operands[..], ["rsp"] | ["esp"]))
+            }
+            InstructionSet::AArch64 => {
+                matches!(mnemonic, "paciasp" | "pacibsp")
+                    || (mnemonic == "stp" && operands == ["x29", "x30"] && stack_memory(&first, "sp"))
+                    || (mnemonic == "sub" && operands == ["sp", "sp"])
+            }
+            InstructionSet::ARM => {
+                let saves_lr = first.operands.iter().any(|x| match x {
+                    Operand::List(list) => list.iter().any(|x| matches!(x, Operand::Register(register) if register.name == "lr")),
+                    _ => false,
+                });
+                matches!(mnemonic, "push" | "push.w" | "stmdb" | "stmfd") && saves_lr
+            }
+            InstructionSet::RISC_V => {
+                mnemonic.trim_start_matches("c.").starts_with("addi") && operands.starts_with(&["sp"]) && negative(&first)
+            }
+            InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => {
+                (matches!(mnemonic, "addiu" | "daddiu") && operands == ["sp", "sp"] && negative(&first))
+                    || (mnemonic == "lui" && operands == ["gp"])
+            }
+            InstructionSet::PowerPC | InstructionSet::PowerPC64 => {
+                (matches!(mnemonic, "stwu" | "stdu") && operands == ["r1"] && stack_memory(&first, "r1"))
+                    || (mnemonic == "mflr" && operands == ["r0"])
+            }
+            _ => false,
+        }
+    }
+}
+
+// Filler the assembler puts between functions
+fn is_padding(instruction: &Instruction) -> bool {
+    let mnemonic = instruction.mnemonic.as_str();
+    mnemonic.starts_with("nop") || mnemonic == "int3" || mnemonic == "ud2" || mnemonic == "hlt"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover_functions, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Relocation, Section, Symbol};
+    use crate::instruction_set::InstructionSet;
+
+    fn section(index: usize, name: &str, data: Vec<u8>, executable: bool) -> Section {
+        Section {
+            index,
+            name: name.to_string(),
+            address: 0,
+            size: data.len() as u64,
+            entry_size: 0,
+            data,
+            allocated: true,
+            executable,
+            writable: false,
+            merged_strings: false,
+        }
+    }
+
+    fn symbol(name: &str, address: u64, size: u64) -> Symbol {
+        Symbol {
+            name: name.to_string(),
+            address,
+            size,
+            function: true,
+            global: true,
+            section: Some(1),
+            dynamic: false,
+            mangled: None,
+        }
+    }
+
+    fn relocation(address: u64, symbol: &str, section: usize) -> Relocation {
+        Relocation { address, kind: 4, symbol: Some(symbol.to_string()), addend: -4, section: Some(section), plt: false, mangled: None }
+    }
+
+    #[test]
+    fn object_file() {
+        // helper: call ext; ret. a: call helper; call ext; ret. Both calls of ext are left to the linker.
+        let mut text = vec![0xe8, 0, 0, 0, 0, 0xc3, 0xe8, 0xf5, 0xff, 0xff, 0xff, 0xe8, 0, 0, 0, 0, 0xc3];
+        text.resize(0x30, 0x90);
+        // A CIE and the FDE of helper, whose start is relocated
+        let mut eh_frame = vec![0x14, 0, 0, 0, 0, 0, 0, 0, 1, b'z', b'R', 0, 1, 0x78, 0x10, 1, 0x1b, 0x0c, 0x07, 0x08, 0x90, 0x01, 0, 0];
+        eh_frame.extend([0x14, 0, 0, 0, 0x1c, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0,
+            relocatable: true,
+            sections: vec![section(1, ".text", text, true), section(2, ".eh_frame", eh_frame, false)],
+            segments: Vec::new(),
+            symbols: vec![symbol("helper", 0, 6), symbol("a", 6, 11)],
+            relocations: vec![relocation(1, "ext", 1), relocation(0xc, "ext", 1), relocation(0x20, ".text", 2)],
+        };
+        let decoder = decoder_for(&image.target).unwrap();
+        let functions = discover_functions(&image, &*decoder);
+        let found: Vec<(u64, u64, String)> = functions.iter().map(|x| (x.start, x.end, x.display_name())).collect();
+        assert_eq!(found, [(0, 6, "helper".to_string()), (6, 0x11, "a".to_string())]);
+        assert_eq!(functions[0].sources, [Source::Symbol, Source::Call]);
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disasm::{FlowKind, Instruction, Operand, ShiftKind};
+use crate::image::Image;
+use crate::instruction_set::InstructionSet;
+use crate::ir::{self, Abi, BinaryOp, CastOp, CompareOp, Destination, Expr, Lifter, Stmt, Storage, UnaryOp};
+
+use super::xrefs::word;
+
+// How far back from the jump the computation of its target and the guarding compare are looked for
+const PATH_LENGTH: usize = 64;
+// Bounds beyond this are more likely wrong than a switch
+const MAX_ENTRIES: u64 = 4096;
+
+/// How the entries of a jump table give the targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entries {
+    // The entries are the addresses of the targets
+    Absolute,
+    // The targets are at `base` plus the entry shifted left by `shift`, sign extended first if `signed`
+    Relative { base: u64, shift: u8, signed: bool },
+}
+
+/// A table an indirect jump goes through, bounded by the compare guarding it
+#[derive(Debug, Clone)]
+pub struct JumpTable {
+    // The indirect jump
+    pub jump: u64,
+    // The address of the first entry
+    pub table: u64,
+    pub entry_size: u8,
+    pub entries: Entries,
+    // The value switched on for the first entry: the index is the value minus this
+    pub first: i64,
+    // By entry, up to the bound or the first entry that doesn't lead to code
+    pub targets: Vec<u64>,
+    // Where the guarding compare sends the values out of bounds
+    pub default: Option<u64>,
+}
+
+impl JumpTable {
+    /// The case values with their targets, in the order of the table
+    pub fn cases(&self) -> impl Iterator<Item = (i64, u64)> + '_ {
+        self.targets.iter().enumerate().map(move |(i, target)| (self.first.wrapping_add(i as i64), *target))
+    }
+
+    fn target(&self, entry: u64) -> u64 {
+        match self.entries {
+            Entries::Absolute => entry,
+            Entries::Relative { base, shift, signed } => {
+                let bits = self.entry_size as u32 * 8;
+                let value = if signed && bits < 64 { (((entry << (64 - bits)) as i64) >> (64 - bits)) as u64 } else { entry };
+                base.wrapping_add(value << shift)
+            }
+        }
+    }
+}
+
+/// The jump tables of the indirect jumps among `instructions`, which are those of a function
+///
+/// On x86, x86-64 and AArch64 the instructions leading to a jump are lifted to find its target as a load from a
+/// table indexed by a value an unsigned compare bounds. On ARM the pc relative table branches are recognized:
+/// tbb and tbh in Thumb, loads into the pc in ARM.
+pub fn jump_tables(image: &Image, instructions: &BTreeMap<u64, Instruction>) -> Vec<JumpTable> {
+    let lifter = ir::lifter_for(&image.target);
+    instructions
+        .values()
+        .filter(|x| x.flow == FlowKind::Jump && x.branch_target().is_none())
+        .filter_map(|x| jump_table(image, lifter.as_deref(), instructions, x))
+        .collect()
+}
+
+pub(super) fn jump_table(
+    image: &Image,
+    lifter: Option<&dyn Lifter>,
+    instructions: &BTreeMap<u64, Instruction>,
+    jump: &Instruction,
+) -> Option<JumpTable> {
+    let path = path(instructions, jump);
+    let mut table = match lifter {
+        Some(lifter) => lifted_table(image, lifter, &path)?,
+        None if image.target.instruction_set == InstructionSet::ARM => arm_table(&path)?,
+        None => return None,
+    };
+
+    let mask = match image.target.bits {
+        64 => u64::MAX,
+        bits => (1u64 << bits) - 1,
+    };
+    // Thumb code is jumped to with the low bit set
+    let thumb = if image.target.instruction_set == InstructionSet::ARM { !1 } else { !0 };
+    let count = table.targets.len() as u64;
+    table.targets.clear();
+    for i in 0..count {
+        let address = table.table + i * table.entry_size as u64;
+        let target = match read(image, address, table.entry_size as usize) {
+            Some(entry) => table.target(entry) & mask & thumb,
+            None => break,
+        };
+        if !image.is_code(target) {
+            break;
+        }
+        table.targets.push(target);
+    }
+    Some(table).filter(|x| !x.targets.is_empty())
+}
+
+// The instructions leading to `jump` and `jump` itself, in order: going back through the instruction falling through
+// to each, or else the only one jumping to it.
+fn path<'a>(instructions: &'a BTreeMap<u64, Instruction>, jump: &'a Instruction) -> Vec<&'a Instruction> {
+    let mut res = vec![jump];
+    let mut seen = BTreeSet::new();
+    seen.insert(jump.address);
+    while res.len() < PATH_LENGTH {
+        let current = res[res.len() - 1].address;
+        let falling = instructions.range(..current).next_back().map(|x| x.1).filter(|x| x.next_address() == current && x.falls_through());
+        let jumping: Vec<&Instruction> =
+            instructions.values().filter(|x| x.flow == FlowKind::Jump && x.branch_target() == Some(current)).collect();
+        let previous = match (falling, &jumping[..]) {
+            (Some(x), _) => x,
+            (None, [x]) => *x,
+            _ => break,
+        };
+        if !seen.insert(previous.address) {
+            break;
+        }
+        res.push(previous);
+    }
+    res.reverse();
+    res
+}
+
+// The table of the last instruction of `path` from the statements it lifts to. The targets are left with as many
+// placeholders as the bound allows, for the caller to read.
+fn lifted_table(image: &Image, lifter: &dyn Lifter, path: &[&Instruction]) -> Option<JumpTable> {
+    let jump = path[path.len() - 1];
+    // What calls leave as it was
+    let mut preserved: BTreeSet<Storage> = BTreeSet::new();
+    preserved.insert(lifter.stack_pointer().storage);
+    if let Some(abi) = Abi::detect(&image.target) {
+        preserved.extend(abi.convention().callee_saved.iter().map(|x| x.storage));
+    }
+    let mut values: BTreeMap<Storage, Expr> = BTreeMap::new();
+    // The condition under which the path goes on at the last branch, and where it goes otherwise
+    let mut guard: Option<(Expr, u64)> = None;
+    let mut target = None;
+    for (i, instruction) in path.iter().enumerate() {
+        let next = path.get(i + 1).map(|x| x.address);
+        for stmt in lifter.lift(instruction) {
+            let substitute = |mut expr: Expr| {
+                expr.substitute(&mut |var| values.get(&var.storage).filter(|x| x.ty() == var.ty).cloned());
+                expr.simplify()
+            };
+            match stmt {
+                Stmt::Assign { var, value } => {
+                    let value = substitute(value);
+                    values.insert(var.storage, value);
+                }
+                Stmt::Branch { condition, target: Destination::Direct(to) } if next.is_some() => {
+                    let taken = next == Some(to);
+                    let condition = substitute(condition);
+                    guard = Some(if taken {
+                        (condition, instruction.resume_address())
+                    } else {
+                        (Expr::unary(UnaryOp::Not, condition).simplify(), to)
+                    });
+                }
+                Stmt::Jump(Destination::Indirect(to)) if next.is_none() => target = Some(substitute(to)),
+                Stmt::Call { .. } | Stmt::Syscall { .. } => values.retain(|storage, _| preserved.contains(storage)),
+                _ => {}
+            }
+        }
+    }
+
+    let target = target?;
+    let (table, index, scale, load_bits) = match table_load(&target)? {
+        Expr::Load { address, ty, .. } => {
+            let (table, index, scale) = table_address(address)?;
+            (table, index, scale, ty.bits())
+        }
+        _ => return None,
+    };
+    let entry_size = load_bits / 8;
+    if scale != entry_size as u64 || !matches!(entry_size, 1 | 2 | 4 | 8) {
+        return None;
+    }
+    let entries = entries(&target, load_bits, image.target.bits)?;
+
+    let (condition, default) = guard?;
+    let (bounded, count) = bound(&condition)?;
+    let bounded = strip_extensions(&bounded);
+    if bounded != strip_extensions(index) || count == 0 || count > MAX_ENTRIES {
+        return None;
+    }
+    // Truncating doesn't change what is subtracted
+    let mut offset = bounded;
+    while let Expr::Cast(CastOp::Truncate, value, _) = offset {
+        offset = value;
+    }
+    let first = match offset {
+        Expr::Binary(BinaryOp::Sub, _, offset) => offset.constant().map(|x| signed(x, offset.ty().bits())),
+        Expr::Binary(BinaryOp::Add, _, offset) => offset.constant().map(|x| signed(x, offset.ty().bits()).wrapping_neg()),
+        _ => None,
+    };
+
+    Some(JumpTable {
+        jump: jump.address,
+        table,
+        entry_size: entry_size as u8,
+        entries,
+        first: first.unwrap_or(0),
+        targets: vec![0; count as usize],
+        default: Some(default),
+    })
+}
+
+// The value an unsigned compare bounds and the number of values it lets through
+fn bound(condition: &Expr) -> Option<(Expr, u64)> {
+    match condition {
+        Expr::Compare(CompareOp::ULe, value, bound) => Some(((**value).clone(), bound.constant()?.checked_add(1)?)),
+        Expr::Compare(CompareOp::ULt, value, bound) => Some(((**value).clone(), bound.constant()?)),
+        // Below or equal, from the flags of a compare, whose zero flag may be folded differently
+        Expr::Binary(BinaryOp::Or, left, right) => {
+            let (below, equal) = match (&**left, &**right) {
+                (below @ Expr::Compare(CompareOp::ULt, ..), equal) | (equal, below @ Expr::Compare(CompareOp::ULt, ..)) => (below, equal),
+                _ => return None,
+            };
+            let (value, count) = bound(below)?;
+            let bits = value.ty().bits();
+            let difference = Expr::binary(BinaryOp::Sub, value.clone(), Expr::int(count, bits));
+            if Expr::compare(CompareOp::Eq, difference, Expr::int(0, bits)).simplify() != *equal {
+                return None;
+            }
+            Some((value, count.checked_add(1)?))
+        }
+        // Not above, as the negation of the flags of above
+        Expr::Unary(UnaryOp::Not, value) => match &**value {
+            Expr::Binary(BinaryOp::And, left, right) => {
+                let negate = |x: &Expr| Expr::unary(UnaryOp::Not, x.clone()).simplify();
+                bound(&Expr::binary(BinaryOp::Or, negate(left), negate(right)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The value a jump through a table switches on, from the target of the jump: the index of the entry loaded,
+/// without the extensions to the size of an address
+pub fn table_index(target: &Expr) -> Option<&Expr> {
+    match table_load(target)? {
+        Expr::Load { address, .. } => table_address(address).map(|x| strip_extensions(x.1)),
+        _ => None,
+    }
+}
+
+// The only load in the computation of a target
+fn table_load(expr: &Expr) -> Option<&Expr> {
+    match expr {
+        Expr::Load { .. } => Some(expr),
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => table_load(value),
+        Expr::Binary(_, left, right) => match (table_load(left), table_load(right)) {
+            (Some(load), None) | (None, Some(load)) => Some(load),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The table, index and scale of the address of an entry: table + index * scale
+fn table_address(address: &Expr) -> Option<(u64, &Expr, u64)> {
+    let (table, scaled) = match address {
+        Expr::Binary(BinaryOp::Add, left, right) => match (left.constant(), right.constant()) {
+            (_, Some(table)) => (table, &**left),
+            (Some(table), None) => (table, &**right),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(match scaled {
+        Expr::Binary(BinaryOp::Mul, index, scale) if scale.constant().is_some() => (table, &**index, scale.constant()?),
+        Expr::Binary(BinaryOp::Shl, index, shift) if shift.constant().is_some_and(|x| x < 8) => {
+            (table, &**index, 1 << shift.constant()?)
+        }
+        index => (table, index, 1),
+    })
+}
+
+// How the loaded entry of `load_bits` becomes the target
+fn entries(target: &Expr, load_bits: u16, address_bits: u8) -> Option<Entries> {
+    let (base, mut current) = match target {
+        Expr::Binary(BinaryOp::Add, left, right) => match (left.constant(), right.constant()) {
+            (_, Some(base)) => (Some(base), &**left),
+            (Some(base), None) => (Some(base), &**right),
+            _ => return None,
+        },
+        _ => (None, target),
+    };
+    let (mut shift, mut signed) = (0, false);
+    loop {
+        current = match current {
+            Expr::Load { .. } => break,
+            Expr::Cast(CastOp::SignExtend, value, _) => {
+                signed = true;
+                value
+            }
+            Expr::Cast(CastOp::ZeroExtend, value, _) => value,
+            Expr::Cast(CastOp::Truncate, value, ty) if ty.bits() >= load_bits => value,
+            Expr::Binary(BinaryOp::Shl, value, amount) if amount.constant().is_some_and(|x| x < 8) => {
+                shift += amount.constant()? as u8;
+                value
+            }
+            Expr::Binary(BinaryOp::Mul, value, factor) if factor.constant().is_some_and(|x| x.is_power_of_two() && x < 256) => {
+                shift += factor.constant()?.trailing_zeros() as u8;
+                value
+            }
+            _ => return None,
+        };
+    }
+    match base {
+        Some(base) => Some(Entries::Relative { base, shift, signed }),
+        None if shift == 0 && load_bits == address_bits as u16 => Some(Entries::Absolute),
+        None => None,
+    }
+}
+
+fn strip_extensions(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Cast(CastOp::ZeroExtend | CastOp::SignExtend, value, _) => strip_extensions(value),
+        _ => expr,
+    }
+}
+
+fn signed(value: u64, bits: u16) -> i64 {
+    if bits >= 64 {
+        value as i64
+    } else {
+        ((value << (64 - bits)) as i64) >> (64 - bits)
+    }
+}
+
+// The Thumb table branches, whose halfword offsets follow them, and loads into the pc from a table after them in
+// ARM code. Their index is bounded by a compare with an immediate before them.
+fn arm_table(path: &[&Instruction]) -> Option<JumpTable> {
+    let jump = path[path.len() - 1];
+    let memory = jump.operands.iter().find_map(|x| match x {
+        Operand::Memory(memory) => Some(memory),
+        _ => None,
+    })?;
+    let index = memory.index?;
+    if memory.base?.name != "pc" || memory.subtract {
+        return None;
+    }
+    let mnemonic = jump.mnemonic.split('.').next().unwrap_or("");
+    let (table, entry_size, entries) = match mnemonic {
+        "tbb" => (jump.address + 4, 1, Entries::Relative { base: jump.address + 4, shift: 1, signed: false }),
+        "tbh" => (jump.address + 4, 2, Entries::Relative { base: jump.address + 4, shift: 1, signed: false }),
+        _ if mnemonic.starts_with("ldr")
+            && matches!(jump.operands.first(), Some(Operand::Register(register)) if register.name == "pc")
+            && memory.index_shift == Some((ShiftKind::Lsl, 2)) =>
+        {
+            (jump.address + 8, 4, Entries::Absolute)
+        }
+        _ => return None,
+    };
+
+    // The compare of the index, and the conditional branch or the condition of the jump using it
+    let mut bound = None;
+    let mut branch = None;
+    for instruction in path[..path.len() - 1].iter().rev() {
+        let registers: Vec<&'static str> = instruction
+            .operands
+            .iter()
+            .filter_map(|x| match x {
+                Operand::Register(register) => Some(register.name),
+                _ => None,
+            })
+            .collect();
+        if instruction.mnemonic.starts_with("cmp") {
+            if let [Operand::Register(register), Operand::Immediate { value, .. }] = &instruction.operands[..] {
+                if register.name == index.name {
+                    bound = Some(*value as u64);
+                    break;
+                }
+            }
+        }
+        if instruction.flow == FlowKind::Jump && instruction.conditional && branch.is_none() {
+            branch = Some(*instruction);
+        } else if registers.first() == Some(&index.name) && !instruction.mnemonic.starts_with("st") {
+            // The index is written after the compare
+            return None;
+        }
+    }
+    let bound = bound?;
+    let condition = |mnemonic: &str, prefix: &str| -> String {
+        let base = mnemonic.split('.').next().unwrap_or("");
+        base.strip_prefix(prefix).unwrap_or("").to_string()
+    };
+    let (count, default) = match branch {
+        // Taken for the values out of bounds
+        Some(branch) => match condition(&branch.mnemonic, "b").as_str() {
+            "hi" => (bound + 1, branch.branch_target()),
+            "hs" | "cs" => (bound, branch.branch_target()),
+            _ => return None,
+        },
+        // Executed for the values in bounds
+        None => match condition(mnemonic, &mnemonic[..3]).as_str() {
+            "ls" => (bound + 1, Some(jump.resume_address())),
+            "lo" | "cc" => (bound, Some(jump.resume_address())),
+            _ => return None,
+        },
+    };
+    if count == 0 || count > MAX_ENTRIES {
+        return None;
+    }
+    Some(JumpTable { jump: jump.address, table, entry_size, entries, first: 0, targets: vec![0; count as usize], default })
+}
+
+// An entry of `size` bytes
+fn read(image: &Image, address: u64, size: usize) -> Option<u64> {
+    let section = image.section_at(address)?;
+    let offset = (address - section.address) as usize;
+    let bytes = section.data.get(offset..offset + size)?;
+    Some(word(bytes, image.target.endianness))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{jump_tables, Entries};
+    use crate::disasm::arm::ArmMode;
+    use crate::disasm::{decoder_for, Instruction, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Section};
+    use crate::instruction_set::InstructionSet;
+
+    // An image with the code at 0x1000 and read only data at 0x2000
+    fn image(target: Target, code: &[u8], rodata: &[u8]) -> Image {
+        let section = |index: usize, name: &str, address: u64, data: &[u8], executable: bool| Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size: 0,
+            data: data.to_vec(),
+            allocated: true,
+            executable,
+            writable: false,
+            merged_strings: false,
+        };
+        Image {
+            target,
+            entry: 0x1000,
+            relocatable: false,
+            sections: vec![section(1, ".text", 0x1000, code, true), section(2, ".rodata", 0x2000, rodata, false)],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+        }
+    }
+
+    fn instructions(image: &Image) -> BTreeMap<u64, Instruction> {
+        let decoder = decoder_for(&image.target).unwrap();
+        let (start, code) = image.code()[0];
+        let mut instructions = BTreeMap::new();
+        let mut offset = 0;
+        while offset < code.len() {
+            let instruction = decoder.decode(&code[offset..], start + offset as u64).unwrap();
+            offset += instruction.length;
+            instructions.insert(instruction.address, instruction);
+        }
+        instructions
+    }
+
+    #[test]
+    fn relative_entries_with_an_offset_index() {
+        let mut code = vec![
+            0x83, 0xef, 0x0a, // sub edi, 10
+            0x83, 0xff, 0x03, // cmp edi, 3
+            0x77, 0x28, // ja 0x1030
+            0x89, 0xff, // mov edi, edi
+            0x48, 0x8d, 0x15, 0xef, 0x0f, 0x00, 0x00, // lea rdx, [rip + 0xfef]
+            0x48, 0x63, 0x04, 0xba, // movsxd rax, DWORD PTR [rdx + rdi * 4]
+            0x48, 0x01, 0xd0, // add rax, rdx
+            0xff, 0xe0, // jmp rax
+        ];
+        code.resize(0x31, 0xc3);
+        let rodata: Vec<u8> = [0x1020u32, 0x1024, 0x1028, 0x102c].iter().flat_map(|x| (x.wrapping_sub(0x2000)).to_le_bytes()).collect();
+        let image = image(Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64), &code, &rodata);
+
+        let tables = jump_tables(&image, &instructions(&image));
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!((table.jump, table.table, table.entry_size), (0x1018, 0x2000, 4));
+        assert_eq!(table.entries, Entries::Relative { base: 0x2000, shift: 0, signed: true });
+        assert_eq!(table.default, Some(0x1030));
+        assert_eq!(table.cases().collect::<Vec<_>>(), [(10, 0x1020), (11, 0x1024), (12, 0x1028), (13, 0x102c)]);
+    }
+
+    #[test]
+    fn absolute_entries_stop_at_data() {
+        let mut code = vec![
+            0x83, 0xf8, 0x03, // cmp eax, 3
+            0x77, 0x1b, // ja 0x1020
+            0xff, 0x24, 0x85, 0x00, 0x20, 0x00, 0x00, // jmp DWORD PTR [eax * 4 + 0x2000]
+        ];
+        code.resize(0x21, 0xc3);
+        // The bound lets four values through, but the last entry points out of the code
+        let rodata: Vec<u8> = [0x1010u32, 0x1014, 0x1018, 0x2000].iter().flat_map(|x| x.to_le_bytes()).collect();
+        let image = image(Target::new(InstructionSet::X86, Endianness::LittleEndian, 32), &code, &rodata);
+
+        let tables = jump_tables(&image, &instructions(&image));
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].entries, Entries::Absolute);
+        assert_eq!(tables[0].default, Some(0x1020));
+        assert_eq!(tables[0].targets, [0x1010, 0x1014, 0x1018]);
+    }
+
+    #[test]
+    fn thumb_table_branch() {
+        let code = [
+            0x02, 0x28, // cmp r0, #2
+            0x06, 0xd8, // bhi 0x1012
+            0xdf, 0xe8, 0x00, 0xf0, // tbb [pc, r0]
+            0x02, 0x03, 0x04, 0x00, // the table, in halfwords from its start
+            0x01, 0x20, 0x02, 0x20, 0x70, 0x47, 0x70, 0x47,
+        ];
+        let mut target = Target::new(InstructionSet::ARM, Endianness::LittleEndian, 32);
+        target.arm_regions = vec![(0x1000, ArmMode::Thumb), (0x1008, ArmMode::Data), (0x100c, ArmMode::Thumb)];
+        let image = image(target, &code, &[]);
+
+        let tables = jump_tables(&image, &instructions(&image));
+        assert_eq!(tables.len(), 1);
+        assert_eq!((tables[0].jump, tables[0].table, tables[0].entry_size), (0x1004, 0x1008, 1));
+        assert_eq!(tables[0].default, Some(0x1012));
+        assert_eq!(tables[0].cases().collect::<Vec<_>>(), [(0, 0x100c), (1, 0x100e), (2, 0x1010)]);
+    }
+}
@@ -0,0 +1,36 @@
+mod disassembly;
+pub use self::disassembly::{linear_sweep, listing, recursive_descent, Line};
+
+mod callgraph;
+pub use self::callgraph::CallGraph;
+
+mod cfg;
+pub use self::cfg::{function_cfg, BlockId, Cfg, DominatorTree, Edge, EdgeKind};
+pub(crate) use self::cfg::{dominator_tree, reverse_postorder};
+
+mod structure;
+pub use self::structure::{structure, BlockContents, Case, Condition, LoopKind, Node, Structure};
+
+mod debuginfo;
+pub use self::debuginfo::debug_info;
+
+mod exceptions;
+pub use self::exceptions::frame_table;
+
+mod functions;
+pub use self::functions::{discover_functions, Function, Source};
+
+mod jumptables;
+pub use self::jumptables::{jump_tables, table_index, Entries, JumpTable};
+
+mod plt;
+pub use self::plt::{plt_names, plt_stubs, PLT_SECTIONS};
+
+mod strings;
+pub use self::strings::{find_strings, Encoding, FoundString, StringOptions};
+
+mod unwind;
+pub use self::unwind::{debug_frame_table, eh_frame_header, read_memory, row_at, stack_height, unwind_rows, unwinder};
+
+mod xrefs;
+pub use self::xrefs::{Xref, XrefKind, Xrefs};
@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::disasm::{self, Decoder, FlowKind, Operand, RegisterClass};
+use crate::image::{Image, Relocation, Section};
+use crate::instruction_set::InstructionSet;
+
+use super::disassembly::{linear_sweep, Line};
+
+/// Sections holding stubs that jump through the GOT
+pub const PLT_SECTIONS: [&str; 4] = [".plt", ".plt.sec", ".plt.got", ".iplt"];
+
+/// A stub through which code calls a symbol of another module
+#[derive(Debug, Clone)]
+pub struct PltStub {
+    pub address: u64,
+    // Exclusive
+    pub end: u64,
+    pub section: String,
+    // GOT slot the stub jumps through, which the dynamic linker fills with the address of the symbol
+    pub slot: Option<u64>,
+    pub symbol: Option<String>,
+    // A lazy binding entry of .plt, which only pushes the index of its JMPREL relocation. With IBT, the stubs
+    // that are called are in .plt.sec.
+    pub lazy: bool,
+}
+
+/// Names of the PLT stubs, as symbol@plt by the address of the stub
+pub fn plt_names(image: &Image, decoder: &dyn Decoder) -> BTreeMap<u64, String> {
+    plt_stubs(image, decoder)
+        .into_iter()
+        .filter(|x| !x.lazy)
+        .filter_map(|x| Some((x.address, format!("{}@plt", x.symbol?))))
+        .collect()
+}
+
+/// The stubs of the PLT sections, .plt.sec and .plt.got included, on x86-64, i386, AArch64 and ARM
+///
+/// A stub is tied to a symbol through the GOT slot it jumps through, which is where the dynamic linker applies
+/// the relocation for the symbol. Lazy binding entries without a slot give the index of the relocation in JMPREL
+/// instead.
+pub fn plt_stubs(image: &Image, decoder: &dyn Decoder) -> Vec<PltStub> {
+    let mut slots: HashMap<u64, &Relocation> = HashMap::new();
+    // JMPREL relocations win over others for the same slot
+    for relocation in image.relocations.iter() {
+        if relocation.plt || !slots.contains_key(&relocation.address) {
+            slots.insert(relocation.address, relocation);
+        }
+    }
+    let jmprel: Vec<&Relocation> = image.relocations.iter().filter(|x| x.plt).collect();
+
+    let mut stubs = Vec::new();
+    for section in image.sections.iter().filter(|x| PLT_SECTIONS.contains(&x.name.as_str()) && !x.data.is_empty()) {
+        let instructions: Vec<disasm::Instruction> = linear_sweep(decoder, &section.data, section.address)
+            .into_iter()
+            .filter_map(|x| match x {
+                Line::Instruction(instruction) => Some(instruction),
+                _ => None,
+            })
+            .collect();
+
+        for chunk in split(image, section, &instructions) {
+            let (start, end) = match (chunk.first(), chunk.last()) {
+                (Some(first), Some(last)) => (first.address, last.next_address()),
+                _ => continue,
+            };
+            let slot = slot(image, decoder, chunk);
+            let mut symbol = slot.and_then(|x| slots.get(&x)).map(|x| match &x.symbol {
+                Some(name) => name.clone(),
+                // IRELATIVE relocations of ifuncs have the resolver as addend, objdump names them after it
+                None => format!("*ABS*+0x{:x}", x.addend),
+            });
+            let mut lazy = false;
+            if symbol.is_none() {
+                symbol = jmprel_index(image, chunk).and_then(|x| jmprel.get(x)).and_then(|x| x.symbol.clone());
+                lazy = symbol.is_some();
+            }
+            stubs.push(PltStub { address: start, end, section: section.name.clone(), slot, symbol, lazy });
+        }
+    }
+    stubs
+}
+
+// The instructions of each stub of a section. Most sections give the size of their entries, the first of which
+// is the stub calling the dynamic linker. ARM doesn't, there the stubs end with their jump, except for the first
+// one which has the offset of the GOT after it.
+fn split<'a>(image: &Image, section: &Section, instructions: &'a [disasm::Instruction]) -> Vec<&'a [disasm::Instruction]> {
+    let instruction_set = image.target.instruction_set;
+    let entry_size = match instruction_set {
+        _ if section.entry_size >= 8 => section.entry_size,
+        // i386 gives the size of the GOT entries instead
+        InstructionSet::X86 => 16,
+        _ => 0,
+    };
+    // The first AArch64 entry is twice as large
+    let first_size = match (instruction_set, section.name.as_str()) {
+        (InstructionSet::AArch64, ".plt") => 2 * entry_size,
+        (InstructionSet::ARM, ".plt") => 20,
+        _ => entry_size,
+    };
+    let entry = |address: u64| {
+        let offset = address - section.address;
+        if offset < first_size {
+            0
+        } else {
+            1 + (offset - first_size) / entry_size
+        }
+    };
+
+    let mut res = Vec::new();
+    let mut start = 0;
+    for (i, instruction) in instructions.iter().enumerate() {
+        let offset = instruction.address - section.address;
+        let ends = if entry_size > 0 {
+            instructions.get(i + 1).is_none_or(|next| entry(next.address) != entry(instruction.address))
+        } else if offset < first_size {
+            instructions.get(i + 1).is_none_or(|next| next.address - section.address >= first_size)
+        } else {
+            // bx pc switches Thumb stubs to ARM for the rest of the stub
+            let mode_switch = instruction.mnemonic == "bx"
+                && matches!(instruction.operands[..], [Operand::Register(x)] if x.class == RegisterClass::ProgramCounter);
+            let jumps = match instruction.flow {
+                FlowKind::Jump | FlowKind::Return => !instruction.conditional,
+                FlowKind::Trap => true,
+                _ => false,
+            };
+            (jumps && !mode_switch) || i + 1 == instructions.len()
+        };
+        if !ends {
+            continue;
+        }
+
+        // Padding between stubs belongs to none of them
+        let chunk = &instructions[start..=i];
+        let padding = chunk.iter().take_while(|x| x.mnemonic.starts_with("nop") || is_thumb_nop(x)).count();
+        if padding < chunk.len() {
+            res.push(&chunk[padding..]);
+        }
+        start = i + 1;
+    }
+    res
+}
+
+// mov r8, r8, the Thumb nop before ARMv6T2
+fn is_thumb_nop(instruction: &disasm::Instruction) -> bool {
+    instruction.mnemonic == "mov" && matches!(instruction.operands[..], [Operand::Register(a), Operand::Register(b)] if a == b)
+}
+
+// The GOT slot a stub loads its target from. Register values are followed through the instructions computing
+// the address: adrp on AArch64, adr, add, movw and movt on ARM.
+fn slot(image: &Image, decoder: &dyn Decoder, stub: &[disasm::Instruction]) -> Option<u64> {
+    let instruction_set = image.target.instruction_set;
+    let mask = if image.target.bits == 32 { 0xffff_ffff } else { u64::MAX };
+    let mut registers: HashMap<(RegisterClass, u16), u64> = HashMap::new();
+    // i386 position independent stubs find the GOT in ebx
+    if instruction_set == InstructionSet::X86 {
+        if let Some(got) = image.section_by_name(".got.plt").or_else(|| image.section_by_name(".got")) {
+            registers.insert((RegisterClass::General, 3), got.address);
+        }
+    }
+
+    for instruction in stub {
+        if let Some(target) = instruction.memory_targets().first() {
+            return Some(*target);
+        }
+        let address = instruction.operands.iter().find_map(|x| match x {
+            Operand::Memory(mem) if mem.index.is_none() => match mem.base {
+                Some(base) => registers.get(&(base.class, base.number)).map(|x| x.wrapping_add(mem.displacement as u64) & mask),
+                // Position dependent i386 stubs jump through the absolute address of the slot
+                None if instruction_set == InstructionSet::X86 => Some(mem.displacement as u64 & mask),
+                None => None,
+            },
+            _ => None,
+        });
+        if address.is_some() {
+            return address;
+        }
+
+        // pc reads as two instructions ahead in ARM state and one 32-bit word ahead in Thumb state
+        let pc = instruction.address + if decoder.alignment(instruction.address) == 2 { 4 } else { 8 };
+        let value = |operand: &Operand| match operand {
+            Operand::Register(x) if x.class == RegisterClass::ProgramCounter => Some(pc),
+            Operand::Register(x) => registers.get(&(x.class, x.number)).copied(),
+            Operand::Immediate { value, .. } => Some(*value as u64),
+            Operand::Address(x) => Some(*x),
+            _ => None,
+        };
+        let destination = match instruction.operands.first() {
+            Some(Operand::Register(x)) => (x.class, x.number),
+            _ => continue,
+        };
+        let result = match (instruction.mnemonic.as_str(), &instruction.operands[..]) {
+            ("adr" | "adrp" | "movw", [_, source]) => value(source),
+            ("add" | "add.w", [_, a, b]) | ("add", [a, b]) => value(a).zip(value(b)).map(|(a, b)| a.wrapping_add(b)),
+            ("movt", [a, source]) => value(a).zip(value(source)).map(|(a, b)| (a & 0xffff) | (b << 16)),
+            _ => None,
+        };
+        match result {
+            Some(x) => registers.insert(destination, x & mask),
+            None => registers.remove(&destination),
+        };
+    }
+    None
+}
+
+// The index of the JMPREL relocation a lazy binding entry pushes before jumping to the first entry. i386 pushes
+// the offset of the relocation in .rel.plt instead.
+fn jmprel_index(image: &Image, stub: &[disasm::Instruction]) -> Option<usize> {
+    let pushed = stub.iter().filter(|x| x.mnemonic == "push").find_map(|x| match x.operands[..] {
+        [Operand::Immediate { value, .. }] => Some(value as usize),
+        _ => None,
+    })?;
+    match image.target.instruction_set {
+        InstructionSet::X86_64 => Some(pushed),
+        InstructionSet::X86 => Some(pushed / 8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plt_names, plt_stubs};
+    use crate::analysis::callgraph::{CallGraph, Callee};
+    use crate::analysis::{Function, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Relocation, Section};
+    use crate::instruction_set::InstructionSet;
+
+    fn section(index: usize, name: &str, address: u64, entry_size: u64, data: Vec<u8>) -> Section {
+        let executable = !name.starts_with(".got");
+        Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size,
+            data,
+            allocated: true,
+            executable,
+            writable: !executable,
+            merged_strings: false,
+        }
+    }
+
+    fn relocation(address: u64, symbol: &str, plt: bool) -> Relocation {
+        Relocation { address, kind: 7, symbol: Some(symbol.to_string()), addend: 0, section: None, plt, mangled: None }
+    }
+
+    // What ld links for puts and malloc called through the PLT with IBT, and __cxa_finalize only through the GOT
+    fn image() -> Image {
+        let plt = [
+            // push [rip + GOT + 8]; jmp [rip + GOT + 16]; nop
+            &[0xff, 0x35, 0xe2, 0x2f, 0, 0, 0xff, 0x25, 0xe4, 0x2f, 0, 0, 0x0f, 0x1f, 0x40, 0][..],
+            // endbr64; push 0; bnd jmp .plt; nop
+            &[0xf3, 0x0f, 0x1e, 0xfa, 0x68, 0, 0, 0, 0, 0xf2, 0xe9, 0xe1, 0xff, 0xff, 0xff, 0x90],
+            &[0xf3, 0x0f, 0x1e, 0xfa, 0x68, 1, 0, 0, 0, 0xf2, 0xe9, 0xd1, 0xff, 0xff, 0xff, 0x90],
+        ]
+        .concat();
+        // endbr64; bnd jmp [rip + slot]; nop
+        let plt_sec = [
+            &[0xf3, 0x0f, 0x1e, 0xfa, 0xf2, 0xff, 0x25, 0xad, 0x2f, 0, 0, 0x0f, 0x1f, 0x44, 0, 0][..],
+            &[0xf3, 0x0f, 0x1e, 0xfa, 0xf2, 0xff, 0x25, 0xa5, 0x2f, 0, 0, 0x0f, 0x1f, 0x44, 0, 0],
+        ]
+        .concat();
+        // jmp [rip + slot]; xchg ax, ax
+        let plt_got = vec![0xff, 0x25, 0x72, 0x2f, 0, 0, 0x66, 0x90];
+        // call puts@plt; call [rip + malloc@got], as -fno-plt compiles it; ret
+        let text = vec![0xe8, 0x5b, 0xff, 0xff, 0xff, 0xff, 0x15, 0x15, 0x2f, 0, 0, 0xc3];
+        Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0x1100,
+            relocatable: false,
+            sections: vec![
+                section(1, ".plt", 0x1020, 16, plt),
+                section(2, ".plt.sec", 0x1060, 16, plt_sec),
+                section(3, ".plt.got", 0x1080, 8, plt_got),
+                section(4, ".text", 0x1100, 0, text),
+                section(5, ".got", 0x3ff8, 8, vec![0; 8]),
+                section(6, ".got.plt", 0x4000, 8, vec![0; 0x28]),
+            ],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: vec![
+                relocation(0x3ff8, "__cxa_finalize", false),
+                relocation(0x4018, "puts", true),
+                relocation(0x4020, "malloc", true),
+            ],
+        }
+    }
+
+    #[test]
+    fn x86_64_stubs() {
+        let image = image();
+        let decoder = decoder_for(&image.target).unwrap();
+        let stubs: Vec<_> = plt_stubs(&image, &*decoder)
+            .into_iter()
+            .map(|x| (x.address, x.end, x.section, x.slot, x.symbol, x.lazy))
+            .collect();
+        let stub = |address, section: &str, slot, symbol: Option<&str>, lazy| {
+            let end = address + if section == ".plt.got" { 8 } else { 16 };
+            (address, end, section.to_string(), slot, symbol.map(|x| x.to_string()), lazy)
+        };
+        assert_eq!(stubs, vec![
+            // The entry calling the dynamic linker has no symbol, the lazy binding entries find theirs through JMPREL
+            stub(0x1020, ".plt", Some(0x4008), None, false),
+            stub(0x1030, ".plt", None, Some("puts"), true),
+            stub(0x1040, ".plt", None, Some("malloc"), true),
+            stub(0x1060, ".plt.sec", Some(0x4018), Some("puts"), false),
+            stub(0x1070, ".plt.sec", Some(0x4020), Some("malloc"), false),
+            stub(0x1080, ".plt.got", Some(0x3ff8), Some("__cxa_finalize"), false),
+        ]);
+
+        let names: Vec<_> = plt_names(&image, &*decoder).into_iter().collect();
+        let expected = [(0x1060, "puts@plt"), (0x1070, "malloc@plt"), (0x1080, "__cxa_finalize@plt")];
+        assert_eq!(names, expected.map(|(address, name)| (address, name.to_string())));
+
+        // Calls through the stubs and straight through the GOT both reach the symbol
+        let functions = vec![Function { start: 0x1100, end: 0x110c, name: None, sources: vec![Source::Entry] }];
+        let graph = CallGraph::build(&image, &*decoder, functions);
+        let callees: Vec<_> = graph.calls.into_iter().map(|x| x.callee).collect();
+        assert_eq!(callees, vec![Callee::Import("puts@plt".to_string()), Callee::Import("malloc".to_string())]);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::endian::Endianness;
+use crate::image::{Image, Section};
+
+use super::xrefs::{XrefKind, Xrefs};
+
+/// How the characters of a string are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Encoding {
+    Ascii,
+    // With at least one character outside of ASCII
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 6] =
+        [Encoding::Ascii, Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Ascii => "ascii",
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Utf32Le => "utf-32le",
+            Encoding::Utf32Be => "utf-32be",
+        }
+    }
+
+    /// The encoding called `name`, with or without the dash
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        let name = name.to_ascii_lowercase().replace('-', "");
+        Encoding::ALL.iter().copied().find(|x| x.name().replace('-', "") == name)
+    }
+
+    /// Size of a code unit in bytes
+    pub fn width(self) -> usize {
+        match self {
+            Encoding::Ascii | Encoding::Utf8 => 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+            Encoding::Utf32Le | Encoding::Utf32Be => 4,
+        }
+    }
+
+    fn endianness(self) -> Endianness {
+        match self {
+            Encoding::Utf16Be | Encoding::Utf32Be => Endianness::BigEndian,
+            _ => Endianness::LittleEndian,
+        }
+    }
+
+    // The first character of `bytes` and its size, None if they don't start with a valid character
+    fn decode(self, bytes: &[u8]) -> Option<(char, usize)> {
+        let unit = |offset: usize| {
+            let bytes = bytes.get(offset..offset + self.width())?;
+            let value = match self.endianness() {
+                Endianness::LittleEndian => bytes.iter().rev().fold(0, |acc, x| (acc << 8) | *x as u32),
+                Endianness::BigEndian => bytes.iter().fold(0, |acc, x| (acc << 8) | *x as u32),
+            };
+            Some(value)
+        };
+        match self {
+            Encoding::Ascii => bytes.first().filter(|x| x.is_ascii()).map(|x| (*x as char, 1)),
+            // Incomplete sequences are errors, so the first prefix that decodes is the character
+            Encoding::Utf8 => (1..=bytes.len().min(4))
+                .find_map(|n| std::str::from_utf8(&bytes[..n]).ok())
+                .and_then(|x| x.chars().next())
+                .map(|x| (x, x.len_utf8())),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let high = unit(0)?;
+                if !(0xd800..0xdc00).contains(&high) {
+                    return char::from_u32(high).map(|x| (x, 2));
+                }
+                let low = unit(2).filter(|x| (0xdc00..0xe000).contains(x))?;
+                char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)).map(|x| (x, 4))
+            }
+            Encoding::Utf32Le | Encoding::Utf32Be => char::from_u32(unit(0)?).map(|x| (x, 4)),
+        }
+    }
+}
+
+/// What to look for
+#[derive(Debug, Clone)]
+pub struct StringOptions {
+    // In characters
+    pub min_length: usize,
+    // UTF-8 includes ASCII
+    pub encodings: Vec<Encoding>,
+}
+
+impl Default for StringOptions {
+    fn default() -> StringOptions {
+        StringOptions { min_length: 4, encodings: Encoding::ALL.to_vec() }
+    }
+}
+
+impl StringOptions {
+    fn wants(&self, encoding: Encoding) -> bool {
+        self.encodings.contains(&encoding) || (encoding == Encoding::Ascii && self.encodings.contains(&Encoding::Utf8))
+    }
+}
+
+/// A string in the data of the binary
+#[derive(Debug, Clone)]
+pub struct FoundString {
+    pub address: u64,
+    // In bytes, without the terminator
+    pub size: usize,
+    pub encoding: Encoding,
+    pub value: String,
+    pub section: String,
+    // The instructions referring to the string or into it, which merged strings may share the end of
+    pub references: Vec<u64>,
+}
+
+/// The strings in the mapped data sections. Sections of merged strings are split at their terminators, the others
+/// are scanned for runs of printable characters in each of the encodings. Tables of fixed size entries, such as
+/// the symbol tables, and the unwinding tables are not scanned.
+pub fn find_strings(image: &Image, xrefs: &Xrefs, options: &StringOptions) -> Vec<FoundString> {
+    let mut res = Vec::new();
+    for section in image.sections.iter().filter(|x| x.allocated && !x.executable && !x.data.is_empty()) {
+        let table = section.entry_size != 0 || [".eh_frame", ".gcc_except_table"].iter().any(|x| section.name.starts_with(x));
+        let found = match (section.merged_strings, table) {
+            (true, _) => merged_strings(image, section, options),
+            (false, true) => continue,
+            (false, false) => scan(section, options),
+        };
+        res.extend(found.into_iter().map(|(offset, size, encoding, value)| {
+            let address = section.address + offset as u64;
+            // The sections of object files all start at 0, so addresses don't tell what is referred to
+            let xrefs = if image.relocatable { Vec::new() } else { xrefs.to_range(address, address + size as u64 + 1) };
+            let mut references: Vec<u64> = xrefs
+                .iter()
+                .filter(|x| !matches!(x.kind, XrefKind::Pointer | XrefKind::Relocation))
+                .map(|x| x.from)
+                .collect();
+            references.sort_unstable();
+            references.dedup();
+            FoundString { address, size, encoding, value, section: section.name.clone(), references }
+        }));
+    }
+    res.sort_by_key(|x| x.address);
+    res
+}
+
+// The null terminated entries of a section of merged strings, as (offset, size, encoding, value)
+fn merged_strings(image: &Image, section: &Section, options: &StringOptions) -> Vec<(usize, usize, Encoding, String)> {
+    let big = image.target.endianness == Endianness::BigEndian;
+    let encoding = match section.entry_size {
+        2 if big => Encoding::Utf16Be,
+        2 => Encoding::Utf16Le,
+        4 if big => Encoding::Utf32Be,
+        4 => Encoding::Utf32Le,
+        _ => Encoding::Utf8,
+    };
+    let width = encoding.width();
+
+    let mut res = Vec::new();
+    let mut start = 0;
+    while start < section.data.len() {
+        let terminator = (start..section.data.len())
+            .step_by(width)
+            .find(|x| section.data[*x..].iter().take(width).all(|x| *x == 0))
+            .unwrap_or(section.data.len());
+        let bytes = &section.data[start..terminator];
+        let mut value = String::new();
+        let mut offset = 0;
+        while let Some((c, size)) = encoding.decode(&bytes[offset..]) {
+            value.push(c);
+            offset += size;
+        }
+        // Everything up to the terminator is the string, whatever the characters are
+        let encoding = if encoding == Encoding::Utf8 && value.is_ascii() { Encoding::Ascii } else { encoding };
+        if offset == bytes.len() && value.chars().count() >= options.min_length.max(1) && options.wants(encoding) {
+            res.push((start, bytes.len(), encoding, value));
+        }
+        start = terminator + width;
+    }
+    res
+}
+
+// Runs of printable characters in each of the encodings. Where they overlap, the narrower encoding wins, since ASCII
+// read two bytes at a time gives plausible CJK characters.
+fn scan(section: &Section, options: &StringOptions) -> Vec<(usize, usize, Encoding, String)> {
+    let mut res: Vec<(usize, usize, Encoding, String)> = Vec::new();
+    let narrow = if options.encodings.contains(&Encoding::Utf8) { Encoding::Utf8 } else { Encoding::Ascii };
+    for encoding in [narrow, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be] {
+        if !options.wants(encoding) {
+            continue;
+        }
+        // Wide strings are aligned to their code units
+        let skip = (section.address as usize).wrapping_neg() % encoding.width();
+        for (offset, size, value) in runs(section.data.get(skip..).unwrap_or_default(), encoding, options.min_length) {
+            let offset = offset + skip;
+            let encoding = if encoding == Encoding::Utf8 && value.is_ascii() { Encoding::Ascii } else { encoding };
+            // Random data decodes to long runs of wide characters, text in other scripts than latin is only found in
+            // merged strings
+            if encoding.width() > 1 && value.chars().filter(|x| x.is_ascii()).count() * 2 < value.chars().count() {
+                continue;
+            }
+            if !options.wants(encoding) || res.iter().any(|x| offset < x.0 + x.1 && x.0 < offset + size) {
+                continue;
+            }
+            res.push((offset, size, encoding, value));
+        }
+    }
+    res.sort_by_key(|x| x.0);
+    res
+}
+
+// The runs of at least `min_length` printable characters, as (offset, size, value). Wide characters outside of the
+// basic multilingual plane mostly come from integers next to strings.
+fn runs(data: &[u8], encoding: Encoding, min_length: usize) -> Vec<(usize, usize, String)> {
+    let printable = |c: char| {
+        (!c.is_control() || matches!(c, '\t' | '\n' | '\r')) && (encoding.width() == 1 || (c as u32) < 0x10000)
+    };
+    let mut res = Vec::new();
+    let mut start = 0;
+    let mut value = String::new();
+    let mut length = 0;
+    let mut offset = 0;
+    while offset < data.len() {
+        match encoding.decode(&data[offset..]).filter(|x| printable(x.0) && x.0 != '\u{fffd}') {
+            Some((c, size)) => {
+                if length == 0 {
+                    start = offset;
+                }
+                value.push(c);
+                length += 1;
+                offset += size;
+            }
+            None => {
+                if length >= min_length.max(1) {
+                    res.push((start, offset - start, std::mem::take(&mut value)));
+                }
+                value.clear();
+                length = 0;
+                offset += encoding.width();
+            }
+        }
+    }
+    if length >= min_length.max(1) {
+        res.push((start, offset - start, value));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_strings, Encoding, StringOptions};
+    use crate::analysis::Xrefs;
+    use crate::disasm::Target;
+    use crate::endian::Endianness;
+    use crate::image::{Image, Section};
+    use crate::instruction_set::InstructionSet;
+
+    fn section(index: usize, name: &str, address: u64, entry_size: u64, data: Vec<u8>) -> Section {
+        Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size,
+            data,
+            allocated: true,
+            executable: false,
+            writable: false,
+            merged_strings: entry_size != 0,
+        }
+    }
+
+    fn strings(image: &Image, encodings: &[Encoding]) -> Vec<(u64, usize, Encoding, String)> {
+        let options = StringOptions { min_length: 4, encodings: encodings.to_vec() };
+        find_strings(image, &Xrefs::default(), &options).into_iter().map(|x| (x.address, x.size, x.encoding, x.value)).collect()
+    }
+
+    #[test]
+    fn encodings() {
+        // Strings in each encoding, at the alignment of their code units
+        let mut rodata = vec![0; 0x40];
+        rodata[..5].copy_from_slice(b"hello");
+        rodata[6..0x13].copy_from_slice("héllo wörld".as_bytes());
+        rodata[0x18..0x20].copy_from_slice(&"wide".encode_utf16().flat_map(|x| x.to_le_bytes()).collect::<Vec<_>>());
+        rodata[0x28..0x38].copy_from_slice(&"word".chars().flat_map(|x| (x as u32).to_be_bytes()).collect::<Vec<_>>());
+        // Merged strings are split at their terminators, those of wide characters are as wide as the entries
+        let mut wide: Vec<u8> = "ok😀!".encode_utf16().flat_map(|x| x.to_le_bytes()).collect();
+        wide.extend([0, 0, b'a', 0, b'b', 0, 0, 0]);
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0,
+            relocatable: false,
+            sections: vec![
+                section(1, ".rodata", 0x2000, 0, rodata),
+                section(2, ".rodata.str2.2", 0x3000, 2, wide),
+                section(3, ".rodata.str1.1", 0x3100, 1, "€uro\0ab\0".as_bytes().to_vec()),
+            ],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+        };
+
+        let found = |address, size, encoding, value: &str| (address, size, encoding, value.to_string());
+        assert_eq!(strings(&image, &Encoding::ALL), vec![
+            found(0x2000, 5, Encoding::Ascii, "hello"),
+            found(0x2006, 13, Encoding::Utf8, "héllo wörld"),
+            found(0x2018, 8, Encoding::Utf16Le, "wide"),
+            found(0x2028, 16, Encoding::Utf32Be, "word"),
+            found(0x3000, 10, Encoding::Utf16Le, "ok😀!"),
+            found(0x3100, 6, Encoding::Utf8, "€uro"),
+        ]);
+        // Without UTF-8, only the ASCII runs of the text are left
+        let ascii = vec![found(0x2000, 5, Encoding::Ascii, "hello"), found(0x2009, 5, Encoding::Ascii, "llo w")];
+        assert_eq!(strings(&image, &[Encoding::Ascii]), ascii);
+        let wide = strings(&image, &[Encoding::Utf16Le, Encoding::Utf32Be]);
+        assert_eq!(wide.iter().map(|x| x.0).collect::<Vec<_>>(), vec![0x2018, 0x2028, 0x3000]);
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(Encoding::from_name("UTF16le"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_name("utf-32be"), Some(Encoding::Utf32Be));
+        assert_eq!(Encoding::Utf8.decode("€".as_bytes()), Some(('€', 3)));
+        // Truncated sequences and lone surrogates are not characters
+        assert_eq!(Encoding::Utf8.decode(&"€".as_bytes()[..2]), None);
+        assert_eq!(Encoding::Utf16Be.decode(&[0xd8, 0x3d, 0xde, 0x00]), Some(('😀', 4)));
+        assert_eq!(Encoding::Utf16Le.decode(&[0x3d, 0xd8, b'a', 0]), None);
+        assert_eq!(Encoding::Utf32Le.decode(&[0, 0xd8, 0, 0]), None);
+        assert_eq!(Encoding::Ascii.decode(&[0xc3, 0xa9]), None);
+    }
+}
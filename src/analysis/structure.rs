@@ -0,0 +1,826 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::disasm::FlowKind;
+
+use super::cfg::{dominator_tree, natural_loops, BlockId, Cfg, DominatorTree, Edge, EdgeKind, Loop};
+
+/// What structuring needs to know of the code in the blocks
+pub trait BlockContents {
+    /// The block does nothing but decide where to go, so it can be the condition of a loop or part of that of an if
+    fn is_condition(&self, block: BlockId) -> bool;
+
+    /// The block only updates variables on its way back to the loop header, like the step of a for loop
+    fn is_step(&self, block: BlockId) -> bool;
+}
+
+/// A condition made of the branches ending blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    // The block branches: it goes to the successor of the True, Return or Indirect edge rather than the False one
+    Branch(BlockId),
+    Not(Box<Condition>),
+    // Evaluated left to right, the right side only when needed
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    pub fn negate(self) -> Condition {
+        match self {
+            Condition::Not(x) => *x,
+            x => Condition::Not(Box::new(x)),
+        }
+    }
+
+    /// The blocks whose branches the condition tests, in order
+    pub fn blocks(&self) -> Vec<BlockId> {
+        match self {
+            Condition::Branch(x) => vec![*x],
+            Condition::Not(x) => x.blocks(),
+            Condition::And(a, b) | Condition::Or(a, b) => a.blocks().into_iter().chain(b.blocks()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopKind {
+    // The condition is tested before each iteration
+    While(Condition),
+    // After each iteration, by the last block of the body
+    DoWhile(Condition),
+    // Like while, with the block running before each test but the first
+    For { condition: Condition, step: BlockId },
+    Endless,
+}
+
+/// A case of a switch: the successor of the switch block it starts at and what runs from there. The last
+/// statement of the body falls through to the next case unless it breaks or leaves otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub target: BlockId,
+    pub body: Node,
+}
+
+/// A structured statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    // The code of a block, without the branch or jump ending it
+    Block(BlockId),
+    Sequence(Vec<Node>),
+    If { condition: Condition, then: Box<Node>, otherwise: Option<Box<Node>> },
+    Loop { header: BlockId, kind: LoopKind, body: Box<Node> },
+    // The block ends with a jump to one of the cases
+    Switch { block: BlockId, cases: Vec<Case> },
+    Break,
+    Continue,
+    // To the exit block
+    Return,
+    Goto(BlockId),
+}
+
+impl Node {
+    fn sequence(nodes: Vec<Node>) -> Node {
+        let mut res = Vec::new();
+        for node in nodes {
+            match node {
+                Node::Sequence(nodes) => res.extend(nodes),
+                node => res.push(node),
+            }
+        }
+        if res.len() == 1 {
+            res.pop().unwrap()
+        } else {
+            Node::Sequence(res)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Node::Sequence(nodes) if nodes.is_empty())
+    }
+
+    // Whether control never goes past the end of the node
+    fn jumps(&self) -> bool {
+        match self {
+            Node::Break | Node::Continue | Node::Return | Node::Goto(_) => true,
+            Node::Sequence(nodes) => nodes.last().is_some_and(|x| x.jumps()),
+            Node::If { then, otherwise: Some(otherwise), .. } => then.jumps() && otherwise.jumps(),
+            _ => false,
+        }
+    }
+
+    // The block control enters the node at
+    fn first_block(&self) -> Option<BlockId> {
+        match self {
+            Node::Block(block) | Node::Goto(block) | Node::Loop { header: block, .. } => Some(*block),
+            Node::Sequence(nodes) => nodes.iter().find(|x| !x.is_empty()).and_then(|x| x.first_block()),
+            _ => None,
+        }
+    }
+
+    // Whether one of the blocks of the node is gone to
+    fn has_label(&self, labels: &BTreeSet<BlockId>) -> bool {
+        match self {
+            Node::Block(block) => labels.contains(block),
+            Node::Sequence(nodes) => nodes.iter().any(|x| x.has_label(labels)),
+            Node::If { then, otherwise, .. } => then.has_label(labels) || otherwise.as_ref().is_some_and(|x| x.has_label(labels)),
+            Node::Loop { header, body, .. } => labels.contains(header) || body.has_label(labels),
+            Node::Switch { cases, .. } => cases.iter().any(|x| x.body.has_label(labels)),
+            Node::Break | Node::Continue | Node::Return | Node::Goto(_) => false,
+        }
+    }
+
+    // The blocks gone to
+    fn gotos(&self, targets: &mut BTreeSet<BlockId>) {
+        match self {
+            Node::Goto(block) => {
+                targets.insert(*block);
+            }
+            Node::Sequence(nodes) => nodes.iter().for_each(|x| x.gotos(targets)),
+            Node::If { then, otherwise, .. } => {
+                then.gotos(targets);
+                if let Some(otherwise) = otherwise {
+                    otherwise.gotos(targets);
+                }
+            }
+            Node::Loop { body, .. } => body.gotos(targets),
+            Node::Switch { cases, .. } => cases.iter().for_each(|x| x.body.gotos(targets)),
+            Node::Block(_) | Node::Break | Node::Continue | Node::Return => {}
+        }
+    }
+
+    // Else branches after branches that jump out are taken out of the if, and the other way round, and loop bodies
+    // don't end with continue. What follows a jump is left out unless it is gone to, and gotos to where control goes
+    // anyway are dropped, or turned into break and continue. `next` is where control goes after the node, `exits`
+    // where continue and break go from it.
+    fn simplify(self, labels: &BTreeSet<BlockId>, next: Option<BlockId>, exits: (Option<BlockId>, Option<BlockId>)) -> Node {
+        match self {
+            Node::Sequence(nodes) => {
+                let afters: Vec<Option<BlockId>> =
+                    (0..nodes.len()).map(|i| nodes[i + 1..].iter().find(|x| !x.is_empty()).map_or(next, |x| x.first_block())).collect();
+                let mut res: Vec<Node> = Vec::new();
+                for (node, after) in nodes.into_iter().zip(afters) {
+                    if res.last().is_some_and(|x| x.jumps()) && !node.has_label(labels) {
+                        continue;
+                    }
+                    res.push(node.simplify(labels, after, exits));
+                }
+                Node::sequence(res)
+            }
+            Node::If { condition, then, otherwise } => {
+                let then = Box::new(then.simplify(labels, next, exits));
+                match otherwise.map(|x| x.simplify(labels, next, exits)) {
+                    Some(otherwise) if then.jumps() => {
+                        Node::sequence(vec![Node::If { condition, then, otherwise: None }, otherwise])
+                    }
+                    Some(otherwise) if otherwise.jumps() => {
+                        let then = *then;
+                        Node::sequence(vec![Node::If { condition: condition.negate(), then: Box::new(otherwise), otherwise: None }, then])
+                    }
+                    Some(otherwise) if then.is_empty() => {
+                        Node::If { condition: condition.negate(), then: Box::new(otherwise), otherwise: None }
+                    }
+                    otherwise if then.is_empty() && otherwise.as_ref().is_none_or(|x| x.is_empty()) => Node::Sequence(Vec::new()),
+                    otherwise => Node::If { condition, then, otherwise: otherwise.map(Box::new) },
+                }
+            }
+            Node::Loop { header, kind, body } => {
+                // The end of the body goes back to the header, unless there's a step or a test at the end to run first
+                let again = match kind {
+                    LoopKind::While(_) | LoopKind::Endless => Some(header),
+                    _ => None,
+                };
+                let body = body.simplify(labels, again, (again, next));
+                Node::Loop { header, kind, body: Box::new(body.without_continue()) }
+            }
+            Node::Switch { block, cases } => {
+                let starts: Vec<BlockId> = cases.iter().map(|x| x.target).collect();
+                let cases = cases
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, x)| {
+                        let next = starts.get(i + 1).copied().or(next);
+                        Case { target: x.target, body: x.body.simplify(labels, next, (exits.0, None)) }
+                    })
+                    .collect();
+                Node::Switch { block, cases }
+            }
+            Node::Goto(block) if Some(block) == next => Node::Sequence(Vec::new()),
+            Node::Goto(block) if Some(block) == exits.0 => Node::Continue,
+            Node::Goto(block) if Some(block) == exits.1 => Node::Break,
+            node => node,
+        }
+    }
+
+    // The node without the continue it ends with, which the end of a loop body does anyway
+    fn without_continue(self) -> Node {
+        match self {
+            Node::Continue => Node::Sequence(Vec::new()),
+            Node::Sequence(mut nodes) if nodes.last() == Some(&Node::Continue) => {
+                nodes.pop();
+                Node::sequence(nodes)
+            }
+            node => node,
+        }
+    }
+}
+
+/// The structured statements of a function, and the blocks gotos go to
+#[derive(Debug, Clone)]
+pub struct Structure {
+    pub root: Node,
+    pub labels: BTreeSet<BlockId>,
+}
+
+/// Recovers ifs, loops and switches from the control flow between the blocks. Conditions of several blocks are
+/// merged into && and ||, gotos are left where the flow isn't structured, as in irreducible regions. Exception
+/// edges aren't followed, so landing pads are left out.
+pub fn structure(blocks: usize, entry: BlockId, exit: BlockId, edges: &[Edge], contents: &dyn BlockContents) -> Structure {
+    let edges: Vec<Edge> = edges.iter().copied().filter(|x| x.kind != EdgeKind::Exception).collect();
+    let mut successors: Vec<Vec<(BlockId, EdgeKind)>> = vec![Vec::new(); blocks];
+    let mut predecessors: Vec<Vec<BlockId>> = vec![Vec::new(); blocks];
+    for edge in edges.iter() {
+        if !successors[edge.from].iter().any(|x| x.0 == edge.to) {
+            successors[edge.from].push((edge.to, edge.kind));
+            predecessors[edge.to].push(edge.from);
+        }
+    }
+    let successor_ids = |x: BlockId| successors[x].iter().map(|x| x.0).collect::<Vec<BlockId>>();
+    let dominators = dominator_tree(blocks, entry, successor_ids, |x| predecessors[x].clone());
+    let loops = natural_loops(&dominators, &edges, |x| predecessors[x].clone(), successor_ids);
+    let post_dominators = dominator_tree(blocks, exit, |x| predecessors[x].clone(), successor_ids);
+
+    let mut structurer = Structurer {
+        exit,
+        contents,
+        loops: loops.into_iter().map(|x| (x.header, x)).collect(),
+        successors,
+        predecessors,
+        dominators,
+        post_dominators,
+        emitted: vec![false; blocks],
+        looped: BTreeSet::new(),
+        reserved: BTreeSet::new(),
+        stops: Vec::new(),
+        labels: BTreeSet::new(),
+    };
+    let mut root = structurer.sequence(entry, Context::default());
+    let mut labels = structurer.labels;
+    // Gotos left out or dropped may leave blocks no longer gone to, and what follows jumps before them unreachable
+    loop {
+        root = root.simplify(&labels, None, (None, None));
+        let mut targets = BTreeSet::new();
+        root.gotos(&mut targets);
+        if targets == labels {
+            break;
+        }
+        labels = targets;
+    }
+    Structure { root, labels }
+}
+
+impl Cfg {
+    /// The structured statements of the function, taking every block as a possible condition and those without calls
+    /// as possible steps
+    pub fn structure(&self) -> Structure {
+        struct Instructions<'a>(&'a Cfg);
+        impl BlockContents for Instructions<'_> {
+            fn is_condition(&self, _: BlockId) -> bool {
+                true
+            }
+
+            fn is_step(&self, block: BlockId) -> bool {
+                let instructions = &self.0.blocks[block].instructions;
+                instructions.iter().all(|x| matches!(x.flow, FlowKind::Sequential | FlowKind::Jump))
+            }
+        }
+        structure(self.blocks.len(), self.entry, self.exit, &self.edges, &Instructions(self))
+    }
+}
+
+// Where the statements being structured are, and what the blocks ending them lead to
+#[derive(Debug, Clone, Copy, Default)]
+struct Context {
+    // Where the enclosing statement continues, which ends the sequence
+    follow: Option<BlockId>,
+    // Where break and continue go, in a loop or switch
+    break_target: Option<BlockId>,
+    continue_target: Option<BlockId>,
+    // The header of the innermost loop
+    header: Option<BlockId>,
+    // The start of the next case of a switch, which the sequence falls through to
+    next_case: Option<BlockId>,
+}
+
+struct Structurer<'a> {
+    exit: BlockId,
+    contents: &'a dyn BlockContents,
+    loops: BTreeMap<BlockId, Loop>,
+    successors: Vec<Vec<(BlockId, EdgeKind)>>,
+    predecessors: Vec<Vec<BlockId>>,
+    dominators: DominatorTree,
+    post_dominators: DominatorTree,
+    emitted: Vec<bool>,
+    // Headers of the loops already structured or being so
+    looped: BTreeSet<BlockId>,
+    // Starts of cases not structured yet
+    reserved: BTreeSet<BlockId>,
+    // Where enclosing statements continue, which nested ones can only reach with a goto
+    stops: Vec<BlockId>,
+    labels: BTreeSet<BlockId>,
+}
+
+impl<'a> Structurer<'a> {
+    // The statements from `start` on, until something ends the sequence
+    fn sequence(&mut self, start: BlockId, context: Context) -> Node {
+        let mut nodes = Vec::new();
+        let mut current = Some(start);
+        while let Some(block) = current.take() {
+            // The body of an endless loop starts at its header, which continuing goes back to
+            let entering = Some(block) == context.header && !self.emitted[block];
+            if !entering {
+                if let Some(end) = self.end(block, context) {
+                    nodes.extend(end);
+                    break;
+                }
+            }
+            if self.loops.contains_key(&block) && self.looped.insert(block) {
+                let (node, follow) = self.structure_loop(block, context);
+                nodes.push(node);
+                current = follow;
+                continue;
+            }
+
+            self.emitted[block] = true;
+            nodes.push(Node::Block(block));
+            match self.successors[block].len() {
+                0 => {}
+                1 => current = Some(self.successors[block][0].0),
+                2 => {
+                    let (node, follow) = self.structure_if(block, context);
+                    nodes.extend(node);
+                    current = follow;
+                }
+                _ => {
+                    let (node, follow) = self.structure_switch(block, context);
+                    nodes.push(node);
+                    current = follow;
+                }
+            }
+        }
+        Node::sequence(nodes)
+    }
+
+    // Whether reaching `block` ends the sequence, and with which statement
+    fn end(&mut self, block: BlockId, context: Context) -> Option<Option<Node>> {
+        if Some(block) == context.follow || Some(block) == context.next_case {
+            Some(None)
+        } else if Some(block) == context.continue_target {
+            Some(Some(Node::Continue))
+        } else if Some(block) == context.break_target {
+            Some(Some(Node::Break))
+        } else if block == self.exit {
+            Some(Some(Node::Return))
+        } else if self.emitted[block] && self.leaves(block) {
+            // Blocks doing nothing but leave the function are repeated rather than gone to
+            Some(Some(Node::sequence(vec![Node::Block(block), Node::Return])))
+        } else if self.emitted[block] || self.reserved.contains(&block) || self.stops.contains(&block) {
+            self.labels.insert(block);
+            Some(Some(Node::Goto(block)))
+        } else {
+            None
+        }
+    }
+
+    fn leaves(&self, block: BlockId) -> bool {
+        self.successors[block].iter().map(|x| x.0).eq([self.exit]) && self.contents.is_condition(block)
+    }
+
+    // Where the statement starting with `block` continues: the block all paths from it go through first, within
+    // the enclosing statement. When they only meet leaving the function, a target the other targets lead to.
+    fn follow(&self, block: BlockId, targets: &[BlockId], context: Context) -> Option<BlockId> {
+        let follow = self.post_dominators.idom[block].filter(|x| match context.header {
+            Some(header) => *x == self.exit || self.loops[&header].body.contains(x),
+            None => true,
+        });
+        let follow = match context.follow {
+            Some(outer) if follow.is_some_and(|x| x != outer && self.post_dominators.dominates(x, outer)) => Some(outer),
+            _ => follow,
+        };
+        if follow.is_some_and(|x| x != self.exit) {
+            return follow;
+        }
+        let merge = targets.iter().copied().find(|target| {
+            *target != self.exit
+                && self.dominators.dominates(block, *target)
+                && context.header.is_none_or(|x| self.loops[&x].body.contains(target))
+                && targets.iter().any(|x| x != target && self.reaches(*x, *target, &[Some(block), context.header]))
+        });
+        merge.or(follow)
+    }
+
+    // Whether there is a path between the blocks not going through those to `avoid`
+    fn reaches(&self, from: BlockId, to: BlockId, avoid: &[Option<BlockId>]) -> bool {
+        let mut visited = BTreeSet::new();
+        let mut work = vec![from];
+        while let Some(block) = work.pop() {
+            if block == to {
+                return true;
+            }
+            if avoid.contains(&Some(block)) || !visited.insert(block) {
+                continue;
+            }
+            work.extend(self.successors[block].iter().map(|x| x.0));
+        }
+        false
+    }
+
+    // Runs `f` with the follow of the enclosing statement only reachable by goto, unless it's `follow`
+    fn nested<T>(&mut self, context: Context, follow: Option<BlockId>, f: impl FnOnce(&mut Self) -> T) -> T {
+        let pushed = match context.follow {
+            Some(outer) if Some(outer) != follow => {
+                self.stops.push(outer);
+                true
+            }
+            _ => false,
+        };
+        let res = f(self);
+        if pushed {
+            self.stops.pop();
+        }
+        res
+    }
+
+    // Where a block with two successors goes when it branches and when it doesn't
+    fn targets(&self, block: BlockId) -> (BlockId, BlockId) {
+        let successors = &self.successors[block];
+        match successors[0].1 {
+            EdgeKind::False => (successors[1].0, successors[0].0),
+            _ => (successors[0].0, successors[1].0),
+        }
+    }
+
+    // The condition of the branch ending `block`, merged with those of the blocks it leads to which only test
+    // another condition on the way to the same places. Gives the condition and where it goes when it holds and
+    // when it doesn't.
+    fn condition(&mut self, block: BlockId, context: Context) -> (Condition, BlockId, BlockId) {
+        let mut condition = Condition::Branch(block);
+        let (mut then, mut otherwise) = self.targets(block);
+        let mut merged = vec![block];
+        loop {
+            let can_merge = |x: BlockId| {
+                x != self.exit
+                    && self.successors[x].len() == 2
+                    && self.predecessors[x].len() == 1
+                    && merged.contains(&self.predecessors[x][0])
+                    && self.contents.is_condition(x)
+                    && !self.emitted[x]
+                    && !self.loops.contains_key(&x)
+                    && !self.reserved.contains(&x)
+                    && !self.stops.contains(&x)
+                    && ![context.follow, context.break_target, context.continue_target, context.next_case]
+                        .contains(&Some(x))
+            };
+            let (merge_then, merge_otherwise) = (can_merge(then), can_merge(otherwise));
+            if merge_then {
+                let next = then;
+                let (a, b) = self.targets(next);
+                if b == otherwise || a == otherwise {
+                    let test = if b == otherwise { Condition::Branch(next) } else { Condition::Branch(next).negate() };
+                    condition = Condition::And(Box::new(condition), Box::new(test));
+                    then = if b == otherwise { a } else { b };
+                    merged.push(next);
+                    self.emitted[next] = true;
+                    continue;
+                }
+            }
+            if merge_otherwise {
+                let next = otherwise;
+                let (a, b) = self.targets(next);
+                if a == then || b == then {
+                    let test = if a == then { Condition::Branch(next) } else { Condition::Branch(next).negate() };
+                    condition = Condition::Or(Box::new(condition), Box::new(test));
+                    otherwise = if a == then { b } else { a };
+                    merged.push(next);
+                    self.emitted[next] = true;
+                    continue;
+                }
+            }
+            break;
+        }
+        (condition, then, otherwise)
+    }
+
+    fn structure_if(&mut self, block: BlockId, context: Context) -> (Option<Node>, Option<BlockId>) {
+        let (condition, then_target, else_target) = self.condition(block, context);
+        let follow = self.follow(block, &[then_target, else_target], context);
+        let inner = Context { follow, next_case: None, ..context };
+        let (then, otherwise) = self.nested(context, follow, |x| {
+            let then = x.sequence(then_target, inner);
+            let otherwise = x.sequence(else_target, inner);
+            (then, otherwise)
+        });
+        // Where both branches jump, the if is only followed by what they go to
+        let follow = follow.filter(|x| !(then.jumps() && otherwise.jumps()) || self.labels.contains(x));
+        let node = match (then.is_empty(), otherwise.is_empty()) {
+            (true, true) => None,
+            (false, true) => Some(Node::If { condition, then: Box::new(then), otherwise: None }),
+            (true, false) => Some(Node::If { condition: condition.negate(), then: Box::new(otherwise), otherwise: None }),
+            (false, false) => Some(Node::If { condition, then: Box::new(then), otherwise: Some(Box::new(otherwise)) }),
+        };
+        (node, follow)
+    }
+
+    fn structure_loop(&mut self, header: BlockId, context: Context) -> (Node, Option<BlockId>) {
+        let body = self.loops[&header].body.clone();
+        let latches = self.loops[&header].latches.clone();
+        let leaves = |x: BlockId| -> Option<BlockId> {
+            let successors = &self.successors[x];
+            match successors.len() {
+                2 if body.contains(&successors[0].0) != body.contains(&successors[1].0) => {
+                    successors.iter().map(|x| x.0).find(|x| !body.contains(x))
+                }
+                _ => None,
+            }
+        };
+
+        // The loop is left where the header or the latch tests whether to go on, or else by its first exit
+        let header_exit = leaves(header).filter(|_| self.contents.is_condition(header));
+        let latch = match latches.as_slice() {
+            [latch] => Some(*latch),
+            _ => None,
+        };
+        let latch_exit = latch.and_then(|x| leaves(x).filter(|_| self.successors[x].iter().any(|x| x.0 == header)));
+        let mut exits: Vec<BlockId> = self.loops[&header].exits.iter().copied().collect();
+        exits.sort_by_key(|x| (*x == self.exit, *x));
+        let follow = header_exit.or(latch_exit).or_else(|| exits.first().copied());
+
+        let inner = Context {
+            follow: None,
+            break_target: follow,
+            continue_target: Some(header),
+            header: Some(header),
+            next_case: None,
+        };
+        let (kind, body) = self.nested(context, None, |x| {
+            let enclosing = [context.break_target, context.continue_target];
+            x.stops.extend(enclosing.iter().flatten());
+            let res = if let Some(follow) = header_exit {
+                x.emitted[header] = true;
+                let (taken, _) = x.targets(header);
+                let start = if taken == follow { x.targets(header).1 } else { taken };
+                let condition = if taken == follow { Condition::Branch(header).negate() } else { Condition::Branch(header) };
+                match latch.filter(|l| *l != header && x.successors[*l].len() == 1) {
+                    Some(step) if x.contents.is_step(step) => {
+                        x.emitted[step] = true;
+                        let body = x.sequence(start, Context { continue_target: Some(step), ..inner });
+                        (LoopKind::For { condition, step }, body)
+                    }
+                    // The body ends with the latch, which only the end of the body can go to
+                    Some(latch) => {
+                        let body = x.sequence(start, Context { follow: Some(latch), ..inner });
+                        x.emitted[latch] = true;
+                        (LoopKind::While(condition), Node::sequence(vec![body, Node::Block(latch)]))
+                    }
+                    None => (LoopKind::While(condition), x.sequence(start, inner)),
+                }
+            } else if let (Some(latch), Some(_)) = (latch, latch_exit) {
+                let (taken, _) = x.targets(latch);
+                let condition = if taken == header { Condition::Branch(latch) } else { Condition::Branch(latch).negate() };
+                if latch == header {
+                    x.emitted[header] = true;
+                    (LoopKind::DoWhile(condition), Node::Block(header))
+                } else if x.contents.is_condition(latch) {
+                    // Continuing goes to the test
+                    x.emitted[latch] = true;
+                    (LoopKind::DoWhile(condition), x.sequence(header, Context { continue_target: Some(latch), ..inner }))
+                } else {
+                    let body = x.sequence(header, Context { follow: Some(latch), continue_target: None, ..inner });
+                    x.emitted[latch] = true;
+                    (LoopKind::DoWhile(condition), Node::sequence(vec![body, Node::Block(latch)]))
+                }
+            } else {
+                (LoopKind::Endless, x.sequence(header, inner))
+            };
+            for _ in enclosing.iter().flatten() {
+                x.stops.pop();
+            }
+            res
+        });
+        (Node::Loop { header, kind, body: Box::new(body) }, follow)
+    }
+
+    fn structure_switch(&mut self, block: BlockId, context: Context) -> (Node, Option<BlockId>) {
+        let follow = self.follow(block, &[], context);
+        let mut targets: Vec<BlockId> = self.successors[block].iter().map(|x| x.0).collect();
+        targets.sort_unstable();
+        let starts: Vec<BlockId> = targets.iter().copied().filter(|x| Some(*x) != follow && !self.emitted[*x]).collect();
+        self.reserved.extend(starts.iter().copied());
+
+        let mut cases = Vec::new();
+        for (i, target) in targets.iter().copied().enumerate() {
+            self.reserved.remove(&target);
+            let next_case = targets.get(i + 1).copied().filter(|x| Some(*x) != follow);
+            let inner = Context { follow: None, break_target: follow, next_case, ..context };
+            let body = self.nested(context, None, |x| x.sequence(target, inner));
+            cases.push(Case { target, body });
+        }
+        (Node::Switch { block, cases }, follow)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Condition::Branch(block) => write!(f, "b{}", block),
+            Condition::Not(x) => match **x {
+                Condition::Branch(block) => write!(f, "!b{}", block),
+                ref x => write!(f, "!({})", x),
+            },
+            Condition::And(a, b) => write!(f, "({} && {})", a, b),
+            Condition::Or(a, b) => write!(f, "({} || {})", a, b),
+        }
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_node(f, &self.root, &self.labels, 0)
+    }
+}
+
+// An outline of the statements, with the blocks by id
+fn write_node(f: &mut fmt::Formatter, node: &Node, labels: &BTreeSet<BlockId>, depth: usize) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    let label = |f: &mut fmt::Formatter, block: BlockId| match labels.contains(&block) {
+        true => writeln!(f, "{}L{}:", "    ".repeat(depth.saturating_sub(1)), block),
+        false => Ok(()),
+    };
+    match node {
+        Node::Block(block) => {
+            label(f, *block)?;
+            writeln!(f, "{}block {}", indent, block)
+        }
+        Node::Sequence(nodes) => nodes.iter().try_for_each(|x| write_node(f, x, labels, depth)),
+        Node::If { condition, then, otherwise } => {
+            writeln!(f, "{}if {} {{", indent, condition)?;
+            write_node(f, then, labels, depth + 1)?;
+            if let Some(otherwise) = otherwise {
+                writeln!(f, "{}}} else {{", indent)?;
+                write_node(f, otherwise, labels, depth + 1)?;
+            }
+            writeln!(f, "{}}}", indent)
+        }
+        Node::Loop { header, kind, body } => {
+            // The other loops start with the block of their header
+            if let LoopKind::While(_) | LoopKind::For { .. } = kind {
+                label(f, *header)?;
+            }
+            match kind {
+                LoopKind::While(condition) => writeln!(f, "{}while {} {{", indent, condition)?,
+                LoopKind::For { condition, step } => writeln!(f, "{}for (; {}; block {}) {{", indent, condition, step)?,
+                LoopKind::DoWhile(_) => writeln!(f, "{}do {{", indent)?,
+                LoopKind::Endless => writeln!(f, "{}loop {{", indent)?,
+            }
+            write_node(f, body, labels, depth + 1)?;
+            match kind {
+                LoopKind::DoWhile(condition) => writeln!(f, "{}}} while {}", indent, condition),
+                _ => writeln!(f, "{}}}", indent),
+            }
+        }
+        Node::Switch { block, cases } => {
+            writeln!(f, "{}switch b{} {{", indent, block)?;
+            for case in cases.iter() {
+                writeln!(f, "{}case {}:", indent, case.target)?;
+                write_node(f, &case.body, labels, depth + 1)?;
+            }
+            writeln!(f, "{}}}", indent)
+        }
+        Node::Break => writeln!(f, "{}break", indent),
+        Node::Continue => writeln!(f, "{}continue", indent),
+        Node::Return => writeln!(f, "{}return", indent),
+        Node::Goto(block) => writeln!(f, "{}goto L{}", indent, block),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Blocks with statements besides their jumps, no block is a step
+    struct Statements<'a>(&'a [BlockId]);
+    impl BlockContents for Statements<'_> {
+        fn is_condition(&self, block: BlockId) -> bool {
+            !self.0.contains(&block)
+        }
+
+        fn is_step(&self, _: BlockId) -> bool {
+            false
+        }
+    }
+
+    // The outline of the blocks, the last of which is the exit
+    fn outline(blocks: usize, statements: &[BlockId], edges: &[(BlockId, BlockId, EdgeKind)]) -> String {
+        let edges: Vec<Edge> = edges.iter().map(|&(from, to, kind)| Edge { from, to, kind }).collect();
+        structure(blocks, 0, blocks - 1, &edges, &Statements(statements)).to_string()
+    }
+
+    fn simplified(node: Node, labels: &[BlockId]) -> Node {
+        node.simplify(&labels.iter().copied().collect(), None, (None, None))
+    }
+
+    #[test]
+    fn nothing_after_jumps() {
+        use EdgeKind::*;
+        // Both branches of the if in the loop go around again, the loop is only left by the break
+        let edges = [(0, 2, True), (0, 3, False), (1, 0, True), (1, 5, False), (2, 4, Unconditional), (3, 1, True), (3, 0, False)];
+        let text = outline(6, &[2, 3, 4], &[&edges[..], &[(4, 2, True), (4, 0, False)]].concat());
+        let expected = "loop {\n    block 0\n    if b0 {\n        do {\n            block 2\n            block 4\n        \
+                        } while b4\n        continue\n    }\n    block 3\n    if (b3 && !b1) {\n        break\n    }\n}\nreturn\n";
+        assert_eq!(text, expected);
+
+        // Block 2 goes on to block 4, which comes next
+        let edges = [(0, 3, True), (0, 2, False), (1, 6, True), (1, 4, False), (2, 4, Unconditional), (3, 3, Unconditional)];
+        let text = outline(7, &[0, 2, 4, 5], &[&edges[..], &[(4, 0, True), (4, 3, False), (5, 0, True), (5, 6, False)]].concat());
+        let expected =
+            "do {\n    block 0\n    if b0 {\n        break\n    }\n    block 2\n    block 4\n} while b4\nloop {\n    block 3\n}\n";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn loops() {
+        use EdgeKind::*;
+        // while (b0) { block 1 }
+        let text = outline(3, &[1], &[(0, 1, True), (0, 2, False), (1, 0, Unconditional)]);
+        assert_eq!(text, "while b0 {\n    block 1\n}\nreturn\n");
+        // do { block 0; if (!b0) continue; block 1; return } while (b2), leaving through block 3
+        let text = outline(5, &[0, 1, 3], &[(0, 1, True), (0, 2, False), (1, 4, Return), (2, 0, True), (2, 3, False), (3, 4, Return)]);
+        let expected = "do {\n    block 0\n    if !b0 {\n        continue\n    }\n    block 1\n    return\n} while b2\nblock 3\nreturn\n";
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn unreachable_after_jumps() {
+        let node = Node::Sequence(vec![Node::Block(0), Node::Goto(3), Node::Return, Node::Goto(2)]);
+        assert_eq!(simplified(node, &[2, 3]), Node::Sequence(vec![Node::Block(0), Node::Goto(3)]));
+        // What is gone to stays
+        let node = Node::Sequence(vec![Node::Goto(3), Node::Block(2), Node::Return]);
+        assert_eq!(simplified(node.clone(), &[2, 3]), node);
+
+        // Both branches of the if jump, the else branch is taken out of it
+        let then = Box::new(Node::Sequence(vec![Node::Block(1), Node::Return]));
+        let node = Node::Sequence(vec![
+            Node::If { condition: Condition::Branch(0), then: then.clone(), otherwise: Some(Box::new(Node::Goto(4))) },
+            Node::Return,
+        ]);
+        let expected = Node::Sequence(vec![Node::If { condition: Condition::Branch(0), then, otherwise: None }, Node::Goto(4)]);
+        assert_eq!(simplified(node, &[4]), expected);
+    }
+
+    #[test]
+    fn gotos_falling_through() {
+        // To the block after the goto, or after the if it ends a branch of
+        let node = Node::Sequence(vec![Node::Block(0), Node::Goto(1), Node::Block(1)]);
+        assert_eq!(simplified(node, &[1]), Node::Sequence(vec![Node::Block(0), Node::Block(1)]));
+        let then = Box::new(Node::Sequence(vec![Node::Block(1), Node::Goto(2)]));
+        let node = Node::Sequence(vec![Node::If { condition: Condition::Branch(0), then, otherwise: None }, Node::Block(2)]);
+        let then = Box::new(Node::Block(1));
+        assert_eq!(
+            simplified(node, &[2]),
+            Node::Sequence(vec![Node::If { condition: Condition::Branch(0), then, otherwise: None }, Node::Block(2)])
+        );
+    }
+
+    #[test]
+    fn gotos_leaving_loops() {
+        // Back to the header of a while loop and to where the loop is left
+        let body = Node::Sequence(vec![
+            Node::Block(1),
+            Node::If { condition: Condition::Branch(1), then: Box::new(Node::Goto(3)), otherwise: None },
+            Node::If { condition: Condition::Branch(2), then: Box::new(Node::Goto(0)), otherwise: None },
+            Node::Block(2),
+            Node::Goto(0),
+        ]);
+        let node = Node::Sequence(vec![
+            Node::Loop { header: 0, kind: LoopKind::While(Condition::Branch(0)), body: Box::new(body) },
+            Node::Block(3),
+        ]);
+        let body = Node::Sequence(vec![
+            Node::Block(1),
+            Node::If { condition: Condition::Branch(1), then: Box::new(Node::Break), otherwise: None },
+            Node::If { condition: Condition::Branch(2), then: Box::new(Node::Continue), otherwise: None },
+            Node::Block(2),
+        ]);
+        let expected = Node::Sequence(vec![
+            Node::Loop { header: 0, kind: LoopKind::While(Condition::Branch(0)), body: Box::new(body) },
+            Node::Block(3),
+        ]);
+        assert_eq!(simplified(node, &[0, 3]), expected);
+
+        // A for loop steps before going back to the header, and a switch takes the break
+        let body = Node::Switch { block: 1, cases: vec![Case { target: 2, body: Node::Goto(0) }, Case { target: 3, body: Node::Goto(4) }] };
+        let kind = LoopKind::For { condition: Condition::Branch(0), step: 5 };
+        let node = Node::Sequence(vec![Node::Loop { header: 0, kind, body: Box::new(body) }, Node::Block(4)]);
+        assert_eq!(simplified(node.clone(), &[0, 4]), node);
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use crate::dwarf::{self, Bases, EhFrameHeader, FrameKind, FrameTable, Reader, UnwindRow, Unwinder};
+use crate::image::{Image, SegmentKind};
+
+use super::exceptions::frame_table;
+
+/// .eh_frame_hdr, from the PT_GNU_EH_FRAME segment or the section of that name
+pub fn eh_frame_header(image: &Image) -> Option<EhFrameHeader> {
+    let (address, data) = match image.segments.iter().find(|x| x.kind == SegmentKind::EhFrameHeader) {
+        Some(segment) => (segment.address, &segment.data[..]),
+        None => {
+            let section = image.section_by_name(".eh_frame_hdr")?;
+            (section.address, &section.data[..])
+        }
+    };
+    let reader = Reader::new(data, image.target.endianness, image.target.bits / 8);
+    EhFrameHeader::parse(&reader, address).ok()
+}
+
+/// The entries of .debug_frame, which describes the code to debuggers and can be there without .eh_frame
+pub fn debug_frame_table(image: &Image) -> FrameTable {
+    let section = match image.section_by_name(".debug_frame") {
+        Some(x) => x,
+        None => return FrameTable::default(),
+    };
+    let reader = Reader::new(&section.data, image.target.endianness, image.target.bits / 8);
+    let bases = Bases { section: section.address, text: None, data: None, function: None };
+    FrameTable::parse(&reader, FrameKind::DebugFrame, &bases).unwrap_or_default()
+}
+
+/// The rows of the unwind tables of all the code, by start address. .eh_frame wins over .debug_frame where both
+/// describe the same code.
+pub fn unwind_rows(image: &Image) -> BTreeMap<u64, UnwindRow> {
+    let mut res = BTreeMap::new();
+    for table in [debug_frame_table(image), frame_table(image)].iter() {
+        for fde in table.fdes.iter().filter(|x| x.length > 0) {
+            let covered: Vec<u64> = res.range(fde.start..fde.end()).map(|x| *x.0).collect();
+            for start in covered {
+                res.remove(&start);
+            }
+            for row in table.rows(fde).unwrap_or_default() {
+                res.insert(row.start, row);
+            }
+        }
+    }
+    res
+}
+
+/// The row covering an address, from the result of [unwind_rows]
+pub fn row_at(rows: &BTreeMap<u64, UnwindRow>, address: u64) -> Option<&UnwindRow> {
+    rows.range(..=address).next_back().map(|x| x.1).filter(|x| x.contains(address))
+}
+
+/// How far the stack pointer is below the CFA at an address: the bytes the function pushed and allocated, plus
+/// what the call pushed. None where the rules give the CFA relative to another register.
+pub fn stack_height(image: &Image, rows: &BTreeMap<u64, UnwindRow>, address: u64) -> Option<i64> {
+    row_at(rows, address)?.stack_height(dwarf::stack_pointer(image.target.instruction_set)?)
+}
+
+/// An unwinder over .eh_frame and .debug_frame, given the tables read by [frame_table] and [debug_frame_table]
+pub fn unwinder<'a>(image: &Image, eh_frame: &'a FrameTable, debug_frame: &'a FrameTable) -> Option<Unwinder<'a>> {
+    let stack_pointer = dwarf::stack_pointer(image.target.instruction_set)?;
+    Some(Unwinder::new(vec![eh_frame, debug_frame], stack_pointer, image.target.endianness, image.target.bits / 8))
+}
+
+/// A value of the file's memory image, for unwinding with what the binary itself holds
+pub fn read_memory(image: &Image, address: u64, size: u8) -> Option<u64> {
+    let mut reader = Reader::new(image.data_at(address)?, image.target.endianness, image.target.bits / 8);
+    reader.sized(size).ok()
+}
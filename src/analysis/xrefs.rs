@@ -0,0 +1,410 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disasm::{self, Decoder, FlowKind, Instruction, Operand};
+use crate::elf::relocation_type_name;
+use crate::endian::Endianness;
+use crate::image::Image;
+use crate::instruction_set::InstructionSet;
+
+use super::disassembly::{linear_sweep, Line};
+use super::exceptions::{call_sites, frame_table};
+use super::functions::{function_instructions, Function};
+use super::plt::PLT_SECTIONS;
+
+/// How one address refers to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum XrefKind {
+    Call,
+    // Conditional or not
+    Jump,
+    // Memory accesses at a known address: pc relative, absolute or through an adrp pair
+    Read,
+    Write,
+    // The address is computed without being accessed, by lea, adr or adrp + add
+    Address,
+    // A pointer stored in data
+    Pointer,
+    // The target of a relocation applied at the source
+    Relocation,
+}
+
+impl XrefKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            XrefKind::Call => "call",
+            XrefKind::Jump => "jump",
+            XrefKind::Read => "read",
+            XrefKind::Write => "write",
+            XrefKind::Address => "address",
+            XrefKind::Pointer => "pointer",
+            XrefKind::Relocation => "relocation",
+        }
+    }
+}
+
+/// A reference from an instruction or a data word to an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Xref {
+    // Address of the instruction, or of the data word
+    pub from: u64,
+    pub to: u64,
+    pub kind: XrefKind,
+}
+
+/// The cross references of a binary, indexed by source and by target
+#[derive(Debug, Clone, Default)]
+pub struct Xrefs {
+    from: BTreeMap<u64, Vec<Xref>>,
+    to: BTreeMap<u64, Vec<Xref>>,
+}
+
+impl Xrefs {
+    /// The references made by the instructions of `functions` and of the PLT, by pointers in the data sections
+    /// and by relocations
+    pub fn build(image: &Image, decoder: &dyn Decoder, functions: &[Function]) -> Xrefs {
+        let mut xrefs = Xrefs::default();
+        let instructions = instructions(image, decoder, functions);
+        for instruction in instructions.values() {
+            for xref in instruction_xrefs(image, instruction) {
+                xrefs.insert(xref);
+            }
+        }
+        // adrp only gives the page, the address is complete once the instruction adding the offset is reached. Pairs
+        // are looked for in runs of contiguous instructions.
+        if image.target.instruction_set == InstructionSet::AArch64 {
+            let mut pairs = Vec::new();
+            let mut run: Vec<Instruction> = Vec::new();
+            for instruction in instructions.values() {
+                if run.last().is_some_and(|x| x.next_address() != instruction.address) {
+                    pairs.extend(disasm::aarch64::address_pairs(&run));
+                    run.clear();
+                }
+                run.push(instruction.clone());
+            }
+            pairs.extend(disasm::aarch64::address_pairs(&run));
+            for pair in pairs {
+                let kind = match instructions.get(&pair.address) {
+                    Some(x) if x.mnemonic == "add" => XrefKind::Address,
+                    Some(x) if is_store(image, x) => XrefKind::Write,
+                    _ => XrefKind::Read,
+                };
+                xrefs.insert(Xref { from: pair.address, to: pair.target, kind });
+            }
+        }
+
+        for (address, target) in relocation_targets(image) {
+            // Relocations applied inside an instruction are references made by it
+            let from = instructions
+                .range(..=address)
+                .next_back()
+                .filter(|(_, x)| address < x.next_address())
+                .map_or(address, |(start, _)| *start);
+            xrefs.insert(Xref { from, to: target, kind: XrefKind::Relocation });
+        }
+        for (address, target) in data_pointers(image) {
+            xrefs.insert(Xref { from: address, to: target, kind: XrefKind::Pointer });
+        }
+        xrefs
+    }
+
+    // The same reference found twice, such as a pointer that also has a relocation, is kept once
+    fn insert(&mut self, xref: Xref) {
+        let existing = self.from.entry(xref.from).or_default();
+        if existing.iter().any(|x| x.to == xref.to) {
+            return;
+        }
+        existing.push(xref);
+        self.to.entry(xref.to).or_default().push(xref);
+    }
+
+    /// What the instruction or data word at `address` refers to
+    pub fn from(&self, address: u64) -> &[Xref] {
+        self.from.get(&address).map_or(&[], |x| &x[..])
+    }
+
+    /// The references made from anywhere in `start..end`, such as a function
+    pub fn from_range(&self, start: u64, end: u64) -> Vec<Xref> {
+        self.from.range(start..end.max(start)).flat_map(|x| x.1.iter().copied()).collect()
+    }
+
+    /// Who refers to `address`
+    pub fn to(&self, address: u64) -> &[Xref] {
+        self.to.get(&address).map_or(&[], |x| &x[..])
+    }
+
+    /// The references to anywhere in `start..end`, such as into an array
+    pub fn to_range(&self, start: u64, end: u64) -> Vec<Xref> {
+        let mut res: Vec<Xref> = self.to.range(start..end.max(start)).flat_map(|x| x.1.iter().copied()).collect();
+        res.sort();
+        res
+    }
+
+    /// All the references, by source
+    pub fn iter(&self) -> impl Iterator<Item = &Xref> {
+        self.from.values().flatten()
+    }
+}
+
+// The instructions of the functions and the PLT stubs, by address
+fn instructions(image: &Image, decoder: &dyn Decoder, functions: &[Function]) -> BTreeMap<u64, Instruction> {
+    let starts: BTreeSet<u64> = functions.iter().map(|x| x.start).collect();
+    let frames = frame_table(image);
+    let mut res = BTreeMap::new();
+    for function in functions {
+        let landing_pads: Vec<u64> = call_sites(image, &frames, function.start).iter().filter_map(|x| x.landing_pad).collect();
+        res.extend(function_instructions(image, decoder, function.start, &landing_pads, &starts));
+    }
+    for section in image.sections.iter().filter(|x| PLT_SECTIONS.contains(&x.name.as_str())) {
+        for line in linear_sweep(decoder, &section.data, section.address) {
+            if let Line::Instruction(instruction) = line {
+                res.insert(instruction.address, instruction);
+            }
+        }
+    }
+    res
+}
+
+// The references an instruction makes on its own
+fn instruction_xrefs(image: &Image, instruction: &Instruction) -> Vec<Xref> {
+    let from = instruction.address;
+    let mut res = Vec::new();
+    let branch = instruction.branch_target();
+    if let Some(to) = branch {
+        let kind = if instruction.flow == FlowKind::Call { XrefKind::Call } else { XrefKind::Jump };
+        res.push(Xref { from, to, kind });
+    }
+
+    for operand in instruction.operands.iter() {
+        match operand {
+            Operand::Memory(mem) => {
+                // fs and gs relative accesses are to thread local storage, not to the address
+                let thread_local = mem.segment.is_some_and(|x| x.name == "fs" || x.name == "gs");
+                let to = match mem.target {
+                    Some(x) if !thread_local => x,
+                    _ => continue,
+                };
+                let kind = if mem.size == 0 {
+                    XrefKind::Address
+                } else if is_store(image, instruction) {
+                    XrefKind::Write
+                } else {
+                    XrefKind::Read
+                };
+                res.push(Xref { from, to, kind });
+                // ARM loads addresses from literal pools next to the code
+                if let Some(pointer) = literal(image, instruction, to) {
+                    res.push(Xref { from, to: pointer, kind: XrefKind::Address });
+                }
+            }
+            Operand::Address(to) if branch != Some(*to) => res.push(Xref { from, to: *to, kind: XrefKind::Address }),
+            _ => {}
+        }
+    }
+    res
+}
+
+// Whether the memory operand of an instruction is written rather than read
+fn is_store(image: &Image, instruction: &Instruction) -> bool {
+    let mnemonic = instruction.mnemonic.as_str();
+    match image.target.instruction_set {
+        // The destination comes first, single operand instructions mostly modify it in place
+        InstructionSet::X86 | InstructionSet::X86_64 => {
+            let first = matches!(instruction.operands.first(), Some(Operand::Memory(_)));
+            let reads = mnemonic.starts_with("cmp")
+                || mnemonic.starts_with("ucomis")
+                || mnemonic.starts_with("comis")
+                || mnemonic.starts_with("fld")
+                || mnemonic.starts_with("fild")
+                || matches!(mnemonic, "test" | "push" | "call" | "jmp" | "bt" | "mul" | "imul" | "div" | "idiv");
+            first && !reads
+        }
+        InstructionSet::RISC_V | InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => {
+            matches!(mnemonic, "sb" | "sh" | "sw" | "sd" | "fsw" | "fsd" | "swc1" | "sdc1" | "swl" | "swr" | "sdl" | "sdr")
+                || mnemonic.starts_with("sc")
+        }
+        // Store mnemonics start with st on the other architectures, exclusive stores included
+        _ => mnemonic.starts_with("st"),
+    }
+}
+
+// The address an ARM pc relative load reads from a literal pool, if it points into the binary
+fn literal(image: &Image, instruction: &Instruction, address: u64) -> Option<u64> {
+    if image.target.instruction_set != InstructionSet::ARM || instruction.mnemonic != "ldr" || !image.is_code(address) {
+        return None;
+    }
+    let value = read_pointer(image, address)?;
+    target_address(image, value)
+}
+
+// The pointer sized word at an address
+fn read_pointer(image: &Image, address: u64) -> Option<u64> {
+    let section = image.section_at(address)?;
+    let size = image.target.bits as usize / 8;
+    let offset = (address - section.address) as usize;
+    let bytes = section.data.get(offset..offset + size)?;
+    Some(word(bytes, image.target.endianness))
+}
+
+pub(super) fn word(bytes: &[u8], endianness: Endianness) -> u64 {
+    let mut value = 0;
+    for i in 0..bytes.len() {
+        let byte = match endianness {
+            Endianness::LittleEndian => bytes[bytes.len() - 1 - i],
+            Endianness::BigEndian => bytes[i],
+        };
+        value = (value << 8) | byte as u64;
+    }
+    value
+}
+
+// The address a pointer value refers to, if it is in an allocated section. Thumb code pointers have their low
+// bit set.
+fn target_address(image: &Image, value: u64) -> Option<u64> {
+    if value == 0 {
+        return None;
+    }
+    if image.section_at(value).is_some() {
+        return Some(value);
+    }
+    let thumb = image.target.instruction_set == InstructionSet::ARM && value & 1 == 1 && image.is_code(value & !1);
+    Some(value & !1).filter(|_| thumb)
+}
+
+// Where the relocations point, by where they are applied. Relocations against imports point nowhere in the
+// binary, and copy relocations only reserve room for the data of an import.
+fn relocation_targets(image: &Image) -> Vec<(u64, u64)> {
+    let mut res = Vec::new();
+    for relocation in image.relocations.iter() {
+        if relocation_type_name(image.target.instruction_set, relocation.kind).is_some_and(|x| x.ends_with("_COPY")) {
+            continue;
+        }
+        // By linkage name, as constructors and destructors of different kinds demangle the same
+        let base = match relocation.linkage_name() {
+            None => 0,
+            Some(name) => {
+                let symbol = image.symbols.iter().filter(|x| x.linkage_name() == name && x.section.is_some()).max_by_key(|x| x.size);
+                let section = image.sections.iter().find(|x| x.name == name && x.allocated);
+                match (symbol, section) {
+                    (Some(symbol), _) => symbol.address,
+                    (None, Some(section)) => section.address,
+                    (None, None) => continue,
+                }
+            }
+        };
+        if let Some(target) = target_address(image, base.wrapping_add(relocation.addend as u64)) {
+            res.push((relocation.address, target));
+        }
+    }
+    res
+}
+
+// Aligned pointer sized words of the data sections whose value is an address in the binary. Tables of other
+// entries, such as the symbol tables, are left out, and so are the unwinding tables and notes, which hold no
+// absolute addresses.
+fn data_pointers(image: &Image) -> Vec<(u64, u64)> {
+    let size = image.target.bits as usize / 8;
+    let mut res = Vec::new();
+    if image.relocatable {
+        return res;
+    }
+    for section in image.sections.iter() {
+        if !section.allocated || section.executable || section.data.is_empty() {
+            continue;
+        }
+        if section.entry_size != 0 && section.entry_size != size as u64 {
+            continue;
+        }
+        if [".eh_frame", ".gcc_except_table", ".note"].iter().any(|x| section.name.starts_with(x)) {
+            continue;
+        }
+        let skip = (section.address as usize).wrapping_neg() % size;
+        for (i, bytes) in section.data.get(skip..).unwrap_or_default().chunks_exact(size).enumerate() {
+            if let Some(target) = target_address(image, word(bytes, image.target.endianness)) {
+                res.push((section.address + (skip + i * size) as u64, target));
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Xref, XrefKind, Xrefs};
+    use crate::analysis::{Function, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Relocation, Section};
+    use crate::instruction_set::InstructionSet;
+
+    fn section(index: usize, name: &str, address: u64, data: Vec<u8>) -> Section {
+        Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size: 0,
+            data,
+            allocated: true,
+            executable: name == ".text",
+            writable: name == ".data",
+            merged_strings: false,
+        }
+    }
+
+    fn function(start: u64, end: u64) -> Function {
+        Function { start, end, name: None, sources: vec![Source::Symbol] }
+    }
+
+    #[test]
+    fn kinds() {
+        // call 0x1020; mov eax, [rip + 0x3000]; mov [rip + 0x3004], eax; lea rdi, [rip + 0x2000]; jne 0x101b; nop; ret
+        let mut text = vec![0xcc; 0x2a];
+        text[..0x1c].copy_from_slice(&[
+            0xe8, 0x1b, 0, 0, 0, 0x8b, 0x05, 0xf5, 0x1f, 0, 0, 0x89, 0x05, 0xf3, 0x1f, 0, 0, 0x48, 0x8d, 0x3d, 0xe8, 0x0f, 0, 0,
+            0x75, 0x01, 0x90, 0xc3,
+        ]);
+        // mov rax, fs:[0x28]; ret
+        text[0x20..].copy_from_slice(&[0x64, 0x48, 0x8b, 0x04, 0x25, 0x28, 0, 0, 0, 0xc3]);
+        // Two integers, pointers to the string and to the second function, a word the dynamic linker sets to
+        // the address of .data, and a number that is no address
+        let mut data = vec![0; 0x28];
+        // R_X86_64_RELATIVE
+        let relative = Relocation { address: 0x3018, kind: 8, symbol: None, addend: 0x3000, section: Some(3), plt: false, mangled: None };
+        data[8..16].copy_from_slice(&0x2000u64.to_le_bytes());
+        data[16..24].copy_from_slice(&0x1020u64.to_le_bytes());
+        data[32..40].copy_from_slice(&0x12345u64.to_le_bytes());
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0x1000,
+            relocatable: false,
+            sections: vec![
+                section(1, ".text", 0x1000, text),
+                section(2, ".rodata", 0x2000, b"hi\0\0".to_vec()),
+                section(3, ".data", 0x3000, data),
+            ],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: vec![relative],
+        };
+        let decoder = decoder_for(&image.target).unwrap();
+        let xrefs = Xrefs::build(&image, &*decoder, &[function(0x1000, 0x101c), function(0x1020, 0x102a)]);
+
+        let xref = |from, to, kind| Xref { from, to, kind };
+        assert_eq!(xrefs.iter().copied().collect::<Vec<_>>(), vec![
+            xref(0x1000, 0x1020, XrefKind::Call),
+            xref(0x1005, 0x3000, XrefKind::Read),
+            xref(0x100b, 0x3004, XrefKind::Write),
+            xref(0x1011, 0x2000, XrefKind::Address),
+            xref(0x1018, 0x101b, XrefKind::Jump),
+            xref(0x3008, 0x2000, XrefKind::Pointer),
+            xref(0x3010, 0x1020, XrefKind::Pointer),
+            xref(0x3018, 0x3000, XrefKind::Relocation),
+        ]);
+        assert_eq!(xrefs.to(0x2000), &[xref(0x1011, 0x2000, XrefKind::Address), xref(0x3008, 0x2000, XrefKind::Pointer)]);
+        assert_eq!(xrefs.from_range(0x1000, 0x1020).len(), 5);
+        let into_data: Vec<_> = xrefs.to_range(0x3000, 0x3008).into_iter().map(|x| (x.from, x.kind)).collect();
+        assert_eq!(into_data, vec![(0x1005, XrefKind::Read), (0x100b, XrefKind::Write), (0x3018, XrefKind::Relocation)]);
+        // Thread local accesses are not to the address they give
+        assert!(xrefs.from(0x1020).is_empty());
+    }
+}
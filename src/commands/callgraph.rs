@@ -0,0 +1,43 @@
+use std::collections::BTreeSet;
+
+use decster::analysis::{self, CallGraph};
+use decster::disasm;
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster callgraph [--from NAME | --from-exports] [--dot | --json] [--no-demangle] [--strict] FILE";
+
+/// `decster callgraph`: the calls between the functions and to imported symbols
+///
+/// With --from, only the functions reachable from the given one are shown, --from-exports does the same for all
+/// exported functions and the entry point.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--from"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+
+    let roots: Option<Vec<u64>> = if let Some(name) = args.option("--from") {
+        Some(vec![super::find_function(&functions, name)?.start])
+    } else if args.flag("--from-exports") {
+        let exported = functions.iter().filter(|x| x.sources.iter().any(|x| matches!(x, analysis::Source::Export | analysis::Source::Entry)));
+        Some(exported.map(|x| x.start).collect())
+    } else {
+        None
+    };
+
+    let graph = CallGraph::build(&image, &*decoder, functions);
+    let reachable: Option<BTreeSet<u64>> = roots.map(|roots| roots.iter().flat_map(|x| graph.reachable(*x)).collect());
+    let output = if args.flag("--dot") {
+        graph.to_dot(reachable.as_ref())
+    } else if args.flag("--json") {
+        graph.to_json(reachable.as_ref())
+    } else {
+        graph.to_text(reachable.as_ref())
+    };
+    print!("{}", output);
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+
+use decster::analysis;
+use decster::disasm::{self, Syntax};
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster cfg --function NAME [--dot] [--structure] [--att] [--no-demangle] [--strict] FILE";
+
+/// `decster cfg`: the control flow graph of a function, as a summary of its blocks, dominators and loops, as
+/// Graphviz DOT with --dot or as an outline of the ifs, loops and switches it is made of with --structure
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--function"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let name = args.option("--function").ok_or(USAGE)?;
+    let syntax = if args.flag("--att") { Syntax::Att } else { Syntax::Intel };
+
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let function = super::find_function(&functions, name)?;
+    let starts: BTreeSet<u64> = functions.iter().map(|x| x.start).collect();
+    let cfg = analysis::function_cfg(&image, &*decoder, &analysis::frame_table(&image), function.start, &starts);
+
+    if args.flag("--dot") {
+        print!("{}", cfg.to_dot(&function.display_name(), syntax));
+        return Ok(());
+    }
+    if args.flag("--structure") {
+        println!("{} at 0x{:x}\n", function.display_name(), function.start);
+        print!("{}", cfg.structure());
+        return Ok(());
+    }
+
+    let dominators = cfg.dominators();
+    let post_dominators = cfg.post_dominators();
+    let id = |x: Option<usize>| x.map(|x| x.to_string()).unwrap_or_else(|| "-".to_string());
+    println!(
+        "{} at 0x{:x}: {} blocks, {}",
+        function.display_name(),
+        function.start,
+        cfg.blocks.len() - 1,
+        if cfg.is_reducible() { "reducible" } else { "irreducible" }
+    );
+    for (i, block) in cfg.blocks.iter().enumerate() {
+        if i == cfg.exit {
+            println!("\nBlock {} (exit)  idom {}", i, id(dominators.idom[i]));
+        } else {
+            println!(
+                "\nBlock {} 0x{:x}..0x{:x}  idom {}  ipdom {}",
+                i,
+                block.start,
+                block.end,
+                id(dominators.idom[i]),
+                id(post_dominators.idom[i])
+            );
+        }
+        for edge in cfg.edges.iter().filter(|x| x.from == i) {
+            println!("    -> {} {}", edge.to, edge.kind);
+        }
+    }
+
+    let loops = cfg.loops();
+    if !loops.is_empty() {
+        println!("\nLoops:");
+    }
+    for x in loops {
+        println!("    header {}, latches {:?}, body {:?}, exits {:?}", x.header, x.latches, x.body, x.exits);
+    }
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use decster::analysis;
+use decster::dwarf::{self, CfaRule, FrameTable, RegisterRule, UnwindRow};
+use decster::image::Image;
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster cfi [--function NAME] [--no-demangle] [--strict] FILE";
+
+/// `decster cfi`: the FDEs of .eh_frame and .debug_frame, with the CFA and register rules of each range of their
+/// code and the stack height they give
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--function"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let image = super::load(path, &args)?;
+    let address = match args.option("--function") {
+        Some(name) => Some(match image.function_named(name) {
+            Some(x) => x.address,
+            None => super::parse_address(name).map_err(|_| format!("No function {}", name))?,
+        }),
+        None => None,
+    };
+
+    if let Some(header) = analysis::eh_frame_header(&image) {
+        println!(".eh_frame_hdr: .eh_frame at 0x{:x}, {} functions in the table", header.eh_frame, header.table.len());
+    }
+    let tables = [(".eh_frame", analysis::frame_table(&image)), (".debug_frame", analysis::debug_frame_table(&image))];
+    if tables.iter().all(|x| x.1.fdes.is_empty()) {
+        return Err(format!("No call frame information in {}", path));
+    }
+    for (name, table) in tables.iter() {
+        let fdes = table.fdes.iter().filter(|x| address.is_none_or(|address| x.start <= address && address < x.end()));
+        for fde in fdes {
+            println!("\n{} FDE at 0x{:x}: {:x}..{:x}, CIE at 0x{:x}", name, fde.offset, fde.start, fde.end(), fde.cie);
+            match table.rows(fde) {
+                Ok(rows) => print_rows(&image, table, fde.cie, &rows),
+                Err(e) => println!("  {:?}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_rows(image: &Image, table: &FrameTable, cie: u64, rows: &[UnwindRow]) {
+    let instruction_set = image.target.instruction_set;
+    let name = |x: u64| dwarf::register_name(instruction_set, x);
+    let stack_pointer = dwarf::stack_pointer(instruction_set);
+    let return_register = table.cies.get(&cie).map(|x| x.return_register);
+    for row in rows.iter() {
+        let cfa = match &row.cfa {
+            CfaRule::RegisterOffset(register, offset) => format!("{}{:+}", name(*register), offset),
+            x => x.to_string(),
+        };
+        let height = match stack_pointer.and_then(|x| row.stack_height(x)) {
+            Some(x) => x.to_string(),
+            None => "?".to_string(),
+        };
+        let mut rules: Vec<String> = Vec::new();
+        for (register, rule) in row.registers.iter() {
+            // The return address is named after what it is rather than the register holding it
+            let register = match Some(*register) == return_register {
+                true => "ra".to_string(),
+                false => name(*register),
+            };
+            let rule = match rule {
+                RegisterRule::Register(x) => name(*x),
+                x => x.to_string(),
+            };
+            rules.push(format!("{}={}", register, rule));
+        }
+        println!("  {:8x} cfa={:<12} height={:<5} {}", row.start, cfa, height, rules.join(" "));
+    }
+}
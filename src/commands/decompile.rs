@@ -0,0 +1,43 @@
+use decster::analysis;
+use decster::decompile::{Decompiler, GlobalNames, LocalNames};
+use decster::disasm;
+use decster::ir::{self, Abi};
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster decompile [--function] NAME [--abi NAME] [--locals registers|numbered] \
+                     [--globals symbols|addresses] [--no-demangle] [--strict] FILE";
+
+/// `decster decompile`: a function as C pseudocode. The function is named before the file, with --function or
+/// without. Locals are named after their registers and stack offsets, or
+/// numbered with --locals numbered. Globals are named after their symbols, or their addresses with --globals
+/// addresses. Calls follow the calling convention of the target or the one --abi names.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--function", "--abi", "--locals", "--globals"])?;
+    let (name, path) = match (args.option("--function"), args.positional.as_slice()) {
+        (Some(name), [path]) => (name, path),
+        (None, [name, path]) => (name.as_str(), path),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let image = super::load(path, &args)?;
+    let architecture = image.target.instruction_set.metadata().name;
+    let decoder = disasm::decoder_for(&image.target).ok_or_else(|| format!("No disassembler for {}", architecture))?;
+    let lifter = ir::lifter_for(&image.target).ok_or_else(|| format!("No lifter for {}", architecture))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let function = super::find_function(&functions, name)?;
+
+    let mut decompiler = Decompiler::new(&image, &*decoder, &*lifter, &functions);
+    if let Some(name) = args.option("--abi") {
+        let abi = Abi::from_name(name).ok_or_else(|| format!("Unknown ABI {}", name))?;
+        decompiler.conventions.set_abi(function.start, abi);
+    }
+    if let Some(name) = args.option("--locals") {
+        decompiler.naming.locals = LocalNames::from_name(name).ok_or_else(|| format!("Unknown naming {}", name))?;
+    }
+    if let Some(name) = args.option("--globals") {
+        decompiler.naming.globals = GlobalNames::from_name(name).ok_or_else(|| format!("Unknown naming {}", name))?;
+    }
+    print!("{}", decompiler.decompile(function));
+    Ok(())
+}
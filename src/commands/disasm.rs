@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+
+use decster::analysis::{self, Function, Line};
+use decster::disasm::{self, DelaySlot, Syntax};
+use decster::dwarf::UnwindRow;
+use decster::elf::relocation_type_name;
+use decster::image::{Image, Relocation, Section};
+use decster::instruction_set::InstructionSet;
+
+use super::{parse_address, Arguments};
+
+const USAGE: &str = "Usage: decster disasm [--function NAME | --range VA..VA | --section NAME] [--recursive] [--stack-height] [--att] \
+                     [--no-demangle] [--strict] FILE";
+
+// What to disassemble
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selection {
+    // All executable sections
+    Code,
+    Function(String),
+    Range(u64, u64),
+    Section(String),
+}
+
+// A part of a section to list
+#[derive(Debug, Clone, Copy)]
+struct Region<'a> {
+    section: &'a Section,
+    start: u64,
+    end: u64,
+}
+
+/// `decster disasm`: a listing with symbols, relocations and the names of PLT stubs
+///
+/// Linear sweep decodes everything in the selected range and is good for a quick look. With --recursive, only
+/// what is reachable from the entry point and the functions is decoded, which keeps data out of the listing.
+/// Functions without a symbol are named sub_ADDRESS. With --stack-height, each instruction is preceded by how far
+/// the stack pointer is below the CFA when it runs, from the call frame information.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--function", "--range", "--section"])?;
+    let path = match args.positional.as_slice() {
+        [path] => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    let selection = selection(&args)?;
+    let syntax = if args.flag("--att") { Syntax::Att } else { Syntax::Intel };
+
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let regions = regions(&image, &functions, &selection)?;
+    let plt = analysis::plt_names(&image, &*decoder);
+    let unnamed: Vec<(u64, String)> =
+        functions.iter().filter(|x| x.name.is_none()).map(|x| (x.start, x.display_name())).collect();
+    let rows = if args.flag("--stack-height") { Some(analysis::unwind_rows(&image)) } else { None };
+
+    let mut roots = Vec::new();
+    if args.flag("--recursive") {
+        if !image.relocatable && image.is_code(image.entry) {
+            roots.push(image.entry);
+        }
+        roots.extend(image.symbols.iter().filter(|x| x.function).map(|x| x.address));
+        roots.extend(functions.iter().map(|x| x.start));
+        roots.extend(plt.keys());
+        if let Selection::Function(_) = selection {
+            roots.extend(regions.iter().map(|x| x.start));
+        }
+    }
+
+    let mut previous = None;
+    for region in regions.iter() {
+        let section = region.section;
+        if previous != Some(section.index) {
+            println!("\nDisassembly of section {}:", section.name);
+            previous = Some(section.index);
+        }
+        // Decoding runs to the end of the section so that the last instruction of the region is not cut short
+        let bytes = &section.data[(region.start - section.address) as usize..];
+        // The lazy binding code of PLT stubs is only reached through the GOT, but it is known to be code
+        let plt_section = analysis::PLT_SECTIONS.contains(&section.name.as_str());
+        let lines = if args.flag("--recursive") && !plt_section {
+            // Sections of object files overlap, each of them is a separate address space
+            let code = match image.relocatable {
+                true => vec![(section.address, &section.data[..])],
+                false => image.code(),
+            };
+            let roots: Vec<u64> = roots.iter().copied().filter(|x| !image.relocatable || section.contains(*x)).collect();
+            let instructions = analysis::recursive_descent(&*decoder, &code, &roots);
+            analysis::listing(&instructions, &bytes[..(region.end - region.start) as usize], region.start)
+        } else {
+            let mut lines = analysis::linear_sweep(&*decoder, bytes, region.start);
+            lines.retain(|x| x.address() < region.end);
+            lines
+        };
+
+        let names = Names::new(&image, &plt, &unnamed, section);
+        print_lines(&image, &names, &lines, syntax, rows.as_ref());
+    }
+    Ok(())
+}
+
+fn selection(args: &Arguments) -> Result<Selection, String> {
+    let mut res = Vec::new();
+    if let Some(name) = args.option("--function") {
+        res.push(Selection::Function(name.to_string()));
+    }
+    if let Some(range) = args.option("--range") {
+        let (start, end) = range.split_once("..").ok_or("A range is given as START..END")?;
+        let (start, end) = (parse_address(start)?, parse_address(end)?);
+        if start > end {
+            return Err(format!("The range {} starts after its end", range));
+        }
+        res.push(Selection::Range(start, end));
+    }
+    if let Some(name) = args.option("--section") {
+        res.push(Selection::Section(name.to_string()));
+    }
+    match res.len() {
+        0 => Ok(Selection::Code),
+        1 => Ok(res.remove(0)),
+        _ => Err("Only one of --function, --range and --section can be given".to_string()),
+    }
+}
+
+fn regions<'a>(image: &'a Image, functions: &[Function], selection: &Selection) -> Result<Vec<Region<'a>>, String> {
+    let whole = |section: &'a Section| Region { section, start: section.address, end: section.address + section.data.len() as u64 };
+    let code = image.sections.iter().filter(|x| x.executable && x.allocated && !x.data.is_empty());
+
+    let res: Vec<Region<'a>> = match selection {
+        Selection::Code => code.map(whole).collect(),
+        Selection::Section(name) => vec![whole(image.section_by_name(name).ok_or_else(|| format!("No section {}", name))?)],
+        Selection::Range(start, end) => code
+            .map(whole)
+            .filter(|x| x.start < *end && *start < x.end)
+            .map(|x| Region { start: x.start.max(*start), end: x.end.min(*end), ..x })
+            .collect(),
+        Selection::Function(name) => {
+            let symbol = match image.function_named(name) {
+                Some(x) => x,
+                // Functions found in the code go by the names they are listed with, or their addresses
+                None => {
+                    let function = super::find_function(functions, name)?;
+                    let section = image.section_at(function.start).ok_or_else(|| format!("{} is not in a section", name))?;
+                    return Ok(vec![Region { start: function.start, end: function.end.max(function.start + 1), ..whole(section) }]);
+                }
+            };
+            let section = symbol.section.and_then(|x| image.sections.get(x)).ok_or_else(|| format!("{} is not defined", name))?;
+            let region = whole(section);
+            // Without a size, the function extends to the next symbol
+            let end = match symbol.size {
+                0 => image
+                    .symbols
+                    .iter()
+                    .filter(|x| x.section == symbol.section && x.address > symbol.address)
+                    .map(|x| x.address)
+                    .min()
+                    .unwrap_or(region.end),
+                size => symbol.address + size,
+            };
+            vec![Region { start: symbol.address.max(region.start), end: end.min(region.end), ..region }]
+        }
+    };
+    if res.is_empty() {
+        return Err("Nothing to disassemble".to_string());
+    }
+    Ok(res)
+}
+
+// Names of addresses in a section, for labels and the targets of branches and loads
+struct Names<'a> {
+    labels: BTreeMap<u64, &'a str>,
+    // Start and end of the functions with a size
+    functions: Vec<(u64, u64, &'a str)>,
+    relocations: Vec<&'a Relocation>,
+}
+
+impl<'a> Names<'a> {
+    fn new(image: &'a Image, plt: &'a BTreeMap<u64, String>, unnamed: &'a [(u64, String)], section: &Section) -> Names<'a> {
+        // Symbols of object files are only meaningful in their own section
+        let mut symbols: Vec<_> = image
+            .symbols
+            .iter()
+            .filter(|x| x.section.is_some() && !x.name.is_empty() && !x.name.starts_with('$'))
+            .filter(|x| !image.relocatable || x.section == Some(section.index))
+            .collect();
+
+        // Where several symbols share an address, functions win over other symbols and global over local ones
+        symbols.sort_by_key(|x| std::cmp::Reverse((x.function, x.global, !x.dynamic)));
+        let mut labels: BTreeMap<u64, &'a str> = plt.iter().map(|(address, name)| (*address, name.as_str())).collect();
+        for symbol in symbols.iter() {
+            labels.entry(symbol.address).or_insert(symbol.name.as_str());
+        }
+        for (address, name) in unnamed.iter().filter(|x| !image.relocatable || section.contains(x.0)) {
+            labels.entry(*address).or_insert(name.as_str());
+        }
+        let functions = symbols
+            .iter()
+            .filter(|x| x.function && x.size > 0)
+            .map(|x| (x.address, x.address + x.size, x.name.as_str()))
+            .collect();
+        let relocations = image
+            .relocations
+            .iter()
+            .filter(|x| section.contains(x.address) && (!image.relocatable || x.section == Some(section.index)))
+            .collect();
+        Names { labels, functions, relocations }
+    }
+
+    // name or name+offset, objdump style
+    fn describe(&self, address: u64) -> Option<String> {
+        if let Some(name) = self.labels.get(&address) {
+            return Some(name.to_string());
+        }
+        self.functions
+            .iter()
+            .find(|(start, end, _)| address >= *start && address < *end)
+            .map(|(start, _, name)| format!("{}+0x{:x}", name, address - start))
+    }
+}
+
+fn print_lines(image: &Image, names: &Names<'_>, lines: &[Line], syntax: Syntax, rows: Option<&BTreeMap<u64, UnwindRow>>) {
+    let width = image.target.bits as usize / 4;
+    let instructions: Vec<disasm::Instruction> = lines
+        .iter()
+        .filter_map(|x| match x {
+            Line::Instruction(instruction) => Some(instruction.clone()),
+            _ => None,
+        })
+        .collect();
+    // adrp pairs only give the address they compute when taken together
+    let pairs = match image.target.instruction_set {
+        InstructionSet::AArch64 => disasm::aarch64::address_pairs(&instructions),
+        _ => Vec::new(),
+    };
+
+    // Instructions in a delay slot execute before the branch, they are indented to show it
+    let mut in_delay_slot = false;
+    for line in lines {
+        let (address, length) = (line.address(), line.length() as u64);
+        match line {
+            Line::Instruction(instruction) => {
+                print_label(names, address, width);
+                let mut text = instruction.render(syntax);
+                let target = instruction.branch_target().or_else(|| instruction.memory_targets().first().copied());
+                if let Some(pair) = pairs.iter().find(|x| x.address == address) {
+                    text.push_str(&format!("        // 0x{:x}", pair.target));
+                    if let Some(name) = names.describe(pair.target) {
+                        text.push_str(&format!(" <{}>", name));
+                    }
+                } else if let Some(name) = target.and_then(|x| names.describe(x)) {
+                    text.push_str(&format!(" <{}>", name));
+                }
+                let indent = if in_delay_slot { " " } else { "" };
+                in_delay_slot = instruction.delay_slot != DelaySlot::None;
+                let height = match rows {
+                    Some(rows) => match analysis::stack_height(image, rows, address) {
+                        Some(x) => format!("{:>5} ", x),
+                        None => format!("{:>5} ", "?"),
+                    },
+                    None => String::new(),
+                };
+                println!("{:8x}: {}{}{}", address, height, indent, text);
+            }
+            Line::Bad { error, .. } => {
+                print_label(names, address, width);
+                in_delay_slot = false;
+                println!("{:8x}: (bad) {:?}", address, error);
+            }
+            Line::Data { bytes, .. } => {
+                in_delay_slot = false;
+                print_data(names, address, bytes, width);
+            }
+        }
+
+        for relocation in names.relocations.iter().filter(|x| x.address >= address && x.address < address + length) {
+            print_relocation(image, relocation);
+        }
+    }
+}
+
+fn print_label(names: &Names<'_>, address: u64, width: usize) {
+    if let Some(name) = names.labels.get(&address) {
+        println!("\n{:0width$x} <{}>:", address, name, width = width);
+    }
+}
+
+// Eight bytes to a line, with lines starting anew at labels
+fn print_data(names: &Names<'_>, address: u64, bytes: &[u8], width: usize) {
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let current = address + offset as u64;
+        print_label(names, current, width);
+        let next_label = names.labels.range(current + 1..).next().map(|x| (x.0 - current) as usize);
+        let length = next_label.unwrap_or(usize::MAX).min(8).min(bytes.len() - offset);
+        let text: Vec<String> = bytes[offset..offset + length].iter().map(|x| format!("0x{:02x}", x)).collect();
+        println!("{:8x}: .byte {}", current, text.join(", "));
+        offset += length;
+    }
+}
+
+fn print_relocation(image: &Image, relocation: &Relocation) {
+    let kind = match relocation_type_name(image.target.instruction_set, relocation.kind) {
+        Some(name) => name.to_string(),
+        None => format!("type {}", relocation.kind),
+    };
+    let addend = match relocation.addend {
+        0 => String::new(),
+        x if x < 0 => format!("-0x{:x}", x.unsigned_abs()),
+        x => format!("+0x{:x}", x),
+    };
+    let symbol = relocation.symbol.as_deref().unwrap_or("");
+    println!("{:8x}:     {} {}{}", relocation.address, kind, symbol, addend);
+}
@@ -0,0 +1,74 @@
+use decster::analysis;
+use decster::dwarf::Location;
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster dwarf [--types] [--lines] [--strict] FILE";
+
+/// `decster dwarf`: the functions, globals and, if asked for, types and line tables of the debug information
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let image = super::load(path, &args)?;
+    let info = analysis::debug_info(&image);
+    if info.units.is_empty() {
+        return Err(format!("No debug information in {}", path));
+    }
+
+    let width = image.target.bits as usize / 4;
+    println!("Units:");
+    for unit in info.units.iter() {
+        let name = unit.root().and_then(|x| x.name()).unwrap_or("?");
+        println!("  {:08x} DWARF {} {} ({} entries)", unit.offset, unit.version, name, unit.dies.len());
+    }
+
+    println!("Functions:");
+    for function in info.functions.iter() {
+        let ranges: Vec<String> = function
+            .ranges
+            .iter()
+            .map(|x| format!("{:0width$x}..{:0width$x}", x.0, x.1, width = width))
+            .collect();
+        let source = match (&function.file, function.line) {
+            (Some(file), Some(line)) => format!("  {}:{}", file, line),
+            _ => String::new(),
+        };
+        println!("  {} {}{}", ranges.join(" "), info.prototype(function), source);
+        for variable in function.parameters.iter().chain(function.variables.iter()) {
+            let location = variable.location.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "-".to_string());
+            println!("      {:<24} {}", location, info.declaration(variable.ty, variable.name.as_deref().unwrap_or("")));
+            if let Some(Location::List(ranges)) = &variable.location {
+                for range in ranges.iter() {
+                    println!("          {:0width$x}..{:0width$x} {}", range.start, range.end, range.location, width = width);
+                }
+            }
+        }
+    }
+
+    println!("Variables:");
+    for variable in info.variables.iter() {
+        let location = variable.location.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "-".to_string());
+        let linkage = if variable.external { "" } else { "static " };
+        println!("  {:<24} {}{}", location, linkage, info.declaration(variable.ty, variable.name.as_deref().unwrap_or("")));
+    }
+
+    if args.flag("--types") {
+        println!("Types:");
+        for ty in info.types.values() {
+            let size = ty.size.map(|x| x.to_string()).unwrap_or_else(|| "-".to_string());
+            println!("  {:08x} {:>6} {}", ty.offset, size, info.type_name(Some(ty.offset)));
+        }
+    }
+
+    if args.flag("--lines") {
+        println!("Lines:");
+        for program in info.lines.iter() {
+            for row in program.rows.iter() {
+                let file = program.file(row.file).unwrap_or_else(|| "?".to_string());
+                let end = if row.end_sequence { " end" } else { "" };
+                println!("  {:0width$x} {}:{}:{}{}", row.address, file, row.line, row.column, end, width = width);
+            }
+        }
+    }
+    Ok(())
+}
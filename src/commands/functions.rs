@@ -0,0 +1,31 @@
+use decster::analysis;
+use decster::disasm;
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster functions [--no-demangle] [--strict] FILE";
+
+/// `decster functions`: the functions found in the code, with where they end and how sure that is
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+
+    let width = image.target.bits as usize / 4;
+    println!("{:<width$} {:<width$} {:<10} {:<30} Found by", "Start", "End", "Confidence", "Name", width = width);
+    for function in analysis::discover_functions(&image, &*decoder) {
+        let sources: Vec<&str> = function.sources.iter().map(|x| x.name()).collect();
+        println!(
+            "{:0width$x} {:0width$x} {:<10} {:<30} {}",
+            function.start,
+            function.end,
+            format!("{:?}", function.confidence()),
+            function.display_name(),
+            sources.join(", "),
+            width = width
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+
+use decster::analysis;
+use decster::disasm;
+use decster::decompile::Decompiler;
+use decster::ir::{self, Abi, CallEffects, Function, StackFrame};
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster ir --function NAME [--ssa] [--optimize] [--abi NAME] [--no-demangle] [--strict] FILE";
+
+/// `decster ir`: the statements of the intermediate representation of a function, block by block. With --ssa in SSA
+/// form, with --optimize also after propagating constants and copies and removing dead code. In SSA form calls
+/// follow the calling convention of the target or the one --abi names. The prototype is shown as the decompiler
+/// recovers it, and the stack frame as it is before the optimizations remove any of its adjustments.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--function", "--abi"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let name = args.option("--function").ok_or(USAGE)?;
+
+    let image = super::load(path, &args)?;
+    let architecture = image.target.instruction_set.metadata().name;
+    let decoder = disasm::decoder_for(&image.target).ok_or_else(|| format!("No disassembler for {}", architecture))?;
+    let lifter = ir::lifter_for(&image.target).ok_or_else(|| format!("No lifter for {}", architecture))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let function = super::find_function(&functions, name)?;
+    let starts: BTreeSet<u64> = functions.iter().map(|x| x.start).collect();
+    let cfg = analysis::function_cfg(&image, &*decoder, &analysis::frame_table(&image), function.start, &starts);
+
+    let mut decompiler = Decompiler::new(&image, &*decoder, &*lifter, &functions);
+    if let Some(name) = args.option("--abi") {
+        let abi = Abi::from_name(name).ok_or_else(|| format!("Unknown ABI {}", name))?;
+        decompiler.conventions.set_abi(function.start, abi);
+    }
+    let convention = decompiler.conventions.abi(function.start).map(|x| x.convention());
+
+    let mut lifted = Function::lift(&cfg, &*lifter);
+    let optimize = args.flag("--optimize");
+    let ssa = optimize || args.flag("--ssa");
+    if ssa {
+        let effects = match &convention {
+            Some(convention) => CallEffects::for_convention(&lifted, convention),
+            None => CallEffects::conservative(&lifted, lifter.stack_pointer()),
+        };
+        lifted.to_ssa(&effects);
+    }
+    let frame = convention.as_ref().filter(|_| ssa).map(|x| StackFrame::analyze(&lifted, x));
+    if optimize {
+        lifted.propagate_constants();
+        lifted.propagate_copies();
+        lifted.eliminate_dead_code();
+    }
+
+    println!("{} at 0x{:x}", function.display_name(), function.start);
+    if let Some(frame) = frame {
+        if let Some(prototype) = decompiler.prototype(function) {
+            println!("prototype: {}", prototype);
+        }
+        let locals: Vec<String> = frame.locals().map(|x| format!("{}:{}", x.offset, x.bits)).collect();
+        println!("frame: {} bytes{}, locals [{}]", frame.depth, if frame.dynamic { ", dynamic" } else { "" }, locals.join(", "));
+    }
+    println!();
+    print!("{}", lifted);
+    Ok(())
+}
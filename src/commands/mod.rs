@@ -0,0 +1,119 @@
+use std::fs::File;
+use std::io::Read;
+
+use decster::analysis::Function;
+use decster::bits;
+use decster::demangle;
+use decster::elf::{self, ElfBitwidth, ElfParseError, ParseMode};
+use decster::image::Image;
+use decster::parsable_file::ParsableFile;
+
+pub mod callgraph;
+pub mod cfg;
+pub mod cfi;
+pub mod decompile;
+pub mod disasm;
+pub mod dwarf;
+pub mod functions;
+pub mod ir;
+pub mod plt;
+pub mod strings;
+pub mod xrefs;
+
+/// Arguments of a command: flags, options taking a value and positional arguments
+#[derive(Debug, Clone, Default)]
+pub struct Arguments {
+    pub positional: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Arguments {
+    /// Options are the arguments in `with_value`, given as `--name value` or `--name=value`. Everything else
+    /// starting with -- is a flag.
+    pub fn parse(args: &[String], with_value: &[&str]) -> Result<Arguments, String> {
+        let mut res = Arguments::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                res.positional.push(arg.clone());
+                continue;
+            }
+            if let Some((name, value)) = arg.split_once('=') {
+                res.options.push((name.to_string(), value.to_string()));
+            } else if with_value.contains(&arg.as_str()) {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                res.options.push((arg.clone(), value.clone()));
+            } else {
+                res.flags.push(arg.clone());
+            }
+        }
+        Ok(res)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|x| x == name)
+    }
+
+    pub fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|x| x.0 == name).map(|x| x.1.as_str())
+    }
+
+    /// How to parse the binary: lenient unless --strict is given
+    pub fn parse_mode(&self) -> ParseMode {
+        if self.flag("--strict") {
+            ParseMode::Strict
+        } else {
+            ParseMode::Lenient
+        }
+    }
+}
+
+/// Reads the binary at `path`, reporting what lenient parsing let through on stderr. Symbols are demangled unless
+/// --no-demangle is given.
+pub fn load(path: &str, args: &Arguments) -> Result<Image, String> {
+    let mode = args.parse_mode();
+    let mut contents = Vec::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut contents))
+        .map_err(|e| format!("Could not read file {}, error {}", path, e))?;
+    let contents = ParsableFile::new(&contents);
+
+    let res = match load_elf::<bits::SixtyfourBit>(contents.clone(), mode) {
+        Err(ElfParseError::WrongBitwidth(_)) => load_elf::<bits::ThirtytwoBit>(contents, mode),
+        res => res,
+    };
+    let mut image = res.map_err(|e| format!("Error parsing ELF {}: {:?}", path, e))?;
+    if !args.flag("--no-demangle") {
+        image.demangle();
+    }
+    Ok(image)
+}
+
+fn load_elf<B: ElfBitwidth>(mut contents: ParsableFile<'_>, mode: ParseMode) -> Result<Image, ElfParseError> {
+    let elf = elf::Elf::<B>::parse(&mut contents, mode)?;
+    for warning in elf.warnings.iter() {
+        eprintln!("Warning: {:?}", warning);
+    }
+    Image::from_elf(&elf, &mut contents)
+}
+
+/// An address given on the command line: hexadecimal with 0x, decimal otherwise
+pub fn parse_address(text: &str) -> Result<u64, String> {
+    let res = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    res.map_err(|_| format!("Invalid address {}", text))
+}
+
+/// The function called `name`: by symbol, mangled or not, by the sub_ADDRESS name of a function without one, or by
+/// its address
+pub fn find_function<'a>(functions: &'a [Function], name: &str) -> Result<&'a Function, String> {
+    let address = name.strip_prefix("0x").and_then(|x| u64::from_str_radix(x, 16).ok());
+    let demangled = demangle::demangled_name(name);
+    functions
+        .iter()
+        .find(|x| x.display_name() == name || x.display_name() == demangled || Some(x.start) == address)
+        .ok_or_else(|| format!("No function {}", name))
+}
@@ -0,0 +1,30 @@
+use decster::analysis;
+use decster::disasm;
+
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster plt [--no-demangle] [--strict] FILE";
+
+/// `decster plt`: the PLT stubs, with the GOT slot each jumps through and the symbol it leads to
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &[])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+
+    let width = image.target.bits as usize / 4;
+    for stub in analysis::plt_stubs(&image, &*decoder) {
+        let slot = match stub.slot {
+            Some(x) => format!("{:0width$x}", x, width = width),
+            None => format!("{:width$}", "-", width = width),
+        };
+        let symbol = match (&stub.symbol, stub.lazy) {
+            (Some(name), true) => format!("{} (lazy binding)", name),
+            (Some(name), false) => format!("{}@plt", name),
+            (None, _) => String::new(),
+        };
+        println!("{:0width$x}..{:0width$x} {:<9} {} {}", stub.address, stub.end, stub.section, slot, symbol, width = width);
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use decster::analysis::{self, Encoding, StringOptions, Xrefs};
+use decster::disasm;
+use decster::json;
+
+use super::xrefs::Names;
+use super::Arguments;
+
+const USAGE: &str = "Usage: decster strings [--min-length N] [--encoding NAME,...] [--json] [--no-demangle] [--strict] FILE";
+
+/// `decster strings`: the strings in the data sections and the instructions referring to them
+///
+/// The encodings are ascii, utf-8 (which includes ascii), utf-16le, utf-16be, utf-32le and utf-32be, all by default.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--min-length", "--encoding"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+
+    let mut options = StringOptions::default();
+    if let Some(length) = args.option("--min-length") {
+        options.min_length = length.parse().map_err(|_| format!("Invalid length {}", length))?;
+    }
+    if let Some(names) = args.option("--encoding") {
+        options.encodings = names
+            .split(',')
+            .map(|x| Encoding::from_name(x).ok_or_else(|| format!("Unknown encoding {}", x)))
+            .collect::<Result<_, _>>()?;
+    }
+
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let plt = analysis::plt_names(&image, &*decoder);
+    let xrefs = Xrefs::build(&image, &*decoder, &functions);
+    let names = Names::new(&image, &functions, &plt);
+    let strings = analysis::find_strings(&image, &xrefs, &options);
+
+    if args.flag("--json") {
+        let lines: Vec<String> = strings
+            .iter()
+            .map(|x| {
+                let references: Vec<String> = x.references.iter().map(|x| json::address(*x)).collect();
+                format!(
+                    "  {{\"address\": {}, \"section\": {}, \"encoding\": \"{}\", \"value\": {}, \"references\": [{}]}}",
+                    json::address(x.address),
+                    json::string(&x.section),
+                    x.encoding.name(),
+                    json::string(&x.value),
+                    references.join(", ")
+                )
+            })
+            .collect();
+        println!("[\n{}\n]", lines.join(",\n"));
+        return Ok(());
+    }
+
+    let width = image.target.bits as usize / 4;
+    for string in strings {
+        println!(
+            "{:0width$x} {:<14} {:<8} \"{}\"",
+            string.address,
+            string.section,
+            string.encoding.name(),
+            string.value.escape_debug(),
+            width = width
+        );
+        for reference in string.references {
+            println!("    from {:0width$x} {}", reference, names.describe(reference).unwrap_or_default(), width = width);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use decster::analysis::{self, Function, Xref, Xrefs};
+use decster::disasm;
+use decster::image::Image;
+use decster::json;
+
+use super::{parse_address, Arguments};
+
+const USAGE: &str = "Usage: decster xrefs [--to NAME | --from NAME] [--json] [--no-demangle] [--strict] FILE";
+
+/// `decster xrefs`: the references between code and data
+///
+/// --to lists who refers to a function, a data symbol (anywhere inside it) or an address, --from what a function
+/// or the instruction at an address refers to. Without either, all the references are listed.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = Arguments::parse(args, &["--to", "--from"])?;
+    let path = args.positional.first().ok_or(USAGE)?;
+    let image = super::load(path, &args)?;
+    let decoder = disasm::decoder_for(&image.target)
+        .ok_or_else(|| format!("No disassembler for {}", image.target.instruction_set.metadata().name))?;
+    let functions = analysis::discover_functions(&image, &*decoder);
+    let plt = analysis::plt_names(&image, &*decoder);
+    let xrefs = Xrefs::build(&image, &*decoder, &functions);
+    let names = Names::new(&image, &functions, &plt);
+
+    let selected: Vec<Xref> = match (args.option("--to"), args.option("--from")) {
+        (Some(_), Some(_)) => return Err("Only one of --to and --from can be given".to_string()),
+        (Some(name), None) => {
+            let (start, end) = names.resolve(name, false)?;
+            xrefs.to_range(start, end)
+        }
+        (None, Some(name)) => {
+            let (start, end) = names.resolve(name, true)?;
+            xrefs.from_range(start, end)
+        }
+        (None, None) => xrefs.iter().copied().collect(),
+    };
+
+    if args.flag("--json") {
+        let lines: Vec<String> = selected
+            .iter()
+            .map(|x| {
+                format!(
+                    "  {{\"from\": {}, \"from_name\": {}, \"to\": {}, \"to_name\": {}, \"kind\": \"{}\"}}",
+                    json::address(x.from),
+                    names.describe(x.from).map_or("null".to_string(), |x| json::string(&x)),
+                    json::address(x.to),
+                    names.describe(x.to).map_or("null".to_string(), |x| json::string(&x)),
+                    x.kind.name()
+                )
+            })
+            .collect();
+        println!("[\n{}\n]", lines.join(",\n"));
+        return Ok(());
+    }
+
+    let width = image.target.bits as usize / 4;
+    for xref in selected {
+        println!(
+            "{:0width$x} {:<30} {:<10} {:0width$x} {}",
+            xref.from,
+            names.describe(xref.from).unwrap_or_default(),
+            xref.kind.name(),
+            xref.to,
+            names.describe(xref.to).unwrap_or_default(),
+            width = width
+        );
+    }
+    Ok(())
+}
+
+// Names of the functions, PLT stubs and data symbols, to show where references are
+pub(super) struct Names<'a> {
+    image: &'a Image,
+    labels: BTreeMap<u64, String>,
+    // Start, end and name of the functions and of the data symbols with a size, and whether it is a function
+    ranges: Vec<(u64, u64, String, bool)>,
+}
+
+impl<'a> Names<'a> {
+    pub(super) fn new(image: &'a Image, functions: &[Function], plt: &BTreeMap<u64, String>) -> Names<'a> {
+        let mut labels = plt.clone();
+        let mut ranges = Vec::new();
+        for function in functions {
+            labels.entry(function.start).or_insert_with(|| function.display_name());
+            ranges.push((function.start, function.end, function.display_name(), true));
+        }
+        let symbols = image.symbols.iter().filter(|x| x.section.is_some() && !x.name.is_empty() && !x.name.starts_with('$'));
+        for symbol in symbols {
+            labels.entry(symbol.address).or_insert_with(|| symbol.name.clone());
+            if !symbol.function && symbol.size > 0 {
+                ranges.push((symbol.address, symbol.address + symbol.size, symbol.name.clone(), false));
+            }
+        }
+        Names { image, labels, ranges }
+    }
+
+    // name or name+offset, or the section and offset outside of them
+    pub(super) fn describe(&self, address: u64) -> Option<String> {
+        if let Some(name) = self.labels.get(&address) {
+            return Some(name.clone());
+        }
+        if let Some((start, _, name, _)) = self.ranges.iter().find(|(start, end, ..)| address >= *start && address < *end) {
+            return Some(format!("{}+0x{:x}", name, address - start));
+        }
+        let section = self.image.section_at(address)?;
+        Some(format!("{}+0x{:x}", section.name, address - section.address))
+    }
+
+    // The addresses a name on the command line stands for. References to a function are to its start, while
+    // references from it are from all of its instructions.
+    fn resolve(&self, name: &str, whole_function: bool) -> Result<(u64, u64), String> {
+        if let Some((start, end, _, function)) = self.ranges.iter().find(|x| x.2 == name) {
+            return Ok(if *function && !whole_function { (*start, start + 1) } else { (*start, *end) });
+        }
+        if let Some((address, _)) = self.labels.iter().find(|x| x.1 == name) {
+            return Ok((*address, address + 1));
+        }
+        let address = parse_address(name).map_err(|_| format!("No symbol or address {}", name))?;
+        Ok((address, address + 1))
+    }
+}
@@ -0,0 +1,1395 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::analysis::{self, BlockContents, BlockId, Condition, EdgeKind, Encoding, FoundString, LoopKind, Node};
+use crate::ir::{self, BinaryOp, CastOp, CompareOp, Destination, Expr, Parameter, Stmt, Storage, Type, UnaryOp, Var};
+
+use super::debug::{self, DebugVariables};
+use super::types::{self, decompose, Ty, Types};
+use super::{Decompiler, GlobalNames, LocalNames, Lowered};
+
+// Precedence of C operators, higher binds tighter
+const PRIMARY: u8 = 16;
+const UNARY: u8 = 15;
+const MULTIPLICATIVE: u8 = 13;
+const ADDITIVE: u8 = 12;
+const SHIFT: u8 = 11;
+const RELATIONAL: u8 = 10;
+const EQUALITY: u8 = 9;
+const BIT_AND: u8 = 8;
+const BIT_XOR: u8 = 7;
+const BIT_OR: u8 = 6;
+const LOGICAL_AND: u8 = 5;
+const LOGICAL_OR: u8 = 4;
+const CONDITIONAL: u8 = 3;
+
+// How the operation using a value interprets it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sign {
+    Signed,
+    Unsigned,
+    Any,
+}
+
+// Where an expression is printed: how it's interpreted, whether its bits matter more than its value, and whether
+// a constant there may be an address
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    sign: Sign,
+    bitwise: bool,
+    address: bool,
+}
+
+impl Context {
+    const VALUE: Context = Context { sign: Sign::Any, bitwise: false, address: true };
+
+    fn number(sign: Sign) -> Context {
+        Context { sign, bitwise: false, address: false }
+    }
+
+    fn bits() -> Context {
+        Context { sign: Sign::Any, bitwise: true, address: false }
+    }
+}
+
+// A stack slot the function uses as a variable: scalars where it's read or written, arrays where its address is
+// taken or it's indexed, with the bytes up to the next slot
+#[derive(Debug, Clone)]
+struct Local {
+    name: String,
+    bits: u16,
+    array: Option<u64>,
+    ty: Ty,
+}
+
+struct Contents<'a>(&'a ir::Function);
+
+impl BlockContents for Contents<'_> {
+    fn is_condition(&self, block: BlockId) -> bool {
+        let statements = &self.0.blocks[block].statements;
+        statements.iter().all(|x| matches!(x.1, Stmt::Jump(_) | Stmt::Branch { .. } | Stmt::Return))
+    }
+
+    fn is_step(&self, block: BlockId) -> bool {
+        let statements = &self.0.blocks[block].statements;
+        statements.iter().all(|x| matches!(x.1, Stmt::Assign { .. } | Stmt::Store { .. } | Stmt::Jump(Destination::Direct(_))))
+    }
+}
+
+struct Printer<'a> {
+    decompiler: &'a Decompiler<'a>,
+    function: &'a ir::Function,
+    lowered: &'a Lowered,
+    stack_pointer: Option<Var>,
+    pointer_bits: u16,
+    names: BTreeMap<Var, String>,
+    types: Types,
+    // What the debug information says of the parameters and the stack
+    debug: DebugVariables,
+    unsigned: BTreeSet<Var>,
+    locals: BTreeMap<i64, Local>,
+    labels: BTreeSet<BlockId>,
+    // The size of the value returned
+    return_bits: Option<u16>,
+    // The registers read before being assigned
+    entry_vars: BTreeSet<Var>,
+    // The variables printed, which are the ones declared
+    printed: RefCell<BTreeSet<Var>>,
+    out: String,
+}
+
+/// The function as C: its signature, the declarations of its variables and its structured statements
+pub(super) fn print(decompiler: &Decompiler, name: &str, lowered: &Lowered) -> String {
+    let function = &lowered.function;
+    let structure = analysis::structure(function.blocks.len(), function.entry, function.exit, &function.edges, &Contents(function));
+    let mut printer = Printer {
+        decompiler,
+        function,
+        lowered,
+        stack_pointer: lowered.convention.as_ref().map(|x| Var { version: 0, ..x.stack_pointer }),
+        pointer_bits: decompiler.image.target.instruction_set.metadata().pointer_size as u16 * 8,
+        names: BTreeMap::new(),
+        types: types::infer(decompiler, lowered),
+        debug: debug::variables(decompiler, lowered, function.blocks[function.entry].start),
+        unsigned: BTreeSet::new(),
+        locals: BTreeMap::new(),
+        labels: structure.labels.clone(),
+        return_bits: None,
+        entry_vars: BTreeSet::new(),
+        printed: RefCell::new(BTreeSet::new()),
+        out: String::new(),
+    };
+    for (_, stmt) in function.blocks.iter().flat_map(|x| x.statements.iter()) {
+        stmt.for_each_use(&mut |x| {
+            if x.version == 0 && matches!(x.storage, Storage::Register(_)) {
+                printer.entry_vars.insert(*x);
+            }
+        });
+    }
+    printer.declared_types();
+    printer.name_variables();
+    let unsigned = printer.types.vars.iter().filter(|x| x.1.integer(printer.pointer_bits).is_some_and(|x| !x.1));
+    printer.unsigned = unsigned.map(|x| *x.0).collect();
+    printer.find_locals();
+
+    let parameters = printer.parameters();
+    printer.return_bits = printer.return_bits();
+    let returns = lowered.returned.map_or(Ty::Void, |x| printer.var_type(&x));
+
+    // Falling off the end returns from a void function
+    let root = match &structure.root {
+        Node::Sequence(nodes) if printer.lowered.returned.is_none() && nodes.last() == Some(&Node::Return) => {
+            Node::Sequence(nodes[..nodes.len() - 1].to_vec())
+        }
+        Node::Return if printer.lowered.returned.is_none() => Node::Sequence(Vec::new()),
+        root => root.clone(),
+    };
+    // Gotos to returns are printed as returns, which their labels aren't needed for
+    let mut targets = BTreeSet::new();
+    gotos(&root, &mut targets);
+    printer.labels = targets.into_iter().filter(|x| !printer.returns(*x)).collect();
+    printer.node(&root, 1);
+    let body = std::mem::take(&mut printer.out);
+    for (index, layout) in printer.types.structs.iter().enumerate() {
+        printer.out.push_str(&format!("{} {{\n", Ty::Struct(index)));
+        let mut end = 0;
+        for (offset, ty) in layout.fields.iter() {
+            if *offset > end {
+                printer.out.push_str(&format!("    char gap_{:x}[{}];\n", end, offset - end));
+            }
+            printer.out.push_str(&format!("    {};\n", ty.declare(&format!("field_{:x}", offset))));
+            end = offset + printer.types.size(ty).unwrap_or(0);
+        }
+        if layout.size > end {
+            printer.out.push_str(&format!("    char gap_{:x}[{}];\n", end, layout.size - end));
+        }
+        printer.out.push_str("};\n\n");
+    }
+    let signature = returns.declare(&format!("{}({})", sanitize(name), parameters));
+    printer.out.push_str(&format!("{}\n{{\n", signature));
+    printer.declarations();
+    printer.out.push_str(&body);
+    printer.out.push_str("}\n");
+    printer.out
+}
+
+impl<'a> Printer<'a> {
+    fn statements(&self) -> impl Iterator<Item = &'a Stmt> {
+        self.function.blocks.iter().flat_map(|x| x.statements.iter().map(|x| &x.1))
+    }
+
+    // The parameters, registers as the function reads them, which may be narrower than the registers
+    fn parameter_vars(&self) -> Vec<Parameter> {
+        let parameters = self.lowered.prototype.iter().flat_map(|x| x.parameters.iter().copied());
+        parameters
+            .map(|parameter| match parameter {
+                Parameter::Register(var) => {
+                    let read = self.entry_vars.iter().find(|x| x.storage == var.storage);
+                    Parameter::Register(read.copied().unwrap_or(var))
+                }
+                parameter => parameter,
+            })
+            .collect()
+    }
+
+    // The types the debug information gives the parameters, the stack slots and what is returned, over those inferred
+    fn declared_types(&mut self) {
+        for parameter in self.parameter_vars() {
+            let declared = match parameter {
+                Parameter::Register(var) => self.debug.registers.get(&var.storage).and_then(|x| x.ty.clone()).map(|x| (var, x)),
+                Parameter::Stack { .. } => None,
+            };
+            if let Some((var, ty)) = declared {
+                self.types.vars.insert(var, ty);
+            }
+        }
+        for (offset, variable) in self.debug.stack.iter() {
+            if let Some(ty) = &variable.ty {
+                self.types.locals.insert(*offset, ty.clone());
+            }
+        }
+        if let (Some(var), Some(ty)) = (self.lowered.returned, &self.debug.returns) {
+            self.types.vars.insert(var, ty.clone());
+        }
+    }
+
+    // Parameters and the registers the function reads on entry keep their own names, or those the debug information
+    // gives parameters, the rest are named after their register and version or numbered in order of definition
+    fn name_variables(&mut self) {
+        let numbered = self.decompiler.naming.locals == LocalNames::Numbered;
+        for (i, parameter) in self.parameter_vars().iter().enumerate() {
+            if let Parameter::Register(var) = parameter {
+                let name = match self.debug.registers.get(&var.storage) {
+                    Some(variable) => variable.name.clone(),
+                    None if numbered => format!("a{}", i + 1),
+                    None => var.to_string(),
+                };
+                self.names.insert(*var, name);
+            }
+        }
+        let mut count = 0;
+        for stmt in self.statements() {
+            let mut vars: Vec<Var> = stmt.definitions().into_iter().copied().collect();
+            stmt.for_each_use(&mut |x| vars.push(*x));
+            // The variable returned may be read by nothing but the return
+            vars.extend(self.lowered.returned);
+            for var in vars {
+                if self.names.contains_key(&var) {
+                    continue;
+                }
+                let entry = var.version == 0 && matches!(var.storage, Storage::Register(_));
+                let name = if numbered && !entry {
+                    count += 1;
+                    format!("v{}", count)
+                } else {
+                    var.to_string()
+                };
+                self.names.insert(var, name);
+            }
+        }
+    }
+
+    // The offset from the stack pointer at entry an address has
+    fn stack_offset(&self, expr: &Expr) -> Option<i64> {
+        let stack_pointer = self.stack_pointer?;
+        match expr {
+            Expr::Var(var) if *var == stack_pointer => Some(0),
+            Expr::Binary(op @ (BinaryOp::Add | BinaryOp::Sub), left, right) if **left == Expr::Var(stack_pointer) => {
+                let offset = signed(right.constant()?, right.ty());
+                Some(if *op == BinaryOp::Add { offset } else { offset.wrapping_neg() })
+            }
+            _ => None,
+        }
+    }
+
+    fn find_locals(&mut self) {
+        // Accesses by offset, with their size or None where the address is taken
+        let mut accesses: Vec<(i64, Option<u16>)> = Vec::new();
+        for stmt in self.statements() {
+            if let Stmt::Store { address, value, .. } = stmt {
+                match self.stack_offset(address) {
+                    Some(offset) => accesses.push((offset, Some(value.ty().bits()))),
+                    None => self.address_accesses(address, &mut accesses),
+                }
+                self.stack_accesses(value, &mut accesses);
+                continue;
+            }
+            for expr in stmt.expressions() {
+                self.stack_accesses(expr, &mut accesses);
+            }
+        }
+
+        let mut scalars: BTreeMap<i64, u16> = BTreeMap::new();
+        // Those indexed are arrays however else they're accessed
+        let mut taken: BTreeSet<i64> = self.types.arrays.keys().copied().collect();
+        for (offset, bits) in accesses {
+            match bits {
+                Some(bits) => {
+                    let entry = scalars.entry(offset).or_default();
+                    *entry = (*entry).max(bits);
+                }
+                None => {
+                    taken.insert(offset);
+                }
+            }
+        }
+        let mut bounds: BTreeSet<i64> = scalars.keys().chain(taken.iter()).copied().collect();
+        bounds.extend(self.lowered.slots.iter().map(|x| x.offset));
+
+        let numbered = self.decompiler.naming.locals == LocalNames::Numbered;
+        let mut parameters: BTreeMap<i64, String> = BTreeMap::new();
+        for (i, parameter) in self.parameter_vars().iter().enumerate() {
+            if let Parameter::Stack { offset, .. } = parameter {
+                let name = if numbered { format!("a{}", i + 1) } else { format!("arg_{:x}", offset) };
+                parameters.insert(*offset, name);
+            }
+        }
+        let offsets: BTreeSet<i64> = scalars.keys().chain(taken.iter()).copied().collect();
+        // Where unoptimized code keeps its parameters, the slot is named after what the debug information calls the
+        // parameter as well
+        let registers: BTreeSet<&str> = self.debug.registers.values().map(|x| x.name.as_str()).collect();
+        let mut count = 0;
+        for offset in offsets {
+            let name = match (parameters.get(&offset), self.debug.stack.get(&offset)) {
+                (_, Some(variable)) if registers.contains(variable.name.as_str()) => format!("{}_local", variable.name),
+                (_, Some(variable)) => variable.name.clone(),
+                (Some(name), None) => name.clone(),
+                (None, None) if offset >= 0 => format!("arg_{:x}", offset),
+                (None, None) if numbered => {
+                    count += 1;
+                    format!("local{}", count)
+                }
+                (None, None) => format!("local_{:x}", offset.unsigned_abs()),
+            };
+            let ty = self.types.locals.get(&offset).cloned();
+            let local = match (scalars.get(&offset), self.types.arrays.get(&offset)) {
+                (Some(bits), None) => {
+                    let ty = ty.filter(|x| self.types.size(x) == Some(*bits as u64 / 8));
+                    let ty = ty.unwrap_or(Ty::Int { bits: *bits, signed: true });
+                    Local { name, bits: *bits, array: None, ty }
+                }
+                (_, element) => {
+                    let end = bounds.range(offset + 1..).next().copied().unwrap_or(if offset < 0 { 0 } else { offset + 1 });
+                    let end = if offset < 0 { end.min(0).max(offset + 1) } else { end };
+                    // Of the elements indexed, or else bytes
+                    let ty = element.cloned().or(ty).filter(|x| *x != Ty::Bool && self.types.size(x).is_some_and(|x| x > 0));
+                    let ty = ty.unwrap_or(Ty::Char);
+                    let size = self.types.size(&ty).unwrap_or(1);
+                    let count = ((end - offset) as u64).div_ceil(size);
+                    Local { name, bits: size as u16 * 8, array: Some(count), ty }
+                }
+            };
+            self.locals.insert(offset, local);
+        }
+    }
+
+    // The stack slots an expression reads, and those whose addresses it takes
+    fn stack_accesses(&self, expr: &Expr, accesses: &mut Vec<(i64, Option<u16>)>) {
+        if let Some(offset) = self.stack_offset(expr) {
+            accesses.push((offset, None));
+            return;
+        }
+        if let Expr::Load { address, ty, .. } = expr {
+            if let Some(offset) = self.stack_offset(address) {
+                accesses.push((offset, Some(ty.bits())));
+                return;
+            }
+            self.address_accesses(address, accesses);
+            return;
+        }
+        for child in children(expr) {
+            self.stack_accesses(child, accesses);
+        }
+    }
+
+    // The stack slots an address reads, and takes the address of: the whole array where it indexes one
+    fn address_accesses(&self, address: &Expr, accesses: &mut Vec<(i64, Option<u16>)>) {
+        match decompose(address) {
+            (base, Some((index, _)), offset) if Some(base) == self.stack_pointer.map(Expr::Var).as_ref() => {
+                accesses.push((offset, None));
+                self.stack_accesses(index, accesses);
+            }
+            _ => self.stack_accesses(address, accesses),
+        }
+    }
+
+    fn parameters(&self) -> String {
+        let parameters: Vec<String> = self
+            .parameter_vars()
+            .iter()
+            .map(|parameter| match parameter {
+                Parameter::Register(var) => self.var_type(var).declare(&self.var_name(var)),
+                Parameter::Stack { offset, bits } => match self.locals.get(offset) {
+                    Some(local) => local.ty.declare(&local.name),
+                    None => format!("{} arg_{:x}", type_name(Type::Int(*bits), false), offset),
+                },
+            })
+            .collect();
+        match parameters.is_empty() {
+            true => "void".to_string(),
+            false => parameters.join(", "),
+        }
+    }
+
+    // The size of what the function returns: that of the values it returns, before they were extended
+    fn return_bits(&self) -> Option<u16> {
+        let returned = self.lowered.returned?;
+        let mut bits = None;
+        for stmt in self.statements() {
+            let value_bits = match stmt {
+                Stmt::Assign { var, value } if *var == returned => natural_bits(value),
+                // The result of a library function is as wide as its signature has it
+                Stmt::Call { target, results, .. } if results.contains(&returned) => {
+                    let signature = self.decompiler.call_target(target).and_then(|x| x.signature);
+                    let result = signature.and_then(|x| Ty::parse(x.returns, self.pointer_bits).integer(self.pointer_bits));
+                    result.map_or(returned.ty.bits(), |x| x.0.max(32))
+                }
+                Stmt::Syscall { results, .. } if results.contains(&returned) => returned.ty.bits(),
+                _ => continue,
+            };
+            bits = Some(bits.map_or(value_bits, |x: u16| x.max(value_bits)));
+        }
+        Some(bits.unwrap_or(returned.ty.bits()))
+    }
+
+    // The name of a variable, which gets declared once printed
+    fn name(&self, var: &Var) -> String {
+        self.printed.borrow_mut().insert(*var);
+        self.var_name(var)
+    }
+
+    fn var_name(&self, var: &Var) -> String {
+        self.names.get(var).cloned().unwrap_or_else(|| var.to_string())
+    }
+
+    // The type a variable is declared with. The one returned is as wide as the values returned.
+    fn var_type(&self, var: &Var) -> Ty {
+        let ty = self.types.vars.get(var).cloned().unwrap_or(Ty::Int { bits: var.ty.bits(), signed: true });
+        match (self.return_bits, &ty) {
+            (Some(bits), Ty::Int { signed, .. }) if Some(*var) == self.lowered.returned => Ty::Int { bits, signed: *signed },
+            _ => ty,
+        }
+    }
+
+    fn declarations(&mut self) {
+        let parameters: BTreeSet<Var> = self
+            .parameter_vars()
+            .iter()
+            .filter_map(|x| match x {
+                Parameter::Register(var) => Some(*var),
+                Parameter::Stack { .. } => None,
+            })
+            .collect();
+        let mut declared = BTreeSet::new();
+        let mut lines = Vec::new();
+        for stmt in self.statements() {
+            for var in stmt.definitions() {
+                if !parameters.contains(var) && self.printed.borrow().contains(var) && declared.insert(*var) {
+                    lines.push(format!("{};", self.var_type(var).declare(&self.var_name(var))));
+                }
+            }
+        }
+        let stack_parameters: BTreeSet<i64> = self
+            .parameter_vars()
+            .iter()
+            .filter_map(|x| match x {
+                Parameter::Stack { offset, .. } => Some(*offset),
+                Parameter::Register(_) => None,
+            })
+            .collect();
+        for (_, local) in self.locals.iter().filter(|x| !stack_parameters.contains(x.0)) {
+            match local.array {
+                Some(count) => lines.push(format!("{};", local.ty.declare(&format!("{}[{}]", local.name, count)))),
+                None => lines.push(format!("{};", local.ty.declare(&local.name))),
+            }
+        }
+        for line in lines.iter() {
+            self.line(1, line);
+        }
+        if !lines.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    fn line(&mut self, depth: usize, text: &str) {
+        self.out.push_str(&"    ".repeat(depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn label(&mut self, block: BlockId, depth: usize) {
+        if self.labels.contains(&block) {
+            let text = format!("label_{:x}:", self.function.blocks[block].start);
+            self.line(depth.saturating_sub(1), &text);
+        }
+    }
+
+    fn node(&mut self, node: &Node, depth: usize) {
+        match node {
+            Node::Block(block) => {
+                self.label(*block, depth);
+                // The jump of a switch is the switch that follows
+                let switch = !self.case_values(*block, None).is_empty();
+                self.block(*block, depth, switch, false);
+            }
+            Node::Sequence(nodes) => {
+                let mut i = 0;
+                while i < nodes.len() {
+                    // The value returned is computed by the last statement of the block before the return, or before
+                    // blocks doing nothing but going there
+                    if let Node::Block(block) = &nodes[i] {
+                        let rest = &nodes[i + 1..];
+                        let empty = rest.iter().take_while(|x| matches!(x, Node::Block(x) if self.is_empty(*x))).count();
+                        if rest.get(empty) == Some(&Node::Return) && self.returned_by(*block).is_some() {
+                            self.label(*block, depth);
+                            self.block(*block, depth, false, true);
+                            i += empty + 2;
+                            continue;
+                        }
+                    }
+                    self.node(&nodes[i], depth);
+                    i += 1;
+                }
+            }
+            Node::If { condition, then, otherwise } => {
+                let (condition, then, otherwise) = match otherwise {
+                    Some(otherwise) if then.is_empty() => (condition.clone().negate(), &**otherwise, None),
+                    _ => (condition.clone(), &**then, otherwise.as_deref()),
+                };
+                let text = format!("if ({}) {{", self.condition(&condition).0);
+                self.line(depth, &text);
+                self.node(then, depth + 1);
+                let mut otherwise = otherwise;
+                while let Some(node) = otherwise {
+                    match node {
+                        Node::If { condition, then, otherwise: next } if !then.is_empty() => {
+                            let text = format!("}} else if ({}) {{", self.condition(condition).0);
+                            self.line(depth, &text);
+                            self.node(then, depth + 1);
+                            otherwise = next.as_deref();
+                        }
+                        node => {
+                            self.line(depth, "} else {");
+                            self.node(node, depth + 1);
+                            otherwise = None;
+                        }
+                    }
+                }
+                self.line(depth, "}");
+            }
+            Node::Loop { header, kind: LoopKind::Endless, body } if exit_early(body).is_some() => {
+                let body = Box::new(exit_early(body).unwrap());
+                self.node(&Node::Loop { header: *header, kind: LoopKind::Endless, body }, depth);
+            }
+            // A loop doing everything in its step is a while loop doing it in its body
+            Node::Loop { header, kind: LoopKind::For { condition, step }, body } if self.is_empty_node(body) => {
+                let body = Box::new(Node::Block(*step));
+                self.node(&Node::Loop { header: *header, kind: LoopKind::While(condition.clone()), body }, depth);
+            }
+            Node::Loop { header, kind, body } => {
+                let text = match kind {
+                    LoopKind::While(condition) => format!("while ({}) {{", self.condition(condition).0),
+                    LoopKind::For { condition, step } => {
+                        let steps: Vec<String> = self.function.blocks[*step]
+                            .statements
+                            .iter()
+                            .filter_map(|x| self.statement(&x.1))
+                            .collect();
+                        format!("for (; {}; {}) {{", self.condition(condition).0, steps.join(", "))
+                    }
+                    LoopKind::DoWhile(_) => "do {".to_string(),
+                    LoopKind::Endless => "while (true) {".to_string(),
+                };
+                if let LoopKind::While(_) | LoopKind::For { .. } = kind {
+                    self.label(*header, depth);
+                }
+                self.line(depth, &text);
+                self.node(body, depth + 1);
+                match kind {
+                    LoopKind::DoWhile(condition) => {
+                        let text = format!("}} while ({});", self.condition(condition).0);
+                        self.line(depth, &text);
+                    }
+                    _ => self.line(depth, "}"),
+                }
+            }
+            Node::Switch { block, cases } => {
+                let target = match self.function.blocks[*block].statements.last() {
+                    Some((_, Stmt::Jump(Destination::Indirect(target)))) => Some(target),
+                    _ => None,
+                };
+                let value = target.and_then(|x| self.switch_value(*block, x));
+                let text = match (&value, target) {
+                    (Some(value), _) => self.expr(value, Context::VALUE).0,
+                    (None, Some(target)) => self.expr(target, Context::VALUE).0,
+                    (None, None) => "0".to_string(),
+                };
+                self.line(depth, &format!("switch ({}) {{", text));
+                let character = value.as_ref().and_then(|x| self.expr_type(x)) == Some(Ty::Char);
+                for case in cases.iter() {
+                    let values = self.case_values(*block, Some(case.target));
+                    if values.is_empty() || value.is_none() {
+                        self.line(depth, &format!("case 0x{:x}:", self.function.blocks[case.target].start));
+                    }
+                    for x in values.iter().filter(|_| value.is_some()) {
+                        let text = match character.then(|| character_literal(*x as u64)).flatten() {
+                            Some(text) => text,
+                            None => x.to_string(),
+                        };
+                        self.line(depth, &format!("case {}:", text));
+                    }
+                    self.node(&case.body, depth + 1);
+                }
+                self.line(depth, "}");
+            }
+            Node::Break => self.line(depth, "break;"),
+            Node::Continue => self.line(depth, "continue;"),
+            Node::Return => {
+                let text = match self.lowered.returned {
+                    Some(var) => format!("return {};", self.returned_value(&Expr::var(var))),
+                    None => "return;".to_string(),
+                };
+                self.line(depth, &text);
+            }
+            // Going to a return is returning
+            Node::Goto(block) if self.returns(*block) => {
+                self.block(*block, depth, false, true);
+                if self.returned_by(*block).is_none() {
+                    self.node(&Node::Return, depth);
+                }
+            }
+            Node::Goto(block) => {
+                let text = format!("goto label_{:x};", self.function.blocks[*block].start);
+                self.line(depth, &text);
+            }
+        }
+    }
+
+    // Whether a block prints nothing: it only jumps or branches, and isn't gone to
+    // The values of the cases of the jump table `block` ends with, of those going to `target` if given
+    fn case_values(&self, block: BlockId, target: Option<BlockId>) -> Vec<i64> {
+        let edges = self.function.edges.iter().filter(|x| x.from == block && target.is_none_or(|target| x.to == target));
+        edges
+            .filter_map(|x| match x.kind {
+                EdgeKind::Case(value) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // The value a switch through a jump table is on: the index of the entry plus the value of the first case. The
+    // target may be computed by the statements of the block before the jump, which still hold what they assigned.
+    fn switch_value(&self, block: BlockId, target: &Expr) -> Option<Expr> {
+        let first = self.case_values(block, None).into_iter().min()?;
+        let mut held: BTreeMap<Var, Expr> = BTreeMap::new();
+        for (_, stmt) in self.function.blocks[block].statements.iter() {
+            match stmt {
+                Stmt::Assign { var, value } => {
+                    held.retain(|x, held| {
+                        let mut reads = false;
+                        held.for_each_var(&mut |y| reads |= y == var);
+                        x != var && !reads
+                    });
+                    held.insert(*var, value.clone());
+                }
+                Stmt::Store { .. } => held.retain(|_, x| !x.has_load()),
+                Stmt::Jump(_) => {}
+                _ => held.clear(),
+            }
+        }
+        let mut target = target.clone();
+        for _ in 0..4 {
+            target.substitute(&mut |x| held.get(x).cloned());
+        }
+        let target = target.simplify();
+        let index = analysis::table_index(&target)?;
+        let first = Expr::int(first as u64, index.ty().bits());
+        Some(Expr::binary(BinaryOp::Add, index.clone(), first).simplify())
+    }
+
+    fn is_empty(&self, block: BlockId) -> bool {
+        !self.labels.contains(&block) && self.function.blocks[block].statements.iter().all(|x| is_terminator(&x.1))
+    }
+
+    fn is_empty_node(&self, node: &Node) -> bool {
+        match node {
+            Node::Sequence(nodes) => nodes.iter().all(|x| self.is_empty_node(x)),
+            Node::Block(block) => self.is_empty(*block),
+            _ => false,
+        }
+    }
+
+    // Whether a block does nothing but return, besides computing the value returned
+    fn returns(&self, block: BlockId) -> bool {
+        let returned_by = self.returned_by(block);
+        let statements = &self.function.blocks[block].statements;
+        if !statements.iter().enumerate().all(|(i, x)| is_terminator(&x.1) || Some(i) == returned_by) {
+            return false;
+        }
+        let mut current = block;
+        for _ in 0..self.function.blocks.len() {
+            let mut successors = self.function.successors(current);
+            successors.dedup();
+            match successors.as_slice() {
+                [next] if *next == self.function.exit => return true,
+                [next] if *next != block && self.function.blocks[*next].statements.iter().all(|x| is_terminator(&x.1)) => {
+                    current = *next;
+                }
+                _ => return false,
+            }
+        }
+        false
+    }
+
+    // The index of the statement assigning what the function returns, if it's the last statement of the block
+    fn returned_by(&self, block: BlockId) -> Option<usize> {
+        let returned = self.lowered.returned?;
+        let statements = &self.function.blocks[block].statements;
+        let index = statements.iter().rposition(|x| !is_terminator(&x.1))?;
+        match &statements[index].1 {
+            Stmt::Assign { var, .. } if *var == returned => Some(index),
+            Stmt::Call { results, .. } if results == &[returned] => Some(index),
+            _ => None,
+        }
+    }
+
+    // The statements of a block. A switch has its jump printed as the switch, and the statement computing the value
+    // returned can be printed as the return.
+    fn block(&mut self, block: BlockId, depth: usize, switch: bool, returns: bool) {
+        let returned_by = if returns { self.returned_by(block) } else { None };
+        for (index, (_, stmt)) in self.function.blocks[block].statements.iter().enumerate() {
+            if Some(index) == returned_by {
+                let value = match stmt {
+                    Stmt::Assign { value, .. } => self.returned_value(value),
+                    Stmt::Call { target, arguments, .. } => self.call(target, arguments),
+                    _ => unreachable!(),
+                };
+                self.line(depth, &format!("return {};", value));
+                continue;
+            }
+            if switch && matches!(stmt, Stmt::Jump(_)) {
+                continue;
+            }
+            if let Some(text) = self.statement(stmt) {
+                self.line(depth, &format!("{};", text));
+            }
+        }
+    }
+
+    // A statement as C, without the semicolon. Jumps, branches and returns are left to the structure around.
+    fn statement(&self, stmt: &Stmt) -> Option<String> {
+        let text = match stmt {
+            Stmt::Assign { var, value } if Some(*var) == self.lowered.returned => {
+                self.assignment(&self.name(var), &Expr::var(*var), &self.return_type_value(value))
+            }
+            Stmt::Assign { var, value } => self.assignment(&self.name(var), &Expr::var(*var), value),
+            Stmt::Phi { var, sources } => {
+                let values: Vec<String> = sources.iter().map(|x| self.expr(&x.1, Context::VALUE).0).collect();
+                format!("{} = __phi({})", self.name(var), values.join(", "))
+            }
+            Stmt::Store { address, value, endianness } => {
+                let target = Expr::load(address.clone(), value.ty(), *endianness);
+                self.assignment(&self.lvalue(address, value.ty(), Sign::Any).0, &target, value)
+            }
+            Stmt::Jump(Destination::Indirect(target)) => format!("goto *{}", self.operand(target, Context::VALUE, UNARY)),
+            Stmt::Jump(_) | Stmt::Branch { .. } | Stmt::Return => return None,
+            Stmt::Call { target, arguments, results } => {
+                let call = self.call(target, arguments);
+                match self.lowered.result(results) {
+                    Some(var) => format!("{} = {}", self.name(&var), call),
+                    None => call,
+                }
+            }
+            Stmt::Syscall { arguments, results } => {
+                let call = format!("syscall({})", self.arguments(arguments));
+                match self.lowered.result(results) {
+                    Some(var) => format!("{} = {}", self.name(&var), call),
+                    None => call,
+                }
+            }
+            Stmt::Trap => "__builtin_trap()".to_string(),
+            Stmt::Intrinsic { name, args } => format!("__{}({})", sanitize(name), self.arguments(args)),
+        };
+        Some(text)
+    }
+
+    // An assignment, as a compound one or an increment where the value combines the target with something
+    fn assignment(&self, name: &str, target: &Expr, value: &Expr) -> String {
+        if let Expr::Binary(op, left, right) = value {
+            if **left == *target && target.ty() != Type::Bool {
+                let amount = signed(right.constant().unwrap_or(0), right.ty());
+                match op {
+                    BinaryOp::Add | BinaryOp::Sub if right.constant().is_some() && amount.unsigned_abs() == 1 => {
+                        let increment = (*op == BinaryOp::Add) == (amount == 1);
+                        return format!("{}{}", name, if increment { "++" } else { "--" });
+                    }
+                    BinaryOp::Add if right.constant().is_some() && amount < 0 => {
+                        return format!("{} -= {}", name, self.expr(&Expr::int(amount.unsigned_abs(), right.ty().bits()), Context::VALUE).0);
+                    }
+                    _ => {}
+                }
+                let symbol = match op {
+                    BinaryOp::Add => Some("+"),
+                    BinaryOp::Sub => Some("-"),
+                    BinaryOp::Mul => Some("*"),
+                    BinaryOp::And => Some("&"),
+                    BinaryOp::Or => Some("|"),
+                    BinaryOp::Xor => Some("^"),
+                    BinaryOp::Shl => Some("<<"),
+                    _ => None,
+                };
+                if let Some(symbol) = symbol {
+                    let bitwise = matches!(op, BinaryOp::And | BinaryOp::Or | BinaryOp::Xor);
+                    let context = Context { bitwise, ..Context::VALUE };
+                    return format!("{} {}= {}", name, symbol, self.expr(right, context).0);
+                }
+            }
+        }
+        format!("{} = {}", name, self.expr(value, Context::VALUE).0)
+    }
+
+    fn call(&self, target: &Destination, arguments: &[Expr]) -> String {
+        let name = match (self.decompiler.call_target(target), target) {
+            (Some(callee), _) => sanitize(&callee.name),
+            (None, Destination::Direct(address)) => format!("sub_{:x}", address),
+            (None, Destination::Indirect(target)) => format!("(*{})", self.operand(target, Context::VALUE, UNARY)),
+        };
+        let parameters = self.decompiler.call_target(target).and_then(|x| x.signature).map_or(&[][..], |x| x.parameters);
+        let arguments: Vec<String> = arguments
+            .iter()
+            .enumerate()
+            .map(|(i, x)| match (parameters.get(i).map(|x| Ty::parse(x, self.pointer_bits)), unextended(x)) {
+                (Some(Ty::Pointer { pointee, constant: true }), Expr::Const { value, .. }) if *pointee == Ty::Char => self.c_string(*value),
+                _ => None,
+            }
+            .unwrap_or_else(|| self.expr(unextended(x), Context::VALUE).0))
+            .collect();
+        format!("{}({})", name, arguments.join(", "))
+    }
+
+    fn arguments(&self, arguments: &[Expr]) -> String {
+        let arguments: Vec<String> = arguments.iter().map(|x| self.expr(unextended(x), Context::VALUE).0).collect();
+        arguments.join(", ")
+    }
+
+    // The literal of the null terminated string a `const char *` argument points to, which may be too short to have
+    // been found with the strings
+    fn c_string(&self, address: u64) -> Option<String> {
+        if !self.is_data(address) {
+            return None;
+        }
+        let data = self.decompiler.image.data_at(address)?;
+        let value = std::str::from_utf8(&data[..data.iter().position(|x| *x == 0)?]).ok()?;
+        if value.chars().any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r')) {
+            return None;
+        }
+        Some(quote("", value))
+    }
+
+    // A value returned, as wide as the return type
+    fn returned_value(&self, value: &Expr) -> String {
+        // Truncated to an unsigned return type as such
+        let unsigned = self.lowered.returned.and_then(|x| self.var_type(&x).integer(self.pointer_bits)).is_some_and(|x| !x.1);
+        let context = Context { sign: if unsigned { Sign::Unsigned } else { Sign::Any }, ..Context::VALUE };
+        self.expr(&self.return_type_value(value), context).0
+    }
+
+    // A value of the variable returned, without the extension to the width of the register
+    fn return_type_value(&self, value: &Expr) -> Expr {
+        let bits = self.return_bits.unwrap_or(value.ty().bits());
+        match unextended(value) {
+            Expr::Const { value, ty } if *ty != Type::Bool && bits < ty.bits() => Expr::int(*value, bits),
+            value => value.clone(),
+        }
+    }
+
+    fn condition(&self, condition: &Condition) -> (String, u8) {
+        match condition {
+            Condition::Branch(block) => self.expr(&self.branch_condition(*block), Context::VALUE),
+            Condition::Not(inner) => match &**inner {
+                Condition::Branch(block) => self.expr(&negate(&self.branch_condition(*block)), Context::VALUE),
+                inner => (format!("!{}", self.wrap(self.condition(inner), UNARY)), UNARY),
+            },
+            Condition::And(left, right) => {
+                let (left, right) = (self.wrap(self.condition(left), LOGICAL_AND), self.wrap(self.condition(right), LOGICAL_AND + 1));
+                (format!("{} && {}", left, right), LOGICAL_AND)
+            }
+            Condition::Or(left, right) => {
+                let (left, right) = (self.wrap(self.condition(left), LOGICAL_OR), self.wrap(self.condition(right), LOGICAL_OR + 1));
+                (format!("{} || {}", left, right), LOGICAL_OR)
+            }
+        }
+    }
+
+    // What the branch ending a block tests
+    fn branch_condition(&self, block: BlockId) -> Expr {
+        match self.function.blocks[block].statements.last() {
+            Some((_, Stmt::Branch { condition, .. })) => condition.clone(),
+            _ => Expr::bool(true),
+        }
+    }
+
+    fn wrap(&self, (text, precedence): (String, u8), min: u8) -> String {
+        if precedence < min {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn operand(&self, expr: &Expr, context: Context, min: u8) -> String {
+        self.wrap(self.expr(expr, context), min)
+    }
+
+    // An expression as C, with the precedence of its operator
+    fn expr(&self, expr: &Expr, context: Context) -> (String, u8) {
+        // Arrays on the stack stand for their address
+        if let Some(local) = self.stack_offset(expr).and_then(|x| self.locals.get(&x)) {
+            return match local.array {
+                Some(_) => (local.name.clone(), PRIMARY),
+                None => (format!("&{}", local.name), UNARY),
+            };
+        }
+        match expr {
+            Expr::Const { value, ty: Type::Bool } => ((*value != 0).to_string(), PRIMARY),
+            Expr::Const { value, ty } => {
+                if context.address && ty.bits() == self.pointer_bits {
+                    if let Some(res) = self.address(*value) {
+                        return res;
+                    }
+                }
+                number(*value, *ty, context)
+            }
+            Expr::Var(var) => {
+                let name = self.name(var);
+                let unsigned = self.unsigned.contains(var);
+                let pointer = self.types.vars.get(var).is_some_and(|x| x.is_pointer());
+                match (context.sign, var.ty) {
+                    _ if pointer => (name, PRIMARY),
+                    (Sign::Unsigned, Type::Int(_)) if !unsigned => (format!("({}){}", type_name(var.ty, true), name), UNARY),
+                    (Sign::Signed, Type::Int(_)) if unsigned => (format!("({}){}", type_name(var.ty, false), name), UNARY),
+                    _ => (name, PRIMARY),
+                }
+            }
+            Expr::Load { address, ty, .. } => self.lvalue(address, *ty, context.sign),
+            Expr::Unary(UnaryOp::Neg, value) => {
+                let value = self.operand(value, Context::number(Sign::Signed), UNARY);
+                let value = if value.starts_with('-') { format!("({})", value) } else { value };
+                (format!("-{}", value), UNARY)
+            }
+            Expr::Unary(UnaryOp::Not, value) if value.ty() == Type::Bool => match &**value {
+                Expr::Compare(..) | Expr::Unary(..) => self.expr(&negate(value), context),
+                value => (format!("!{}", self.operand(value, Context::VALUE, UNARY)), UNARY),
+            },
+            Expr::Unary(UnaryOp::Not, value) => (format!("~{}", self.operand(value, Context::bits(), UNARY)), UNARY),
+            Expr::Binary(op, left, right) => self.binary(*op, left, right, context),
+            Expr::Compare(op, left, right) => {
+                // Constants go on the right
+                let mirrored = left.constant().is_some() && right.constant().is_none();
+                let (left, right) = if mirrored { (right, left) } else { (left, right) };
+                let (symbol, sign) = compare_symbol(*op, mirrored);
+                let precedence = if matches!(op, CompareOp::Eq | CompareOp::Ne) { EQUALITY } else { RELATIONAL };
+                // Characters are compared with characters
+                let right = match (self.expr_type(left), right.constant().and_then(character_literal)) {
+                    (Some(Ty::Char), Some(text)) => text,
+                    _ => self.operand(right, Context::number(sign), precedence + 1),
+                };
+                let left = self.operand(left, Context::number(sign), precedence + 1);
+                (format!("{} {} {}", left, symbol, right), precedence)
+            }
+            Expr::Cast(CastOp::ZeroExtend, value, _) if value.ty() == Type::Bool => self.expr(value, context),
+            Expr::Cast(op, value, ty) => {
+                let (unsigned, sign) = match op {
+                    CastOp::ZeroExtend => (true, Sign::Unsigned),
+                    CastOp::SignExtend => (false, Sign::Signed),
+                    CastOp::Truncate => (context.sign == Sign::Unsigned, Sign::Any),
+                };
+                let value = self.operand(value, Context::number(sign), UNARY);
+                (format!("({}){}", type_name(*ty, unsigned), value), UNARY)
+            }
+            Expr::Select { condition, then, otherwise } => {
+                let condition = self.operand(condition, Context::VALUE, CONDITIONAL + 1);
+                let then = self.operand(then, context, CONDITIONAL + 1);
+                let otherwise = self.operand(otherwise, context, CONDITIONAL);
+                (format!("{} ? {} : {}", condition, then, otherwise), CONDITIONAL)
+            }
+            Expr::Intrinsic { name, args, .. } => (format!("__{}({})", sanitize(name), self.arguments(args)), PRIMARY),
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, left: &Expr, right: &Expr, context: Context) -> (String, u8) {
+        if let (BinaryOp::Add | BinaryOp::Sub, Some(Ty::Pointer { pointee, .. })) = (op, self.expr_type(left)) {
+            if let Some(res) = self.pointer_arithmetic(op, left, right, &pointee) {
+                return res;
+            }
+        }
+        let logical = left.ty() == Type::Bool;
+        let (symbol, precedence, sign) = match op {
+            BinaryOp::And if logical => ("&&", LOGICAL_AND, Sign::Any),
+            BinaryOp::Or if logical => ("||", LOGICAL_OR, Sign::Any),
+            BinaryOp::Xor if logical => ("!=", EQUALITY, Sign::Any),
+            BinaryOp::Add => {
+                // Adding a negative constant is a subtraction
+                if let Some(value) = right.constant().filter(|_| context.sign != Sign::Unsigned) {
+                    let amount = signed(value, right.ty());
+                    if amount < 0 && right.ty().bits() <= 64 {
+                        let right = Expr::int(amount.unsigned_abs(), right.ty().bits());
+                        return self.binary(BinaryOp::Sub, left, &right, context);
+                    }
+                }
+                ("+", ADDITIVE, context.sign)
+            }
+            BinaryOp::Sub => ("-", ADDITIVE, context.sign),
+            BinaryOp::Mul => ("*", MULTIPLICATIVE, context.sign),
+            BinaryOp::UDiv => ("/", MULTIPLICATIVE, Sign::Unsigned),
+            BinaryOp::SDiv => ("/", MULTIPLICATIVE, Sign::Signed),
+            BinaryOp::URem => ("%", MULTIPLICATIVE, Sign::Unsigned),
+            BinaryOp::SRem => ("%", MULTIPLICATIVE, Sign::Signed),
+            BinaryOp::And => ("&", BIT_AND, Sign::Any),
+            BinaryOp::Or => ("|", BIT_OR, Sign::Any),
+            BinaryOp::Xor => ("^", BIT_XOR, Sign::Any),
+            BinaryOp::Shl => ("<<", SHIFT, Sign::Any),
+            BinaryOp::LShr => (">>", SHIFT, Sign::Unsigned),
+            BinaryOp::AShr => (">>", SHIFT, Sign::Signed),
+            BinaryOp::RotL | BinaryOp::RotR => {
+                let name = if op == BinaryOp::RotL { "__rol" } else { "__ror" };
+                let left = self.expr(left, Context::bits()).0;
+                let right = self.expr(right, Context::number(Sign::Any)).0;
+                return (format!("{}({}, {})", name, left, right), PRIMARY);
+            }
+        };
+        let (left_context, right_context, left_min, right_min) = match precedence {
+            LOGICAL_AND | LOGICAL_OR => (Context::VALUE, Context::VALUE, precedence, precedence + 1),
+            EQUALITY => (Context::VALUE, Context::VALUE, precedence + 1, precedence + 1),
+            // Operators mixed with bitwise ones are parenthesized even where C wouldn't need it
+            BIT_AND | BIT_XOR | BIT_OR => {
+                let chained = matches!(left, Expr::Binary(x, ..) if *x == op);
+                (Context::bits(), Context::bits(), if chained { precedence } else { MULTIPLICATIVE }, MULTIPLICATIVE)
+            }
+            SHIFT => (Context::number(sign), Context::number(Sign::Any), MULTIPLICATIVE, MULTIPLICATIVE),
+            _ => {
+                let context = Context { sign, bitwise: false, address: context.address && precedence == ADDITIVE };
+                (context, Context { address: false, ..context }, precedence, precedence + 1)
+            }
+        };
+        let left = self.operand(left, left_context, left_min);
+        let right = self.operand(right, right_context, right_min);
+        (format!("{} {} {}", left, symbol, right), precedence)
+    }
+
+    // The value in memory at an address: a local or global variable when there's one there, otherwise through a
+    // pointer
+    fn lvalue(&self, address: &Expr, ty: Type, sign: Sign) -> (String, u8) {
+        if let Some(local) = self.stack_offset(address).and_then(|x| self.locals.get(&x)) {
+            if local.array.is_none() && local.bits == ty.bits() {
+                return (local.name.clone(), PRIMARY);
+            }
+        }
+        let (base, index, offset) = decompose(address);
+        if self.stack_pointer.is_some_and(|x| *base == Expr::Var(x)) {
+            if let Some(element) = self.local_element(index, offset, ty) {
+                return element;
+            }
+        }
+        if let Some(Ty::Pointer { pointee, .. }) = self.expr_type(base) {
+            if let Some(element) = self.types.element(&pointee, index.map(|x| x.1), offset, ty.bits()) {
+                let text = match (index, element.index) {
+                    (None, 0) if element.field.is_none() => return (format!("*{}", self.operand(base, Context::VALUE, UNARY)), UNARY),
+                    (None, 0) => format!("{}->", self.operand(base, Context::VALUE, PRIMARY)),
+                    (index, k) => format!("{}[{}].", self.operand(base, Context::VALUE, PRIMARY), self.index(index, k)),
+                };
+                return match element.field {
+                    Some(field) => (format!("{}field_{:x}", text, field), PRIMARY),
+                    None => (text.trim_end_matches('.').to_string(), PRIMARY),
+                };
+            }
+        }
+        if let Some(address) = address.constant().filter(|_| address.ty().bits() == self.pointer_bits) {
+            if self.is_data(address) {
+                match self.global(address) {
+                    Some((name, 0)) => return (name, PRIMARY),
+                    Some(_) => {}
+                    None => return (format!("g_{:x}", address), PRIMARY),
+                }
+            }
+        }
+        let pointer = self.operand(address, Context::VALUE, UNARY);
+        (format!("*({} *){}", type_name(ty, sign == Sign::Unsigned), pointer), UNARY)
+    }
+
+    // An element of an array on the stack
+    fn local_element(&self, index: Option<(&Expr, u64)>, offset: i64, ty: Type) -> Option<(String, u8)> {
+        let (start, local) = self.locals.range(..=offset).next_back()?;
+        let (count, size) = (local.array? as i64, local.bits as i64 / 8);
+        let within = offset - start;
+        let fits = size > 0 && within % size == 0 && within / size < count && local.bits == ty.bits();
+        if !fits || index.is_some_and(|x| x.1 as i64 != size) {
+            return None;
+        }
+        Some((format!("{}[{}]", local.name, self.index(index, within / size)), PRIMARY))
+    }
+
+    // An index into an array, plus a constant
+    fn index(&self, index: Option<(&Expr, u64)>, k: i64) -> String {
+        let Some((index, _)) = index else { return k.to_string() };
+        match k {
+            0 => self.expr(unextended(index), Context::VALUE).0,
+            k => {
+                let index = self.operand(unextended(index), Context::VALUE, ADDITIVE);
+                format!("{} {} {}", index, if k < 0 { "-" } else { "+" }, k.unsigned_abs())
+            }
+        }
+    }
+
+    // The type of the value of an expression, where it's a variable or what is accessed through one
+    fn expr_type(&self, expr: &Expr) -> Option<Ty> {
+        match expr {
+            Expr::Var(var) => self.types.vars.get(var).cloned(),
+            Expr::Load { address, ty, .. } => {
+                let (base, index, offset) = decompose(address);
+                if self.stack_pointer.is_some_and(|x| *base == Expr::Var(x)) {
+                    let local = self.locals.get(&offset).filter(|x| x.array.is_none() && index.is_none() && x.bits == ty.bits());
+                    return local.map(|x| x.ty.clone());
+                }
+                let Some(Ty::Pointer { pointee, .. }) = self.expr_type(base) else { return None };
+                self.types.element(&pointee, index.map(|x| x.1), offset, ty.bits()).map(|x| x.ty)
+            }
+            _ => None,
+        }
+    }
+
+    // Moving a pointer by some bytes, as C counts them: in elements of what it points to, or to a field of it
+    fn pointer_arithmetic(&self, op: BinaryOp, left: &Expr, right: &Expr, pointee: &Ty) -> Option<(String, u8)> {
+        let size = self.types.size(pointee).filter(|x| *x > 1)? as i64;
+        let pointer = self.operand(left, Context::VALUE, ADDITIVE);
+        let (symbol, count) = match (right.constant(), types::scaled(right)) {
+            (Some(value), _) => {
+                let value = if op == BinaryOp::Sub { signed(value, right.ty()).wrapping_neg() } else { signed(value, right.ty()) };
+                if value % size != 0 {
+                    if let (Ty::Struct(index), true) = (pointee, value > 0) {
+                        let offset = value as u64;
+                        if self.types.structs[*index].fields.contains_key(&offset) {
+                            return Some((format!("&{}->field_{:x}", self.operand(left, Context::VALUE, PRIMARY), offset), UNARY));
+                        }
+                    }
+                    let value = number(value.unsigned_abs(), Type::Int(64), Context::VALUE).0;
+                    let symbol = if op == BinaryOp::Sub { "-" } else { "+" };
+                    let pointer = self.operand(left, Context::VALUE, UNARY);
+                    return Some((format!("(char *){} {} {}", pointer, symbol, value), ADDITIVE));
+                }
+                let count = value / size;
+                (if count < 0 { "-" } else { "+" }, count.unsigned_abs().to_string())
+            }
+            (None, Some((index, scale))) if scale as i64 == size => {
+                (if op == BinaryOp::Sub { "-" } else { "+" }, self.operand(unextended(index), Context::VALUE, ADDITIVE + 1))
+            }
+            _ => {
+                let symbol = if op == BinaryOp::Sub { "-" } else { "+" };
+                let pointer = self.operand(left, Context::VALUE, UNARY);
+                let right = self.operand(right, Context::VALUE, ADDITIVE + 1);
+                return Some((format!("(char *){} {} {}", pointer, symbol, right), ADDITIVE));
+            }
+        };
+        Some((format!("{} {} {}", pointer, symbol, count), ADDITIVE))
+    }
+
+    // A constant as an address: a string literal, a function, or a global variable or a place within one
+    fn address(&self, value: u64) -> Option<(String, u8)> {
+        if self.decompiler.image.is_code(value) {
+            let named = self.decompiler.starts.contains(&value) || self.decompiler.plt.contains_key(&value);
+            return self.decompiler.callee(value).filter(|_| named).map(|x| (sanitize(&x.name), PRIMARY));
+        }
+        if !self.is_data(value) {
+            return None;
+        }
+        if let Some((_, string)) = self.decompiler.strings.range(..=value).next_back() {
+            let offset = (value - string.address) as usize;
+            if offset < string.size {
+                if let Some(literal) = literal(string, offset) {
+                    return Some((literal, PRIMARY));
+                }
+            }
+        }
+        match self.global(value) {
+            Some((name, 0)) => Some((format!("&{}", name), UNARY)),
+            Some((name, offset)) => Some((format!("(char *)&{} + {}", name, number(offset, Type::Int(64), Context::VALUE).0), ADDITIVE)),
+            None => Some((format!("&g_{:x}", value), UNARY)),
+        }
+    }
+
+    // Whether an address is in one of the sections holding the variables and constants of the program
+    fn is_data(&self, address: u64) -> bool {
+        const DATA: [&str; 7] = [".rodata", ".data", ".bss", ".tdata", ".tbss", ".got", ".init_array"];
+        let section = self.decompiler.image.section_at(address);
+        section.is_some_and(|x| !x.executable && DATA.iter().any(|name| x.name.starts_with(name)))
+    }
+
+    // The symbol covering an address and the offset into it
+    fn global(&self, address: u64) -> Option<(String, u64)> {
+        if self.decompiler.naming.globals == GlobalNames::Addresses {
+            return None;
+        }
+        let symbol = self
+            .decompiler
+            .image
+            .symbols
+            .iter()
+            .filter(|x| !x.function && x.section.is_some() && !x.name.is_empty() && !x.name.starts_with('$'))
+            .filter(|x| x.address <= address && address - x.address < x.size.max(1))
+            .max_by_key(|x| (x.address, x.global))?;
+        Some((sanitize(&symbol.name), address - symbol.address))
+    }
+}
+
+// The body of an endless loop going around again where a condition holds and leaving otherwise, as one leaving
+// where it doesn't hold
+fn exit_early(body: &Node) -> Option<Node> {
+    let Node::Sequence(nodes) = body else { return None };
+    let [start @ .., Node::If { condition, then, otherwise: None }, Node::Break] = nodes.as_slice() else { return None };
+    let rest = match &**then {
+        Node::Continue => &[][..],
+        Node::Sequence(nodes) if nodes.last() == Some(&Node::Continue) => &nodes[..nodes.len() - 1],
+        _ => return None,
+    };
+    let mut res = start.to_vec();
+    res.push(Node::If { condition: condition.clone().negate(), then: Box::new(Node::Break), otherwise: None });
+    res.extend(rest.iter().cloned());
+    Some(Node::Sequence(res))
+}
+
+// The blocks gone to
+fn gotos(node: &Node, targets: &mut BTreeSet<BlockId>) {
+    match node {
+        Node::Goto(block) => {
+            targets.insert(*block);
+        }
+        Node::Sequence(nodes) => nodes.iter().for_each(|x| gotos(x, targets)),
+        Node::If { then, otherwise, .. } => {
+            gotos(then, targets);
+            if let Some(otherwise) = otherwise {
+                gotos(otherwise, targets);
+            }
+        }
+        Node::Loop { body, .. } => gotos(body, targets),
+        Node::Switch { cases, .. } => cases.iter().for_each(|x| gotos(&x.body, targets)),
+        Node::Block(_) | Node::Break | Node::Continue | Node::Return => {}
+    }
+}
+
+// The operands of an expression
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Const { .. } | Expr::Var(_) => vec![],
+        Expr::Load { address, .. } => vec![address],
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => vec![value],
+        Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => vec![left, right],
+        Expr::Select { condition, then, otherwise } => vec![condition, then, otherwise],
+        Expr::Intrinsic { args, .. } => args.iter().collect(),
+    }
+}
+
+fn is_terminator(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Jump(_) | Stmt::Branch { .. } | Stmt::Return)
+}
+
+// A constant of some type as a signed number
+pub(super) fn signed(value: u64, ty: Type) -> i64 {
+    match ty.bits() {
+        bits @ 1..=63 => ((value << (64 - bits)) as i64) >> (64 - bits),
+        _ => value as i64,
+    }
+}
+
+// The value without the extensions widening it
+fn unextended(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Cast(CastOp::ZeroExtend | CastOp::SignExtend, value, _) if value.ty() != Type::Bool => unextended(value),
+        expr => expr,
+    }
+}
+
+// How many bits a value needs: constants fit in an int unless they are wide, extended values in what they were
+fn natural_bits(value: &Expr) -> u16 {
+    match value {
+        // Such as those moved to a 32 bit register, which zero extends them
+        Expr::Const { value, ty } if ty.bits() > 32 => {
+            if *value >> 32 == 0 || (signed(*value, *ty) >= i32::MIN as i64 && signed(*value, *ty) < 0) {
+                32
+            } else {
+                ty.bits()
+            }
+        }
+        Expr::Const { ty, .. } => ty.bits().max(32),
+        Expr::Cast(CastOp::ZeroExtend | CastOp::SignExtend, value, _) if value.ty() == Type::Bool => 32,
+        Expr::Cast(CastOp::ZeroExtend | CastOp::SignExtend, value, _) => natural_bits(value),
+        value => value.ty().bits(),
+    }
+}
+
+fn type_name(ty: Type, unsigned: bool) -> String {
+    match ty {
+        Type::Bool => "bool".to_string(),
+        Type::Int(bits) => format!("{}int{}_t", if unsigned { "u" } else { "" }, bits),
+    }
+}
+
+// Constants are decimal when small, hexadecimal otherwise and where their bits matter. Signed ones with the top
+// bit set are negative.
+fn number(value: u64, ty: Type, context: Context) -> (String, u8) {
+    let format = |value: u64| match (value < 10, value < 256 && !context.bitwise) {
+        (true, _) | (_, true) => value.to_string(),
+        _ => format!("0x{:x}", value),
+    };
+    let negative = signed(value, ty) < 0 && ty.bits() <= 64;
+    if negative && context.sign != Sign::Unsigned && !context.bitwise {
+        return (format!("-{}", format(signed(value, ty).unsigned_abs())), UNARY);
+    }
+    (format(value), PRIMARY)
+}
+
+// A printable character as a C character literal
+fn character_literal(value: u64) -> Option<String> {
+    match value {
+        0x20..=0x7e => Some(match value as u8 as char {
+            c @ ('\'' | '\\') => format!("'\\{}'", c),
+            c => format!("'{}'", c),
+        }),
+        _ => None,
+    }
+}
+
+// The C operator of a comparison, or of the same one with its operands swapped, and how it interprets them
+fn compare_symbol(op: CompareOp, mirrored: bool) -> (&'static str, Sign) {
+    match (op, mirrored) {
+        (CompareOp::Eq, _) => ("==", Sign::Any),
+        (CompareOp::Ne, _) => ("!=", Sign::Any),
+        (CompareOp::SLt, false) => ("<", Sign::Signed),
+        (CompareOp::SLe, false) => ("<=", Sign::Signed),
+        (CompareOp::ULt, false) => ("<", Sign::Unsigned),
+        (CompareOp::ULe, false) => ("<=", Sign::Unsigned),
+        (CompareOp::SLt, true) => (">", Sign::Signed),
+        (CompareOp::SLe, true) => (">=", Sign::Signed),
+        (CompareOp::ULt, true) => (">", Sign::Unsigned),
+        (CompareOp::ULe, true) => (">=", Sign::Unsigned),
+    }
+}
+
+// The negation of a condition, with comparisons inverted and De Morgan's laws applied
+fn negate(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Compare(op, left, right) => {
+            let (left, right) = (*left.clone(), *right.clone());
+            match op {
+                CompareOp::Eq => Expr::compare(CompareOp::Ne, left, right),
+                CompareOp::Ne => Expr::compare(CompareOp::Eq, left, right),
+                CompareOp::SLt => Expr::compare(CompareOp::SLe, right, left),
+                CompareOp::SLe => Expr::compare(CompareOp::SLt, right, left),
+                CompareOp::ULt => Expr::compare(CompareOp::ULe, right, left),
+                CompareOp::ULe => Expr::compare(CompareOp::ULt, right, left),
+            }
+        }
+        Expr::Unary(UnaryOp::Not, value) => *value.clone(),
+        Expr::Binary(BinaryOp::And, left, right) if expr.ty() == Type::Bool => {
+            Expr::binary(BinaryOp::Or, negate(left), negate(right))
+        }
+        Expr::Binary(BinaryOp::Or, left, right) if expr.ty() == Type::Bool => {
+            Expr::binary(BinaryOp::And, negate(left), negate(right))
+        }
+        expr => Expr::unary(UnaryOp::Not, expr.clone()),
+    }
+}
+
+// A name usable in C: the symbol version left out and anything but letters, digits and underscores replaced
+fn sanitize(name: &str) -> String {
+    let name = name.split('@').next().unwrap_or(name);
+    name.chars().map(|x| if x.is_ascii_alphanumeric() || x == '_' { x } else { '_' }).collect()
+}
+
+// A string from some offset as a C literal
+fn literal(string: &FoundString, offset: usize) -> Option<String> {
+    let (prefix, value) = match string.encoding {
+        Encoding::Ascii | Encoding::Utf8 => ("", string.value.get(offset..)?),
+        Encoding::Utf16Le | Encoding::Utf16Be if offset == 0 => ("u", string.value.as_str()),
+        Encoding::Utf32Le | Encoding::Utf32Be if offset == 0 => ("U", string.value.as_str()),
+        _ => return None,
+    };
+    Some(quote(prefix, value))
+}
+
+// A C string literal, with the characters escaped that need to be
+fn quote(prefix: &str, value: &str) -> String {
+    let mut res = format!("{}\"", prefix);
+    for c in value.chars() {
+        match c {
+            '\n' => res.push_str("\\n"),
+            '\t' => res.push_str("\\t"),
+            '\r' => res.push_str("\\r"),
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            c if (c as u32) < 0x20 || c == '\x7f' => res.push_str(&format!("\\x{:02x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeMap;
+
+use crate::dwarf::constants::*;
+use crate::dwarf::{self, CfaRule, DebugInfo, Location, TypeKind, VariableRecord};
+use crate::ir::{Parameter, Storage};
+
+use super::types::Ty;
+use super::{Decompiler, Lowered};
+
+/// A parameter or variable as the debug information of the function has it, with its type where the decompiler
+/// has one like it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugVariable {
+    pub name: String,
+    pub ty: Option<Ty>,
+}
+
+/// The names and types the debug information gives the parameters passed in registers and the variables kept on
+/// the stack, by their offset from the stack pointer at entry, and the type of what the function returns. Variables
+/// of optimized code, which move between registers along the function, are left out.
+#[derive(Debug, Clone, Default)]
+pub struct DebugVariables {
+    pub registers: BTreeMap<Storage, DebugVariable>,
+    pub stack: BTreeMap<i64, DebugVariable>,
+    pub returns: Option<Ty>,
+}
+
+/// The variables of the function starting at `start`, from the DW_TAG_formal_parameter and DW_TAG_variable entries
+/// of its DW_TAG_subprogram
+pub(super) fn variables(decompiler: &Decompiler, lowered: &Lowered, start: u64) -> DebugVariables {
+    let mut res = DebugVariables::default();
+    let debug = &decompiler.debug;
+    let Some(function) = debug.functions.iter().find(|x| x.start() == Some(start)) else {
+        return res;
+    };
+    let pointer_bits = decompiler.image.target.instruction_set.metadata().pointer_size as u16 * 8;
+    let variable = |record: &VariableRecord| {
+        let name = record.name.clone()?;
+        Some(DebugVariable { name, ty: scalar(debug, record.ty, pointer_bits, 0) })
+    };
+    res.returns = scalar(debug, function.return_type, pointer_bits, 0);
+
+    // Locations relative to the frame base, as offsets from the stack pointer at entry
+    let base = frame_base(decompiler, function.frame_base.as_ref(), start);
+    for record in function.parameters.iter().chain(function.variables.iter()) {
+        if let (Some(base), Some(Location::FrameOffset(offset)), Some(variable)) = (base, &record.location, variable(record)) {
+            res.stack.entry(base + offset).or_insert(variable);
+        }
+    }
+
+    // Parameters in registers, where their location says so at entry, or else in the order the convention passes
+    // them: integers and floating point values each in their own registers
+    let (Some(convention), Some(prototype)) = (&lowered.convention, &lowered.prototype) else {
+        return res;
+    };
+    let mut classes = [convention.arguments.iter(), convention.float_arguments.iter()];
+    let mut by_register = BTreeMap::new();
+    for record in function.parameters.iter() {
+        let class = match resolved(debug, record.ty, 0).map(|x| &x.kind) {
+            Some(TypeKind::Base(encoding)) if *encoding == DW_ATE_FLOAT => 1,
+            Some(TypeKind::Base(_) | TypeKind::Pointer(_) | TypeKind::Reference(_) | TypeKind::Enum(_)) => 0,
+            // What's passed for structures depends on their size and members
+            _ => break,
+        };
+        let at_entry = match &record.location {
+            Some(Location::Register(number)) => Some(*number),
+            Some(Location::List(ranges)) => ranges.iter().find_map(|x| match x.location {
+                Location::Register(number) if x.start <= start && start < x.end => Some(number),
+                _ => None,
+            }),
+            _ => None,
+        };
+        let register = at_entry.map(|x| dwarf::register_name(decompiler.image.target.instruction_set, x));
+        let passed = classes[class].next();
+        let storage = match register {
+            Some(name) => convention.arguments.iter().chain(convention.float_arguments.iter()).find(|x| x.to_string() == name),
+            None => passed,
+        };
+        if let (Some(storage), Some(variable)) = (storage, variable(record)) {
+            by_register.insert(storage.storage, variable);
+        }
+    }
+    for parameter in prototype.parameters.iter() {
+        if let Parameter::Register(var) = parameter {
+            if let Some(variable) = by_register.remove(&var.storage) {
+                res.registers.insert(var.storage, variable);
+            }
+        }
+    }
+    res
+}
+
+// Where the frame base of the function is, as an offset from the stack pointer at entry: the canonical frame
+// address, or a frame pointer the unwind table gives the CFA relative to
+fn frame_base(decompiler: &Decompiler, frame_base: Option<&Location>, start: u64) -> Option<i64> {
+    let instruction_set = decompiler.image.target.instruction_set;
+    let stack_pointer = dwarf::stack_pointer(instruction_set)?;
+    let fde = decompiler.frames.fde_for(start)?;
+    let rows = decompiler.frames.rows(fde).ok()?;
+    let cfa = rows.iter().find(|x| x.contains(start))?.stack_height(stack_pointer)?;
+    match frame_base? {
+        Location::CallFrameCfa => Some(cfa),
+        Location::Register(register) => rows.iter().find_map(|row| match row.cfa {
+            CfaRule::RegisterOffset(x, offset) if x == *register => Some(cfa - offset),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// The type a DWARF type stands for, through typedefs and qualifiers
+fn resolved(debug: &DebugInfo, ty: Option<u64>, depth: usize) -> Option<&dwarf::TypeRecord> {
+    let record = debug.types.get(&ty?)?;
+    match record.kind {
+        TypeKind::Typedef(x) | TypeKind::Const(x) | TypeKind::Volatile(x) | TypeKind::Restrict(x) | TypeKind::Atomic(x)
+            if depth < 16 =>
+        {
+            resolved(debug, x, depth + 1)
+        }
+        _ => Some(record),
+    }
+}
+
+// A DWARF type as one of the decompiler: integers, characters, flags and the pointers to those or to nothing. The
+// typedefs of the C library keep their name. Structures are left to what the accesses say.
+fn scalar(debug: &DebugInfo, ty: Option<u64>, pointer_bits: u16, depth: usize) -> Option<Ty> {
+    let record = debug.types.get(&ty?)?;
+    if depth > 16 {
+        return None;
+    }
+    match &record.kind {
+        TypeKind::Typedef(x) => {
+            let named = record.name.as_deref().and_then(|x| super::types::library_integer(x, pointer_bits));
+            named.or_else(|| scalar(debug, *x, pointer_bits, depth + 1))
+        }
+        TypeKind::Const(x) | TypeKind::Volatile(x) | TypeKind::Restrict(x) | TypeKind::Atomic(x) => {
+            scalar(debug, *x, pointer_bits, depth + 1)
+        }
+        TypeKind::Base(encoding) => {
+            let bits = record.size? as u16 * 8;
+            match *encoding {
+                DW_ATE_BOOLEAN => Some(Ty::Bool),
+                DW_ATE_SIGNED_CHAR | DW_ATE_UNSIGNED_CHAR if record.name.as_deref() == Some("char") => Some(Ty::Char),
+                DW_ATE_SIGNED | DW_ATE_SIGNED_CHAR => Some(Ty::Int { bits, signed: true }),
+                DW_ATE_UNSIGNED | DW_ATE_UNSIGNED_CHAR => Some(Ty::Int { bits, signed: false }),
+                _ => None,
+            }
+        }
+        TypeKind::Pointer(pointee) => {
+            let constant = matches!(pointee.and_then(|x| debug.types.get(&x)).map(|x| &x.kind), Some(TypeKind::Const(_)));
+            let pointee = match resolved(debug, *pointee, 0) {
+                None => Ty::Void,
+                Some(record) if record.kind == TypeKind::Unspecified => Ty::Void,
+                _ => scalar(debug, *pointee, pointer_bits, depth + 1)?,
+            };
+            Some(Ty::Pointer { pointee: Box::new(pointee), constant })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::super::Decompiler;
+    use crate::analysis::{Function, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::dwarf::constants::*;
+    use crate::dwarf::{FunctionRecord, Location, TypeKind, TypeRecord, VariableRecord};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Section};
+    use crate::instruction_set::InstructionSet;
+    use crate::ir::lifter_for;
+
+    fn section(index: usize, name: &str, address: u64, data: Vec<u8>, executable: bool) -> Section {
+        Section {
+            index,
+            name: name.to_string(),
+            address,
+            size: data.len() as u64,
+            entry_size: 0,
+            data,
+            allocated: true,
+            executable,
+            writable: false,
+            merged_strings: false,
+        }
+    }
+
+    fn variable(name: &str, ty: u64, offset: i64) -> VariableRecord {
+        let location = Some(Location::FrameOffset(offset));
+        VariableRecord { name: Some(name.to_string()), ty: Some(ty), location, ..VariableRecord::default() }
+    }
+
+    fn ty(offset: u64, name: Option<&str>, size: Option<u64>, kind: TypeKind) -> (u64, TypeRecord) {
+        (offset, TypeRecord { offset, name: name.map(|x| x.to_string()), size, kind })
+    }
+
+    #[test]
+    fn unoptimized_variables() {
+        // unsigned f(char *p, int n) { unsigned sum = n + 1; return sum; } at -O0: push rbp; mov rbp, rsp;
+        // mov [rbp - 0x18], rdi; mov [rbp - 0x1c], esi; mov eax, [rbp - 0x1c]; add eax, 1; mov [rbp - 4], eax;
+        // mov eax, [rbp - 4]; pop rbp; ret
+        let text = vec![
+            0x55, 0x48, 0x89, 0xe5, 0x48, 0x89, 0x7d, 0xe8, 0x89, 0x75, 0xe4, 0x8b, 0x45, 0xe4, 0x83, 0xc0, 0x01, 0x89, 0x45, 0xfc,
+            0x8b, 0x45, 0xfc, 0x5d, 0xc3,
+        ];
+        // A CIE with the CFA 8 bytes above the stack pointer at entry, and the FDE of the function
+        let mut eh_frame = vec![0x14, 0, 0, 0, 0, 0, 0, 0, 1, b'z', b'R', 0, 1, 0x78, 0x10, 1, 0x1b, 0x0c, 0x07, 0x08, 0x90, 0x01, 0, 0];
+        eh_frame.extend([0x14, 0, 0, 0, 0x1c, 0, 0, 0, 0xe0, 0xef, 0xff, 0xff, text.len() as u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0x1000,
+            relocatable: false,
+            sections: vec![section(1, ".text", 0x1000, text, true), section(2, ".eh_frame", 0x2000, eh_frame, false)],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+        };
+        let (decoder, lifter) = (decoder_for(&image.target).unwrap(), lifter_for(&image.target).unwrap());
+        let functions = [Function { start: 0x1000, end: 0x1019, name: Some("f".to_string()), sources: vec![Source::Symbol] }];
+        let mut decompiler = Decompiler::new(&image, &*decoder, &*lifter, &functions);
+
+        // Relative to the CFA, which is 8 bytes above where the stack pointer is at entry
+        decompiler.debug.functions.push(FunctionRecord {
+            name: Some("f".to_string()),
+            ranges: vec![(0x1000, 0x1019)],
+            return_type: Some(4),
+            parameters: vec![variable("p", 3, -40), variable("n", 1, -44)],
+            variables: vec![variable("sum", 4, -20)],
+            frame_base: Some(Location::CallFrameCfa),
+            ..FunctionRecord::default()
+        });
+        decompiler.debug.types = BTreeMap::from([
+            ty(1, Some("int"), Some(4), TypeKind::Base(DW_ATE_SIGNED)),
+            ty(2, Some("char"), Some(1), TypeKind::Base(DW_ATE_SIGNED_CHAR)),
+            ty(3, None, Some(8), TypeKind::Pointer(Some(2))),
+            ty(4, Some("unsigned int"), Some(4), TypeKind::Base(DW_ATE_UNSIGNED)),
+        ]);
+        let text = decompiler.decompile(&functions[0]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "uint32_t f(char *p, int32_t n)", "{}", text);
+        for declaration in ["char *p_local;", "int32_t n_local;", "uint32_t sum;"] {
+            assert!(lines.iter().any(|x| x.trim() == declaration), "{}", text);
+        }
+        assert!(lines.iter().any(|x| x.trim() == "return sum;"), "{}", text);
+    }
+}
@@ -0,0 +1,515 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::analysis::{self, EdgeKind, FoundString, Function, StringOptions, Xrefs};
+use crate::disasm::Decoder;
+use crate::dwarf::{DebugInfo, FrameTable};
+use crate::image::Image;
+use crate::ir::{
+    self, library_function, BinaryOp, CallEffects, CallingConvention, Conventions, DefUse, Destination, Expr, Lifter,
+    Parameter, Prototype, Signature, StackFrame, StackSlot, Stmt, Storage, Var,
+};
+
+mod c;
+mod debug;
+mod types;
+
+pub use self::types::{decompose, Element, Struct, Ty, Types};
+
+/// How the variables and stack slots of a function are named
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalNames {
+    // After the registers holding them and their versions, and the offsets of stack slots: rdi, rax_3, local_58
+    Registers,
+    // In order of appearance: a1 for parameters, v1 for variables and local1 for stack slots
+    Numbered,
+}
+
+impl LocalNames {
+    pub const ALL: [LocalNames; 2] = [LocalNames::Registers, LocalNames::Numbered];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LocalNames::Registers => "registers",
+            LocalNames::Numbered => "numbered",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LocalNames> {
+        LocalNames::ALL.iter().copied().find(|x| x.name() == name)
+    }
+}
+
+/// How data outside of the stack is named
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlobalNames {
+    // After the symbol covering the address, g_ADDRESS without one
+    Symbols,
+    // Always g_ADDRESS
+    Addresses,
+}
+
+impl GlobalNames {
+    pub const ALL: [GlobalNames; 2] = [GlobalNames::Symbols, GlobalNames::Addresses];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GlobalNames::Symbols => "symbols",
+            GlobalNames::Addresses => "addresses",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GlobalNames> {
+        GlobalNames::ALL.iter().copied().find(|x| x.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Naming {
+    pub locals: LocalNames,
+    pub globals: GlobalNames,
+}
+
+impl Default for Naming {
+    fn default() -> Naming {
+        Naming { locals: LocalNames::Registers, globals: GlobalNames::Symbols }
+    }
+}
+
+/// What a call goes to: the name of the function, and its signature if it's one of the C library
+#[derive(Debug, Clone)]
+struct Callee {
+    name: String,
+    signature: Option<&'static Signature>,
+}
+
+/// A function lowered for printing: out of SSA form, with the stack addressed from the stack pointer at entry
+struct Lowered {
+    function: ir::Function,
+    convention: Option<CallingConvention>,
+    prototype: Option<Prototype>,
+    slots: Vec<StackSlot>,
+    // The variable holding what the function returns
+    returned: Option<Var>,
+}
+
+impl Lowered {
+    // The result of a call that is used: the first result register of the convention
+    fn result(&self, results: &[Var]) -> Option<Var> {
+        let first = self.convention.as_ref().and_then(|x| x.results.first());
+        results.iter().find(|x| Some(x.storage) == first.map(|x| x.storage)).or_else(|| results.first()).copied()
+    }
+}
+
+/// Turns functions into C pseudocode: their control flow structured into ifs, loops and switches, the calls made
+/// with the arguments the calling convention passes and the library functions take, the stack slots as local
+/// variables, and the addresses of strings, functions and symbols by what is there. The output only depends on
+/// the binary and the options, so it can be compared from one run to the next.
+pub struct Decompiler<'a> {
+    image: &'a Image,
+    decoder: &'a dyn Decoder,
+    lifter: &'a dyn Lifter,
+    functions: &'a [Function],
+    starts: BTreeSet<u64>,
+    frames: FrameTable,
+    plt: BTreeMap<u64, String>,
+    strings: BTreeMap<u64, FoundString>,
+    debug: DebugInfo,
+    pub conventions: Conventions,
+    pub naming: Naming,
+}
+
+impl<'a> Decompiler<'a> {
+    pub fn new(image: &'a Image, decoder: &'a dyn Decoder, lifter: &'a dyn Lifter, functions: &'a [Function]) -> Decompiler<'a> {
+        // Strings are only looked up by the addresses the code uses, so short ones can't be confused with others
+        let options = StringOptions { min_length: 2, ..StringOptions::default() };
+        let strings = analysis::find_strings(image, &Xrefs::default(), &options).into_iter().map(|x| (x.address, x)).collect();
+        Decompiler {
+            image,
+            decoder,
+            lifter,
+            functions,
+            starts: functions.iter().map(|x| x.start).collect(),
+            frames: analysis::frame_table(image),
+            plt: analysis::plt_names(image, decoder),
+            strings,
+            debug: analysis::debug_info(image),
+            conventions: Conventions::new(&image.target),
+            naming: Naming::default(),
+        }
+    }
+
+    /// The C pseudocode of a function
+    pub fn decompile(&self, function: &Function) -> String {
+        let lowered = self.lower(function.start);
+        c::print(self, &function.display_name(), &lowered)
+    }
+
+    /// The types inferred for the variables of a function, as they are declared in its pseudocode
+    pub fn types(&self, function: &Function) -> Types {
+        types::infer(self, &self.lower(function.start))
+    }
+
+    // What is at the start of the function a direct call or jump goes to
+    fn callee(&self, address: u64) -> Option<Callee> {
+        let name = match self.plt.get(&address) {
+            Some(name) => name.trim_end_matches("@plt").to_string(),
+            None => match self.functions.iter().find(|x| x.start == address) {
+                Some(function) => function.display_name(),
+                None => self.image.symbols.iter().find(|x| x.function && x.address == address)?.name.clone(),
+            },
+        };
+        let name = name.split('@').next().unwrap_or(&name).to_string();
+        Some(Callee { signature: library_function(&name), name })
+    }
+
+    // The function an indirect call goes to through a slot the dynamic linker fills in, such as one of the GOT
+    fn imported(&self, target: &Expr) -> Option<Callee> {
+        let Expr::Load { address, .. } = target else { return None };
+        let slot = address.constant()?;
+        let relocation = self.image.relocations.iter().find(|x| x.address == slot)?;
+        let name = relocation.symbol.as_deref()?;
+        let name = name.split('@').next().unwrap_or(name).to_string();
+        Some(Callee { signature: library_function(&name), name })
+    }
+
+    // What a call statement goes to, if that's known
+    fn call_target(&self, target: &Destination) -> Option<Callee> {
+        match target {
+            Destination::Direct(address) => self.callee(*address),
+            Destination::Indirect(target) => self.imported(target),
+        }
+    }
+
+    // The function lifted, with tail jumps made calls followed by returns and the calls to functions that don't
+    // return cut from what follows them
+    fn lift(&self, start: u64) -> ir::Function {
+        let cfg = analysis::function_cfg(self.image, self.decoder, &self.frames, start, &self.starts);
+        let mut function = ir::Function::lift(&cfg, self.lifter);
+        let exit = function.exit;
+        for block in 0..function.blocks.len() {
+            let tail = function.edges.iter().any(|x| x.from == block && x.to == exit && x.kind == EdgeKind::Unconditional);
+            let statements = &mut function.blocks[block].statements;
+            if let (true, Some((address, Stmt::Jump(target)))) = (tail, statements.last().cloned()) {
+                statements.pop();
+                statements.push((address, Stmt::call(target)));
+                statements.push((address, Stmt::Return));
+                for edge in function.edges.iter_mut().filter(|x| x.from == block && x.to == exit) {
+                    edge.kind = EdgeKind::Return;
+                }
+            }
+
+            let noreturn = function.blocks[block].statements.iter().any(|(_, stmt)| match stmt {
+                Stmt::Call { target, .. } => self.call_target(target).and_then(|x| x.signature).is_some_and(|x| x.noreturn),
+                _ => false,
+            });
+            if noreturn {
+                function.edges.retain(|x| x.from != block || x.kind == EdgeKind::Exception);
+            }
+        }
+        function
+    }
+
+    fn effects(&self, function: &ir::Function, convention: Option<&CallingConvention>) -> CallEffects {
+        match convention {
+            Some(convention) => CallEffects::for_convention(function, convention),
+            None => CallEffects::conservative(function, self.lifter.stack_pointer()),
+        }
+    }
+
+    /// The prototype of a function, as its pseudocode has it
+    pub fn prototype(&self, function: &Function) -> Option<Prototype> {
+        let convention = self.conventions.abi(function.start).map(|x| x.convention());
+        let optimized = self.optimized(function.start, convention.as_ref());
+        let prototypes = RefCell::new(BTreeMap::new());
+        self.conventions.prototype(function.start, &optimized, &|x| self.arguments_taken(x, convention.as_ref(), Some(&prototypes)))
+    }
+
+    // The prototype of a function called, from its code
+    fn callee_prototype(&self, start: u64) -> Option<Prototype> {
+        let convention = self.conventions.abi(start)?.convention();
+        let function = self.optimized(start, Some(&convention));
+        self.conventions.prototype(start, &function, &|x| self.arguments_taken(x, Some(&convention), None))
+    }
+
+    // The function in SSA form, with constants and copies propagated and dead code removed
+    fn optimized(&self, start: u64, convention: Option<&CallingConvention>) -> ir::Function {
+        let mut function = self.lift(start);
+        function.to_ssa(&self.effects(&function, convention));
+        function.propagate_constants();
+        function.propagate_copies();
+        function.eliminate_dead_code();
+        function
+    }
+
+    // How many integer and floating point argument registers the function a call goes to takes: as many as the
+    // signature of a library function has, or the prototype of a function of the binary if `prototypes` caches them
+    fn arguments_taken(
+        &self,
+        target: &Destination,
+        convention: Option<&CallingConvention>,
+        prototypes: Option<&RefCell<BTreeMap<u64, Option<Prototype>>>>,
+    ) -> Option<(usize, usize)> {
+        if let Some(signature) = self.call_target(target).and_then(|x| x.signature) {
+            return Some((signature.parameters.len(), 0));
+        }
+        let (Destination::Direct(address), Some(convention), Some(prototypes)) = (target, convention, prototypes) else {
+            return None;
+        };
+        if !self.starts.contains(address) || self.plt.contains_key(address) {
+            return None;
+        }
+        let cached = prototypes.borrow().get(address).cloned();
+        let prototype = match cached {
+            Some(prototype) => prototype,
+            None => {
+                let prototype = self.callee_prototype(*address);
+                prototypes.borrow_mut().insert(*address, prototype.clone());
+                prototype
+            }
+        }?;
+        let position = |class: &[Var]| {
+            let index = |x: &Parameter| match x {
+                Parameter::Register(var) => class.iter().position(|x| x.storage == var.storage),
+                Parameter::Stack { .. } => None,
+            };
+            prototype.parameters.iter().filter_map(index).max().map_or(0, |x| x + 1)
+        };
+        Some((position(&convention.arguments), position(&convention.float_arguments)))
+    }
+
+    fn lower(&self, start: u64) -> Lowered {
+        let convention = self.conventions.abi(start).map(|x| x.convention());
+        let mut function = self.optimized(start, convention.as_ref());
+        let prototypes = RefCell::new(BTreeMap::new());
+        let taken = |x: &Destination| self.arguments_taken(x, convention.as_ref(), Some(&prototypes));
+        let prototype = self.conventions.prototype(start, &function, &taken);
+
+        self.bind_arguments(&mut function, convention.as_ref(), prototype.as_ref(), &prototypes);
+        function.eliminate_dead_code();
+        function.fold_conditions();
+
+        // Registers the caller doesn't get back are only restored for it
+        let returns: Vec<Storage> = prototype.iter().flat_map(|x| x.returns.iter().map(|x| x.storage)).collect();
+        let exit = function.exit;
+        function.blocks[exit].statements.retain(|(_, stmt)| match stmt {
+            Stmt::Phi { var, .. } => returns.contains(&var.storage),
+            _ => true,
+        });
+        function.eliminate_dead_code();
+
+        let mut slots = Vec::new();
+        if let Some(convention) = &convention {
+            let frame = StackFrame::analyze(&function, convention);
+            rebase(&mut function, &frame, convention);
+            drop_saves(&mut function, &frame, convention);
+            function.eliminate_dead_code();
+            slots = frame.slots;
+        }
+        function.narrow_variables();
+
+        let returned = function.blocks[exit].statements.iter().find_map(|(_, stmt)| match stmt {
+            Stmt::Phi { var, .. } => Some(*var),
+            _ => None,
+        });
+        let classes = function.leave_ssa();
+        let returned = returned.map(|x| classes.get(&x).copied().unwrap_or(x));
+        inline(&mut function, returned);
+        Lowered { function, convention, prototype, slots, returned }
+    }
+
+    // Calls and system calls get the arguments the callee takes: as many as the signature of a library function
+    // has, or the prototype of a function of the binary. Otherwise, and for the variable arguments, they are those
+    // up to the last register set for the call rather than left from the caller or a previous call.
+    fn bind_arguments(
+        &self,
+        function: &mut ir::Function,
+        convention: Option<&CallingConvention>,
+        prototype: Option<&Prototype>,
+        prototypes: &RefCell<BTreeMap<u64, Option<Prototype>>>,
+    ) {
+        let definitions = DefUse::build(function);
+        let parameters: BTreeSet<Var> = prototype
+            .iter()
+            .flat_map(|x| x.parameters.iter())
+            .filter_map(|x| match x {
+                Parameter::Register(var) => Some(*var),
+                Parameter::Stack { .. } => None,
+            })
+            .collect();
+        let integers = convention.map_or(0, |x| x.arguments.len());
+
+        let mut bound: Vec<(usize, usize, Vec<Expr>)> = Vec::new();
+        for (block, statements) in function.blocks.iter().enumerate().map(|(i, x)| (i, &x.statements)) {
+            for (index, (_, stmt)) in statements.iter().enumerate() {
+                let (arguments, callee, target) = match stmt {
+                    Stmt::Call { target, arguments, .. } => (arguments, self.call_target(target), Some(target)),
+                    Stmt::Syscall { arguments, .. } => (arguments, None, None),
+                    _ => continue,
+                };
+                let split = integers.min(arguments.len());
+                let (ints, floats) = arguments.split_at(split);
+                let fresh = |values: &[Expr]| {
+                    values.iter().rposition(|x| !is_stale(function, &definitions, &parameters, x, &mut BTreeSet::new())).map_or(0, |x| x + 1)
+                };
+                let taken = target.and_then(|x| self.arguments_taken(x, convention, Some(prototypes)));
+                let (ints, floats) = match (taken, callee.and_then(|x| x.signature)) {
+                    (Some((taken, _)), Some(signature)) if signature.variadic => (taken.max(fresh(ints)), fresh(floats)),
+                    (Some(taken), _) => taken,
+                    _ if convention.is_none() => (fresh(ints), 0),
+                    _ => (fresh(ints), fresh(floats)),
+                };
+                let mut values: Vec<Expr> = arguments[..ints.min(split)].to_vec();
+                values.extend(arguments[split..].iter().take(floats).cloned());
+                bound.push((block, index, values));
+            }
+        }
+
+        for (block, index, values) in bound {
+            if let Stmt::Call { arguments, .. } | Stmt::Syscall { arguments, .. } = &mut function.blocks[block].statements[index].1 {
+                *arguments = values;
+            }
+        }
+    }
+}
+
+// Whether an argument is what a register held rather than a value set for the call: what the function was called
+// with in a register that isn't a parameter, or what a call left there
+fn is_stale(function: &ir::Function, definitions: &DefUse, parameters: &BTreeSet<Var>, value: &Expr, visiting: &mut BTreeSet<Var>) -> bool {
+    let Expr::Var(var) = value else { return false };
+    if var.version == 0 {
+        return matches!(var.storage, Storage::Register(_)) && !parameters.contains(var);
+    }
+    // Phis reached again around a loop don't decide
+    if !visiting.insert(*var) {
+        return true;
+    }
+    match definitions.definition(var).map(|x| function.statement(x)) {
+        Some(Stmt::Call { .. } | Stmt::Syscall { .. }) => true,
+        Some(Stmt::Phi { sources, .. }) => {
+            sources.iter().all(|x| is_stale(function, definitions, parameters, &x.1, visiting))
+        }
+        _ => false,
+    }
+}
+
+// Variables known to point into the stack are replaced by the stack pointer at entry plus their offset
+fn rebase(function: &mut ir::Function, frame: &StackFrame, convention: &CallingConvention) {
+    let stack_pointer = Var { version: 0, ..convention.stack_pointer };
+    let bits = stack_pointer.ty.bits();
+    for (_, stmt) in function.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+        for expr in stmt.expressions_mut() {
+            let mut changed = false;
+            expr.substitute(&mut |var| {
+                let offset = frame.offset(&Expr::var(*var)).filter(|_| *var != stack_pointer)?;
+                changed = true;
+                Some(Expr::binary(BinaryOp::Add, Expr::var(stack_pointer), Expr::int(offset as u64, bits)))
+            });
+            if changed {
+                *expr = expr.clone().simplify();
+            }
+        }
+    }
+}
+
+// Removes the stores saving the registers the caller expects back, and the return address, to slots nothing reads
+// them back from
+fn drop_saves(function: &mut ir::Function, frame: &StackFrame, convention: &CallingConvention) {
+    let saved: Vec<Var> =
+        convention.callee_saved.iter().chain(convention.return_address.iter()).map(|x| Var { version: 0, ..*x }).collect();
+    let read = |offset: i64| frame.slots.iter().any(|x| x.offset == offset && x.read);
+    for block in function.blocks.iter_mut() {
+        block.statements.retain(|(_, stmt)| match stmt {
+            Stmt::Store { address, value: Expr::Var(var), .. } if saved.contains(var) => {
+                frame.offset(address).is_none_or(read)
+            }
+            _ => true,
+        });
+    }
+}
+
+// Assignments of variables read once are moved into the statement reading them, when it's in the same block and
+// nothing in between changes what they read. Values loaded from memory don't move past stores or calls. Moving
+// a value can leave others read once, such as what the flags of a comparison were computed from.
+fn inline(function: &mut ir::Function, returned: Option<Var>) {
+    let mut definitions: BTreeMap<Var, usize> = BTreeMap::new();
+    let mut uses: BTreeMap<Var, usize> = BTreeMap::new();
+    for (_, stmt) in function.blocks.iter().flat_map(|x| x.statements.iter()) {
+        for var in stmt.definitions() {
+            *definitions.entry(*var).or_default() += 1;
+        }
+        count_uses(stmt, &mut uses, true);
+    }
+
+    for block in function.blocks.iter_mut() {
+        let statements = &mut block.statements;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut i = 0;
+            while i < statements.len() {
+                let Some(j) = inline_target(statements, i, &definitions, &uses, returned) else {
+                    i += 1;
+                    continue;
+                };
+                let (_, removed) = statements.remove(i);
+                count_uses(&removed, &mut uses, false);
+                let Stmt::Assign { var, value } = removed else { unreachable!() };
+                let stmt = &mut statements[j - 1].1;
+                count_uses(stmt, &mut uses, false);
+                for expr in stmt.expressions_mut() {
+                    let mut found = false;
+                    expr.substitute(&mut |x| {
+                        found |= *x == var;
+                        Some(value.clone()).filter(|_| *x == var)
+                    });
+                    if found {
+                        *expr = expr.clone().simplify();
+                    }
+                }
+                count_uses(stmt, &mut uses, true);
+                changed = true;
+            }
+        }
+    }
+}
+
+fn count_uses(stmt: &Stmt, uses: &mut BTreeMap<Var, usize>, add: bool) {
+    stmt.for_each_use(&mut |x| {
+        let count = uses.entry(*x).or_default();
+        *count = if add { *count + 1 } else { count.saturating_sub(1) };
+    });
+}
+
+// The statement after the i-th of a block the value it assigns can be moved into
+fn inline_target(
+    statements: &[(u64, Stmt)],
+    i: usize,
+    definitions: &BTreeMap<Var, usize>,
+    uses: &BTreeMap<Var, usize>,
+    returned: Option<Var>,
+) -> Option<usize> {
+    let Stmt::Assign { var, value } = &statements[i].1 else { return None };
+    if Some(*var) == returned || definitions.get(var) != Some(&1) || uses.get(var) != Some(&1) {
+        return None;
+    }
+    let mut read = BTreeSet::new();
+    value.for_each_var(&mut |x| {
+        read.insert(*x);
+    });
+    for (j, (_, stmt)) in statements.iter().enumerate().skip(i + 1) {
+        let mut reads = false;
+        stmt.for_each_use(&mut |x| reads |= x == var);
+        if reads {
+            return Some(j);
+        }
+        if stmt.definitions().iter().any(|x| read.contains(*x)) {
+            return None;
+        }
+        let effects = matches!(stmt, Stmt::Store { .. } | Stmt::Call { .. } | Stmt::Syscall { .. } | Stmt::Intrinsic { .. });
+        if effects && value.has_load() {
+            return None;
+        }
+    }
+    None
+}
@@ -0,0 +1,908 @@
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ir::{BinaryOp, CastOp, CompareOp, Expr, Parameter, Stmt, Type, Var};
+
+use super::c::signed;
+use super::{Decompiler, Lowered};
+
+// The integer types of the C library, with their size where it doesn't follow the pointers and whether they are
+// signed. Those named by a typedef keep their name.
+const INTEGERS: [(&str, Option<u16>, bool); 13] = [
+    ("int", Some(32), true),
+    ("unsigned int", Some(32), false),
+    ("short", Some(16), true),
+    ("unsigned short", Some(16), false),
+    ("long", None, true),
+    ("unsigned long", None, false),
+    ("long long", Some(64), true),
+    ("unsigned long long", Some(64), false),
+    ("size_t", None, false),
+    ("ssize_t", None, true),
+    ("off_t", Some(64), true),
+    ("time_t", Some(64), true),
+    ("pid_t", Some(32), true),
+];
+
+// The type of an integer typedef of the C library, such as size_t, by its name
+pub(super) fn library_integer(name: &str, pointer_bits: u16) -> Option<Ty> {
+    INTEGERS.iter().find(|x| x.0 == name).map(|x| Ty::parse(x.0, pointer_bits))
+}
+
+/// A C type recovered for a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ty {
+    Void,
+    Bool,
+    Int { bits: u16, signed: bool },
+    Char,
+    /// A type by the name the C library gives it, such as FILE, size_t or a function pointer type
+    Named(&'static str),
+    /// A pointer, to something constant or not
+    Pointer { pointee: Box<Ty>, constant: bool },
+    /// One of the structures recovered, by its index
+    Struct(usize),
+}
+
+impl Ty {
+    /// The type a C library signature names, for a target with pointers of the given size
+    pub fn parse(text: &'static str, pointer_bits: u16) -> Ty {
+        let text = text.trim();
+        if text.contains("(*)") {
+            return Ty::Named(text);
+        }
+        if let Some(rest) = text.strip_suffix('*') {
+            // A constant pointer is a pointer all the same
+            let rest = rest.trim_end();
+            let rest = rest.strip_suffix("const").map_or(rest, |x| x.trim_end());
+            let (rest, constant) = match rest.strip_prefix("const ") {
+                Some(rest) => (rest, true),
+                None => (rest, false),
+            };
+            return Ty::Pointer { pointee: Box::new(Ty::parse(rest, pointer_bits)), constant };
+        }
+        match text {
+            "void" => Ty::Void,
+            "char" => Ty::Char,
+            "bool" | "_Bool" => Ty::Bool,
+            _ => match INTEGERS.iter().find(|x| x.0 == text) {
+                Some((name, ..)) if name.ends_with("_t") => Ty::Named(name),
+                Some((_, bits, signed)) => Ty::Int { bits: bits.unwrap_or(pointer_bits), signed: *signed },
+                None => Ty::Named(text),
+            },
+        }
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        match self {
+            Ty::Pointer { .. } => true,
+            Ty::Named(name) => name.contains("(*)"),
+            _ => false,
+        }
+    }
+
+    /// The size and signedness of an integer type
+    pub fn integer(&self, pointer_bits: u16) -> Option<(u16, bool)> {
+        match self {
+            Ty::Bool => Some((1, false)),
+            Ty::Char => Some((8, true)),
+            Ty::Int { bits, signed } => Some((*bits, *signed)),
+            Ty::Named(name) => INTEGERS.iter().find(|x| x.0 == *name).map(|x| (x.1.unwrap_or(pointer_bits), x.2)),
+            _ => None,
+        }
+    }
+
+    // Gives the structures mentioned new indices
+    fn renumber(&mut self, f: &dyn Fn(usize) -> usize) {
+        match self {
+            Ty::Pointer { pointee, .. } => pointee.renumber(f),
+            Ty::Struct(index) => *index = f(*index),
+            _ => {}
+        }
+    }
+
+    /// A declaration of something of this type
+    pub fn declare(&self, name: &str) -> String {
+        match self {
+            Ty::Named(text) if text.contains("(*)") => text.replacen("(*)", &format!("(*{})", name), 1),
+            ty => {
+                let ty = ty.to_string();
+                match ty.ends_with('*') {
+                    true => format!("{}{}", ty, name),
+                    false => format!("{} {}", ty, name),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ty::Void => write!(f, "void"),
+            Ty::Bool => write!(f, "bool"),
+            Ty::Int { bits, signed } => write!(f, "{}int{}_t", if *signed { "" } else { "u" }, bits),
+            Ty::Char => write!(f, "char"),
+            Ty::Named(name) => write!(f, "{}", name),
+            Ty::Pointer { pointee, constant } => {
+                let pointee = pointee.to_string();
+                let constant = if *constant { "const " } else { "" };
+                match pointee.ends_with('*') {
+                    true => write!(f, "{}{}*", constant, pointee),
+                    false => write!(f, "{}{} *", constant, pointee),
+                }
+            }
+            Ty::Struct(index) => write!(f, "struct struct_{}", index + 1),
+        }
+    }
+}
+
+/// A structure recovered from the offsets a pointer is accessed at, with the fields by their offset. The size is
+/// the distance between the elements of the arrays of it, or the end of its last field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Struct {
+    pub size: u64,
+    pub fields: BTreeMap<u64, Ty>,
+}
+
+/// Where an access through a pointer falls: which element of the array pointed to, and the field of it when
+/// those are structures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub index: i64,
+    pub field: Option<u64>,
+    pub ty: Ty,
+}
+
+/// The types of the variables of a function, and of what it keeps on the stack
+#[derive(Debug, Clone, Default)]
+pub struct Types {
+    pub vars: BTreeMap<Var, Ty>,
+    /// What is stored at offsets from the stack pointer at entry
+    pub locals: BTreeMap<i64, Ty>,
+    /// The arrays on the stack indexed by a variable, by their offset, with the type of their elements
+    pub arrays: BTreeMap<i64, Ty>,
+    pub structs: Vec<Struct>,
+    pub pointer_bits: u16,
+}
+
+impl Types {
+    /// The size of a type in bytes, when it is known
+    pub fn size(&self, ty: &Ty) -> Option<u64> {
+        match ty {
+            Ty::Struct(index) => Some(self.structs[*index].size),
+            ty if ty.is_pointer() => Some(self.pointer_bits as u64 / 8),
+            ty => ty.integer(self.pointer_bits).map(|x| (x.0 as u64).div_ceil(8)),
+        }
+    }
+
+    /// Where an access of some bits at an offset from a pointer to a type, and maybe an index scaled by the
+    /// size of its elements, falls
+    pub fn element(&self, pointee: &Ty, scale: Option<u64>, offset: i64, bits: u16) -> Option<Element> {
+        let size = self.size(pointee)? as i64;
+        if size == 0 || scale.is_some_and(|x| x as i64 != size) {
+            return None;
+        }
+        let (index, within) = (offset.div_euclid(size), offset.rem_euclid(size) as u64);
+        let (field, ty) = match pointee {
+            Ty::Struct(id) => (Some(within), self.structs[*id].fields.get(&within)?),
+            ty if within == 0 => (None, ty),
+            _ => return None,
+        };
+        let matches = self.size(ty).is_some_and(|x| x * 8 == bits as u64) || (*ty == Ty::Bool && bits == 8);
+        matches.then(|| Element { index, field, ty: ty.clone() })
+    }
+}
+
+/// An address as a base, an index scaled by the size of the elements, and a constant offset
+pub fn decompose(address: &Expr) -> (&Expr, Option<(&Expr, u64)>, i64) {
+    match address {
+        Expr::Binary(op @ (BinaryOp::Add | BinaryOp::Sub), left, right) if right.constant().is_some() => {
+            let (base, index, offset) = decompose(left);
+            let value = signed(right.constant().unwrap_or(0), right.ty());
+            (base, index, if *op == BinaryOp::Add { offset.wrapping_add(value) } else { offset.wrapping_sub(value) })
+        }
+        Expr::Binary(BinaryOp::Add, left, right) => {
+            // The index is the scaled operand, or the extended one, or else the second
+            let (base, index) = match (scaled(left), scaled(right)) {
+                (_, Some(index)) => (&**left, index),
+                (Some(index), None) => (&**right, index),
+                _ if is_extension(left) && !is_extension(right) => (&**right, (&**left, 1)),
+                _ => (&**left, (&**right, 1)),
+            };
+            match decompose(base) {
+                (base, None, offset) => (base, Some(index), offset),
+                _ => (address, None, 0),
+            }
+        }
+        address => (address, None, 0),
+    }
+}
+
+// An index scaled by a constant, as compilers multiply: by shifts and additions too
+pub(super) fn scaled(expr: &Expr) -> Option<(&Expr, u64)> {
+    Some(multiple(expr)).filter(|x| x.1 > 1)
+}
+
+fn multiple(expr: &Expr) -> (&Expr, u64) {
+    match expr {
+        Expr::Binary(BinaryOp::Mul, index, scale) | Expr::Binary(BinaryOp::Mul, scale, index) if scale.constant().is_some() => {
+            let (index, factor) = multiple(index);
+            (index, factor.wrapping_mul(scale.constant().unwrap_or(0)))
+        }
+        Expr::Binary(BinaryOp::Shl, index, shift) if shift.constant().is_some_and(|x| x < 16) => {
+            let (index, factor) = multiple(index);
+            (index, factor << shift.constant().unwrap_or(0))
+        }
+        Expr::Binary(BinaryOp::Add, left, right) => match (multiple(left), multiple(right)) {
+            ((left, a), (right, b)) if left == right => (left, a + b),
+            _ => (expr, 1),
+        },
+        expr => (expr, 1),
+    }
+}
+
+fn is_extension(expr: &Expr) -> bool {
+    matches!(expr, Expr::Cast(CastOp::ZeroExtend | CastOp::SignExtend, ..))
+}
+
+// The vote for the signedness of what a value is assigned to: bytes and halves zero extended, as by movzx, and the
+// results of unsigned divisions and shifts are unsigned, those sign extended or of signed ones signed
+fn signedness(value: &Expr) -> i32 {
+    match value {
+        Expr::Cast(CastOp::ZeroExtend, value, _) if value.ty() != Type::Bool && value.ty().bits() < 32 => 1,
+        Expr::Cast(CastOp::SignExtend, ..) => -1,
+        Expr::Cast(_, value, _) => signedness(value),
+        Expr::Binary(BinaryOp::UDiv | BinaryOp::URem | BinaryOp::LShr, ..) => 1,
+        Expr::Binary(BinaryOp::SDiv | BinaryOp::SRem | BinaryOp::AShr, ..) => -1,
+        _ => 0,
+    }
+}
+
+// What a type variable stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Var(Var),
+    Local(i64),
+}
+
+// What the uses of the values of a type variable say about its type
+#[derive(Debug, Clone, Default)]
+struct Facts {
+    bits: u16,
+    // Used as an address, or known to be one
+    pointer: bool,
+    // Votes for unsigned, positive, and signed, negative
+    unsigned: i32,
+    // The type a library function gives it
+    declared: Option<Ty>,
+    // The type variables of what is accessed through it, by offset
+    fields: BTreeMap<i64, usize>,
+    // The distance between the elements accessed through it with an index
+    stride: Option<u64>,
+}
+
+// Type variables unified as the values flow between them, and the facts known of each set of them
+struct Solver<'a> {
+    decompiler: &'a Decompiler<'a>,
+    lowered: &'a Lowered,
+    stack_pointer: Option<Var>,
+    // The registers the function takes its parameters in
+    parameters: BTreeSet<Var>,
+    pointer_bits: u16,
+    parent: Vec<usize>,
+    facts: Vec<Facts>,
+    keys: BTreeMap<Key, usize>,
+    // The stack slots indexed, which start arrays
+    indexed: BTreeSet<i64>,
+    // Variables set to others plus a constant, which point where those do
+    offsets: Vec<(usize, usize)>,
+    // The pointers among those, with what they were moved from
+    derived: BTreeMap<usize, usize>,
+    types: Types,
+    structs: BTreeMap<usize, usize>,
+    resolving: BTreeSet<usize>,
+}
+
+/// Infers the types of the variables of a function from how they are used: addresses are pointers, and what is
+/// accessed at offsets from them the fields of a structure or the elements of an array where it's indexed. Values
+/// copied, stored and loaded back, compared or selected together get the same type. Signed and unsigned
+/// comparisons, divisions, shifts and extensions vote for the signedness of integers, and the signatures of the
+/// library functions called give the types of their arguments and results.
+pub(super) fn infer(decompiler: &Decompiler, lowered: &Lowered) -> Types {
+    let pointer_bits = decompiler.image.target.instruction_set.metadata().pointer_size as u16 * 8;
+    let mut solver = Solver {
+        decompiler,
+        lowered,
+        stack_pointer: lowered.convention.as_ref().map(|x| Var { version: 0, ..x.stack_pointer }),
+        parameters: BTreeSet::new(),
+        pointer_bits,
+        parent: Vec::new(),
+        facts: Vec::new(),
+        keys: BTreeMap::new(),
+        indexed: BTreeSet::new(),
+        offsets: Vec::new(),
+        derived: BTreeMap::new(),
+        types: Types { pointer_bits, ..Types::default() },
+        structs: BTreeMap::new(),
+        resolving: BTreeSet::new(),
+    };
+    // Read narrower than the registers they come in
+    let function = &lowered.function;
+    let registers: Vec<_> = lowered.prototype.iter().flat_map(|x| x.parameters.iter()).filter_map(|x| match x {
+        Parameter::Register(var) => Some(var.storage),
+        Parameter::Stack { .. } => None,
+    }).collect();
+    for (_, stmt) in function.blocks.iter().flat_map(|x| x.statements.iter()) {
+        stmt.for_each_use(&mut |x| {
+            if x.version == 0 && registers.contains(&x.storage) {
+                solver.parameters.insert(*x);
+            }
+        });
+    }
+    for (_, stmt) in function.blocks.iter().flat_map(|x| x.statements.iter()) {
+        solver.statement(stmt);
+    }
+    for var in solver.parameters.clone() {
+        solver.var(var);
+    }
+
+    // Pointers moved along stay pointers, to the same kind of thing
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (var, base) in solver.offsets.clone() {
+            let (var, base) = (solver.find(var), solver.find(base));
+            if var == base || !solver.facts[base].pointer || solver.facts[var].bits != pointer_bits {
+                continue;
+            }
+            solver.derived.entry(var).or_insert(base);
+            if !solver.facts[var].pointer {
+                solver.facts[var].pointer = true;
+                changed = true;
+            }
+        }
+    }
+
+    let keys: Vec<(Key, usize)> = solver.keys.iter().map(|(key, node)| (*key, *node)).collect();
+    for (key, node) in keys {
+        let ty = solver.resolve(node);
+        match key {
+            Key::Var(var) => {
+                solver.types.vars.insert(var, ty);
+            }
+            Key::Local(offset) => {
+                if solver.indexed.contains(&offset) {
+                    solver.types.arrays.insert(offset, ty.clone());
+                }
+                solver.types.locals.insert(offset, ty);
+            }
+        }
+    }
+    merge_structs(&mut solver.types);
+    solver.types
+}
+
+// Makes the structures laid out alike one, those pointed to by values that never met. Structures pointing to
+// others of their kind are alike when they point to their own kind.
+fn merge_structs(types: &mut Types) {
+    loop {
+        let alike = |a: usize, b: usize| {
+            let same = |x: usize| if x == b { a } else { x };
+            let [mut first, mut second] = [types.structs[a].clone(), types.structs[b].clone()];
+            first.fields.values_mut().chain(second.fields.values_mut()).for_each(|x| x.renumber(&same));
+            first == second
+        };
+        let Some((kept, merged)) = (0..types.structs.len()).find_map(|b| (0..b).find(|a| alike(*a, b)).map(|a| (a, b))) else {
+            return;
+        };
+        types.structs.remove(merged);
+        let renumber = |x: usize| match x.cmp(&merged) {
+            std::cmp::Ordering::Less => x,
+            std::cmp::Ordering::Equal => kept,
+            std::cmp::Ordering::Greater => x - 1,
+        };
+        let fields = types.structs.iter_mut().flat_map(|x| x.fields.values_mut());
+        for ty in types.vars.values_mut().chain(types.locals.values_mut()).chain(types.arrays.values_mut()).chain(fields) {
+            ty.renumber(&renumber);
+        }
+    }
+}
+
+impl Solver<'_> {
+    fn node(&mut self, bits: u16) -> usize {
+        self.parent.push(self.parent.len());
+        self.facts.push(Facts { bits, ..Facts::default() });
+        self.parent.len() - 1
+    }
+
+    fn key(&mut self, key: Key, bits: u16) -> usize {
+        let node = match self.keys.get(&key) {
+            Some(node) => *node,
+            None => {
+                let node = self.node(bits);
+                self.keys.insert(key, node);
+                node
+            }
+        };
+        let root = self.find(node);
+        self.facts[root].bits = self.facts[root].bits.max(bits);
+        node
+    }
+
+    fn var(&mut self, var: Var) -> usize {
+        self.key(Key::Var(var), var.ty.bits())
+    }
+
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut node = node;
+        while self.parent[node] != root {
+            let next = self.parent[node];
+            self.parent[node] = root;
+            node = next;
+        }
+        root
+    }
+
+    // Makes two type variables one, along with what is accessed through them at the same offsets
+    fn unify(&mut self, a: usize, b: usize) {
+        let mut pending = vec![(a, b)];
+        while let Some((a, b)) = pending.pop() {
+            let (a, b) = (self.find(a), self.find(b));
+            if a == b {
+                continue;
+            }
+            self.parent[b] = a;
+            let other = std::mem::take(&mut self.facts[b]);
+            let facts = &mut self.facts[a];
+            facts.bits = facts.bits.max(other.bits);
+            facts.pointer |= other.pointer;
+            facts.unsigned += other.unsigned;
+            facts.stride = facts.stride.or(other.stride);
+            // The more precise of the types declared
+            let generic = |x: &Option<Ty>| matches!(x, Some(Ty::Pointer { pointee, .. }) if **pointee == Ty::Void);
+            if facts.declared.is_none() || (generic(&facts.declared) && other.declared.is_some()) {
+                facts.declared = other.declared;
+            }
+            for (offset, field) in other.fields {
+                match facts.fields.entry(offset) {
+                    Entry::Occupied(entry) => pending.push((*entry.get(), field)),
+                    Entry::Vacant(entry) => {
+                        entry.insert(field);
+                    }
+                }
+            }
+        }
+    }
+
+    fn unify_all(&mut self, a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                self.unify(a, b);
+                Some(a)
+            }
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn vote(&mut self, node: Option<usize>, unsigned: i32) {
+        if let Some(node) = node {
+            let root = self.find(node);
+            self.facts[root].unsigned += unsigned;
+        }
+    }
+
+    fn declare(&mut self, node: usize, ty: Ty) {
+        let root = self.find(node);
+        let facts = &mut self.facts[root];
+        facts.pointer |= ty.is_pointer();
+        if let Some((_, signed)) = ty.integer(self.pointer_bits) {
+            // Much more than the votes of the operations
+            facts.unsigned += if signed { -100 } else { 100 };
+        }
+        if facts.declared.is_none() {
+            facts.declared = Some(ty);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Assign { var, value } => {
+                let node = self.var(*var);
+                let value_node = self.expr(value);
+                self.unify_all(Some(node), value_node);
+                self.vote(Some(node), signedness(value));
+                // Pointers moved along by a constant
+                if let Expr::Binary(BinaryOp::Add | BinaryOp::Sub, left, right) = value {
+                    if let (Some(base), Some(_)) = (self.term(left), right.constant()) {
+                        self.offsets.push((node, base));
+                    }
+                }
+            }
+            Stmt::Phi { var, sources } => {
+                let node = self.var(*var);
+                for (_, source) in sources {
+                    let source = self.expr(source);
+                    self.unify_all(Some(node), source);
+                }
+            }
+            Stmt::Store { address, value, .. } => {
+                let field = self.access(address, value.ty().bits());
+                self.vote(field, signedness(value));
+                let value = self.expr(value);
+                self.unify_all(field, value);
+            }
+            Stmt::Call { target, arguments, results } => {
+                if let crate::ir::Destination::Indirect(target) = target {
+                    self.expr(target);
+                }
+                let nodes: Vec<Option<usize>> = arguments.iter().map(|x| self.expr(x)).collect();
+                let Some(signature) = self.decompiler.call_target(target).and_then(|x| x.signature) else { return };
+                for ((argument, node), parameter) in arguments.iter().zip(nodes).zip(signature.parameters.iter()) {
+                    let ty = Ty::parse(parameter, self.pointer_bits);
+                    // The stack passed to a pointer holds what is pointed to
+                    let (base, index, offset) = decompose(argument);
+                    match (ty, node) {
+                        (Ty::Pointer { pointee, .. }, _) if index.is_none() && self.is_stack_pointer(base) => {
+                            if *pointee != Ty::Void && !matches!(*pointee, Ty::Named(_)) {
+                                let local = self.key(Key::Local(offset), 0);
+                                self.declare(local, *pointee);
+                            }
+                        }
+                        (ty, Some(node)) => self.declare(node, ty),
+                        (_, None) => {}
+                    }
+                }
+                if let Some(result) = self.lowered.result(results) {
+                    let ty = Ty::parse(signature.returns, self.pointer_bits);
+                    if ty != Ty::Void {
+                        let node = self.var(result);
+                        self.declare(node, ty);
+                    }
+                }
+            }
+            Stmt::Syscall { arguments, .. } | Stmt::Intrinsic { args: arguments, .. } => {
+                for argument in arguments {
+                    self.expr(argument);
+                }
+            }
+            stmt => {
+                for expr in stmt.expressions() {
+                    self.expr(expr);
+                }
+            }
+        }
+    }
+
+    fn is_stack_pointer(&self, expr: &Expr) -> bool {
+        matches!(expr, Expr::Var(var) if Some(*var) == self.stack_pointer)
+    }
+
+    // The type variable of a value, without looking into it for facts. Registers read on entry that aren't
+    // parameters, such as the base of thread local storage, aren't typed.
+    fn term(&mut self, expr: &Expr) -> Option<usize> {
+        match expr {
+            Expr::Var(var) if var.version == 0 && !self.parameters.contains(var) => None,
+            Expr::Var(var) => Some(self.var(*var)),
+            _ => None,
+        }
+    }
+
+    // Gathers the facts within an expression, and gives the type variable of its value if it has one
+    fn expr(&mut self, expr: &Expr) -> Option<usize> {
+        match expr {
+            Expr::Var(_) => self.term(expr),
+            Expr::Const { value, ty } if ty.bits() == self.pointer_bits => {
+                // Addresses of strings, where the data is
+                let string = self.decompiler.strings.range(..=*value).next_back().is_some_and(|(address, string)| {
+                    address + string.size as u64 > *value
+                });
+                if !string {
+                    return None;
+                }
+                let node = self.node(self.pointer_bits);
+                self.declare(node, Ty::Pointer { pointee: Box::new(Ty::Char), constant: true });
+                Some(node)
+            }
+            Expr::Const { .. } => None,
+            Expr::Load { address, ty, .. } => self.access(address, ty.bits()),
+            Expr::Unary(_, value) => {
+                self.expr(value);
+                None
+            }
+            Expr::Binary(op, left, right) => {
+                let nodes = (self.expr(left), self.expr(right));
+                let votes = match op {
+                    BinaryOp::SDiv | BinaryOp::SRem => (-1, -1),
+                    BinaryOp::UDiv | BinaryOp::URem => (1, 1),
+                    BinaryOp::AShr => (-1, 0),
+                    BinaryOp::LShr => (1, 0),
+                    _ => (0, 0),
+                };
+                self.vote_operand(left, nodes.0, votes.0);
+                self.vote_operand(right, nodes.1, votes.1);
+                None
+            }
+            Expr::Compare(op, left, right) => {
+                let constant = left.constant().is_some() || right.constant().is_some();
+                let nodes = (self.expr(left), self.expr(right));
+                let vote = match op {
+                    // Values compared for equality are of one type, unless one is a constant
+                    CompareOp::Eq | CompareOp::Ne => {
+                        self.unify_all(nodes.0, nodes.1);
+                        0
+                    }
+                    CompareOp::SLt | CompareOp::SLe => -1,
+                    // Which is also how compilers check that signed values are within a range
+                    CompareOp::ULt | CompareOp::ULe if constant => 0,
+                    CompareOp::ULt | CompareOp::ULe => 1,
+                };
+                self.vote_operand(left, nodes.0, vote);
+                self.vote_operand(right, nodes.1, vote);
+                None
+            }
+            Expr::Cast(op, value, _) => {
+                let node = self.expr(value);
+                match op {
+                    CastOp::SignExtend => self.vote(node, -1),
+                    // Rather than 32 bit values, which registers are zero extended from without asking
+                    CastOp::ZeroExtend if value.ty() != Type::Bool && value.ty().bits() < 32 => self.vote(node, 1),
+                    _ => {}
+                }
+                None
+            }
+            Expr::Select { condition, then, otherwise } => {
+                self.expr(condition);
+                let (then, otherwise) = (self.expr(then), self.expr(otherwise));
+                self.unify_all(then, otherwise)
+            }
+            Expr::Intrinsic { args, .. } => {
+                for arg in args {
+                    self.expr(arg);
+                }
+                None
+            }
+        }
+    }
+
+    // Votes for the signedness of an operand of a signed or unsigned operation, and of the variable it truncates,
+    // or zero extends for unsigned ones, whose bits the operation reads all the same
+    fn vote_operand(&mut self, expr: &Expr, node: Option<usize>, unsigned: i32) {
+        if unsigned == 0 {
+            return;
+        }
+        let mut value = expr;
+        while let Expr::Cast(op, inner, _) = value {
+            match op {
+                CastOp::Truncate => value = inner,
+                CastOp::ZeroExtend if unsigned > 0 && inner.ty() != Type::Bool => value = inner,
+                _ => break,
+            }
+        }
+        let node = match value {
+            Expr::Var(_) => self.term(value).or(node),
+            _ => node,
+        };
+        self.vote(node, unsigned);
+    }
+
+    // The type variable of what is accessed at an address: a stack slot, or what a pointer points to
+    fn access(&mut self, address: &Expr, bits: u16) -> Option<usize> {
+        let (base, index, offset) = decompose(address);
+        if let Some((index, _)) = index {
+            self.expr(index);
+        }
+        if self.is_stack_pointer(base) {
+            let local = self.key(Key::Local(offset), bits);
+            if index.is_some() {
+                self.indexed.insert(offset);
+            }
+            return Some(local);
+        }
+        let base = self.expr(base)?;
+        let root = self.find(base);
+        self.facts[root].pointer = true;
+        if let Some((_, scale)) = index {
+            self.facts[root].stride = self.facts[root].stride.or(Some(scale));
+        }
+        let field = match self.facts[root].fields.get(&offset) {
+            Some(field) => *field,
+            None => {
+                let field = self.node(bits);
+                self.facts[root].fields.insert(offset, field);
+                field
+            }
+        };
+        let field_root = self.find(field);
+        self.facts[field_root].bits = self.facts[field_root].bits.max(bits);
+        Some(field)
+    }
+
+    // The type of a set of type variables: what a library function declared it to be, unless it's a pointer to
+    // nothing in particular and more is known of what it points to, or a pointer where it's used as one, or else
+    // an integer
+    fn resolve(&mut self, node: usize) -> Ty {
+        let root = self.find(node);
+        let facts = self.facts[root].clone();
+        match facts.declared.clone() {
+            Some(Ty::Pointer { pointee, .. }) if *pointee == Ty::Void && !facts.fields.is_empty() => {}
+            Some(ty) if ty.integer(self.pointer_bits).is_none_or(|x| x.0 == facts.bits || facts.bits == 0) => return ty,
+            _ => {}
+        }
+        if facts.pointer && facts.bits == self.pointer_bits {
+            // Structures pointing to others of their kind
+            if !self.resolving.insert(root) {
+                let pointee = self.structs.get(&root).map_or(Ty::Void, |x| Ty::Struct(*x));
+                return Ty::Pointer { pointee: Box::new(pointee), constant: false };
+            }
+            let pointee = self.pointee(root, &facts);
+            self.resolving.remove(&root);
+            return Ty::Pointer { pointee: Box::new(pointee), constant: false };
+        }
+        match facts.bits {
+            1 => Ty::Bool,
+            bits => Ty::Int { bits, signed: facts.unsigned <= 0 },
+        }
+    }
+
+    // What a pointer points to: a single type where it's only accessed at its start and through an index by its
+    // size, a structure otherwise
+    fn pointee(&mut self, root: usize, facts: &Facts) -> Ty {
+        let mut fields = facts.fields.iter().map(|(offset, field)| (*offset, *field));
+        let single = match (fields.next(), fields.next()) {
+            (None, _) => {
+                return match self.derived.get(&root).copied().map(|x| self.resolve(x)) {
+                    Some(Ty::Pointer { pointee, .. }) => *pointee,
+                    _ => Ty::Void,
+                };
+            }
+            (Some((0, field)), None) => Some(field),
+            _ => None,
+        };
+        if let Some(field) = single {
+            let field_root = self.find(field);
+            let bits = self.facts[field_root].bits as u64;
+            // Bytes pointed to are characters
+            if facts.stride.is_none_or(|x| x * 8 == bits) {
+                return match self.resolve(field) {
+                    Ty::Int { bits: 8, signed: true } => Ty::Char,
+                    ty => ty,
+                };
+            }
+        }
+        if let Some(index) = self.structs.get(&root) {
+            return Ty::Struct(*index);
+        }
+
+        // Accesses past the end of the elements of an array are within the next ones. Those before the start of
+        // what is pointed to don't make a structure.
+        let mut offsets: BTreeMap<u64, usize> = BTreeMap::new();
+        for (offset, field) in facts.fields.iter() {
+            let offset = match facts.stride {
+                Some(stride) => offset.rem_euclid(stride as i64) as u64,
+                None if *offset >= 0 => *offset as u64,
+                None => continue,
+            };
+            offsets.entry(offset).or_insert(*field);
+        }
+        if offsets.is_empty() {
+            return Ty::Void;
+        }
+        let index = self.types.structs.len();
+        self.types.structs.push(Struct::default());
+        self.structs.insert(root, index);
+        let mut layout = Struct::default();
+        let mut end = 0;
+        for (offset, field) in offsets {
+            // Fields overlapping those before are left out
+            if offset < end {
+                continue;
+            }
+            let field_root = self.find(field);
+            let bytes = (self.facts[field_root].bits as u64).div_ceil(8);
+            let ty = self.resolve(field);
+            layout.fields.insert(offset, ty);
+            end = offset + bytes;
+        }
+        layout.size = facts.stride.unwrap_or(end).max(end);
+        self.types.structs[index] = layout;
+        Ty::Struct(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Decompiler;
+    use crate::analysis::{Function, Source};
+    use crate::disasm::{decoder_for, Target};
+    use crate::endian::Endianness;
+    use crate::image::{Image, Section};
+    use crate::instruction_set::InstructionSet;
+    use crate::ir::lifter_for;
+
+    // Decompiles x86-64 functions, each in 32 bytes of code from 0x1000 on, and gives their signatures and how many
+    // structures each declares
+    fn decompile(functions: &[(&str, &[u8])]) -> Vec<(String, usize)> {
+        let mut code = Vec::new();
+        for (_, bytes) in functions {
+            code.extend_from_slice(bytes);
+            code.resize(code.len().next_multiple_of(32), 0xcc);
+        }
+        let text = Section {
+            index: 1,
+            name: ".text".to_string(),
+            address: 0x1000,
+            size: code.len() as u64,
+            entry_size: 0,
+            data: code,
+            allocated: true,
+            executable: true,
+            writable: false,
+            merged_strings: false,
+        };
+        let image = Image {
+            target: Target::new(InstructionSet::X86_64, Endianness::LittleEndian, 64),
+            entry: 0x1000,
+            relocatable: false,
+            sections: vec![text],
+            segments: Vec::new(),
+            symbols: Vec::new(),
+            relocations: Vec::new(),
+        };
+        let starts = (0..functions.len()).map(|i| 0x1000 + 32 * i as u64);
+        let functions: Vec<Function> = starts
+            .zip(functions)
+            .map(|(start, (name, _))| Function { start, end: start + 32, name: Some(name.to_string()), sources: vec![Source::Symbol] })
+            .collect();
+        let (decoder, lifter) = (decoder_for(&image.target).unwrap(), lifter_for(&image.target).unwrap());
+        let decompiler = Decompiler::new(&image, &*decoder, &*lifter, &functions);
+        functions
+            .iter()
+            .map(|function| {
+                let text = decompiler.decompile(function);
+                let signature = text.lines().find(|x| x.contains(&*function.display_name())).unwrap_or_default().to_string();
+                (signature, decompiler.types(function).structs.len())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unsigned_uses() {
+        let signatures = decompile(&[
+            // movzx eax, BYTE PTR [rdi + 3]
+            ("byte", &[0x0f, 0xb6, 0x47, 0x03, 0xc3]),
+            // mov eax, edi; xor edx, edx; div esi
+            ("udv", &[0x89, 0xf8, 0x31, 0xd2, 0xf7, 0xf6, 0xc3]),
+            // xor eax, eax; cmp edi, esi; setb al
+            ("lt", &[0x31, 0xc0, 0x39, 0xf7, 0x0f, 0x92, 0xc0, 0xc3]),
+            // mov eax, edi; shr eax, 3
+            ("shift", &[0x89, 0xf8, 0xc1, 0xe8, 0x03, 0xc3]),
+        ]);
+        assert_eq!(signatures[0].0, "uint8_t byte(struct struct_1 *rdi)");
+        assert_eq!(signatures[1].0, "uint32_t udv(uint32_t rdi, uint32_t rsi)");
+        assert_eq!(signatures[2].0, "int32_t lt(uint32_t rdi, uint32_t rsi)");
+        assert_eq!(signatures[3].0, "uint32_t shift(uint32_t rdi)");
+    }
+
+    #[test]
+    fn structures() {
+        let signatures = decompile(&[
+            // mov rax, [rdi]; mov rdx, [rdi + 8]; imul rax, [rsi + 8]; imul rdx, [rsi]; add rax, rdx
+            (
+                "both",
+                &[0x48, 0x8b, 0x07, 0x48, 0x8b, 0x57, 0x08, 0x48, 0x0f, 0xaf, 0x46, 0x08, 0x48, 0x0f, 0xaf, 0x16, 0x48, 0x01, 0xd0, 0xc3],
+            ),
+            // mov rax, [rdi - 8]; add rax, [rdi - 16]
+            ("before", &[0x48, 0x8b, 0x47, 0xf8, 0x48, 0x03, 0x47, 0xf0, 0xc3]),
+        ]);
+        // Alike, so one structure for both
+        assert_eq!(signatures[0], ("int64_t both(struct struct_1 *rdi, struct struct_1 *rsi)".to_string(), 1));
+        // Nothing at or after the start of what is pointed to, so no structure at all
+        assert_eq!(signatures[1], ("int64_t before(void *rdi)".to_string(), 0));
+    }
+}
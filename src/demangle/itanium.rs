@@ -0,0 +1,1268 @@
+use std::collections::BTreeMap;
+
+use super::{list, Demangled, FunctionType, Name, Qualifiers, Scheme, Segment, Special, TemplateArg, Type};
+
+// Nesting of types, names and expressions before giving up, against symbols crafted to overflow the stack
+const MAX_DEPTH: usize = 256;
+
+// Bytes of names the copies of substitutions and template arguments may add up to, against symbols whose types
+// double in size with each substitution
+const MAX_COPIED: usize = 1 << 16;
+
+// Operators: their code, how they are shown and how many operands they take in expressions. The ones of new and
+// delete only appear in names.
+const OPERATORS: &[(&str, &str, usize)] = &[
+    ("nw", "new", 0),
+    ("na", "new[]", 0),
+    ("dl", "delete", 0),
+    ("da", "delete[]", 0),
+    ("ps", "+", 1),
+    ("ng", "-", 1),
+    ("ad", "&", 1),
+    ("de", "*", 1),
+    ("co", "~", 1),
+    ("nt", "!", 1),
+    ("pp", "++", 1),
+    ("mm", "--", 1),
+    ("pl", "+", 2),
+    ("mi", "-", 2),
+    ("ml", "*", 2),
+    ("dv", "/", 2),
+    ("rm", "%", 2),
+    ("an", "&", 2),
+    ("or", "|", 2),
+    ("eo", "^", 2),
+    ("aS", "=", 2),
+    ("pL", "+=", 2),
+    ("mI", "-=", 2),
+    ("mL", "*=", 2),
+    ("dV", "/=", 2),
+    ("rM", "%=", 2),
+    ("aN", "&=", 2),
+    ("oR", "|=", 2),
+    ("eO", "^=", 2),
+    ("ls", "<<", 2),
+    ("rs", ">>", 2),
+    ("lS", "<<=", 2),
+    ("rS", ">>=", 2),
+    ("eq", "==", 2),
+    ("ne", "!=", 2),
+    ("lt", "<", 2),
+    ("gt", ">", 2),
+    ("le", "<=", 2),
+    ("ge", ">=", 2),
+    ("ss", "<=>", 2),
+    ("aa", "&&", 2),
+    ("oo", "||", 2),
+    ("cm", ",", 2),
+    ("pm", "->*", 2),
+    ("pt", "->", 2),
+    ("cl", "()", 2),
+    ("ix", "[]", 2),
+    ("qu", "?", 3),
+];
+
+// What the name of an encoding tells about the rest of it
+#[derive(Debug, Clone, Copy, Default)]
+struct NameInfo {
+    // The last segment has template arguments, so a function has its return type in the mangling
+    template: bool,
+    // Constructors, destructors and conversion operators have none even as templates
+    no_return_type: bool,
+    // Of member functions
+    qualifiers: Qualifiers,
+    reference: Option<&'static str>,
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    // The types and prefixes S_, S0_ and so on refer to, in the order they appear
+    substitutions: Vec<Type>,
+    // The template arguments T_, T0_ and so on refer to: those of the function being parsed
+    template_args: Vec<TemplateArg>,
+    // The substitutions that are template parameters, with their index. They stand for the arguments of the
+    // function they are used in, which a local name may have parsed them outside of, until a reference is made to
+    // them: that one stays with the arguments it was made with.
+    parameters: BTreeMap<usize, usize>,
+    // The last of those parsed as a type, and where it starts
+    last_parameter: Option<(usize, usize)>,
+    // Template parameters in the signature of a lambda are its auto parameters
+    in_lambda: bool,
+    depth: usize,
+    // What copying substitutions and template arguments may still cost, as each copy can double the size of the
+    // types built from it
+    budget: usize,
+}
+
+/// Demangles `_Z` symbols, with the clone suffixes GCC appends
+pub(super) fn demangle(symbol: &str) -> Option<Demangled> {
+    let (mangled, suffixes) = split_suffixes(symbol);
+    let input = mangled.strip_prefix("_Z")?.as_bytes();
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        substitutions: Vec::new(),
+        template_args: Vec::new(),
+        parameters: BTreeMap::new(),
+        last_parameter: None,
+        in_lambda: false,
+        depth: 0,
+        budget: MAX_COPIED,
+    };
+    let mut res = parser.encoding()?;
+    if parser.pos != input.len() {
+        return None;
+    }
+    res.suffixes = suffixes;
+    Some(res)
+}
+
+// Mangled names have no dots, after them come suffixes like .cold, .isra.0 or .constprop.1, which are a word and the
+// numbers that follow it
+fn split_suffixes(symbol: &str) -> (&str, Vec<String>) {
+    let (mangled, rest) = match symbol.find('.') {
+        Some(i) => symbol.split_at(i),
+        None => return (symbol, Vec::new()),
+    };
+    let mut suffixes: Vec<String> = Vec::new();
+    for part in rest[1..].split('.') {
+        match suffixes.last_mut() {
+            Some(last) if !part.is_empty() && part.bytes().all(|x| x.is_ascii_digit()) => {
+                last.push('.');
+                last.push_str(part);
+            }
+            _ => suffixes.push(format!(".{}", part)),
+        }
+    }
+    (mangled, suffixes)
+}
+
+fn builtin(c: u8) -> Option<&'static str> {
+    Some(match c {
+        b'v' => "void",
+        b'w' => "wchar_t",
+        b'b' => "bool",
+        b'c' => "char",
+        b'a' => "signed char",
+        b'h' => "unsigned char",
+        b's' => "short",
+        b't' => "unsigned short",
+        b'i' => "int",
+        b'j' => "unsigned int",
+        b'l' => "long",
+        b'm' => "unsigned long",
+        b'x' => "long long",
+        b'y' => "unsigned long long",
+        b'n' => "__int128",
+        b'o' => "unsigned __int128",
+        b'f' => "float",
+        b'd' => "double",
+        b'e' => "long double",
+        b'g' => "__float128",
+        b'z' => "...",
+        _ => return None,
+    })
+}
+
+// The builtin types starting with D
+fn extended_builtin(c: u8) -> Option<&'static str> {
+    Some(match c {
+        b'd' => "decimal64",
+        b'e' => "decimal128",
+        b'f' => "decimal32",
+        b'h' => "half",
+        b'i' => "char32_t",
+        b's' => "char16_t",
+        b'u' => "char8_t",
+        b'a' => "auto",
+        b'c' => "decltype(auto)",
+        b'n' => "decltype(nullptr)",
+        _ => return None,
+    })
+}
+
+// std::allocator and the like, which have abbreviations of their own. The ones of std::string and the streams stand
+// for instances of templates, whose constructors and destructors are named after the template.
+fn abbreviation(c: u8) -> Option<(Type, Option<Name>)> {
+    let std = |name: &str, arguments: Option<Vec<TemplateArg>>| Name {
+        segments: vec![Segment::new("std"), Segment { name: name.to_string(), arguments, turbofish: false }],
+    };
+    let char_type = || TemplateArg::Type(Type::Builtin("char".to_string()));
+    let traits = || TemplateArg::Type(Type::Named(std("char_traits", Some(vec![char_type()]))));
+    let allocator = || TemplateArg::Type(Type::Named(std("allocator", Some(vec![char_type()]))));
+    let (short, full) = match c {
+        b'a' => ("allocator", None),
+        b'b' => ("basic_string", None),
+        b's' => ("string", Some(std("basic_string", Some(vec![char_type(), traits(), allocator()])))),
+        b'i' => ("istream", Some(std("basic_istream", Some(vec![char_type(), traits()])))),
+        b'o' => ("ostream", Some(std("basic_ostream", Some(vec![char_type(), traits()])))),
+        b'd' => ("iostream", Some(std("basic_iostream", Some(vec![char_type(), traits()])))),
+        _ => return None,
+    };
+    Some((Type::Named(std(short, None)), full))
+}
+
+// A type used as a prefix of a name
+fn name_of(ty: Type) -> Name {
+    match ty {
+        Type::Named(name) => name,
+        ty => Name { segments: vec![Segment::new(&ty.to_string())] },
+    }
+}
+
+fn set_arguments(name: &mut Name, arguments: Vec<TemplateArg>) -> Option<()> {
+    name.segments.last_mut()?.arguments = Some(arguments);
+    Some(())
+}
+
+// What a constructor or destructor is named after: the class without its template arguments and ABI tags
+fn base_name(prefix: &Name) -> Option<String> {
+    let name = &prefix.segments.last()?.name;
+    Some(name.split("[abi:").next().unwrap_or(name).to_string())
+}
+
+// A parameter list of just void is an empty one
+fn void_list(types: Vec<Type>) -> Vec<Type> {
+    match types.as_slice() {
+        [Type::Builtin(x)] if x == "void" => Vec::new(),
+        _ => types,
+    }
+}
+
+// About the memory a type takes: the bytes of its names
+fn weight(ty: &Type) -> usize {
+    match ty {
+        Type::Builtin(text) | Type::Verbatim(text) => text.len(),
+        Type::Named(name) => name_weight(name),
+        Type::Qualified(inner, _)
+        | Type::Pointer(inner)
+        | Type::Reference(inner)
+        | Type::RValueReference(inner)
+        | Type::PackExpansion(inner) => 1 + weight(inner),
+        Type::Function(function) => {
+            1 + function.return_type.as_deref().map_or(0, weight) + function.parameters.iter().map(weight).sum::<usize>()
+        }
+        Type::Array(element, dimension) => dimension.len() + weight(element),
+        Type::MemberPointer(class, member) => weight(class) + weight(member),
+        Type::Pack(args) => args.iter().map(arg_weight).sum(),
+    }
+}
+
+fn name_weight(name: &Name) -> usize {
+    name.segments.iter().map(|x| x.name.len() + x.arguments.iter().flatten().map(arg_weight).sum::<usize>()).sum()
+}
+
+fn arg_weight(arg: &TemplateArg) -> usize {
+    match arg {
+        TemplateArg::Type(ty) => weight(ty),
+        TemplateArg::Value(value) => value.len(),
+        TemplateArg::Pack(args) => args.iter().map(arg_weight).sum(),
+    }
+}
+
+impl<'a> Parser<'a> {
+    // 0 past the end, which nothing in a mangling is
+    fn peek(&self) -> u8 {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> u8 {
+        self.input.get(self.pos + n).copied().unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let res = self.input.get(self.pos).copied();
+        self.pos += 1;
+        res
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        let res = self.peek() == c;
+        if res {
+            self.pos += 1;
+        }
+        res
+    }
+
+    fn eat_str(&mut self, text: &str) -> bool {
+        let res = self.input[self.pos..].starts_with(text.as_bytes());
+        if res {
+            self.pos += text.len();
+        }
+        res
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        if self.eat(c) {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    // A decimal number, negative with n
+    fn number(&mut self) -> Option<i64> {
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        let mut res: i64 = 0;
+        while self.peek().is_ascii_digit() {
+            res = res.checked_mul(10)?.checked_add((self.peek() - b'0') as i64)?;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        Some(if negative { -res } else { res })
+    }
+
+    // A number in base 36 with upper case letters, as substitutions are numbered
+    fn seq_id(&mut self) -> Option<usize> {
+        let start = self.pos;
+        let mut res: usize = 0;
+        while let Some(digit) = (self.peek() as char).to_digit(36).filter(|_| !self.peek().is_ascii_lowercase()) {
+            res = res.checked_mul(36)?.checked_add(digit as usize)?;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        Some(res)
+    }
+
+    fn source_name(&mut self) -> Option<String> {
+        let length = self.number().filter(|x| *x > 0)? as usize;
+        let bytes = self.input.get(self.pos..self.pos.checked_add(length)?)?;
+        self.pos += length;
+        if bytes.starts_with(b"_GLOBAL__N") {
+            return Some("(anonymous namespace)".to_string());
+        }
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn cv_qualifiers(&mut self) -> Qualifiers {
+        let restrict = self.eat(b'r');
+        let volatile = self.eat(b'V');
+        let constant = self.eat(b'K');
+        Qualifiers { constant, volatile, restrict }
+    }
+
+    // Numbers entities of the same name in a function: _ and a digit, or __, a number and _
+    fn discriminator(&mut self) -> Option<()> {
+        if self.eat_str("__") {
+            self.number()?;
+            return self.expect(b'_');
+        }
+        if self.peek() == b'_' && self.peek_at(1).is_ascii_digit() {
+            self.pos += 2;
+        }
+        Some(())
+    }
+
+    // The number of an unnamed type or lambda, from 1: _ for the first, then the number less 2 and _
+    fn unnamed_number(&mut self) -> Option<i64> {
+        if self.eat(b'_') {
+            return Some(1);
+        }
+        let res = self.number().filter(|x| *x >= 0)?;
+        self.expect(b'_')?;
+        Some(res + 2)
+    }
+
+    fn encoding(&mut self) -> Option<Demangled> {
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let res = self.encoding_inner();
+        self.depth -= 1;
+        res
+    }
+
+    fn encoding_inner(&mut self) -> Option<Demangled> {
+        if matches!(self.peek(), b'T' | b'G') {
+            return self.special_name();
+        }
+        let (name, info) = self.name(true)?;
+        let mut res = Demangled::new(Scheme::Itanium, name);
+        // Data, or the function a local name is in
+        if self.at_end() || self.peek() == b'E' {
+            return Some(res);
+        }
+        if info.template && !info.no_return_type {
+            res.return_type = Some(self.ty()?);
+        }
+        let mut parameters = Vec::new();
+        while !self.at_end() && self.peek() != b'E' {
+            parameters.push(self.ty()?);
+        }
+        res.parameters = Some(void_list(parameters));
+        res.qualifiers = info.qualifiers;
+        res.reference = info.reference;
+        Some(res)
+    }
+
+    // Vtables, typeinfo, guard variables, thunks and the like
+    fn special_name(&mut self) -> Option<Demangled> {
+        let code = [self.peek(), self.peek_at(1)];
+        // The h and v of thunks start their call offsets
+        self.pos += if matches!(&code, b"Th" | b"Tv") { 1 } else { 2 };
+        let (special, mut res) = match &code {
+            b"TV" => (Special::VTable, self.type_entity()?),
+            b"TT" => (Special::Vtt, self.type_entity()?),
+            b"TI" => (Special::TypeInfo, self.type_entity()?),
+            b"TS" => (Special::TypeInfoName, self.type_entity()?),
+            b"TC" => {
+                let complete = self.ty()?;
+                self.number()?;
+                self.expect(b'_')?;
+                (Special::ConstructionVTable(complete), self.type_entity()?)
+            }
+            b"TH" => (Special::TlsInit, self.entity()?),
+            b"TW" => (Special::TlsWrapper, self.entity()?),
+            b"Th" => {
+                self.call_offset()?;
+                (Special::NonVirtualThunk, self.encoding()?)
+            }
+            b"Tv" => {
+                self.call_offset()?;
+                (Special::VirtualThunk, self.encoding()?)
+            }
+            b"Tc" => {
+                self.call_offset()?;
+                self.call_offset()?;
+                (Special::CovariantThunk, self.encoding()?)
+            }
+            b"GV" => (Special::Guard, self.entity()?),
+            b"GR" => {
+                let entity = self.entity()?;
+                let number = match self.eat(b'_') {
+                    true => 0,
+                    false => {
+                        let res = self.seq_id()? + 1;
+                        self.expect(b'_')?;
+                        res
+                    }
+                };
+                (Special::ReferenceTemporary(number as u64), entity)
+            }
+            b"GT" => {
+                let special = match self.next()? {
+                    b't' => Special::TransactionClone,
+                    b'n' => Special::NonTransactionClone,
+                    _ => return None,
+                };
+                (special, self.encoding()?)
+            }
+            b"GA" => (Special::HiddenAlias, self.encoding()?),
+            _ => return None,
+        };
+        res.special = Some(special);
+        Some(res)
+    }
+
+    // The adjustment of this by a thunk: h and a fixed offset, or v, an offset and one into the vtable
+    fn call_offset(&mut self) -> Option<()> {
+        match self.next()? {
+            b'h' => {
+                self.number()?;
+                self.expect(b'_')
+            }
+            b'v' => {
+                self.number()?;
+                self.expect(b'_')?;
+                self.number()?;
+                self.expect(b'_')
+            }
+            _ => None,
+        }
+    }
+
+    fn entity(&mut self) -> Option<Demangled> {
+        Some(Demangled::new(Scheme::Itanium, self.name(false)?.0))
+    }
+
+    fn type_entity(&mut self) -> Option<Demangled> {
+        Some(Demangled::new(Scheme::Itanium, name_of(self.ty()?)))
+    }
+
+    // A name, whose template arguments are the ones of the function if it is the name of an encoding (`top`)
+    fn name(&mut self, top: bool) -> Option<(Name, NameInfo)> {
+        let mut info = NameInfo::default();
+        let name = match self.peek() {
+            b'N' => return self.nested_name(top),
+            b'Z' => return self.local_name(top),
+            // Only templates are named by substitutions outside of nested names
+            b'S' if self.peek_at(1) != b't' => {
+                let mut name = name_of(self.substitution()?.0);
+                if self.peek() != b'I' {
+                    return None;
+                }
+                let args = self.template_args(top)?;
+                set_arguments(&mut name, args)?;
+                info.template = true;
+                name
+            }
+            _ => {
+                let mut name = Name::default();
+                if self.eat_str("St") {
+                    name.segments.push(Segment::new("std"));
+                }
+                let segment = self.unqualified_name(&name, &mut info)?;
+                name.segments.push(segment);
+                if self.peek() == b'I' {
+                    self.substitutions.push(Type::Named(name.clone()));
+                    let args = self.template_args(top)?;
+                    set_arguments(&mut name, args)?;
+                    info.template = true;
+                }
+                name
+            }
+        };
+        Some((name, info))
+    }
+
+    // N, the qualifiers of a member function, then the prefixes of the name and E. Each prefix is a substitution,
+    // the whole name is not.
+    fn nested_name(&mut self, top: bool) -> Option<(Name, NameInfo)> {
+        self.expect(b'N')?;
+        let mut info = NameInfo { qualifiers: self.cv_qualifiers(), ..NameInfo::default() };
+        info.reference = match self.peek() {
+            b'R' => Some("&"),
+            b'O' => Some("&&"),
+            _ => None,
+        };
+        if info.reference.is_some() {
+            self.pos += 1;
+        }
+        let mut name = Name::default();
+        let mut pushed = false;
+        while !self.eat(b'E') {
+            pushed = true;
+            match (self.peek(), self.peek_at(1)) {
+                (b'S', b't') => {
+                    self.pos += 2;
+                    name.segments.push(Segment::new("std"));
+                    pushed = false;
+                }
+                (b'S', _) if name.segments.is_empty() => {
+                    let (ty, full) = self.substitution()?;
+                    name = match full {
+                        Some(full) if matches!(self.peek(), b'C' | b'D') => full,
+                        _ => name_of(ty),
+                    };
+                    info.template = false;
+                    pushed = false;
+                }
+                (b'T', _) if name.segments.is_empty() => {
+                    name = name_of(self.template_param()?);
+                    info.template = false;
+                }
+                (b'D', b't') | (b'D', b'T') if name.segments.is_empty() => {
+                    name = name_of(self.ty()?);
+                    info.template = false;
+                }
+                (b'I', _) => {
+                    if name.segments.is_empty() {
+                        return None;
+                    }
+                    let args = self.template_args(top)?;
+                    set_arguments(&mut name, args)?;
+                    info.template = true;
+                }
+                // Closes the data member that a lambda in its initializer belongs to
+                (b'M', _) => {
+                    self.pos += 1;
+                    pushed = false;
+                }
+                _ => {
+                    let segment = self.unqualified_name(&name, &mut info)?;
+                    name.segments.push(segment);
+                    info.template = false;
+                }
+            }
+            if pushed {
+                self.substitutions.push(Type::Named(name.clone()));
+            }
+        }
+        if pushed {
+            self.substitutions.pop();
+        }
+        if name.segments.is_empty() {
+            return None;
+        }
+        Some((name, info))
+    }
+
+    // Z, the function an entity is local to, E and the entity
+    fn local_name(&mut self, top: bool) -> Option<(Name, NameInfo)> {
+        self.expect(b'Z')?;
+        // The function keeps its template arguments to itself
+        let template_args = std::mem::take(&mut self.template_args);
+        let function = self.encoding();
+        self.template_args = template_args;
+        let mut function = function?;
+        self.expect(b'E')?;
+        // Shown without its return type
+        function.return_type = None;
+        let mut name = Name { segments: vec![Segment::new(&function.to_string())] };
+        if self.eat(b's') {
+            name.segments.push(Segment::new("string literal"));
+            self.discriminator()?;
+            return Some((name, NameInfo::default()));
+        }
+        // Entities in default arguments, numbered from the last parameter
+        if self.eat(b'd') {
+            let number = if self.peek() == b'_' { 0 } else { self.number().filter(|x| *x >= 0)? + 1 };
+            self.expect(b'_')?;
+            name.segments.push(Segment::new(&format!("{{default arg#{}}}", number + 1)));
+        }
+        let (entity, info) = self.name(top)?;
+        self.discriminator()?;
+        name.segments.extend(entity.segments);
+        Some((name, info))
+    }
+
+    // A segment of a name, after the ones in `prefix`, with its ABI tags
+    fn unqualified_name(&mut self, prefix: &Name, info: &mut NameInfo) -> Option<Segment> {
+        let mut name = match self.peek() {
+            b'0'..=b'9' => self.source_name()?,
+            // Internal linkage
+            b'L' => {
+                self.pos += 1;
+                let res = self.source_name()?;
+                self.discriminator()?;
+                res
+            }
+            b'C' => {
+                self.pos += 1;
+                let inheriting = self.eat(b'I');
+                if !matches!(self.next()?, b'1'..=b'5') {
+                    return None;
+                }
+                if inheriting {
+                    self.ty()?;
+                }
+                info.no_return_type = true;
+                base_name(prefix)?
+            }
+            b'D' if matches!(self.peek_at(1), b'0' | b'1' | b'2' | b'4' | b'5') => {
+                self.pos += 2;
+                info.no_return_type = true;
+                format!("~{}", base_name(prefix)?)
+            }
+            b'U' => self.unnamed_type()?,
+            b'a'..=b'z' => self.operator_name(info)?,
+            _ => return None,
+        };
+        while self.eat(b'B') {
+            let tag = self.source_name()?;
+            name = format!("{}[abi:{}]", name, tag);
+        }
+        Some(Segment { name, ..Segment::default() })
+    }
+
+    fn operator_name(&mut self, info: &mut NameInfo) -> Option<String> {
+        let code = [self.peek(), self.peek_at(1)];
+        self.pos += 2;
+        match &code {
+            b"cv" => {
+                info.no_return_type = true;
+                Some(format!("operator {}", self.ty()?))
+            }
+            b"li" => Some(format!("operator\"\" {}", self.source_name()?)),
+            [b'v', b'0'..=b'9'] => Some(format!("operator {}", self.source_name()?)),
+            _ => {
+                let (_, text, _) = OPERATORS.iter().find(|x| x.0.as_bytes() == code)?;
+                match text.as_bytes()[0].is_ascii_alphabetic() {
+                    true => Some(format!("operator {}", text)),
+                    false => Some(format!("operator{}", text)),
+                }
+            }
+        }
+    }
+
+    // Unnamed classes and the closure types of lambdas, with the parameters of the lambda
+    fn unnamed_type(&mut self) -> Option<String> {
+        self.expect(b'U')?;
+        match self.next()? {
+            b't' => Some(format!("{{unnamed type#{}}}", self.unnamed_number()?)),
+            b'l' => {
+                let in_lambda = self.in_lambda;
+                self.in_lambda = true;
+                let mut parameters = Vec::new();
+                while !self.eat(b'E') {
+                    match self.ty() {
+                        Some(x) => parameters.push(x),
+                        None => {
+                            self.in_lambda = in_lambda;
+                            return None;
+                        }
+                    }
+                }
+                self.in_lambda = in_lambda;
+                let parameters = list(void_list(parameters).iter().map(|x| x.to_string()));
+                Some(format!("{{lambda({})#{}}}", parameters, self.unnamed_number()?))
+            }
+            _ => None,
+        }
+    }
+
+    fn template_args(&mut self, top: bool) -> Option<Vec<TemplateArg>> {
+        self.expect(b'I')?;
+        let mut args = Vec::new();
+        while !self.eat(b'E') {
+            args.push(self.template_arg()?);
+        }
+        if top {
+            self.template_args = args.clone();
+        }
+        Some(args)
+    }
+
+    fn template_arg(&mut self) -> Option<TemplateArg> {
+        match self.peek() {
+            b'L' => Some(TemplateArg::Value(self.literal()?)),
+            b'X' => {
+                self.pos += 1;
+                let res = self.expression()?;
+                self.expect(b'E')?;
+                Some(TemplateArg::Value(res))
+            }
+            b'J' => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                while !self.eat(b'E') {
+                    args.push(self.template_arg()?);
+                }
+                Some(TemplateArg::Pack(args))
+            }
+            _ => Some(TemplateArg::Type(self.ty()?)),
+        }
+    }
+
+    fn template_param(&mut self) -> Option<Type> {
+        let index = self.template_param_index()?;
+        self.template_param_type(index)
+    }
+
+    fn template_param_type(&mut self, index: usize) -> Option<Type> {
+        if self.in_lambda {
+            return Some(Type::Builtin(format!("auto:{}", index + 1)));
+        }
+        self.template_arg_type(index)
+    }
+
+    fn template_param_index(&mut self) -> Option<usize> {
+        self.expect(b'T')?;
+        match self.eat(b'_') {
+            true => Some(0),
+            false => {
+                let res = self.number().filter(|x| *x >= 0)? as usize + 1;
+                self.expect(b'_')?;
+                Some(res)
+            }
+        }
+    }
+
+    // A substitution for a template parameter, noted so that it refers to the arguments in use where it's used
+    fn push_template_param(&mut self, index: usize, param: Type, start: usize) {
+        if !self.in_lambda {
+            self.parameters.insert(self.substitutions.len(), index);
+            self.last_parameter = Some((self.substitutions.len(), start));
+        }
+        self.substitutions.push(param);
+    }
+
+    // A reference to what was parsed from `start`: if that's a template parameter, the substitution for it stays
+    // the argument it is now
+    fn referenced(&mut self, start: usize) {
+        let Some((substitution, _)) = self.last_parameter.filter(|x| x.1 == start) else { return };
+        if let Some(ty) = self.parameters.remove(&substitution).and_then(|x| self.template_arg_type(x)) {
+            self.substitutions[substitution] = ty;
+        }
+    }
+
+    // The template argument of the function being parsed as a type
+    fn template_arg_type(&mut self, index: usize) -> Option<Type> {
+        let arg = self.template_args.get(index)?;
+        self.budget = self.budget.checked_sub(arg_weight(arg))?;
+        Some(match arg {
+            TemplateArg::Type(ty) => ty.clone(),
+            TemplateArg::Pack(args) => Type::Pack(args.clone()),
+            TemplateArg::Value(value) => Type::Verbatim(value.clone()),
+        })
+    }
+
+    // S_, S0_ and so on, or an abbreviation. Gives the full name of the abbreviations that have one.
+    fn substitution(&mut self) -> Option<(Type, Option<Name>)> {
+        let start = self.pos;
+        self.expect(b'S')?;
+        let index = match self.peek() {
+            b'_' => 0,
+            b'0'..=b'9' | b'A'..=b'Z' => self.seq_id()? + 1,
+            c => {
+                self.pos += 1;
+                return abbreviation(c);
+            }
+        };
+        self.expect(b'_')?;
+        if let Some(param) = self.parameters.get(&index).copied() {
+            if let Some(ty) = self.template_arg_type(param) {
+                self.last_parameter = Some((index, start));
+                return Some((ty, None));
+            }
+        }
+        let ty = self.substitutions.get(index)?;
+        self.budget = self.budget.checked_sub(weight(ty))?;
+        Some((ty.clone(), None))
+    }
+
+    fn ty(&mut self) -> Option<Type> {
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let res = self.ty_inner();
+        self.depth -= 1;
+        res
+    }
+
+    // Types other than builtins and plain substitutions become substitutions themselves
+    fn ty_inner(&mut self) -> Option<Type> {
+        let c = self.peek();
+        if let Some(name) = builtin(c) {
+            self.pos += 1;
+            return Some(Type::Builtin(name.to_string()));
+        }
+        if let Some(name) = extended_builtin(self.peek_at(1)).filter(|_| c == b'D') {
+            self.pos += 2;
+            return Some(Type::Builtin(name.to_string()));
+        }
+        let res = match c {
+            b'D' => match self.peek_at(1) {
+                b'F' => {
+                    self.pos += 2;
+                    let bits = self.number()?;
+                    let res = match self.next()? {
+                        b'_' => format!("_Float{}", bits),
+                        b'x' => format!("_Float{}x", bits),
+                        b'b' if bits == 16 => "std::bfloat16_t".to_string(),
+                        _ => return None,
+                    };
+                    return Some(Type::Builtin(res));
+                }
+                b'B' | b'U' => {
+                    let unsigned = self.peek_at(1) == b'U';
+                    self.pos += 2;
+                    let bits = self.number()?;
+                    self.expect(b'_')?;
+                    let sign = if unsigned { "unsigned " } else { "" };
+                    return Some(Type::Builtin(format!("{}_BitInt({})", sign, bits)));
+                }
+                b'p' => {
+                    self.pos += 2;
+                    Type::PackExpansion(Box::new(self.ty()?))
+                }
+                b't' | b'T' => {
+                    self.pos += 2;
+                    let res = self.expression()?;
+                    self.expect(b'E')?;
+                    Type::Verbatim(format!("decltype({})", res))
+                }
+                b'v' => {
+                    self.pos += 2;
+                    let size = self.number()?;
+                    self.expect(b'_')?;
+                    Type::Verbatim(format!("{} __vector({})", self.ty()?, size))
+                }
+                b'o' | b'O' | b'w' | b'x' => self.function_type()?,
+                _ => return None,
+            },
+            // Vendor extended types
+            b'u' => {
+                self.pos += 1;
+                Type::Builtin(self.source_name()?)
+            }
+            b'K' | b'V' | b'r' => {
+                let qualifiers = self.cv_qualifiers();
+                match self.ty()? {
+                    Type::Function(mut function) => {
+                        // The qualifiers apply to this, so only the qualified function type is a substitution
+                        self.substitutions.pop();
+                        function.qualifiers = qualifiers;
+                        Type::Function(function)
+                    }
+                    // Qualifying what a template parameter already qualifies
+                    Type::Qualified(inner, mut existing) => {
+                        existing.constant |= qualifiers.constant;
+                        existing.volatile |= qualifiers.volatile;
+                        existing.restrict |= qualifiers.restrict;
+                        Type::Qualified(inner, existing)
+                    }
+                    inner => Type::Qualified(Box::new(inner), qualifiers),
+                }
+            }
+            // Vendor extended qualifiers
+            b'U' => {
+                self.pos += 1;
+                let qualifier = self.source_name()?;
+                if self.peek() == b'I' {
+                    self.template_args(false)?;
+                }
+                Type::Verbatim(format!("{} {}", self.ty()?, qualifier))
+            }
+            b'P' => {
+                self.pos += 1;
+                Type::Pointer(Box::new(self.ty()?))
+            }
+            b'R' => {
+                self.pos += 1;
+                let start = self.pos;
+                let inner = self.ty()?;
+                self.referenced(start);
+                Type::Reference(Box::new(inner))
+            }
+            b'O' => {
+                self.pos += 1;
+                let start = self.pos;
+                let inner = self.ty()?;
+                self.referenced(start);
+                Type::RValueReference(Box::new(inner))
+            }
+            b'C' => {
+                self.pos += 1;
+                Type::Verbatim(format!("{} _Complex", self.ty()?))
+            }
+            b'G' => {
+                self.pos += 1;
+                Type::Verbatim(format!("{} _Imaginary", self.ty()?))
+            }
+            b'F' => self.function_type()?,
+            b'A' => {
+                self.pos += 1;
+                let dimension = match self.peek() {
+                    b'_' => String::new(),
+                    b'0'..=b'9' => self.number()?.to_string(),
+                    _ => self.expression()?,
+                };
+                self.expect(b'_')?;
+                Type::Array(Box::new(self.ty()?), dimension)
+            }
+            b'M' => {
+                self.pos += 1;
+                let class = self.ty()?;
+                Type::MemberPointer(Box::new(class), Box::new(self.ty()?))
+            }
+            b'T' => match self.peek_at(1) {
+                // struct, union and enum spelled out
+                b's' | b'u' | b'e' => {
+                    self.pos += 2;
+                    Type::Named(self.name(false)?.0)
+                }
+                _ => {
+                    let start = self.pos;
+                    let index = self.template_param_index()?;
+                    let param = self.template_param_type(index)?;
+                    self.push_template_param(index, param.clone(), start);
+                    if self.peek() != b'I' {
+                        return Some(param);
+                    }
+                    // A template template parameter
+                    let args = self.template_args(false)?;
+                    let mut name = name_of(param);
+                    set_arguments(&mut name, args)?;
+                    Type::Named(name)
+                }
+            },
+            b'S' if self.peek_at(1) != b't' => {
+                let (ty, _) = self.substitution()?;
+                if self.peek() != b'I' {
+                    return Some(ty);
+                }
+                let args = self.template_args(false)?;
+                let mut name = name_of(ty);
+                set_arguments(&mut name, args)?;
+                Type::Named(name)
+            }
+            b'0'..=b'9' | b'N' | b'Z' | b'S' => Type::Named(self.name(false)?.0),
+            _ => return None,
+        };
+        self.substitutions.push(res.clone());
+        Some(res)
+    }
+
+    // The exception specification, F, the return type, the parameters, the ref-qualifier and E
+    fn function_type(&mut self) -> Option<Type> {
+        if self.eat_str("DO") {
+            self.expression()?;
+            self.expect(b'E')?;
+        } else if self.eat_str("Dw") {
+            while !self.eat(b'E') {
+                self.ty()?;
+            }
+        } else {
+            self.eat_str("Do");
+        }
+        self.eat_str("Dx");
+        self.expect(b'F')?;
+        // extern "C"
+        self.eat(b'Y');
+        let mut function = FunctionType { return_type: Some(Box::new(self.ty()?)), ..FunctionType::default() };
+        loop {
+            if self.eat(b'E') {
+                break;
+            }
+            if self.eat_str("RE") {
+                function.reference = Some("&");
+                break;
+            }
+            if self.eat_str("OE") {
+                function.reference = Some("&&");
+                break;
+            }
+            function.parameters.push(self.ty()?);
+        }
+        function.parameters = void_list(function.parameters);
+        Some(Type::Function(function))
+    }
+
+    // L, a type and a value, or the mangled name of an entity, and E
+    fn literal(&mut self) -> Option<String> {
+        self.expect(b'L')?;
+        if self.eat_str("_Z") || self.eat(b'Z') {
+            let res = self.encoding()?;
+            self.expect(b'E')?;
+            return Some(res.to_string());
+        }
+        let ty = self.ty()?.to_string();
+        let sign = if self.eat(b'n') { "-" } else { "" };
+        let start = self.pos;
+        while !self.at_end() && self.peek() != b'E' {
+            self.pos += 1;
+        }
+        let value = String::from_utf8_lossy(&self.input[start..self.pos]).into_owned();
+        self.expect(b'E')?;
+        let suffix = match ty.as_str() {
+            "bool" if value == "0" => return Some("false".to_string()),
+            "bool" if value == "1" => return Some("true".to_string()),
+            "decltype(nullptr)" => return Some("nullptr".to_string()),
+            "int" => "",
+            "unsigned int" => "u",
+            "long" => "l",
+            "unsigned long" => "ul",
+            "long long" => "ll",
+            "unsigned long long" => "ull",
+            _ => return Some(format!("({}){}{}", ty, sign, value)),
+        };
+        Some(format!("{}{}{}", sign, value, suffix))
+    }
+
+    fn expression(&mut self) -> Option<String> {
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let res = self.expression_inner();
+        self.depth -= 1;
+        res
+    }
+
+    // A name in an expression that depends on template parameters, like std::is_signed<T>::value: sr, the scope
+    // and the name in it, or just the name
+    fn unresolved_name(&mut self) -> Option<String> {
+        let global = if self.eat_str("gs") { "::" } else { "" };
+        let mut scope = Vec::new();
+        if self.eat_str("srN") {
+            scope.push(self.unresolved_type()?);
+            while !self.eat(b'E') {
+                scope.push(self.simple_id()?);
+            }
+        } else if self.eat_str("sr") {
+            if self.peek().is_ascii_digit() {
+                while !self.eat(b'E') {
+                    scope.push(self.simple_id()?);
+                }
+            } else {
+                scope.push(self.unresolved_type()?);
+            }
+        }
+        let name = if self.eat_str("on") {
+            let mut segment = Segment { name: self.operator_name(&mut NameInfo::default())?, ..Segment::default() };
+            if self.peek() == b'I' {
+                segment.arguments = Some(self.template_args(false)?);
+            }
+            segment.to_string()
+        } else if self.eat_str("dn") {
+            match self.peek().is_ascii_digit() {
+                true => format!("~{}", self.simple_id()?),
+                false => format!("~{}", self.unresolved_type()?),
+            }
+        } else {
+            self.simple_id()?
+        };
+        scope.push(name);
+        Some(format!("{}{}", global, scope.join("::")))
+    }
+
+    // A template parameter, decltype or substitution that an unresolved name is scoped in
+    fn unresolved_type(&mut self) -> Option<String> {
+        let res = match self.peek() {
+            b'T' => {
+                let param = self.template_param()?;
+                self.substitutions.push(param.clone());
+                if self.peek() != b'I' {
+                    return Some(param.to_string());
+                }
+                let mut name = name_of(param);
+                set_arguments(&mut name, self.template_args(false)?)?;
+                Type::Named(name)
+            }
+            b'D' => self.ty()?,
+            b'S' => {
+                let (ty, _) = self.substitution()?;
+                if self.peek() != b'I' {
+                    return Some(ty.to_string());
+                }
+                let mut name = name_of(ty);
+                set_arguments(&mut name, self.template_args(false)?)?;
+                Type::Named(name)
+            }
+            _ => return None,
+        };
+        Some(res.to_string())
+    }
+
+    // A source name with template arguments, if it has any
+    fn simple_id(&mut self) -> Option<String> {
+        let mut segment = Segment { name: self.source_name()?, ..Segment::default() };
+        if self.peek() == b'I' {
+            segment.arguments = Some(self.template_args(false)?);
+        }
+        Some(segment.to_string())
+    }
+
+    // The expressions of template arguments, array bounds and decltype. Member accesses, casts other than the C
+    // style one and the like are not understood.
+    fn expression_inner(&mut self) -> Option<String> {
+        let code = [self.peek(), self.peek_at(1)];
+        match &code {
+            [b'L', _] => self.literal(),
+            [b'T', _] => Some(self.template_param()?.to_string()),
+            b"fp" => {
+                self.pos += 2;
+                self.cv_qualifiers();
+                let number = if self.peek() == b'_' { 1 } else { self.number().filter(|x| *x >= 0)? + 2 };
+                self.expect(b'_')?;
+                Some(format!("{{parm#{}}}", number))
+            }
+            b"sr" | b"gs" | [b'0'..=b'9', _] | b"on" | b"dn" => self.unresolved_name(),
+            b"sZ" => {
+                self.pos += 2;
+                Some(format!("sizeof...({})", self.expression()?))
+            }
+            b"st" | b"at" => {
+                self.pos += 2;
+                let operator = if code[0] == b's' { "sizeof" } else { "alignof" };
+                Some(format!("{} ({})", operator, self.ty()?))
+            }
+            b"sz" | b"az" => {
+                self.pos += 2;
+                let operator = if code[0] == b's' { "sizeof" } else { "alignof" };
+                Some(format!("{} ({})", operator, self.expression()?))
+            }
+            b"cv" => {
+                self.pos += 2;
+                let ty = self.ty()?;
+                let mut operands = Vec::new();
+                if self.eat(b'_') {
+                    while !self.eat(b'E') {
+                        operands.push(self.expression()?);
+                    }
+                } else {
+                    operands.push(self.expression()?);
+                }
+                Some(format!("({})({})", ty, operands.join(", ")))
+            }
+            b"cl" => {
+                self.pos += 2;
+                let function = self.expression()?;
+                let mut arguments = Vec::new();
+                while !self.eat(b'E') {
+                    arguments.push(self.expression()?);
+                }
+                Some(format!("{}({})", function, arguments.join(", ")))
+            }
+            _ => {
+                let (_, text, arity) = OPERATORS.iter().find(|x| x.0.as_bytes() == code)?;
+                self.pos += 2;
+                match arity {
+                    1 => Some(format!("{}({})", text, self.expression()?)),
+                    2 => {
+                        let a = self.expression()?;
+                        Some(format!("({}){}({})", a, text, self.expression()?))
+                    }
+                    3 => {
+                        let a = self.expression()?;
+                        let b = self.expression()?;
+                        Some(format!("({})?({}):({})", a, b, self.expression()?))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demangle;
+
+    fn demangled(symbol: &str) -> String {
+        demangle(symbol).map(|x| x.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn basics() {
+        let cases = [
+            ("_ZNKSt6vectorIiSaIiEE4sizeEv", "std::vector<int, std::allocator<int>>::size() const"),
+            ("_ZN9__gnu_cxx13new_allocatorIcED2Ev", "__gnu_cxx::new_allocator<char>::~new_allocator()"),
+            ("_ZN3FooclERKS_", "Foo::operator()(Foo const&)"),
+            ("_Z3fooPFivEA10_i", "foo(int (*)(), int [10])"),
+            ("_Z1fIJidEEvDpT_", "void f<int, double>(int, double)"),
+            ("_ZZ4mainE5count", "main::count"),
+            ("_ZTV3Foo", "vtable for Foo"),
+            ("_ZThn8_N3Foo3barEv", "non-virtual thunk to Foo::bar()"),
+            ("_Z3foov.cold", "foo() [clone .cold]"),
+        ];
+        for (symbol, text) in cases.iter() {
+            assert_eq!(demangled(symbol), *text, "{}", symbol);
+        }
+        assert_eq!(demangled("main"), "");
+    }
+
+    #[test]
+    fn template_params_of_local_names() {
+        // SC_ is the T_ of LegalizeUpdates, parsed in the closure type but standing for the T_ of __insertion_sort
+        let symbol = "_ZSt16__insertion_sortIPN4llvm3cfg6UpdateIPNS0_10BasicBlockEEEN9__gnu_cxx5__ops15_Iter_comp_iterIZNS1_\
+                      15LegalizeUpdatesIS4_EEvNS0_8ArrayRefINS2_IT_EEEERNS0_15SmallVectorImplISD_EEbbEUlRKS5_SJ_E_EEEvSC_SC_T0_";
+        let update = "llvm::cfg::Update<llvm::BasicBlock*>";
+        let lambda = format!(
+            "llvm::cfg::LegalizeUpdates<llvm::BasicBlock*>(llvm::ArrayRef<{0}>, llvm::SmallVectorImpl<{0}>&, bool, bool)::\
+             {{lambda({0} const&, {0} const&)#1}}",
+            update
+        );
+        let compare = format!("__gnu_cxx::__ops::_Iter_comp_iter<{}>", lambda);
+        let expected = format!("void std::__insertion_sort<{0}*, {1}>({0}*, {0}*, {1})", update, compare);
+        assert_eq!(demangled(symbol), expected);
+
+        // A reference made in the local name keeps the argument it was made with
+        assert_eq!(demangled("_ZN1AC1IZ1gIiEvOT_EUlvE_EERS2_"), "A::A<g<int>(int&&)::{lambda()#1}>(int&)");
+        // T_ after a local name is a template parameter of the function again
+        assert_eq!(demangled("_Z1fIiEvZ1gIcEvT_E1xT_"), "void f<int>(g<char>(char)::x, int)");
+    }
+}
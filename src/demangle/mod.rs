@@ -0,0 +1,434 @@
+//! Demangling of the names of C++ (the Itanium ABI) and Rust (the legacy and v0 schemes) symbols, into a structure
+//! with the namespaces, template arguments and parameter types, and into the text tools like c++filt show.
+
+use std::fmt;
+
+mod itanium;
+mod rust_legacy;
+mod rust_v0;
+
+/// The mangling scheme of a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Itanium,
+    // Itanium nested names ending in a hash, what rustc uses unless asked for v0
+    RustLegacy,
+    RustV0,
+}
+
+/// A part of a qualified name: a namespace, a class, a function and so on
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Segment {
+    // As it is shown: an identifier, an operator, a destructor, {lambda()#1} and so on
+    pub name: String,
+    // None unless the segment is a template instance
+    pub arguments: Option<Vec<TemplateArg>>,
+    // Rust paths of values put generic arguments after ::
+    pub turbofish: bool,
+}
+
+impl Segment {
+    pub fn new(name: &str) -> Segment {
+        Segment { name: name.to_string(), ..Segment::default() }
+    }
+}
+
+/// A qualified name, outermost segment first
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Name {
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Qualifiers {
+    pub constant: bool,
+    pub volatile: bool,
+    pub restrict: bool,
+}
+
+impl Qualifiers {
+    pub fn is_empty(&self) -> bool {
+        !self.constant && !self.volatile && !self.restrict
+    }
+}
+
+/// The type of a function, of function pointers and the like
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FunctionType {
+    pub return_type: Option<Box<Type>>,
+    pub parameters: Vec<Type>,
+    pub qualifiers: Qualifiers,
+    // & or && of member functions
+    pub reference: Option<&'static str>,
+}
+
+/// A type, as far as the mangling tells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    // int, unsigned long, decltype(nullptr), and the primitive types of Rust
+    Builtin(String),
+    // Classes, enums, and the paths of Rust
+    Named(Name),
+    Qualified(Box<Type>, Qualifiers),
+    Pointer(Box<Type>),
+    Reference(Box<Type>),
+    RValueReference(Box<Type>),
+    Function(FunctionType),
+    // With the dimension as shown, empty when the array has no bound
+    Array(Box<Type>, String),
+    // The class, then the type of the member
+    MemberPointer(Box<Type>, Box<Type>),
+    // A template parameter pack, which expansions show one element at a time
+    Pack(Vec<TemplateArg>),
+    PackExpansion(Box<Type>),
+    // Types only kept as text: vector types, decltype, and the compound types of Rust
+    Verbatim(String),
+}
+
+/// An argument of a template or of generic parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateArg {
+    Type(Type),
+    // Literals and expressions, as shown
+    Value(String),
+    Pack(Vec<TemplateArg>),
+}
+
+/// What a special symbol is for, besides the entity it names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Special {
+    VTable,
+    Vtt,
+    TypeInfo,
+    TypeInfoName,
+    // The vtable of the named class as a base of this one
+    ConstructionVTable(Type),
+    Guard,
+    ReferenceTemporary(u64),
+    TlsInit,
+    TlsWrapper,
+    TransactionClone,
+    NonTransactionClone,
+    HiddenAlias,
+    NonVirtualThunk,
+    VirtualThunk,
+    CovariantThunk,
+}
+
+impl Special {
+    fn prefix(&self) -> String {
+        let res = match self {
+            Special::VTable => "vtable for ",
+            Special::Vtt => "VTT for ",
+            Special::TypeInfo => "typeinfo for ",
+            Special::TypeInfoName => "typeinfo name for ",
+            Special::ConstructionVTable(_) => "construction vtable for ",
+            Special::Guard => "guard variable for ",
+            Special::ReferenceTemporary(n) => return format!("reference temporary #{} for ", n),
+            Special::TlsInit => "TLS init function for ",
+            Special::TlsWrapper => "TLS wrapper function for ",
+            Special::TransactionClone => "transaction clone for ",
+            Special::NonTransactionClone => "non-transaction clone for ",
+            Special::HiddenAlias => "hidden alias for ",
+            Special::NonVirtualThunk => "non-virtual thunk to ",
+            Special::VirtualThunk => "virtual thunk to ",
+            Special::CovariantThunk => "covariant return thunk to ",
+        };
+        res.to_string()
+    }
+}
+
+/// A demangled symbol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Demangled {
+    pub scheme: Scheme,
+    pub special: Option<Special>,
+    pub name: Name,
+    // Only template functions have theirs in the mangling
+    pub return_type: Option<Type>,
+    // None for data, and for Rust functions, whose manglings don't have them
+    pub parameters: Option<Vec<Type>>,
+    // Of member functions
+    pub qualifiers: Qualifiers,
+    pub reference: Option<&'static str>,
+    // What the compiler appended: .cold and .isra.0 in C++, .llvm.123 in Rust
+    pub suffixes: Vec<String>,
+    // The hash of Rust legacy symbols, which the text leaves out
+    pub hash: Option<String>,
+}
+
+impl Demangled {
+    fn new(scheme: Scheme, name: Name) -> Demangled {
+        Demangled {
+            scheme,
+            special: None,
+            name,
+            return_type: None,
+            parameters: None,
+            qualifiers: Qualifiers::default(),
+            reference: None,
+            suffixes: Vec::new(),
+            hash: None,
+        }
+    }
+
+    /// The segments around the entity: namespaces, classes and enclosing functions
+    pub fn namespaces(&self) -> &[Segment] {
+        &self.name.segments[..self.name.segments.len().saturating_sub(1)]
+    }
+
+    /// The entity itself, without its namespaces
+    pub fn base_name(&self) -> Option<&Segment> {
+        self.name.segments.last()
+    }
+}
+
+/// Demangles a C++ or Rust symbol, None for names that aren't mangled or that this doesn't understand
+pub fn demangle(symbol: &str) -> Option<Demangled> {
+    if symbol.starts_with("_R") {
+        return rust_v0::demangle(symbol);
+    }
+    rust_legacy::demangle(symbol).or_else(|| itanium::demangle(symbol))
+}
+
+/// The demangled text of a symbol, or the symbol as it is
+pub fn demangled_name(symbol: &str) -> String {
+    match demangle(symbol) {
+        Some(x) => x.to_string(),
+        None => symbol.to_string(),
+    }
+}
+
+impl fmt::Display for Demangled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(special) = &self.special {
+            write!(f, "{}", special.prefix())?;
+        }
+        if let Some(ty) = &self.return_type {
+            write!(f, "{} ", ty)?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(parameters) = &self.parameters {
+            write!(f, "({})", list(parameters.iter().map(|x| x.to_string())))?;
+            write!(f, "{}", qualifiers(&self.qualifiers))?;
+            if let Some(reference) = self.reference {
+                write!(f, " {}", reference)?;
+            }
+        }
+        if let Some(Special::ConstructionVTable(ty)) = &self.special {
+            write!(f, "-in-{}", ty)?;
+        }
+        for suffix in self.suffixes.iter() {
+            match self.scheme {
+                Scheme::Itanium => write!(f, " [clone {}]", suffix)?,
+                // The hash LLVM gives symbols it makes local tells nothing to the reader
+                _ => match suffix.strip_prefix(".llvm.") {
+                    Some(rest) => write!(f, "{}", rest.trim_start_matches(|x: char| x.is_ascii_hexdigit()))?,
+                    None => write!(f, "{}", suffix)?,
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i != 0 {
+                write!(f, "::")?;
+            }
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(arguments) = &self.arguments {
+            // operator< <int> rather than operator<<int>
+            let separator = match (self.turbofish, self.name.ends_with('<')) {
+                (true, _) => "::",
+                (false, true) => " ",
+                (false, false) => "",
+            };
+            write!(f, "{}<{}>", separator, list(arguments.iter().map(|x| x.to_string())))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TemplateArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateArg::Type(ty) => write!(f, "{}", ty),
+            TemplateArg::Value(value) => write!(f, "{}", value),
+            TemplateArg::Pack(args) => write!(f, "{}", list(args.iter().map(|x| x.to_string()))),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", left(self, None), right(self, None))
+    }
+}
+
+// Joins the elements of a list, leaving out empty packs
+fn list(items: impl Iterator<Item = String>) -> String {
+    items.filter(|x| !x.is_empty()).collect::<Vec<String>>().join(", ")
+}
+
+fn qualifiers(qualifiers: &Qualifiers) -> String {
+    let mut res = String::new();
+    for (set, text) in [(qualifiers.constant, " const"), (qualifiers.volatile, " volatile"), (qualifiers.restrict, " restrict")] {
+        if set {
+            res.push_str(text);
+        }
+    }
+    res
+}
+
+// Types are shown as declarators: the part left of where a name would go, and the part right of it, as in
+// `void (*)(int)`. Within the expansion of a pack, `pack` is the element being shown.
+fn left(ty: &Type, pack: Option<usize>) -> String {
+    match ty {
+        Type::Builtin(name) | Type::Verbatim(name) => name.clone(),
+        Type::Named(name) => name.to_string(),
+        Type::Qualified(inner, q) => match **inner {
+            Type::Function(_) => left(inner, pack),
+            _ => format!("{}{}", left(inner, pack), qualifiers(q)),
+        },
+        Type::Pointer(inner) => declarator(inner, "*", pack),
+        Type::Reference(_) | Type::RValueReference(_) => {
+            let (operator, inner, pack) = collapse(ty, pack);
+            declarator(inner, operator, pack)
+        }
+        Type::Function(function) => match &function.return_type {
+            Some(ty) => format!("{}{} ", left(ty, pack), right(ty, pack)),
+            None => String::new(),
+        },
+        Type::Array(element, _) => left(element, pack),
+        Type::MemberPointer(class, member) => {
+            let class = format!("{}{}", left(class, pack), right(class, pack));
+            match strip_qualifiers(member) {
+                Type::Function(_) => format!("{}({}::*", left(member, pack), class),
+                _ => format!("{} {}::*", left(member, pack), class),
+            }
+        }
+        Type::Pack(args) => match pack.and_then(|x| args.get(x)) {
+            Some(TemplateArg::Type(ty)) => left(ty, None),
+            Some(arg) => arg.to_string(),
+            None => list(args.iter().map(|x| x.to_string())),
+        },
+        Type::PackExpansion(inner) => match pack_size(inner) {
+            Some(size) => list((0..size).map(|i| format!("{}{}", left(inner, Some(i)), right(inner, Some(i))))),
+            None => format!("{}{}...", left(inner, pack), right(inner, pack)),
+        },
+    }
+}
+
+fn right(ty: &Type, pack: Option<usize>) -> String {
+    match ty {
+        Type::Qualified(inner, q) => match **inner {
+            Type::Function(_) => format!("{}{}", right(inner, pack), qualifiers(q)),
+            _ => right(inner, pack),
+        },
+        Type::Pointer(_) | Type::Reference(_) | Type::RValueReference(_) => {
+            let (_, inner, pack) = collapse(ty, pack);
+            match strip_qualifiers(inner) {
+                Type::Function(_) | Type::Array(..) => format!("){}", right(inner, pack)),
+                _ => right(inner, pack),
+            }
+        }
+        Type::Function(function) => {
+            let parameters = list(function.parameters.iter().map(|x| format!("{}{}", left(x, pack), right(x, pack))));
+            let reference = function.reference.map(|x| format!(" {}", x)).unwrap_or_default();
+            format!("({}){}{}", parameters, qualifiers(&function.qualifiers), reference)
+        }
+        Type::Array(element, dimension) => {
+            // Dimensions of nested arrays follow one another, int [2][3]
+            let rest = right(element, pack);
+            format!(" [{}]{}", dimension, rest.strip_prefix(' ').filter(|x| x.starts_with('[')).unwrap_or(&rest))
+        }
+        Type::MemberPointer(_, member) => match strip_qualifiers(member) {
+            Type::Function(_) => format!("){}", right(member, pack)),
+            _ => right(member, pack),
+        },
+        Type::Pack(args) => match pack.and_then(|x| args.get(x)) {
+            Some(TemplateArg::Type(ty)) => right(ty, None),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+// The element of a pack being shown, if the type is a pack
+fn resolve(ty: &Type, pack: Option<usize>) -> (&Type, Option<usize>) {
+    match (ty, pack) {
+        (Type::Pack(args), Some(i)) => match args.get(i) {
+            Some(TemplateArg::Type(ty)) => (ty, None),
+            _ => (ty, pack),
+        },
+        _ => (ty, pack),
+    }
+}
+
+// What a pointer or reference points to, with references to references collapsed: & wins over &&
+fn collapse(ty: &Type, pack: Option<usize>) -> (&'static str, &Type, Option<usize>) {
+    let (mut operator, mut inner) = match ty {
+        Type::Pointer(inner) => {
+            let (inner, pack) = resolve(inner, pack);
+            return ("*", inner, pack);
+        }
+        Type::Reference(inner) => ("&", &**inner),
+        Type::RValueReference(inner) => ("&&", &**inner),
+        _ => return ("", ty, pack),
+    };
+    let mut pack = pack;
+    loop {
+        let (resolved, index) = resolve(inner, pack);
+        match resolved {
+            Type::Reference(x) => {
+                operator = "&";
+                inner = x;
+            }
+            Type::RValueReference(x) => inner = x,
+            _ => return (operator, resolved, index),
+        }
+        pack = index;
+    }
+}
+
+// Pointers and references to functions and arrays wrap what they point to in parentheses
+fn declarator(inner: &Type, operator: &str, pack: Option<usize>) -> String {
+    let (inner, pack) = resolve(inner, pack);
+    match strip_qualifiers(inner) {
+        Type::Function(_) => format!("{}({}", left(inner, pack), operator),
+        Type::Array(..) => format!("{} ({}", left(inner, pack), operator),
+        _ => format!("{}{}", left(inner, pack), operator),
+    }
+}
+
+fn strip_qualifiers(ty: &Type) -> &Type {
+    match ty {
+        Type::Qualified(inner, _) => strip_qualifiers(inner),
+        _ => ty,
+    }
+}
+
+// The number of elements of the first pack in a type, which an expansion repeats the type for
+fn pack_size(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Pack(args) => Some(args.len()),
+        Type::Qualified(inner, _) | Type::Pointer(inner) | Type::Reference(inner) | Type::RValueReference(inner) => pack_size(inner),
+        Type::Array(inner, _) => pack_size(inner),
+        Type::MemberPointer(class, member) => pack_size(class).or_else(|| pack_size(member)),
+        Type::Function(function) => {
+            function.return_type.as_deref().and_then(pack_size).or_else(|| function.parameters.iter().find_map(pack_size))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,101 @@
+use super::{Demangled, Name, Scheme, Segment};
+
+// Escapes of the characters identifiers can't have, as $name$
+const ESCAPES: &[(&str, &str)] =
+    &[("SP", "@"), ("BP", "*"), ("RF", "&"), ("LT", "<"), ("GT", ">"), ("LP", "("), ("RP", ")"), ("C", ",")];
+
+/// Demangles Rust symbols of the legacy scheme: an Itanium nested name of escaped identifiers, the last of which is
+/// h and 16 hexadecimal digits
+pub(super) fn demangle(symbol: &str) -> Option<Demangled> {
+    let input = symbol.strip_prefix("_ZN").or_else(|| symbol.strip_prefix("ZN"))?.as_bytes();
+    let mut pos = 0;
+    let mut identifiers = Vec::new();
+    while input.get(pos)? != &b'E' {
+        let start = pos;
+        while input.get(pos)?.is_ascii_digit() {
+            pos += 1;
+        }
+        let length: usize = std::str::from_utf8(&input[start..pos]).ok()?.parse().ok()?;
+        identifiers.push(std::str::from_utf8(input.get(pos..pos.checked_add(length)?)?).ok()?);
+        pos += length;
+    }
+    let hash = identifiers.pop()?.strip_prefix('h').filter(|x| x.len() == 16 && x.bytes().all(|x| x.is_ascii_hexdigit()))?;
+
+    let mut segments = Vec::new();
+    for identifier in identifiers {
+        segments.push(Segment::new(&unescape(identifier)?));
+    }
+    let mut res = Demangled::new(Scheme::RustLegacy, Name { segments });
+    res.hash = Some(hash.to_string());
+    // What LLVM appends to names it makes local, .llvm. and a hash, and the like
+    let rest = std::str::from_utf8(&input[pos + 1..]).ok()?;
+    if !rest.is_empty() {
+        res.suffixes.push(rest.to_string());
+    }
+    Some(res)
+}
+
+// Identifiers that would start with $ have a _ in front, .. stands for ::, and $u and hexadecimal digits for any
+// other character
+fn unescape(identifier: &str) -> Option<String> {
+    let mut rest = match identifier.strip_prefix("_$") {
+        Some(_) => &identifier[1..],
+        None => identifier,
+    };
+    let mut res = String::new();
+    while let Some(c) = rest.chars().next() {
+        if let Some(escaped) = rest.strip_prefix('$') {
+            let end = escaped.find('$')?;
+            let code = &escaped[..end];
+            match ESCAPES.iter().find(|x| x.0 == code) {
+                Some((_, text)) => res.push_str(text),
+                None => res.push(char::from_u32(u32::from_str_radix(code.strip_prefix('u')?, 16).ok()?)?),
+            }
+            rest = &escaped[end + 1..];
+        } else if let Some(after) = rest.strip_prefix("..") {
+            res.push_str("::");
+            rest = after;
+        } else {
+            res.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demangle;
+    use crate::demangle::{Scheme, Segment};
+
+    #[test]
+    fn hash_and_suffixes_are_kept_apart() {
+        let demangled = demangle("_ZN3foo3bar17h05af221e174051e9E.llvm.1234").unwrap();
+        assert_eq!(demangled.scheme, Scheme::RustLegacy);
+        assert_eq!(demangled.namespaces(), [Segment::new("foo")]);
+        assert_eq!(demangled.base_name(), Some(&Segment::new("bar")));
+        assert_eq!(demangled.hash.as_deref(), Some("05af221e174051e9"));
+        assert_eq!(demangled.suffixes, [".llvm.1234"]);
+        assert_eq!(demangled.to_string(), "foo::bar");
+
+        // Without the 16 digit hash this is an Itanium name
+        assert!(demangle("_ZN3foo3bar17h05af221eE").is_none());
+        assert!(demangle("_ZN3foo3barE").is_none());
+    }
+
+    #[test]
+    fn escapes() {
+        let text = |symbol: &str| demangle(symbol).map(|x| x.to_string());
+        assert_eq!(
+            text("_ZN71_$LT$Test$u20$$u2b$$u20$$u27$static$u20$as$u20$foo..Bar$LT$Test$GT$$GT$3bar17h930b740aa94f1d3aE").as_deref(),
+            Some("<Test + 'static as foo::Bar<Test>>::bar")
+        );
+        assert_eq!(
+            text("_ZN4core3ptr85drop_in_place$LT$std..rt..lang_start$LT$$LP$$RP$$GT$..$u7b$$u7b$closure$u7d$$u7d$$GT$17h0f2bd5e3de0c6eb4E")
+                .as_deref(),
+            Some("core::ptr::drop_in_place<std::rt::lang_start<()>::{{closure}}>")
+        );
+        // An escape that isn't closed
+        assert!(text("_ZN2$L17h0f2bd5e3de0c6eb4E").is_none());
+    }
+}
@@ -0,0 +1,555 @@
+use std::convert::TryFrom;
+
+use super::{Demangled, Name, Scheme, Segment, TemplateArg, Type};
+
+// Nesting of paths and types before giving up, against symbols crafted to overflow the stack
+const MAX_DEPTH: usize = 256;
+
+// Paths and types parsed, counting those back references lead to each time
+const MAX_PARSED: usize = 1 << 16;
+
+struct Parser<'a> {
+    // After _R, which back references count from
+    input: &'a [u8],
+    pos: usize,
+    // Lifetimes bound by the for<> of the types being parsed
+    bound_lifetimes: u64,
+    depth: usize,
+    // Paths and types that may still be parsed, as back references parse again what they point to and can make the
+    // work grow exponentially with the length of the symbol
+    budget: usize,
+}
+
+/// Demangles Rust symbols of the v0 scheme: `_R`, the path of the item, the crate it is instantiated in and a
+/// suffix
+pub(super) fn demangle(symbol: &str) -> Option<Demangled> {
+    let input = symbol.strip_prefix("_R")?;
+    // Versions other than the first one would start with a number
+    if input.starts_with(|x: char| x.is_ascii_digit()) {
+        return None;
+    }
+    let (input, suffix) = match input.find('.') {
+        Some(i) => input.split_at(i),
+        None => (input, ""),
+    };
+    let mut parser = Parser { input: input.as_bytes(), pos: 0, bound_lifetimes: 0, depth: 0, budget: MAX_PARSED };
+    let name = parser.path(true)?;
+    // The crate the generic item is instantiated in
+    if !parser.at_end() {
+        parser.path(false)?;
+    }
+    if !parser.at_end() {
+        return None;
+    }
+    let mut res = Demangled::new(Scheme::RustV0, name);
+    if !suffix.is_empty() {
+        res.suffixes.push(suffix.to_string());
+    }
+    Some(res)
+}
+
+fn basic_type(c: u8) -> Option<&'static str> {
+    Some(match c {
+        b'a' => "i8",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "f64",
+        b'e' => "str",
+        b'f' => "f32",
+        b'h' => "u8",
+        b'i' => "isize",
+        b'j' => "usize",
+        b'l' => "i32",
+        b'm' => "u32",
+        b'n' => "i128",
+        b'o' => "u128",
+        b's' => "i16",
+        b't' => "u16",
+        b'u' => "()",
+        b'v' => "...",
+        b'x' => "i64",
+        b'y' => "u64",
+        b'z' => "!",
+        b'p' => "_",
+        _ => return None,
+    })
+}
+
+// Names of lifetimes: 'a to 'z, then '_ and a number
+fn lifetime_name(index: u64) -> String {
+    match index {
+        0..=25 => format!("'{}", (b'a' + index as u8) as char),
+        _ => format!("'_{}", index),
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> u8 {
+        self.input.get(self.pos).copied().unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let res = self.input.get(self.pos).copied();
+        self.pos += 1;
+        res
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        let res = self.peek() == c;
+        if res {
+            self.pos += 1;
+        }
+        res
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    // Digits and letters of both cases, then _. A lone _ is 0, otherwise the value is one more than the digits say.
+    fn base62(&mut self) -> Option<u64> {
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut res: u64 = 0;
+        loop {
+            let digit = match self.next()? {
+                c @ b'0'..=b'9' => c - b'0',
+                c @ b'a'..=b'z' => c - b'a' + 10,
+                c @ b'A'..=b'Z' => c - b'A' + 36,
+                b'_' => return res.checked_add(1),
+                _ => return None,
+            };
+            res = res.checked_mul(62)?.checked_add(digit as u64)?;
+        }
+    }
+
+    fn decimal(&mut self) -> Option<usize> {
+        // Without leading zeros, so a 0 ends the number, as in the empty names of closures, 00
+        if self.eat(b'0') {
+            return Some(0);
+        }
+        let start = self.pos;
+        let mut res: usize = 0;
+        while self.peek().is_ascii_digit() {
+            res = res.checked_mul(10)?.checked_add((self.peek() - b'0') as usize)?;
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return None;
+        }
+        Some(res)
+    }
+
+    // s and a number telling apart items of the same name, which the text leaves out
+    fn disambiguator(&mut self) -> Option<u64> {
+        match self.eat(b's') {
+            true => self.base62().map(|x| x + 1),
+            false => Some(0),
+        }
+    }
+
+    // The length, a _ if the identifier starts with a digit or _, and the identifier. With u in front, it is
+    // Punycode.
+    fn identifier(&mut self) -> Option<String> {
+        let punycode = self.eat(b'u');
+        let length = self.decimal()?;
+        self.eat(b'_');
+        let bytes = self.input.get(self.pos..self.pos.checked_add(length)?)?;
+        self.pos += length;
+        let text = std::str::from_utf8(bytes).ok()?;
+        match punycode {
+            true => decode_punycode(text),
+            false => Some(text.to_string()),
+        }
+    }
+
+    // Runs `parse` at the position a B and base 62 number refers to, which must be before the reference
+    fn backref<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let start = self.pos;
+        self.pos += 1;
+        let target = self.base62()? as usize;
+        if target >= start {
+            return None;
+        }
+        let end = self.pos;
+        self.pos = target;
+        let res = parse(self);
+        self.pos = end;
+        res
+    }
+
+    fn enter(&mut self) -> Option<()> {
+        if self.depth > MAX_DEPTH {
+            return None;
+        }
+        self.budget = self.budget.checked_sub(1)?;
+        self.depth += 1;
+        Some(())
+    }
+
+    // A path, whose generic arguments follow :: when it names a value rather than a type
+    fn path(&mut self, value: bool) -> Option<Name> {
+        self.enter()?;
+        let res = self.path_inner(value);
+        self.depth -= 1;
+        res
+    }
+
+    fn path_inner(&mut self, value: bool) -> Option<Name> {
+        match self.peek() {
+            // The root of a crate
+            b'C' => {
+                self.pos += 1;
+                self.disambiguator()?;
+                Some(Name { segments: vec![Segment::new(&self.identifier()?)] })
+            }
+            // Inherent impls, <T>
+            b'M' => {
+                self.pos += 1;
+                self.impl_path()?;
+                let ty = self.ty()?;
+                Some(Name { segments: vec![Segment::new(&format!("<{}>", ty))] })
+            }
+            // Trait impls, <T as Trait>
+            b'X' => {
+                self.pos += 1;
+                self.impl_path()?;
+                let ty = self.ty()?;
+                let trait_name = self.path(false)?;
+                Some(Name { segments: vec![Segment::new(&format!("<{} as {}>", ty, trait_name))] })
+            }
+            // Trait definitions
+            b'Y' => {
+                self.pos += 1;
+                let ty = self.ty()?;
+                let trait_name = self.path(false)?;
+                Some(Name { segments: vec![Segment::new(&format!("<{} as {}>", ty, trait_name))] })
+            }
+            // A namespace: lower case ones are plain, upper case ones are closures, shims and the like
+            b'N' => {
+                self.pos += 1;
+                let namespace = self.next()?;
+                let mut res = self.path(value)?;
+                let disambiguator = self.disambiguator()?;
+                let identifier = self.identifier()?;
+                let name = match namespace {
+                    // Tuple struct and variant constructors have no name of their own
+                    b'a'..=b'z' if identifier.is_empty() => return Some(res),
+                    b'a'..=b'z' => identifier,
+                    b'A'..=b'Z' => {
+                        let kind = match namespace {
+                            b'C' => "closure".to_string(),
+                            b'S' => "shim".to_string(),
+                            c => (c as char).to_string(),
+                        };
+                        match identifier.is_empty() {
+                            true => format!("{{{}#{}}}", kind, disambiguator),
+                            false => format!("{{{}:{}#{}}}", kind, identifier, disambiguator),
+                        }
+                    }
+                    _ => return None,
+                };
+                res.segments.push(Segment::new(&name));
+                Some(res)
+            }
+            // Generic arguments
+            b'I' => {
+                self.pos += 1;
+                let mut res = self.path(value)?;
+                let mut args = Vec::new();
+                while !self.eat(b'E') {
+                    args.push(self.generic_arg()?);
+                }
+                let last = res.segments.last_mut()?;
+                last.arguments = Some(args);
+                last.turbofish = value;
+                Some(res)
+            }
+            b'B' => self.backref(|x| x.path(value)),
+            _ => None,
+        }
+    }
+
+    // The path of the module an impl is in, which the text leaves out
+    fn impl_path(&mut self) -> Option<()> {
+        self.disambiguator()?;
+        self.path(false)?;
+        Some(())
+    }
+
+    fn generic_arg(&mut self) -> Option<TemplateArg> {
+        match self.peek() {
+            b'L' => {
+                self.pos += 1;
+                Some(TemplateArg::Value(self.lifetime()?))
+            }
+            b'K' => {
+                self.pos += 1;
+                Some(TemplateArg::Value(self.constant()?))
+            }
+            _ => Some(TemplateArg::Type(self.ty()?)),
+        }
+    }
+
+    // After the L: 0 for an erased lifetime, otherwise one bound by a for<>, counting back from the innermost
+    fn lifetime(&mut self) -> Option<String> {
+        let index = self.base62()?;
+        if index == 0 {
+            return Some("'_".to_string());
+        }
+        Some(lifetime_name(self.bound_lifetimes.checked_sub(index)?))
+    }
+
+    // G and the number of lifetimes less one, which for<> then names
+    fn binder(&mut self) -> Option<String> {
+        if !self.eat(b'G') {
+            return Some(String::new());
+        }
+        let count = self.base62()? + 1;
+        let names: Vec<String> = (0..count).map(|x| lifetime_name(self.bound_lifetimes + x)).collect();
+        self.bound_lifetimes += count;
+        Some(format!("for<{}> ", names.join(", ")))
+    }
+
+    fn ty(&mut self) -> Option<Type> {
+        self.enter()?;
+        let res = self.ty_inner();
+        self.depth -= 1;
+        res
+    }
+
+    fn ty_inner(&mut self) -> Option<Type> {
+        if let Some(name) = basic_type(self.peek()) {
+            self.pos += 1;
+            return Some(Type::Builtin(name.to_string()));
+        }
+        let text = match self.peek() {
+            b'A' => {
+                self.pos += 1;
+                let element = self.ty()?;
+                format!("[{}; {}]", element, self.constant()?)
+            }
+            b'S' => {
+                self.pos += 1;
+                format!("[{}]", self.ty()?)
+            }
+            b'T' => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                while !self.eat(b'E') {
+                    elements.push(self.ty()?.to_string());
+                }
+                match elements.len() {
+                    1 => format!("({},)", elements[0]),
+                    _ => format!("({})", elements.join(", ")),
+                }
+            }
+            b'R' | b'Q' => {
+                let mutable = self.next()? == b'Q';
+                let lifetime = match self.eat(b'L') {
+                    true => format!("{} ", self.lifetime()?),
+                    false => String::new(),
+                };
+                format!("&{}{}{}", lifetime, if mutable { "mut " } else { "" }, self.ty()?)
+            }
+            b'P' => {
+                self.pos += 1;
+                format!("*const {}", self.ty()?)
+            }
+            b'O' => {
+                self.pos += 1;
+                format!("*mut {}", self.ty()?)
+            }
+            b'F' => {
+                self.pos += 1;
+                let bound = self.bound_lifetimes;
+                let res = self.function_signature();
+                self.bound_lifetimes = bound;
+                res?
+            }
+            b'D' => {
+                self.pos += 1;
+                let bound = self.bound_lifetimes;
+                let res = self.dyn_bounds();
+                self.bound_lifetimes = bound;
+                res?
+            }
+            b'B' => return self.backref(|x| x.ty()),
+            _ => return Some(Type::Named(self.path(false)?)),
+        };
+        Some(Type::Verbatim(text))
+    }
+
+    // After the F: the lifetimes it binds, unsafe, the ABI, the parameters, E and the return type
+    fn function_signature(&mut self) -> Option<String> {
+        let binder = self.binder()?;
+        let mut res = binder;
+        if self.eat(b'U') {
+            res.push_str("unsafe ");
+        }
+        if self.eat(b'K') {
+            let abi = match self.eat(b'C') {
+                true => "C".to_string(),
+                false => self.identifier()?.replace('_', "-"),
+            };
+            res.push_str(&format!("extern \"{}\" ", abi));
+        }
+        let mut parameters = Vec::new();
+        while !self.eat(b'E') {
+            parameters.push(self.ty()?.to_string());
+        }
+        res.push_str(&format!("fn({})", parameters.join(", ")));
+        match self.ty()? {
+            Type::Builtin(x) if x == "()" => {}
+            ty => res.push_str(&format!(" -> {}", ty)),
+        }
+        Some(res)
+    }
+
+    // After the D: the lifetimes it binds, the traits with their associated types, E and the lifetime of the object
+    fn dyn_bounds(&mut self) -> Option<String> {
+        let binder = self.binder()?;
+        let mut traits = Vec::new();
+        while !self.eat(b'E') {
+            let mut name = self.path(false)?;
+            while self.eat(b'p') {
+                let associated = self.identifier()?;
+                let binding = TemplateArg::Value(format!("{} = {}", associated, self.ty()?));
+                name.segments.last_mut()?.arguments.get_or_insert_with(Vec::new).push(binding);
+            }
+            traits.push(name.to_string());
+        }
+        self.eat(b'L');
+        let lifetime = self.lifetime()?;
+        if lifetime != "'_" {
+            traits.push(lifetime);
+        }
+        Some(format!("dyn {}{}", binder, traits.join(" + ")))
+    }
+
+    // The value of a const generic: a placeholder, or a type and its value in hexadecimal
+    fn constant(&mut self) -> Option<String> {
+        if self.eat(b'p') {
+            return Some("_".to_string());
+        }
+        if self.peek() == b'B' {
+            return self.backref(|x| x.constant());
+        }
+        let ty = self.next()?;
+        let negative = self.eat(b'n');
+        let start = self.pos;
+        while self.peek().is_ascii_hexdigit() {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.pos]).ok()?;
+        if !self.eat(b'_') {
+            return None;
+        }
+        let sign = if negative { "-" } else { "" };
+        // Values too large for 64 bits stay in hexadecimal
+        let value = match digits.is_empty() {
+            true => 0,
+            false => match u64::from_str_radix(digits, 16) {
+                Ok(value) => value,
+                Err(_) if digits.len() <= 32 => return Some(format!("{}0x{}", sign, digits)),
+                Err(_) => return None,
+            },
+        };
+        match ty {
+            b'a' | b's' | b'l' | b'x' | b'n' | b'i' | b'h' | b't' | b'm' | b'y' | b'o' | b'j' => {
+                Some(format!("{}{}", sign, value))
+            }
+            b'b' => match value {
+                0 => Some("false".to_string()),
+                1 => Some("true".to_string()),
+                _ => None,
+            },
+            b'c' => Some(format!("{:?}", char::from_u32(u32::try_from(value).ok()?)?)),
+            _ => None,
+        }
+    }
+}
+
+// Punycode as in RFC 3492, with _ rather than - between the ASCII characters and the encoded ones
+fn decode_punycode(text: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    let (basic, encoded) = match text.rfind('_') {
+        Some(i) => (&text[..i], &text[i + 1..]),
+        None => ("", text),
+    };
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n: u32 = 128;
+    let mut i: u32 = 0;
+    let mut bias: u32 = 72;
+    let mut digits = encoded.bytes().peekable();
+    let mut first = true;
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                c @ b'a'..=b'z' => (c - b'a') as u32,
+                c @ b'0'..=b'9' => (c - b'0') as u32 + 26,
+                _ => return None,
+            };
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let t = k.saturating_sub(bias).clamp(T_MIN, T_MAX);
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        // Adapts the bias to the size of the delta
+        let mut delta = if first { (i - old_i) / 700 } else { (i - old_i) / 2 };
+        first = false;
+        delta += delta / length;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        bias = k + ((BASE - T_MIN + 1) * delta) / (delta + 38);
+
+        n = n.checked_add(i / length)?;
+        i %= length;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demangle;
+
+    #[test]
+    fn paths_generics_and_back_references() {
+        let cases = [
+            ("_RNvC6_123foo3bar", "123foo::bar"),
+            ("_RNvMNtCs1234_7mycrate3fooNtB2_3Bar3new", "<mycrate::foo::Bar>::new"),
+            ("_RNCNvCs1234_7mycrate4main0B3_", "mycrate::main::{closure#0}"),
+            ("_RMCs4fqI2P2rA04_13const_genericINtB0_8UnsignedKhb_E", "<const_generic::Unsigned<11>>"),
+            (
+                "_RINbNbCskIICzLVDPPb_5alloc5alloc8box_freeDINbNiB4_5boxed5FnBoxuEp6OutputuEL_ECs1iopQbuBiw2_3std",
+                "alloc::alloc::box_free::<dyn alloc::boxed::FnBox<(), Output = ()>>",
+            ),
+        ];
+        for (symbol, text) in cases.iter() {
+            assert_eq!(demangle(symbol).map(|x| x.to_string()).as_deref(), Some(*text), "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn malformed_symbols() {
+        // A later version of the scheme, a back reference to itself and a truncated path
+        for symbol in ["_R1NvC3foo3bar", "_RNvB0_3foo", "_RNvC3fo"].iter() {
+            assert!(demangle(symbol).is_none(), "{}", symbol);
+        }
+    }
+}
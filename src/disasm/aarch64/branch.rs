@@ -0,0 +1,481 @@
+use crate::disasm::{Operand, Register, RegisterClass};
+
+use super::registers::{gpr, gpr_sp};
+use super::{bit, bits, imm, ins, reg, sign_extend, Decoded, CONDITIONS};
+
+/// Branches, exception generation and system instructions, op0 = 101x
+pub fn branch_system(word: u32, address: u64) -> Decoded {
+    let relative = |offset: i64| Operand::Address(address.wrapping_add(offset as u64));
+
+    if bits(word, 26, 5) == 0b00101 {
+        let target = relative(sign_extend(bits(word, 0, 26), 26) << 2);
+        return ins(if bit(word, 31) { "bl" } else { "b" }, vec![target]);
+    }
+
+    if bits(word, 25, 6) == 0b011010 {
+        let rt = reg(gpr(bits(word, 0, 5), bit(word, 31)));
+        let target = relative(sign_extend(bits(word, 5, 19), 19) << 2);
+        return ins(if bit(word, 24) { "cbnz" } else { "cbz" }, vec![rt, target]);
+    }
+
+    if bits(word, 25, 6) == 0b011011 {
+        let rt = reg(gpr(bits(word, 0, 5), bit(word, 31)));
+        let bit_number = imm(((bits(word, 31, 1) << 5) | bits(word, 19, 5)) as i64);
+        let target = relative(sign_extend(bits(word, 5, 14), 14) << 2);
+        return ins(if bit(word, 24) { "tbnz" } else { "tbz" }, vec![rt, bit_number, target]);
+    }
+
+    match bits(word, 24, 8) {
+        0b0101_0100 if !bit(word, 4) => {
+            let target = relative(sign_extend(bits(word, 5, 19), 19) << 2);
+            let mnemonic = format!("b.{}", CONDITIONS[bits(word, 0, 4) as usize]);
+            return Some((mnemonic, vec![target]));
+        }
+        0b1101_0100 => return exception(word),
+        _ => {}
+    }
+
+    if bits(word, 22, 10) == 0b11_0101_0100 {
+        return system(word);
+    }
+
+    if bits(word, 25, 7) == 0b110_1011 {
+        return branch_register(word);
+    }
+
+    None
+}
+
+fn exception(word: u32) -> Decoded {
+    if bits(word, 2, 3) != 0 {
+        return None;
+    }
+
+    let value = bits(word, 5, 16) as i64;
+    let mnemonic = match (bits(word, 21, 3), bits(word, 0, 2)) {
+        (0b000, 0b01) => "svc",
+        (0b000, 0b10) => "hvc",
+        (0b000, 0b11) => "smc",
+        (0b001, 0b00) => "brk",
+        (0b010, 0b00) => "hlt",
+        (0b101, level @ 1..=3) => {
+            let mnemonic = ["dcps1", "dcps2", "dcps3"][level as usize - 1];
+            return ins(mnemonic, if value == 0 { vec![] } else { vec![imm(value)] });
+        }
+        _ => return None,
+    };
+    ins(mnemonic, vec![imm(value)])
+}
+
+const HINTS: [(u32, &str, Option<&str>); 28] = [
+    (0, "nop", None),
+    (1, "yield", None),
+    (2, "wfe", None),
+    (3, "wfi", None),
+    (4, "sev", None),
+    (5, "sevl", None),
+    (6, "dgh", None),
+    (7, "xpaclri", None),
+    (8, "pacia1716", None),
+    (10, "pacib1716", None),
+    (12, "autia1716", None),
+    (14, "autib1716", None),
+    (16, "esb", None),
+    (17, "psb", Some("csync")),
+    (18, "tsb", Some("csync")),
+    (20, "csdb", None),
+    (24, "paciaz", None),
+    (25, "paciasp", None),
+    (26, "pacibz", None),
+    (27, "pacibsp", None),
+    (28, "autiaz", None),
+    (29, "autiasp", None),
+    (30, "autibz", None),
+    (31, "autibsp", None),
+    (32, "bti", None),
+    (34, "bti", Some("c")),
+    (36, "bti", Some("j")),
+    (38, "bti", Some("jc")),
+];
+
+const BARRIER_OPTIONS: [Option<&str>; 16] = [
+    None, Some("oshld"), Some("oshst"), Some("osh"),
+    None, Some("nshld"), Some("nshst"), Some("nsh"),
+    None, Some("ishld"), Some("ishst"), Some("ish"),
+    None, Some("ld"), Some("st"), Some("sy"),
+];
+
+const CONTROL_REGISTERS: [&str; 16] = [
+    "c0", "c1", "c2", "c3", "c4", "c5", "c6", "c7",
+    "c8", "c9", "c10", "c11", "c12", "c13", "c14", "c15",
+];
+
+fn system(word: u32) -> Decoded {
+    let read = bit(word, 21);
+    let op0 = bits(word, 19, 2);
+    let op1 = bits(word, 16, 3);
+    let crn = bits(word, 12, 4);
+    let crm = bits(word, 8, 4);
+    let op2 = bits(word, 5, 3);
+    let rt_number = bits(word, 0, 5);
+    let rt = reg(gpr(rt_number, true));
+
+    if op0 >= 2 {
+        let sysreg = system_register(op0, op1, crn, crm, op2);
+        return if read {
+            ins("mrs", vec![rt, reg(sysreg)])
+        } else {
+            ins("msr", vec![reg(sysreg), rt])
+        };
+    }
+
+    if op0 == 1 {
+        let generic = || {
+            let mut operands = vec![
+                imm(op1 as i64),
+                Operand::Name(CONTROL_REGISTERS[crn as usize]),
+                Operand::Name(CONTROL_REGISTERS[crm as usize]),
+                imm(op2 as i64),
+            ];
+            if read {
+                operands.insert(0, rt.clone());
+                return ins("sysl", operands);
+            }
+            if rt_number != 31 {
+                operands.push(rt.clone());
+            }
+            ins("sys", operands)
+        };
+
+        if read {
+            return generic();
+        }
+        return match system_alias(op1, crn, crm, op2) {
+            Some((mnemonic, operation, takes_register)) if takes_register || rt_number == 31 => {
+                let mut operands = vec![Operand::Name(operation)];
+                if takes_register {
+                    operands.push(rt);
+                }
+                ins(mnemonic, operands)
+            }
+            _ => generic(),
+        };
+    }
+
+    if read {
+        return None;
+    }
+
+    match (crn, rt_number) {
+        (0b0010, 31) if op1 == 0b011 => {
+            let index = crm << 3 | op2;
+            match HINTS.iter().find(|x| x.0 == index) {
+                Some((_, mnemonic, Some(operation))) => ins(mnemonic, vec![Operand::Name(operation)]),
+                Some((_, mnemonic, None)) => ins(mnemonic, vec![]),
+                None => ins("hint", vec![imm(index as i64)]),
+            }
+        }
+        (0b0011, 31) if op1 == 0b011 => {
+            let option = match BARRIER_OPTIONS[crm as usize] {
+                Some(name) => Operand::Name(name),
+                None => imm(crm as i64),
+            };
+            match op2 {
+                0b010 if crm == 15 => ins("clrex", vec![]),
+                0b010 => ins("clrex", vec![imm(crm as i64)]),
+                0b100 if crm == 0 => ins("ssbb", vec![]),
+                0b100 if crm == 4 => ins("pssbb", vec![]),
+                0b100 => ins("dsb", vec![option]),
+                0b101 => ins("dmb", vec![option]),
+                0b110 if crm == 15 => ins("isb", vec![]),
+                0b110 => ins("isb", vec![imm(crm as i64)]),
+                0b111 if crm == 0 => ins("sb", vec![]),
+                _ => None,
+            }
+        }
+        (0b0100, 31) => {
+            let field = match (op1, op2) {
+                (0b000, 0b000) if crm == 0 => return ins("cfinv", vec![]),
+                (0b000, 0b001) if crm == 0 => return ins("xaflag", vec![]),
+                (0b000, 0b010) if crm == 0 => return ins("axflag", vec![]),
+                (0b000, 0b011) => "uao",
+                (0b000, 0b100) => "pan",
+                (0b000, 0b101) => "spsel",
+                (0b011, 0b001) => "ssbs",
+                (0b011, 0b010) => "dit",
+                (0b011, 0b100) => "tco",
+                (0b011, 0b110) => "daifset",
+                (0b011, 0b111) => "daifclr",
+                _ => return None,
+            };
+            ins("msr", vec![Operand::Name(field), imm(crm as i64)])
+        }
+        _ => None,
+    }
+}
+
+// Named forms of sys: (mnemonic, operation, whether a register operand is taken)
+fn system_alias(op1: u32, crn: u32, crm: u32, op2: u32) -> Option<(&'static str, &'static str, bool)> {
+    let key = (op1, crm, op2);
+    match crn {
+        0b0111 => {
+            let at = match key {
+                (0, 8, 0) => Some("s1e1r"),
+                (0, 8, 1) => Some("s1e1w"),
+                (0, 8, 2) => Some("s1e0r"),
+                (0, 8, 3) => Some("s1e0w"),
+                (0, 9, 0) => Some("s1e1rp"),
+                (0, 9, 1) => Some("s1e1wp"),
+                (4, 8, 0) => Some("s1e2r"),
+                (4, 8, 1) => Some("s1e2w"),
+                (4, 8, 4) => Some("s12e1r"),
+                (4, 8, 5) => Some("s12e1w"),
+                (4, 8, 6) => Some("s12e0r"),
+                (4, 8, 7) => Some("s12e0w"),
+                (6, 8, 0) => Some("s1e3r"),
+                (6, 8, 1) => Some("s1e3w"),
+                _ => None,
+            };
+            if let Some(operation) = at {
+                return Some(("at", operation, true));
+            }
+
+            let dc = match key {
+                (3, 4, 1) => Some("zva"),
+                (3, 4, 3) => Some("gva"),
+                (3, 4, 4) => Some("gzva"),
+                (0, 6, 1) => Some("ivac"),
+                (0, 6, 2) => Some("isw"),
+                (0, 10, 2) => Some("csw"),
+                (0, 14, 2) => Some("cisw"),
+                (3, 10, 1) => Some("cvac"),
+                (3, 11, 1) => Some("cvau"),
+                (3, 12, 1) => Some("cvap"),
+                (3, 13, 1) => Some("cvadp"),
+                (3, 14, 1) => Some("civac"),
+                _ => None,
+            };
+            if let Some(operation) = dc {
+                return Some(("dc", operation, true));
+            }
+
+            match key {
+                (0, 1, 0) => Some(("ic", "ialluis", false)),
+                (0, 5, 0) => Some(("ic", "iallu", false)),
+                (3, 5, 1) => Some(("ic", "ivau", true)),
+                _ => None,
+            }
+        }
+        0b1000 => {
+            let (operation, takes_register) = match key {
+                (0, 3, 0) => ("vmalle1is", false),
+                (0, 3, 1) => ("vae1is", true),
+                (0, 3, 2) => ("aside1is", true),
+                (0, 3, 3) => ("vaae1is", true),
+                (0, 3, 5) => ("vale1is", true),
+                (0, 3, 7) => ("vaale1is", true),
+                (0, 7, 0) => ("vmalle1", false),
+                (0, 7, 1) => ("vae1", true),
+                (0, 7, 2) => ("aside1", true),
+                (0, 7, 3) => ("vaae1", true),
+                (0, 7, 5) => ("vale1", true),
+                (0, 7, 7) => ("vaale1", true),
+                (4, 0, 1) => ("ipas2e1is", true),
+                (4, 0, 5) => ("ipas2le1is", true),
+                (4, 3, 0) => ("alle2is", false),
+                (4, 3, 1) => ("vae2is", true),
+                (4, 3, 4) => ("alle1is", false),
+                (4, 3, 5) => ("vale2is", true),
+                (4, 3, 6) => ("vmalls12e1is", false),
+                (4, 4, 1) => ("ipas2e1", true),
+                (4, 4, 5) => ("ipas2le1", true),
+                (4, 7, 0) => ("alle2", false),
+                (4, 7, 1) => ("vae2", true),
+                (4, 7, 4) => ("alle1", false),
+                (4, 7, 5) => ("vale2", true),
+                (4, 7, 6) => ("vmalls12e1", false),
+                (6, 3, 0) => ("alle3is", false),
+                (6, 3, 1) => ("vae3is", true),
+                (6, 3, 5) => ("vale3is", true),
+                (6, 7, 0) => ("alle3", false),
+                (6, 7, 1) => ("vae3", true),
+                (6, 7, 5) => ("vale3", true),
+                _ => return None,
+            };
+            Some(("tlbi", operation, takes_register))
+        }
+        _ => None,
+    }
+}
+
+// (op0, op1, CRn, CRm, op2)
+// op0, op1, CRn, CRm, op2
+type SystemRegisterEncoding = (u32, u32, u32, u32, u32);
+
+const SYSTEM_REGISTERS: &[(SystemRegisterEncoding, &str)] = &[
+    ((3, 3, 4, 2, 0), "nzcv"),
+    ((3, 3, 4, 2, 1), "daif"),
+    ((3, 3, 4, 2, 5), "dit"),
+    ((3, 3, 4, 2, 6), "ssbs"),
+    ((3, 3, 4, 2, 7), "tco"),
+    ((3, 3, 4, 4, 0), "fpcr"),
+    ((3, 3, 4, 4, 1), "fpsr"),
+    ((3, 0, 4, 2, 0), "spsel"),
+    ((3, 0, 4, 2, 2), "currentel"),
+    ((3, 0, 4, 2, 3), "pan"),
+    ((3, 0, 4, 2, 4), "uao"),
+    ((3, 3, 13, 0, 2), "tpidr_el0"),
+    ((3, 3, 13, 0, 3), "tpidrro_el0"),
+    ((3, 0, 13, 0, 4), "tpidr_el1"),
+    ((3, 4, 13, 0, 2), "tpidr_el2"),
+    ((3, 6, 13, 0, 2), "tpidr_el3"),
+    ((3, 0, 13, 0, 1), "contextidr_el1"),
+    ((3, 3, 0, 0, 1), "ctr_el0"),
+    ((3, 3, 0, 0, 7), "dczid_el0"),
+    ((3, 0, 0, 0, 0), "midr_el1"),
+    ((3, 0, 0, 0, 5), "mpidr_el1"),
+    ((3, 0, 0, 0, 6), "revidr_el1"),
+    ((3, 0, 0, 4, 0), "id_aa64pfr0_el1"),
+    ((3, 0, 0, 4, 1), "id_aa64pfr1_el1"),
+    ((3, 0, 0, 4, 4), "id_aa64zfr0_el1"),
+    ((3, 0, 0, 5, 0), "id_aa64dfr0_el1"),
+    ((3, 0, 0, 5, 1), "id_aa64dfr1_el1"),
+    ((3, 0, 0, 6, 0), "id_aa64isar0_el1"),
+    ((3, 0, 0, 6, 1), "id_aa64isar1_el1"),
+    ((3, 0, 0, 7, 0), "id_aa64mmfr0_el1"),
+    ((3, 0, 0, 7, 1), "id_aa64mmfr1_el1"),
+    ((3, 0, 0, 7, 2), "id_aa64mmfr2_el1"),
+    ((3, 1, 0, 0, 0), "ccsidr_el1"),
+    ((3, 1, 0, 0, 1), "clidr_el1"),
+    ((3, 2, 0, 0, 0), "csselr_el1"),
+    ((3, 3, 2, 4, 0), "rndr"),
+    ((3, 3, 2, 4, 1), "rndrrs"),
+    ((3, 3, 14, 0, 0), "cntfrq_el0"),
+    ((3, 3, 14, 0, 1), "cntpct_el0"),
+    ((3, 3, 14, 0, 2), "cntvct_el0"),
+    ((3, 3, 14, 2, 0), "cntp_tval_el0"),
+    ((3, 3, 14, 2, 1), "cntp_ctl_el0"),
+    ((3, 3, 14, 2, 2), "cntp_cval_el0"),
+    ((3, 3, 14, 3, 0), "cntv_tval_el0"),
+    ((3, 3, 14, 3, 1), "cntv_ctl_el0"),
+    ((3, 3, 14, 3, 2), "cntv_cval_el0"),
+    ((3, 0, 14, 1, 0), "cntkctl_el1"),
+    ((3, 3, 9, 12, 0), "pmcr_el0"),
+    ((3, 3, 9, 12, 1), "pmcntenset_el0"),
+    ((3, 3, 9, 13, 0), "pmccntr_el0"),
+    ((3, 3, 9, 14, 0), "pmuserenr_el0"),
+    ((3, 0, 1, 0, 0), "sctlr_el1"),
+    ((3, 0, 1, 0, 1), "actlr_el1"),
+    ((3, 0, 1, 0, 2), "cpacr_el1"),
+    ((3, 0, 2, 0, 0), "ttbr0_el1"),
+    ((3, 0, 2, 0, 1), "ttbr1_el1"),
+    ((3, 0, 2, 0, 2), "tcr_el1"),
+    ((3, 0, 2, 1, 0), "apiakeylo_el1"),
+    ((3, 0, 2, 1, 1), "apiakeyhi_el1"),
+    ((3, 0, 2, 1, 2), "apibkeylo_el1"),
+    ((3, 0, 2, 1, 3), "apibkeyhi_el1"),
+    ((3, 0, 2, 2, 0), "apdakeylo_el1"),
+    ((3, 0, 2, 2, 1), "apdakeyhi_el1"),
+    ((3, 0, 2, 2, 2), "apdbkeylo_el1"),
+    ((3, 0, 2, 2, 3), "apdbkeyhi_el1"),
+    ((3, 0, 2, 3, 0), "apgakeylo_el1"),
+    ((3, 0, 2, 3, 1), "apgakeyhi_el1"),
+    ((3, 0, 4, 0, 0), "spsr_el1"),
+    ((3, 0, 4, 0, 1), "elr_el1"),
+    ((3, 0, 4, 1, 0), "sp_el0"),
+    ((3, 0, 4, 6, 0), "icc_pmr_el1"),
+    ((3, 0, 5, 1, 0), "afsr0_el1"),
+    ((3, 0, 5, 1, 1), "afsr1_el1"),
+    ((3, 0, 5, 2, 0), "esr_el1"),
+    ((3, 0, 6, 0, 0), "far_el1"),
+    ((3, 0, 7, 4, 0), "par_el1"),
+    ((3, 0, 10, 2, 0), "mair_el1"),
+    ((3, 0, 10, 3, 0), "amair_el1"),
+    ((3, 0, 12, 0, 0), "vbar_el1"),
+    ((3, 0, 12, 1, 0), "isr_el1"),
+    ((3, 0, 12, 11, 5), "icc_sgi1r_el1"),
+    ((3, 0, 12, 12, 0), "icc_iar1_el1"),
+    ((3, 0, 12, 12, 1), "icc_eoir1_el1"),
+    ((3, 0, 12, 12, 4), "icc_ctlr_el1"),
+    ((3, 0, 12, 12, 5), "icc_sre_el1"),
+    ((3, 0, 12, 12, 7), "icc_igrpen1_el1"),
+    ((3, 4, 0, 0, 0), "vpidr_el2"),
+    ((3, 4, 0, 0, 5), "vmpidr_el2"),
+    ((3, 4, 1, 0, 0), "sctlr_el2"),
+    ((3, 4, 1, 1, 0), "hcr_el2"),
+    ((3, 4, 1, 1, 1), "mdcr_el2"),
+    ((3, 4, 1, 1, 2), "cptr_el2"),
+    ((3, 4, 1, 1, 3), "hstr_el2"),
+    ((3, 4, 2, 0, 0), "ttbr0_el2"),
+    ((3, 4, 2, 0, 2), "tcr_el2"),
+    ((3, 4, 2, 1, 0), "vttbr_el2"),
+    ((3, 4, 2, 1, 2), "vtcr_el2"),
+    ((3, 4, 4, 0, 0), "spsr_el2"),
+    ((3, 4, 4, 0, 1), "elr_el2"),
+    ((3, 4, 4, 1, 0), "sp_el1"),
+    ((3, 4, 5, 2, 0), "esr_el2"),
+    ((3, 4, 6, 0, 0), "far_el2"),
+    ((3, 4, 6, 0, 4), "hpfar_el2"),
+    ((3, 4, 10, 2, 0), "mair_el2"),
+    ((3, 4, 12, 0, 0), "vbar_el2"),
+    ((3, 4, 14, 0, 3), "cntvoff_el2"),
+    ((3, 4, 14, 1, 0), "cnthctl_el2"),
+    ((3, 6, 1, 0, 0), "sctlr_el3"),
+    ((3, 6, 1, 1, 0), "scr_el3"),
+    ((3, 6, 1, 1, 2), "cptr_el3"),
+    ((3, 6, 2, 0, 0), "ttbr0_el3"),
+    ((3, 6, 2, 0, 2), "tcr_el3"),
+    ((3, 6, 4, 0, 0), "spsr_el3"),
+    ((3, 6, 4, 0, 1), "elr_el3"),
+    ((3, 6, 4, 1, 0), "sp_el2"),
+    ((3, 6, 5, 2, 0), "esr_el3"),
+    ((3, 6, 6, 0, 0), "far_el3"),
+    ((3, 6, 10, 2, 0), "mair_el3"),
+    ((3, 6, 12, 0, 0), "vbar_el3"),
+    ((2, 0, 0, 2, 2), "mdscr_el1"),
+    ((2, 0, 1, 0, 4), "oslar_el1"),
+    ((2, 3, 0, 1, 0), "mdccsr_el0"),
+    ((2, 3, 0, 4, 0), "dbgdtr_el0"),
+];
+
+/// A system register for mrs/msr. Registers without a known name have an empty name.
+fn system_register(op0: u32, op1: u32, crn: u32, crm: u32, op2: u32) -> Register {
+    let key = (op0, op1, crn, crm, op2);
+    let name = SYSTEM_REGISTERS.iter().find(|x| x.0 == key).map(|x| x.1).unwrap_or("");
+    let number = op0 << 14 | op1 << 11 | crn << 7 | crm << 3 | op2;
+    Register::new(RegisterClass::Special, number as u16, 64, name)
+}
+
+fn branch_register(word: u32) -> Decoded {
+    let opc = bits(word, 21, 4);
+    let op3 = bits(word, 10, 6);
+    let rn_number = bits(word, 5, 5);
+    let op4 = bits(word, 0, 5);
+    let rn = reg(gpr(rn_number, true));
+
+    if bits(word, 16, 5) != 0b11111 {
+        return None;
+    }
+
+    match (opc, op3, op4) {
+        (0b0000, 0, 0) => ins("br", vec![rn]),
+        (0b0001, 0, 0) => ins("blr", vec![rn]),
+        (0b0010, 0, 0) if rn_number == 30 => ins("ret", vec![]),
+        (0b0010, 0, 0) => ins("ret", vec![rn]),
+        (0b0100, 0, 0) if rn_number == 31 => ins("eret", vec![]),
+        (0b0101, 0, 0) if rn_number == 31 => ins("drps", vec![]),
+        (0b0000, 0b000010, 31) => ins("braaz", vec![rn]),
+        (0b0000, 0b000011, 31) => ins("brabz", vec![rn]),
+        (0b0001, 0b000010, 31) => ins("blraaz", vec![rn]),
+        (0b0001, 0b000011, 31) => ins("blrabz", vec![rn]),
+        (0b0010, 0b000010, 31) if rn_number == 31 => ins("retaa", vec![]),
+        (0b0010, 0b000011, 31) if rn_number == 31 => ins("retab", vec![]),
+        (0b0100, 0b000010, 31) if rn_number == 31 => ins("eretaa", vec![]),
+        (0b0100, 0b000011, 31) if rn_number == 31 => ins("eretab", vec![]),
+        (0b1000, 0b000010, _) => ins("braa", vec![rn, reg(gpr_sp(op4, true))]),
+        (0b1000, 0b000011, _) => ins("brab", vec![rn, reg(gpr_sp(op4, true))]),
+        (0b1001, 0b000010, _) => ins("blraa", vec![rn, reg(gpr_sp(op4, true))]),
+        (0b1001, 0b000011, _) => ins("blrab", vec![rn, reg(gpr_sp(op4, true))]),
+        _ => None,
+    }
+}
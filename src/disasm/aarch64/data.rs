@@ -0,0 +1,714 @@
+use crate::disasm::{Operand, ShiftKind};
+
+use super::registers::{gpr, gpr_sp};
+use super::{bit, bits, imm, ins, reg, shift, sign_extend, Decoded};
+
+const SHIFTS: [ShiftKind; 4] = [ShiftKind::Lsl, ShiftKind::Lsr, ShiftKind::Asr, ShiftKind::Ror];
+
+const EXTENDS: [ShiftKind; 8] = [
+    ShiftKind::Uxtb, ShiftKind::Uxth, ShiftKind::Uxtw, ShiftKind::Uxtx,
+    ShiftKind::Sxtb, ShiftKind::Sxth, ShiftKind::Sxtw, ShiftKind::Sxtx,
+];
+
+/// Data processing with immediate operands, op0 = 100x
+pub fn immediate(word: u32, address: u64) -> Decoded {
+    match bits(word, 23, 3) {
+        0b000 | 0b001 => pc_relative(word, address),
+        0b010 => add_sub_immediate(word),
+        0b011 => add_sub_tags(word),
+        0b100 => logical_immediate(word),
+        0b101 => move_wide(word),
+        0b110 => bitfield(word),
+        _ => extract(word),
+    }
+}
+
+/// Data processing with register operands, op0 = x101
+pub fn register(word: u32) -> Decoded {
+    let op1 = bit(word, 28);
+    let op2 = bits(word, 21, 4);
+
+    if !op1 {
+        return if op2 & 8 == 0 {
+            logical_shifted(word)
+        } else if op2 & 1 == 0 {
+            add_sub_shifted(word)
+        } else {
+            add_sub_extended(word)
+        };
+    }
+
+    match op2 {
+        0b0000 if bits(word, 10, 6) == 0 => add_sub_carry(word),
+        0b0010 => conditional_compare(word),
+        0b0100 => conditional_select(word),
+        0b0110 if bit(word, 30) => one_source(word),
+        0b0110 => two_source(word),
+        0b1000..=0b1111 => three_source(word),
+        _ => None,
+    }
+}
+
+fn pc_relative(word: u32, address: u64) -> Decoded {
+    let rd = gpr(bits(word, 0, 5), true);
+    let offset = sign_extend((bits(word, 5, 19) << 2) | bits(word, 29, 2), 21);
+
+    if bit(word, 31) {
+        let target = (address & !0xFFF).wrapping_add((offset << 12) as u64);
+        ins("adrp", vec![reg(rd), Operand::Address(target)])
+    } else {
+        ins("adr", vec![reg(rd), Operand::Address(address.wrapping_add(offset as u64))])
+    }
+}
+
+fn add_sub_immediate(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op = bit(word, 30);
+    let set_flags = bit(word, 29);
+    let shifted = bit(word, 22);
+    let value = bits(word, 10, 12) as i64;
+    let rn_number = bits(word, 5, 5);
+    let rd_number = bits(word, 0, 5);
+
+    let rn = gpr_sp(rn_number, sf);
+    let rd = if set_flags { gpr(rd_number, sf) } else { gpr_sp(rd_number, sf) };
+
+    let mut operands = vec![reg(rd), reg(rn), imm(value)];
+    if shifted {
+        operands.push(shift(ShiftKind::Lsl, 12));
+    }
+
+    if !op && !set_flags && !shifted && value == 0 && (rd_number == 31 || rn_number == 31) {
+        return ins("mov", vec![reg(rd), reg(rn)]);
+    }
+    if set_flags && rd_number == 31 {
+        operands.remove(0);
+        return ins(if op { "cmp" } else { "cmn" }, operands);
+    }
+
+    let mnemonic = match (op, set_flags) {
+        (false, false) => "add",
+        (false, true) => "adds",
+        (true, false) => "sub",
+        (true, true) => "subs",
+    };
+    ins(mnemonic, operands)
+}
+
+// Memory tagging, addg/subg
+fn add_sub_tags(word: u32) -> Decoded {
+    if !bit(word, 31) || bit(word, 29) || bit(word, 22) {
+        return None;
+    }
+
+    let mnemonic = if bit(word, 30) { "subg" } else { "addg" };
+    ins(mnemonic, vec![
+        reg(gpr_sp(bits(word, 0, 5), true)),
+        reg(gpr_sp(bits(word, 5, 5), true)),
+        imm(bits(word, 16, 6) as i64 * 16),
+        imm(bits(word, 10, 4) as i64),
+    ])
+}
+
+/// Expands the N:immr:imms bitmask immediate encoding used by logical instructions
+pub fn decode_bit_mask(n: bool, imms: u32, immr: u32, width: u32) -> Option<u64> {
+    let combined = ((n as u32) << 6) | (!imms & 0x3F);
+    if combined == 0 {
+        return None;
+    }
+    let len = 31 - combined.leading_zeros();
+    if len < 1 {
+        return None;
+    }
+
+    let levels = (1 << len) - 1;
+    let s = imms & levels;
+    let r = immr & levels;
+    if s == levels {
+        return None;
+    }
+
+    let element_size = 1u32 << len;
+    let element_mask = if element_size == 64 { u64::MAX } else { (1u64 << element_size) - 1 };
+    let ones = (1u64 << (s + 1)) - 1;
+    let element = if r == 0 {
+        ones
+    } else {
+        ((ones >> r) | (ones << (element_size - r))) & element_mask
+    };
+
+    let mut value = 0;
+    let mut position = 0;
+    while position < width {
+        value |= element << position;
+        position += element_size;
+    }
+    Some(value)
+}
+
+// Whether orr with a bitmask immediate should be shown as movz/movn instead of mov
+fn move_wide_preferred(sf: bool, n: bool, imms: u32, immr: u32) -> bool {
+    let width = if sf { 64 } else { 32 };
+
+    if sf && !n {
+        return false;
+    }
+    if !sf && (n || imms & 0x20 != 0) {
+        return false;
+    }
+
+    if imms < 16 {
+        return (16 - (immr % 16)) % 16 <= 15 - imms;
+    }
+    if imms >= width - 15 {
+        return immr % 16 <= imms - (width - 15);
+    }
+    false
+}
+
+fn logical_immediate(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let opc = bits(word, 29, 2);
+    let n = bit(word, 22);
+    let immr = bits(word, 16, 6);
+    let imms = bits(word, 10, 6);
+    let rn_number = bits(word, 5, 5);
+    let rd_number = bits(word, 0, 5);
+
+    if !sf && n {
+        return None;
+    }
+    let width = if sf { 64 } else { 32 };
+    let value = decode_bit_mask(n, imms, immr, width)?;
+    let value = Operand::Immediate { value: value as i64, size: width as u8 / 8 };
+
+    let rn = gpr(rn_number, sf);
+    let rd = if opc == 0b11 { gpr(rd_number, sf) } else { gpr_sp(rd_number, sf) };
+
+    match opc {
+        0b11 if rd_number == 31 => return ins("tst", vec![reg(rn), value]),
+        0b01 if rn_number == 31 && !move_wide_preferred(sf, n, imms, immr) => {
+            return ins("mov", vec![reg(rd), value]);
+        }
+        _ => {}
+    }
+
+    let mnemonic = ["and", "orr", "eor", "ands"][opc as usize];
+    ins(mnemonic, vec![reg(rd), reg(rn), value])
+}
+
+fn move_wide(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let opc = bits(word, 29, 2);
+    let hw = bits(word, 21, 2);
+    let value = bits(word, 5, 16) as u64;
+    let rd = gpr(bits(word, 0, 5), sf);
+
+    if !sf && hw >= 2 {
+        return None;
+    }
+    let amount = hw * 16;
+    let size = if sf { 8 } else { 4 };
+    let mask = if sf { u64::MAX } else { 0xFFFF_FFFF };
+
+    // The mov alias is used whenever the value can't also be encoded with a smaller shift
+    let alias = !(value == 0 && hw != 0);
+
+    let mnemonic = match opc {
+        0b00 if alias && (sf || value != 0xFFFF) => {
+            let value = !(value << amount) & mask;
+            return ins("mov", vec![reg(rd), Operand::Immediate { value: value as i64, size }]);
+        }
+        0b10 if alias => {
+            return ins("mov", vec![reg(rd), Operand::Immediate { value: (value << amount) as i64, size }]);
+        }
+        0b00 => "movn",
+        0b10 => "movz",
+        0b11 => "movk",
+        _ => return None,
+    };
+
+    let mut operands = vec![reg(rd), imm(value as i64)];
+    if amount != 0 {
+        operands.push(shift(ShiftKind::Lsl, amount as u8));
+    }
+    ins(mnemonic, operands)
+}
+
+// Whether ubfm/sbfm should be shown as ubfx/sbfx
+fn bfx_preferred(sf: bool, unsigned: bool, imms: u32, immr: u32) -> bool {
+    if imms < immr {
+        return false;
+    }
+    if imms == if sf { 63 } else { 31 } {
+        return false;
+    }
+    if immr == 0 {
+        if !sf && (imms == 7 || imms == 15) {
+            return false;
+        }
+        if sf && !unsigned && (imms == 7 || imms == 15 || imms == 31) {
+            return false;
+        }
+    }
+    true
+}
+
+fn bitfield(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let opc = bits(word, 29, 2);
+    let n = bit(word, 22);
+    let immr = bits(word, 16, 6);
+    let imms = bits(word, 10, 6);
+    let rn_number = bits(word, 5, 5);
+
+    if n != sf || opc == 0b11 || (!sf && (immr | imms) & 0x20 != 0) {
+        return None;
+    }
+
+    let width = if sf { 64 } else { 32 };
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let rn = reg(gpr(rn_number, sf));
+    let small = |x: u32| imm(x as i64);
+
+    // Position and width of the inserted field when imms < immr
+    let lsb = (width - immr) % width;
+    let field = imms + 1;
+
+    match opc {
+        0b00 => {
+            if imms == width - 1 {
+                return ins("asr", vec![rd, rn, small(immr)]);
+            }
+            if imms < immr {
+                return ins("sbfiz", vec![rd, rn, small(lsb), small(field)]);
+            }
+            if bfx_preferred(sf, false, imms, immr) {
+                return ins("sbfx", vec![rd, rn, small(immr), small(imms - immr + 1)]);
+            }
+            if immr == 0 {
+                let wn = reg(gpr(rn_number, false));
+                match imms {
+                    7 => return ins("sxtb", vec![rd, wn]),
+                    15 => return ins("sxth", vec![rd, wn]),
+                    31 => return ins("sxtw", vec![rd, wn]),
+                    _ => {}
+                }
+            }
+            ins("sbfm", vec![rd, rn, small(immr), small(imms)])
+        }
+        0b01 => {
+            if imms < immr {
+                if rn_number == 31 {
+                    return ins("bfc", vec![rd, small(lsb), small(field)]);
+                }
+                return ins("bfi", vec![rd, rn, small(lsb), small(field)]);
+            }
+            ins("bfxil", vec![rd, rn, small(immr), small(imms - immr + 1)])
+        }
+        _ => {
+            if imms != width - 1 && imms + 1 == immr {
+                return ins("lsl", vec![rd, rn, small(width - 1 - imms)]);
+            }
+            if imms == width - 1 {
+                return ins("lsr", vec![rd, rn, small(immr)]);
+            }
+            if imms < immr {
+                return ins("ubfiz", vec![rd, rn, small(lsb), small(field)]);
+            }
+            if bfx_preferred(sf, true, imms, immr) {
+                return ins("ubfx", vec![rd, rn, small(immr), small(imms - immr + 1)]);
+            }
+            if immr == 0 {
+                let wn = reg(gpr(rn_number, false));
+                match imms {
+                    7 => return ins("uxtb", vec![rd, wn]),
+                    15 => return ins("uxth", vec![rd, wn]),
+                    _ => {}
+                }
+            }
+            ins("ubfm", vec![rd, rn, small(immr), small(imms)])
+        }
+    }
+}
+
+fn extract(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let imms = bits(word, 10, 6);
+    if bits(word, 29, 2) != 0 || bit(word, 22) != sf || bit(word, 21) || (!sf && imms >= 32) {
+        return None;
+    }
+
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let rn_number = bits(word, 5, 5);
+    let rm_number = bits(word, 16, 5);
+    if rn_number == rm_number {
+        return ins("ror", vec![rd, reg(gpr(rn_number, sf)), imm(imms as i64)]);
+    }
+    ins("extr", vec![rd, reg(gpr(rn_number, sf)), reg(gpr(rm_number, sf)), imm(imms as i64)])
+}
+
+fn logical_shifted(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let opc = bits(word, 29, 2);
+    let kind = SHIFTS[bits(word, 22, 2) as usize];
+    let negate = bit(word, 21);
+    let amount = bits(word, 10, 6);
+    let rn_number = bits(word, 5, 5);
+    let rd_number = bits(word, 0, 5);
+
+    if !sf && amount >= 32 {
+        return None;
+    }
+
+    let rd = reg(gpr(rd_number, sf));
+    let rn = reg(gpr(rn_number, sf));
+    let mut rm = vec![reg(gpr(bits(word, 16, 5), sf))];
+    if kind != ShiftKind::Lsl || amount != 0 {
+        rm.push(shift(kind, amount as u8));
+    }
+
+    let mnemonic = match (opc, negate) {
+        (0b01, false) if rn_number == 31 && kind == ShiftKind::Lsl && amount == 0 => {
+            return ins("mov", [vec![rd], rm].concat());
+        }
+        (0b01, true) if rn_number == 31 => return ins("mvn", [vec![rd], rm].concat()),
+        (0b11, false) if rd_number == 31 => return ins("tst", [vec![rn], rm].concat()),
+        (0b00, false) => "and",
+        (0b00, true) => "bic",
+        (0b01, false) => "orr",
+        (0b01, true) => "orn",
+        (0b10, false) => "eor",
+        (0b10, true) => "eon",
+        (0b11, false) => "ands",
+        _ => "bics",
+    };
+    ins(mnemonic, [vec![rd, rn], rm].concat())
+}
+
+fn add_sub_shifted(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op = bit(word, 30);
+    let set_flags = bit(word, 29);
+    let shift_type = bits(word, 22, 2);
+    let amount = bits(word, 10, 6);
+    let rn_number = bits(word, 5, 5);
+    let rd_number = bits(word, 0, 5);
+
+    if shift_type == 0b11 || bit(word, 21) || (!sf && amount >= 32) {
+        return None;
+    }
+
+    let rd = reg(gpr(rd_number, sf));
+    let rn = reg(gpr(rn_number, sf));
+    let mut rm = vec![reg(gpr(bits(word, 16, 5), sf))];
+    if shift_type != 0 || amount != 0 {
+        rm.push(shift(SHIFTS[shift_type as usize], amount as u8));
+    }
+
+    if set_flags && rd_number == 31 {
+        return ins(if op { "cmp" } else { "cmn" }, [vec![rn], rm].concat());
+    }
+    if op && rn_number == 31 {
+        return ins(if set_flags { "negs" } else { "neg" }, [vec![rd], rm].concat());
+    }
+
+    let mnemonic = match (op, set_flags) {
+        (false, false) => "add",
+        (false, true) => "adds",
+        (true, false) => "sub",
+        (true, true) => "subs",
+    };
+    ins(mnemonic, [vec![rd, rn], rm].concat())
+}
+
+fn add_sub_extended(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op = bit(word, 30);
+    let set_flags = bit(word, 29);
+    let option = bits(word, 13, 3);
+    let amount = bits(word, 10, 3);
+    let rn_number = bits(word, 5, 5);
+    let rd_number = bits(word, 0, 5);
+
+    if bits(word, 22, 2) != 0 || amount > 4 {
+        return None;
+    }
+
+    let rd = reg(if set_flags { gpr(rd_number, sf) } else { gpr_sp(rd_number, sf) });
+    let rn = reg(gpr_sp(rn_number, sf));
+    let mut rm = vec![reg(gpr(bits(word, 16, 5), sf && option & 3 == 3))];
+
+    // With the stack pointer involved, the extend matching the register size is shown as lsl
+    let uses_sp = (rd_number == 31 && !set_flags) || rn_number == 31;
+    if uses_sp && option == if sf { 0b011 } else { 0b010 } {
+        if amount != 0 {
+            rm.push(shift(ShiftKind::Lsl, amount as u8));
+        }
+    } else {
+        rm.push(shift(EXTENDS[option as usize], amount as u8));
+    }
+
+    if set_flags && rd_number == 31 {
+        return ins(if op { "cmp" } else { "cmn" }, [vec![rn], rm].concat());
+    }
+
+    let mnemonic = match (op, set_flags) {
+        (false, false) => "add",
+        (false, true) => "adds",
+        (true, false) => "sub",
+        (true, true) => "subs",
+    };
+    ins(mnemonic, [vec![rd, rn], rm].concat())
+}
+
+fn add_sub_carry(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op = bit(word, 30);
+    let set_flags = bit(word, 29);
+    let rn_number = bits(word, 5, 5);
+
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let rn = reg(gpr(rn_number, sf));
+    let rm = reg(gpr(bits(word, 16, 5), sf));
+
+    if op && rn_number == 31 {
+        return ins(if set_flags { "ngcs" } else { "ngc" }, vec![rd, rm]);
+    }
+
+    let mnemonic = match (op, set_flags) {
+        (false, false) => "adc",
+        (false, true) => "adcs",
+        (true, false) => "sbc",
+        (true, true) => "sbcs",
+    };
+    ins(mnemonic, vec![rd, rn, rm])
+}
+
+fn conditional_compare(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    if !bit(word, 29) || bit(word, 10) || bit(word, 4) {
+        return None;
+    }
+
+    let rn = reg(gpr(bits(word, 5, 5), sf));
+    let second = if bit(word, 11) {
+        imm(bits(word, 16, 5) as i64)
+    } else {
+        reg(gpr(bits(word, 16, 5), sf))
+    };
+    let nzcv = imm(bits(word, 0, 4) as i64);
+    let condition = Operand::Condition(bits(word, 12, 4) as u8);
+
+    let mnemonic = if bit(word, 30) { "ccmp" } else { "ccmn" };
+    ins(mnemonic, vec![rn, second, nzcv, condition])
+}
+
+fn conditional_select(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op = bit(word, 30);
+    let op2 = bits(word, 10, 2);
+    if bit(word, 29) || op2 > 1 {
+        return None;
+    }
+
+    let rn_number = bits(word, 5, 5);
+    let rm_number = bits(word, 16, 5);
+    let condition = bits(word, 12, 4) as u8;
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let rn = reg(gpr(rn_number, sf));
+    let rm = reg(gpr(rm_number, sf));
+
+    // The aliases test the inverted condition, which doesn't exist for al/nv
+    let inverted = Operand::Condition(condition ^ 1);
+    let invertible = condition < 14;
+
+    match (op, op2 == 1) {
+        (false, false) => ins("csel", vec![rd, rn, rm, Operand::Condition(condition)]),
+        (false, true) => {
+            if invertible && rn_number == 31 && rm_number == 31 {
+                ins("cset", vec![rd, inverted])
+            } else if invertible && rn_number == rm_number {
+                ins("cinc", vec![rd, rn, inverted])
+            } else {
+                ins("csinc", vec![rd, rn, rm, Operand::Condition(condition)])
+            }
+        }
+        (true, false) => {
+            if invertible && rn_number == 31 && rm_number == 31 {
+                ins("csetm", vec![rd, inverted])
+            } else if invertible && rn_number == rm_number {
+                ins("cinv", vec![rd, rn, inverted])
+            } else {
+                ins("csinv", vec![rd, rn, rm, Operand::Condition(condition)])
+            }
+        }
+        (true, true) => {
+            if invertible && rn_number == rm_number {
+                ins("cneg", vec![rd, rn, inverted])
+            } else {
+                ins("csneg", vec![rd, rn, rm, Operand::Condition(condition)])
+            }
+        }
+    }
+}
+
+fn two_source(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let opcode = bits(word, 10, 6);
+    if sf && (opcode == 0 || opcode == 0b000100 || opcode == 0b000101) {
+        return memory_tags(word);
+    }
+    if bit(word, 29) {
+        return None;
+    }
+
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let rn = reg(gpr(bits(word, 5, 5), sf));
+    let rm_number = bits(word, 16, 5);
+    let rm = reg(gpr(rm_number, sf));
+
+    let mnemonic = match opcode {
+        0b000010 => "udiv",
+        0b000011 => "sdiv",
+        0b001000 => "lsl",
+        0b001001 => "lsr",
+        0b001010 => "asr",
+        0b001011 => "ror",
+        0b001100 if sf => {
+            let rm = reg(gpr_sp(rm_number, true));
+            return ins("pacga", vec![rd, rn, rm]);
+        }
+        opcode @ 0b010000..=0b010111 => {
+            let size = opcode & 3;
+            if (size == 3) != sf {
+                return None;
+            }
+            let wd = reg(gpr(bits(word, 0, 5), false));
+            let wn = reg(gpr(bits(word, 5, 5), false));
+            let mnemonic = [
+                "crc32b", "crc32h", "crc32w", "crc32x",
+                "crc32cb", "crc32ch", "crc32cw", "crc32cx",
+            ][(opcode & 7) as usize];
+            return ins(mnemonic, vec![wd, wn, reg(gpr(rm_number, sf))]);
+        }
+        _ => return None,
+    };
+    ins(mnemonic, vec![rd, rn, rm])
+}
+
+// Memory tagging extension: subp/subps, irg and gmi
+fn memory_tags(word: u32) -> Decoded {
+    let set_flags = bit(word, 29);
+    let rd_number = bits(word, 0, 5);
+    let rn = reg(gpr_sp(bits(word, 5, 5), true));
+    let rm_number = bits(word, 16, 5);
+
+    match bits(word, 10, 6) {
+        0b000000 => {
+            let rm = reg(gpr_sp(rm_number, true));
+            if set_flags && rd_number == 31 {
+                return ins("cmpp", vec![rn, rm]);
+            }
+            ins(if set_flags { "subps" } else { "subp" }, vec![reg(gpr(rd_number, true)), rn, rm])
+        }
+        _ if set_flags => None,
+        0b000100 => {
+            let mut operands = vec![reg(gpr_sp(rd_number, true)), rn];
+            if rm_number != 31 {
+                operands.push(reg(gpr(rm_number, true)));
+            }
+            ins("irg", operands)
+        }
+        _ => ins("gmi", vec![reg(gpr(rd_number, true)), rn, reg(gpr(rm_number, true))]),
+    }
+}
+
+fn one_source(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    if bit(word, 29) {
+        return None;
+    }
+
+    let rd_number = bits(word, 0, 5);
+    let rn_number = bits(word, 5, 5);
+    let opcode = bits(word, 10, 6);
+
+    match bits(word, 16, 5) {
+        0b00000 => {
+            let mnemonic = match (opcode, sf) {
+                (0b000000, _) => "rbit",
+                (0b000001, _) => "rev16",
+                (0b000010, false) => "rev",
+                (0b000010, true) => "rev32",
+                (0b000011, true) => "rev",
+                (0b000100, _) => "clz",
+                (0b000101, _) => "cls",
+                _ => return None,
+            };
+            ins(mnemonic, vec![reg(gpr(rd_number, sf)), reg(gpr(rn_number, sf))])
+        }
+        0b00001 if sf => {
+            let rd = reg(gpr(rd_number, true));
+            const KEYED: [&str; 8] = ["pacia", "pacib", "pacda", "pacdb", "autia", "autib", "autda", "autdb"];
+            const ZERO: [&str; 8] = ["paciza", "pacizb", "pacdza", "pacdzb", "autiza", "autizb", "autdza", "autdzb"];
+            match opcode {
+                0b000000..=0b000111 => ins(KEYED[opcode as usize], vec![rd, reg(gpr_sp(rn_number, true))]),
+                0b001000..=0b001111 if rn_number == 31 => ins(ZERO[(opcode & 7) as usize], vec![rd]),
+                0b010000 if rn_number == 31 => ins("xpaci", vec![rd]),
+                0b010001 if rn_number == 31 => ins("xpacd", vec![rd]),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn three_source(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let op31 = bits(word, 21, 3);
+    let subtract = bit(word, 15);
+    let ra_number = bits(word, 10, 5);
+
+    if bits(word, 29, 2) != 0 {
+        return None;
+    }
+
+    let rd = reg(gpr(bits(word, 0, 5), sf));
+    let ra = reg(gpr(ra_number, sf));
+
+    match op31 {
+        0b000 => {
+            let rn = reg(gpr(bits(word, 5, 5), sf));
+            let rm = reg(gpr(bits(word, 16, 5), sf));
+            match (subtract, ra_number == 31) {
+                (false, true) => ins("mul", vec![rd, rn, rm]),
+                (false, false) => ins("madd", vec![rd, rn, rm, ra]),
+                (true, true) => ins("mneg", vec![rd, rn, rm]),
+                (true, false) => ins("msub", vec![rd, rn, rm, ra]),
+            }
+        }
+        0b001 | 0b101 if sf => {
+            let rn = reg(gpr(bits(word, 5, 5), false));
+            let rm = reg(gpr(bits(word, 16, 5), false));
+            let signed = op31 == 0b001;
+            let mnemonic = match (signed, subtract, ra_number == 31) {
+                (true, false, true) => return ins("smull", vec![rd, rn, rm]),
+                (true, true, true) => return ins("smnegl", vec![rd, rn, rm]),
+                (false, false, true) => return ins("umull", vec![rd, rn, rm]),
+                (false, true, true) => return ins("umnegl", vec![rd, rn, rm]),
+                (true, false, false) => "smaddl",
+                (true, true, false) => "smsubl",
+                (false, false, false) => "umaddl",
+                (false, true, false) => "umsubl",
+            };
+            ins(mnemonic, vec![rd, rn, rm, ra])
+        }
+        0b010 | 0b110 if sf && !subtract => {
+            let rn = reg(gpr(bits(word, 5, 5), true));
+            let rm = reg(gpr(bits(word, 16, 5), true));
+            ins(if op31 == 0b010 { "smulh" } else { "umulh" }, vec![rd, rn, rm])
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,176 @@
+use crate::disasm::{IndexMode, Instruction, MemoryOperand, Operand, Register, RegisterClass, ShiftKind};
+
+use super::CONDITIONS;
+
+// Rendering follows the GNU objdump conventions: data immediates are hex, shift amounts, bit positions and
+// memory offsets are decimal
+
+pub fn format(instruction: &Instruction) -> String {
+    let decimal = decimal_immediates(&instruction.mnemonic);
+    let operands: Vec<String> = instruction.operands.iter().map(|x| operand(x, decimal)).collect();
+
+    let mut res = instruction.mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(", "));
+    }
+    res
+}
+
+// Whether the immediates of the instruction are shift amounts or bit positions rather than data
+fn decimal_immediates(mnemonic: &str) -> bool {
+    let mnemonic = mnemonic.trim_end_matches('2');
+    matches!(
+        mnemonic,
+        "lsl" | "lsr" | "asr" | "ror" | "extr" | "ext"
+            | "sbfx" | "ubfx" | "sbfiz" | "ubfiz" | "bfi" | "bfxil" | "bfc" | "sbfm" | "ubfm" | "bfm"
+            | "tbz" | "tbnz"
+            | "scvtf" | "ucvtf" | "fcvtzs" | "fcvtzu"
+            | "cmgt" | "cmge" | "cmeq" | "cmle" | "cmlt"
+            | "sshr" | "ushr" | "ssra" | "usra" | "srshr" | "urshr" | "srsra" | "ursra" | "sri" | "shl" | "sli"
+            | "sqshlu" | "sqshl" | "uqshl" | "shrn" | "rshrn" | "sqshrn" | "sqrshrn" | "sqshrun" | "sqrshrun"
+            | "uqshrn" | "uqrshrn" | "sshll" | "ushll" | "shll"
+    )
+}
+
+fn register(register: &Register) -> String {
+    if register.class == RegisterClass::Special && register.name.is_empty() {
+        // Unnamed system registers are shown by their encoding
+        let number = register.number as u32;
+        return format!(
+            "s{}_{}_c{}_c{}_{}",
+            number >> 14,
+            (number >> 11) & 7,
+            (number >> 7) & 15,
+            (number >> 3) & 15,
+            number & 7,
+        );
+    }
+    register.name.to_string()
+}
+
+// Data immediates are shown unsigned, at the width of the operation
+fn masked(value: i64, size: u8) -> u64 {
+    if size >= 8 {
+        value as u64
+    } else {
+        value as u64 & ((1u64 << (size as u32 * 8)) - 1)
+    }
+}
+
+fn shift_name(kind: ShiftKind) -> &'static str {
+    match kind {
+        ShiftKind::Lsl => "lsl",
+        ShiftKind::Lsr => "lsr",
+        ShiftKind::Asr => "asr",
+        ShiftKind::Ror => "ror",
+        ShiftKind::Msl => "msl",
+        ShiftKind::Uxtb => "uxtb",
+        ShiftKind::Uxth => "uxth",
+        ShiftKind::Uxtw => "uxtw",
+        ShiftKind::Uxtx => "uxtx",
+        ShiftKind::Sxtb => "sxtb",
+        ShiftKind::Sxth => "sxth",
+        ShiftKind::Sxtw => "sxtw",
+        ShiftKind::Sxtx => "sxtx",
+        ShiftKind::Rrx => "rrx",
+    }
+}
+
+fn is_shift(kind: ShiftKind) -> bool {
+    matches!(kind, ShiftKind::Lsl | ShiftKind::Lsr | ShiftKind::Asr | ShiftKind::Ror | ShiftKind::Msl)
+}
+
+fn shift(kind: ShiftKind, amount: u8) -> String {
+    // Extensions without a shift are written without an amount
+    if amount == 0 && !is_shift(kind) {
+        return shift_name(kind).to_string();
+    }
+    format!("{} #{}", shift_name(kind), amount)
+}
+
+fn element_suffix(element_bits: u16) -> char {
+    match element_bits {
+        8 => 'b',
+        16 => 'h',
+        32 => 's',
+        64 => 'd',
+        _ => 'q',
+    }
+}
+
+fn vector(register: &Register, element_bits: u16, elements: u8, lane: Option<u8>) -> String {
+    match lane {
+        Some(lane) if elements != 0 => format!("{}.{}{}[{}]", register.name, elements, element_suffix(element_bits), lane),
+        Some(lane) => format!("{}.{}[{}]", register.name, element_suffix(element_bits), lane),
+        None => format!("{}.{}{}", register.name, elements, element_suffix(element_bits)),
+    }
+}
+
+fn list(operands: &[Operand], decimal: bool) -> String {
+    // Lists of single lanes share the index, {v0.s, v1.s}[1]
+    let lanes: Vec<(&Register, u16, u8)> = operands
+        .iter()
+        .filter_map(|x| match x {
+            Operand::Vector { register, element_bits, lane: Some(lane), .. } => Some((register, *element_bits, *lane)),
+            _ => None,
+        })
+        .collect();
+
+    if !lanes.is_empty() && lanes.len() == operands.len() {
+        let names: Vec<String> = lanes
+            .iter()
+            .map(|(register, element_bits, _)| format!("{}.{}", register.name, element_suffix(*element_bits)))
+            .collect();
+        return format!("{{{}}}[{}]", names.join(", "), lanes[0].2);
+    }
+
+    let items: Vec<String> = operands.iter().map(|x| operand(x, decimal)).collect();
+    format!("{{{}}}", items.join(", "))
+}
+
+fn memory(mem: &MemoryOperand) -> String {
+    let base = match mem.base {
+        Some(base) => register(&base),
+        // Literal loads only have the resolved address
+        None => return format!("0x{:x}", mem.target.unwrap_or(mem.displacement as u64)),
+    };
+
+    if let Some(index) = mem.index {
+        if mem.mode == IndexMode::PostIndex {
+            return format!("[{}], {}", base, register(&index));
+        }
+
+        let amount = mem.scale.trailing_zeros() as u8;
+        let suffix = match mem.extend {
+            Some(kind) if amount != 0 || kind == ShiftKind::Lsl => format!(", {} #{}", shift_name(kind), amount),
+            Some(kind) => format!(", {}", shift_name(kind)),
+            None => String::new(),
+        };
+        return format!("[{}, {}{}]", base, register(&index), suffix);
+    }
+
+    match mem.mode {
+        IndexMode::Offset if mem.displacement == 0 => format!("[{}]", base),
+        IndexMode::Offset => format!("[{}, #{}]", base, mem.displacement),
+        IndexMode::PreIndex => format!("[{}, #{}]!", base, mem.displacement),
+        IndexMode::PostIndex => format!("[{}], #{}", base, mem.displacement),
+    }
+}
+
+fn operand(operand: &Operand, decimal: bool) -> String {
+    match operand {
+        Operand::Register(x) => register(x),
+        Operand::Immediate { value, .. } if decimal => format!("#{}", value),
+        Operand::Immediate { value, size } => format!("#0x{:x}", masked(*value, *size)),
+        Operand::Memory(mem) => memory(mem),
+        Operand::Address(address) => format!("0x{:x}", address),
+        Operand::Shift { kind, amount } => shift(*kind, *amount),
+        Operand::Condition(condition) => CONDITIONS[*condition as usize & 15].to_string(),
+        Operand::Vector { register, element_bits, elements, lane } => vector(register, *element_bits, *elements, *lane),
+        Operand::List(operands) => list(operands, decimal),
+        Operand::Name(name) => name.to_string(),
+        Operand::Float(value) => format!("#{:?}", f64::from_bits(*value)),
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,514 @@
+use crate::disasm::{IndexMode, MemoryOperand, Operand, Register, ShiftKind};
+
+use super::registers::{fp, gpr, gpr_sp};
+use super::{bit, bits, imm, ins, reg, sign_extend, vector_arrangement, vector_lane, Decoded};
+
+/// Loads and stores, op0 = x1x0
+pub fn load_store(word: u32, address: u64) -> Decoded {
+    let simd = bit(word, 26);
+
+    match bits(word, 28, 2) {
+        0b00 if simd && !bit(word, 31) && !bit(word, 29) => {
+            if bit(word, 24) {
+                simd_single(word)
+            } else {
+                simd_multiple(word)
+            }
+        }
+        0b00 if !simd && bits(word, 24, 2) == 0 && !bit(word, 29) => exclusive(word),
+        0b01 if !bit(word, 24) => literal(word, address),
+        0b01 if !simd && bit(word, 21) => memory_tags(word),
+        0b01 if !simd && bits(word, 10, 2) == 0 => release_consistent(word),
+        0b10 => pair(word),
+        0b11 => register(word),
+        _ => None,
+    }
+}
+
+fn base_memory(rn: u32, size: u16) -> MemoryOperand {
+    let mut mem = MemoryOperand::new(size);
+    mem.base = Some(gpr_sp(rn, true));
+    mem
+}
+
+fn offset_memory(rn: u32, size: u16, displacement: i64, mode: IndexMode) -> Operand {
+    let mut mem = base_memory(rn, size);
+    mem.displacement = displacement;
+    mem.mode = mode;
+    Operand::Memory(mem)
+}
+
+// Names of the prefetch operations, indexed by the Rt field of prfm
+const PREFETCH_OPERATIONS: [Option<&str>; 32] = [
+    Some("pldl1keep"), Some("pldl1strm"), Some("pldl2keep"), Some("pldl2strm"),
+    Some("pldl3keep"), Some("pldl3strm"), None, None,
+    Some("plil1keep"), Some("plil1strm"), Some("plil2keep"), Some("plil2strm"),
+    Some("plil3keep"), Some("plil3strm"), None, None,
+    Some("pstl1keep"), Some("pstl1strm"), Some("pstl2keep"), Some("pstl2strm"),
+    Some("pstl3keep"), Some("pstl3strm"), None, None,
+    None, None, None, None,
+    None, None, None, None,
+];
+
+fn prefetch_operation(rt: u32) -> Operand {
+    match PREFETCH_OPERATIONS[rt as usize] {
+        Some(name) => Operand::Name(name),
+        None => imm(rt as i64),
+    }
+}
+
+fn literal(word: u32, address: u64) -> Decoded {
+    let opc = bits(word, 30, 2);
+    let rt = bits(word, 0, 5);
+    let target = address.wrapping_add((sign_extend(bits(word, 5, 19), 19) << 2) as u64);
+
+    let (mnemonic, destination, size) = match (bit(word, 26), opc) {
+        (false, 0b00) => ("ldr", reg(gpr(rt, false)), 4),
+        (false, 0b01) => ("ldr", reg(gpr(rt, true)), 8),
+        (false, 0b10) => ("ldrsw", reg(gpr(rt, true)), 4),
+        (false, _) => ("prfm", prefetch_operation(rt), 0),
+        (true, 0b00) => ("ldr", reg(fp(rt, 4)), 4),
+        (true, 0b01) => ("ldr", reg(fp(rt, 8)), 8),
+        (true, 0b10) => ("ldr", reg(fp(rt, 16)), 16),
+        (true, _) => return None,
+    };
+
+    let mut mem = MemoryOperand::new(size);
+    mem.target = Some(target);
+    ins(mnemonic, vec![destination, Operand::Memory(mem)])
+}
+
+fn pair(word: u32) -> Decoded {
+    let opc = bits(word, 30, 2);
+    let simd = bit(word, 26);
+    let kind = bits(word, 23, 2);
+    let load = bit(word, 22);
+    let offset = sign_extend(bits(word, 15, 7), 7);
+    let rt2 = bits(word, 10, 5);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    let mode = match kind {
+        0b01 => IndexMode::PostIndex,
+        0b11 => IndexMode::PreIndex,
+        _ => IndexMode::Offset,
+    };
+
+    if !simd && opc == 0b01 && !load {
+        // Memory tagging, stores the tag and a pair of registers
+        if kind == 0b00 {
+            return None;
+        }
+        return ins("stgp", vec![
+            reg(gpr(rt, true)),
+            reg(gpr(rt2, true)),
+            offset_memory(rn, 16, offset * 16, mode),
+        ]);
+    }
+
+    let (mnemonic, size, registers): (&str, u16, fn(u32) -> Register) = match (simd, opc, load) {
+        (false, 0b00, false) => ("stp", 4, |x| gpr(x, false)),
+        (false, 0b00, true) => ("ldp", 4, |x| gpr(x, false)),
+        (false, 0b01, true) => ("ldpsw", 4, |x| gpr(x, true)),
+        (false, 0b10, false) => ("stp", 8, |x| gpr(x, true)),
+        (false, 0b10, true) => ("ldp", 8, |x| gpr(x, true)),
+        (true, 0b00, _) => (if load { "ldp" } else { "stp" }, 4, |x| fp(x, 4)),
+        (true, 0b01, _) => (if load { "ldp" } else { "stp" }, 8, |x| fp(x, 8)),
+        (true, 0b10, _) => (if load { "ldp" } else { "stp" }, 16, |x| fp(x, 16)),
+        _ => return None,
+    };
+
+    let mnemonic = match (kind, mnemonic) {
+        (0b00, "ldpsw") => return None,
+        (0b00, "ldp") => "ldnp",
+        (0b00, "stp") => "stnp",
+        (_, mnemonic) => mnemonic,
+    };
+
+    ins(mnemonic, vec![
+        reg(registers(rt)),
+        reg(registers(rt2)),
+        offset_memory(rn, size * 2, offset * size as i64, mode),
+    ])
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Store,
+    Load,
+    Prefetch,
+}
+
+// Decodes size:V:opc of the single register forms into (access, mnemonic suffix, register, access size)
+fn register_kind(word: u32) -> Option<(Access, &'static str, Register, u16)> {
+    let size = bits(word, 30, 2);
+    let opc = bits(word, 22, 2);
+    let rt = bits(word, 0, 5);
+
+    if bit(word, 26) {
+        let access = if opc & 1 == 0 { Access::Store } else { Access::Load };
+        return match (size, opc >> 1) {
+            (_, 0) => Some((access, "", fp(rt, 1 << size), 1 << size)),
+            (0b00, 1) => Some((access, "", fp(rt, 16), 16)),
+            _ => None,
+        };
+    }
+
+    let bytes = 1 << size;
+    let (suffix, signed) = match size {
+        0b00 => ("b", "sb"),
+        0b01 => ("h", "sh"),
+        0b10 => ("", "sw"),
+        _ => ("", ""),
+    };
+    match (size, opc) {
+        (_, 0b00) => Some((Access::Store, suffix, gpr(rt, size == 3), bytes)),
+        (_, 0b01) => Some((Access::Load, suffix, gpr(rt, size == 3), bytes)),
+        (0b11, 0b10) => Some((Access::Prefetch, "", gpr(rt, true), 8)),
+        (0b11, _) => None,
+        (0b10, 0b11) => None,
+        (_, 0b10) => Some((Access::Load, signed, gpr(rt, true), bytes)),
+        (_, _) => Some((Access::Load, signed, gpr(rt, false), bytes)),
+    }
+}
+
+fn register(word: u32) -> Decoded {
+    let rn = bits(word, 5, 5);
+
+    if !bit(word, 24) && bit(word, 21) {
+        match bits(word, 10, 2) {
+            0b10 => {}
+            0b00 if !bit(word, 26) => return atomic(word),
+            _ if bits(word, 30, 2) == 0b11 && !bit(word, 26) && bit(word, 10) => return pointer_authenticated(word),
+            _ => return None,
+        }
+    }
+
+    let (access, suffix, rt, size) = register_kind(word)?;
+    let destination = if access == Access::Prefetch {
+        prefetch_operation(bits(word, 0, 5))
+    } else {
+        reg(rt)
+    };
+
+    let stem = |scaled: &str, unscaled: &str| -> Option<String> {
+        Some(match access {
+            Access::Store => format!("st{}{}", scaled, suffix),
+            Access::Load => format!("ld{}{}", scaled, suffix),
+            Access::Prefetch => unscaled.to_string(),
+        })
+    };
+
+    if bit(word, 24) {
+        let offset = bits(word, 10, 12) as i64 * size as i64;
+        let mnemonic = stem("r", "prfm")?;
+        return Some((mnemonic, vec![destination, offset_memory(rn, size, offset, IndexMode::Offset)]));
+    }
+
+    if bit(word, 21) {
+        let option = bits(word, 13, 3);
+        if option & 2 == 0 {
+            return None;
+        }
+        let shifted = bit(word, 12);
+        let amount = if shifted { size.trailing_zeros() } else { 0 };
+
+        let mut mem = base_memory(rn, size);
+        mem.index = Some(gpr(bits(word, 16, 5), option & 1 == 1));
+        mem.scale = 1 << amount;
+        mem.extend = match option {
+            0b011 if shifted => Some(ShiftKind::Lsl),
+            0b011 => None,
+            0b010 => Some(ShiftKind::Uxtw),
+            0b110 => Some(ShiftKind::Sxtw),
+            _ => Some(ShiftKind::Sxtx),
+        };
+        let mnemonic = stem("r", "prfm")?;
+        return Some((mnemonic, vec![destination, Operand::Memory(mem)]));
+    }
+
+    let offset = sign_extend(bits(word, 12, 9), 9);
+    let (mnemonic, mode) = match bits(word, 10, 2) {
+        0b00 => (stem("ur", "prfum")?, IndexMode::Offset),
+        0b01 if access != Access::Prefetch => (stem("r", "")?, IndexMode::PostIndex),
+        0b10 if access != Access::Prefetch && !bit(word, 26) => (stem("tr", "")?, IndexMode::Offset),
+        0b11 if access != Access::Prefetch => (stem("r", "")?, IndexMode::PreIndex),
+        _ => return None,
+    };
+    Some((mnemonic, vec![destination, offset_memory(rn, size, offset, mode)]))
+}
+
+// Unscaled load-acquire RCpc and store-release, ldapur/stlur
+fn release_consistent(word: u32) -> Decoded {
+    let size = bits(word, 30, 2);
+    let rt = bits(word, 0, 5);
+    let suffix = ["b", "h", "", ""][size as usize];
+
+    let (mnemonic, destination) = match (size, bits(word, 22, 2)) {
+        (_, 0b00) => (format!("stlur{}", suffix), gpr(rt, size == 3)),
+        (_, 0b01) => (format!("ldapur{}", suffix), gpr(rt, size == 3)),
+        (0b10, 0b10) => ("ldapursw".to_string(), gpr(rt, true)),
+        (0b00, _) | (0b01, _) => (format!("ldapurs{}", suffix), gpr(rt, bits(word, 22, 2) == 0b10)),
+        _ => return None,
+    };
+    let offset = sign_extend(bits(word, 12, 9), 9);
+    Some((mnemonic, vec![reg(destination), offset_memory(bits(word, 5, 5), 1 << size, offset, IndexMode::Offset)]))
+}
+
+// Memory tagging extension, stg/stzg/st2g/stz2g and the tag loads
+fn memory_tags(word: u32) -> Decoded {
+    if bits(word, 30, 2) != 0b11 {
+        return None;
+    }
+    let opc = bits(word, 22, 2);
+    let imm9 = bits(word, 12, 9);
+    let op2 = bits(word, 10, 2);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    if op2 == 0 {
+        let mnemonic = match opc {
+            0b01 => {
+                let offset = sign_extend(imm9, 9) * 16;
+                return ins("ldg", vec![reg(gpr(rt, true)), offset_memory(rn, 16, offset, IndexMode::Offset)]);
+            }
+            _ if imm9 != 0 => return None,
+            0b00 => "stzgm",
+            0b10 => "stgm",
+            _ => "ldgm",
+        };
+        return ins(mnemonic, vec![reg(gpr(rt, true)), Operand::Memory(base_memory(rn, 0))]);
+    }
+
+    let mnemonic = ["stg", "stzg", "st2g", "stz2g"][opc as usize];
+    let mode = match op2 {
+        0b01 => IndexMode::PostIndex,
+        0b10 => IndexMode::Offset,
+        _ => IndexMode::PreIndex,
+    };
+    let size = if opc & 2 != 0 { 32 } else { 16 };
+    ins(mnemonic, vec![reg(gpr_sp(rt, true)), offset_memory(rn, size, sign_extend(imm9, 9) * 16, mode)])
+}
+
+fn atomic(word: u32) -> Decoded {
+    let size = bits(word, 30, 2);
+    let acquire = bit(word, 23);
+    let release = bit(word, 22);
+    let rs = bits(word, 16, 5);
+    let o3 = bit(word, 15);
+    let opc = bits(word, 12, 3);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    let wide = size == 3;
+    let width = match size {
+        0b00 => "b",
+        0b01 => "h",
+        _ => "",
+    };
+    let memory = Operand::Memory(base_memory(rn, 1 << size));
+
+    const OPERATIONS: [&str; 8] = ["add", "clr", "eor", "set", "smax", "smin", "umax", "umin"];
+
+    if o3 {
+        return match opc {
+            0b000 => {
+                let mnemonic = format!("swp{}{}{}", if acquire { "a" } else { "" }, if release { "l" } else { "" }, width);
+                Some((mnemonic, vec![reg(gpr(rs, wide)), reg(gpr(rt, wide)), memory]))
+            }
+            0b100 if acquire && !release && rs == 31 => {
+                Some((format!("ldapr{}", width), vec![reg(gpr(rt, wide)), memory]))
+            }
+            _ => None,
+        };
+    }
+
+    let operation = OPERATIONS[opc as usize];
+    if rt == 31 && !acquire {
+        let mnemonic = format!("st{}{}{}", operation, if release { "l" } else { "" }, width);
+        return Some((mnemonic, vec![reg(gpr(rs, wide)), memory]));
+    }
+
+    let mnemonic = format!("ld{}{}{}{}", operation, if acquire { "a" } else { "" }, if release { "l" } else { "" }, width);
+    Some((mnemonic, vec![reg(gpr(rs, wide)), reg(gpr(rt, wide)), memory]))
+}
+
+// ldraa/ldrab
+fn pointer_authenticated(word: u32) -> Decoded {
+    let offset = sign_extend((bits(word, 22, 1) << 9) | bits(word, 12, 9), 10) << 3;
+    let mode = if bit(word, 11) { IndexMode::PreIndex } else { IndexMode::Offset };
+    let mnemonic = if bit(word, 23) { "ldrab" } else { "ldraa" };
+    ins(mnemonic, vec![reg(gpr(bits(word, 0, 5), true)), offset_memory(bits(word, 5, 5), 8, offset, mode)])
+}
+
+fn exclusive(word: u32) -> Decoded {
+    let size = bits(word, 30, 2);
+    let o2 = bit(word, 23);
+    let load = bit(word, 22);
+    let o1 = bit(word, 21);
+    let rs = bits(word, 16, 5);
+    let o0 = bit(word, 15);
+    let rt2 = bits(word, 10, 5);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    let wide = size == 3;
+    let width = match size {
+        0b00 => "b",
+        0b01 => "h",
+        _ => "",
+    };
+    let memory = |size: u16| Operand::Memory(base_memory(rn, size));
+
+    match (o2, o1) {
+        // Compare and swap, the Rt2 field is fixed to all ones
+        (true, true) if rt2 == 31 => {
+            let mnemonic = format!("cas{}{}{}", if load { "a" } else { "" }, if o0 { "l" } else { "" }, width);
+            Some((mnemonic, vec![reg(gpr(rs, wide)), reg(gpr(rt, wide)), memory(1 << size)]))
+        }
+        (false, true) if size < 2 => {
+            if rs & 1 == 1 || rt & 1 == 1 || rt2 != 31 {
+                return None;
+            }
+            let wide = size == 1;
+            let mnemonic = format!("casp{}{}", if load { "a" } else { "" }, if o0 { "l" } else { "" });
+            Some((mnemonic, vec![
+                reg(gpr(rs, wide)),
+                reg(gpr(rs + 1, wide)),
+                reg(gpr(rt, wide)),
+                reg(gpr(rt + 1, wide)),
+                memory(if wide { 16 } else { 8 }),
+            ]))
+        }
+        // Exclusive pairs
+        (false, true) => {
+            let wide = size == 3;
+            let pair_size = if wide { 16 } else { 8 };
+            if load {
+                let mnemonic = if o0 { "ldaxp" } else { "ldxp" };
+                ins(mnemonic, vec![reg(gpr(rt, wide)), reg(gpr(rt2, wide)), memory(pair_size)])
+            } else {
+                let mnemonic = if o0 { "stlxp" } else { "stxp" };
+                ins(mnemonic, vec![reg(gpr(rs, false)), reg(gpr(rt, wide)), reg(gpr(rt2, wide)), memory(pair_size)])
+            }
+        }
+        (false, false) => {
+            if load {
+                let mnemonic = format!("ld{}xr{}", if o0 { "a" } else { "" }, width);
+                Some((mnemonic, vec![reg(gpr(rt, wide)), memory(1 << size)]))
+            } else {
+                let mnemonic = format!("st{}xr{}", if o0 { "l" } else { "" }, width);
+                Some((mnemonic, vec![reg(gpr(rs, false)), reg(gpr(rt, wide)), memory(1 << size)]))
+            }
+        }
+        // Load-acquire and store-release, o0 clear selects the LORegion variants
+        (true, true) => None,
+        (true, false) => {
+            let mnemonic = match (load, o0) {
+                (true, true) => format!("ldar{}", width),
+                (true, false) => format!("ldlar{}", width),
+                (false, true) => format!("stlr{}", width),
+                (false, false) => format!("stllr{}", width),
+            };
+            Some((mnemonic, vec![reg(gpr(rt, wide)), memory(1 << size)]))
+        }
+    }
+}
+
+fn post_index(rm: u32, mem: &mut MemoryOperand, bytes: u16) {
+    mem.mode = IndexMode::PostIndex;
+    if rm == 31 {
+        mem.displacement = bytes as i64;
+    } else {
+        mem.index = Some(gpr(rm, true));
+    }
+}
+
+fn simd_multiple(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let load = bit(word, 22);
+    let post = bit(word, 23);
+    let rm = bits(word, 16, 5);
+    let size = bits(word, 10, 2);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    if (!post && rm != 0) || bit(word, 21) {
+        return None;
+    }
+
+    let (structure, count) = match bits(word, 12, 4) {
+        0b0000 => (4, 4),
+        0b0010 => (1, 4),
+        0b0100 => (3, 3),
+        0b0110 => (1, 3),
+        0b0111 => (1, 1),
+        0b1000 => (2, 2),
+        0b1010 => (1, 2),
+        _ => return None,
+    };
+    if structure != 1 && size == 3 && !q {
+        return None;
+    }
+
+    let element_bits = 8 << size;
+    let elements = if q { 128 } else { 64 } / element_bits;
+    let list = (0..count).map(|i| vector_arrangement((rt + i) % 32, element_bits, elements as u8)).collect();
+
+    let bytes = count as u16 * if q { 16 } else { 8 };
+    let mut mem = base_memory(rn, bytes);
+    if post {
+        post_index(rm, &mut mem, bytes);
+    }
+
+    let mnemonic = format!("{}{}", if load { "ld" } else { "st" }, structure);
+    Some((mnemonic, vec![Operand::List(list), Operand::Memory(mem)]))
+}
+
+fn simd_single(word: u32) -> Decoded {
+    let q = bit(word, 30) as u32;
+    let load = bit(word, 22);
+    let post = bit(word, 23);
+    let rm = bits(word, 16, 5);
+    let opcode = bits(word, 13, 3);
+    let s = bit(word, 12) as u32;
+    let size = bits(word, 10, 2);
+    let rn = bits(word, 5, 5);
+    let rt = bits(word, 0, 5);
+
+    if !post && rm != 0 {
+        return None;
+    }
+
+    let count = (((opcode & 1) << 1) | bit(word, 21) as u32) + 1;
+    let prefix = if load { "ld" } else { "st" };
+
+    let (element_bits, index) = match opcode >> 1 {
+        0b00 => (8, q << 3 | s << 2 | size),
+        0b01 if size & 1 == 0 => (16, q << 2 | s << 1 | size >> 1),
+        0b10 if size == 0 => (32, q << 1 | s),
+        0b10 if size == 1 && s == 0 => (64, q),
+        0b11 if load && s == 0 => {
+            // Load and replicate to all lanes
+            let element_bits = 8 << size;
+            let elements = if q == 1 { 128 } else { 64 } / element_bits;
+            let list = (0..count).map(|i| vector_arrangement((rt + i) % 32, element_bits, elements as u8)).collect();
+
+            let bytes = count as u16 * element_bits / 8;
+            let mut mem = base_memory(rn, bytes);
+            if post {
+                post_index(rm, &mut mem, bytes);
+            }
+            return Some((format!("{}{}r", prefix, count), vec![Operand::List(list), Operand::Memory(mem)]));
+        }
+        _ => return None,
+    };
+
+    let list = (0..count).map(|i| vector_lane((rt + i) % 32, element_bits, index as u8)).collect();
+    let bytes = count as u16 * element_bits / 8;
+    let mut mem = base_memory(rn, bytes);
+    if post {
+        post_index(rm, &mut mem, bytes);
+    }
+    Some((format!("{}{}", prefix, count), vec![Operand::List(list), Operand::Memory(mem)]))
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, FlowKind, IndexMode, Instruction, Operand, Register, RegisterClass, ShiftKind};
+
+mod registers;
+pub use self::registers::{gpr_sp, vector};
+
+mod data;
+mod branch;
+mod memory;
+mod simd;
+
+mod format;
+pub use self::format::format;
+
+// Every A64 instruction is a single little endian word
+const LENGTH: usize = 4;
+
+/// Condition code names, indexed by the 4 bit condition field
+pub const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc",
+    "hi", "ls", "ge", "lt", "gt", "le", "al", "nv",
+];
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is unallocated
+type Decoded = Option<(String, Vec<Operand>)>;
+
+#[derive(Debug, Clone)]
+pub struct AArch64Decoder;
+
+impl Decoder for AArch64Decoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let word = u32::from_le_bytes(bytes[..LENGTH].try_into().unwrap());
+
+        let (mnemonic, operands) = decode_word(word, address).ok_or(DecodeError::InvalidOpcode(word))?;
+
+        let mut instruction = Instruction::new(InstructionSet::AArch64, address, LENGTH, String::new(), operands);
+        let (flow, conditional) = flow_of(&mnemonic);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.mnemonic = mnemonic;
+
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        LENGTH
+    }
+}
+
+fn decode_word(word: u32, address: u64) -> Decoded {
+    let op0 = bits(word, 25, 4);
+
+    match op0 {
+        0b0000 if bits(word, 16, 16) == 0 => ins("udf", vec![imm(bits(word, 0, 16) as i64)]),
+        0b1000 | 0b1001 => data::immediate(word, address),
+        0b1010 | 0b1011 => branch::branch_system(word, address),
+        _ if op0 & 0b0101 == 0b0100 => memory::load_store(word, address),
+        _ if op0 & 0b0111 == 0b0101 => data::register(word),
+        _ if op0 & 0b0111 == 0b0111 => simd::simd_fp(word),
+        _ => None,
+    }
+}
+
+fn flow_of(mnemonic: &str) -> (FlowKind, bool) {
+    match mnemonic {
+        "b" | "br" | "braa" | "brab" | "braaz" | "brabz" => (FlowKind::Jump, false),
+        "cbz" | "cbnz" | "tbz" | "tbnz" => (FlowKind::Jump, true),
+        "bl" | "blr" | "blraa" | "blrab" | "blraaz" | "blrabz" => (FlowKind::Call, false),
+        "ret" | "retaa" | "retab" | "eret" | "eretaa" | "eretab" | "drps" => (FlowKind::Return, false),
+        "svc" | "hvc" | "smc" => (FlowKind::Syscall, false),
+        "brk" | "hlt" | "udf" => (FlowKind::Trap, false),
+        _ if mnemonic.starts_with("b.") => (FlowKind::Jump, true),
+        _ => (FlowKind::Sequential, false),
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn reg(register: Register) -> Operand {
+    Operand::Register(register)
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+fn shift(kind: ShiftKind, amount: u8) -> Operand {
+    Operand::Shift { kind, amount }
+}
+
+fn vector_arrangement(number: u32, element_bits: u16, elements: u8) -> Operand {
+    Operand::Vector { register: vector(number), element_bits, elements, lane: None }
+}
+
+fn vector_lane(number: u32, element_bits: u16, lane: u8) -> Operand {
+    Operand::Vector { register: vector(number), element_bits, elements: 0, lane: Some(lane) }
+}
+
+/// An address built from an adrp and a later instruction adding the low 12 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressPair {
+    // Address of the adrp
+    pub page_address: u64,
+    // Address of the add, load or store completing the address
+    pub address: u64,
+    pub target: u64,
+}
+
+/// Finds adrp + add/ldr/str sequences in straight line code
+///
+/// The page held by each register is forgotten when it is overwritten or control flow leaves the block,
+/// so this only finds pairs that are certain to refer to the same adrp
+pub fn address_pairs(instructions: &[Instruction]) -> Vec<AddressPair> {
+    let mut pages: HashMap<u16, (u64, u64)> = HashMap::new();
+    let mut res = Vec::new();
+
+    for instruction in instructions {
+        if instruction.flow != FlowKind::Sequential {
+            pages.clear();
+            continue;
+        }
+
+        let destination = match instruction.operands.first() {
+            Some(Operand::Register(register)) if register.class == RegisterClass::General => Some(register.number),
+            _ => None,
+        };
+
+        match (instruction.mnemonic.as_str(), &instruction.operands[..]) {
+            ("adrp", [_, Operand::Address(page)]) => {
+                pages.insert(destination.unwrap_or(31), (instruction.address, *page));
+                continue;
+            }
+            ("add", [_, Operand::Register(base), Operand::Immediate { value, .. }]) if base.bits == 64 => {
+                if let Some(&(page_address, page)) = pages.get(&base.number) {
+                    res.push(AddressPair { page_address, address: instruction.address, target: page.wrapping_add(*value as u64) });
+                }
+            }
+            _ => {
+                let memory = instruction.operands.iter().find_map(|x| match x {
+                    Operand::Memory(mem) => Some(mem),
+                    _ => None,
+                });
+                if let Some(mem) = memory {
+                    let page = mem.base.and_then(|base| pages.get(&base.number));
+                    if let (Some(&(page_address, page)), IndexMode::Offset, None) = (page, mem.mode, mem.index) {
+                        res.push(AddressPair {
+                            page_address,
+                            address: instruction.address,
+                            target: page.wrapping_add(mem.displacement as u64),
+                        });
+                    } else if mem.mode != IndexMode::Offset {
+                        // Writeback changes the base register
+                        if let Some(base) = mem.base {
+                            pages.remove(&base.number);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(number) = destination {
+            if writes_first_operand(&instruction.mnemonic) {
+                pages.remove(&number);
+            }
+        }
+    }
+
+    res
+}
+
+// Whether the first operand of the instruction is written, as opposed to only read
+fn writes_first_operand(mnemonic: &str) -> bool {
+    // Exclusive stores write their status to the first operand
+    let exclusive = mnemonic.starts_with("stx") || mnemonic.starts_with("stlx");
+    !((mnemonic.starts_with("st") && !exclusive)
+        || mnemonic.starts_with("prf")
+        || matches!(mnemonic, "cmp" | "cmn" | "tst" | "ccmp" | "ccmn" | "msr" | "sys" | "at" | "dc" | "ic" | "tlbi"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AArch64Decoder;
+    use crate::disasm::{Decoder, FlowKind, Syntax};
+
+    fn text(word: u32) -> String {
+        AArch64Decoder.decode(&word.to_le_bytes(), 0x1000).unwrap().render(Syntax::Intel)
+    }
+
+    #[test]
+    fn data_processing_and_memory() {
+        assert_eq!(text(0xa9bf7bfd), "stp x29, x30, [sp, #-16]!");
+        assert_eq!(text(0x910003fd), "mov x29, sp");
+        assert_eq!(text(0xb9400801), "ldr w1, [x0, #8]");
+        assert_eq!(text(0xd2a24682), "mov x2, #0x12340000");
+        assert_eq!(text(0x8b224820), "add x0, x1, w2, uxtw #2");
+        assert_eq!(text(0x1e622820), "fadd d0, d1, d2");
+        assert_eq!(text(0xd503201f), "nop");
+        // The page is relative to the page of the instruction
+        assert_eq!(text(0xd0000080), "adrp x0, 0x13000");
+    }
+
+    #[test]
+    fn branches() {
+        let branch = |word: u32| {
+            let instruction = AArch64Decoder.decode(&word.to_le_bytes(), 0x1000).unwrap();
+            (instruction.render(Syntax::Intel), instruction.flow, instruction.conditional)
+        };
+        assert_eq!(branch(0x94000440), ("bl 0x2100".to_string(), FlowKind::Call, false));
+        assert_eq!(branch(0x34008101), ("cbz w1, 0x2020".to_string(), FlowKind::Jump, true));
+        assert_eq!(branch(0x54008001), ("b.ne 0x2000".to_string(), FlowKind::Jump, true));
+        assert_eq!(branch(0xd61f0200), ("br x16".to_string(), FlowKind::Jump, false));
+        assert_eq!(branch(0xd65f03c0), ("ret".to_string(), FlowKind::Return, false));
+        assert_eq!(branch(0x00000000), ("udf #0x0".to_string(), FlowKind::Trap, false));
+    }
+}
@@ -0,0 +1,1270 @@
+use crate::disasm::{Operand, ShiftKind};
+
+use super::registers::{fp, gpr, vector};
+use super::{bit, bits, imm, ins, reg, shift, vector_arrangement, vector_lane, Decoded};
+
+/// Scalar floating point and Advanced SIMD, op0 = x111
+pub fn simd_fp(word: u32) -> Decoded {
+    if bit(word, 28) && !bit(word, 30) {
+        return scalar_fp(word);
+    }
+    if bit(word, 31) {
+        return None;
+    }
+    if bit(word, 28) {
+        advanced_scalar(word)
+    } else {
+        advanced_vector(word)
+    }
+}
+
+// The whole vector register with the lanes given by size and Q
+fn arrangement(number: u32, size: u32, q: bool) -> Operand {
+    let element_bits = 8 << size;
+    vector_arrangement(number, element_bits, ((if q { 128 } else { 64 }) / element_bits) as u8)
+}
+
+fn lane(number: u32, size: u32, index: u32) -> Operand {
+    vector_lane(number, 8 << size, index as u8)
+}
+
+// A SIMD & FP register as a scalar of 1 << size bytes
+fn scalar(number: u32, size: u32) -> Operand {
+    reg(fp(number, 1 << size))
+}
+
+// Floating point type field to a size, half precision is 1, single 2 and double 3
+fn float_size(ftype: u32) -> Option<u32> {
+    match ftype {
+        0b00 => Some(2),
+        0b01 => Some(3),
+        0b11 => Some(1),
+        _ => None,
+    }
+}
+
+// Second half variants of widening and narrowing operations have a 2 suffix
+fn upper(mnemonic: &str, q: bool) -> String {
+    if q {
+        format!("{}2", mnemonic)
+    } else {
+        mnemonic.to_string()
+    }
+}
+
+fn float_zero() -> Operand {
+    Operand::Float(0f64.to_bits())
+}
+
+/// Expands the 8 bit floating point immediate of fmov
+fn expand_float(imm8: u32) -> f64 {
+    let exponent = ((imm8 >> 4) & 7) as i32;
+    let exponent = if exponent & 4 != 0 { exponent - 8 } else { exponent };
+    let value = (16 + (imm8 & 15)) as f64 / 16.0 * 2f64.powi(exponent + 1);
+    if imm8 & 0x80 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn scalar_fp(word: u32) -> Decoded {
+    if bit(word, 29) {
+        return None;
+    }
+
+    if bit(word, 24) {
+        if bit(word, 31) {
+            return None;
+        }
+        let size = float_size(bits(word, 22, 2))?;
+        let mnemonic = match (bit(word, 21), bit(word, 15)) {
+            (false, false) => "fmadd",
+            (false, true) => "fmsub",
+            (true, false) => "fnmadd",
+            (true, true) => "fnmsub",
+        };
+        return ins(mnemonic, vec![
+            scalar(bits(word, 0, 5), size),
+            scalar(bits(word, 5, 5), size),
+            scalar(bits(word, 16, 5), size),
+            scalar(bits(word, 10, 5), size),
+        ]);
+    }
+
+    if !bit(word, 21) {
+        return fixed_conversion(word);
+    }
+
+    match bits(word, 10, 2) {
+        0b01 => return float_conditional_compare(word),
+        0b10 => return float_two_source(word),
+        0b11 => return float_conditional_select(word),
+        _ => {}
+    }
+
+    if bit(word, 12) {
+        float_immediate(word)
+    } else if bit(word, 13) {
+        float_compare(word)
+    } else if bit(word, 14) {
+        float_one_source(word)
+    } else if bits(word, 10, 6) == 0 {
+        integer_conversion(word)
+    } else {
+        None
+    }
+}
+
+fn float_one_source(word: u32) -> Decoded {
+    if bit(word, 31) {
+        return None;
+    }
+    let ftype = bits(word, 22, 2);
+    let size = float_size(ftype)?;
+    let opcode = bits(word, 15, 6);
+    let rd = bits(word, 0, 5);
+    let rn = scalar(bits(word, 5, 5), size);
+
+    let mnemonic = match opcode {
+        0b000000 => "fmov",
+        0b000001 => "fabs",
+        0b000010 => "fneg",
+        0b000011 => "fsqrt",
+        0b000100..=0b000111 => {
+            let destination = float_size(opcode & 3)?;
+            if destination == size {
+                return None;
+            }
+            return ins("fcvt", vec![scalar(rd, destination), rn]);
+        }
+        0b001000 => "frintn",
+        0b001001 => "frintp",
+        0b001010 => "frintm",
+        0b001011 => "frintz",
+        0b001100 => "frinta",
+        0b001110 => "frintx",
+        0b001111 => "frinti",
+        0b010000 if size > 1 => "frint32z",
+        0b010001 if size > 1 => "frint32x",
+        0b010010 if size > 1 => "frint64z",
+        0b010011 if size > 1 => "frint64x",
+        _ => return None,
+    };
+    ins(mnemonic, vec![scalar(rd, size), rn])
+}
+
+fn float_compare(word: u32) -> Decoded {
+    if bit(word, 31) || bits(word, 14, 2) != 0 || bits(word, 0, 3) != 0 {
+        return None;
+    }
+    let size = float_size(bits(word, 22, 2))?;
+    let mnemonic = if bit(word, 4) { "fcmpe" } else { "fcmp" };
+    let rn = scalar(bits(word, 5, 5), size);
+    let second = if bit(word, 3) {
+        float_zero()
+    } else {
+        scalar(bits(word, 16, 5), size)
+    };
+    ins(mnemonic, vec![rn, second])
+}
+
+fn float_immediate(word: u32) -> Decoded {
+    if bit(word, 31) || bits(word, 5, 5) != 0 {
+        return None;
+    }
+    let size = float_size(bits(word, 22, 2))?;
+    let value = expand_float(bits(word, 13, 8));
+    ins("fmov", vec![scalar(bits(word, 0, 5), size), Operand::Float(value.to_bits())])
+}
+
+fn float_conditional_compare(word: u32) -> Decoded {
+    if bit(word, 31) {
+        return None;
+    }
+    let size = float_size(bits(word, 22, 2))?;
+    let mnemonic = if bit(word, 4) { "fccmpe" } else { "fccmp" };
+    ins(mnemonic, vec![
+        scalar(bits(word, 5, 5), size),
+        scalar(bits(word, 16, 5), size),
+        imm(bits(word, 0, 4) as i64),
+        Operand::Condition(bits(word, 12, 4) as u8),
+    ])
+}
+
+fn float_two_source(word: u32) -> Decoded {
+    if bit(word, 31) {
+        return None;
+    }
+    let size = float_size(bits(word, 22, 2))?;
+    let mnemonic = match bits(word, 12, 4) {
+        0b0000 => "fmul",
+        0b0001 => "fdiv",
+        0b0010 => "fadd",
+        0b0011 => "fsub",
+        0b0100 => "fmax",
+        0b0101 => "fmin",
+        0b0110 => "fmaxnm",
+        0b0111 => "fminnm",
+        0b1000 => "fnmul",
+        _ => return None,
+    };
+    ins(mnemonic, vec![
+        scalar(bits(word, 0, 5), size),
+        scalar(bits(word, 5, 5), size),
+        scalar(bits(word, 16, 5), size),
+    ])
+}
+
+fn float_conditional_select(word: u32) -> Decoded {
+    if bit(word, 31) {
+        return None;
+    }
+    let size = float_size(bits(word, 22, 2))?;
+    ins("fcsel", vec![
+        scalar(bits(word, 0, 5), size),
+        scalar(bits(word, 5, 5), size),
+        scalar(bits(word, 16, 5), size),
+        Operand::Condition(bits(word, 12, 4) as u8),
+    ])
+}
+
+fn integer_conversion(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let ftype = bits(word, 22, 2);
+    let rmode = bits(word, 19, 2);
+    let opcode = bits(word, 16, 3);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    // fmov to and from the upper half of a vector register
+    if ftype == 0b10 {
+        if !sf || rmode != 0b01 {
+            return None;
+        }
+        return match opcode {
+            0b110 => ins("fmov", vec![reg(gpr(rd, true)), vector_lane(rn, 64, 1)]),
+            0b111 => ins("fmov", vec![vector_lane(rd, 64, 1), reg(gpr(rn, true))]),
+            _ => None,
+        };
+    }
+
+    let size = float_size(ftype)?;
+    match opcode {
+        0b000 | 0b001 => {
+            let rounding = ["n", "p", "m", "z"][rmode as usize];
+            let mnemonic = format!("fcvt{}{}", rounding, if opcode == 0 { "s" } else { "u" });
+            Some((mnemonic, vec![reg(gpr(rd, sf)), scalar(rn, size)]))
+        }
+        0b010 | 0b011 if rmode == 0 => {
+            let mnemonic = if opcode == 0b010 { "scvtf" } else { "ucvtf" };
+            ins(mnemonic, vec![scalar(rd, size), reg(gpr(rn, sf))])
+        }
+        0b100 | 0b101 if rmode == 0 => {
+            let mnemonic = if opcode == 0b100 { "fcvtas" } else { "fcvtau" };
+            ins(mnemonic, vec![reg(gpr(rd, sf)), scalar(rn, size)])
+        }
+        0b110 if rmode == 0b11 && !sf && ftype == 0b01 => {
+            ins("fjcvtzs", vec![reg(gpr(rd, false)), scalar(rn, size)])
+        }
+        0b110 | 0b111 if rmode == 0 => {
+            // The register sizes have to match, except for half precision
+            if size != 1 && sf != (size == 3) {
+                return None;
+            }
+            if opcode == 0b110 {
+                ins("fmov", vec![reg(gpr(rd, sf)), scalar(rn, size)])
+            } else {
+                ins("fmov", vec![scalar(rd, size), reg(gpr(rn, sf))])
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fixed_conversion(word: u32) -> Decoded {
+    let sf = bit(word, 31);
+    let size = float_size(bits(word, 22, 2))?;
+    let scale = bits(word, 10, 6);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    if !sf && scale < 32 {
+        return None;
+    }
+    let fraction_bits = imm(64 - scale as i64);
+
+    match (bits(word, 19, 2), bits(word, 16, 3)) {
+        (0b11, 0b000) => ins("fcvtzs", vec![reg(gpr(rd, sf)), scalar(rn, size), fraction_bits]),
+        (0b11, 0b001) => ins("fcvtzu", vec![reg(gpr(rd, sf)), scalar(rn, size), fraction_bits]),
+        (0b00, 0b010) => ins("scvtf", vec![scalar(rd, size), reg(gpr(rn, sf)), fraction_bits]),
+        (0b00, 0b011) => ins("ucvtf", vec![scalar(rd, size), reg(gpr(rn, sf)), fraction_bits]),
+        _ => None,
+    }
+}
+
+fn advanced_vector(word: u32) -> Decoded {
+    if bit(word, 24) {
+        if !bit(word, 10) {
+            return vector_element(word, false);
+        }
+        if bit(word, 23) {
+            return None;
+        }
+        return if bits(word, 19, 4) == 0 {
+            modified_immediate(word)
+        } else {
+            shift_immediate(word, false)
+        };
+    }
+
+    if bit(word, 21) {
+        if bit(word, 10) {
+            return three_same(word, false);
+        }
+        if !bit(word, 11) {
+            return three_different(word);
+        }
+        return match bits(word, 17, 4) {
+            0b0000 => two_register_misc(word, false),
+            0b1000 => across_lanes(word),
+            0b0100 => aes(word),
+            _ => None,
+        };
+    }
+
+    if bit(word, 15) {
+        return if bit(word, 10) { three_same_extra(word) } else { None };
+    }
+    if bit(word, 11) && !bit(word, 10) && !bit(word, 29) {
+        return permute(word);
+    }
+    if bits(word, 22, 2) != 0 {
+        return None;
+    }
+    if bit(word, 10) {
+        copy(word)
+    } else if bit(word, 29) {
+        extract(word)
+    } else {
+        table_lookup(word)
+    }
+}
+
+// Rounding doubling multiply accumulate and dot products
+fn three_same_extra(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+    let rm = bits(word, 16, 5);
+
+    match (unsigned, bits(word, 11, 4)) {
+        (true, 0b0000) | (true, 0b0001) if size == 1 || size == 2 => {
+            let mnemonic = if bit(word, 11) { "sqrdmlsh" } else { "sqrdmlah" };
+            ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), arrangement(rm, size, q)])
+        }
+        (_, 0b0010) if size == 2 => {
+            let mnemonic = if unsigned { "udot" } else { "sdot" };
+            ins(mnemonic, vec![arrangement(rd, 2, q), arrangement(rn, 0, q), arrangement(rm, 0, q)])
+        }
+        _ => None,
+    }
+}
+
+fn advanced_scalar(word: u32) -> Decoded {
+    if bit(word, 24) {
+        if !bit(word, 10) {
+            return vector_element(word, true);
+        }
+        if bit(word, 23) {
+            return None;
+        }
+        return shift_immediate(word, true);
+    }
+
+    if bit(word, 21) {
+        if bit(word, 10) {
+            return three_same(word, true);
+        }
+        if !bit(word, 11) {
+            return scalar_three_different(word);
+        }
+        return match bits(word, 17, 4) {
+            0b0000 => two_register_misc(word, true),
+            0b1000 => scalar_pairwise(word),
+            0b0100 if !bit(word, 29) && bits(word, 22, 2) == 0 => sha_two_register(word),
+            _ => None,
+        };
+    }
+
+    if !bit(word, 29) && bits(word, 22, 2) == 0 && !bit(word, 15) && bits(word, 10, 2) == 0 {
+        return sha_three_register(word);
+    }
+
+    // Scalar dup, always shown as mov
+    if bits(word, 22, 2) == 0 && !bit(word, 15) && bit(word, 10) && !bit(word, 29) && bits(word, 11, 4) == 0 {
+        let imm5 = bits(word, 16, 5);
+        let size = imm5.trailing_zeros();
+        if size > 3 {
+            return None;
+        }
+        return ins("mov", vec![scalar(bits(word, 0, 5), size), lane(bits(word, 5, 5), size, imm5 >> (size + 1))]);
+    }
+    None
+}
+
+fn copy(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let op = bit(word, 29);
+    let imm5 = bits(word, 16, 5);
+    let imm4 = bits(word, 11, 4);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    let size = imm5.trailing_zeros();
+    if size > 3 {
+        return None;
+    }
+    let index = imm5 >> (size + 1);
+
+    if op {
+        if !q {
+            return None;
+        }
+        return ins("mov", vec![lane(rd, size, index), lane(rn, size, imm4 >> size)]);
+    }
+
+    match imm4 {
+        0b0000 if size != 3 || q => ins("dup", vec![arrangement(rd, size, q), lane(rn, size, index)]),
+        0b0001 if size != 3 || q => ins("dup", vec![arrangement(rd, size, q), reg(gpr(rn, size == 3))]),
+        0b0011 if q => ins("mov", vec![lane(rd, size, index), reg(gpr(rn, size == 3))]),
+        0b0101 if (q && size < 3) || size < 2 => ins("smov", vec![reg(gpr(rd, q)), lane(rn, size, index)]),
+        0b0111 => {
+            match (q, size) {
+                (false, 2) | (true, 3) => ins("mov", vec![reg(gpr(rd, q)), lane(rn, size, index)]),
+                (false, _) => ins("umov", vec![reg(gpr(rd, false)), lane(rn, size, index)]),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn extract(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let index = bits(word, 11, 4);
+    if !q && index >= 8 {
+        return None;
+    }
+    ins("ext", vec![
+        arrangement(bits(word, 0, 5), 0, q),
+        arrangement(bits(word, 5, 5), 0, q),
+        arrangement(bits(word, 16, 5), 0, q),
+        imm(index as i64),
+    ])
+}
+
+fn permute(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let size = bits(word, 22, 2);
+    if size == 3 && !q {
+        return None;
+    }
+    let mnemonic = match bits(word, 12, 3) {
+        0b001 => "uzp1",
+        0b010 => "trn1",
+        0b011 => "zip1",
+        0b101 => "uzp2",
+        0b110 => "trn2",
+        0b111 => "zip2",
+        _ => return None,
+    };
+    ins(mnemonic, vec![
+        arrangement(bits(word, 0, 5), size, q),
+        arrangement(bits(word, 5, 5), size, q),
+        arrangement(bits(word, 16, 5), size, q),
+    ])
+}
+
+fn table_lookup(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let count = bits(word, 13, 2) + 1;
+    let rn = bits(word, 5, 5);
+    let table = (0..count).map(|i| arrangement((rn + i) % 32, 0, true)).collect();
+    let mnemonic = if bit(word, 12) { "tbx" } else { "tbl" };
+    ins(mnemonic, vec![
+        arrangement(bits(word, 0, 5), 0, q),
+        Operand::List(table),
+        arrangement(bits(word, 16, 5), 0, q),
+    ])
+}
+
+fn aes(word: u32) -> Decoded {
+    if !bit(word, 30) || bit(word, 29) || bits(word, 22, 2) != 0 {
+        return None;
+    }
+    let mnemonic = match bits(word, 12, 5) {
+        0b00100 => "aese",
+        0b00101 => "aesd",
+        0b00110 => "aesmc",
+        0b00111 => "aesimc",
+        _ => return None,
+    };
+    ins(mnemonic, vec![arrangement(bits(word, 0, 5), 0, true), arrangement(bits(word, 5, 5), 0, true)])
+}
+
+fn sha_three_register(word: u32) -> Decoded {
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+    let rm = arrangement(bits(word, 16, 5), 2, true);
+    let (mnemonic, operands) = match bits(word, 12, 3) {
+        0b000 => ("sha1c", vec![scalar(rd, 4), scalar(rn, 2), rm]),
+        0b001 => ("sha1p", vec![scalar(rd, 4), scalar(rn, 2), rm]),
+        0b010 => ("sha1m", vec![scalar(rd, 4), scalar(rn, 2), rm]),
+        0b011 => ("sha1su0", vec![arrangement(rd, 2, true), arrangement(rn, 2, true), rm]),
+        0b100 => ("sha256h", vec![scalar(rd, 4), scalar(rn, 4), rm]),
+        0b101 => ("sha256h2", vec![scalar(rd, 4), scalar(rn, 4), rm]),
+        0b110 => ("sha256su1", vec![arrangement(rd, 2, true), arrangement(rn, 2, true), rm]),
+        _ => return None,
+    };
+    ins(mnemonic, operands)
+}
+
+fn sha_two_register(word: u32) -> Decoded {
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+    match bits(word, 12, 5) {
+        0b00000 => ins("sha1h", vec![scalar(rd, 2), scalar(rn, 2)]),
+        0b00001 => ins("sha1su1", vec![arrangement(rd, 2, true), arrangement(rn, 2, true)]),
+        0b00010 => ins("sha256su0", vec![arrangement(rd, 2, true), arrangement(rn, 2, true)]),
+        _ => None,
+    }
+}
+
+// Floating point form of the three same group: (U, a, opcode) to mnemonic
+fn three_same_float(unsigned: bool, a: bool, opcode: u32, scalar: bool) -> Option<&'static str> {
+    let mnemonic = match (unsigned, a, opcode) {
+        (false, false, 0b11011) => "fmulx",
+        (false, false, 0b11100) => "fcmeq",
+        (false, false, 0b11111) => "frecps",
+        (false, true, 0b11111) => "frsqrts",
+        (true, false, 0b11100) => "fcmge",
+        (true, false, 0b11101) => "facge",
+        (true, true, 0b11010) => "fabd",
+        (true, true, 0b11100) => "fcmgt",
+        (true, true, 0b11101) => "facgt",
+        _ if scalar => return None,
+        (false, false, 0b11000) => "fmaxnm",
+        (false, false, 0b11001) => "fmla",
+        (false, false, 0b11010) => "fadd",
+        (false, false, 0b11110) => "fmax",
+        (false, true, 0b11000) => "fminnm",
+        (false, true, 0b11001) => "fmls",
+        (false, true, 0b11010) => "fsub",
+        (false, true, 0b11110) => "fmin",
+        (true, false, 0b11000) => "fmaxnmp",
+        (true, false, 0b11010) => "faddp",
+        (true, false, 0b11011) => "fmul",
+        (true, false, 0b11110) => "fmaxp",
+        (true, false, 0b11111) => "fdiv",
+        (true, true, 0b11000) => "fminnmp",
+        (true, true, 0b11110) => "fminp",
+        _ => return None,
+    };
+    Some(mnemonic)
+}
+
+fn three_same(word: u32, is_scalar: bool) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let opcode = bits(word, 11, 5);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+    let rm = bits(word, 16, 5);
+
+    if opcode >= 0b11000 {
+        let mnemonic = three_same_float(unsigned, size & 2 != 0, opcode, is_scalar)?;
+        let size = 2 + (size & 1);
+        if is_scalar {
+            return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size), scalar(rm, size)]);
+        }
+        if size == 3 && !q {
+            return None;
+        }
+        return ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), arrangement(rm, size, q)]);
+    }
+
+    if opcode == 0b00011 {
+        if is_scalar {
+            return None;
+        }
+        let mnemonic = match (unsigned, size) {
+            (false, 0b00) => "and",
+            (false, 0b01) => "bic",
+            (false, 0b10) if rn == rm => {
+                return ins("mov", vec![arrangement(rd, 0, q), arrangement(rn, 0, q)]);
+            }
+            (false, 0b10) => "orr",
+            (false, _) => "orn",
+            (true, 0b00) => "eor",
+            (true, 0b01) => "bsl",
+            (true, 0b10) => "bit",
+            (true, _) => "bif",
+        };
+        return ins(mnemonic, vec![arrangement(rd, 0, q), arrangement(rn, 0, q), arrangement(rm, 0, q)]);
+    }
+
+    // Operations that also exist for 64 bit elements
+    let any_size = matches!(opcode, 0b00001 | 0b00101 | 0b00110 | 0b00111 | 0b01000..=0b01011 | 0b10000 | 0b10001 | 0b10111);
+
+    let mnemonic = match (unsigned, opcode) {
+        (false, 0b00000) => "shadd",
+        (true, 0b00000) => "uhadd",
+        (false, 0b00001) => "sqadd",
+        (true, 0b00001) => "uqadd",
+        (false, 0b00010) => "srhadd",
+        (true, 0b00010) => "urhadd",
+        (false, 0b00100) => "shsub",
+        (true, 0b00100) => "uhsub",
+        (false, 0b00101) => "sqsub",
+        (true, 0b00101) => "uqsub",
+        (false, 0b00110) => "cmgt",
+        (true, 0b00110) => "cmhi",
+        (false, 0b00111) => "cmge",
+        (true, 0b00111) => "cmhs",
+        (false, 0b01000) => "sshl",
+        (true, 0b01000) => "ushl",
+        (false, 0b01001) => "sqshl",
+        (true, 0b01001) => "uqshl",
+        (false, 0b01010) => "srshl",
+        (true, 0b01010) => "urshl",
+        (false, 0b01011) => "sqrshl",
+        (true, 0b01011) => "uqrshl",
+        (false, 0b01100) => "smax",
+        (true, 0b01100) => "umax",
+        (false, 0b01101) => "smin",
+        (true, 0b01101) => "umin",
+        (false, 0b01110) => "sabd",
+        (true, 0b01110) => "uabd",
+        (false, 0b01111) => "saba",
+        (true, 0b01111) => "uaba",
+        (false, 0b10000) => "add",
+        (true, 0b10000) => "sub",
+        (false, 0b10001) => "cmtst",
+        (true, 0b10001) => "cmeq",
+        (false, 0b10010) => "mla",
+        (true, 0b10010) => "mls",
+        (false, 0b10011) => "mul",
+        (true, 0b10011) if size == 0 => "pmul",
+        (false, 0b10100) => "smaxp",
+        (true, 0b10100) => "umaxp",
+        (false, 0b10101) => "sminp",
+        (true, 0b10101) => "uminp",
+        (false, 0b10110) if size == 1 || size == 2 => "sqdmulh",
+        (true, 0b10110) if size == 1 || size == 2 => "sqrdmulh",
+        (false, 0b10111) => "addp",
+        _ => return None,
+    };
+
+    if is_scalar {
+        // Only saturating, shift, compare and the sqdmulh family have scalar forms, mostly limited to 64 bits
+        let saturating = matches!(opcode, 0b00001 | 0b00101 | 0b01001 | 0b01011);
+        let doubling = opcode == 0b10110;
+        let double_only = matches!(opcode, 0b00110 | 0b00111 | 0b01000 | 0b01010 | 0b10000 | 0b10001);
+        if !(saturating || doubling || (double_only && size == 3)) {
+            return None;
+        }
+        return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size), scalar(rm, size)]);
+    }
+
+    if size == 3 && (!any_size || !q) {
+        return None;
+    }
+    ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), arrangement(rm, size, q)])
+}
+
+fn three_different(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let opcode = bits(word, 12, 4);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+    let rm = bits(word, 16, 5);
+
+    if opcode == 0b1110 && !unsigned && (size == 0 || size == 3) {
+        // Polynomial multiply, 64 bit elements produce a single 128 bit result
+        if size == 3 {
+            return Some((upper("pmull", q), vec![
+                vector_arrangement(rd, 128, 1),
+                arrangement(rn, 3, q),
+                arrangement(rm, 3, q),
+            ]));
+        }
+        return Some((upper("pmull", q), vec![arrangement(rd, 1, true), arrangement(rn, 0, q), arrangement(rm, 0, q)]));
+    }
+    if size == 3 {
+        return None;
+    }
+
+    // Shape of the operands: long (wide <- narrow, narrow), wide (wide <- wide, narrow) or narrow (narrow <- wide, wide)
+    enum Shape {
+        Long,
+        Wide,
+        Narrow,
+    }
+
+    let (mnemonic, shape) = match (unsigned, opcode) {
+        (false, 0b0000) => ("saddl", Shape::Long),
+        (true, 0b0000) => ("uaddl", Shape::Long),
+        (false, 0b0001) => ("saddw", Shape::Wide),
+        (true, 0b0001) => ("uaddw", Shape::Wide),
+        (false, 0b0010) => ("ssubl", Shape::Long),
+        (true, 0b0010) => ("usubl", Shape::Long),
+        (false, 0b0011) => ("ssubw", Shape::Wide),
+        (true, 0b0011) => ("usubw", Shape::Wide),
+        (false, 0b0100) => ("addhn", Shape::Narrow),
+        (true, 0b0100) => ("raddhn", Shape::Narrow),
+        (false, 0b0101) => ("sabal", Shape::Long),
+        (true, 0b0101) => ("uabal", Shape::Long),
+        (false, 0b0110) => ("subhn", Shape::Narrow),
+        (true, 0b0110) => ("rsubhn", Shape::Narrow),
+        (false, 0b0111) => ("sabdl", Shape::Long),
+        (true, 0b0111) => ("uabdl", Shape::Long),
+        (false, 0b1000) => ("smlal", Shape::Long),
+        (true, 0b1000) => ("umlal", Shape::Long),
+        (false, 0b1001) if size != 0 => ("sqdmlal", Shape::Long),
+        (false, 0b1010) => ("smlsl", Shape::Long),
+        (true, 0b1010) => ("umlsl", Shape::Long),
+        (false, 0b1011) if size != 0 => ("sqdmlsl", Shape::Long),
+        (false, 0b1100) => ("smull", Shape::Long),
+        (true, 0b1100) => ("umull", Shape::Long),
+        (false, 0b1101) if size != 0 => ("sqdmull", Shape::Long),
+        _ => return None,
+    };
+
+    let wide = |x| arrangement(x, size + 1, true);
+    let narrow = |x| arrangement(x, size, q);
+    let operands = match shape {
+        Shape::Long => vec![wide(rd), narrow(rn), narrow(rm)],
+        Shape::Wide => vec![wide(rd), wide(rn), narrow(rm)],
+        Shape::Narrow => vec![narrow(rd), wide(rn), wide(rm)],
+    };
+    Some((upper(mnemonic, q), operands))
+}
+
+fn scalar_three_different(word: u32) -> Decoded {
+    let size = bits(word, 22, 2);
+    if bit(word, 29) || size == 0 || size == 3 {
+        return None;
+    }
+    let mnemonic = match bits(word, 12, 4) {
+        0b1001 => "sqdmlal",
+        0b1011 => "sqdmlsl",
+        0b1101 => "sqdmull",
+        _ => return None,
+    };
+    ins(mnemonic, vec![
+        scalar(bits(word, 0, 5), size + 1),
+        scalar(bits(word, 5, 5), size),
+        scalar(bits(word, 16, 5), size),
+    ])
+}
+
+fn two_register_misc(word: u32, is_scalar: bool) -> Decoded {
+    let q = bit(word, 30) || is_scalar;
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let opcode = bits(word, 12, 5);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    let a = size & 2 != 0;
+    let float_size = 2 + (size & 1);
+
+    // Operands for the element size `size`, either scalar or vector
+    let same = |x: u32, size: u32| if is_scalar { scalar(x, size) } else { arrangement(x, size, q) };
+
+    // Conversions between element sizes
+    match (unsigned, opcode) {
+        (_, 0b10010) | (_, 0b10100) => {
+            let mnemonic = match (unsigned, opcode) {
+                (false, 0b10010) => "xtn",
+                (true, 0b10010) => "sqxtun",
+                (false, _) => "sqxtn",
+                (true, _) => "uqxtn",
+            };
+            if size == 3 || (is_scalar && mnemonic == "xtn") {
+                return None;
+            }
+            if is_scalar {
+                return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size + 1)]);
+            }
+            return Some((upper(mnemonic, bit(word, 30)), vec![arrangement(rd, size, bit(word, 30)), arrangement(rn, size + 1, true)]));
+        }
+        (true, 0b10011) if !is_scalar && size != 3 => {
+            let q = bit(word, 30);
+            return Some((upper("shll", q), vec![
+                arrangement(rd, size + 1, true),
+                arrangement(rn, size, q),
+                imm(8 << size),
+            ]));
+        }
+        (false, 0b10110) | (true, 0b10110) if !a => {
+            let mnemonic = if unsigned { "fcvtxn" } else { "fcvtn" };
+            if unsigned && size & 1 == 0 {
+                return None;
+            }
+            if is_scalar {
+                if !unsigned {
+                    return None;
+                }
+                return ins(mnemonic, vec![scalar(rd, 2), scalar(rn, 3)]);
+            }
+            let q = bit(word, 30);
+            return Some((upper(mnemonic, q), vec![arrangement(rd, float_size - 1, q), arrangement(rn, float_size, true)]));
+        }
+        (false, 0b10111) if !a && !is_scalar => {
+            let q = bit(word, 30);
+            return Some((upper("fcvtl", q), vec![arrangement(rd, float_size, true), arrangement(rn, float_size - 1, q)]));
+        }
+        (_, 0b00010) | (_, 0b00110) if !is_scalar && size != 3 => {
+            let mnemonic = match (unsigned, opcode) {
+                (false, 0b00010) => "saddlp",
+                (true, 0b00010) => "uaddlp",
+                (false, _) => "sadalp",
+                (true, _) => "uadalp",
+            };
+            let wide = vector_arrangement(rd, 16 << size, ((if q { 128 } else { 64 }) / (16 << size)) as u8);
+            return ins(mnemonic, vec![wide, arrangement(rn, size, q)]);
+        }
+        _ => {}
+    }
+
+    // Floating point operations, a selects between pairs of operations
+    let float = match (unsigned, a, opcode) {
+        (false, false, 0b11000) => Some("frintn"),
+        (false, false, 0b11001) => Some("frintm"),
+        (false, false, 0b11010) => Some("fcvtns"),
+        (false, false, 0b11011) => Some("fcvtms"),
+        (false, false, 0b11100) => Some("fcvtas"),
+        (false, false, 0b11101) => Some("scvtf"),
+        (false, true, 0b01111) => Some("fabs"),
+        (false, true, 0b11000) => Some("frintp"),
+        (false, true, 0b11001) => Some("frintz"),
+        (false, true, 0b11010) => Some("fcvtps"),
+        (false, true, 0b11011) => Some("fcvtzs"),
+        (false, true, 0b11101) => Some("frecpe"),
+        (false, true, 0b11111) => Some("frecpx"),
+        (true, false, 0b11000) => Some("frinta"),
+        (true, false, 0b11001) => Some("frintx"),
+        (true, false, 0b11010) => Some("fcvtnu"),
+        (true, false, 0b11011) => Some("fcvtmu"),
+        (true, false, 0b11100) => Some("fcvtau"),
+        (true, false, 0b11101) => Some("ucvtf"),
+        (true, true, 0b01111) => Some("fneg"),
+        (true, true, 0b11001) => Some("frinti"),
+        (true, true, 0b11010) => Some("fcvtpu"),
+        (true, true, 0b11011) => Some("fcvtzu"),
+        (true, true, 0b11101) => Some("frsqrte"),
+        (true, true, 0b11111) => Some("fsqrt"),
+        _ => None,
+    };
+    if let Some(mnemonic) = float {
+        let rounding = mnemonic.starts_with("frint") || matches!(mnemonic, "fabs" | "fneg" | "fsqrt");
+        let only_vector = rounding || mnemonic == "fsqrt";
+        let only_scalar = mnemonic == "frecpx";
+        if (is_scalar && only_vector) || (!is_scalar && only_scalar) || (!is_scalar && float_size == 3 && !q) {
+            return None;
+        }
+        return ins(mnemonic, vec![same(rd, float_size), same(rn, float_size)]);
+    }
+
+    // Floating point comparisons with zero
+    let float_compare = match (unsigned, a, opcode) {
+        (false, true, 0b01100) => Some("fcmgt"),
+        (false, true, 0b01101) => Some("fcmeq"),
+        (false, true, 0b01110) => Some("fcmlt"),
+        (true, true, 0b01100) => Some("fcmge"),
+        (true, true, 0b01101) => Some("fcmle"),
+        _ => None,
+    };
+    if let Some(mnemonic) = float_compare {
+        if !is_scalar && float_size == 3 && !q {
+            return None;
+        }
+        return ins(mnemonic, vec![same(rd, float_size), same(rn, float_size), float_zero()]);
+    }
+
+    // Integer estimates, only for 32 bit elements
+    match (unsigned, opcode) {
+        (false, 0b11100) | (true, 0b11100) if size == 2 && !is_scalar => {
+            return ins(if unsigned { "ursqrte" } else { "urecpe" }, vec![same(rd, 2), same(rn, 2)]);
+        }
+        _ => {}
+    }
+
+    let compare_zero = match (unsigned, opcode) {
+        (false, 0b01000) => Some("cmgt"),
+        (true, 0b01000) => Some("cmge"),
+        (false, 0b01001) => Some("cmeq"),
+        (true, 0b01001) => Some("cmle"),
+        (false, 0b01010) => Some("cmlt"),
+        _ => None,
+    };
+    if let Some(mnemonic) = compare_zero {
+        if (is_scalar && size != 3) || (!is_scalar && size == 3 && !q) {
+            return None;
+        }
+        return ins(mnemonic, vec![same(rd, size), same(rn, size), imm(0)]);
+    }
+
+    let mnemonic = match (unsigned, opcode) {
+        (false, 0b00000) if size < 3 && !is_scalar => "rev64",
+        (true, 0b00000) if size < 2 && !is_scalar => "rev32",
+        (false, 0b00001) if size == 0 && !is_scalar => "rev16",
+        (false, 0b00011) => "suqadd",
+        (true, 0b00011) => "usqadd",
+        (false, 0b00100) if size < 3 && !is_scalar => "cls",
+        (true, 0b00100) if size < 3 && !is_scalar => "clz",
+        (false, 0b00101) if size == 0 && !is_scalar => "cnt",
+        (true, 0b00101) if size == 0 && !is_scalar => "mvn",
+        (true, 0b00101) if size == 1 && !is_scalar => {
+            return ins("rbit", vec![arrangement(rd, 0, q), arrangement(rn, 0, q)]);
+        }
+        (false, 0b00111) => "sqabs",
+        (true, 0b00111) => "sqneg",
+        (false, 0b01011) => "abs",
+        (true, 0b01011) => "neg",
+        _ => return None,
+    };
+    if is_scalar && matches!(mnemonic, "abs" | "neg") && size != 3 {
+        return None;
+    }
+    if !is_scalar && size == 3 && !q {
+        return None;
+    }
+    ins(mnemonic, vec![same(rd, size), same(rn, size)])
+}
+
+fn across_lanes(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let opcode = bits(word, 12, 5);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    if unsigned && (opcode == 0b01100 || opcode == 0b01111) {
+        if size & 1 != 0 || !q {
+            return None;
+        }
+        let mnemonic = match (opcode, size & 2 != 0) {
+            (0b01100, false) => "fmaxnmv",
+            (0b01100, true) => "fminnmv",
+            (_, false) => "fmaxv",
+            (_, true) => "fminv",
+        };
+        return ins(mnemonic, vec![scalar(rd, 2), arrangement(rn, 2, true)]);
+    }
+
+    if size == 3 || (size == 2 && !q) {
+        return None;
+    }
+    let (mnemonic, long) = match (unsigned, opcode) {
+        (false, 0b00011) => ("saddlv", true),
+        (true, 0b00011) => ("uaddlv", true),
+        (false, 0b01010) => ("smaxv", false),
+        (true, 0b01010) => ("umaxv", false),
+        (false, 0b11010) => ("sminv", false),
+        (true, 0b11010) => ("uminv", false),
+        (false, 0b11011) => ("addv", false),
+        _ => return None,
+    };
+    let destination = scalar(rd, if long { size + 1 } else { size });
+    ins(mnemonic, vec![destination, arrangement(rn, size, q)])
+}
+
+fn scalar_pairwise(word: u32) -> Decoded {
+    let size = bits(word, 22, 2);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    if !bit(word, 29) {
+        if size == 3 && bits(word, 12, 5) == 0b11011 {
+            return ins("addp", vec![scalar(rd, 3), arrangement(rn, 3, true)]);
+        }
+        return None;
+    }
+
+    let float_size = 2 + (size & 1);
+    let mnemonic = match (bits(word, 12, 5), size & 2 != 0) {
+        (0b01100, false) => "fmaxnmp",
+        (0b01100, true) => "fminnmp",
+        (0b01101, false) => "faddp",
+        (0b01111, false) => "fmaxp",
+        (0b01111, true) => "fminp",
+        _ => return None,
+    };
+    ins(mnemonic, vec![scalar(rd, float_size), arrangement(rn, float_size, float_size == 3)])
+}
+
+fn modified_immediate(word: u32) -> Decoded {
+    let q = bit(word, 30);
+    let op = bit(word, 29);
+    let cmode = bits(word, 12, 4);
+    let imm8 = (bits(word, 16, 3) << 5) | bits(word, 5, 5);
+    let rd = bits(word, 0, 5);
+
+    if bit(word, 11) {
+        return None;
+    }
+    let value = imm(imm8 as i64);
+
+    match cmode {
+        // 32 bit lanes, shifted by a multiple of 8
+        0b0000..=0b0111 => {
+            let mnemonic = match (cmode & 1 != 0, op) {
+                (false, false) => "movi",
+                (false, true) => "mvni",
+                (true, false) => "orr",
+                (true, true) => "bic",
+            };
+            let mut operands = vec![arrangement(rd, 2, q), value];
+            let amount = (cmode >> 1 & 3) * 8;
+            if amount != 0 {
+                operands.push(shift(ShiftKind::Lsl, amount as u8));
+            }
+            ins(mnemonic, operands)
+        }
+        // 16 bit lanes
+        0b1000..=0b1011 => {
+            let mnemonic = match (cmode & 1 != 0, op) {
+                (false, false) => "movi",
+                (false, true) => "mvni",
+                (true, false) => "orr",
+                (true, true) => "bic",
+            };
+            let mut operands = vec![arrangement(rd, 1, q), value];
+            if cmode & 2 != 0 {
+                operands.push(shift(ShiftKind::Lsl, 8));
+            }
+            ins(mnemonic, operands)
+        }
+        // 32 bit lanes, shifting in ones
+        0b1100 | 0b1101 => {
+            let amount = if cmode & 1 != 0 { 16 } else { 8 };
+            ins(if op { "mvni" } else { "movi" }, vec![arrangement(rd, 2, q), value, shift(ShiftKind::Msl, amount)])
+        }
+        0b1110 if !op => ins("movi", vec![arrangement(rd, 0, q), value]),
+        0b1110 => {
+            // Every bit of the immediate selects a byte of ones
+            let mut expanded = 0u64;
+            for i in 0..8 {
+                if imm8 & (1 << i) != 0 {
+                    expanded |= 0xFF << (i * 8);
+                }
+            }
+            let destination = if q { arrangement(rd, 3, true) } else { scalar(rd, 3) };
+            ins("movi", vec![destination, Operand::Immediate { value: expanded as i64, size: 8 }])
+        }
+        _ => {
+            let value = Operand::Float(expand_float(imm8).to_bits());
+            match (op, q) {
+                (false, _) => ins("fmov", vec![arrangement(rd, 2, q), value]),
+                (true, true) => ins("fmov", vec![arrangement(rd, 3, true), value]),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn shift_immediate(word: u32, is_scalar: bool) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let immh = bits(word, 19, 4);
+    let immhb = bits(word, 16, 7);
+    let opcode = bits(word, 11, 5);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    if immh == 0 {
+        return None;
+    }
+    let size = 31 - immh.leading_zeros();
+    let element_bits = 8 << size;
+    let right = imm((2 * element_bits - immhb) as i64);
+    let left = imm((immhb - element_bits) as i64);
+
+    // Narrowing and widening shifts
+    let narrowing = match (unsigned, opcode) {
+        (false, 0b10000) => Some("shrn"),
+        (false, 0b10001) => Some("rshrn"),
+        (false, 0b10010) => Some("sqshrn"),
+        (false, 0b10011) => Some("sqrshrn"),
+        (true, 0b10000) => Some("sqshrun"),
+        (true, 0b10001) => Some("sqrshrun"),
+        (true, 0b10010) => Some("uqshrn"),
+        (true, 0b10011) => Some("uqrshrn"),
+        _ => None,
+    };
+    if let Some(mnemonic) = narrowing {
+        if size == 3 || (is_scalar && (mnemonic == "shrn" || mnemonic == "rshrn")) {
+            return None;
+        }
+        if is_scalar {
+            return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size + 1), right]);
+        }
+        return Some((upper(mnemonic, q), vec![arrangement(rd, size, q), arrangement(rn, size + 1, true), right]));
+    }
+    if opcode == 0b10100 {
+        if size == 3 || is_scalar {
+            return None;
+        }
+        let wide = arrangement(rd, size + 1, true);
+        if immhb == element_bits {
+            let mnemonic = if unsigned { "uxtl" } else { "sxtl" };
+            return Some((upper(mnemonic, q), vec![wide, arrangement(rn, size, q)]));
+        }
+        let mnemonic = if unsigned { "ushll" } else { "sshll" };
+        return Some((upper(mnemonic, q), vec![wide, arrangement(rn, size, q), left]));
+    }
+
+    let (mnemonic, amount) = match (unsigned, opcode) {
+        (false, 0b00000) => ("sshr", right),
+        (true, 0b00000) => ("ushr", right),
+        (false, 0b00010) => ("ssra", right),
+        (true, 0b00010) => ("usra", right),
+        (false, 0b00100) => ("srshr", right),
+        (true, 0b00100) => ("urshr", right),
+        (false, 0b00110) => ("srsra", right),
+        (true, 0b00110) => ("ursra", right),
+        (true, 0b01000) => ("sri", right),
+        (false, 0b01010) => ("shl", left),
+        (true, 0b01010) => ("sli", left),
+        (true, 0b01100) => ("sqshlu", left),
+        (false, 0b01110) => ("sqshl", left),
+        (true, 0b01110) => ("uqshl", left),
+        (false, 0b11100) => ("scvtf", right),
+        (true, 0b11100) => ("ucvtf", right),
+        (false, 0b11111) => ("fcvtzs", right),
+        (true, 0b11111) => ("fcvtzu", right),
+        _ => return None,
+    };
+
+    let fixed_point = matches!(opcode, 0b11100 | 0b11111);
+    if fixed_point && size < 2 {
+        return None;
+    }
+    // Plain shifts only exist for 64 bit scalars
+    let saturating = matches!(opcode, 0b01100 | 0b01110);
+    if is_scalar && !fixed_point && !saturating && size != 3 {
+        return None;
+    }
+    if is_scalar {
+        return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size), amount]);
+    }
+    if size == 3 && !q {
+        return None;
+    }
+    ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), amount])
+}
+
+fn vector_element(word: u32, is_scalar: bool) -> Decoded {
+    let q = bit(word, 30);
+    let unsigned = bit(word, 29);
+    let size = bits(word, 22, 2);
+    let l = bits(word, 21, 1);
+    let m = bits(word, 20, 1);
+    let opcode = bits(word, 12, 4);
+    let h = bits(word, 11, 1);
+    let rd = bits(word, 0, 5);
+    let rn = bits(word, 5, 5);
+
+    let float = matches!((unsigned, opcode), (false, 0b0001) | (false, 0b0101) | (false, 0b1001) | (true, 0b1001));
+
+    // Lane index and register of the element operand
+    let element = |size: u32| -> Option<Operand> {
+        match size {
+            1 => Some(lane(bits(word, 16, 4), 1, h << 2 | l << 1 | m)),
+            2 => Some(lane(bits(word, 16, 5), 2, h << 1 | l)),
+            3 if l == 0 => Some(lane(bits(word, 16, 5), 3, h)),
+            _ => None,
+        }
+    };
+
+    if float {
+        let mnemonic = match (unsigned, opcode) {
+            (false, 0b0001) => "fmla",
+            (false, 0b0101) => "fmls",
+            (false, 0b1001) => "fmul",
+            _ => "fmulx",
+        };
+        if size < 2 {
+            return None;
+        }
+        let element = element(size)?;
+        if is_scalar {
+            return ins(mnemonic, vec![scalar(rd, size), scalar(rn, size), element]);
+        }
+        if size == 3 && !q {
+            return None;
+        }
+        return ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), element]);
+    }
+
+    if size == 0 || size == 3 {
+        return None;
+    }
+
+    if opcode == 0b1110 && !is_scalar {
+        // Dot products take a group of four bytes as the element
+        if size != 2 {
+            return None;
+        }
+        let mnemonic = if unsigned { "udot" } else { "sdot" };
+        let group = Operand::Vector {
+            register: vector(bits(word, 16, 5)),
+            element_bits: 8,
+            elements: 4,
+            lane: Some((h << 1 | l) as u8),
+        };
+        return ins(mnemonic, vec![arrangement(rd, 2, q), arrangement(rn, 0, q), group]);
+    }
+
+    let element = element(size)?;
+
+    let (mnemonic, long) = match (unsigned, opcode) {
+        (false, 0b0011) => ("sqdmlal", true),
+        (false, 0b0111) => ("sqdmlsl", true),
+        (false, 0b1011) => ("sqdmull", true),
+        (false, 0b1100) => ("sqdmulh", false),
+        (false, 0b1101) => ("sqrdmulh", false),
+        (true, 0b1101) => ("sqrdmlah", false),
+        (true, 0b1111) => ("sqrdmlsh", false),
+        _ if is_scalar => return None,
+        (false, 0b0010) => ("smlal", true),
+        (false, 0b0110) => ("smlsl", true),
+        (false, 0b1000) => ("mul", false),
+        (false, 0b1010) => ("smull", true),
+        (true, 0b0000) => ("mla", false),
+        (true, 0b0010) => ("umlal", true),
+        (true, 0b0100) => ("mls", false),
+        (true, 0b0110) => ("umlsl", true),
+        (true, 0b1010) => ("umull", true),
+        _ => return None,
+    };
+
+    if is_scalar {
+        let destination = scalar(rd, if long { size + 1 } else { size });
+        return ins(mnemonic, vec![destination, scalar(rn, size), element]);
+    }
+    if long {
+        return Some((upper(mnemonic, q), vec![arrangement(rd, size + 1, true), arrangement(rn, size, q), element]));
+    }
+    ins(mnemonic, vec![arrangement(rd, size, q), arrangement(rn, size, q), element])
+}
+
@@ -0,0 +1,724 @@
+use crate::disasm::{Operand, ShiftKind};
+
+use super::registers::{gpr, special, PC, SP};
+use super::{
+    bit, bits, conditional, immediate_shift, imm, ins, memory_immediate, memory_register, multiple_base, reg,
+    register_list, sign_extend, target, vfp, Decoded, ALWAYS, SHIFTS,
+};
+
+pub const DATA_PROCESSING: [&str; 16] = [
+    "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc",
+    "tst", "teq", "cmp", "cmn", "orr", "mov", "bic", "mvn",
+];
+
+/// Memory barrier options, indexed by the 4 bit option field
+pub const BARRIER_OPTIONS: [&str; 16] = [
+    "#0x0", "oshld", "oshst", "osh", "#0x4", "nshld", "nshst", "nsh",
+    "#0x8", "ishld", "ishst", "ish", "#0xc", "ld", "st", "sy",
+];
+
+// Fields written by msr, indexed by the mask: c, x, s and f from the low bit
+const CPSR_FIELDS: [&str; 16] = [
+    "cpsr", "cpsr_c", "cpsr_x", "cpsr_xc", "cpsr_s", "cpsr_sc", "cpsr_sx", "cpsr_sxc",
+    "cpsr_f", "cpsr_fc", "cpsr_fx", "cpsr_fxc", "cpsr_fs", "cpsr_fsc", "cpsr_fsx", "cpsr_fsxc",
+];
+const SPSR_FIELDS: [&str; 16] = [
+    "spsr", "spsr_c", "spsr_x", "spsr_xc", "spsr_s", "spsr_sc", "spsr_sx", "spsr_sxc",
+    "spsr_f", "spsr_fc", "spsr_fx", "spsr_fxc", "spsr_fs", "spsr_fsc", "spsr_fsx", "spsr_fsxc",
+];
+
+// Prefixes of the parallel add and subtract instructions, indexed by the 3 bit op1 field
+const PARALLEL_PREFIXES: [&str; 8] = ["", "s", "q", "sh", "", "u", "uq", "uh"];
+
+const COPROCESSORS: [&str; 16] = [
+    "p0", "p1", "p2", "p3", "p4", "p5", "p6", "p7",
+    "p8", "p9", "p10", "p11", "p12", "p13", "p14", "p15",
+];
+const COPROCESSOR_REGISTERS: [&str; 16] = [
+    "c0", "c1", "c2", "c3", "c4", "c5", "c6", "c7",
+    "c8", "c9", "c10", "c11", "c12", "c13", "c14", "c15",
+];
+
+/// ARMExpandImm: an 8 bit value rotated right by twice the 4 bit rotation
+pub fn expand_immediate(imm12: u32) -> u32 {
+    bits(imm12, 0, 8).rotate_right(2 * bits(imm12, 8, 4))
+}
+
+/// Status register operand of msr, from the R bit and the field mask
+pub fn status_fields(spsr: bool, mask: u32) -> Operand {
+    let name = if spsr { SPSR_FIELDS[mask as usize] } else { CPSR_FIELDS[mask as usize] };
+    Operand::Register(special(mask as u16, name))
+}
+
+/// Status register operand of mrs
+pub fn status_register(spsr: bool) -> Operand {
+    Operand::Register(if spsr { special(1, "spsr") } else { special(0, "apsr") })
+}
+
+/// The parallel add and subtract instructions, prefix from op1 and operation from op2
+pub fn parallel(prefix: u32, operation: u32) -> Option<String> {
+    let prefix = PARALLEL_PREFIXES[prefix as usize & 7];
+    let operation = match operation {
+        0b000 => "add16",
+        0b001 => "asx",
+        0b010 => "sax",
+        0b011 => "sub16",
+        0b100 => "add8",
+        0b111 => "sub8",
+        _ => return None,
+    };
+    if prefix.is_empty() {
+        return None;
+    }
+    Some(format!("{}{}", prefix, operation))
+}
+
+/// Rotation of the extend instructions, ror #0 is not shown
+pub fn rotation(field: u32) -> Option<Operand> {
+    match field {
+        0 => None,
+        _ => Some(Operand::Shift { kind: ShiftKind::Ror, amount: (field * 8) as u8 }),
+    }
+}
+
+pub fn decode(word: u32, address: u64) -> Decoded {
+    let condition = bits(word, 28, 4);
+    if condition == 0b1111 {
+        return unconditional(word, address);
+    }
+    let pc = address.wrapping_add(8);
+
+    match bits(word, 25, 3) {
+        0b000 | 0b001 => data_misc(word, condition, pc),
+        0b010 => load_store(word, condition, pc),
+        0b011 if !bit(word, 4) => load_store(word, condition, pc),
+        0b011 => media(word, condition),
+        0b100 => load_store_multiple(word, condition),
+        0b101 => {
+            let offset = sign_extend(bits(word, 0, 24) << 2, 26);
+            let mnemonic = if bit(word, 24) { "bl" } else { "b" };
+            ins(mnemonic, condition, vec![target(pc.wrapping_add(offset as u64))])
+        }
+        0b110 => coprocessor(word, condition, pc),
+        _ if bit(word, 24) => ins("svc", condition, vec![imm(bits(word, 0, 24) as i64)]),
+        _ => coprocessor(word, condition, pc),
+    }
+}
+
+fn data_misc(word: u32, condition: u32, pc: u64) -> Decoded {
+    let op1 = bits(word, 20, 5);
+    let op2 = bits(word, 4, 4);
+
+    if bit(word, 25) {
+        return match op1 {
+            0b10000 | 0b10100 => {
+                let value = bits(word, 16, 4) << 12 | bits(word, 0, 12);
+                let mnemonic = if op1 == 0b10000 { "movw" } else { "movt" };
+                ins(mnemonic, condition, vec![reg(bits(word, 12, 4)), imm(value as i64)])
+            }
+            0b10010 | 0b10110 => msr_immediate_hints(word, condition),
+            _ => data_processing(word, condition, pc),
+        };
+    }
+
+    let misc_space = op1 & 0b11001 == 0b10000;
+    match op2 {
+        0b1001 if op1 & 0b10000 == 0 => multiply(word, condition),
+        0b1001 => synchronization(word, condition),
+        0b1011 | 0b1101 | 0b1111 => extra_load_store(word, condition, pc),
+        _ if misc_space && !bit(word, 7) => miscellaneous(word, condition),
+        _ if misc_space => halfword_multiply(word, condition),
+        _ => data_processing(word, condition, pc),
+    }
+}
+
+// Data processing with an immediate, an immediate shifted register or a register shifted register
+fn data_processing(word: u32, condition: u32, pc: u64) -> Decoded {
+    let opcode = bits(word, 21, 4);
+    let set_flags = bit(word, 20);
+    let (rn, rd) = (bits(word, 16, 4), bits(word, 12, 4));
+
+    // The second operand and its shift
+    let mut second = Vec::new();
+    if bit(word, 25) {
+        second.push(imm(expand_immediate(bits(word, 0, 12)) as i64));
+    } else if bit(word, 4) {
+        second.push(reg(bits(word, 0, 4)));
+        second.push(Operand::RegisterShift { kind: SHIFTS[bits(word, 5, 2) as usize], register: gpr(bits(word, 8, 4)) });
+    } else {
+        second.push(reg(bits(word, 0, 4)));
+        second.extend(immediate_shift(bits(word, 5, 2), bits(word, 7, 5)));
+    }
+
+    let suffix = if set_flags { "s" } else { "" };
+    match opcode {
+        // Comparisons only exist with the S bit set, the rest of the space is used by other instructions
+        0b1000..=0b1011 => {
+            let mut operands = vec![reg(rn)];
+            operands.extend(second);
+            ins(DATA_PROCESSING[opcode as usize], condition, operands)
+        }
+        // Moves have no first operand, the field should be zero
+        0b1101 if rn != 0 => None,
+        0b1111 if rn != 0 && bit(word, 25) => None,
+        0b1101 if !bit(word, 25) => {
+            // mov with a shift is written as the shift
+            let (mnemonic, operands) = match &second[..] {
+                [source] => ("mov".to_string(), vec![reg(rd), source.clone()]),
+                [source, Operand::Shift { kind: ShiftKind::Rrx, .. }] => ("rrx".to_string(), vec![reg(rd), source.clone()]),
+                [source, Operand::Shift { kind, amount }] => {
+                    (shift_name(*kind).to_string(), vec![reg(rd), source.clone(), imm(*amount as i64)])
+                }
+                [source, Operand::RegisterShift { kind, register }] => {
+                    (shift_name(*kind).to_string(), vec![reg(rd), source.clone(), Operand::Register(*register)])
+                }
+                _ => return None,
+            };
+            ins(&format!("{}{}", mnemonic, suffix), condition, operands)
+        }
+        0b1101 | 0b1111 => {
+            let mut operands = vec![reg(rd)];
+            operands.extend(second);
+            ins(&format!("{}{}", DATA_PROCESSING[opcode as usize], suffix), condition, operands)
+        }
+        _ => {
+            // adr for pc relative additions and subtractions of an immediate
+            if rn == PC && !set_flags && bit(word, 25) && (opcode == 0b0100 || opcode == 0b0010) {
+                let value = expand_immediate(bits(word, 0, 12)) as u64;
+                let address = if opcode == 0b0100 { pc.wrapping_add(value) } else { pc.wrapping_sub(value) };
+                return ins("adr", condition, vec![reg(rd), target(address)]);
+            }
+            let mut operands = vec![reg(rd), reg(rn)];
+            operands.extend(second);
+            ins(&format!("{}{}", DATA_PROCESSING[opcode as usize], suffix), condition, operands)
+        }
+    }
+}
+
+pub fn shift_name(kind: ShiftKind) -> &'static str {
+    match kind {
+        ShiftKind::Lsl => "lsl",
+        ShiftKind::Lsr => "lsr",
+        ShiftKind::Asr => "asr",
+        ShiftKind::Ror => "ror",
+        _ => "rrx",
+    }
+}
+
+fn msr_immediate_hints(word: u32, condition: u32) -> Decoded {
+    let mask = bits(word, 16, 4);
+    let spsr = bit(word, 22);
+    if bits(word, 12, 4) != 0b1111 {
+        return None;
+    }
+
+    if !spsr && mask == 0 {
+        return match bits(word, 0, 8) {
+            0 => ins("nop", condition, vec![]),
+            1 => ins("yield", condition, vec![]),
+            2 => ins("wfe", condition, vec![]),
+            3 => ins("wfi", condition, vec![]),
+            4 => ins("sev", condition, vec![]),
+            value if value >> 4 == 0xf => ins("dbg", condition, vec![imm((value & 0xf) as i64)]),
+            _ => None,
+        };
+    }
+    let value = expand_immediate(bits(word, 0, 12));
+    ins("msr", condition, vec![status_fields(spsr, mask), imm(value as i64)])
+}
+
+fn miscellaneous(word: u32, condition: u32) -> Decoded {
+    let op = bits(word, 21, 2);
+    let (rd, rm) = (bits(word, 12, 4), bits(word, 0, 4));
+
+    match (bits(word, 4, 3), op) {
+        (0b000, 0b00) | (0b000, 0b10) if !bit(word, 9) => {
+            ins("mrs", condition, vec![reg(rd), status_register(bit(word, 22))])
+        }
+        (0b000, 0b01) | (0b000, 0b11) if !bit(word, 9) && bits(word, 8, 8) == 0b1111_0000 => {
+            ins("msr", condition, vec![status_fields(bit(word, 22), bits(word, 16, 4)), reg(rm)])
+        }
+        (0b001, _) | (0b010, _) | (0b011, _) if bits(word, 8, 12) != 0xfff && op != 0b11 => None,
+        (0b001, 0b01) => ins("bx", condition, vec![reg(rm)]),
+        (0b001, 0b11) if bits(word, 16, 4) == 0b1111 && bits(word, 8, 4) == 0b1111 => ins("clz", condition, vec![reg(rd), reg(rm)]),
+        (0b010, 0b01) => ins("bxj", condition, vec![reg(rm)]),
+        (0b011, 0b01) => ins("blx", condition, vec![reg(rm)]),
+        (0b101, op) => {
+            let mnemonic = ["qadd", "qsub", "qdadd", "qdsub"][op as usize];
+            ins(mnemonic, condition, vec![reg(rd), reg(rm), reg(bits(word, 16, 4))])
+        }
+        (0b110, 0b11) if bits(word, 0, 4) == 0b1110 && bits(word, 8, 12) == 0 => ins("eret", condition, vec![]),
+        (0b111, 0b01) if condition == ALWAYS => {
+            ins("bkpt", condition, vec![imm((bits(word, 8, 12) << 4 | bits(word, 0, 4)) as i64)])
+        }
+        (0b111, 0b10) => ins("hvc", ALWAYS, vec![imm((bits(word, 8, 12) << 4 | bits(word, 0, 4)) as i64)]),
+        (0b111, 0b11) if bits(word, 8, 12) == 0 => ins("smc", condition, vec![imm(bits(word, 0, 4) as i64)]),
+        _ => None,
+    }
+}
+
+// Bottom or top half of a register for the halfword multiplies
+fn half(top: bool) -> &'static str {
+    if top {
+        "t"
+    } else {
+        "b"
+    }
+}
+
+fn halfword_multiply(word: u32, condition: u32) -> Decoded {
+    let (rd, ra, rm, rn) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let (x, y) = (half(bit(word, 5)), half(bit(word, 6)));
+
+    match bits(word, 21, 2) {
+        0b00 => ins(&format!("smla{}{}", x, y), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        0b01 if !bit(word, 5) => ins(&format!("smlaw{}", y), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        0b01 => ins(&format!("smulw{}", y), condition, vec![reg(rd), reg(rn), reg(rm)]),
+        0b10 => ins(&format!("smlal{}{}", x, y), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        _ => ins(&format!("smul{}{}", x, y), condition, vec![reg(rd), reg(rn), reg(rm)]),
+    }
+}
+
+fn multiply(word: u32, condition: u32) -> Decoded {
+    let (rd, ra, rm, rn) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let suffix = if bit(word, 20) { "s" } else { "" };
+
+    match bits(word, 21, 3) {
+        0b000 => ins(&format!("mul{}", suffix), condition, vec![reg(rd), reg(rn), reg(rm)]),
+        0b001 => ins(&format!("mla{}", suffix), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        0b010 if !bit(word, 20) => ins("umaal", condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        0b011 if !bit(word, 20) => ins("mls", condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        0b100 => ins(&format!("umull{}", suffix), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        0b101 => ins(&format!("umlal{}", suffix), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        0b110 => ins(&format!("smull{}", suffix), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        0b111 => ins(&format!("smlal{}", suffix), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        _ => None,
+    }
+}
+
+// A memory operand which is just a base register
+fn base_only(rn: u32, size: u16) -> Operand {
+    memory_immediate(rn, 0, true, true, false, size, 0)
+}
+
+// swp and the exclusive loads and stores
+fn synchronization(word: u32, condition: u32) -> Decoded {
+    let (rn, rd, rt) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 0, 4));
+
+    if !bit(word, 23) {
+        if bits(word, 20, 2) != 0 {
+            return None;
+        }
+        let (mnemonic, size) = if bit(word, 22) { ("swpb", 1) } else { ("swp", 4) };
+        return ins(mnemonic, condition, vec![reg(rd), reg(rt), base_only(rn, size)]);
+    }
+    // Load acquire and store release are ARMv8
+    if bits(word, 8, 2) != 0b11 {
+        return None;
+    }
+
+    let (name, size) = match bits(word, 21, 2) {
+        0b00 => ("", 4),
+        0b01 => ("d", 8),
+        0b10 => ("b", 1),
+        _ => ("h", 2),
+    };
+    if bits(word, 8, 4) != 0b1111 {
+        return None;
+    }
+    if bit(word, 20) {
+        if rt != 0b1111 {
+            return None;
+        }
+        let mut operands = vec![reg(rd)];
+        if size == 8 {
+            operands.push(reg(rd + 1));
+        }
+        operands.push(base_only(rn, size));
+        ins(&format!("ldrex{}", name), condition, operands)
+    } else {
+        let mut operands = vec![reg(rd), reg(rt)];
+        if size == 8 {
+            operands.push(reg(rt + 1));
+        }
+        operands.push(base_only(rn, size));
+        ins(&format!("strex{}", name), condition, operands)
+    }
+}
+
+// Halfword, signed byte and doubleword loads and stores
+fn extra_load_store(word: u32, condition: u32, pc: u64) -> Decoded {
+    let (index, add, writeback, load) = (bit(word, 24), bit(word, 23), bit(word, 21), bit(word, 20));
+    let (rn, rt) = (bits(word, 16, 4), bits(word, 12, 4));
+    let unprivileged = !index && writeback;
+
+    let (mnemonic, size) = match (bits(word, 5, 2), load) {
+        (0b01, false) => ("strh", 2),
+        (0b01, true) => ("ldrh", 2),
+        (0b10, false) if !unprivileged => ("ldrd", 8),
+        (0b10, true) => ("ldrsb", 1),
+        (0b11, false) if !unprivileged => ("strd", 8),
+        (0b11, true) => ("ldrsh", 2),
+        _ => return None,
+    };
+    let mnemonic = if unprivileged { format!("{}t", mnemonic) } else { mnemonic.to_string() };
+    if size == 8 && rt == PC {
+        return None;
+    }
+    if unprivileged && !load && !bit(word, 22) && bits(word, 8, 4) != 0 {
+        return None;
+    }
+
+    let address = if bit(word, 22) {
+        memory_immediate(rn, bits(word, 8, 4) << 4 | bits(word, 0, 4), add, index, writeback, size, pc)
+    } else {
+        memory_register(rn, bits(word, 0, 4), add, index, writeback, None, size)
+    };
+
+    let mut operands = vec![reg(rt)];
+    if size == 8 {
+        operands.push(reg(rt + 1));
+    }
+    operands.push(address);
+    ins(&mnemonic, condition, operands)
+}
+
+fn load_store(word: u32, condition: u32, pc: u64) -> Decoded {
+    let (index, add, byte, writeback, load) = (bit(word, 24), bit(word, 23), bit(word, 22), bit(word, 21), bit(word, 20));
+    let (rn, rt) = (bits(word, 16, 4), bits(word, 12, 4));
+    let size = if byte { 1 } else { 4 };
+
+    let mut mnemonic = String::from(if load { "ldr" } else { "str" });
+    if byte {
+        mnemonic.push('b');
+    }
+    if !index && writeback {
+        mnemonic.push('t');
+    }
+
+    if bit(word, 25) {
+        let shift = immediate_shift(bits(word, 5, 2), bits(word, 7, 5));
+        let address = memory_register(rn, bits(word, 0, 4), add, index, writeback, shift, size);
+        return ins(&mnemonic, condition, vec![reg(rt), address]);
+    }
+
+    let offset = bits(word, 0, 12);
+    // Single register push and pop
+    if rn == SP && !byte {
+        match (load, index, add, writeback, offset) {
+            (false, true, false, true, 4) => return ins("push", condition, vec![register_list(1 << rt)]),
+            (true, false, true, false, 4) => return ins("pop", condition, vec![register_list(1 << rt)]),
+            _ => {}
+        }
+    }
+    ins(&mnemonic, condition, vec![reg(rt), memory_immediate(rn, offset, add, index, writeback, size, pc)])
+}
+
+// Parallel arithmetic, packing, saturation, reversal, signed multiplies and bit fields
+fn media(word: u32, condition: u32) -> Decoded {
+    let op1 = bits(word, 20, 5);
+    let op2 = bits(word, 5, 3);
+    let (rn, rd, rs, rm) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+
+    match op1 {
+        0b00000..=0b00111 => {
+            let mnemonic = parallel(bits(word, 20, 3), op2)?;
+            ins(&mnemonic, condition, vec![reg(rd), reg(rn), reg(rm)])
+        }
+        0b01000..=0b01111 => packing(word, condition),
+        0b10000 | 0b10100 | 0b10101 | 0b10001 | 0b10011 => signed_multiply(word, condition),
+        0b11000 if op2 == 0 => match rd {
+            PC => ins("usad8", condition, vec![reg(rn), reg(rm), reg(rs)]),
+            _ => ins("usada8", condition, vec![reg(rn), reg(rm), reg(rs), reg(rd)]),
+        },
+        0b11010 | 0b11011 | 0b11110 | 0b11111 if op2 & 0b11 == 0b10 => {
+            let (lsb, width) = (bits(word, 7, 5), bits(word, 16, 5) + 1);
+            let mnemonic = if op1 & 0b100 != 0 { "ubfx" } else { "sbfx" };
+            ins(mnemonic, condition, vec![reg(rd), reg(rm), imm(lsb as i64), imm(width as i64)])
+        }
+        0b11100 | 0b11101 if op2 & 0b11 == 0 => {
+            let (lsb, msb) = (bits(word, 7, 5), bits(word, 16, 5));
+            if msb < lsb {
+                return None;
+            }
+            let width = imm((msb - lsb + 1) as i64);
+            match rm {
+                PC => ins("bfc", condition, vec![reg(rd), imm(lsb as i64), width]),
+                _ => ins("bfi", condition, vec![reg(rd), reg(rm), imm(lsb as i64), width]),
+            }
+        }
+        0b11111 if op2 == 0b111 && condition == ALWAYS => {
+            ins("udf", condition, vec![imm((bits(word, 8, 12) << 4 | bits(word, 0, 4)) as i64)])
+        }
+        _ => None,
+    }
+}
+
+fn packing(word: u32, condition: u32) -> Decoded {
+    let op1 = bits(word, 20, 3);
+    let op2 = bits(word, 5, 3);
+    let (rn, rd, rm) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 0, 4));
+
+    // Saturation to a bit position, with an optional shift of the source
+    if op1 & 0b010 == 0b010 && op2 & 1 == 0 {
+        let amount = bits(word, 7, 5);
+        let shift = match (bit(word, 6), amount) {
+            (false, 0) => None,
+            (false, _) => Some(Operand::Shift { kind: ShiftKind::Lsl, amount: amount as u8 }),
+            (true, 0) => Some(Operand::Shift { kind: ShiftKind::Asr, amount: 32 }),
+            (true, _) => Some(Operand::Shift { kind: ShiftKind::Asr, amount: amount as u8 }),
+        };
+        let (mnemonic, position) = if op1 & 0b100 != 0 {
+            ("usat", bits(word, 16, 5))
+        } else {
+            ("ssat", bits(word, 16, 5) + 1)
+        };
+        let mut operands = vec![reg(rd), imm(position as i64), reg(rm)];
+        operands.extend(shift);
+        return ins(mnemonic, condition, operands);
+    }
+
+    if (op2 == 0b001 || (op2 == 0b101 && op1 != 0)) && bits(word, 8, 4) != 0b1111 {
+        return None;
+    }
+
+    // Extensions with an optional accumulation, rn == pc for the plain forms
+    let extend = |name: &str| {
+        let mut operands = vec![reg(rd)];
+        let mnemonic = if rn == PC {
+            format!("{}{}", &name[..3], &name[4..])
+        } else {
+            operands.push(reg(rn));
+            name.to_string()
+        };
+        operands.push(reg(rm));
+        operands.extend(rotation(bits(word, 10, 2)));
+        ins(&mnemonic, condition, operands)
+    };
+
+    match (op1, op2) {
+        (0b000, 0b000) | (0b000, 0b010) | (0b000, 0b100) | (0b000, 0b110) => {
+            let amount = bits(word, 7, 5);
+            if bit(word, 6) {
+                let amount = if amount == 0 { 32 } else { amount };
+                ins("pkhtb", condition, vec![reg(rd), reg(rn), reg(rm), Operand::Shift { kind: ShiftKind::Asr, amount: amount as u8 }])
+            } else {
+                let mut operands = vec![reg(rd), reg(rn), reg(rm)];
+                operands.extend(immediate_shift(0, amount));
+                ins("pkhbt", condition, operands)
+            }
+        }
+        (0b000, 0b011) => extend("sxtab16"),
+        (0b000, 0b101) => ins("sel", condition, vec![reg(rd), reg(rn), reg(rm)]),
+        (0b010, 0b001) => ins("ssat16", condition, vec![reg(rd), imm((bits(word, 16, 4) + 1) as i64), reg(rm)]),
+        (0b010, 0b011) => extend("sxtab"),
+        (0b011, 0b001) => ins("rev", condition, vec![reg(rd), reg(rm)]),
+        (0b011, 0b011) => extend("sxtah"),
+        (0b011, 0b101) => ins("rev16", condition, vec![reg(rd), reg(rm)]),
+        (0b100, 0b011) => extend("uxtab16"),
+        (0b110, 0b001) => ins("usat16", condition, vec![reg(rd), imm(bits(word, 16, 4) as i64), reg(rm)]),
+        (0b110, 0b011) => extend("uxtab"),
+        (0b111, 0b001) => ins("rbit", condition, vec![reg(rd), reg(rm)]),
+        (0b111, 0b011) => extend("uxtah"),
+        (0b111, 0b101) => ins("revsh", condition, vec![reg(rd), reg(rm)]),
+        _ => None,
+    }
+}
+
+fn signed_multiply(word: u32, condition: u32) -> Decoded {
+    let op1 = bits(word, 20, 3);
+    let op2 = bits(word, 5, 3);
+    let (rd, ra, rm, rn) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let x = if bit(word, 5) { "x" } else { "" };
+    let r = if bit(word, 5) { "r" } else { "" };
+
+    // Multiply and accumulate, without the accumulator when ra is the pc
+    let accumulate = |with: &str, without: &str, suffix: &str| {
+        if ra == PC {
+            ins(&format!("{}{}", without, suffix), condition, vec![reg(rd), reg(rn), reg(rm)])
+        } else {
+            ins(&format!("{}{}", with, suffix), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)])
+        }
+    };
+
+    match (op1, op2 >> 1) {
+        (0b000, 0b00) => accumulate("smlad", "smuad", x),
+        (0b000, 0b01) => accumulate("smlsd", "smusd", x),
+        (0b001, 0b00) if op2 == 0 && ra == PC => ins("sdiv", condition, vec![reg(rd), reg(rn), reg(rm)]),
+        (0b011, 0b00) if op2 == 0 && ra == PC => ins("udiv", condition, vec![reg(rd), reg(rn), reg(rm)]),
+        (0b100, 0b00) => ins(&format!("smlald{}", x), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        (0b100, 0b01) => ins(&format!("smlsld{}", x), condition, vec![reg(ra), reg(rd), reg(rn), reg(rm)]),
+        (0b101, 0b00) => accumulate("smmla", "smmul", r),
+        (0b101, 0b11) => ins(&format!("smmls{}", r), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        _ => None,
+    }
+}
+
+fn load_store_multiple(word: u32, condition: u32) -> Decoded {
+    let (before, increment, user, writeback, load) = (bit(word, 24), bit(word, 23), bit(word, 22), bit(word, 21), bit(word, 20));
+    let rn = bits(word, 16, 4);
+    let list = bits(word, 0, 16);
+    if list == 0 {
+        return None;
+    }
+
+    if rn == SP && writeback && !user && list.count_ones() > 1 {
+        match (load, before, increment) {
+            (true, false, true) => return ins("pop", condition, vec![register_list(list)]),
+            (false, true, false) => return ins("push", condition, vec![register_list(list)]),
+            _ => {}
+        }
+    }
+
+    let mode = match (before, increment) {
+        (false, false) => "da",
+        (false, true) => "",
+        (true, false) => "db",
+        (true, true) => "ib",
+    };
+    let mnemonic = format!("{}{}", if load { "ldm" } else { "stm" }, mode);
+    let mut operands = vec![multiple_base(rn, writeback, (list.count_ones() * 4) as u16), register_list(list)];
+    if user {
+        operands.push(Operand::Name("^"));
+    }
+    ins(&mnemonic, condition, operands)
+}
+
+// Coprocessor instructions, the VFP ones using coprocessors 10 and 11
+pub fn coprocessor(word: u32, condition: u32, pc: u64) -> Decoded {
+    let coprocessor = bits(word, 8, 4);
+    if coprocessor >> 1 == 0b101 {
+        return vfp::decode(word, condition, pc);
+    }
+
+    let name = Operand::Name(COPROCESSORS[coprocessor as usize]);
+    let (crn, crm) = (Operand::Name(COPROCESSOR_REGISTERS[bits(word, 16, 4) as usize]), Operand::Name(COPROCESSOR_REGISTERS[bits(word, 0, 4) as usize]));
+
+    match bits(word, 24, 2) {
+        0b10 if bit(word, 4) => {
+            let mnemonic = if bit(word, 20) { "mrc" } else { "mcr" };
+            // Loads into the pc set the condition flags
+            let rt = match bits(word, 12, 4) {
+                PC if bit(word, 20) => Operand::Register(special(0, "APSR_nzcv")),
+                rt => reg(rt),
+            };
+            let operands = vec![name, imm(bits(word, 21, 3) as i64), rt, crn, crm, imm(bits(word, 5, 3) as i64)];
+            ins(mnemonic, condition, operands)
+        }
+        0b10 => {
+            let crd = Operand::Name(COPROCESSOR_REGISTERS[bits(word, 12, 4) as usize]);
+            let operands = vec![name, imm(bits(word, 20, 4) as i64), crd, crn, crm, imm(bits(word, 5, 3) as i64)];
+            ins("cdp", condition, operands)
+        }
+        0b00 if bits(word, 21, 4) == 0b0010 => {
+            let mnemonic = if bit(word, 20) { "mrrc" } else { "mcrr" };
+            let operands = vec![name, imm(bits(word, 4, 4) as i64), reg(bits(word, 12, 4)), reg(bits(word, 16, 4)), crm];
+            ins(mnemonic, condition, operands)
+        }
+        0b00 | 0b01 => {
+            // ldc and stc, the unindexed form with an option field isn't supported
+            let (index, add, writeback) = (bit(word, 24), bit(word, 23), bit(word, 21));
+            if !index && !writeback {
+                return None;
+            }
+            let mnemonic = format!("{}{}", if bit(word, 20) { "ldc" } else { "stc" }, if bit(word, 22) { "l" } else { "" });
+            let crd = Operand::Name(COPROCESSOR_REGISTERS[bits(word, 12, 4) as usize]);
+            let address = memory_immediate(bits(word, 16, 4), bits(word, 0, 8) << 2, add, index, writeback, 0, pc);
+            ins(&mnemonic, condition, vec![name, crd, address])
+        }
+        _ => None,
+    }
+}
+
+/// The coprocessor instructions without a condition, the "2" variants such as mcr2 and ldc2l
+pub fn coprocessor2(word: u32, pc: u64) -> Decoded {
+    if bits(word, 9, 3) == 0b101 {
+        return None;
+    }
+    let (mnemonic, operands) = coprocessor(word, ALWAYS, pc)?;
+    let split = if mnemonic.ends_with('l') { 3 } else { mnemonic.len() };
+    Some((format!("{}2{}", &mnemonic[..split], &mnemonic[split..]), operands))
+}
+
+// Instructions with the condition field set to 0b1111
+fn unconditional(word: u32, address: u64) -> Decoded {
+    let pc = address.wrapping_add(8);
+
+    match bits(word, 25, 3) {
+        0b101 => {
+            // blx to Thumb code, the H bit selects the halfword
+            let offset = sign_extend(bits(word, 0, 24) << 2 | (bit(word, 24) as u32) << 1, 26);
+            ins("blx", ALWAYS, vec![target(pc.wrapping_add(offset as u64))])
+        }
+        0b000 if bits(word, 20, 8) == 0b0001_0000 => {
+            if bit(word, 16) {
+                if word & 0xfffffdff != 0xf1010000 {
+                    return None;
+                }
+                let endian = if bit(word, 9) { "be" } else { "le" };
+                return ins("setend", ALWAYS, vec![Operand::Name(endian)]);
+            }
+            change_processor_state(word)
+        }
+        0b010 | 0b011 => preload_barrier(word, pc),
+        0b100 if bit(word, 22) && !bit(word, 20) && bits(word, 8, 12) == 0xd05 => {
+            let mode = ["da", "", "db", "ib"][bits(word, 23, 2) as usize];
+            let mut base = vec![multiple_base(SP, bit(word, 21), 8)];
+            base.push(imm(bits(word, 0, 5) as i64));
+            Some((format!("srs{}", mode), base))
+        }
+        0b100 if !bit(word, 22) && bit(word, 20) && bits(word, 0, 16) == 0x0a00 => {
+            let mode = ["da", "", "db", "ib"][bits(word, 23, 2) as usize];
+            Some((format!("rfe{}", mode), vec![multiple_base(bits(word, 16, 4), bit(word, 21), 8)]))
+        }
+        0b110 | 0b111 => coprocessor2(word, pc),
+        _ => None,
+    }
+}
+
+fn change_processor_state(word: u32) -> Decoded {
+    let flags = ["", "f", "i", "if", "a", "af", "ai", "aif"][bits(word, 6, 3) as usize];
+    let mode = imm(bits(word, 0, 5) as i64);
+    match (bits(word, 18, 2), bit(word, 17)) {
+        (0b10, false) => Some(("cpsie".to_string(), vec![Operand::Name(flags)])),
+        (0b11, false) => Some(("cpsid".to_string(), vec![Operand::Name(flags)])),
+        (0b10, true) => Some(("cpsie".to_string(), vec![Operand::Name(flags), mode])),
+        (0b11, true) => Some(("cpsid".to_string(), vec![Operand::Name(flags), mode])),
+        (0b00, true) => Some(("cps".to_string(), vec![mode])),
+        _ => None,
+    }
+}
+
+fn preload_barrier(word: u32, pc: u64) -> Decoded {
+    let (rn, add) = (bits(word, 16, 4), bit(word, 23));
+
+    if bits(word, 20, 8) == 0b0101_0111 && bits(word, 8, 12) == 0xff0 {
+        let option = Operand::Name(BARRIER_OPTIONS[bits(word, 0, 4) as usize]);
+        return match bits(word, 4, 4) {
+            0b0001 => Some(("clrex".to_string(), vec![])),
+            0b0100 => Some(("dsb".to_string(), vec![option])),
+            0b0101 => Some(("dmb".to_string(), vec![option])),
+            0b0110 => Some(("isb".to_string(), vec![option])),
+            _ => None,
+        };
+    }
+
+    let mnemonic = match (bits(word, 24, 3), bits(word, 20, 3)) {
+        (0b100 | 0b110, 0b101) => "pli",
+        (0b101 | 0b111, 0b101) => "pld",
+        (0b101 | 0b111, 0b001) => "pldw",
+        _ => return None,
+    };
+    if bits(word, 12, 4) != 0b1111 {
+        return None;
+    }
+    let address = if bit(word, 25) {
+        if bit(word, 4) {
+            return None;
+        }
+        memory_register(rn, bits(word, 0, 4), add, true, false, immediate_shift(bits(word, 5, 2), bits(word, 7, 5)), 0)
+    } else {
+        memory_immediate(rn, bits(word, 0, 12), add, true, false, 0, pc)
+    };
+    Some((conditional(mnemonic, ALWAYS), vec![address]))
+}
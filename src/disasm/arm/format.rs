@@ -0,0 +1,116 @@
+use crate::disasm::{IndexMode, Instruction, MemoryOperand, Operand, RegisterClass, ShiftKind};
+
+use super::CONDITIONS;
+
+// Rendering follows the GNU objdump conventions: immediates are decimal unless they are large, and the
+// addresses of literal loads are shown in a comment
+
+pub fn format(instruction: &Instruction) -> String {
+    let mnemonic = &instruction.mnemonic;
+    // Data in code sections is shown as the raw value
+    let data = mnemonic.starts_with('.');
+    let multiple = multiple_transfer(mnemonic);
+
+    let mut res = mnemonic.clone();
+    let mut separator = " ";
+    for x in &instruction.operands {
+        // The user registers marker of ldm and stm is attached to the register list
+        if let Operand::Name("^") = x {
+            res.push('^');
+            continue;
+        }
+        res.push_str(separator);
+        res.push_str(&operand(x, data, multiple));
+        separator = ", ";
+    }
+
+    let literal = instruction.operands.iter().find_map(|x| match x {
+        Operand::Memory(mem) if mem.base.map(|x| x.class) == Some(RegisterClass::ProgramCounter) => mem.target,
+        _ => None,
+    });
+    if let Some(target) = literal {
+        res.push_str(&format!("        @ 0x{:x}", target));
+    }
+    res
+}
+
+// Instructions whose memory operand is only a base register with optional writeback
+fn multiple_transfer(mnemonic: &str) -> bool {
+    ["ldm", "stm", "vldm", "vstm", "srs", "rfe"].iter().any(|x| mnemonic.starts_with(x))
+}
+
+fn immediate(value: i64) -> String {
+    let value = value as u32;
+    if value > 0xffff {
+        format!("#0x{:x}", value)
+    } else {
+        format!("#{}", value)
+    }
+}
+
+fn shift_name(kind: ShiftKind) -> &'static str {
+    match kind {
+        ShiftKind::Lsl => "lsl",
+        ShiftKind::Lsr => "lsr",
+        ShiftKind::Asr => "asr",
+        ShiftKind::Ror => "ror",
+        _ => "rrx",
+    }
+}
+
+fn shift(kind: ShiftKind, amount: u8) -> String {
+    match kind {
+        ShiftKind::Rrx => "rrx".to_string(),
+        _ => format!("{} #{}", shift_name(kind), amount),
+    }
+}
+
+fn memory(mem: &MemoryOperand, multiple: bool) -> String {
+    let base = mem.base.map(|x| x.name).unwrap_or_default();
+    if multiple {
+        return match mem.mode {
+            IndexMode::Offset => base.to_string(),
+            _ => format!("{}!", base),
+        };
+    }
+
+    let sign = if mem.subtract { "-" } else { "" };
+    let offset = match mem.index {
+        Some(index) => {
+            let shift = mem.index_shift.map(|(kind, amount)| format!(", {}", shift(kind, amount))).unwrap_or_default();
+            format!("{}{}{}", sign, index.name, shift)
+        }
+        None if mem.displacement == 0 && !mem.subtract && mem.mode == IndexMode::Offset => return format!("[{}]", base),
+        None => format!("#{}{}", sign, mem.displacement.abs()),
+    };
+
+    match mem.mode {
+        IndexMode::Offset => format!("[{}, {}]", base, offset),
+        IndexMode::PreIndex => format!("[{}, {}]!", base, offset),
+        IndexMode::PostIndex => format!("[{}], {}", base, offset),
+    }
+}
+
+fn operand(operand: &Operand, data: bool, multiple: bool) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, size } if data => format!("0x{:0width$x}", value, width = *size as usize * 2),
+        Operand::Immediate { value, .. } => immediate(*value),
+        Operand::Memory(mem) => memory(mem, multiple),
+        Operand::Address(address) => format!("0x{:x}", address),
+        Operand::Shift { kind, amount } => shift(*kind, *amount),
+        Operand::RegisterShift { kind, register } => format!("{} {}", shift_name(*kind), register.name),
+        Operand::Condition(condition) => match CONDITIONS[*condition as usize & 15] {
+            "" => "al".to_string(),
+            name => name.to_string(),
+        },
+        Operand::Vector { register, lane: Some(lane), .. } => format!("{}[{}]", register.name, lane),
+        Operand::List(operands) => {
+            let items: Vec<String> = operands.iter().map(|x| self::operand(x, data, multiple)).collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        Operand::Name(name) => name.to_string(),
+        Operand::Float(value) => format!("#{:?}", f64::from_bits(*value)),
+        other => format!("{:?}", other),
+    }
+}
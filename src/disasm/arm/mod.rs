@@ -0,0 +1,497 @@
+use std::cell::Cell;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, FlowKind, IndexMode, Instruction, MemoryOperand, Operand, ShiftKind};
+
+mod registers;
+use self::registers::{gpr, LR, PC, SP};
+
+mod a32;
+mod thumb;
+mod thumb2;
+mod vfp;
+
+mod format;
+pub use self::format::format;
+
+/// Condition code names, indexed by the 4 bit condition field. Always is written without a suffix.
+pub const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc",
+    "hi", "ls", "ge", "lt", "gt", "le", "", "",
+];
+const ALWAYS: u32 = 0b1110;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is undefined or not supported
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// The instruction set state of a range of addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmMode {
+    Arm,
+    Thumb,
+    // Literal pools and other data in code sections
+    Data,
+}
+
+/// Mode selected by a mapping symbol ($a, $t and $d, optionally followed by a dot and anything)
+pub fn mapping_symbol(name: &[u8]) -> Option<ArmMode> {
+    let mode = match name.get(..2) {
+        Some(b"$a") => ArmMode::Arm,
+        Some(b"$t") => ArmMode::Thumb,
+        Some(b"$d") => ArmMode::Data,
+        _ => return None,
+    };
+    match name.get(2) {
+        None | Some(b'.') => Some(mode),
+        _ => None,
+    }
+}
+
+/// Start addresses of each mode, from (address, name, is function) of the symbols in the binary
+///
+/// Mapping symbols are authoritative. Binaries without them fall back to the low bit of function addresses,
+/// which is set for Thumb functions.
+pub fn mode_regions(symbols: &[(u64, &[u8], bool)]) -> Vec<(u64, ArmMode)> {
+    let mut regions: Vec<(u64, ArmMode)> = symbols
+        .iter()
+        .filter_map(|(address, name, _)| mapping_symbol(name).map(|mode| (*address, mode)))
+        .collect();
+
+    if regions.is_empty() {
+        regions = symbols
+            .iter()
+            .filter(|(_, _, function)| *function)
+            .map(|(address, _, _)| {
+                let mode = if address & 1 != 0 { ArmMode::Thumb } else { ArmMode::Arm };
+                (address & !1, mode)
+            })
+            .collect();
+    }
+
+    regions.sort_by_key(|x| x.0);
+    regions.dedup_by_key(|x| x.0);
+    regions
+}
+
+// Where the decoder is in an IT block: address of the next instruction and the remaining IT state
+#[derive(Debug, Clone, Copy)]
+struct ItState {
+    address: u64,
+    state: u8,
+}
+
+// What the 16 and 32 bit Thumb decoders need besides the instruction
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+    // Condition from an enclosing IT block, ALWAYS outside of one
+    condition: u32,
+    in_it_block: bool,
+}
+
+impl Context {
+    // Value of the pc as read by Thumb instructions
+    fn pc(&self) -> u64 {
+        self.address.wrapping_add(4)
+    }
+}
+
+/// Decoder for A32 and T32 (ARMv7 with VFPv4), switching between them as given by the mode regions
+#[derive(Debug, Clone)]
+pub struct ArmDecoder {
+    regions: Vec<(u64, ArmMode)>,
+    // Mode of addresses before the first region
+    default_mode: ArmMode,
+    // Instructions are big endian, only in pre ARMv6 big endian (BE32) binaries
+    big_endian: bool,
+    // Condition state carried from an IT instruction to the ones it applies to
+    it: Cell<Option<ItState>>,
+}
+
+impl ArmDecoder {
+    pub fn new(regions: Vec<(u64, ArmMode)>, default_mode: ArmMode, big_endian: bool) -> ArmDecoder {
+        ArmDecoder {
+            regions,
+            default_mode,
+            big_endian,
+            it: Cell::new(None),
+        }
+    }
+
+    /// The mode at an address and where the region it belongs to ends
+    pub fn mode_at(&self, address: u64) -> (ArmMode, Option<u64>) {
+        let index = self.regions.partition_point(|x| x.0 <= address);
+        let mode = match index {
+            0 => self.default_mode,
+            _ => self.regions[index - 1].1,
+        };
+        (mode, self.regions.get(index).map(|x| x.0))
+    }
+
+    fn read(&self, bytes: &[u8], n: usize) -> Result<u32, DecodeError> {
+        if bytes.len() < n {
+            return Err(DecodeError::EndOfInput);
+        }
+        let bytes = &bytes[..n];
+        let value = if self.big_endian {
+            bytes.iter().fold(0, |acc, x| acc << 8 | *x as u32)
+        } else {
+            bytes.iter().rev().fold(0, |acc, x| acc << 8 | *x as u32)
+        };
+        Ok(value)
+    }
+
+    fn data(&self, bytes: &[u8], address: u64, end: Option<u64>) -> Result<Instruction, DecodeError> {
+        // The largest naturally aligned item which fits before the data ends
+        let room = end.map(|x| x.saturating_sub(address) as usize).unwrap_or(usize::MAX).min(bytes.len());
+        let (mnemonic, size) = match room {
+            0 => return Err(DecodeError::EndOfInput),
+            _ if room >= 4 && address & 3 == 0 => (".word", 4),
+            _ if room >= 2 && address & 1 == 0 => (".short", 2),
+            _ => (".byte", 1),
+        };
+        let value = self.read(bytes, size)?;
+        let operands = vec![Operand::Immediate { value: value as i64, size: size as u8 }];
+        Ok(Instruction::new(InstructionSet::ARM, address, size, mnemonic.to_string(), operands))
+    }
+
+    fn thumb(&self, bytes: &[u8], address: u64) -> Result<(Decoded, usize, u32, u32), DecodeError> {
+        let it = self.it.take().filter(|x| x.address == address);
+        let context = Context {
+            address,
+            condition: it.map(|x| (x.state >> 4) as u32).unwrap_or(ALWAYS),
+            in_it_block: it.is_some(),
+        };
+
+        let first = self.read(bytes, 2)?;
+        let (decoded, length, word) = if first >> 11 >= 0b11101 {
+            let second = self.read(&bytes[2..], 2)?;
+            let word = first << 16 | second;
+            (thumb2::decode(word, context), 4, word)
+        } else {
+            (thumb::decode(first, context), 2, first)
+        };
+
+        // Advance the IT state past this instruction, or start a new block
+        let next = address.wrapping_add(length as u64);
+        if let Some(it) = it {
+            if it.state & 0b111 != 0 {
+                let state = (it.state & 0b1110_0000) | ((it.state << 1) & 0b1_1111);
+                self.it.set(Some(ItState { address: next, state }));
+            }
+        } else if length == 2 && word >> 8 == 0b1011_1111 && word & 0xf != 0 && decoded.is_some() {
+            self.it.set(Some(ItState { address: next, state: word as u8 }));
+        }
+
+        let condition = match branch_condition(word, length) {
+            Some(condition) => condition,
+            None => context.condition,
+        };
+        Ok((decoded, length, condition, word))
+    }
+}
+
+impl Decoder for ArmDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        let (mode, end) = self.mode_at(address);
+
+        let (decoded, length, condition, word) = match mode {
+            ArmMode::Data => return self.data(bytes, address, end),
+            ArmMode::Arm => {
+                let word = self.read(bytes, 4)?;
+                let condition = bits(word, 28, 4);
+                (a32::decode(word, address), 4, condition, word)
+            }
+            ArmMode::Thumb => self.thumb(bytes, address)?,
+        };
+
+        let (mnemonic, operands) = decoded.ok_or(DecodeError::InvalidOpcode(word))?;
+        let mut instruction = Instruction::new(InstructionSet::ARM, address, length, String::new(), operands);
+        instruction.conditional = condition < ALWAYS || mnemonic.starts_with("cbz") || mnemonic.starts_with("cbnz");
+        instruction.flow = flow_of(&mnemonic, &instruction.operands);
+        instruction.mnemonic = mnemonic;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, address: u64) -> usize {
+        match self.mode_at(address).0 {
+            ArmMode::Arm => 4,
+            ArmMode::Thumb => 2,
+            ArmMode::Data => 1,
+        }
+    }
+}
+
+// Condition of the Thumb conditional branches, the only ones encoding a condition outside of IT blocks
+fn branch_condition(word: u32, length: usize) -> Option<u32> {
+    if length == 2 && word >> 12 == 0b1101 {
+        Some(bits(word, 8, 4))
+    } else if length == 4 && word >> 27 == 0b11110 && bits(word, 14, 2) == 0b10 && !bit(word, 12) {
+        let condition = bits(word, 22, 4);
+        if condition >> 1 != 0b111 {
+            return Some(condition);
+        }
+        None
+    } else {
+        None
+    }
+}
+
+fn flow_of(mnemonic: &str, operands: &[Operand]) -> FlowKind {
+    let base = mnemonic.split('.').next().unwrap_or("");
+    let is_pc = |x: &Operand| matches!(x, Operand::Register(register) if register.number as u32 == PC);
+    let condition_suffix = |rest: &str| CONDITIONS.contains(&rest);
+
+    if base == "svc" {
+        return FlowKind::Syscall;
+    }
+    if base.starts_with("udf") || base.starts_with("bkpt") {
+        return FlowKind::Trap;
+    }
+    if base.starts_with("cbz") || base.starts_with("cbnz") || base.starts_with("tbb") || base.starts_with("tbh") {
+        return FlowKind::Jump;
+    }
+
+    if let Some(rest) = base.strip_prefix('b') {
+        let link = rest.strip_prefix("lx").or_else(|| rest.strip_prefix('l'));
+        if link.is_some_and(condition_suffix) {
+            return FlowKind::Call;
+        }
+        if let Some(rest) = rest.strip_prefix('x') {
+            if condition_suffix(rest) {
+                return match operands.first() {
+                    Some(Operand::Register(register)) if register.number as u32 == LR => FlowKind::Return,
+                    _ => FlowKind::Jump,
+                };
+            }
+        }
+        if condition_suffix(rest) {
+            return FlowKind::Jump;
+        }
+    }
+
+    // Everything else only changes the flow by writing the pc
+    for operand in operands {
+        if let Operand::List(registers) = operand {
+            if registers.iter().any(is_pc) {
+                let from_stack = base.starts_with("pop")
+                    || operands.iter().any(|x| matches!(x, Operand::Memory(mem) if mem.base.map(|x| x.number as u32) == Some(SP)));
+                return if from_stack { FlowKind::Return } else { FlowKind::Jump };
+            }
+        }
+    }
+
+    let writes_first = !(base.starts_with("st")
+        || base.starts_with("cmp")
+        || base.starts_with("cmn")
+        || base.starts_with("tst")
+        || base.starts_with("teq")
+        || base.starts_with("push")
+        || base.starts_with("pl")
+        || base.starts_with("msr")
+        || base.starts_with("mcr")
+        || base.starts_with("vmsr"));
+    match operands.first() {
+        Some(first) if is_pc(first) && writes_first => {
+            let returns = match &operands[1..] {
+                [Operand::Register(register)] => register.number as u32 == LR,
+                [Operand::Memory(mem), ..] => mem.base.map(|x| x.number as u32) == Some(SP) && mem.mode == IndexMode::PostIndex,
+                _ => false,
+            };
+            if returns {
+                FlowKind::Return
+            } else {
+                FlowKind::Jump
+            }
+        }
+        _ => FlowKind::Sequential,
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+// Inserts the condition before the data type or width suffix, as in addeq.w and vaddeq.f32
+fn conditional(mnemonic: &str, condition: u32) -> String {
+    let suffix = CONDITIONS[condition as usize & 15];
+    match mnemonic.find('.') {
+        Some(dot) => format!("{}{}{}", &mnemonic[..dot], suffix, &mnemonic[dot..]),
+        None => format!("{}{}", mnemonic, suffix),
+    }
+}
+
+fn ins(mnemonic: &str, condition: u32, operands: Vec<Operand>) -> Decoded {
+    Some((conditional(mnemonic, condition), operands))
+}
+
+fn reg(number: u32) -> Operand {
+    Operand::Register(gpr(number))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 4 }
+}
+
+fn shift(kind: ShiftKind, amount: u8) -> Operand {
+    Operand::Shift { kind, amount }
+}
+
+fn target(address: u64) -> Operand {
+    Operand::Address(address & 0xffff_ffff)
+}
+
+const SHIFTS: [ShiftKind; 4] = [ShiftKind::Lsl, ShiftKind::Lsr, ShiftKind::Asr, ShiftKind::Ror];
+
+// DecodeImmShift: the 2 bit type and 5 bit amount of an immediate shift, None for lsl #0
+fn immediate_shift(kind: u32, amount: u32) -> Option<Operand> {
+    match (kind, amount) {
+        (0, 0) => None,
+        (3, 0) => Some(shift(ShiftKind::Rrx, 0)),
+        (1, 0) | (2, 0) => Some(shift(SHIFTS[kind as usize], 32)),
+        _ => Some(shift(SHIFTS[kind as usize], amount as u8)),
+    }
+}
+
+// A register list from a bit mask
+fn register_list(mask: u32) -> Operand {
+    Operand::List((0..16).filter(|x| mask & (1 << x) != 0).map(reg).collect())
+}
+
+// Base register of a load or store multiple, with or without writeback
+fn multiple_base(rn: u32, writeback: bool, size: u16) -> Operand {
+    let mut mem = MemoryOperand::new(size);
+    mem.base = Some(gpr(rn));
+    mem.mode = if writeback { IndexMode::PreIndex } else { IndexMode::Offset };
+    Operand::Memory(mem)
+}
+
+// A load or store with an immediate offset. Literal addresses are resolved relative to `pc`.
+fn memory_immediate(rn: u32, offset: u32, add: bool, index: bool, writeback: bool, size: u16, pc: u64) -> Operand {
+    let mut mem = MemoryOperand::new(size);
+    mem.base = Some(gpr(rn));
+    mem.displacement = if add { offset as i64 } else { -(offset as i64) };
+    mem.subtract = !add;
+    mem.mode = match (index, writeback) {
+        (true, false) => IndexMode::Offset,
+        (true, true) => IndexMode::PreIndex,
+        (false, _) => IndexMode::PostIndex,
+    };
+    if rn == PC && mem.mode == IndexMode::Offset {
+        mem.target = Some((pc & !3).wrapping_add(mem.displacement as u64) & 0xffff_ffff);
+    }
+    Operand::Memory(mem)
+}
+
+// A load or store with a register offset, optionally shifted
+fn memory_register(rn: u32, rm: u32, add: bool, index: bool, writeback: bool, shift: Option<Operand>, size: u16) -> Operand {
+    let mut mem = MemoryOperand::new(size);
+    mem.base = Some(gpr(rn));
+    mem.index = Some(gpr(rm));
+    mem.subtract = !add;
+    mem.index_shift = match shift {
+        Some(Operand::Shift { kind, amount }) => Some((kind, amount)),
+        _ => None,
+    };
+    mem.mode = match (index, writeback) {
+        (true, false) => IndexMode::Offset,
+        (true, true) => IndexMode::PreIndex,
+        (false, _) => IndexMode::PostIndex,
+    };
+    Operand::Memory(mem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mode_regions, ArmDecoder, ArmMode};
+    use crate::disasm::{Decoder, FlowKind, Syntax};
+
+    // Text and whether it is conditional, for each instruction of the bytes in turn
+    fn run(decoder: &ArmDecoder, address: u64, bytes: &[u8]) -> Vec<(String, bool)> {
+        let mut decoded = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let instruction = decoder.decode(&bytes[offset..], address + offset as u64).unwrap();
+            decoded.push((instruction.render(Syntax::Intel), instruction.conditional));
+            offset += instruction.length;
+        }
+        decoded
+    }
+
+    fn texts(decoded: Vec<(String, bool)>) -> Vec<String> {
+        decoded.into_iter().map(|x| x.0).collect()
+    }
+
+    #[test]
+    fn a32() {
+        let decoder = ArmDecoder::new(vec![], ArmMode::Arm, false);
+        let bytes = [
+            0x10, 0x40, 0x2d, 0xe9, 0x08, 0x00, 0x9f, 0xe5, 0x82, 0x01, 0x81, 0x00, 0x40, 0x00, 0x00, 0xeb, 0x02, 0x0b,
+            0x31, 0xee, 0x10, 0x80, 0xbd, 0xe8,
+        ];
+        let expected = [
+            ("push {r4, lr}", false),
+            ("ldr r0, [pc, #8]        @ 0x1014", false),
+            ("addeq r0, r1, r2, lsl #3", true),
+            ("bl 0x1114", false),
+            ("vadd.f64 d0, d1, d2", false),
+            ("pop {r4, pc}", false),
+        ];
+        let expected: Vec<(String, bool)> = expected.iter().map(|x| (x.0.to_string(), x.1)).collect();
+        assert_eq!(run(&decoder, 0x1000, &bytes), expected);
+        assert_eq!(decoder.decode(&[0x10, 0x80, 0xbd, 0xe8], 0).unwrap().flow, FlowKind::Return);
+    }
+
+    #[test]
+    fn thumb_widths() {
+        let decoder = ArmDecoder::new(vec![], ArmMode::Thumb, false);
+        let bytes = [0x00, 0xf0, 0x80, 0xf8, 0xd1, 0xf8, 0x04, 0x00, 0x08, 0xd0, 0x40, 0xb1, 0x70, 0x47];
+        let expected = ["bl 0x1104", "ldr.w r0, [r1, #4]", "beq 0x101c", "cbz r0, 0x101e", "bx lr"];
+        assert_eq!(texts(run(&decoder, 0x1000, &bytes)), expected);
+    }
+
+    #[test]
+    fn it_blocks() {
+        let decoder = ArmDecoder::new(vec![], ArmMode::Thumb, false);
+        // cmp r0, #0; ite eq; mov r0, #1; mov r0, #2; mov r0, #1
+        let bytes = [0x00, 0x28, 0x0c, 0xbf, 0x01, 0x20, 0x02, 0x20, 0x01, 0x20];
+        let expected = [
+            ("cmp r0, #0", false),
+            ("ite eq", false),
+            ("moveq r0, #1", true),
+            ("movne r0, #2", true),
+            // Past the end of the block the same encoding sets the flags again
+            ("movs r0, #1", false),
+        ];
+        let expected: Vec<(String, bool)> = expected.iter().map(|x| (x.0.to_string(), x.1)).collect();
+        assert_eq!(run(&decoder, 0x1000, &bytes), expected);
+
+        // The block only applies to the instructions which follow the it
+        decoder.decode(&[0x0c, 0xbf], 0x2000).unwrap();
+        assert_eq!(texts(run(&decoder, 0x3000, &[0x01, 0x20])), ["movs r0, #1"]);
+    }
+
+    #[test]
+    fn mapping_symbols_select_the_mode() {
+        let symbols: [(u64, &[u8], bool); 4] =
+            [(0x1000, b"$a", false), (0x1008, b"$t.0", false), (0x100c, b"$d", false), (0x1000, b"main", true)];
+        let decoder = ArmDecoder::new(mode_regions(&symbols), ArmMode::Arm, false);
+        let bytes = [0x1e, 0xff, 0x2f, 0xe1, 0x1e, 0xff, 0x2f, 0xe1, 0x10, 0xb5, 0x10, 0xbd, 0x78, 0x56, 0x34, 0x12, 0xff];
+        let expected = ["bx lr", "bx lr", "push {r4, lr}", "pop {r4, pc}", ".word 0x12345678", ".byte 0xff"];
+        assert_eq!(texts(run(&decoder, 0x1000, &bytes)), expected);
+
+        // Without mapping symbols the low bit of function addresses marks Thumb
+        let symbols: [(u64, &[u8], bool); 2] = [(0x1000, b"f", true), (0x2001, b"g", true)];
+        assert_eq!(mode_regions(&symbols), [(0x1000, ArmMode::Arm), (0x2000, ArmMode::Thumb)]);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::disasm::{Register, RegisterClass};
+
+// GNU names: r11 is the frame pointer and r12 the intra procedure call scratch register
+const R: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7",
+    "r8", "r9", "sl", "fp", "ip", "sp", "lr", "pc",
+];
+
+const S: [&str; 32] = [
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "s12", "s13", "s14", "s15",
+    "s16", "s17", "s18", "s19", "s20", "s21", "s22", "s23",
+    "s24", "s25", "s26", "s27", "s28", "s29", "s30", "s31",
+];
+
+const D: [&str; 32] = [
+    "d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7",
+    "d8", "d9", "d10", "d11", "d12", "d13", "d14", "d15",
+    "d16", "d17", "d18", "d19", "d20", "d21", "d22", "d23",
+    "d24", "d25", "d26", "d27", "d28", "d29", "d30", "d31",
+];
+
+pub const SP: u32 = 13;
+pub const LR: u32 = 14;
+pub const PC: u32 = 15;
+
+/// A core register, r15 being the program counter
+pub fn gpr(number: u32) -> Register {
+    let number = number & 15;
+    let class = if number == PC { RegisterClass::ProgramCounter } else { RegisterClass::General };
+    Register::new(class, number as u16, 32, R[number as usize])
+}
+
+/// A single precision VFP register
+pub fn single(number: u32) -> Register {
+    let number = number & 31;
+    Register::new(RegisterClass::Float, number as u16, 32, S[number as usize])
+}
+
+/// A double precision VFP register
+pub fn double(number: u32) -> Register {
+    let number = number & 31;
+    Register::new(RegisterClass::Float, number as u16, 64, D[number as usize])
+}
+
+/// Status and system registers, numbered by their encoding
+pub fn special(number: u16, name: &'static str) -> Register {
+    Register::new(RegisterClass::Special, number, 32, name)
+}
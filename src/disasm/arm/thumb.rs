@@ -0,0 +1,229 @@
+use crate::disasm::Operand;
+
+use super::registers::{LR, PC, SP};
+use super::{
+    bit, bits, imm, immediate_shift, ins, memory_immediate, memory_register, multiple_base, reg, register_list,
+    sign_extend, target, Context, Decoded,
+};
+
+// Operations of the two register data processing instructions, indexed by the 4 bit opcode
+const DATA_PROCESSING: [&str; 16] = [
+    "and", "eor", "lsl", "lsr", "asr", "adc", "sbc", "ror",
+    "tst", "neg", "cmp", "cmn", "orr", "mul", "bic", "mvn",
+];
+
+const HINTS: [&str; 5] = ["nop", "yield", "wfe", "wfi", "sev"];
+
+// Instructions which set the flags outside of IT blocks but not inside them
+fn setting(mnemonic: &str, context: Context) -> String {
+    if context.in_it_block {
+        mnemonic.to_string()
+    } else {
+        format!("{}s", mnemonic)
+    }
+}
+
+/// Decodes a 16 bit Thumb instruction
+pub fn decode(half: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rd, rn, rm) = (bits(half, 0, 3), bits(half, 3, 3), bits(half, 6, 3));
+
+    match bits(half, 10, 6) {
+        0b000000..=0b000101 => {
+            // Shift by an immediate, lsl #0 is a move
+            let (kind, amount) = (bits(half, 11, 2), bits(half, 6, 5));
+            match immediate_shift(kind, amount) {
+                None => ins(&setting("mov", context), condition, vec![reg(rd), reg(rn)]),
+                Some(Operand::Shift { kind, amount }) => {
+                    let mnemonic = setting(super::a32::shift_name(kind), context);
+                    ins(&mnemonic, condition, vec![reg(rd), reg(rn), imm(amount as i64)])
+                }
+                _ => None,
+            }
+        }
+        0b000110 | 0b000111 => {
+            let mnemonic = setting(if bit(half, 9) { "sub" } else { "add" }, context);
+            let operand = if bit(half, 10) { imm(rm as i64) } else { reg(rm) };
+            ins(&mnemonic, condition, vec![reg(rd), reg(rn), operand])
+        }
+        0b001000..=0b001111 => {
+            let rd = bits(half, 8, 3);
+            let value = imm(bits(half, 0, 8) as i64);
+            match bits(half, 11, 2) {
+                0b00 => ins(&setting("mov", context), condition, vec![reg(rd), value]),
+                0b01 => ins("cmp", condition, vec![reg(rd), value]),
+                0b10 => ins(&setting("add", context), condition, vec![reg(rd), value]),
+                _ => ins(&setting("sub", context), condition, vec![reg(rd), value]),
+            }
+        }
+        0b010000 => {
+            let opcode = bits(half, 6, 4);
+            let name = DATA_PROCESSING[opcode as usize];
+            match opcode {
+                0b1000 | 0b1010 | 0b1011 => ins(name, condition, vec![reg(rd), reg(rn)]),
+                0b1001 => ins(&setting(name, context), condition, vec![reg(rd), reg(rn)]),
+                0b1101 => ins(&setting(name, context), condition, vec![reg(rd), reg(rn), reg(rd)]),
+                _ => ins(&setting(name, context), condition, vec![reg(rd), reg(rn)]),
+            }
+        }
+        0b010001 => special_data_branch(half, context),
+        0b010010 | 0b010011 => {
+            let offset = bits(half, 0, 8) << 2;
+            let address = memory_immediate(PC, offset, true, true, false, 4, context.pc());
+            ins("ldr", condition, vec![reg(bits(half, 8, 3)), address])
+        }
+        0b010100..=0b010111 => {
+            let (mnemonic, size) = [
+                ("str", 4), ("strh", 2), ("strb", 1), ("ldrsb", 1),
+                ("ldr", 4), ("ldrh", 2), ("ldrb", 1), ("ldrsh", 2),
+            ][bits(half, 9, 3) as usize];
+            ins(mnemonic, condition, vec![reg(rd), memory_register(rn, rm, true, true, false, None, size)])
+        }
+        0b011000..=0b100011 => {
+            // Loads and stores with an immediate offset scaled by the access size
+            let (name, size) = match bits(half, 12, 4) {
+                0b0110 => ("r", 4),
+                0b0111 => ("rb", 1),
+                _ => ("rh", 2),
+            };
+            let mnemonic = format!("{}{}", if bit(half, 11) { "ld" } else { "st" }, name);
+            let offset = bits(half, 6, 5) * size as u32;
+            ins(&mnemonic, condition, vec![reg(rd), memory_immediate(rn, offset, true, true, false, size, 0)])
+        }
+        0b100100..=0b100111 => {
+            let mnemonic = if bit(half, 11) { "ldr" } else { "str" };
+            let offset = bits(half, 0, 8) << 2;
+            ins(mnemonic, condition, vec![reg(bits(half, 8, 3)), memory_immediate(SP, offset, true, true, false, 4, 0)])
+        }
+        0b101000 | 0b101001 => {
+            let address = (context.pc() & !3).wrapping_add((bits(half, 0, 8) << 2) as u64);
+            ins("adr", condition, vec![reg(bits(half, 8, 3)), target(address)])
+        }
+        0b101010 | 0b101011 => {
+            ins("add", condition, vec![reg(bits(half, 8, 3)), reg(SP), imm((bits(half, 0, 8) << 2) as i64)])
+        }
+        0b101100..=0b101111 => miscellaneous(half, context),
+        0b110000..=0b110011 => {
+            let rn = bits(half, 8, 3);
+            let list = bits(half, 0, 8);
+            if list == 0 {
+                return None;
+            }
+            // Loads only write back when the base isn't loaded
+            let load = bit(half, 11);
+            let writeback = !load || list & (1 << rn) == 0;
+            let mnemonic = if load { "ldm" } else { "stm" };
+            ins(mnemonic, condition, vec![multiple_base(rn, writeback, (list.count_ones() * 4) as u16), register_list(list)])
+        }
+        0b110100..=0b110111 => match bits(half, 8, 4) {
+            0b1110 => ins("udf", condition, vec![imm(bits(half, 0, 8) as i64)]),
+            0b1111 => ins("svc", condition, vec![imm(bits(half, 0, 8) as i64)]),
+            branch_condition => {
+                let offset = sign_extend(bits(half, 0, 8) << 1, 9);
+                ins("b", branch_condition, vec![target(context.pc().wrapping_add(offset as u64))])
+            }
+        },
+        0b111000 | 0b111001 => {
+            let offset = sign_extend(bits(half, 0, 11) << 1, 12);
+            ins("b", condition, vec![target(context.pc().wrapping_add(offset as u64))])
+        }
+        _ => None,
+    }
+}
+
+// Operations on all 16 registers and branch and exchange
+fn special_data_branch(half: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let rd = (bit(half, 7) as u32) << 3 | bits(half, 0, 3);
+    let rm = bits(half, 3, 4);
+
+    match bits(half, 8, 2) {
+        // The sp form names the destination twice
+        0b00 if rm == SP => ins("add", condition, vec![reg(rd), reg(SP), reg(rd)]),
+        0b00 => ins("add", condition, vec![reg(rd), reg(rm)]),
+        0b01 => ins("cmp", condition, vec![reg(rd), reg(rm)]),
+        0b10 => ins("mov", condition, vec![reg(rd), reg(rm)]),
+        _ if bit(half, 7) && bits(half, 0, 3) != 0 => None,
+        _ if bit(half, 7) => ins("blx", condition, vec![reg(rm)]),
+        _ => ins("bx", condition, vec![reg(rm)]),
+    }
+}
+
+fn miscellaneous(half: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rd, rm) = (bits(half, 0, 3), bits(half, 3, 3));
+
+    match bits(half, 5, 7) {
+        0b0000000..=0b0000011 => ins("add", condition, vec![reg(SP), imm((bits(half, 0, 7) << 2) as i64)]),
+        0b0000100..=0b0000111 => ins("sub", condition, vec![reg(SP), imm((bits(half, 0, 7) << 2) as i64)]),
+        0b0010000..=0b0010111 => {
+            let mnemonic = ["sxth", "sxtb", "uxth", "uxtb"][bits(half, 6, 2) as usize];
+            ins(mnemonic, condition, vec![reg(rd), reg(rm)])
+        }
+        0b0100000..=0b0101111 => {
+            let list = bits(half, 0, 8) | (bit(half, 8) as u32) << LR;
+            if list == 0 {
+                return None;
+            }
+            ins("push", condition, vec![register_list(list)])
+        }
+        0b0110010 if context.in_it_block || !bit(half, 4) || bits(half, 0, 3) != 0 => None,
+        0b0110010 => {
+            let endian = if bit(half, 3) { "be" } else { "le" };
+            Some(("setend".to_string(), vec![Operand::Name(endian)]))
+        }
+        0b0110011 if context.in_it_block => None,
+        0b0110011 => {
+            let flags = ["", "f", "i", "if", "a", "af", "ai", "aif"][bits(half, 0, 3) as usize];
+            let mnemonic = if bit(half, 4) { "cpsid" } else { "cpsie" };
+            Some((mnemonic.to_string(), vec![Operand::Name(flags)]))
+        }
+        0b1010000..=0b1010011 | 0b1010110..=0b1010111 => {
+            let mnemonic = ["rev", "rev16", "", "revsh"][bits(half, 6, 2) as usize];
+            ins(mnemonic, condition, vec![reg(rd), reg(rm)])
+        }
+        0b1100000..=0b1101111 => {
+            let list = bits(half, 0, 8) | (bit(half, 8) as u32) << PC;
+            if list == 0 {
+                return None;
+            }
+            ins("pop", condition, vec![register_list(list)])
+        }
+        0b1110000..=0b1110111 => Some(("bkpt".to_string(), vec![imm(bits(half, 0, 8) as i64)])),
+        0b1111000..=0b1111111 => {
+            let mask = bits(half, 0, 4);
+            if mask == 0 {
+                return match HINTS.get(bits(half, 4, 4) as usize) {
+                    Some(hint) => ins(hint, condition, vec![]),
+                    None => ins("hint", condition, vec![imm(bits(half, 4, 4) as i64)]),
+                };
+            }
+            if context.in_it_block {
+                return None;
+            }
+            if_then(bits(half, 4, 4), mask)
+        }
+        _ => cbz(half, context),
+    }
+}
+
+// IT, the pattern of then and else for up to three instructions after the first
+fn if_then(first: u32, mask: u32) -> Decoded {
+    let mut mnemonic = String::from("it");
+    let length = 3 - mask.trailing_zeros();
+    for i in 0..length {
+        let then = bit(mask, 3 - i) == bit(first, 0);
+        mnemonic.push(if then { 't' } else { 'e' });
+    }
+    Some((mnemonic, vec![Operand::Condition(first as u8)]))
+}
+
+fn cbz(half: u32, context: Context) -> Decoded {
+    // Compare and branch, the remaining encodings are unallocated
+    if half & 0b0000_0101_0000_0000 != 0b0000_0001_0000_0000 || context.in_it_block {
+        return None;
+    }
+    let offset = (bit(half, 9) as u32) << 6 | bits(half, 3, 5) << 1;
+    let mnemonic = if bit(half, 11) { "cbnz" } else { "cbz" };
+    Some((mnemonic.to_string(), vec![reg(bits(half, 0, 3)), target(context.pc().wrapping_add(offset as u64))]))
+}
@@ -0,0 +1,581 @@
+use crate::disasm::{Operand, ShiftKind};
+
+use super::a32::{self, parallel, rotation, shift_name, status_fields, status_register, BARRIER_OPTIONS};
+use super::registers::{LR, PC, SP};
+use super::{
+    bit, bits, imm, immediate_shift, ins, memory_immediate, memory_register, multiple_base, reg, register_list, shift,
+    sign_extend, target, Context, Decoded, ALWAYS,
+};
+
+// Data processing operations of the modified immediate and shifted register forms, indexed by the 4 bit opcode
+const DATA_PROCESSING: [&str; 16] = [
+    "and", "bic", "orr", "orn", "eor", "", "", "",
+    "add", "", "adc", "sbc", "", "sub", "rsb", "",
+];
+
+const HINTS: [&str; 5] = ["nop", "yield", "wfe", "wfi", "sev"];
+
+// Parallel add and subtract operations in the A32 numbering, indexed by the T32 one. 0b101 is unallocated in both.
+const PARALLEL_OPERATIONS: [u32; 8] = [0b100, 0b000, 0b001, 0b101, 0b111, 0b011, 0b010, 0b101];
+
+// Wide encodings of instructions which also have a 16 bit form are marked with .w
+fn wide(mnemonic: &str) -> String {
+    format!("{}.w", mnemonic)
+}
+
+/// ThumbExpandImm: an 8 bit value either replicated over the word or rotated into place
+pub fn expand_immediate(imm12: u32) -> u32 {
+    let value = bits(imm12, 0, 8);
+    if bits(imm12, 10, 2) != 0 {
+        return (0x80 | bits(imm12, 0, 7)).rotate_right(bits(imm12, 7, 5));
+    }
+    match bits(imm12, 8, 2) {
+        0b00 => value,
+        0b01 => value << 16 | value,
+        0b10 => value << 24 | value << 8,
+        _ => value * 0x0101_0101,
+    }
+}
+
+/// Decodes a 32 bit Thumb instruction, the first halfword in the upper bits
+pub fn decode(word: u32, context: Context) -> Decoded {
+    match bits(word, 27, 2) {
+        0b01 => match bits(word, 25, 2) {
+            0b00 if !bit(word, 22) => load_store_multiple(word, context),
+            0b00 => dual_exclusive_table(word, context),
+            0b01 => shifted_register(word, context),
+            _ => coprocessor(word, context),
+        },
+        0b10 if bit(word, 15) => branch_miscellaneous(word, context),
+        0b10 if bit(word, 25) => plain_immediate(word, context),
+        0b10 => modified_immediate(word, context),
+        _ => match bits(word, 23, 4) {
+            0b0000..=0b0011 => load_store(word, context),
+            0b0100 | 0b0101 => data_processing_register(word, context),
+            0b0110 => multiply(word, context),
+            0b0111 => long_multiply(word, context),
+            _ => coprocessor(word, context),
+        },
+    }
+}
+
+fn coprocessor(word: u32, context: Context) -> Decoded {
+    // Advanced SIMD isn't supported
+    if bits(word, 24, 2) == 0b11 {
+        return None;
+    }
+    if bit(word, 28) {
+        let (mnemonic, operands) = a32::coprocessor2(word, context.pc())?;
+        return ins(&mnemonic, context.condition, operands);
+    }
+    a32::coprocessor(word, context.condition, context.pc())
+}
+
+fn load_store_multiple(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (writeback, load, rn) = (bit(word, 21), bit(word, 20), bits(word, 16, 4));
+    let list = bits(word, 0, 16);
+
+    match bits(word, 23, 2) {
+        // Stores never include sp or pc
+        0b01 | 0b10 if list == 0 || (!load && list & (1 << SP | 1 << PC) != 0) => None,
+        0b01 if load && rn == SP && writeback => ins(&wide("pop"), condition, vec![register_list(list)]),
+        0b10 if !load && rn == SP && writeback => ins(&wide("push"), condition, vec![register_list(list)]),
+        mode @ 0b01 | mode @ 0b10 => {
+            let mnemonic = match (load, mode) {
+                (true, 0b01) => wide("ldm"),
+                (false, 0b01) => wide("stm"),
+                (true, _) => "ldmdb".to_string(),
+                (false, _) => "stmdb".to_string(),
+            };
+            ins(&mnemonic, condition, vec![multiple_base(rn, writeback, (list.count_ones() * 4) as u16), register_list(list)])
+        }
+        mode => {
+            let mode = if mode == 0 { "db" } else { "ia" };
+            if load {
+                if bits(word, 0, 16) != 0xc000 {
+                    return None;
+                }
+                ins(&format!("rfe{}", mode), condition, vec![multiple_base(rn, writeback, 8)])
+            } else {
+                if rn != SP || bits(word, 5, 11) != 0b110_0000_0000 {
+                    return None;
+                }
+                ins(&format!("srs{}", mode), condition, vec![multiple_base(SP, writeback, 8), imm(bits(word, 0, 5) as i64)])
+            }
+        }
+    }
+}
+
+// A memory operand which is just a base register
+fn base_only(rn: u32, size: u16) -> Operand {
+    memory_immediate(rn, 0, true, true, false, size, 0)
+}
+
+// Doubleword loads and stores, exclusive accesses and table branches
+fn dual_exclusive_table(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (index, add, writeback, load) = (bit(word, 24), bit(word, 23), bit(word, 21), bit(word, 20));
+    let (rn, rt, rt2, rd) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let offset = bits(word, 0, 8) << 2;
+
+    if index || writeback {
+        let address = memory_immediate(rn, offset, add, index, writeback, 8, context.pc());
+        let mnemonic = if load { "ldrd" } else { "strd" };
+        return ins(mnemonic, condition, vec![reg(rt), reg(rt2), address]);
+    }
+
+    match (add, load) {
+        (false, false) => ins("strex", condition, vec![reg(rt2), reg(rt), memory_immediate(rn, offset, true, true, false, 4, 0)]),
+        (false, true) if rt2 != PC => None,
+        (false, true) => ins("ldrex", condition, vec![reg(rt), memory_immediate(rn, offset, true, true, false, 4, 0)]),
+        (true, false) => match bits(word, 4, 4) {
+            0b0100 => ins("strexb", condition, vec![reg(rd), reg(rt), base_only(rn, 1)]),
+            0b0101 => ins("strexh", condition, vec![reg(rd), reg(rt), base_only(rn, 2)]),
+            0b0111 => ins("strexd", condition, vec![reg(rd), reg(rt), reg(rt2), base_only(rn, 8)]),
+            _ => None,
+        },
+        (true, true) if bits(word, 4, 4) & 0b0100 != 0 && (bits(word, 0, 4) != 0b1111 || (bits(word, 4, 4) != 0b0111 && rt2 != PC)) => None,
+        (true, true) => match bits(word, 4, 4) {
+            0b0000 => ins("tbb", condition, vec![memory_register(rn, rd, true, true, false, None, 1)]),
+            0b0001 => {
+                let scaled = Some(shift(ShiftKind::Lsl, 1));
+                ins("tbh", condition, vec![memory_register(rn, rd, true, true, false, scaled, 2)])
+            }
+            0b0100 => ins("ldrexb", condition, vec![reg(rt), base_only(rn, 1)]),
+            0b0101 => ins("ldrexh", condition, vec![reg(rt), base_only(rn, 2)]),
+            0b0111 => ins("ldrexd", condition, vec![reg(rt), reg(rt2), base_only(rn, 8)]),
+            _ => None,
+        },
+    }
+}
+
+// Data processing with a register shifted by an immediate
+fn shifted_register(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let opcode = bits(word, 21, 4);
+    let set_flags = bit(word, 20);
+    let (rn, rd, rm) = (bits(word, 16, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let amount = bits(word, 12, 3) << 2 | bits(word, 6, 2);
+    let shifted = immediate_shift(bits(word, 4, 2), amount);
+    let suffix = if set_flags { "s" } else { "" };
+
+    let mut second = vec![reg(rm)];
+    second.extend(shifted.clone());
+
+    match opcode {
+        0b0000 | 0b0100 | 0b1000 | 0b1101 if rd == PC && set_flags => {
+            let mnemonic = match opcode {
+                0b0000 => "tst",
+                0b0100 => "teq",
+                0b1000 => "cmn",
+                _ => "cmp",
+            };
+            let mut operands = vec![reg(rn)];
+            operands.extend(second);
+            ins(&wide(mnemonic), condition, operands)
+        }
+        0b0010 if rn == PC => match shifted {
+            None => ins(&wide(&format!("mov{}", suffix)), condition, vec![reg(rd), reg(rm)]),
+            Some(Operand::Shift { kind: ShiftKind::Rrx, .. }) => ins(&format!("rrx{}", suffix), condition, vec![reg(rd), reg(rm)]),
+            Some(Operand::Shift { kind, amount }) => {
+                let mnemonic = wide(&format!("{}{}", shift_name(kind), suffix));
+                ins(&mnemonic, condition, vec![reg(rd), reg(rm), imm(amount as i64)])
+            }
+            _ => None,
+        },
+        0b0011 if rn == PC => {
+            let mut operands = vec![reg(rd)];
+            operands.extend(second);
+            ins(&format!("mvn{}", suffix), condition, operands)
+        }
+        0b0110 if !set_flags => {
+            if bit(word, 5) {
+                let amount = if amount == 0 { 32 } else { amount };
+                ins("pkhtb", condition, vec![reg(rd), reg(rn), reg(rm), shift(ShiftKind::Asr, amount as u8)])
+            } else {
+                let mut operands = vec![reg(rd), reg(rn), reg(rm)];
+                operands.extend(immediate_shift(0, amount));
+                ins("pkhbt", condition, operands)
+            }
+        }
+        _ if DATA_PROCESSING[opcode as usize].is_empty() => None,
+        _ => {
+            let name = format!("{}{}", DATA_PROCESSING[opcode as usize], suffix);
+            let mnemonic = if opcode == 0b0011 { name } else { wide(&name) };
+            let mut operands = vec![reg(rd), reg(rn)];
+            operands.extend(second);
+            ins(&mnemonic, condition, operands)
+        }
+    }
+}
+
+fn modified_immediate(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let opcode = bits(word, 21, 4);
+    let set_flags = bit(word, 20);
+    let (rn, rd) = (bits(word, 16, 4), bits(word, 8, 4));
+    let value = imm(expand_immediate((bit(word, 26) as u32) << 11 | bits(word, 12, 3) << 8 | bits(word, 0, 8)) as i64);
+    let suffix = if set_flags { "s" } else { "" };
+
+    match opcode {
+        0b0000 | 0b0100 | 0b1000 | 0b1101 if rd == PC && set_flags => match opcode {
+            0b0000 => ins("tst", condition, vec![reg(rn), value]),
+            0b0100 => ins("teq", condition, vec![reg(rn), value]),
+            0b1000 => ins(&wide("cmn"), condition, vec![reg(rn), value]),
+            _ => ins(&wide("cmp"), condition, vec![reg(rn), value]),
+        },
+        0b0010 if rn == PC => ins(&wide(&format!("mov{}", suffix)), condition, vec![reg(rd), value]),
+        0b0011 if rn == PC => ins(&format!("mvn{}", suffix), condition, vec![reg(rd), value]),
+        _ if DATA_PROCESSING[opcode as usize].is_empty() => None,
+        0b1000 | 0b1101 => {
+            let mnemonic = wide(&format!("{}{}", DATA_PROCESSING[opcode as usize], suffix));
+            ins(&mnemonic, condition, vec![reg(rd), reg(rn), value])
+        }
+        _ => {
+            let mnemonic = format!("{}{}", DATA_PROCESSING[opcode as usize], suffix);
+            ins(&mnemonic, condition, vec![reg(rd), reg(rn), value])
+        }
+    }
+}
+
+// Saturation with an optional shift of the source, the 16 bit variants when shifting right by 0
+fn saturate(word: u32, condition: u32, signed: bool) -> Decoded {
+    let (rn, rd) = (bits(word, 16, 4), bits(word, 8, 4));
+    let amount = bits(word, 12, 3) << 2 | bits(word, 6, 2);
+    let bias = if signed { 1 } else { 0 };
+
+    if bit(word, 21) && amount == 0 {
+        let mnemonic = if signed { "ssat16" } else { "usat16" };
+        return ins(mnemonic, condition, vec![reg(rd), imm((bits(word, 0, 4) + bias) as i64), reg(rn)]);
+    }
+    let mut operands = vec![reg(rd), imm((bits(word, 0, 5) + bias) as i64), reg(rn)];
+    if bit(word, 21) {
+        operands.push(shift(ShiftKind::Asr, amount as u8));
+    } else {
+        operands.extend(immediate_shift(0, amount));
+    }
+    ins(if signed { "ssat" } else { "usat" }, condition, operands)
+}
+
+fn plain_immediate(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rn, rd) = (bits(word, 16, 4), bits(word, 8, 4));
+    let imm12 = (bit(word, 26) as u32) << 11 | bits(word, 12, 3) << 8 | bits(word, 0, 8);
+    let lsb = bits(word, 12, 3) << 2 | bits(word, 6, 2);
+    let literal = context.pc() & !3;
+
+    match bits(word, 20, 5) {
+        0b00000 if rn == PC => ins(&wide("adr"), condition, vec![reg(rd), target(literal.wrapping_add(imm12 as u64))]),
+        0b00000 => ins("addw", condition, vec![reg(rd), reg(rn), imm(imm12 as i64)]),
+        0b00100 => ins("movw", condition, vec![reg(rd), imm((rn << 12 | imm12) as i64)]),
+        0b01010 if rn == PC => ins(&wide("adr"), condition, vec![reg(rd), target(literal.wrapping_sub(imm12 as u64))]),
+        0b01010 => ins("subw", condition, vec![reg(rd), reg(rn), imm(imm12 as i64)]),
+        0b01100 => ins("movt", condition, vec![reg(rd), imm((rn << 12 | imm12) as i64)]),
+        // The saturation encodings have no i bit and a zero bit between imm2 and the saturation position
+        0b10000 | 0b10010 | 0b11000 | 0b11010 if bit(word, 26) || bit(word, 5) => None,
+        0b10000 | 0b10010 => saturate(word, condition, true),
+        0b11000 | 0b11010 => saturate(word, condition, false),
+        op @ 0b10100 | op @ 0b11100 => {
+            let width = bits(word, 0, 5) + 1;
+            let mnemonic = if op == 0b10100 { "sbfx" } else { "ubfx" };
+            ins(mnemonic, condition, vec![reg(rd), reg(rn), imm(lsb as i64), imm(width as i64)])
+        }
+        0b10110 if bit(word, 26) => None,
+        0b10110 => {
+            let msb = bits(word, 0, 5);
+            if msb < lsb {
+                return None;
+            }
+            let width = imm((msb - lsb + 1) as i64);
+            match rn {
+                PC => ins("bfc", condition, vec![reg(rd), imm(lsb as i64), width]),
+                _ => ins("bfi", condition, vec![reg(rd), reg(rn), imm(lsb as i64), width]),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn branch_miscellaneous(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let pc = context.pc();
+    let op1 = bits(word, 12, 3);
+    let op = bits(word, 20, 7);
+    let sign = bit(word, 26);
+    let (j1, j2) = (bit(word, 13), bit(word, 11));
+
+    if op1 & 0b101 == 0b000 {
+        if op & 0b0111000 != 0b0111000 {
+            // Conditional branch, the condition is in the instruction rather than from an IT block
+            let offset = (sign as u32) << 20 | (j2 as u32) << 19 | (j1 as u32) << 18 | bits(word, 16, 6) << 12 | bits(word, 0, 11) << 1;
+            let address = pc.wrapping_add(sign_extend(offset, 21) as u64);
+            return ins(&wide("b"), bits(word, 22, 4), vec![target(address)]);
+        }
+        return match op {
+            0b0111000 | 0b0111001 if bit(word, 13) || bits(word, 0, 8) != 0 => None,
+            0b0111000 | 0b0111001 => ins("msr", condition, vec![status_fields(bit(word, 20), bits(word, 8, 4)), reg(bits(word, 16, 4))]),
+            0b0111010 => change_processor_state_hints(word, context),
+            0b0111011 => {
+                let option = Operand::Name(BARRIER_OPTIONS[bits(word, 0, 4) as usize]);
+                match bits(word, 4, 4) {
+                    0b0010 => ins("clrex", condition, vec![]),
+                    0b0100 => ins("dsb", condition, vec![option]),
+                    0b0101 => ins("dmb", condition, vec![option]),
+                    0b0110 => ins("isb", condition, vec![option]),
+                    _ => None,
+                }
+            }
+            0b0111100 if bits(word, 0, 14) != 0x0f00 => None,
+            0b0111100 => ins("bxj", condition, vec![reg(bits(word, 16, 4))]),
+            0b0111101 if bits(word, 0, 8) == 0 => ins("eret", condition, vec![]),
+            0b0111101 => ins("subs", condition, vec![reg(PC), reg(LR), imm(bits(word, 0, 8) as i64)]),
+            0b0111110 | 0b0111111 if bits(word, 16, 4) != 0b1111 || bits(word, 0, 8) != 0 => None,
+            0b0111110 | 0b0111111 => ins("mrs", condition, vec![reg(bits(word, 8, 4)), status_register(bit(word, 20))]),
+            0b1111110 if op1 == 0b000 => ins("hvc", condition, vec![imm((bits(word, 16, 4) << 12 | bits(word, 0, 12)) as i64)]),
+            0b1111111 if op1 == 0b000 => ins("smc", condition, vec![imm(bits(word, 16, 4) as i64)]),
+            0b1111111 => ins(&wide("udf"), condition, vec![imm((bits(word, 16, 4) << 12 | bits(word, 0, 12)) as i64)]),
+            _ => None,
+        };
+    }
+
+    // I1 and I2 are the inverted J bits when the sign is clear
+    let (i1, i2) = (!(j1 ^ sign), !(j2 ^ sign));
+    let high = (sign as u32) << 24 | (i1 as u32) << 23 | (i2 as u32) << 22 | bits(word, 16, 10) << 12;
+    match op1 {
+        0b001 | 0b011 => {
+            let offset = sign_extend(high | bits(word, 0, 11) << 1, 25);
+            ins(&wide("b"), condition, vec![target(pc.wrapping_add(offset as u64))])
+        }
+        0b100 | 0b110 => {
+            // blx switches to A32, the target is word aligned
+            if bit(word, 0) {
+                return None;
+            }
+            let offset = sign_extend(high | bits(word, 1, 10) << 2, 25);
+            ins("blx", condition, vec![target((pc & !3).wrapping_add(offset as u64))])
+        }
+        _ => {
+            let offset = sign_extend(high | bits(word, 0, 11) << 1, 25);
+            ins("bl", condition, vec![target(pc.wrapping_add(offset as u64))])
+        }
+    }
+}
+
+fn change_processor_state_hints(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+
+    if bits(word, 8, 3) == 0 {
+        return match bits(word, 0, 8) {
+            hint @ 0..=4 => ins(&wide(HINTS[hint as usize]), condition, vec![]),
+            value if value >> 4 == 0xf => ins("dbg", condition, vec![imm((value & 0xf) as i64)]),
+            _ => None,
+        };
+    }
+    if context.in_it_block {
+        return None;
+    }
+
+    let flags = Operand::Name(["", "f", "i", "if", "a", "af", "ai", "aif"][bits(word, 5, 3) as usize]);
+    let mode = imm(bits(word, 0, 5) as i64);
+    let mnemonic = match bits(word, 9, 2) {
+        0b10 => "cpsie.w",
+        0b11 => "cpsid.w",
+        0b00 if bit(word, 8) => return ins("cps", ALWAYS, vec![mode]),
+        _ => return None,
+    };
+    if bit(word, 8) {
+        ins(&mnemonic[..5], ALWAYS, vec![flags, mode])
+    } else {
+        ins(mnemonic, ALWAYS, vec![flags])
+    }
+}
+
+// Single loads and stores of bytes, halfwords and words, and the preload hints
+fn load_store(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (signed, positive, size_field, load) = (bit(word, 24), bit(word, 23), bits(word, 21, 2), bit(word, 20));
+    let (rn, rt) = (bits(word, 16, 4), bits(word, 12, 4));
+    if size_field == 0b11 || (signed && !load) {
+        return None;
+    }
+    let size = 1 << size_field;
+
+    let name = match (load, signed, size_field) {
+        (false, _, 0b00) => "strb",
+        (false, _, 0b01) => "strh",
+        (false, _, _) => "str",
+        (true, false, 0b00) => "ldrb",
+        (true, false, 0b01) => "ldrh",
+        (true, false, _) => "ldr",
+        (true, true, 0b00) => "ldrsb",
+        (true, true, 0b01) => "ldrsh",
+        (true, true, _) => return None,
+    };
+    let hint = match (load && rt == PC, signed, size_field) {
+        (true, false, 0b00) => Some("pld"),
+        (true, true, 0b00) => Some("pli"),
+        (true, false, 0b01) => Some("pldw"),
+        (true, true, 0b01) => return None,
+        _ => None,
+    };
+    let imm8 = bits(word, 0, 8);
+
+    // The address and whether the form has a 16 bit counterpart
+    let (mnemonic, address) = if rn == PC {
+        if !load {
+            return None;
+        }
+        (wide(name), memory_immediate(PC, bits(word, 0, 12), positive, true, false, size, context.pc()))
+    } else if positive {
+        (wide(name), memory_immediate(rn, bits(word, 0, 12), true, true, false, size, 0))
+    } else if bits(word, 6, 6) == 0 {
+        let scaled = match bits(word, 4, 2) {
+            0 => None,
+            amount => Some(shift(ShiftKind::Lsl, amount as u8)),
+        };
+        (wide(name), memory_register(rn, bits(word, 0, 4), true, true, false, scaled, size))
+    } else if !bit(word, 11) {
+        return None;
+    } else if bit(word, 8) {
+        if hint.is_some() {
+            return None;
+        }
+        (name.to_string(), memory_immediate(rn, imm8, bit(word, 9), bit(word, 10), true, size, 0))
+    } else {
+        match bits(word, 8, 4) {
+            0b1100 => (name.to_string(), memory_immediate(rn, imm8, false, true, false, size, 0)),
+            0b1110 if hint.is_none() => (format!("{}t", name), memory_immediate(rn, imm8, true, true, false, size, 0)),
+            _ => return None,
+        }
+    };
+
+    match hint {
+        Some(hint) => ins(hint, condition, vec![address]),
+        None => ins(&mnemonic, condition, vec![reg(rt), address]),
+    }
+}
+
+// Shifts by a register, extensions, parallel arithmetic and the miscellaneous operations
+fn data_processing_register(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rn, rd, rm) = (bits(word, 16, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let (op1, op2) = (bits(word, 20, 4), bits(word, 4, 4));
+    if bits(word, 12, 4) != 0b1111 {
+        return None;
+    }
+
+    if op1 < 0b1000 && op2 == 0 {
+        let suffix = if bit(word, 20) { "s" } else { "" };
+        let mnemonic = wide(&format!("{}{}", shift_name(super::SHIFTS[bits(word, 21, 2) as usize]), suffix));
+        return ins(&mnemonic, condition, vec![reg(rd), reg(rn), reg(rm)]);
+    }
+
+    if op1 < 0b0110 && op2 & 0b1000 != 0 {
+        let name = ["sxtah", "uxtah", "sxtab16", "uxtab16", "sxtab", "uxtab"][op1 as usize];
+        let mut operands = vec![reg(rd)];
+        let mnemonic = if rn == PC {
+            // The plain extensions with a 16 bit form
+            let plain = format!("{}{}", &name[..2], &name[3..]);
+            if op1 == 0b0010 || op1 == 0b0011 { plain } else { wide(&plain) }
+        } else {
+            operands.push(reg(rn));
+            name.to_string()
+        };
+        operands.push(reg(rm));
+        operands.extend(rotation(bits(word, 4, 2)));
+        return ins(&mnemonic, condition, operands);
+    }
+
+    if op1 >= 0b1000 && op2 < 0b1000 {
+        let prefix = (bit(word, 6) as u32) << 2 | (bits(word, 4, 2) + 1);
+        let mnemonic = parallel(prefix, PARALLEL_OPERATIONS[bits(word, 20, 3) as usize])?;
+        return ins(&mnemonic, condition, vec![reg(rd), reg(rn), reg(rm)]);
+    }
+
+    if op1 & 0b1100 == 0b1000 && op2 & 0b1100 == 0b1000 {
+        return match (bits(word, 20, 2), bits(word, 4, 2)) {
+            (0b00, op) => {
+                let mnemonic = ["qadd", "qdadd", "qsub", "qdsub"][op as usize];
+                ins(mnemonic, condition, vec![reg(rd), reg(rm), reg(rn)])
+            }
+            (0b01, 0b00) => ins(&wide("rev"), condition, vec![reg(rd), reg(rm)]),
+            (0b01, 0b01) => ins(&wide("rev16"), condition, vec![reg(rd), reg(rm)]),
+            (0b01, 0b10) => ins("rbit", condition, vec![reg(rd), reg(rm)]),
+            (0b01, _) => ins(&wide("revsh"), condition, vec![reg(rd), reg(rm)]),
+            (0b10, 0b00) => ins("sel", condition, vec![reg(rd), reg(rn), reg(rm)]),
+            (0b11, 0b00) => ins("clz", condition, vec![reg(rd), reg(rm)]),
+            _ => None,
+        };
+    }
+    None
+}
+
+// Bottom or top half of a register for the halfword multiplies
+fn half(top: bool) -> &'static str {
+    if top {
+        "t"
+    } else {
+        "b"
+    }
+}
+
+fn multiply(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rn, ra, rd, rm) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let (op1, op2) = (bits(word, 20, 3), bits(word, 4, 2));
+    if bits(word, 6, 2) != 0 {
+        return None;
+    }
+    let x = if bit(word, 4) { "x" } else { "" };
+    let r = if bit(word, 4) { "r" } else { "" };
+
+    // Multiply and accumulate, without the accumulator when ra is the pc
+    let accumulate = |with: String, without: String| {
+        if ra == PC {
+            ins(&without, condition, vec![reg(rd), reg(rn), reg(rm)])
+        } else {
+            ins(&with, condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)])
+        }
+    };
+
+    match (op1, op2) {
+        (0b000, 0b00) => accumulate("mla".to_string(), "mul".to_string()),
+        (0b000, 0b01) => ins("mls", condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        (0b001, _) => {
+            let halves = format!("{}{}", half(bit(word, 5)), half(bit(word, 4)));
+            accumulate(format!("smla{}", halves), format!("smul{}", halves))
+        }
+        (0b010, 0b00) | (0b010, 0b01) => accumulate(format!("smlad{}", x), format!("smuad{}", x)),
+        (0b011, 0b00) | (0b011, 0b01) => {
+            let y = half(bit(word, 4));
+            accumulate(format!("smlaw{}", y), format!("smulw{}", y))
+        }
+        (0b100, 0b00) | (0b100, 0b01) => accumulate(format!("smlsd{}", x), format!("smusd{}", x)),
+        (0b101, 0b00) | (0b101, 0b01) => accumulate(format!("smmla{}", r), format!("smmul{}", r)),
+        (0b110, 0b00) | (0b110, 0b01) => ins(&format!("smmls{}", r), condition, vec![reg(rd), reg(rn), reg(rm), reg(ra)]),
+        (0b111, 0b00) => accumulate("usada8".to_string(), "usad8".to_string()),
+        _ => None,
+    }
+}
+
+fn long_multiply(word: u32, context: Context) -> Decoded {
+    let condition = context.condition;
+    let (rn, low, high, rm) = (bits(word, 16, 4), bits(word, 12, 4), bits(word, 8, 4), bits(word, 0, 4));
+    let x = if bit(word, 4) { "x" } else { "" };
+    let long = |mnemonic: &str| ins(mnemonic, condition, vec![reg(low), reg(high), reg(rn), reg(rm)]);
+
+    match (bits(word, 20, 3), bits(word, 4, 4)) {
+        (0b000, 0b0000) => long("smull"),
+        (0b001, 0b1111) if low == PC => ins("sdiv", condition, vec![reg(high), reg(rn), reg(rm)]),
+        (0b010, 0b0000) => long("umull"),
+        (0b011, 0b1111) if low == PC => ins("udiv", condition, vec![reg(high), reg(rn), reg(rm)]),
+        (0b100, 0b0000) => long("smlal"),
+        (0b100, 0b1000..=0b1011) => long(&format!("smlal{}{}", half(bit(word, 5)), half(bit(word, 4)))),
+        (0b100, 0b1100..=0b1101) => long(&format!("smlald{}", x)),
+        (0b101, 0b1100..=0b1101) => long(&format!("smlsld{}", x)),
+        (0b110, 0b0000) => long("umlal"),
+        (0b110, 0b0110) => long("umaal"),
+        _ => None,
+    }
+}
@@ -0,0 +1,279 @@
+use crate::disasm::Operand;
+
+use super::registers::{double, single, special, PC, SP};
+use super::{bit, bits, ins, memory_immediate, multiple_base, reg, Decoded};
+
+// System registers accessible with vmrs and vmsr, by their 4 bit number
+const SYSTEM_REGISTERS: [(u32, &str); 7] = [
+    (0b0000, "fpsid"), (0b0001, "fpscr"), (0b0110, "mvfr1"), (0b0111, "mvfr0"), (0b1000, "fpexc"), (0b1001, "fpinst"),
+    (0b1010, "fpinst2"),
+];
+
+// Register operands, single precision registers put the extra bit at the bottom and double precision ones at the top
+fn register(double_precision: bool, field: u32, extra: bool) -> Operand {
+    if double_precision {
+        Operand::Register(double((extra as u32) << 4 | field))
+    } else {
+        Operand::Register(single(field << 1 | extra as u32))
+    }
+}
+
+fn vd(word: u32, double_precision: bool) -> Operand {
+    register(double_precision, bits(word, 12, 4), bit(word, 22))
+}
+
+fn vn(word: u32, double_precision: bool) -> Operand {
+    register(double_precision, bits(word, 16, 4), bit(word, 7))
+}
+
+fn vm(word: u32, double_precision: bool) -> Operand {
+    register(double_precision, bits(word, 0, 4), bit(word, 5))
+}
+
+fn precision(double_precision: bool) -> &'static str {
+    if double_precision {
+        "f64"
+    } else {
+        "f32"
+    }
+}
+
+/// VFPExpandImm: the 8 bit floating point immediate of vmov, as the bits of an f64
+pub fn expand_immediate(imm8: u32) -> u64 {
+    let b = bit(imm8, 6) as u32;
+    let exponent = (b ^ 1) << 7 | (b * 0b11111) << 2 | bits(imm8, 4, 2);
+    let value = f32::from_bits((bit(imm8, 7) as u32) << 31 | exponent << 23 | bits(imm8, 0, 4) << 19);
+    (value as f64).to_bits()
+}
+
+/// Decodes the VFP instructions in coprocessor space, given in the A32 layout
+pub fn decode(word: u32, condition: u32, pc: u64) -> Decoded {
+    match (bits(word, 24, 4), bit(word, 4)) {
+        (0b1100, _) | (0b1101, _) => load_store(word, condition, pc),
+        (0b1110, false) => data_processing(word, condition),
+        (0b1110, true) => transfer(word, condition),
+        _ => None,
+    }
+}
+
+// Extension register loads and stores, and transfers of two core registers
+fn load_store(word: u32, condition: u32, pc: u64) -> Decoded {
+    let double_precision = bit(word, 8);
+    let (index, add, writeback, load) = (bit(word, 24), bit(word, 23), bit(word, 21), bit(word, 20));
+    let rn = bits(word, 16, 4);
+    let imm8 = bits(word, 0, 8);
+
+    if bits(word, 21, 4) == 0b0010 {
+        // vmov between two core registers and two single or one double precision register
+        if bits(word, 6, 2) != 0 || !bit(word, 4) {
+            return None;
+        }
+        let (rt, rt2) = (reg(bits(word, 12, 4)), reg(bits(word, 16, 4)));
+        let registers = if double_precision {
+            vec![vm(word, true)]
+        } else {
+            let first = bits(word, 0, 4) << 1 | bit(word, 5) as u32;
+            if first == 31 {
+                return None;
+            }
+            vec![Operand::Register(single(first)), Operand::Register(single(first + 1))]
+        };
+        let operands = if load {
+            vec![rt, rt2].into_iter().chain(registers).collect()
+        } else {
+            registers.into_iter().chain(vec![rt, rt2]).collect()
+        };
+        return ins("vmov", condition, operands);
+    }
+
+    if index && !writeback {
+        let mnemonic = if load { "vldr" } else { "vstr" };
+        let size = if double_precision { 8 } else { 4 };
+        let address = memory_immediate(rn, imm8 << 2, add, true, false, size, pc);
+        return ins(mnemonic, condition, vec![vd(word, double_precision), address]);
+    }
+
+    // Multiple registers, decrement before with writeback or increment after
+    if index == add {
+        return None;
+    }
+    let count = if double_precision {
+        // Odd counts are the deprecated fldmx and fstmx
+        if imm8 & 1 != 0 {
+            return None;
+        }
+        imm8 / 2
+    } else {
+        imm8
+    };
+    let first = if double_precision {
+        (bit(word, 22) as u32) << 4 | bits(word, 12, 4)
+    } else {
+        bits(word, 12, 4) << 1 | bit(word, 22) as u32
+    };
+    if count == 0 || first + count > 32 || (double_precision && count > 16) {
+        return None;
+    }
+    let list = Operand::List(
+        (first..first + count)
+            .map(|x| Operand::Register(if double_precision { double(x) } else { single(x) }))
+            .collect(),
+    );
+
+    match (load, index) {
+        (true, false) if rn == SP && writeback => ins("vpop", condition, vec![list]),
+        (false, true) if rn == SP => ins("vpush", condition, vec![list]),
+        _ => {
+            let mnemonic = format!("{}{}", if load { "vldm" } else { "vstm" }, if index { "db" } else { "ia" });
+            ins(&mnemonic, condition, vec![multiple_base(rn, writeback, (imm8 * 4) as u16), list])
+        }
+    }
+}
+
+fn data_processing(word: u32, condition: u32) -> Decoded {
+    let double_precision = bit(word, 8);
+    let opcode = (bit(word, 23) as u32) << 2 | bits(word, 20, 2);
+    let negated = bit(word, 6);
+    let suffix = precision(double_precision);
+    let three = |name: &str| {
+        let operands = vec![vd(word, double_precision), vn(word, double_precision), vm(word, double_precision)];
+        ins(&format!("{}.{}", name, suffix), condition, operands)
+    };
+
+    match (opcode, negated) {
+        (0b000, false) => three("vmla"),
+        (0b000, true) => three("vmls"),
+        (0b001, false) => three("vnmls"),
+        (0b001, true) => three("vnmla"),
+        (0b010, false) => three("vmul"),
+        (0b010, true) => three("vnmul"),
+        (0b011, false) => three("vadd"),
+        (0b011, true) => three("vsub"),
+        (0b100, false) => three("vdiv"),
+        (0b101, false) => three("vfnms"),
+        (0b101, true) => three("vfnma"),
+        (0b110, false) => three("vfma"),
+        (0b110, true) => three("vfms"),
+        (0b111, _) => other_data_processing(word, condition),
+        _ => None,
+    }
+}
+
+// vmov immediate and the operations with a single source: moves, comparisons and conversions
+fn other_data_processing(word: u32, condition: u32) -> Decoded {
+    let double_precision = bit(word, 8);
+    let suffix = precision(double_precision);
+    let (d, m) = (vd(word, double_precision), vm(word, double_precision));
+    let unary = |name: &str, operands: Vec<Operand>| ins(&format!("{}.{}", name, suffix), condition, operands);
+
+    if !bit(word, 6) {
+        if bits(word, 4, 4) & 0b1011 != 0 {
+            return None;
+        }
+        let value = Operand::Float(expand_immediate(bits(word, 16, 4) << 4 | bits(word, 0, 4)));
+        return unary("vmov", vec![d, value]);
+    }
+
+    let opc2 = bits(word, 16, 4);
+    let high = bit(word, 7);
+    match (opc2, high) {
+        (0b0000, false) => unary("vmov", vec![d, m]),
+        (0b0000, true) => unary("vabs", vec![d, m]),
+        (0b0001, false) => unary("vneg", vec![d, m]),
+        (0b0001, true) => unary("vsqrt", vec![d, m]),
+        (0b0010, _) | (0b0011, _) => {
+            // Half precision conversions, to or from the bottom or top half of a single precision register
+            if double_precision {
+                return None;
+            }
+            let half = if high { "vcvtt" } else { "vcvtb" };
+            let types = if opc2 == 0b0010 { "f32.f16" } else { "f16.f32" };
+            ins(&format!("{}.{}", half, types), condition, vec![vd(word, false), vm(word, false)])
+        }
+        (0b0100, _) => unary(if high { "vcmpe" } else { "vcmp" }, vec![d, m]),
+        (0b0101, _) => {
+            if bits(word, 0, 6) != 0 {
+                return None;
+            }
+            unary(if high { "vcmpe" } else { "vcmp" }, vec![d, Operand::Float(0f64.to_bits())])
+        }
+        (0b0111, true) => {
+            let types = if double_precision { "f32.f64" } else { "f64.f32" };
+            ins(&format!("vcvt.{}", types), condition, vec![vd(word, !double_precision), m])
+        }
+        (0b1000, _) => {
+            let source = if high { "s32" } else { "u32" };
+            ins(&format!("vcvt.{}.{}", suffix, source), condition, vec![d, vm(word, false)])
+        }
+        (0b1010, _) | (0b1011, _) | (0b1110, _) | (0b1111, _) => fixed_point(word, condition),
+        (0b1100, _) | (0b1101, _) => {
+            // To integer, rounding towards zero or with the rounding mode from the fpscr
+            let mnemonic = if high { "vcvt" } else { "vcvtr" };
+            let destination = if opc2 == 0b1101 { "s32" } else { "u32" };
+            ins(&format!("{}.{}.{}", mnemonic, destination, suffix), condition, vec![vd(word, false), m])
+        }
+        _ => None,
+    }
+}
+
+// Conversions between floating point and fixed point in place
+fn fixed_point(word: u32, condition: u32) -> Decoded {
+    let double_precision = bit(word, 8);
+    let size = if bit(word, 7) { 32 } else { 16 };
+    let immediate = bits(word, 0, 4) << 1 | bit(word, 5) as u32;
+    if immediate > size {
+        return None;
+    }
+    let fixed = format!("{}{}", if bit(word, 16) { "u" } else { "s" }, size);
+    let float = precision(double_precision);
+    let types = if bit(word, 18) { format!("{}.{}", fixed, float) } else { format!("{}.{}", float, fixed) };
+
+    let d = vd(word, double_precision);
+    let fraction_bits = Operand::Immediate { value: (size - immediate) as i64, size: 4 };
+    ins(&format!("vcvt.{}", types), condition, vec![d.clone(), d, fraction_bits])
+}
+
+// Transfers between a core register and a single precision register, a lane or a system register
+fn transfer(word: u32, condition: u32) -> Decoded {
+    let (load, rt) = (bit(word, 20), bits(word, 12, 4));
+
+    match (bit(word, 8), bits(word, 21, 3)) {
+        (false, 0b000) if bits(word, 0, 4) != 0 || bits(word, 5, 2) != 0 || rt == PC => None,
+        (false, 0b000) => {
+            let s = vn(word, false);
+            if load {
+                ins("vmov", condition, vec![reg(rt), s])
+            } else {
+                ins("vmov", condition, vec![s, reg(rt)])
+            }
+        }
+        (false, 0b111) => {
+            let number = bits(word, 16, 4);
+            let &(_, name) = SYSTEM_REGISTERS.iter().find(|x| x.0 == number)?;
+            let system = Operand::Register(special(number as u16, name));
+            match (load, rt) {
+                (true, PC) if number == 0b0001 => ins("vmrs", condition, vec![Operand::Register(special(0, "APSR_nzcv")), system]),
+                (true, _) => ins("vmrs", condition, vec![reg(rt), system]),
+                (false, _) => ins("vmsr", condition, vec![system, reg(rt)]),
+            }
+        }
+        (true, 0b000) | (true, 0b001) => {
+            // Only the 32 bit lanes are VFP, the smaller ones need Advanced SIMD
+            if bits(word, 5, 2) != 0 || bits(word, 0, 4) != 0 {
+                return None;
+            }
+            let lane = Operand::Vector {
+                register: double((bit(word, 7) as u32) << 4 | bits(word, 16, 4)),
+                element_bits: 32,
+                elements: 0,
+                lane: Some(bit(word, 21) as u8),
+            };
+            if load {
+                ins("vmov.32", condition, vec![reg(rt), lane])
+            } else {
+                ins("vmov.32", condition, vec![lane, reg(rt)])
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,6 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecodeError {
+    EndOfInput,
+    InvalidOpcode(u32), // The offending opcode bytes, as far as they were read
+    TooLong,
+}
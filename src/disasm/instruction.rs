@@ -0,0 +1,240 @@
+use crate::instruction_set::InstructionSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(unused)]
+pub enum RegisterClass {
+    General,
+    Segment,
+    Control,
+    Debug,
+    Float,
+    Vector,
+    Mask,
+    Mmx,
+    ProgramCounter,
+    Flags,
+    // Hardwired zero register (xzr)
+    Zero,
+    // System registers, `number` holds the architecture specific encoding
+    Special,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register {
+    pub class: RegisterClass,
+    pub number: u16,
+    pub bits: u16,
+    // Bit offset into the full register, e.g. 8 for ah
+    pub offset: u16,
+    pub name: &'static str,
+}
+
+impl Register {
+    pub const fn new(class: RegisterClass, number: u16, bits: u16, name: &'static str) -> Register {
+        Register {
+            class,
+            number,
+            bits,
+            offset: 0,
+            name,
+        }
+    }
+
+    /// Whether both registers refer to (parts of) the same architectural register
+    #[allow(unused)]
+    pub fn aliases(&self, other: &Register) -> bool {
+        self.class == other.class && self.number == other.number
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum IndexMode {
+    Offset,
+    PreIndex,
+    PostIndex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum ShiftKind {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    Msl,
+    Uxtb,
+    Uxth,
+    Uxtw,
+    Uxtx,
+    Sxtb,
+    Sxth,
+    Sxtw,
+    Sxtx,
+    // Rotate right by one through the carry flag (ARM)
+    Rrx,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryOperand {
+    pub base: Option<Register>,
+    pub index: Option<Register>,
+    pub scale: u8,
+    // Extension applied to the index register before scaling, if any
+    pub extend: Option<ShiftKind>,
+    pub displacement: i64,
+    // Size of the access in bytes, 0 if the operand is only an address computation (lea)
+    pub size: u16,
+    pub segment: Option<Register>,
+    pub mode: IndexMode,
+    // ARM: the index register or displacement is subtracted from the base
+    pub subtract: bool,
+    // ARM: shift applied to the index register, which isn't necessarily a scale
+    pub index_shift: Option<(ShiftKind, u8)>,
+    // Absolute address of the operand if it can be computed statically (pc relative or absolute)
+    pub target: Option<u64>,
+}
+
+impl MemoryOperand {
+    pub fn new(size: u16) -> MemoryOperand {
+        MemoryOperand {
+            base: None,
+            index: None,
+            scale: 1,
+            extend: None,
+            displacement: 0,
+            size,
+            segment: None,
+            mode: IndexMode::Offset,
+            subtract: false,
+            index_shift: None,
+            target: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(unused)]
+pub enum Operand {
+    Register(Register),
+    // Value is sign extended, size is in bytes
+    Immediate { value: i64, size: u8 },
+    Memory(MemoryOperand),
+    // An absolute code address, e.g. the target of a relative branch
+    Address(u64),
+    // AVX-512 opmask applied to the preceding operand
+    Mask { register: Register, zeroing: bool },
+    // Shift or extension applied to the preceding operand
+    Shift { kind: ShiftKind, amount: u8 },
+    // Shift applied to the preceding operand by the amount in a register (ARM)
+    RegisterShift { kind: ShiftKind, register: Register },
+    // Architecture specific condition code
+    Condition(u8),
+    // A vector register split into `elements` lanes of `element_bits` each, or a single lane if `lane` is set
+    Vector { register: Register, element_bits: u16, elements: u8, lane: Option<u8> },
+    // Register lists, e.g. {v0.16b, v1.16b}
+    List(Vec<Operand>),
+    // Named operands without register semantics, such as barrier options and prefetch operations
+    Name(&'static str),
+    // Floating point immediate, stored as the bits of an f64
+    Float(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKind {
+    Sequential,
+    Jump,
+    Call,
+    Return,
+    Syscall,
+    // Execution does not continue after this instruction (hlt, ud2, ...)
+    Trap,
+}
+
+/// Whether the instruction after a branch executes before the branch takes effect (MIPS, SPARC)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum DelaySlot {
+    None,
+    // The next instruction always executes
+    Always,
+    // The next instruction only executes if the branch is taken (branch likely), it is annulled otherwise
+    Taken,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub instruction_set: InstructionSet,
+    pub address: u64,
+    pub length: usize,
+    pub mnemonic: String,
+    pub operands: Vec<Operand>,
+    pub flow: FlowKind,
+    pub conditional: bool,
+    pub delay_slot: DelaySlot,
+}
+
+impl Instruction {
+    pub fn new(instruction_set: InstructionSet, address: u64, length: usize, mnemonic: String, operands: Vec<Operand>) -> Instruction {
+        Instruction {
+            instruction_set,
+            address,
+            length,
+            mnemonic,
+            operands,
+            flow: FlowKind::Sequential,
+            conditional: false,
+            delay_slot: DelaySlot::None,
+        }
+    }
+
+    #[allow(unused)]
+    pub fn next_address(&self) -> u64 {
+        self.address.wrapping_add(self.length as u64)
+    }
+
+    /// Address where execution continues after the instruction and its delay slot, if it has one
+    #[allow(unused)]
+    pub fn resume_address(&self) -> u64 {
+        match self.delay_slot {
+            DelaySlot::None => self.next_address(),
+            _ => self.next_address().wrapping_add(self.length as u64),
+        }
+    }
+
+    /// Statically known target of a jump or call
+    #[allow(unused)]
+    pub fn branch_target(&self) -> Option<u64> {
+        match self.flow {
+            FlowKind::Jump | FlowKind::Call => {}
+            _ => return None,
+        }
+
+        self.operands.iter().find_map(|x| match x {
+            Operand::Address(addr) => Some(*addr),
+            _ => None,
+        })
+    }
+
+    /// Whether execution can continue with the next instruction in memory
+    #[allow(unused)]
+    pub fn falls_through(&self) -> bool {
+        match self.flow {
+            FlowKind::Sequential | FlowKind::Call | FlowKind::Syscall => true,
+            FlowKind::Jump | FlowKind::Return => self.conditional,
+            FlowKind::Trap => false,
+        }
+    }
+
+    /// Addresses of memory operands that can be computed statically
+    #[allow(unused)]
+    pub fn memory_targets(&self) -> Vec<u64> {
+        self.operands
+            .iter()
+            .filter_map(|x| match x {
+                Operand::Memory(mem) => mem.target,
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::disasm::{MemoryOperand, Operand};
+
+use super::registers::ZERO;
+use super::{bits, imm, ins, integer, rs, rt, sign_extend, simm16, Context, Decoded};
+
+/// Release 6 compact branches, which reuse the opcodes of the removed branch likely and addi instructions
+pub fn branch(word: u32, c: &Context) -> Decoded {
+    let opcode = bits(word, 26, 6);
+    let (rs, rt) = (rs(word), rt(word));
+    let target = c.branch(simm16(word));
+
+    match opcode {
+        // blez and bgtz are still there, with $zero in rt
+        0x06 | 0x07 if rt == ZERO => integer::branch(word, c),
+        0x06 if rs == ZERO => ins("blezalc", vec![c.reg(rt), target]),
+        0x06 if rs == rt => ins("bgezalc", vec![c.reg(rt), target]),
+        0x06 => ins("bgeuc", vec![c.reg(rs), c.reg(rt), target]),
+        0x07 if rs == ZERO => ins("bgtzalc", vec![c.reg(rt), target]),
+        0x07 if rs == rt => ins("bltzalc", vec![c.reg(rt), target]),
+        0x07 => ins("bltuc", vec![c.reg(rs), c.reg(rt), target]),
+        // The order of the registers distinguishes the overflow tests from the comparisons
+        0x08 if rs >= rt => ins("bovc", vec![c.reg(rs), c.reg(rt), target]),
+        0x08 if rs == ZERO => ins("beqzalc", vec![c.reg(rt), target]),
+        0x08 => ins("beqc", vec![c.reg(rs), c.reg(rt), target]),
+        0x18 if rs >= rt => ins("bnvc", vec![c.reg(rs), c.reg(rt), target]),
+        0x18 if rs == ZERO => ins("bnezalc", vec![c.reg(rt), target]),
+        0x18 => ins("bnec", vec![c.reg(rs), c.reg(rt), target]),
+        0x16 | 0x17 if rt == ZERO => None,
+        0x16 if rs == ZERO => ins("blezc", vec![c.reg(rt), target]),
+        0x16 if rs == rt => ins("bgezc", vec![c.reg(rt), target]),
+        0x16 => ins("bgec", vec![c.reg(rs), c.reg(rt), target]),
+        0x17 if rs == ZERO => ins("bgtzc", vec![c.reg(rt), target]),
+        0x17 if rs == rt => ins("bltzc", vec![c.reg(rt), target]),
+        0x17 => ins("bltc", vec![c.reg(rs), c.reg(rt), target]),
+        0x32 | 0x3a => {
+            let target = c.branch(sign_extend(bits(word, 0, 26), 26));
+            ins(if opcode == 0x32 { "bc" } else { "balc" }, vec![target])
+        }
+        // With $zero in rs these jump to a register plus an offset
+        0x36 | 0x3e if rs == ZERO => {
+            // Written as jrc and jalrc without an offset
+            match (opcode == 0x36, simm16(word)) {
+                (true, 0) => ins("jrc", vec![c.reg(rt)]),
+                (false, 0) => ins("jalrc", vec![c.reg(rt)]),
+                (true, offset) => ins("jic", vec![c.reg(rt), imm(offset)]),
+                (false, offset) => ins("jialc", vec![c.reg(rt), imm(offset)]),
+            }
+        }
+        0x36 | 0x3e => {
+            let target = c.branch(sign_extend(bits(word, 0, 21), 21));
+            ins(if opcode == 0x36 { "beqzc" } else { "bnezc" }, vec![c.reg(rs), target])
+        }
+        _ => None,
+    }
+}
+
+/// Release 6 pc relative additions and loads
+pub fn pc_relative(word: u32, c: &Context) -> Decoded {
+    let rs = rs(word);
+    let offset19 = sign_extend(bits(word, 0, 19), 19) << 2;
+
+    match bits(word, 19, 2) {
+        0b00 => ins("addiupc", vec![c.reg(rs), imm(offset19)]),
+        0b01 => ins("lwpc", vec![c.reg(rs), pc_memory(c.address, offset19, 4)]),
+        0b10 if c.mips64 => ins("lwupc", vec![c.reg(rs), pc_memory(c.address, offset19, 4)]),
+        _ => match bits(word, 16, 5) {
+            0b11110 => ins("auipc", vec![c.reg(rs), imm(bits(word, 0, 16) as i64)]),
+            0b11111 => ins("aluipc", vec![c.reg(rs), imm(bits(word, 0, 16) as i64)]),
+            0b11000..=0b11011 if c.mips64 => {
+                // ldpc addresses doublewords relative to the aligned pc
+                let offset = sign_extend(bits(word, 0, 18), 18) << 3;
+                ins("ldpc", vec![c.reg(rs), pc_memory(c.address & !7, offset, 8)])
+            }
+            _ => None,
+        },
+    }
+}
+
+fn pc_memory(base: u64, offset: i64, size: u16) -> Operand {
+    let mut mem = MemoryOperand::new(size);
+    mem.displacement = offset;
+    mem.target = Some(base.wrapping_add(offset as u64));
+    Operand::Memory(mem)
+}
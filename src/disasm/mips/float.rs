@@ -0,0 +1,252 @@
+use crate::disasm::{MemoryOperand, Operand};
+
+use super::registers::{condition_code, fpr, gpr, numbered};
+use super::{bit, bits, imm, ins, rs, rt, simm16, Context, Decoded};
+
+// Conditions of c.cond.fmt, the fourth bit selects the signaling variant
+const CONDITIONS: [&str; 16] = [
+    "f", "un", "eq", "ueq", "olt", "ult", "ole", "ule", "sf", "ngle", "seq", "ngl", "lt", "nge", "le", "ngt",
+];
+
+// Conditions of the release 6 cmp.cond.fmt, empty where reserved
+const CMP_CONDITIONS: [&str; 32] = [
+    "af", "un", "eq", "ueq", "lt", "ult", "le", "ule", "saf", "sun", "seq", "sueq", "slt", "sult", "sle", "sule",
+    "", "or", "une", "ne", "", "", "", "", "", "sor", "sune", "sne", "", "", "", "",
+];
+
+// Formats of the rs field, with the register width they operate on
+fn format_of(fmt: u32) -> Option<(&'static str, u16)> {
+    Some(match fmt {
+        0x10 => ("s", 32),
+        0x11 => ("d", 64),
+        0x14 => ("w", 32),
+        0x15 => ("l", 64),
+        0x16 => ("ps", 64),
+        _ => return None,
+    })
+}
+
+fn ft(word: u32, width: u16) -> Operand {
+    Operand::Register(fpr(bits(word, 16, 5), width))
+}
+
+fn fs(word: u32, width: u16) -> Operand {
+    Operand::Register(fpr(bits(word, 11, 5), width))
+}
+
+fn fd(word: u32, width: u16) -> Operand {
+    Operand::Register(fpr(bits(word, 6, 5), width))
+}
+
+/// The COP1 opcode: moves between register files, branches on condition codes and arithmetic
+pub fn cop1(word: u32, c: &Context) -> Decoded {
+    let fmt = rs(word);
+    let r6 = c.release6;
+
+    match fmt {
+        0x00..=0x07 => {
+            if bits(word, 0, 11) != 0 || (matches!(fmt, 0x01 | 0x05) && !c.mips64) {
+                return None;
+            }
+            let mnemonic = ["mfc1", "dmfc1", "cfc1", "mfhc1", "mtc1", "dmtc1", "ctc1", "mthc1"][fmt as usize];
+            // The control registers are only known by number
+            let register = match fmt {
+                0x02 | 0x06 => Operand::Register(numbered(bits(word, 11, 5))),
+                0x01 | 0x05 => fs(word, 64),
+                _ => fs(word, 32),
+            };
+            ins(mnemonic, vec![c.reg(rt(word)), register])
+        }
+        0x08 if !r6 => {
+            let name = ["bc1f", "bc1t", "bc1fl", "bc1tl"][bits(word, 16, 2) as usize];
+            let mut operands = vec![];
+            let cc = bits(word, 18, 3);
+            if cc != 0 {
+                operands.push(Operand::Register(condition_code(cc)));
+            }
+            operands.push(c.branch(simm16(word)));
+            ins(name, operands)
+        }
+        0x09 | 0x0d if r6 => {
+            let mnemonic = if fmt == 0x09 { "bc1eqz" } else { "bc1nez" };
+            ins(mnemonic, vec![ft(word, 64), c.branch(simm16(word))])
+        }
+        0x14 | 0x15 if r6 && !bit(word, 5) => {
+            let cond = CMP_CONDITIONS[bits(word, 0, 5) as usize];
+            if cond.is_empty() {
+                return None;
+            }
+            let (suffix, width) = if fmt == 0x14 { ("s", 32) } else { ("d", 64) };
+            ins(&format!("cmp.{}.{}", cond, suffix), vec![fd(word, width), fs(word, width), ft(word, width)])
+        }
+        0x10 | 0x11 | 0x14 | 0x15 | 0x16 => arithmetic(word, c),
+        _ => None,
+    }
+}
+
+// Arithmetic, conversions and comparisons for one of the formats
+fn arithmetic(word: u32, c: &Context) -> Decoded {
+    let (format, width) = format_of(rs(word))?;
+    let funct = bits(word, 0, 6);
+    let r6 = c.release6;
+    let (float, paired, fixed) = (format == "s" || format == "d", format == "ps", format == "w" || format == "l");
+    if paired && r6 {
+        return None;
+    }
+    let name = |base: &str| format!("{}.{}", base, format);
+    // Unary operations have ft as zero
+    let unary = bits(word, 16, 5) == 0;
+
+    match funct {
+        0x00..=0x03 if !fixed => {
+            let base = ["add", "sub", "mul", "div"][funct as usize];
+            if paired && funct == 0x03 {
+                return None;
+            }
+            ins(&name(base), vec![fd(word, width), fs(word, width), ft(word, width)])
+        }
+        0x04..=0x07 if !fixed && unary => {
+            let base = ["sqrt", "abs", "mov", "neg"][funct as usize - 0x04];
+            if paired && funct == 0x04 {
+                return None;
+            }
+            ins(&name(base), vec![fd(word, width), fs(word, width)])
+        }
+        // Rounding to long and word integers
+        0x08..=0x0f if float && unary => {
+            let base = ["round", "trunc", "ceil", "floor"][(funct & 3) as usize];
+            let (target, target_width) = if funct < 0x0c { ("l", 64) } else { ("w", 32) };
+            ins(&format!("{}.{}.{}", base, target, format), vec![fd(word, target_width), fs(word, width)])
+        }
+        0x11 if !r6 && !fixed && !bit(word, 17) => {
+            let base = if bit(word, 16) { "movt" } else { "movf" };
+            ins(&name(base), vec![fd(word, width), fs(word, width), Operand::Register(condition_code(bits(word, 18, 3)))])
+        }
+        0x12 | 0x13 if !r6 && !fixed => {
+            let base = if funct == 0x12 { "movz" } else { "movn" };
+            ins(&name(base), vec![fd(word, width), fs(word, width), c.reg(rt(word))])
+        }
+        0x15 | 0x16 if float && unary => {
+            let base = if funct == 0x15 { "recip" } else { "rsqrt" };
+            ins(&name(base), vec![fd(word, width), fs(word, width)])
+        }
+        // Release 6 selects, fused multiply-add and min/max
+        0x10 | 0x14 | 0x17 | 0x18 | 0x19 | 0x1c..=0x1f if r6 && float => {
+            let base = match funct {
+                0x10 => "sel",
+                0x14 => "seleqz",
+                0x17 => "selnez",
+                0x18 => "maddf",
+                0x19 => "msubf",
+                0x1c => "min",
+                0x1d => "max",
+                0x1e => "mina",
+                _ => "maxa",
+            };
+            ins(&name(base), vec![fd(word, width), fs(word, width), ft(word, width)])
+        }
+        0x1a | 0x1b if r6 && float && unary => {
+            let base = if funct == 0x1a { "rint" } else { "class" };
+            ins(&name(base), vec![fd(word, width), fs(word, width)])
+        }
+        0x20 | 0x21 | 0x24 | 0x25 if unary => {
+            let (target, target_width) = match funct {
+                0x20 => ("s", 32),
+                0x21 => ("d", 64),
+                0x24 => ("w", 32),
+                _ => ("l", 64),
+            };
+            if target == format || (fixed && funct >= 0x24) {
+                return None;
+            }
+            // Paired singles only convert their upper half to a single
+            let source = if paired {
+                if funct != 0x20 {
+                    return None;
+                }
+                "pu"
+            } else {
+                format
+            };
+            ins(&format!("cvt.{}.{}", target, source), vec![fd(word, target_width), fs(word, width)])
+        }
+        0x26 if !r6 && format == "s" => ins("cvt.ps.s", vec![fd(word, 64), fs(word, 32), ft(word, 32)]),
+        0x28 if paired && unary => ins("cvt.s.pl", vec![fd(word, 32), fs(word, width)]),
+        0x2c..=0x2f if paired => {
+            let base = ["pll", "plu", "pul", "puu"][funct as usize - 0x2c];
+            ins(&name(base), vec![fd(word, width), fs(word, width), ft(word, width)])
+        }
+        0x30..=0x3f if !r6 && !fixed && bits(word, 6, 2) == 0 => {
+            let mut operands = vec![];
+            let cc = bits(word, 8, 3);
+            if cc != 0 {
+                operands.push(Operand::Register(condition_code(cc)));
+            }
+            operands.push(fs(word, width));
+            operands.push(ft(word, width));
+            ins(&name(&format!("c.{}", CONDITIONS[funct as usize - 0x30])), operands)
+        }
+        _ => None,
+    }
+}
+
+/// The COP1X opcode of MIPS IV: indexed loads and stores and fused multiply-add
+pub fn cop1x(word: u32, c: &Context) -> Decoded {
+    let funct = bits(word, 0, 6);
+    let (base, index) = (rs(word), rt(word));
+    let indexed = |size: u16| {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(gpr(base, c.mips64));
+        mem.index = Some(gpr(index, c.mips64));
+        Operand::Memory(mem)
+    };
+
+    match funct {
+        0x00 | 0x01 | 0x05 if bits(word, 11, 5) == 0 => {
+            let (mnemonic, size) = match funct {
+                0x00 => ("lwxc1", 4),
+                0x01 => ("ldxc1", 8),
+                _ => ("luxc1", 8),
+            };
+            ins(mnemonic, vec![fd(word, size * 8), indexed(size)])
+        }
+        0x08 | 0x09 | 0x0d if bits(word, 6, 5) == 0 => {
+            let (mnemonic, size) = match funct {
+                0x08 => ("swxc1", 4),
+                0x09 => ("sdxc1", 8),
+                _ => ("suxc1", 8),
+            };
+            ins(mnemonic, vec![fs(word, size * 8), indexed(size)])
+        }
+        0x0f if bits(word, 6, 5) == 0 => ins("prefx", vec![imm(bits(word, 11, 5) as i64), indexed(0)]),
+        0x1e => ins("alnv.ps", vec![fd(word, 64), fs(word, 64), ft(word, 64), c.reg(base)]),
+        0x20..=0x3f => {
+            let base = match funct >> 3 {
+                0b100 => "madd",
+                0b101 => "msub",
+                0b110 => "nmadd",
+                _ => "nmsub",
+            };
+            let (format, width) = match funct & 7 {
+                0 => ("s", 32),
+                1 => ("d", 64),
+                6 => ("ps", 64),
+                _ => return None,
+            };
+            let fr = Operand::Register(fpr(bits(word, 21, 5), width));
+            ins(&format!("{}.{}", base, format), vec![fd(word, width), fr, fs(word, width), ft(word, width)])
+        }
+        _ => None,
+    }
+}
+
+/// lwc1, ldc1, swc1 and sdc1
+pub fn load_store(word: u32, c: &Context) -> Decoded {
+    let (mnemonic, size) = match bits(word, 26, 6) {
+        0x31 => ("lwc1", 4),
+        0x35 => ("ldc1", 8),
+        0x39 => ("swc1", 4),
+        _ => ("sdc1", 8),
+    };
+    ins(mnemonic, vec![ft(word, size * 8), c.memory(rs(word), simm16(word), size)])
+}
@@ -0,0 +1,52 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma without a space, memory
+// operands are written as offset(base), logical and upper immediates and codes are hex
+
+pub fn format(instruction: &Instruction) -> String {
+    let mnemonic = &instruction.mnemonic;
+    let hex = hex_immediates(mnemonic);
+    let operands: Vec<String> = instruction.operands.iter().map(|x| operand(x, hex)).collect();
+
+    let mut res = mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(","));
+    }
+    // Addresses resolved through $gp or the pc are shown as a comment
+    if let Some(target) = instruction.memory_targets().first() {
+        res.push_str(&format!("        # 0x{:x}", target));
+    }
+    res
+}
+
+fn hex_immediates(mnemonic: &str) -> bool {
+    matches!(
+        mnemonic,
+        "lui" | "aui" | "daui" | "dahi" | "dati" | "andi" | "ori" | "xori" | "auipc" | "aluipc" | "cache" | "pref"
+            | "prefx" | "syscall" | "break" | "sdbbp" | "wait" | "sigrie" | "c2"
+            | "tge" | "tgeu" | "tlt" | "tltu" | "teq" | "tne"
+            | "ext" | "ins" | "dext" | "dins"
+    )
+}
+
+fn memory(mem: &MemoryOperand) -> String {
+    let base = mem.base.map(|x| x.name).unwrap_or_default();
+    match (mem.index, mem.base) {
+        (Some(index), _) => format!("{}({})", index.name, base),
+        // pc relative loads only have an offset
+        (None, None) => format!("{}", mem.displacement),
+        _ => format!("{}({})", mem.displacement, base),
+    }
+}
+
+fn operand(operand: &Operand, hex: bool) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, .. } if hex => format!("0x{:x}", value),
+        Operand::Immediate { value, .. } => format!("{}", value),
+        Operand::Memory(mem) => memory(mem),
+        Operand::Address(address) => format!("0x{:x}", address),
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,417 @@
+use crate::disasm::Operand;
+
+use super::registers::{condition_code, numbered, RA, ZERO};
+use super::{bit, bits, imm, ins, rd, rs, rt, sa, sign_extend, simm16, Context, Decoded};
+
+// Operations with three registers in SPECIAL, by function
+fn three_register(funct: u32) -> Option<&'static str> {
+    Some(match funct {
+        0x20 => "add",
+        0x21 => "addu",
+        0x22 => "sub",
+        0x23 => "subu",
+        0x24 => "and",
+        0x25 => "or",
+        0x26 => "xor",
+        0x27 => "nor",
+        0x2a => "slt",
+        0x2b => "sltu",
+        0x2c => "dadd",
+        0x2d => "daddu",
+        0x2e => "dsub",
+        0x2f => "dsubu",
+        _ => return None,
+    })
+}
+
+// Functions of SPECIAL which only exist on 64 bit processors
+fn is_64bit(funct: u32) -> bool {
+    matches!(funct, 0x14..=0x17 | 0x1c..=0x1f | 0x2c..=0x2f | 0x38..=0x3f)
+}
+
+/// The SPECIAL opcode: shifts, register operations, jumps through registers, multiplication and traps
+pub fn special(word: u32, c: &Context) -> Decoded {
+    let funct = bits(word, 0, 6);
+    let (rs, rt, rd, sa) = (rs(word), rt(word), rd(word), sa(word));
+    let r6 = c.release6;
+    if is_64bit(funct) && !c.mips64 {
+        return None;
+    }
+
+    match funct {
+        0x00 if rs == 0 && rd == 0 && rt == 0 => match sa {
+            0 => ins("nop", vec![]),
+            1 => ins("ssnop", vec![]),
+            3 => ins("ehb", vec![]),
+            5 => ins("pause", vec![]),
+            _ => ins("sll", vec![c.reg(rd), c.reg(rt), imm(sa as i64)]),
+        },
+        0x01 if !r6 && !bit(word, 17) && sa == 0 => {
+            let mnemonic = if bit(word, 16) { "movt" } else { "movf" };
+            ins(mnemonic, vec![c.reg(rd), c.reg(rs), Operand::Register(condition_code(bits(word, 18, 3)))])
+        }
+        // Shifts by an immediate, the rs field selects rotation
+        0x00 | 0x02 | 0x03 | 0x38 | 0x3a | 0x3b | 0x3c | 0x3e | 0x3f => {
+            let name = match (funct, rs) {
+                (0x00, 0) => "sll",
+                (0x02, 0) => "srl",
+                (0x02, 1) => "rotr",
+                (0x03, 0) => "sra",
+                (0x38, 0) => "dsll",
+                (0x3a, 0) => "dsrl",
+                (0x3a, 1) => "drotr",
+                (0x3b, 0) => "dsra",
+                (0x3c, 0) => "dsll32",
+                (0x3e, 0) => "dsrl32",
+                (0x3e, 1) => "drotr32",
+                (0x3f, 0) => "dsra32",
+                _ => return None,
+            };
+            ins(name, vec![c.reg(rd), c.reg(rt), imm(sa as i64)])
+        }
+        // Shifts by a register, the sa field selects rotation
+        0x04 | 0x06 | 0x07 | 0x14 | 0x16 | 0x17 => {
+            let name = match (funct, sa) {
+                (0x04, 0) => "sllv",
+                (0x06, 0) => "srlv",
+                (0x06, 1) => "rotrv",
+                (0x07, 0) => "srav",
+                (0x14, 0) => "dsllv",
+                (0x16, 0) => "dsrlv",
+                (0x16, 1) => "drotrv",
+                (0x17, 0) => "dsrav",
+                _ => return None,
+            };
+            ins(name, vec![c.reg(rd), c.reg(rt), c.reg(rs)])
+        }
+        0x05 | 0x15 if r6 && bits(word, 8, 3) == 0 => {
+            if funct == 0x15 && !c.mips64 {
+                return None;
+            }
+            let mnemonic = if funct == 0x05 { "lsa" } else { "dlsa" };
+            ins(mnemonic, vec![c.reg(rd), c.reg(rs), c.reg(rt), imm(bits(word, 6, 2) as i64 + 1)])
+        }
+        0x08 if !r6 && rt == 0 && rd == 0 && bits(word, 6, 4) == 0 => {
+            let mnemonic = if bit(word, 10) { "jr.hb" } else { "jr" };
+            ins(mnemonic, vec![c.reg(rs)])
+        }
+        0x09 if rt == 0 && bits(word, 6, 4) == 0 => {
+            // Release 6 writes jr as jalr with $zero as the link register
+            let hazard = if bit(word, 10) { ".hb" } else { "" };
+            match rd {
+                ZERO if r6 => ins(&format!("jr{}", hazard), vec![c.reg(rs)]),
+                RA => ins(&format!("jalr{}", hazard), vec![c.reg(rs)]),
+                _ => ins(&format!("jalr{}", hazard), vec![c.reg(rd), c.reg(rs)]),
+            }
+        }
+        0x0a | 0x0b if !r6 && sa == 0 => ins(if funct == 0x0a { "movz" } else { "movn" }, vec![c.reg(rd), c.reg(rs), c.reg(rt)]),
+        0x0c => code("syscall", bits(word, 6, 20)),
+        0x0d => {
+            // The code is split in two halves, the second one is usually 0
+            let (high, low) = (bits(word, 16, 10), bits(word, 6, 10));
+            match (high, low) {
+                (0, 0) => ins("break", vec![]),
+                (_, 0) => ins("break", vec![imm(high as i64)]),
+                _ => ins("break", vec![imm(high as i64), imm(low as i64)]),
+            }
+        }
+        0x0e if r6 => code("sdbbp", bits(word, 6, 20)),
+        0x0f if bits(word, 11, 15) == 0 => match sa {
+            0 => ins("sync", vec![]),
+            stype => ins("sync", vec![imm(stype as i64)]),
+        },
+        0x10 | 0x12 if !r6 && rs == 0 && rt == 0 && sa == 0 => ins(if funct == 0x10 { "mfhi" } else { "mflo" }, vec![c.reg(rd)]),
+        0x11 | 0x13 if !r6 && bits(word, 6, 15) == 0 => ins(if funct == 0x11 { "mthi" } else { "mtlo" }, vec![c.reg(rs)]),
+        0x10..=0x13 if r6 && rt == 0 && sa == 1 => {
+            if funct >= 0x12 && !c.mips64 {
+                return None;
+            }
+            let mnemonic = ["clz", "clo", "dclz", "dclo"][funct as usize - 0x10];
+            ins(mnemonic, vec![c.reg(rd), c.reg(rs)])
+        }
+        0x18..=0x1f if !r6 && rd == 0 && sa == 0 => {
+            // Results go to hi and lo, objdump shows the destination of divisions as $zero
+            let mnemonic = ["mult", "multu", "div", "divu", "dmult", "dmultu", "ddiv", "ddivu"][funct as usize - 0x18];
+            if mnemonic.contains("div") {
+                ins(mnemonic, vec![c.reg(ZERO), c.reg(rs), c.reg(rt)])
+            } else {
+                ins(mnemonic, vec![c.reg(rs), c.reg(rt)])
+            }
+        }
+        0x18..=0x1f if r6 && (sa == 2 || sa == 3) => {
+            const NAMES: [[&str; 2]; 8] = [
+                ["mul", "muh"], ["mulu", "muhu"], ["div", "mod"], ["divu", "modu"],
+                ["dmul", "dmuh"], ["dmulu", "dmuhu"], ["ddiv", "dmod"], ["ddivu", "dmodu"],
+            ];
+            let mnemonic = NAMES[funct as usize - 0x18][sa as usize - 2];
+            ins(mnemonic, vec![c.reg(rd), c.reg(rs), c.reg(rt)])
+        }
+        0x20..=0x2f if sa == 0 => {
+            let name = three_register(funct)?;
+            // Moves, negations and not are written as such
+            match (funct, rs, rt) {
+                (0x21, _, ZERO) | (0x25, _, ZERO) | (0x2d, _, ZERO) => ins("move", vec![c.reg(rd), c.reg(rs)]),
+                (0x22, ZERO, _) => ins("neg", vec![c.reg(rd), c.reg(rt)]),
+                (0x23, ZERO, _) => ins("negu", vec![c.reg(rd), c.reg(rt)]),
+                (0x2e, ZERO, _) => ins("dneg", vec![c.reg(rd), c.reg(rt)]),
+                (0x2f, ZERO, _) => ins("dnegu", vec![c.reg(rd), c.reg(rt)]),
+                (0x27, _, ZERO) => ins("not", vec![c.reg(rd), c.reg(rs)]),
+                _ => ins(name, vec![c.reg(rd), c.reg(rs), c.reg(rt)]),
+            }
+        }
+        0x30..=0x34 | 0x36 => {
+            let mnemonic = match funct {
+                0x30 => "tge",
+                0x31 => "tgeu",
+                0x32 => "tlt",
+                0x33 => "tltu",
+                0x34 => "teq",
+                _ => "tne",
+            };
+            let mut operands = vec![c.reg(rs), c.reg(rt)];
+            let code = bits(word, 6, 10);
+            if code != 0 {
+                operands.push(imm(code as i64));
+            }
+            ins(mnemonic, operands)
+        }
+        0x35 | 0x37 if r6 && sa == 0 => ins(if funct == 0x35 { "seleqz" } else { "selnez" }, vec![c.reg(rd), c.reg(rs), c.reg(rt)]),
+        _ => None,
+    }
+}
+
+// Instructions taking an optional code for the exception handler
+fn code(mnemonic: &str, value: u32) -> Decoded {
+    if value == 0 {
+        ins(mnemonic, vec![])
+    } else {
+        ins(mnemonic, vec![imm(value as i64)])
+    }
+}
+
+/// The REGIMM opcode: branches comparing with zero, trap immediates and synci
+pub fn regimm(word: u32, c: &Context) -> Decoded {
+    let (rs, op) = (rs(word), rt(word));
+    let r6 = c.release6;
+    let target = c.branch(simm16(word));
+    let value = imm(simm16(word));
+
+    match op {
+        0x00 => ins("bltz", vec![c.reg(rs), target]),
+        0x01 if rs == ZERO => ins("b", vec![target]),
+        0x01 => ins("bgez", vec![c.reg(rs), target]),
+        0x02 if !r6 => ins("bltzl", vec![c.reg(rs), target]),
+        0x03 if !r6 => ins("bgezl", vec![c.reg(rs), target]),
+        0x06 if r6 && c.mips64 && rs != 0 => ins("dahi", vec![c.reg(rs), imm(bits(word, 0, 16) as i64)]),
+        0x08..=0x0c | 0x0e if !r6 => {
+            let mnemonic = ["tgei", "tgeiu", "tlti", "tltiu", "teqi", "", "tnei"][op as usize - 0x08];
+            ins(mnemonic, vec![c.reg(rs), value])
+        }
+        0x10 if !r6 => ins("bltzal", vec![c.reg(rs), target]),
+        0x11 if rs == ZERO => ins("bal", vec![target]),
+        0x11 if !r6 => ins("bgezal", vec![c.reg(rs), target]),
+        0x12 if !r6 => ins("bltzall", vec![c.reg(rs), target]),
+        0x13 if !r6 => ins("bgezall", vec![c.reg(rs), target]),
+        0x17 if r6 && rs == 0 => code("sigrie", bits(word, 0, 16)),
+        0x1e if r6 && c.mips64 && rs != 0 => ins("dati", vec![c.reg(rs), imm(bits(word, 0, 16) as i64)]),
+        0x1f => ins("synci", vec![c.memory(rs, simm16(word), 0)]),
+        _ => None,
+    }
+}
+
+/// j, jal and jalx, which jump within the current 256 MiB region
+pub fn jump(word: u32, c: &Context) -> Decoded {
+    let target = c.region(bits(word, 0, 26));
+    match bits(word, 26, 6) {
+        0x02 => ins("j", vec![target]),
+        0x03 => ins("jal", vec![target]),
+        _ => ins("jalx", vec![target]),
+    }
+}
+
+/// Branches comparing two registers or one register with zero, and their likely variants
+pub fn branch(word: u32, c: &Context) -> Decoded {
+    let (rs, rt) = (rs(word), rt(word));
+    let target = c.branch(simm16(word));
+    let opcode = bits(word, 26, 6);
+    // The likely variants are at the same position 16 opcodes up
+    let likely = if opcode >= 0x14 { "l" } else { "" };
+
+    match opcode & 0b11 {
+        0b00 if rs == ZERO && rt == ZERO && likely.is_empty() => ins("b", vec![target]),
+        0b00 | 0b01 if rt == ZERO => {
+            let name = if opcode & 1 == 0 { "beqz" } else { "bnez" };
+            ins(&format!("{}{}", name, likely), vec![c.reg(rs), target])
+        }
+        0b00 => ins(&format!("beq{}", likely), vec![c.reg(rs), c.reg(rt), target]),
+        0b01 => ins(&format!("bne{}", likely), vec![c.reg(rs), c.reg(rt), target]),
+        _ if rt != ZERO => None,
+        0b10 => ins(&format!("blez{}", likely), vec![c.reg(rs), target]),
+        _ => ins(&format!("bgtz{}", likely), vec![c.reg(rs), target]),
+    }
+}
+
+/// Arithmetic and logic with a 16 bit immediate
+pub fn immediate(word: u32, c: &Context) -> Decoded {
+    let (rs, rt) = (rs(word), rt(word));
+    let (signed, unsigned) = (imm(simm16(word)), imm(bits(word, 0, 16) as i64));
+
+    match bits(word, 26, 6) {
+        0x08 => ins("addi", vec![c.reg(rt), c.reg(rs), signed]),
+        0x09 if rs == ZERO => ins("li", vec![c.reg(rt), signed]),
+        0x09 => ins("addiu", vec![c.reg(rt), c.reg(rs), signed]),
+        0x0a => ins("slti", vec![c.reg(rt), c.reg(rs), signed]),
+        0x0b => ins("sltiu", vec![c.reg(rt), c.reg(rs), signed]),
+        0x0c => ins("andi", vec![c.reg(rt), c.reg(rs), unsigned]),
+        0x0d => ins("ori", vec![c.reg(rt), c.reg(rs), unsigned]),
+        0x0e => ins("xori", vec![c.reg(rt), c.reg(rs), unsigned]),
+        0x0f if rs == ZERO => ins("lui", vec![c.reg(rt), unsigned]),
+        0x0f if c.release6 => ins("aui", vec![c.reg(rt), c.reg(rs), unsigned]),
+        0x18 => ins("daddi", vec![c.reg(rt), c.reg(rs), signed]),
+        0x19 => ins("daddiu", vec![c.reg(rt), c.reg(rs), signed]),
+        0x1d if rs != ZERO => ins("daui", vec![c.reg(rt), c.reg(rs), unsigned]),
+        _ => None,
+    }
+}
+
+/// The SPECIAL2 opcode of MIPS32 and MIPS64 before release 6
+pub fn special2(word: u32, c: &Context) -> Decoded {
+    let (rs, rt, rd) = (rs(word), rt(word), rd(word));
+    let funct = bits(word, 0, 6);
+    if funct != 0x3f && sa(word) != 0 {
+        return None;
+    }
+
+    match funct {
+        0x00 | 0x01 | 0x04 | 0x05 if rd == 0 => {
+            let mnemonic = ["madd", "maddu", "", "", "msub", "msubu"][funct as usize];
+            ins(mnemonic, vec![c.reg(rs), c.reg(rt)])
+        }
+        0x02 => ins("mul", vec![c.reg(rd), c.reg(rs), c.reg(rt)]),
+        // Count leading zeros or ones, rt has to be the same as rd
+        0x20 | 0x21 | 0x24 | 0x25 if rt == rd => {
+            if funct >= 0x24 && !c.mips64 {
+                return None;
+            }
+            let mnemonic = match funct {
+                0x20 => "clz",
+                0x21 => "clo",
+                0x24 => "dclz",
+                _ => "dclo",
+            };
+            ins(mnemonic, vec![c.reg(rd), c.reg(rs)])
+        }
+        0x3f => code("sdbbp", bits(word, 6, 20)),
+        _ => None,
+    }
+}
+
+/// The SPECIAL3 opcode: bit field operations, byte swaps, rdhwr and the release 6 atomics
+pub fn special3(word: u32, c: &Context) -> Decoded {
+    let (rs, rt, rd) = (rs(word), rt(word), rd(word));
+    let (msb, lsb) = (rd, sa(word));
+    let funct = bits(word, 0, 6);
+    let r6 = c.release6;
+
+    match funct {
+        // Extract: the field is lsb and size, positions past 32 use separate opcodes which objdump also shows as dext
+        0x00 => ins("ext", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm(msb as i64 + 1)]),
+        0x01 if c.mips64 => ins("dext", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm(msb as i64 + 33)]),
+        0x02 if c.mips64 => ins("dext", vec![c.reg(rt), c.reg(rs), imm(lsb as i64 + 32), imm(msb as i64 + 1)]),
+        0x03 if c.mips64 => ins("dext", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm(msb as i64 + 1)]),
+        // Insert: the field is given by its most significant bit instead of the size
+        0x04 if msb >= lsb => ins("ins", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm((msb - lsb) as i64 + 1)]),
+        0x05 if c.mips64 && msb + 32 >= lsb => {
+            ins("dins", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm((msb + 32 - lsb) as i64 + 1)])
+        }
+        0x06 if c.mips64 && msb >= lsb => {
+            ins("dins", vec![c.reg(rt), c.reg(rs), imm(lsb as i64 + 32), imm((msb - lsb) as i64 + 1)])
+        }
+        0x07 if c.mips64 && msb >= lsb => ins("dins", vec![c.reg(rt), c.reg(rs), imm(lsb as i64), imm((msb - lsb) as i64 + 1)]),
+        0x20 => byte_shuffle(word, c, false),
+        0x24 if c.mips64 => byte_shuffle(word, c, true),
+        0x3b if rs == 0 && (r6 || bits(word, 6, 5) == 0) => {
+            let mut operands = vec![c.reg(rt), Operand::Register(numbered(rd))];
+            let select = bits(word, 6, 3);
+            if select != 0 {
+                operands.push(imm(select as i64));
+            }
+            ins("rdhwr", operands)
+        }
+        // Release 6 moved these here, with a 9 bit offset
+        0x25 | 0x26 | 0x27 | 0x35 | 0x36 | 0x37 if r6 && !bit(word, 6) => {
+            let offset = sign_extend(bits(word, 7, 9), 9);
+            match funct {
+                0x25 => ins("cache", vec![imm(rt as i64), c.memory(rs, offset, 0)]),
+                0x35 => ins("pref", vec![imm(rt as i64), c.memory(rs, offset, 0)]),
+                0x26 => ins("sc", vec![c.reg(rt), c.memory(rs, offset, 4)]),
+                0x36 => ins("ll", vec![c.reg(rt), c.memory(rs, offset, 4)]),
+                0x27 if c.mips64 => ins("scd", vec![c.reg(rt), c.memory(rs, offset, 8)]),
+                0x37 if c.mips64 => ins("lld", vec![c.reg(rt), c.memory(rs, offset, 8)]),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// BSHFL and DBSHFL: byte swaps, sign extensions and the release 6 align and bitswap
+fn byte_shuffle(word: u32, c: &Context, double: bool) -> Decoded {
+    let (rs, rt, rd) = (rs(word), rt(word), rd(word));
+    let op = sa(word);
+    let prefix = if double { "d" } else { "" };
+
+    match op {
+        0x00 if c.release6 && rs == 0 => ins(&format!("{}bitswap", prefix), vec![c.reg(rd), c.reg(rt)]),
+        0x02 if rs == 0 => ins(if double { "dsbh" } else { "wsbh" }, vec![c.reg(rd), c.reg(rt)]),
+        0x05 if double && rs == 0 => ins("dshd", vec![c.reg(rd), c.reg(rt)]),
+        0x10 | 0x18 if !double && rs == 0 => ins(if op == 0x10 { "seb" } else { "seh" }, vec![c.reg(rd), c.reg(rt)]),
+        0x08..=0x0b if c.release6 && !double => ins("align", vec![c.reg(rd), c.reg(rs), c.reg(rt), imm(bits(op, 0, 2) as i64)]),
+        0x08..=0x0f if c.release6 && double => ins("dalign", vec![c.reg(rd), c.reg(rs), c.reg(rt), imm(bits(op, 0, 3) as i64)]),
+        _ => None,
+    }
+}
+
+/// Integer loads and stores with a 16 bit displacement
+pub fn load_store(word: u32, c: &Context) -> Decoded {
+    let opcode = bits(word, 26, 6);
+    let (base, rt) = (rs(word), rt(word));
+    let r6 = c.release6;
+
+    let (mnemonic, size, wide) = match opcode {
+        0x1a => ("ldl", 8, true),
+        0x1b => ("ldr", 8, true),
+        0x20 => ("lb", 1, false),
+        0x21 => ("lh", 2, false),
+        0x22 if !r6 => ("lwl", 4, false),
+        0x23 => ("lw", 4, false),
+        0x24 => ("lbu", 1, false),
+        0x25 => ("lhu", 2, false),
+        0x26 if !r6 => ("lwr", 4, false),
+        0x27 => ("lwu", 4, true),
+        0x28 => ("sb", 1, false),
+        0x29 => ("sh", 2, false),
+        0x2a if !r6 => ("swl", 4, false),
+        0x2b => ("sw", 4, false),
+        0x2c if !r6 => ("sdl", 8, true),
+        0x2d if !r6 => ("sdr", 8, true),
+        0x2e if !r6 => ("swr", 4, false),
+        0x30 => ("ll", 4, false),
+        0x34 => ("lld", 8, true),
+        0x37 => ("ld", 8, true),
+        0x38 => ("sc", 4, false),
+        0x3c => ("scd", 8, true),
+        0x3f => ("sd", 8, true),
+        _ => return None,
+    };
+    if wide && !c.mips64 {
+        return None;
+    }
+    ins(mnemonic, vec![c.reg(rt), c.memory(base, simm16(word), size)])
+}
+
+/// cache and pref before release 6, whose first operand is an operation rather than a register
+pub fn cache_prefetch(word: u32, c: &Context) -> Decoded {
+    let mnemonic = if bits(word, 26, 6) == 0x2f { "cache" } else { "pref" };
+    ins(mnemonic, vec![imm(rt(word) as i64), c.memory(rs(word), simm16(word), 0)])
+}
@@ -0,0 +1,276 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, DelaySlot, FlowKind, Instruction, MemoryOperand, Operand};
+
+mod registers;
+use self::registers::{gpr, GP, RA};
+
+mod integer;
+mod compact;
+mod float;
+mod system;
+
+mod format;
+pub use self::format::format;
+
+const LENGTH: usize = 4;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is reserved or not supported
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// Decoder for MIPS I to MIPS V and MIPS32/MIPS64 up to release 6, with the FPU
+#[derive(Debug, Clone)]
+pub struct MipsDecoder {
+    big_endian: bool,
+    // The 64 bit instructions and register names of n32 and n64
+    mips64: bool,
+    // Release 6 reassigned the encodings of many removed instructions
+    release6: bool,
+    // Value of $gp, to resolve accesses to the .got and small data sections
+    gp: Option<u64>,
+}
+
+impl MipsDecoder {
+    pub fn new(big_endian: bool, mips64: bool, release6: bool, gp: Option<u64>) -> MipsDecoder {
+        MipsDecoder {
+            big_endian,
+            mips64,
+            release6,
+            gp,
+        }
+    }
+}
+
+// What the decoding functions need besides the instruction word
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+    mips64: bool,
+    release6: bool,
+    gp: Option<u64>,
+}
+
+impl Context {
+    fn reg(&self, number: u32) -> Operand {
+        Operand::Register(gpr(number, self.mips64))
+    }
+
+    // A memory access of `size` bytes at base + displacement, whose address is known when the base is $gp
+    fn memory(&self, base: u32, displacement: i64, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(gpr(base, self.mips64));
+        mem.displacement = displacement;
+        if base == GP {
+            mem.target = self.gp.map(|gp| gp.wrapping_add(displacement as u64));
+        }
+        Operand::Memory(mem)
+    }
+
+    // Target of a pc relative branch, the offset counts instructions from the delay slot
+    fn branch(&self, offset: i64) -> Operand {
+        let target = self.address.wrapping_add(4).wrapping_add((offset << 2) as u64);
+        Operand::Address(if self.mips64 { target } else { target & 0xffff_ffff })
+    }
+
+    // Target of j and jal, within the 256 MiB region of the delay slot
+    fn region(&self, index: u32) -> Operand {
+        let region = self.address.wrapping_add(4) & !0x0fff_ffff;
+        Operand::Address(region | (index as u64) << 2)
+    }
+}
+
+impl Decoder for MipsDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let raw: [u8; LENGTH] = bytes[..LENGTH].try_into().unwrap();
+        let word = if self.big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) };
+
+        let context = Context {
+            address,
+            mips64: self.mips64,
+            release6: self.release6,
+            gp: self.gp,
+        };
+        let (mnemonic, operands) = decode_word(word, &context).ok_or(DecodeError::InvalidOpcode(word))?;
+
+        let mut instruction = Instruction::new(InstructionSet::MIPS, address, LENGTH, String::new(), operands);
+        let (flow, conditional, delay_slot) = flow_of(&mnemonic, &instruction.operands);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.delay_slot = delay_slot;
+        instruction.mnemonic = mnemonic;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        LENGTH
+    }
+}
+
+fn decode_word(word: u32, c: &Context) -> Decoded {
+    let r6 = c.release6;
+    match bits(word, 26, 6) {
+        0x00 => integer::special(word, c),
+        0x01 => integer::regimm(word, c),
+        0x02 | 0x03 => integer::jump(word, c),
+        0x04..=0x07 if !r6 => integer::branch(word, c),
+        0x04 | 0x05 => integer::branch(word, c),
+        0x06 | 0x07 | 0x08 | 0x16 | 0x17 | 0x18 if r6 => compact::branch(word, c),
+        0x08..=0x0f => integer::immediate(word, c),
+        0x10 => system::cop0(word, c),
+        0x11 => float::cop1(word, c),
+        0x12 => system::cop2(word, c),
+        0x13 if !r6 => float::cop1x(word, c),
+        0x14..=0x17 if !r6 => integer::branch(word, c),
+        0x18 | 0x19 if c.mips64 => integer::immediate(word, c),
+        0x1a | 0x1b if c.mips64 && !r6 => integer::load_store(word, c),
+        0x1c if !r6 => integer::special2(word, c),
+        0x1d if r6 && c.mips64 => integer::immediate(word, c),
+        0x1d if !r6 => integer::jump(word, c),
+        0x1f => integer::special3(word, c),
+        0x20..=0x2e | 0x37 | 0x3f => integer::load_store(word, c),
+        0x30 | 0x34 | 0x38 | 0x3c if !r6 => integer::load_store(word, c),
+        0x2f | 0x33 if !r6 => integer::cache_prefetch(word, c),
+        0x32 | 0x36 | 0x3a | 0x3e if r6 => compact::branch(word, c),
+        0x32 | 0x36 | 0x3a | 0x3e => system::cop2_load_store(word, c),
+        0x3b if r6 => compact::pc_relative(word, c),
+        0x31 | 0x35 | 0x39 | 0x3d => float::load_store(word, c),
+        _ => None,
+    }
+}
+
+fn flow_of(mnemonic: &str, operands: &[Operand]) -> (FlowKind, bool, DelaySlot) {
+    use DelaySlot::{Always, Taken};
+
+    let returns = matches!(operands.first(), Some(Operand::Register(register)) if register.number as u32 == RA);
+    match mnemonic {
+        "syscall" => (FlowKind::Syscall, false, DelaySlot::None),
+        "break" | "sdbbp" | "sigrie" => (FlowKind::Trap, false, DelaySlot::None),
+        "eret" | "eretnc" | "deret" => (FlowKind::Return, false, DelaySlot::None),
+        "jr" | "jr.hb" if returns => (FlowKind::Return, false, Always),
+        "j" | "b" | "jr" | "jr.hb" => (FlowKind::Jump, false, Always),
+        "jal" | "jalx" | "bal" | "jalr" | "jalr.hb" => (FlowKind::Call, false, Always),
+        "bltzal" | "bgezal" => (FlowKind::Call, true, Always),
+        "bltzall" | "bgezall" => (FlowKind::Call, true, Taken),
+        "beql" | "bnel" | "blezl" | "bgtzl" | "bltzl" | "bgezl" | "beqzl" | "bnezl" | "bc1tl" | "bc1fl" | "bc2tl"
+        | "bc2fl" => (FlowKind::Jump, true, Taken),
+        "beq" | "bne" | "blez" | "bgtz" | "bltz" | "bgez" | "beqz" | "bnez" | "bc1t" | "bc1f" | "bc1eqz" | "bc1nez"
+        | "bc2t" | "bc2f" | "bc2eqz" | "bc2nez" => (FlowKind::Jump, true, Always),
+        // Release 6 compact branches have a forbidden slot instead of a delay slot
+        "jrc" if returns => (FlowKind::Return, false, DelaySlot::None),
+        "bc" | "jic" | "jrc" => (FlowKind::Jump, false, DelaySlot::None),
+        "balc" | "jialc" | "jalrc" => (FlowKind::Call, false, DelaySlot::None),
+        "beqzalc" | "bnezalc" | "blezalc" | "bgezalc" | "bgtzalc" | "bltzalc" => (FlowKind::Call, true, DelaySlot::None),
+        "beqzc" | "bnezc" | "beqc" | "bnec" | "bltc" | "bgec" | "bltuc" | "bgeuc" | "bltzc" | "blezc" | "bgezc"
+        | "bgtzc" | "bovc" | "bnvc" => (FlowKind::Jump, true, DelaySlot::None),
+        _ => (FlowKind::Sequential, false, DelaySlot::None),
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+fn rs(word: u32) -> u32 {
+    bits(word, 21, 5)
+}
+
+fn rt(word: u32) -> u32 {
+    bits(word, 16, 5)
+}
+
+fn rd(word: u32) -> u32 {
+    bits(word, 11, 5)
+}
+
+fn sa(word: u32) -> u32 {
+    bits(word, 6, 5)
+}
+
+// The sign extended 16 bit immediate of I-type instructions
+fn simm16(word: u32) -> i64 {
+    sign_extend(bits(word, 0, 16), 16)
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MipsDecoder;
+    use crate::disasm::{DelaySlot, Decoder, FlowKind, Instruction, Syntax};
+
+    fn decode(decoder: &MipsDecoder, word: u32, address: u64) -> Instruction {
+        let bytes = if decoder.big_endian { word.to_be_bytes() } else { word.to_le_bytes() };
+        decoder.decode(&bytes, address).unwrap()
+    }
+
+    #[test]
+    fn delay_slots() {
+        let decoder = MipsDecoder::new(true, false, false, None);
+        let cases = [
+            (0x27bdffe0, "addiu sp,sp,-32", FlowKind::Sequential, false, DelaySlot::None),
+            (0x0c000100, "jal 0x400", FlowKind::Call, false, DelaySlot::Always),
+            (0x0320f809, "jalr t9", FlowKind::Call, false, DelaySlot::Always),
+            (0x10800004, "beqz a0,0x400018", FlowKind::Jump, true, DelaySlot::Always),
+            // Branch likely annuls its delay slot when not taken
+            (0x50850004, "beql a0,a1,0x400018", FlowKind::Jump, true, DelaySlot::Taken),
+            (0x03e00008, "jr ra", FlowKind::Return, false, DelaySlot::Always),
+            (0x0000000c, "syscall", FlowKind::Syscall, false, DelaySlot::None),
+        ];
+        for &(word, text, flow, conditional, delay_slot) in cases.iter() {
+            let instruction = decode(&decoder, word, 0x400004);
+            assert_eq!(instruction.render(Syntax::Intel), text);
+            assert_eq!((instruction.flow, instruction.conditional, instruction.delay_slot), (flow, conditional, delay_slot), "{}", text);
+        }
+    }
+
+    #[test]
+    fn release6_compact_branches_have_no_delay_slot() {
+        let decoder = MipsDecoder::new(true, false, true, None);
+        let bc = decode(&decoder, 0xc8000004, 0x1000);
+        assert_eq!((bc.render(Syntax::Intel).as_str(), bc.delay_slot), ("bc 0x1014", DelaySlot::None));
+        let beqzc = decode(&decoder, 0xd8800004, 0x1000);
+        assert_eq!((beqzc.render(Syntax::Intel).as_str(), beqzc.conditional), ("beqzc a0,0x1014", true));
+        let jrc = decode(&decoder, 0xd81f0000, 0x1000);
+        assert_eq!((jrc.render(Syntax::Intel).as_str(), jrc.flow, jrc.delay_slot), ("jrc ra", FlowKind::Return, DelaySlot::None));
+        assert_eq!(decode(&decoder, 0xd8190010, 0x1000).render(Syntax::Intel), "jic t9,16");
+        // Branch likely is gone in release 6, its opcodes either hold compact branches or are reserved
+        assert_eq!(decode(&decoder, 0x58850004, 0x1000).render(Syntax::Intel), "bgec a0,a1,0x1014");
+        assert!(decoder.decode(&0x50850004u32.to_be_bytes(), 0x1000).is_err());
+    }
+
+    #[test]
+    fn byte_order_and_width() {
+        let little = MipsDecoder::new(false, false, false, None);
+        assert_eq!(little.decode(&[0xe0, 0xff, 0xbd, 0x27], 0).unwrap().render(Syntax::Intel), "addiu sp,sp,-32");
+        let mips64 = MipsDecoder::new(true, true, false, None);
+        assert_eq!(decode(&mips64, 0xdfa40008, 0).render(Syntax::Intel), "ld a0,8(sp)");
+        assert_eq!(decode(&mips64, 0x67bdfff0, 0).render(Syntax::Intel), "daddiu sp,sp,-16");
+        assert!(MipsDecoder::new(true, false, false, None).decode(&0xdfa40008u32.to_be_bytes(), 0).is_err());
+    }
+
+    #[test]
+    fn gp_relative_loads() {
+        let decoder = MipsDecoder::new(true, false, false, Some(0x418010));
+        assert_eq!(decode(&decoder, 0x8f998010, 0x400000).render(Syntax::Intel), "lw t9,-32752(gp)        # 0x410020");
+    }
+}
@@ -0,0 +1,65 @@
+use crate::disasm::{Register, RegisterClass};
+
+// GNU names for o32, where $8 to $15 are temporaries
+const OLD_ABI: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3",
+    "t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7",
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7",
+    "t8", "t9", "k0", "k1", "gp", "sp", "s8", "ra",
+];
+
+// n32 and n64 pass eight arguments in registers, which takes four of the temporaries
+const NEW_ABI: [&str; 32] = [
+    "zero", "at", "v0", "v1", "a0", "a1", "a2", "a3",
+    "a4", "a5", "a6", "a7", "t0", "t1", "t2", "t3",
+    "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7",
+    "t8", "t9", "k0", "k1", "gp", "sp", "s8", "ra",
+];
+
+const F: [&str; 32] = [
+    "$f0", "$f1", "$f2", "$f3", "$f4", "$f5", "$f6", "$f7",
+    "$f8", "$f9", "$f10", "$f11", "$f12", "$f13", "$f14", "$f15",
+    "$f16", "$f17", "$f18", "$f19", "$f20", "$f21", "$f22", "$f23",
+    "$f24", "$f25", "$f26", "$f27", "$f28", "$f29", "$f30", "$f31",
+];
+
+// Coprocessor, floating point control and hardware registers are shown by number
+const NUMBERED: [&str; 32] = [
+    "$0", "$1", "$2", "$3", "$4", "$5", "$6", "$7",
+    "$8", "$9", "$10", "$11", "$12", "$13", "$14", "$15",
+    "$16", "$17", "$18", "$19", "$20", "$21", "$22", "$23",
+    "$24", "$25", "$26", "$27", "$28", "$29", "$30", "$31",
+];
+
+// Floating point condition codes of MIPS IV and later
+const CC: [&str; 8] = ["$fcc0", "$fcc1", "$fcc2", "$fcc3", "$fcc4", "$fcc5", "$fcc6", "$fcc7"];
+
+pub const ZERO: u32 = 0;
+pub const GP: u32 = 28;
+pub const RA: u32 = 31;
+
+/// A general purpose register, $0 being hardwired to zero. The 64 bit ABIs have different names.
+pub fn gpr(number: u32, mips64: bool) -> Register {
+    let number = number & 31;
+    let class = if number == ZERO { RegisterClass::Zero } else { RegisterClass::General };
+    let (bits, names) = if mips64 { (64, &NEW_ABI) } else { (32, &OLD_ABI) };
+    Register::new(class, number as u16, bits, names[number as usize])
+}
+
+/// A floating point register, holding a value of `bits` bits
+pub fn fpr(number: u32, bits: u16) -> Register {
+    let number = number & 31;
+    Register::new(RegisterClass::Float, number as u16, bits, F[number as usize])
+}
+
+/// A register of a coprocessor, the floating point control registers or rdhwr
+pub fn numbered(number: u32) -> Register {
+    let number = number & 31;
+    Register::new(RegisterClass::Special, number as u16, 32, NUMBERED[number as usize])
+}
+
+/// A floating point condition code
+pub fn condition_code(number: u32) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::Flags, number as u16, 1, CC[number as usize])
+}
@@ -0,0 +1,124 @@
+use crate::disasm::Operand;
+
+use super::registers::{condition_code, numbered};
+use super::{bit, bits, imm, ins, rd, rs, rt, sign_extend, simm16, Context, Decoded};
+
+/// The COP0 opcode: system control registers, TLB maintenance and exception returns
+pub fn cop0(word: u32, c: &Context) -> Decoded {
+    let (op, rt, rd) = (rs(word), rt(word), rd(word));
+
+    match op {
+        0x00 | 0x01 | 0x04 | 0x05 if bits(word, 3, 8) == 0 => {
+            if op & 1 == 1 && !c.mips64 {
+                return None;
+            }
+            let mnemonic = ["mfc0", "dmfc0", "", "", "mtc0", "dmtc0"][op as usize];
+            let mut operands = vec![c.reg(rt), Operand::Register(numbered(rd))];
+            let select = bits(word, 0, 3);
+            if select != 0 {
+                operands.push(imm(select as i64));
+            }
+            ins(mnemonic, operands)
+        }
+        // Moves between the current and the previous shadow register set
+        0x0a | 0x0e if bits(word, 0, 11) == 0 => {
+            let mnemonic = if op == 0x0a { "rdpgpr" } else { "wrpgpr" };
+            ins(mnemonic, vec![c.reg(rd), c.reg(rt)])
+        }
+        0x0b if rd == 12 && bits(word, 0, 5) == 0 && bits(word, 6, 5) == 0 => {
+            let mnemonic = if bit(word, 5) { "ei" } else { "di" };
+            if rt == 0 {
+                ins(mnemonic, vec![])
+            } else {
+                ins(mnemonic, vec![c.reg(rt)])
+            }
+        }
+        0x10..=0x1f => {
+            let funct = bits(word, 0, 6);
+            // wait keeps an implementation defined code in the other bits
+            if funct == 0x20 {
+                let code = bits(word, 6, 19);
+                return if code == 0 { ins("wait", vec![]) } else { ins("wait", vec![imm(code as i64)]) };
+            }
+            if bits(word, 6, 19) != 0 && !(funct == 0x18 && bits(word, 6, 19) == 1) {
+                return None;
+            }
+            let mnemonic = match funct {
+                0x01 => "tlbr",
+                0x02 => "tlbwi",
+                0x03 => "tlbinv",
+                0x04 => "tlbinvf",
+                0x06 => "tlbwr",
+                0x08 => "tlbp",
+                0x18 if bit(word, 6) => "eretnc",
+                0x18 => "eret",
+                0x1f => "deret",
+                _ => return None,
+            };
+            ins(mnemonic, vec![])
+        }
+        _ => None,
+    }
+}
+
+/// The COP2 opcode, whose operations are implementation defined
+pub fn cop2(word: u32, c: &Context) -> Decoded {
+    let (op, rt, rd) = (rs(word), rt(word), rd(word));
+    let r6 = c.release6;
+
+    match op {
+        0x00..=0x07 if bits(word, 3, 8) == 0 => {
+            if matches!(op, 0x01 | 0x05) && !c.mips64 {
+                return None;
+            }
+            let mnemonic = ["mfc2", "dmfc2", "cfc2", "mfhc2", "mtc2", "dmtc2", "ctc2", "mthc2"][op as usize];
+            let mut operands = vec![c.reg(rt), Operand::Register(numbered(rd))];
+            // Only the moves to and from data registers take a select
+            let select = bits(word, 0, 3);
+            match op {
+                0x00 | 0x01 | 0x04 | 0x05 if select != 0 => operands.push(imm(select as i64)),
+                _ if select != 0 => return None,
+                _ => {}
+            }
+            ins(mnemonic, operands)
+        }
+        0x08 if !r6 => {
+            let name = ["bc2f", "bc2t", "bc2fl", "bc2tl"][bits(word, 16, 2) as usize];
+            let mut operands = vec![];
+            let cc = bits(word, 18, 3);
+            if cc != 0 {
+                operands.push(Operand::Register(condition_code(cc)));
+            }
+            operands.push(c.branch(simm16(word)));
+            ins(name, operands)
+        }
+        0x09 | 0x0d if r6 => {
+            let mnemonic = if op == 0x09 { "bc2eqz" } else { "bc2nez" };
+            ins(mnemonic, vec![Operand::Register(numbered(rt)), c.branch(simm16(word))])
+        }
+        // Release 6 moved the coprocessor 2 loads and stores here, with an 11 bit offset
+        0x0a | 0x0b | 0x0e | 0x0f if r6 => {
+            let (mnemonic, size) = match op {
+                0x0a => ("lwc2", 4),
+                0x0b => ("swc2", 4),
+                0x0e => ("ldc2", 8),
+                _ => ("sdc2", 8),
+            };
+            let memory = c.memory(rd, sign_extend(bits(word, 0, 11), 11), size);
+            ins(mnemonic, vec![Operand::Register(numbered(rt)), memory])
+        }
+        0x10..=0x1f => ins("c2", vec![imm(bits(word, 0, 25) as i64)]),
+        _ => None,
+    }
+}
+
+/// lwc2, ldc2, swc2 and sdc2 before release 6
+pub fn cop2_load_store(word: u32, c: &Context) -> Decoded {
+    let (mnemonic, size) = match bits(word, 26, 6) {
+        0x32 => ("lwc2", 4),
+        0x36 => ("ldc2", 8),
+        0x3a => ("swc2", 4),
+        _ => ("sdc2", 8),
+    };
+    ins(mnemonic, vec![Operand::Register(numbered(rt(word))), c.memory(rs(word), simm16(word), size)])
+}
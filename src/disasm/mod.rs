@@ -0,0 +1,131 @@
+use crate::elf::OsABI;
+use crate::endian::Endianness;
+use crate::instruction_set::InstructionSet;
+
+mod error;
+pub use self::error::DecodeError;
+
+mod instruction;
+pub use self::instruction::{Instruction, Operand, MemoryOperand, IndexMode, ShiftKind, Register, RegisterClass, FlowKind, DelaySlot};
+
+pub mod x86;
+pub mod aarch64;
+pub mod riscv;
+pub mod arm;
+pub mod mips;
+pub mod powerpc;
+pub mod sparc;
+pub mod superh;
+pub mod s390;
+
+pub trait Decoder {
+    /// Decodes a single instruction from the start of `bytes`, which are located at `address`
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError>;
+
+    /// Number of bytes to skip over when the instruction at `address` can not be decoded
+    fn alignment(&self, _address: u64) -> usize {
+        1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Intel,
+    Att,
+}
+
+/// What a decoder needs to know about the binary besides the instruction set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub instruction_set: InstructionSet,
+    pub endianness: Endianness,
+    // Width of addresses and general purpose registers
+    pub bits: u8,
+    // RISC-V: 16 bit compressed instructions are enabled
+    pub compressed: bool,
+    // ARM: instructions are little endian in a big endian binary (BE8)
+    pub be8: bool,
+    // ARM: where A32 code, T32 code and data start, and the mode of addresses before the first of them
+    pub arm_regions: Vec<(u64, arm::ArmMode)>,
+    pub arm_default_mode: arm::ArmMode,
+    // ARM: floating point values are passed in the integer registers (EF_ARM_ABI_FLOAT_SOFT)
+    pub soft_float: bool,
+    // MIPS: the 64 bit instructions are available, which n32 binaries use with 32 bit addresses
+    pub mips64: bool,
+    // MIPS: release 6, which reassigned the encodings of removed instructions
+    pub release6: bool,
+    // MIPS: value of $gp, to resolve accesses to the .got
+    pub gp: Option<u64>,
+    // Operating system the binary is for, which calling conventions can depend on
+    pub os_abi: OsABI,
+}
+
+impl Target {
+    pub fn new(instruction_set: InstructionSet, endianness: Endianness, bits: u8) -> Target {
+        Target {
+            instruction_set,
+            endianness,
+            bits,
+            compressed: false,
+            be8: false,
+            arm_regions: Vec::new(),
+            arm_default_mode: arm::ArmMode::Arm,
+            soft_float: false,
+            mips64: bits == 64,
+            release6: false,
+            gp: None,
+            os_abi: OsABI::System_V,
+        }
+    }
+}
+
+pub fn decoder_for(target: &Target) -> Option<Box<dyn Decoder>> {
+    match target.instruction_set {
+        InstructionSet::X86 => Some(Box::new(x86::X86Decoder::new(x86::Mode::Bits32))),
+        InstructionSet::X86_64 => Some(Box::new(x86::X86Decoder::new(x86::Mode::Bits64))),
+        InstructionSet::AArch64 => Some(Box::new(aarch64::AArch64Decoder)),
+        InstructionSet::RISC_V => Some(Box::new(riscv::RiscVDecoder::new(target.bits == 64, target.compressed))),
+        InstructionSet::ARM => {
+            // Only BE32 binaries, from before ARMv6, store instructions big endian
+            let big_endian = target.endianness == Endianness::BigEndian && !target.be8;
+            Some(Box::new(arm::ArmDecoder::new(target.arm_regions.clone(), target.arm_default_mode, big_endian)))
+        }
+        InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => {
+            let big_endian = target.endianness == Endianness::BigEndian;
+            Some(Box::new(mips::MipsDecoder::new(big_endian, target.mips64, target.release6, target.gp)))
+        }
+        InstructionSet::PowerPC | InstructionSet::PowerPC64 => {
+            let big_endian = target.endianness == Endianness::BigEndian;
+            Some(Box::new(powerpc::PowerPcDecoder::new(big_endian, target.bits == 64)))
+        }
+        InstructionSet::SPARC | InstructionSet::SPARC32Plus | InstructionSet::SPARC_V9 => {
+            let big_endian = target.endianness == Endianness::BigEndian;
+            // V8+ binaries are 32 bit but use the V9 instructions
+            let v9 = matches!(target.instruction_set, InstructionSet::SPARC32Plus | InstructionSet::SPARC_V9);
+            Some(Box::new(sparc::SparcDecoder::new(big_endian, v9)))
+        }
+        InstructionSet::SuperH => {
+            let big_endian = target.endianness == Endianness::BigEndian;
+            Some(Box::new(superh::SuperHDecoder::new(big_endian)))
+        }
+        InstructionSet::S390 => Some(Box::new(s390::S390Decoder)),
+        _ => None,
+    }
+}
+
+impl Instruction {
+    pub fn render(&self, syntax: Syntax) -> String {
+        match self.instruction_set {
+            InstructionSet::X86 | InstructionSet::X86_64 => x86::format(self, syntax),
+            InstructionSet::AArch64 => aarch64::format(self),
+            InstructionSet::RISC_V => riscv::format(self),
+            InstructionSet::ARM => arm::format(self),
+            InstructionSet::MIPS => mips::format(self),
+            InstructionSet::PowerPC => powerpc::format(self),
+            InstructionSet::SPARC => sparc::format(self),
+            InstructionSet::SuperH => superh::format(self),
+            InstructionSet::S390 => s390::format(self),
+            _ => format!("{} {:?}", self.mnemonic, self.operands),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::disasm::{FlowKind, Operand};
+
+use super::registers::cr;
+use super::{bit, bits, imm, ins, ra, rb, rt, sign_extend, xo, Context, Decoded};
+
+const TRUE_CONDITIONS: [&str; 4] = ["lt", "gt", "eq", "so"];
+const FALSE_CONDITIONS: [&str; 4] = ["ge", "le", "ne", "ns"];
+
+// BO with both "don't test" bits set, the branch is always taken
+fn always(bo: u32) -> bool {
+    bo & 0b10100 == 0b10100
+}
+
+// Condition part of the extended mnemonic for BO and BI, with the operands it needs, None if BO is reserved
+fn condition(bo: u32, bi: u32) -> Option<(String, Vec<Operand>)> {
+    let mut operands = vec![];
+    let name = if always(bo) {
+        String::new()
+    } else if bo & 0b00100 != 0 {
+        // Only the CR bit is tested, the two low bits are a static prediction
+        let names = if bo & 0b01000 != 0 { TRUE_CONDITIONS } else { FALSE_CONDITIONS };
+        if bi / 4 != 0 {
+            operands.push(Operand::Register(cr(bi / 4)));
+        }
+        format!("{}{}", names[(bi % 4) as usize], hint(bo & 0b11)?)
+    } else {
+        let counter = if bo & 0b00010 != 0 { "dz" } else { "dnz" };
+        if bo & 0b10000 != 0 {
+            // Only CTR is tested, the prediction is in the a and t bits
+            format!("{}{}", counter, hint(((bo >> 2) & 0b10) | (bo & 1))?)
+        } else {
+            operands.push(Operand::Condition(bi as u8));
+            format!("{}{}", counter, if bo & 0b01000 != 0 { "t" } else { "f" })
+        }
+    };
+    Some((name, operands))
+}
+
+fn hint(at: u32) -> Option<&'static str> {
+    match at {
+        0b00 => Some(""),
+        0b10 => Some("-"),
+        0b11 => Some("+"),
+        _ => None,
+    }
+}
+
+// Inserts the link and absolute suffixes before the prediction hint
+fn mnemonic(condition: &str, register: &str, link: bool, absolute: bool) -> String {
+    let (condition, hint) = match condition.strip_suffix(|x| x == '+' || x == '-') {
+        Some(stripped) => (stripped, &condition[stripped.len()..]),
+        None => (condition, ""),
+    };
+    format!(
+        "b{}{}{}{}{}",
+        condition,
+        register,
+        if link { "l" } else { "" },
+        if absolute { "a" } else { "" },
+        hint
+    )
+}
+
+/// b and bc, relative or absolute, with or without link
+pub fn branch(word: u32, c: &Context) -> Decoded {
+    let (absolute, link) = (bit(word, 1), bit(word, 0));
+
+    if bits(word, 26, 6) == 0x12 {
+        let target = c.target(sign_extend(bits(word, 2, 24), 24) << 2, absolute);
+        return ins(&mnemonic("", "", link, absolute), vec![target]);
+    }
+
+    let (bo, bi) = (rt(word), ra(word));
+    let target = c.target(sign_extend(bits(word, 2, 14), 14) << 2, absolute);
+    match condition(bo, bi) {
+        Some((name, mut operands)) => {
+            operands.push(target);
+            ins(&mnemonic(&name, "", link, absolute), operands)
+        }
+        None => ins(&mnemonic("c", "", link, absolute), vec![imm(bo as i64), imm(bi as i64), target]),
+    }
+}
+
+// bclr, bcctr and bctar, which branch to a special purpose register
+fn branch_register(word: u32, register: &str) -> Decoded {
+    let (bo, bi, bh) = (rt(word), ra(word), bits(word, 11, 2));
+    let link = bit(word, 0);
+    // Branching to ctr while decrementing it is invalid
+    if register == "ctr" && bo & 0b00100 == 0 {
+        return None;
+    }
+
+    match condition(bo, bi) {
+        Some((name, operands)) if bh == 0 => ins(&mnemonic(&name, register, link, false), operands),
+        _ => {
+            let base = format!("bc{}{}", register, if link { "l" } else { "" });
+            ins(&base, vec![imm(bo as i64), imm(bi as i64), imm(bh as i64)])
+        }
+    }
+}
+
+/// Opcode 19: branches to registers, condition register logic and context synchronisation
+pub fn condition_register(word: u32, c: &Context) -> Decoded {
+    let (bt, ba, bb) = (rt(word), ra(word), rb(word));
+    let bit_operand = |n: u32| Operand::Condition(n as u8);
+
+    // addpcis has a 16 bit immediate spread over three fields
+    if bits(word, 1, 5) == 2 {
+        let d = (bits(word, 6, 10) << 6) | (bits(word, 16, 5) << 1) | bits(word, 0, 1);
+        let d = sign_extend(d, 16);
+        return if d == 0 { ins("lnia", vec![c.reg(bt)]) } else { ins("addpcis", vec![c.reg(bt), imm(d)]) };
+    }
+
+    match xo(word) {
+        0 if bits(word, 21, 2) == 0 && bits(word, 16, 2) == 0 && bits(word, 11, 5) == 0 && !bit(word, 0) => {
+            ins("mcrf", vec![Operand::Register(cr(bits(word, 23, 3))), Operand::Register(cr(bits(word, 18, 3)))])
+        }
+        16 if bits(word, 13, 3) == 0 => branch_register(word, "lr"),
+        528 if bits(word, 13, 3) == 0 => branch_register(word, "ctr"),
+        560 if bits(word, 13, 3) == 0 => branch_register(word, "tar"),
+        18 | 50 | 150 | 274 if bits(word, 11, 15) == 0 && !bit(word, 0) => {
+            let mnemonic = match xo(word) {
+                18 => "rfid",
+                50 => "rfi",
+                150 => "isync",
+                _ => "hrfid",
+            };
+            ins(mnemonic, vec![])
+        }
+        33 | 129 | 193 | 225 | 257 | 289 | 417 | 449 if !bit(word, 0) => {
+            // Setting, clearing, moving and negating bits have their own mnemonics
+            match (xo(word), ba == bb, bt == ba) {
+                (193, true, true) => ins("crclr", vec![bit_operand(bt)]),
+                (289, true, true) => ins("crset", vec![bit_operand(bt)]),
+                (449, true, _) => ins("crmove", vec![bit_operand(bt), bit_operand(ba)]),
+                (33, true, _) => ins("crnot", vec![bit_operand(bt), bit_operand(ba)]),
+                (op, _, _) => {
+                    let mnemonic = match op {
+                        33 => "crnor",
+                        129 => "crandc",
+                        193 => "crxor",
+                        225 => "crnand",
+                        257 => "crand",
+                        289 => "creqv",
+                        417 => "crorc",
+                        _ => "cror",
+                    };
+                    ins(mnemonic, vec![bit_operand(bt), bit_operand(ba), bit_operand(bb)])
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Control flow of an instruction, and whether it only happens if a condition holds
+pub fn flow_of(word: u32, mnemonic: &str) -> (FlowKind, bool) {
+    let link = bit(word, 0);
+    let bo = rt(word);
+    match bits(word, 26, 6) {
+        0x12 if link => (FlowKind::Call, false),
+        0x12 => (FlowKind::Jump, false),
+        0x10 if link => (FlowKind::Call, !always(bo)),
+        0x10 => (FlowKind::Jump, !always(bo)),
+        0x11 => (FlowKind::Syscall, false),
+        0x13 => match xo(word) {
+            16 if link => (FlowKind::Call, !always(bo)),
+            16 => (FlowKind::Return, !always(bo)),
+            528 | 560 if link => (FlowKind::Call, !always(bo)),
+            528 | 560 => (FlowKind::Jump, !always(bo)),
+            18 | 50 | 274 => (FlowKind::Return, false),
+            _ => (FlowKind::Sequential, false),
+        },
+        _ if mnemonic == "trap" => (FlowKind::Trap, false),
+        _ => (FlowKind::Sequential, false),
+    }
+}
@@ -0,0 +1,92 @@
+use crate::disasm::Operand;
+
+use super::registers::{cr, fpr};
+use super::{bit, bits, imm, ins, ra, rb, rc, record, rt, xo, Context, Decoded};
+
+fn f(number: u32) -> Operand {
+    Operand::Register(fpr(number))
+}
+
+/// Opcodes 59 and 63: single and double precision arithmetic, conversions, comparisons and FPSCR moves
+pub fn float(word: u32, _c: &Context) -> Decoded {
+    let single = bits(word, 26, 6) == 0x3b;
+    let suffix = if single { "s" } else { "" };
+    let dot = record(word);
+    let (frt, fra, frb, frc) = (rt(word), ra(word), rb(word), rc(word));
+
+    // A-form, with a 5 bit extended opcode
+    let a_form = |name: &str, operands: Vec<Operand>| ins(&format!("{}{}{}", name, suffix, dot), operands);
+    match bits(word, 1, 5) {
+        18 | 20 | 21 if frc == 0 => {
+            let name = match bits(word, 1, 5) {
+                18 => "fdiv",
+                20 => "fsub",
+                _ => "fadd",
+            };
+            return a_form(name, vec![f(frt), f(fra), f(frb)]);
+        }
+        22 | 24 | 26 if fra == 0 && frc == 0 => {
+            let name = match bits(word, 1, 5) {
+                22 => "fsqrt",
+                24 => "fre",
+                _ => "frsqrte",
+            };
+            return a_form(name, vec![f(frt), f(frb)]);
+        }
+        23 if !single => return a_form("fsel", vec![f(frt), f(fra), f(frc), f(frb)]),
+        25 if frb == 0 => return a_form("fmul", vec![f(frt), f(fra), f(frc)]),
+        28..=31 => {
+            let name = ["fmsub", "fmadd", "fnmsub", "fnmadd"][bits(word, 1, 5) as usize - 28];
+            return a_form(name, vec![f(frt), f(fra), f(frc), f(frb)]);
+        }
+        _ => {}
+    }
+
+    // X-form, with a 10 bit extended opcode
+    let x_form = |name: &str, operands: Vec<Operand>| ins(&format!("{}{}", name, dot), operands);
+    let unary = |name: &str| if fra == 0 { x_form(name, vec![f(frt), f(frb)]) } else { None };
+    if single {
+        return match xo(word) {
+            846 => unary("fcfids"),
+            974 => unary("fcfidus"),
+            _ => None,
+        };
+    }
+
+    match xo(word) {
+        0 | 32 if bits(word, 21, 2) == 0 && !bit(word, 0) => {
+            let name = if xo(word) == 0 { "fcmpu" } else { "fcmpo" };
+            ins(name, vec![Operand::Register(cr(bits(word, 23, 3))), f(fra), f(frb)])
+        }
+        8 => x_form("fcpsgn", vec![f(frt), f(fra), f(frb)]),
+        12 => unary("frsp"),
+        14 => unary("fctiw"),
+        15 => unary("fctiwz"),
+        40 => unary("fneg"),
+        72 => unary("fmr"),
+        136 => unary("fnabs"),
+        142 => unary("fctiwu"),
+        143 => unary("fctiwuz"),
+        264 => unary("fabs"),
+        392 => unary("frin"),
+        424 => unary("friz"),
+        456 => unary("frip"),
+        488 => unary("frim"),
+        814 => unary("fctid"),
+        815 => unary("fctidz"),
+        846 => unary("fcfid"),
+        942 => unary("fctidu"),
+        943 => unary("fctiduz"),
+        974 => unary("fcfidu"),
+        38 | 70 if fra == 0 && frb == 0 => x_form(if xo(word) == 38 { "mtfsb1" } else { "mtfsb0" }, vec![imm(frt as i64)]),
+        64 if bits(word, 21, 2) == 0 && bits(word, 16, 2) == 0 && frb == 0 && !bit(word, 0) => {
+            ins("mcrfs", vec![Operand::Register(cr(bits(word, 23, 3))), Operand::Register(cr(bits(word, 18, 3)))])
+        }
+        134 if bits(word, 16, 7) == 0 && !bit(word, 11) => {
+            x_form("mtfsfi", vec![imm(bits(word, 23, 3) as i64), imm(bits(word, 12, 4) as i64)])
+        }
+        583 if fra == 0 && frb == 0 => x_form("mffs", vec![f(frt)]),
+        711 if !bit(word, 25) && !bit(word, 16) => x_form("mtfsf", vec![imm(bits(word, 17, 8) as i64), f(frb)]),
+        _ => None,
+    }
+}
@@ -0,0 +1,46 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma without a space, immediates are
+// decimal and memory operands are written as offset(base) or base,index
+
+const CONDITIONS: [&str; 4] = ["lt", "gt", "eq", "so"];
+
+pub fn format(instruction: &Instruction) -> String {
+    let operands: Vec<String> = instruction.operands.iter().map(operand).collect();
+
+    let mut res = instruction.mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(","));
+    }
+    res
+}
+
+fn memory(mem: &MemoryOperand) -> String {
+    let base = mem.base.map(|x| x.name).unwrap_or("0");
+    match mem.index {
+        Some(index) => format!("{},{}", base, index.name),
+        None => format!("{}({})", mem.displacement, base),
+    }
+}
+
+// A bit of the condition register, named by its field and position in the field
+fn condition_bit(bit: u8) -> String {
+    let name = CONDITIONS[(bit % 4) as usize];
+    if bit < 4 {
+        name.to_string()
+    } else {
+        format!("4*cr{}+{}", bit / 4, name)
+    }
+}
+
+fn operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, .. } => format!("{}", value),
+        Operand::Memory(mem) => memory(mem),
+        Operand::Address(address) => format!("0x{:x}", address),
+        Operand::Condition(bit) => condition_bit(*bit),
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,300 @@
+use crate::disasm::Operand;
+
+use super::{bit, bits, cr_field, imm, ins, ra, rb, rc, record, rt, simm16, xo, Context, Decoded};
+
+// Conditions of the trap extended mnemonics, by TO field
+fn trap_condition(to: u32) -> Option<&'static str> {
+    Some(match to {
+        1 => "lgt",
+        2 => "llt",
+        4 => "eq",
+        5 => "lge",
+        6 => "lle",
+        8 => "gt",
+        12 => "ge",
+        16 => "lt",
+        20 => "le",
+        24 => "ne",
+        31 => "u",
+        _ => return None,
+    })
+}
+
+/// twi and tdi
+pub fn trap_immediate(word: u32, c: &Context) -> Decoded {
+    let base = if bits(word, 26, 6) == 0x02 { "td" } else { "tw" };
+    let (to, ra) = (rt(word), ra(word));
+    match trap_condition(to) {
+        Some(condition) => ins(&format!("{}{}i", base, condition), vec![c.reg(ra), imm(simm16(word))]),
+        None => ins(&format!("{}i", base), vec![imm(to as i64), c.reg(ra), imm(simm16(word))]),
+    }
+}
+
+// tw and td
+fn trap(word: u32, c: &Context, base: &str) -> Decoded {
+    let (to, ra, rb) = (rt(word), ra(word), rb(word));
+    if base == "tw" && to == 31 && ra == 0 && rb == 0 {
+        return ins("trap", vec![]);
+    }
+    match trap_condition(to) {
+        Some(condition) => ins(&format!("{}{}", base, condition), vec![c.reg(ra), c.reg(rb)]),
+        None => ins(base, vec![imm(to as i64), c.reg(ra), c.reg(rb)]),
+    }
+}
+
+/// Arithmetic with a 16 bit immediate: mulli, subfic, addic, addi and addis
+pub fn arithmetic_immediate(word: u32, c: &Context) -> Decoded {
+    let (rt, ra) = (rt(word), ra(word));
+    let value = simm16(word);
+
+    match bits(word, 26, 6) {
+        0x07 => ins("mulli", vec![c.reg(rt), c.reg(ra), imm(value)]),
+        0x08 => ins("subfic", vec![c.reg(rt), c.reg(ra), imm(value)]),
+        0x0c => ins("addic", vec![c.reg(rt), c.reg(ra), imm(value)]),
+        0x0d => ins("addic.", vec![c.reg(rt), c.reg(ra), imm(value)]),
+        // rA = 0 means the value 0 rather than r0, so these load an immediate
+        0x0e if ra == 0 => ins("li", vec![c.reg(rt), imm(value)]),
+        0x0e => ins("addi", vec![c.reg(rt), c.reg(ra), imm(value)]),
+        0x0f if ra == 0 => ins("lis", vec![c.reg(rt), imm(value)]),
+        _ => ins("addis", vec![c.reg(rt), c.reg(ra), imm(value)]),
+    }
+}
+
+/// cmpi and cmpli, written as cmpwi, cmpdi, cmplwi and cmpldi
+pub fn compare_immediate(word: u32, c: &Context) -> Decoded {
+    if bit(word, 22) {
+        return None;
+    }
+    let width = if bit(word, 21) { "d" } else { "w" };
+    let mut operands: Vec<Operand> = cr_field(bits(word, 23, 3)).into_iter().collect();
+    operands.push(c.reg(ra(word)));
+    if bits(word, 26, 6) == 0x0a {
+        operands.push(imm(bits(word, 0, 16) as i64));
+        ins(&format!("cmpl{}i", width), operands)
+    } else {
+        operands.push(imm(simm16(word)));
+        ins(&format!("cmp{}i", width), operands)
+    }
+}
+
+/// ori, oris, xori, xoris, andi. and andis.
+pub fn logical_immediate(word: u32, c: &Context) -> Decoded {
+    let (rs, ra) = (rt(word), ra(word));
+    let value = bits(word, 0, 16);
+    let opcode = bits(word, 26, 6);
+    if opcode == 0x18 && rs == 0 && ra == 0 && value == 0 {
+        return ins("nop", vec![]);
+    }
+    if opcode == 0x1a && rs == 0 && ra == 0 && value == 0 {
+        return ins("xnop", vec![]);
+    }
+
+    let mnemonic = ["ori", "oris", "xori", "xoris", "andi.", "andis."][opcode as usize - 0x18];
+    ins(mnemonic, vec![c.reg(ra), c.reg(rs), imm(value as i64)])
+}
+
+/// The rotate and mask instructions, shown with the usual shift and clear mnemonics where possible
+pub fn rotate(word: u32, c: &Context) -> Decoded {
+    let (rs, ra) = (rt(word), ra(word));
+    let dot = record(word);
+    let op = |name: &str, values: &[u32]| {
+        let mut operands = vec![c.reg(ra), c.reg(rs)];
+        operands.extend(values.iter().map(|x| imm(*x as i64)));
+        ins(&format!("{}{}", name, dot), operands)
+    };
+
+    match bits(word, 26, 6) {
+        0x14 => op("rlwimi", &[rb(word), rc(word), bits(word, 1, 5)]),
+        0x15 => {
+            let (sh, mb, me) = (rb(word), rc(word), bits(word, 1, 5));
+            match (sh, mb, me) {
+                (0, _, 31) => op("clrlwi", &[mb]),
+                (0, 0, _) => op("clrrwi", &[31 - me]),
+                (_, 0, 31) => op("rotlwi", &[sh]),
+                (_, 0, _) if me == 31 - sh => op("slwi", &[sh]),
+                (_, _, 31) if sh + mb == 32 => op("srwi", &[mb]),
+                _ => op("rlwinm", &[sh, mb, me]),
+            }
+        }
+        0x17 => {
+            let (mb, me) = (rc(word), bits(word, 1, 5));
+            let mut operands = vec![c.reg(ra), c.reg(rs), c.reg(rb(word))];
+            if mb == 0 && me == 31 {
+                return ins(&format!("rotlw{}", dot), operands);
+            }
+            operands.extend([imm(mb as i64), imm(me as i64)].iter().cloned());
+            ins(&format!("rlwnm{}", dot), operands)
+        }
+        _ => {
+            // The 6 bit shift and mask fields have their most significant bit stored separately
+            let sh = rb(word) | (bits(word, 1, 1) << 5);
+            let raw = bits(word, 5, 6);
+            let mask = (raw >> 1) | ((raw & 1) << 5);
+            match bits(word, 2, 3) {
+                0 => match (sh, mask) {
+                    (_, 0) => op("rotldi", &[sh]),
+                    (0, _) => op("clrldi", &[mask]),
+                    _ if sh + mask == 64 => op("srdi", &[mask]),
+                    _ => op("rldicl", &[sh, mask]),
+                },
+                1 => match (sh, mask) {
+                    (0, _) => op("clrrdi", &[63 - mask]),
+                    _ if mask == 63 - sh => op("sldi", &[sh]),
+                    _ => op("rldicr", &[sh, mask]),
+                },
+                2 => op("rldic", &[sh, mask]),
+                3 => op("rldimi", &[sh, mask]),
+                4 => {
+                    let mut operands = vec![c.reg(ra), c.reg(rs), c.reg(rb(word))];
+                    if !bit(word, 1) && mask == 0 {
+                        return ins(&format!("rotld{}", dot), operands);
+                    }
+                    operands.push(imm(mask as i64));
+                    let name = if bit(word, 1) { "rldcr" } else { "rldcl" };
+                    ins(&format!("{}{}", name, dot), operands)
+                }
+                _ => None,
+            }
+        }
+    }
+}
+
+// XO-form arithmetic of opcode 31, by the 9 bit extended opcode: name and whether rB is an operand
+fn xo_arithmetic(xo: u32) -> Option<(&'static str, bool)> {
+    Some(match xo {
+        8 => ("subfc", true),
+        9 => ("mulhdu", true),
+        10 => ("addc", true),
+        11 => ("mulhwu", true),
+        40 => ("subf", true),
+        73 => ("mulhd", true),
+        75 => ("mulhw", true),
+        104 => ("neg", false),
+        136 => ("subfe", true),
+        138 => ("adde", true),
+        200 => ("subfze", false),
+        202 => ("addze", false),
+        232 => ("subfme", false),
+        233 => ("mulld", true),
+        234 => ("addme", false),
+        235 => ("mullw", true),
+        266 => ("add", true),
+        393 => ("divdeu", true),
+        395 => ("divweu", true),
+        425 => ("divde", true),
+        427 => ("divwe", true),
+        457 => ("divdu", true),
+        459 => ("divwu", true),
+        489 => ("divd", true),
+        491 => ("divw", true),
+        _ => return None,
+    })
+}
+
+// X-form logical and shift operations of opcode 31: rA, rS, rB
+fn x_logical(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        24 => "slw",
+        27 => "sld",
+        28 => "and",
+        60 => "andc",
+        124 => "nor",
+        284 => "eqv",
+        316 => "xor",
+        412 => "orc",
+        444 => "or",
+        476 => "nand",
+        536 => "srw",
+        539 => "srd",
+        792 => "sraw",
+        794 => "srad",
+        _ => return None,
+    })
+}
+
+// X-form operations of opcode 31 on a single register: rA, rS
+fn x_unary(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        26 => "cntlzw",
+        58 => "cntlzd",
+        538 => "cnttzw",
+        570 => "cnttzd",
+        922 => "extsh",
+        954 => "extsb",
+        986 => "extsw",
+        _ => return None,
+    })
+}
+
+/// The integer operations of opcode 31: arithmetic, logic, shifts, comparisons and traps
+pub fn extended(word: u32, c: &Context) -> Decoded {
+    let (rt, ra, rb) = (rt(word), ra(word), rb(word));
+    let dot = record(word);
+    let xo = xo(word);
+
+    // isel has a 5 bit extended opcode, with the condition bit in the rc field
+    if bits(word, 1, 5) == 15 && !bit(word, 0) {
+        return ins("isel", vec![c.reg(rt), c.reg(ra), c.reg(rb), Operand::Condition(rc(word) as u8)]);
+    }
+
+    match xo {
+        0 | 32 if !bit(word, 22) && !bit(word, 0) => {
+            let width = if bit(word, 21) { "d" } else { "w" };
+            let name = if xo == 0 { "cmp" } else { "cmpl" };
+            let mut operands: Vec<Operand> = cr_field(bits(word, 23, 3)).into_iter().collect();
+            operands.extend([c.reg(ra), c.reg(rb)].iter().cloned());
+            ins(&format!("{}{}", name, width), operands)
+        }
+        4 if !bit(word, 0) => trap(word, c, "tw"),
+        68 if !bit(word, 0) => trap(word, c, "td"),
+        // Population counts and the byte comparison don't have a record form
+        122 | 378 | 506 if rb == 0 && !bit(word, 0) => {
+            let mnemonic = match xo {
+                122 => "popcntb",
+                378 => "popcntw",
+                _ => "popcntd",
+            };
+            ins(mnemonic, vec![c.reg(ra), c.reg(rt)])
+        }
+        508 if !bit(word, 0) => ins("cmpb", vec![c.reg(ra), c.reg(rt), c.reg(rb)]),
+        // The modulo operations are X-form, they would otherwise be taken for XO-form with OE set
+        265 | 267 | 777 | 779 if !bit(word, 0) => {
+            let mnemonic = match xo {
+                265 => "modud",
+                267 => "moduw",
+                777 => "modsd",
+                _ => "modsw",
+            };
+            ins(mnemonic, vec![c.reg(rt), c.reg(ra), c.reg(rb)])
+        }
+        824 => ins(&format!("srawi{}", dot), vec![c.reg(ra), c.reg(rt), imm(rb as i64)]),
+        // sradi is XS-form, the sixth bit of the shift replaces the low bit of the extended opcode
+        826 | 827 => ins(&format!("sradi{}", dot), vec![c.reg(ra), c.reg(rt), imm((rb | (bits(word, 1, 1) << 5)) as i64)]),
+        444 if rt == rb => ins(&format!("mr{}", dot), vec![c.reg(ra), c.reg(rt)]),
+        124 if rt == rb => ins(&format!("not{}", dot), vec![c.reg(ra), c.reg(rt)]),
+        _ => {
+            if let Some(name) = x_logical(xo) {
+                return ins(&format!("{}{}", name, dot), vec![c.reg(ra), c.reg(rt), c.reg(rb)]);
+            }
+            if let Some(name) = x_unary(xo) {
+                return if rb == 0 { ins(&format!("{}{}", name, dot), vec![c.reg(ra), c.reg(rt)]) } else { None };
+            }
+
+            let (name, binary) = xo_arithmetic(bits(word, 1, 9))?;
+            let overflow = if bit(word, 10) { "o" } else { "" };
+            // The high word multiplications have no overflow form
+            if matches!(name, "mulhdu" | "mulhwu" | "mulhd" | "mulhw") && bit(word, 10) {
+                return None;
+            }
+            let mnemonic = format!("{}{}{}", name, overflow, dot);
+            if binary {
+                ins(&mnemonic, vec![c.reg(rt), c.reg(ra), c.reg(rb)])
+            } else if rb == 0 {
+                ins(&mnemonic, vec![c.reg(rt), c.reg(ra)])
+            } else {
+                None
+            }
+        }
+    }
+}
+
@@ -0,0 +1,176 @@
+use crate::disasm::Operand;
+
+use super::registers::{fpr, vr, vsr};
+use super::{bit, bits, imm, ins, ra, rb, rt, sign_extend, simm16, xo, Context, Decoded};
+
+// What the rt field of a load or store holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Data {
+    General,
+    Float,
+    // A VSX register, the low bits of the number are in rt and the high one is given
+    Vector(u32),
+    // An AltiVec register, the upper half of the VSX registers
+    AltiVec,
+}
+
+/// D-form and DS-form loads and stores: displacement(rA)
+pub fn displacement(word: u32, c: &Context) -> Decoded {
+    use self::Data::*;
+
+    let opcode = bits(word, 26, 6);
+    let (mnemonic, size, data) = match opcode {
+        0x20 => ("lwz", 4, General),
+        0x21 => ("lwzu", 4, General),
+        0x22 => ("lbz", 1, General),
+        0x23 => ("lbzu", 1, General),
+        0x24 => ("stw", 4, General),
+        0x25 => ("stwu", 4, General),
+        0x26 => ("stb", 1, General),
+        0x27 => ("stbu", 1, General),
+        0x28 => ("lhz", 2, General),
+        0x29 => ("lhzu", 2, General),
+        0x2a => ("lha", 2, General),
+        0x2b => ("lhau", 2, General),
+        0x2c => ("sth", 2, General),
+        0x2d => ("sthu", 2, General),
+        0x2e => ("lmw", 4, General),
+        0x2f => ("stmw", 4, General),
+        0x30 => ("lfs", 4, Float),
+        0x31 => ("lfsu", 4, Float),
+        0x32 => ("lfd", 8, Float),
+        0x33 => ("lfdu", 8, Float),
+        0x34 => ("stfs", 4, Float),
+        0x35 => ("stfsu", 4, Float),
+        0x36 => ("stfd", 8, Float),
+        0x37 => ("stfdu", 8, Float),
+        // DQ-form, the quadword is loaded into an even/odd register pair
+        0x38 if bits(word, 0, 4) == 0 && !bit(word, 21) => ("lq", 16, General),
+        // DS-form, the two low bits of the displacement select the operation
+        0x39 => match bits(word, 0, 2) {
+            2 => ("lxsd", 8, AltiVec),
+            3 => ("lxssp", 4, AltiVec),
+            _ => return None,
+        },
+        0x3a => match bits(word, 0, 2) {
+            0 => ("ld", 8, General),
+            1 => ("ldu", 8, General),
+            2 => ("lwa", 4, General),
+            _ => return None,
+        },
+        0x3d => match bits(word, 0, 3) {
+            1 => ("lxv", 16, Vector(bits(word, 3, 1) << 5)),
+            5 => ("stxv", 16, Vector(bits(word, 3, 1) << 5)),
+            2 | 6 => ("stxsd", 8, AltiVec),
+            3 | 7 => ("stxssp", 4, AltiVec),
+            _ => return None,
+        },
+        0x3e => match bits(word, 0, 2) {
+            0 => ("std", 8, General),
+            1 => ("stdu", 8, General),
+            2 if !bit(word, 21) => ("stq", 16, General),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let offset = match opcode {
+        0x38 => sign_extend(bits(word, 4, 12), 12) << 4,
+        0x3d if mnemonic == "lxv" || mnemonic == "stxv" => sign_extend(bits(word, 4, 12), 12) << 4,
+        0x39..=0x3e => sign_extend(bits(word, 2, 14), 14) << 2,
+        _ => simm16(word),
+    };
+    let register = match data {
+        General => c.reg(rt(word)),
+        Float => Operand::Register(fpr(rt(word))),
+        Vector(high) => Operand::Register(vsr(rt(word) | high)),
+        AltiVec => Operand::Register(vr(rt(word))),
+    };
+    ins(mnemonic, vec![register, c.displacement(ra(word), offset, size)])
+}
+
+// X-form loads and stores of opcode 31, by extended opcode
+fn indexed_operation(xo: u32) -> Option<(&'static str, u16, Data)> {
+    use self::Data::*;
+
+    Some(match xo {
+        20 => ("lwarx", 4, General),
+        21 => ("ldx", 8, General),
+        23 => ("lwzx", 4, General),
+        52 => ("lbarx", 1, General),
+        53 => ("ldux", 8, General),
+        55 => ("lwzux", 4, General),
+        84 => ("ldarx", 8, General),
+        87 => ("lbzx", 1, General),
+        116 => ("lharx", 2, General),
+        119 => ("lbzux", 1, General),
+        149 => ("stdx", 8, General),
+        150 => ("stwcx.", 4, General),
+        151 => ("stwx", 4, General),
+        181 => ("stdux", 8, General),
+        183 => ("stwux", 4, General),
+        214 => ("stdcx.", 8, General),
+        215 => ("stbx", 1, General),
+        247 => ("stbux", 1, General),
+        279 => ("lhzx", 2, General),
+        311 => ("lhzux", 2, General),
+        341 => ("lwax", 4, General),
+        343 => ("lhax", 2, General),
+        373 => ("lwaux", 4, General),
+        375 => ("lhaux", 2, General),
+        407 => ("sthx", 2, General),
+        439 => ("sthux", 2, General),
+        532 => ("ldbrx", 8, General),
+        533 => ("lswx", 0, General),
+        534 => ("lwbrx", 4, General),
+        535 => ("lfsx", 4, Float),
+        567 => ("lfsux", 4, Float),
+        599 => ("lfdx", 8, Float),
+        631 => ("lfdux", 8, Float),
+        660 => ("stdbrx", 8, General),
+        661 => ("stswx", 0, General),
+        662 => ("stwbrx", 4, General),
+        663 => ("stfsx", 4, Float),
+        694 => ("stbcx.", 1, General),
+        695 => ("stfsux", 4, Float),
+        726 => ("sthcx.", 2, General),
+        727 => ("stfdx", 8, Float),
+        759 => ("stfdux", 8, Float),
+        790 => ("lhbrx", 2, General),
+        855 => ("lfiwax", 4, Float),
+        887 => ("lfiwzx", 4, Float),
+        918 => ("sthbrx", 2, General),
+        983 => ("stfiwx", 4, Float),
+        _ => return None,
+    })
+}
+
+/// X-form loads and stores: rA|0 + rB, and the string instructions with an immediate length
+pub fn indexed(word: u32, c: &Context) -> Decoded {
+    let (rt, ra, rb) = (rt(word), ra(word), rb(word));
+    let xo = xo(word);
+
+    if (xo == 597 || xo == 725) && !bit(word, 0) {
+        let mnemonic = if xo == 597 { "lswi" } else { "stswi" };
+        return ins(mnemonic, vec![c.reg(rt), c.reg(ra), imm(rb as i64)]);
+    }
+
+    let (mnemonic, size, data) = indexed_operation(xo)?;
+    let reserve = mnemonic.ends_with("arx");
+    // Store conditional has the record bit set, reservations use it as a hint that the lock is exclusive
+    if mnemonic.ends_with('.') != bit(word, 0) && !reserve {
+        return None;
+    }
+    let register = match data {
+        Data::General => c.reg(rt),
+        Data::Float => Operand::Register(fpr(rt)),
+        Data::Vector(high) => Operand::Register(vsr(rt | high)),
+        Data::AltiVec => Operand::Register(vr(rt)),
+    };
+
+    let mut operands = vec![register, c.indexed(ra, rb, size)];
+    if reserve && bit(word, 0) {
+        operands.push(imm(1));
+    }
+    ins(mnemonic, operands)
+}
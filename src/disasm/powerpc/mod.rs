@@ -0,0 +1,243 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, Instruction, MemoryOperand, Operand};
+
+mod registers;
+use self::registers::{cr, gpr};
+
+mod branch;
+mod integer;
+mod memory;
+mod float;
+mod vector;
+mod system;
+
+mod format;
+pub use self::format::format;
+
+const LENGTH: usize = 4;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is reserved or not supported
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// Decoder for the 32 and 64 bit PowerPC user and supervisor instruction sets, with the FPU and AltiVec
+#[derive(Debug, Clone)]
+pub struct PowerPcDecoder {
+    big_endian: bool,
+    // Registers are 64 bits wide and branch targets aren't truncated to 32 bits
+    bits64: bool,
+}
+
+impl PowerPcDecoder {
+    pub fn new(big_endian: bool, bits64: bool) -> PowerPcDecoder {
+        PowerPcDecoder { big_endian, bits64 }
+    }
+}
+
+// What the decoding functions need besides the instruction word
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+    bits64: bool,
+}
+
+impl Context {
+    fn reg(&self, number: u32) -> Operand {
+        Operand::Register(gpr(number, self.bits64))
+    }
+
+    // A D-form access: displacement(rA), where rA = 0 stands for the value 0 rather than r0
+    fn displacement(&self, base: u32, displacement: i64, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        if base != 0 {
+            mem.base = Some(gpr(base, self.bits64));
+        }
+        mem.displacement = displacement;
+        Operand::Memory(mem)
+    }
+
+    // An X-form access: rA|0 + rB
+    fn indexed(&self, base: u32, index: u32, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        if base != 0 {
+            mem.base = Some(gpr(base, self.bits64));
+        }
+        mem.index = Some(gpr(index, self.bits64));
+        Operand::Memory(mem)
+    }
+
+    // Target of a branch, relative unless the AA bit is set
+    fn target(&self, offset: i64, absolute: bool) -> Operand {
+        let base = if absolute { 0 } else { self.address };
+        let target = base.wrapping_add(offset as u64);
+        Operand::Address(if self.bits64 { target } else { target & 0xffff_ffff })
+    }
+}
+
+impl Decoder for PowerPcDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let raw: [u8; LENGTH] = bytes[..LENGTH].try_into().unwrap();
+        let word = if self.big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) };
+
+        let context = Context {
+            address,
+            bits64: self.bits64,
+        };
+        let (mnemonic, operands) = decode_word(word, &context).ok_or(DecodeError::InvalidOpcode(word))?;
+
+        let mut instruction = Instruction::new(InstructionSet::PowerPC, address, LENGTH, String::new(), operands);
+        let (flow, conditional) = branch::flow_of(word, &mnemonic);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.mnemonic = mnemonic;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        LENGTH
+    }
+}
+
+fn decode_word(word: u32, c: &Context) -> Decoded {
+    match bits(word, 26, 6) {
+        0x02 | 0x03 => integer::trap_immediate(word, c),
+        0x04 => vector::vector(word, c),
+        0x07 | 0x08 | 0x0c | 0x0d | 0x0e | 0x0f => integer::arithmetic_immediate(word, c),
+        0x0a | 0x0b => integer::compare_immediate(word, c),
+        0x10 | 0x12 => branch::branch(word, c),
+        0x11 => system::system_call(word, c),
+        0x13 => branch::condition_register(word, c),
+        0x14 | 0x15 | 0x17 | 0x1e => integer::rotate(word, c),
+        0x18..=0x1d => integer::logical_immediate(word, c),
+        0x1f => integer::extended(word, c)
+            .or_else(|| memory::indexed(word, c))
+            .or_else(|| system::extended(word, c))
+            .or_else(|| vector::extended(word, c)),
+        0x20..=0x3a | 0x3d | 0x3e => memory::displacement(word, c),
+        0x3b | 0x3f => float::float(word, c),
+        0x3c => vector::vsx(word, c),
+        _ => None,
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+// The fields are named as in the ISA, which numbers bits from the most significant one
+fn rt(word: u32) -> u32 {
+    bits(word, 21, 5)
+}
+
+fn ra(word: u32) -> u32 {
+    bits(word, 16, 5)
+}
+
+fn rb(word: u32) -> u32 {
+    bits(word, 11, 5)
+}
+
+fn rc(word: u32) -> u32 {
+    bits(word, 6, 5)
+}
+
+// Extended opcode of X-form instructions
+fn xo(word: u32) -> u32 {
+    bits(word, 1, 10)
+}
+
+// The record bit, which makes the instruction set cr0 (or cr1 for floating point) and adds a dot to the mnemonic
+fn record(word: u32) -> &'static str {
+    if bit(word, 0) {
+        "."
+    } else {
+        ""
+    }
+}
+
+fn simm16(word: u32) -> i64 {
+    sign_extend(bits(word, 0, 16), 16)
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+// A condition register field, omitted by the extended mnemonics when it is cr0
+fn cr_field(field: u32) -> Option<Operand> {
+    if field == 0 {
+        None
+    } else {
+        Some(Operand::Register(cr(field)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PowerPcDecoder;
+    use crate::disasm::{Decoder, Syntax};
+
+    // The text followed by the flow, marked with ? when conditional
+    fn describe(decoder: &PowerPcDecoder, word: u32) -> String {
+        let bytes = if decoder.big_endian { word.to_be_bytes() } else { word.to_le_bytes() };
+        let instruction = decoder.decode(&bytes, 0x1000).unwrap();
+        let condition = if instruction.conditional { "?" } else { "" };
+        format!("{} {:?}{}", instruction.render(Syntax::Intel), instruction.flow, condition)
+    }
+
+    #[test]
+    fn absolute_and_link_bits() {
+        let decoder = PowerPcDecoder::new(true, false);
+        assert_eq!(describe(&decoder, 0x48000100), "b 0x1100 Jump");
+        assert_eq!(describe(&decoder, 0x48000101), "bl 0x1100 Call");
+        assert_eq!(describe(&decoder, 0x48000202), "ba 0x200 Jump");
+        assert_eq!(describe(&decoder, 0x48000103), "bla 0x100 Call");
+        // Raw bc, when the BO field has no simplified mnemonic
+        assert_eq!(describe(&decoder, 0x42262354), "bc 17,6,0x3354 Jump?");
+        assert_eq!(describe(&decoder, 0x42262356), "bca 17,6,0x2354 Jump?");
+        assert_eq!(describe(&decoder, 0x42262357), "bcla 17,6,0x2354 Call?");
+    }
+
+    #[test]
+    fn simplified_branches() {
+        let decoder = PowerPcDecoder::new(true, false);
+        assert_eq!(describe(&decoder, 0x41820020), "beq 0x1020 Jump?");
+        assert_eq!(describe(&decoder, 0x41820011), "beql 0x1010 Call?");
+        assert_eq!(describe(&decoder, 0x42000010), "bdnz 0x1010 Jump?");
+        assert_eq!(describe(&decoder, 0x4e800020), "blr Return");
+        assert_eq!(describe(&decoder, 0x4d820020), "beqlr Return?");
+        assert_eq!(describe(&decoder, 0x4e800421), "bctrl Call");
+    }
+
+    #[test]
+    fn loads_stores_and_widths() {
+        let ppc32 = PowerPcDecoder::new(true, false);
+        assert_eq!(describe(&ppc32, 0x9421fff0), "stwu r1,-16(r1) Sequential");
+        assert_eq!(describe(&ppc32, 0x7c0802a6), "mflr r0 Sequential");
+        assert_eq!(describe(&ppc32, 0x807f0008), "lwz r3,8(r31) Sequential");
+        assert_eq!(describe(&ppc32, 0x3c620001), "addis r3,r2,1 Sequential");
+
+        let ppc64 = PowerPcDecoder::new(false, true);
+        assert_eq!(describe(&ppc64, 0xe8610008), "ld r3,8(r1) Sequential");
+        assert_eq!(describe(&ppc64, 0xf8010010), "std r0,16(r1) Sequential");
+        assert_eq!(describe(&ppc64, 0x78830020), "clrldi r3,r4,32 Sequential");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::disasm::Operand;
+
+use super::registers::cr;
+use super::{bit, bits, imm, ins, ra, rb, rt, xo, Context, Decoded};
+
+// Special purpose registers with their own move mnemonics, e.g. mflr
+fn spr_name(spr: u32) -> Option<&'static str> {
+    Some(match spr {
+        1 => "xer",
+        8 => "lr",
+        9 => "ctr",
+        18 => "dsisr",
+        19 => "dar",
+        22 => "dec",
+        25 => "sdr1",
+        26 => "srr0",
+        27 => "srr1",
+        256 => "vrsave",
+        _ => return None,
+    })
+}
+
+/// sc and scv
+pub fn system_call(word: u32, _c: &Context) -> Decoded {
+    let level = bits(word, 5, 7);
+    if bits(word, 12, 14) != 0 || bits(word, 2, 3) != 0 {
+        return None;
+    }
+    let mnemonic = match bits(word, 0, 2) {
+        0b10 => "sc",
+        0b01 => "scv",
+        _ => return None,
+    };
+    if level == 0 && mnemonic == "sc" {
+        ins(mnemonic, vec![])
+    } else {
+        ins(mnemonic, vec![imm(level as i64)])
+    }
+}
+
+/// The supervisor, cache and synchronisation instructions of opcode 31
+pub fn extended(word: u32, c: &Context) -> Decoded {
+    let (rt, ra, rb) = (rt(word), ra(word), rb(word));
+    if bit(word, 0) {
+        return None;
+    }
+    // The two halves of the SPR number are swapped in the encoding
+    let spr = ra | (rb << 5);
+
+    match xo(word) {
+        19 if !bit(word, 20) && bits(word, 11, 9) == 0 => ins("mfcr", vec![c.reg(rt)]),
+        19 if bit(word, 20) && !bit(word, 11) => ins("mfocrf", vec![c.reg(rt), imm(bits(word, 12, 8) as i64)]),
+        144 if !bit(word, 11) => {
+            let mask = bits(word, 12, 8);
+            match (bit(word, 20), mask) {
+                (false, 0xff) => ins("mtcr", vec![c.reg(rt)]),
+                (false, _) => ins("mtcrf", vec![imm(mask as i64), c.reg(rt)]),
+                (true, _) => ins("mtocrf", vec![imm(mask as i64), c.reg(rt)]),
+            }
+        }
+        83 if ra == 0 && rb == 0 => ins("mfmsr", vec![c.reg(rt)]),
+        146 | 178 if bits(word, 17, 4) == 0 && rb == 0 => {
+            let mnemonic = if xo(word) == 146 { "mtmsr" } else { "mtmsrd" };
+            let mut operands = vec![c.reg(rt)];
+            if bit(word, 16) {
+                operands.push(imm(1));
+            }
+            ins(mnemonic, operands)
+        }
+        339 => match spr_name(spr) {
+            Some(name) => ins(&format!("mf{}", name), vec![c.reg(rt)]),
+            None => ins("mfspr", vec![c.reg(rt), imm(spr as i64)]),
+        },
+        467 => match spr_name(spr) {
+            Some(name) => ins(&format!("mt{}", name), vec![c.reg(rt)]),
+            None => ins("mtspr", vec![imm(spr as i64), c.reg(rt)]),
+        },
+        371 => match spr {
+            268 => ins("mftb", vec![c.reg(rt)]),
+            269 => ins("mftbu", vec![c.reg(rt)]),
+            _ => None,
+        },
+        598 if bits(word, 23, 3) == 0 && ra == 0 && rb == 0 => match bits(word, 21, 2) {
+            0 => ins("sync", vec![]),
+            1 => ins("lwsync", vec![]),
+            2 => ins("ptesync", vec![]),
+            _ => None,
+        },
+        854 if rt == 0 && ra == 0 && rb == 0 => ins("eieio", vec![]),
+        566 if rt == 0 && ra == 0 && rb == 0 => ins("tlbsync", vec![]),
+        370 if rt == 0 && ra == 0 && rb == 0 => ins("tlbia", vec![]),
+        512 if bits(word, 11, 12) == 0 => ins("mcrxr", vec![Operand::Register(cr(bits(word, 23, 3)))]),
+        // The touch hints and the flush level are an optional third operand
+        86 | 246 | 278 if rt != 0 => {
+            let mnemonic = match xo(word) {
+                86 if rt == 1 => return ins("dcbfl", vec![c.indexed(ra, rb, 0)]),
+                86 if rt < 4 => "dcbf",
+                246 => "dcbtst",
+                278 => "dcbt",
+                _ => return None,
+            };
+            ins(mnemonic, vec![c.indexed(ra, rb, 0), imm(rt as i64)])
+        }
+        // Segment registers of the 32 bit MMU
+        595 if bits(word, 20, 1) == 0 && rb == 0 => ins("mfsr", vec![c.reg(rt), imm(bits(word, 16, 4) as i64)]),
+        210 if bits(word, 20, 1) == 0 && rb == 0 => ins("mtsr", vec![imm(bits(word, 16, 4) as i64), c.reg(rt)]),
+        659 if ra == 0 => ins("mfsrin", vec![c.reg(rt), c.reg(rb)]),
+        242 if ra == 0 => ins("mtsrin", vec![c.reg(rt), c.reg(rb)]),
+        // Cache management, on the effective address rA|0 + rB
+        54 | 86 | 246 | 278 | 470 | 982 | 1014 if rt == 0 => {
+            let mnemonic = match xo(word) {
+                54 => "dcbst",
+                86 => "dcbf",
+                246 => "dcbtst",
+                278 => "dcbt",
+                470 => "dcbi",
+                982 => "icbi",
+                _ => "dcbz",
+            };
+            ins(mnemonic, vec![c.indexed(ra, rb, 0)])
+        }
+        306 | 274 if ra == 0 => {
+            let mnemonic = if xo(word) == 306 { "tlbie" } else { "tlbiel" };
+            let mut operands = vec![c.reg(rb)];
+            if rt != 0 {
+                operands.push(c.reg(rt));
+            }
+            ins(mnemonic, operands)
+        }
+        _ => None,
+    }
+}
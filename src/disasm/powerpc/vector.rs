@@ -0,0 +1,511 @@
+use crate::disasm::Operand;
+
+use super::registers::{cr, vr, vsr};
+use super::{bit, bits, imm, ins, ra, rb, rc, rt, sign_extend, xo, Context, Decoded};
+
+fn v(number: u32) -> Operand {
+    Operand::Register(vr(number))
+}
+
+fn vs(number: u32) -> Operand {
+    Operand::Register(vsr(number))
+}
+
+// VX-form operations with vD, vA and vB, by the 11 bit extended opcode
+fn vx_binary(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        0 => "vaddubm",
+        2 => "vmaxub",
+        4 => "vrlb",
+        8 => "vmuloub",
+        10 => "vaddfp",
+        12 => "vmrghb",
+        14 => "vpkuhum",
+        64 => "vadduhm",
+        66 => "vmaxuh",
+        68 => "vrlh",
+        72 => "vmulouh",
+        74 => "vsubfp",
+        76 => "vmrghh",
+        78 => "vpkuwum",
+        128 => "vadduwm",
+        130 => "vmaxuw",
+        132 => "vrlw",
+        133 => "vrlwmi",
+        136 => "vmulouw",
+        137 => "vmuluwm",
+        140 => "vmrghw",
+        142 => "vpkuhus",
+        192 => "vaddudm",
+        194 => "vmaxud",
+        196 => "vrld",
+        197 => "vrldmi",
+        206 => "vpkuwus",
+        256 => "vadduqm",
+        258 => "vmaxsb",
+        260 => "vslb",
+        264 => "vmulosb",
+        268 => "vmrglb",
+        270 => "vpkshus",
+        320 => "vaddcuq",
+        322 => "vmaxsh",
+        324 => "vslh",
+        328 => "vmulosh",
+        332 => "vmrglh",
+        334 => "vpkswus",
+        384 => "vaddcuw",
+        386 => "vmaxsw",
+        389 => "vrlwnm",
+        388 => "vslw",
+        392 => "vmulosw",
+        396 => "vmrglw",
+        398 => "vpkshss",
+        450 => "vmaxsd",
+        452 => "vsl",
+        453 => "vrldnm",
+        462 => "vpkswss",
+        512 => "vaddubs",
+        514 => "vminub",
+        516 => "vsrb",
+        520 => "vmuleub",
+        576 => "vadduhs",
+        578 => "vminuh",
+        580 => "vsrh",
+        584 => "vmuleuh",
+        640 => "vadduws",
+        642 => "vminuw",
+        644 => "vsrw",
+        648 => "vmuleuw",
+        706 => "vminud",
+        708 => "vsr",
+        768 => "vaddsbs",
+        770 => "vminsb",
+        772 => "vsrab",
+        776 => "vmulesb",
+        782 => "vpkpx",
+        832 => "vaddshs",
+        834 => "vminsh",
+        836 => "vsrah",
+        840 => "vmulesh",
+        896 => "vaddsws",
+        898 => "vminsw",
+        900 => "vsraw",
+        904 => "vmulesw",
+        962 => "vminsd",
+        964 => "vsrad",
+        1024 => "vsububm",
+        1026 => "vavgub",
+        1027 => "vabsdub",
+        1028 => "vand",
+        1032 => "vpmsumb",
+        1034 => "vmaxfp",
+        1036 => "vslo",
+        1088 => "vsubuhm",
+        1090 => "vavguh",
+        1091 => "vabsduh",
+        1092 => "vandc",
+        1096 => "vpmsumh",
+        1098 => "vminfp",
+        1100 => "vsro",
+        1102 => "vpkudum",
+        1152 => "vsubuwm",
+        1154 => "vavguw",
+        1155 => "vabsduw",
+        1156 => "vor",
+        1160 => "vpmsumw",
+        1216 => "vsubudm",
+        1220 => "vxor",
+        1224 => "vpmsumd",
+        1230 => "vpkudus",
+        1280 => "vsubuqm",
+        1282 => "vavgsb",
+        1284 => "vnor",
+        1288 => "vcipher",
+        1289 => "vcipherlast",
+        1344 => "vsubcuq",
+        1346 => "vavgsh",
+        1348 => "vorc",
+        1352 => "vncipher",
+        1353 => "vncipherlast",
+        1358 => "vpksdus",
+        1408 => "vsubcuw",
+        1410 => "vavgsw",
+        1412 => "vnand",
+        1476 => "vsld",
+        1486 => "vpksdss",
+        1536 => "vsububs",
+        1544 => "vsum4ubs",
+        1600 => "vsubuhs",
+        1608 => "vsum4shs",
+        1664 => "vsubuws",
+        1668 => "veqv",
+        1672 => "vsum2sws",
+        1676 => "vmrgow",
+        1732 => "vsrd",
+        1796 => "vsrv",
+        1792 => "vsubsbs",
+        1800 => "vsum4sbs",
+        1856 => "vsubshs",
+        1860 => "vslv",
+        1920 => "vsubsws",
+        1928 => "vsumsws",
+        1932 => "vmrgew",
+        _ => return None,
+    })
+}
+
+// VX-form operations with vD and vB
+fn vx_unary(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        266 => "vrefp",
+        330 => "vrsqrtefp",
+        394 => "vexptefp",
+        458 => "vlogefp",
+        522 => "vrfin",
+        526 => "vupkhsb",
+        586 => "vrfiz",
+        590 => "vupkhsh",
+        650 => "vrfip",
+        654 => "vupklsb",
+        714 => "vrfim",
+        718 => "vupklsh",
+        846 => "vupkhpx",
+        974 => "vupklpx",
+        1356 => "vgbbd",
+        1614 => "vupkhsw",
+        1742 => "vupklsw",
+        1794 => "vclzb",
+        1795 => "vpopcntb",
+        1858 => "vclzh",
+        1859 => "vpopcnth",
+        1922 => "vclzw",
+        1923 => "vpopcntw",
+        1986 => "vclzd",
+        1987 => "vpopcntd",
+        _ => return None,
+    })
+}
+
+// VC-form comparisons, by the 10 bit extended opcode
+fn vc_compare(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        6 => "vcmpequb",
+        7 => "vcmpneb",
+        70 => "vcmpequh",
+        71 => "vcmpneh",
+        134 => "vcmpequw",
+        135 => "vcmpnew",
+        263 => "vcmpnezb",
+        327 => "vcmpnezh",
+        391 => "vcmpnezw",
+        198 => "vcmpeqfp",
+        199 => "vcmpequd",
+        454 => "vcmpgefp",
+        518 => "vcmpgtub",
+        582 => "vcmpgtuh",
+        646 => "vcmpgtuw",
+        710 => "vcmpgtfp",
+        711 => "vcmpgtud",
+        774 => "vcmpgtsb",
+        838 => "vcmpgtsh",
+        902 => "vcmpgtsw",
+        966 => "vcmpbfp",
+        967 => "vcmpgtsd",
+        _ => return None,
+    })
+}
+
+/// Opcode 4: AltiVec arithmetic, logic, permutes and comparisons
+pub fn vector(word: u32, c: &Context) -> Decoded {
+    let (vd, va, vb, vc) = (rt(word), ra(word), rb(word), rc(word));
+
+    // VA-form, with four register operands
+    let va_form = match bits(word, 0, 6) {
+        32 => Some("vmhaddshs"),
+        33 => Some("vmhraddshs"),
+        34 => Some("vmladduhm"),
+        36 => Some("vmsumubm"),
+        37 => Some("vmsummbm"),
+        38 => Some("vmsumuhm"),
+        39 => Some("vmsumuhs"),
+        40 => Some("vmsumshm"),
+        41 => Some("vmsumshs"),
+        42 => Some("vsel"),
+        43 => Some("vperm"),
+        45 => Some("vpermxor"),
+        35 => Some("vmsumudm"),
+        59 => Some("vpermr"),
+        60 => Some("vaddeuqm"),
+        61 => Some("vaddecuq"),
+        62 => Some("vsubeuqm"),
+        63 => Some("vsubecuq"),
+        _ => None,
+    };
+    if let Some(name) = va_form {
+        return ins(name, vec![v(vd), v(va), v(vb), v(vc)]);
+    }
+    // Integer multiply-add on general purpose registers, which shares the VA-form
+    let multiply_add = match bits(word, 0, 6) {
+        48 => Some("maddhd"),
+        49 => Some("maddhdu"),
+        51 => Some("maddld"),
+        _ => None,
+    };
+    if let Some(name) = multiply_add {
+        return ins(name, vec![c.reg(vd), c.reg(va), c.reg(vb), c.reg(vc)]);
+    }
+    match bits(word, 0, 6) {
+        44 if !bit(word, 10) => return ins("vsldoi", vec![v(vd), v(va), v(vb), imm(bits(word, 6, 4) as i64)]),
+        // The multiply-add operands are in the order of the operation, vC before vB
+        46 => return ins("vmaddfp", vec![v(vd), v(va), v(vc), v(vb)]),
+        47 => return ins("vnmsubfp", vec![v(vd), v(va), v(vc), v(vb)]),
+        _ => {}
+    }
+
+    let xo = bits(word, 0, 11);
+    if let Some(name) = vx_binary(xo) {
+        return ins(name, vec![v(vd), v(va), v(vb)]);
+    }
+    if let Some(name) = vx_unary(xo) {
+        return if va == 0 { ins(name, vec![v(vd), v(vb)]) } else { None };
+    }
+    if let Some(name) = vc_compare(bits(word, 0, 10)) {
+        let dot = if bit(word, 10) { "." } else { "" };
+        return ins(&format!("{}{}", name, dot), vec![v(vd), v(va), v(vb)]);
+    }
+
+    match xo {
+        524 | 588 | 652 => {
+            let name = match xo {
+                524 => "vspltb",
+                588 => "vsplth",
+                _ => "vspltw",
+            };
+            ins(name, vec![v(vd), v(vb), imm(va as i64)])
+        }
+        780 | 844 | 908 if vb == 0 => {
+            let name = match xo {
+                780 => "vspltisb",
+                844 => "vspltish",
+                _ => "vspltisw",
+            };
+            ins(name, vec![v(vd), imm(sign_extend(va, 5))])
+        }
+        778 | 842 | 906 | 970 => {
+            let name = match xo {
+                778 => "vcfux",
+                842 => "vcfsx",
+                906 => "vctuxs",
+                _ => "vctsxs",
+            };
+            ins(name, vec![v(vd), v(vb), imm(va as i64)])
+        }
+        1480 if vb == 0 => ins("vsbox", vec![v(vd), v(va)]),
+        1540 if va == 0 && vb == 0 => ins("mfvscr", vec![v(vd)]),
+        1604 if vd == 0 && va == 0 => ins("mtvscr", vec![v(vb)]),
+        _ => None,
+    }
+}
+
+/// The AltiVec and VSX loads, stores and register moves of opcode 31
+pub fn extended(word: u32, c: &Context) -> Decoded {
+    let (rt, ra, rb) = (rt(word), ra(word), rb(word));
+    let xo = xo(word);
+
+    let altivec = match xo {
+        6 => Some(("lvsl", 0)),
+        7 => Some(("lvebx", 1)),
+        38 => Some(("lvsr", 0)),
+        39 => Some(("lvehx", 2)),
+        71 => Some(("lvewx", 4)),
+        103 => Some(("lvx", 16)),
+        135 => Some(("stvebx", 1)),
+        167 => Some(("stvehx", 2)),
+        199 => Some(("stvewx", 4)),
+        231 => Some(("stvx", 16)),
+        359 => Some(("lvxl", 16)),
+        487 => Some(("stvxl", 16)),
+        _ => None,
+    };
+    if let Some((name, size)) = altivec {
+        return if bit(word, 0) { None } else { ins(name, vec![v(rt), c.indexed(ra, rb, size)]) };
+    }
+
+    // The low bit extends the register number to the 64 VSX registers
+    let xt = rt | (bits(word, 0, 1) << 5);
+    let vsx = match xo {
+        12 => Some(("lxsiwzx", 4)),
+        76 => Some(("lxsiwax", 4)),
+        140 => Some(("stxsiwx", 4)),
+        268 => Some(("lxvx", 16)),
+        332 => Some(("lxvdsx", 8)),
+        396 => Some(("stxvx", 16)),
+        524 => Some(("lxsspx", 4)),
+        588 => Some(("lxsdx", 8)),
+        652 => Some(("stxsspx", 4)),
+        716 => Some(("stxsdx", 8)),
+        780 => Some(("lxvw4x", 16)),
+        844 => Some(("lxvd2x", 16)),
+        908 => Some(("stxvw4x", 16)),
+        972 => Some(("stxvd2x", 16)),
+        _ => None,
+    };
+    if let Some((name, size)) = vsx {
+        return ins(name, vec![vs(xt), c.indexed(ra, rb, size)]);
+    }
+
+    // Moves between general purpose and VSX registers
+    match xo {
+        51 | 115 if rb == 0 => ins(if xo == 51 { "mfvsrd" } else { "mfvsrwz" }, vec![c.reg(ra), vs(xt)]),
+        179 | 211 | 243 if rb == 0 => {
+            let name = match xo {
+                179 => "mtvsrd",
+                211 => "mtvsrwa",
+                _ => "mtvsrwz",
+            };
+            ins(name, vec![vs(xt), c.reg(ra)])
+        }
+        _ => None,
+    }
+}
+
+// XX3-form operations with three VSX registers, by the 8 bit extended opcode
+fn xx3(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        0 => "xsaddsp",
+        1 => "xsmaddasp",
+        8 => "xssubsp",
+        9 => "xsmaddmsp",
+        16 => "xsmulsp",
+        17 => "xsmsubasp",
+        18 => "xxmrghw",
+        24 => "xsdivsp",
+        25 => "xsmsubmsp",
+        33 => "xsmaddadp",
+        41 => "xsmaddmdp",
+        49 => "xsmsubadp",
+        57 => "xsmsubmdp",
+        65 => "xvmaddasp",
+        73 => "xvmaddmsp",
+        81 => "xvmsubasp",
+        89 => "xvmsubmsp",
+        97 => "xvmaddadp",
+        105 => "xvmaddmdp",
+        113 => "xvmsubadp",
+        121 => "xvmsubmdp",
+        129 => "xsnmaddasp",
+        137 => "xsnmaddmsp",
+        145 => "xsnmsubasp",
+        153 => "xsnmsubmsp",
+        160 => "xsmaxdp",
+        161 => "xsnmaddadp",
+        168 => "xsmindp",
+        169 => "xsnmaddmdp",
+        176 => "xscpsgndp",
+        177 => "xsnmsubadp",
+        185 => "xsnmsubmdp",
+        192 => "xvmaxsp",
+        193 => "xvnmaddasp",
+        200 => "xvminsp",
+        201 => "xvnmaddmsp",
+        208 => "xvcpsgnsp",
+        209 => "xvnmsubasp",
+        217 => "xvnmsubmsp",
+        224 => "xvmaxdp",
+        225 => "xvnmaddadp",
+        232 => "xvmindp",
+        233 => "xvnmaddmdp",
+        240 => "xvcpsgndp",
+        241 => "xvnmsubadp",
+        249 => "xvnmsubmdp",
+        32 => "xsadddp",
+        40 => "xssubdp",
+        48 => "xsmuldp",
+        50 => "xxmrglw",
+        56 => "xsdivdp",
+        64 => "xvaddsp",
+        72 => "xvsubsp",
+        80 => "xvmulsp",
+        88 => "xvdivsp",
+        96 => "xvadddp",
+        104 => "xvsubdp",
+        112 => "xvmuldp",
+        120 => "xvdivdp",
+        130 => "xxland",
+        138 => "xxlandc",
+        146 => "xxlor",
+        154 => "xxlxor",
+        162 => "xxlnor",
+        170 => "xxlorc",
+        178 => "xxlnand",
+        186 => "xxleqv",
+        _ => return None,
+    })
+}
+
+// XX3-form vector comparisons, by the 7 bit extended opcode with the record bit above it
+fn xx3_compare(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        67 => "xvcmpeqsp",
+        75 => "xvcmpgtsp",
+        83 => "xvcmpgesp",
+        99 => "xvcmpeqdp",
+        107 => "xvcmpgtdp",
+        115 => "xvcmpgedp",
+        _ => return None,
+    })
+}
+
+// XX2-form operations with two VSX registers, by the 9 bit extended opcode
+fn xx2(xo: u32) -> Option<&'static str> {
+    Some(match xo {
+        265 => "xscvdpsp",
+        267 => "xscvdpspn",
+        329 => "xscvspdp",
+        331 => "xscvspdpn",
+        _ => return None,
+    })
+}
+
+/// Opcode 60: the common VSX arithmetic, logic and permutes
+pub fn vsx(word: u32, _c: &Context) -> Decoded {
+    let xt = rt(word) | (bits(word, 0, 1) << 5);
+    let xa = ra(word) | (bits(word, 2, 1) << 5);
+    let xb = rb(word) | (bits(word, 1, 1) << 5);
+
+    if let Some(name) = xx3(bits(word, 3, 8)) {
+        return ins(name, vec![vs(xt), vs(xa), vs(xb)]);
+    }
+    if let Some(name) = xx3_compare(bits(word, 3, 7)) {
+        let dot = if bit(word, 10) { "." } else { "" };
+        return ins(&format!("{}{}", name, dot), vec![vs(xt), vs(xa), vs(xb)]);
+    }
+    if let Some(name) = xx2(bits(word, 2, 9)) {
+        return if ra(word) == 0 { ins(name, vec![vs(xt), vs(xb)]) } else { None };
+    }
+
+    // XX4-form select, with a fourth register
+    if bits(word, 4, 2) == 3 {
+        let xc = rc(word) | (bits(word, 3, 1) << 5);
+        return ins("xxsel", vec![vs(xt), vs(xa), vs(xb), vs(xc)]);
+    }
+
+    match bits(word, 3, 5) {
+        // Selects a doubleword of each source
+        10 if !bit(word, 10) => match (bits(word, 8, 2), xa == xb) {
+            (0, true) => ins("xxspltd", vec![vs(xt), vs(xa), imm(0)]),
+            (3, true) => ins("xxspltd", vec![vs(xt), vs(xa), imm(1)]),
+            (2, true) => ins("xxswapd", vec![vs(xt), vs(xa)]),
+            (0, false) => ins("xxmrghd", vec![vs(xt), vs(xa), vs(xb)]),
+            (3, false) => ins("xxmrgld", vec![vs(xt), vs(xa), vs(xb)]),
+            (dm, _) => ins("xxpermdi", vec![vs(xt), vs(xa), vs(xb), imm(dm as i64)]),
+        },
+        2 if !bit(word, 10) => ins("xxsldwi", vec![vs(xt), vs(xa), vs(xb), imm(bits(word, 8, 2) as i64)]),
+        _ => match bits(word, 3, 8) {
+            35 if bits(word, 21, 2) == 0 && !bit(word, 0) => {
+                ins("xscmpudp", vec![Operand::Register(cr(bits(word, 23, 3))), vs(xa), vs(xb)])
+            }
+            _ => None,
+        },
+    }
+}
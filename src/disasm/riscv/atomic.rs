@@ -0,0 +1,42 @@
+use super::registers::ZERO;
+use super::{bit, bits, funct3, memory, rd, rs1, rs2, x, Decoded};
+
+// The A extension: load reserved / store conditional and atomic memory operations
+pub fn atomic(word: u32, xlen64: bool) -> Decoded {
+    let (width, size) = match funct3(word) {
+        0b010 => ("w", 4),
+        0b011 if xlen64 => ("d", 8),
+        _ => return None,
+    };
+
+    let operation = match bits(word, 27, 5) {
+        0b00010 if rs2(word) != ZERO => return None,
+        0b00010 => "lr",
+        0b00011 => "sc",
+        0b00001 => "amoswap",
+        0b00000 => "amoadd",
+        0b00100 => "amoxor",
+        0b01100 => "amoand",
+        0b01000 => "amoor",
+        0b10000 => "amomin",
+        0b10100 => "amomax",
+        0b11000 => "amominu",
+        0b11100 => "amomaxu",
+        _ => return None,
+    };
+
+    let ordering = match (bit(word, 26), bit(word, 25)) {
+        (true, true) => ".aqrl",
+        (true, false) => ".aq",
+        (false, true) => ".rl",
+        (false, false) => "",
+    };
+
+    let mut operands = vec![x(rd(word), xlen64)];
+    if operation != "lr" {
+        operands.push(x(rs2(word), xlen64));
+    }
+    operands.push(memory(rs1(word), 0, size, xlen64));
+
+    Some((format!("{}.{}{}", operation, width, ordering), operands))
+}
@@ -0,0 +1,212 @@
+use super::registers::{RA, SP, ZERO};
+
+// Compressed instructions are expanded to the 32 bit instruction they stand for, so the rest of the decoder only
+// deals with one encoding. Offsets are kept relative to the compressed instruction, which has the same address.
+
+const LOAD: u32 = 0b0000011;
+const LOAD_FP: u32 = 0b0000111;
+const OP_IMM: u32 = 0b0010011;
+const OP_IMM_32: u32 = 0b0011011;
+const STORE: u32 = 0b0100011;
+const STORE_FP: u32 = 0b0100111;
+const OP: u32 = 0b0110011;
+const OP_32: u32 = 0b0111011;
+const LUI: u32 = 0b0110111;
+const BRANCH: u32 = 0b1100011;
+const JALR: u32 = 0b1100111;
+const JAL: u32 = 0b1101111;
+const SYSTEM: u32 = 0b1110011;
+
+fn bits(half: u32, low: u32, count: u32) -> u32 {
+    (half >> low) & ((1 << count) - 1)
+}
+
+fn bit(half: u32, n: u32) -> u32 {
+    (half >> n) & 1
+}
+
+// One of the 8 registers x8-x15 addressable by the 3 bit fields
+fn short_register(field: u32) -> u32 {
+    field + 8
+}
+
+fn r_type(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    funct7 << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode
+}
+
+fn i_type(imm: i32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    ((imm as u32) & 0xfff) << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode
+}
+
+fn s_type(imm: i32, rs2: u32, rs1: u32, funct3: u32, opcode: u32) -> u32 {
+    let imm = imm as u32;
+    bits(imm, 5, 7) << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | bits(imm, 0, 5) << 7 | opcode
+}
+
+fn b_type(imm: i32, rs2: u32, rs1: u32, funct3: u32) -> u32 {
+    let imm = imm as u32;
+    bit(imm, 12) << 31 | bits(imm, 5, 6) << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12
+        | bits(imm, 1, 4) << 8 | bit(imm, 11) << 7 | BRANCH
+}
+
+fn j_type(imm: i32, rd: u32) -> u32 {
+    let imm = imm as u32;
+    bit(imm, 20) << 31 | bits(imm, 1, 10) << 21 | bit(imm, 11) << 20 | bits(imm, 12, 8) << 12 | rd << 7 | JAL
+}
+
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+/// The 32 bit instruction equivalent to a 16 bit one, None for reserved encodings
+pub fn expand(half: u16, xlen64: bool) -> Option<u32> {
+    let half = half as u32;
+    match half & 3 {
+        0b00 => quadrant0(half, xlen64),
+        0b01 => quadrant1(half, xlen64),
+        0b10 => quadrant2(half, xlen64),
+        _ => None,
+    }
+}
+
+// Loads and stores relative to one of x8-x15
+fn quadrant0(half: u32, xlen64: bool) -> Option<u32> {
+    let rd = short_register(bits(half, 2, 3));
+    let rs1 = short_register(bits(half, 7, 3));
+    // Offsets of word and double word accesses
+    let word_offset = (bits(half, 10, 3) << 3 | bit(half, 6) << 2 | bit(half, 5) << 6) as i32;
+    let double_offset = (bits(half, 10, 3) << 3 | bits(half, 5, 2) << 6) as i32;
+
+    match bits(half, 13, 3) {
+        0b000 => {
+            // c.addi4spn
+            let imm = bits(half, 11, 2) << 4 | bits(half, 7, 4) << 6 | bit(half, 6) << 2 | bit(half, 5) << 3;
+            if imm == 0 {
+                return None;
+            }
+            Some(i_type(imm as i32, SP, 0b000, rd, OP_IMM))
+        }
+        0b001 => Some(i_type(double_offset, rs1, 0b011, rd, LOAD_FP)),
+        0b010 => Some(i_type(word_offset, rs1, 0b010, rd, LOAD)),
+        0b011 if xlen64 => Some(i_type(double_offset, rs1, 0b011, rd, LOAD)),
+        0b011 => Some(i_type(word_offset, rs1, 0b010, rd, LOAD_FP)),
+        0b101 => Some(s_type(double_offset, rd, rs1, 0b011, STORE_FP)),
+        0b110 => Some(s_type(word_offset, rd, rs1, 0b010, STORE)),
+        0b111 if xlen64 => Some(s_type(double_offset, rd, rs1, 0b011, STORE)),
+        0b111 => Some(s_type(word_offset, rd, rs1, 0b010, STORE_FP)),
+        _ => None,
+    }
+}
+
+// Immediates, arithmetic, jumps and branches
+fn quadrant1(half: u32, xlen64: bool) -> Option<u32> {
+    let rd = bits(half, 7, 5);
+    let imm = sign_extend(bit(half, 12) << 5 | bits(half, 2, 5), 6);
+    let jump_offset = sign_extend(
+        bit(half, 12) << 11 | bit(half, 11) << 4 | bits(half, 9, 2) << 8 | bit(half, 8) << 10
+            | bit(half, 7) << 6 | bit(half, 6) << 7 | bits(half, 3, 3) << 1 | bit(half, 2) << 5,
+        12,
+    );
+    let branch_offset = sign_extend(
+        bit(half, 12) << 8 | bits(half, 10, 2) << 3 | bits(half, 5, 2) << 6 | bits(half, 3, 2) << 1 | bit(half, 2) << 5,
+        9,
+    );
+
+    match bits(half, 13, 3) {
+        0b000 => Some(i_type(imm, rd, 0b000, rd, OP_IMM)),
+        // c.addiw
+        0b001 if xlen64 && rd != ZERO => Some(i_type(imm, rd, 0b000, rd, OP_IMM_32)),
+        0b001 if xlen64 => None,
+        // c.jal
+        0b001 => Some(j_type(jump_offset, RA)),
+        // c.li
+        0b010 => Some(i_type(imm, ZERO, 0b000, rd, OP_IMM)),
+        0b011 if rd == SP => {
+            // c.addi16sp
+            let imm = sign_extend(
+                bit(half, 12) << 9 | bit(half, 6) << 4 | bit(half, 5) << 6 | bits(half, 3, 2) << 7 | bit(half, 2) << 5,
+                10,
+            );
+            if imm == 0 {
+                return None;
+            }
+            Some(i_type(imm, SP, 0b000, SP, OP_IMM))
+        }
+        0b011 => {
+            // c.lui
+            if imm == 0 {
+                return None;
+            }
+            Some(((imm as u32) & 0xfffff) << 12 | rd << 7 | LUI)
+        }
+        0b100 => arithmetic(half, xlen64),
+        // c.j
+        0b101 => Some(j_type(jump_offset, ZERO)),
+        // c.beqz, c.bnez
+        0b110 => Some(b_type(branch_offset, ZERO, short_register(bits(half, 7, 3)), 0b000)),
+        _ => Some(b_type(branch_offset, ZERO, short_register(bits(half, 7, 3)), 0b001)),
+    }
+}
+
+// Register-register and register-immediate operations on x8-x15
+fn arithmetic(half: u32, xlen64: bool) -> Option<u32> {
+    let rd = short_register(bits(half, 7, 3));
+    let rs2 = short_register(bits(half, 2, 3));
+    let shamt = bit(half, 12) << 5 | bits(half, 2, 5);
+
+    match bits(half, 10, 2) {
+        0b00 | 0b01 if !xlen64 && shamt >= 32 => None,
+        0b00 => Some(i_type(shamt as i32, rd, 0b101, rd, OP_IMM)),
+        0b01 => Some(i_type((0b0100000 << 5 | shamt) as i32, rd, 0b101, rd, OP_IMM)),
+        0b10 => Some(i_type(sign_extend(shamt, 6), rd, 0b111, rd, OP_IMM)),
+        _ => match (bit(half, 12), bits(half, 5, 2)) {
+            (0, 0b00) => Some(r_type(0b0100000, rs2, rd, 0b000, rd, OP)),
+            (0, 0b01) => Some(r_type(0, rs2, rd, 0b100, rd, OP)),
+            (0, 0b10) => Some(r_type(0, rs2, rd, 0b110, rd, OP)),
+            (0, _) => Some(r_type(0, rs2, rd, 0b111, rd, OP)),
+            (_, 0b00) if xlen64 => Some(r_type(0b0100000, rs2, rd, 0b000, rd, OP_32)),
+            (_, 0b01) if xlen64 => Some(r_type(0, rs2, rd, 0b000, rd, OP_32)),
+            _ => None,
+        },
+    }
+}
+
+// Stack pointer relative loads and stores, moves, jumps through registers
+fn quadrant2(half: u32, xlen64: bool) -> Option<u32> {
+    let rd = bits(half, 7, 5);
+    let rs2 = bits(half, 2, 5);
+    let shamt = bit(half, 12) << 5 | bits(half, 2, 5);
+    let word_load_offset = (bit(half, 12) << 5 | bits(half, 4, 3) << 2 | bits(half, 2, 2) << 6) as i32;
+    let double_load_offset = (bit(half, 12) << 5 | bits(half, 5, 2) << 3 | bits(half, 2, 3) << 6) as i32;
+    let word_store_offset = (bits(half, 9, 4) << 2 | bits(half, 7, 2) << 6) as i32;
+    let double_store_offset = (bits(half, 10, 3) << 3 | bits(half, 7, 3) << 6) as i32;
+
+    match bits(half, 13, 3) {
+        0b000 if !xlen64 && shamt >= 32 => None,
+        0b000 => Some(i_type(shamt as i32, rd, 0b001, rd, OP_IMM)),
+        0b001 => Some(i_type(double_load_offset, SP, 0b011, rd, LOAD_FP)),
+        0b010 if rd == ZERO => None,
+        0b010 => Some(i_type(word_load_offset, SP, 0b010, rd, LOAD)),
+        0b011 if xlen64 && rd == ZERO => None,
+        0b011 if xlen64 => Some(i_type(double_load_offset, SP, 0b011, rd, LOAD)),
+        0b011 => Some(i_type(word_load_offset, SP, 0b010, rd, LOAD_FP)),
+        0b100 => match (bit(half, 12), rd, rs2) {
+            // c.jr
+            (0, ZERO, ZERO) => None,
+            (0, _, ZERO) => Some(i_type(0, rd, 0b000, ZERO, JALR)),
+            // c.mv
+            (0, _, _) => Some(r_type(0, rs2, ZERO, 0b000, rd, OP)),
+            // c.ebreak
+            (_, ZERO, ZERO) => Some(1 << 20 | SYSTEM),
+            // c.jalr
+            (_, _, ZERO) => Some(i_type(0, rd, 0b000, RA, JALR)),
+            // c.add
+            _ => Some(r_type(0, rs2, rd, 0b000, rd, OP)),
+        },
+        0b101 => Some(s_type(double_store_offset, rs2, SP, 0b011, STORE_FP)),
+        0b110 => Some(s_type(word_store_offset, rs2, SP, 0b010, STORE)),
+        _ if xlen64 => Some(s_type(double_store_offset, rs2, SP, 0b011, STORE)),
+        _ => Some(s_type(word_store_offset, rs2, SP, 0b010, STORE_FP)),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::disasm::Operand;
+
+use super::registers::ZERO;
+use super::{bits, f, funct3, funct7, i_immediate, ins, memory, rd, rs1, rs2, sign_extend, x, Decoded};
+
+// Rounding modes, 5 and 6 are reserved and 7 selects the mode in frm
+const ROUNDING_MODES: [&str; 5] = ["rne", "rtz", "rdn", "rup", "rmm"];
+const DYNAMIC_ROUNDING: u32 = 7;
+
+// Width in bits and mnemonic suffix of the fmt field, F and D only
+fn format_of(fmt: u32) -> Option<(u16, &'static str)> {
+    match fmt {
+        0b00 => Some((32, "s")),
+        0b01 => Some((64, "d")),
+        _ => None,
+    }
+}
+
+// Appends the rounding mode when it isn't the dynamic one
+fn with_rounding(mnemonic: String, mut operands: Vec<Operand>, word: u32) -> Decoded {
+    match funct3(word) {
+        DYNAMIC_ROUNDING => {}
+        rm if (rm as usize) < ROUNDING_MODES.len() => operands.push(Operand::Name(ROUNDING_MODES[rm as usize])),
+        _ => return None,
+    }
+    Some((mnemonic, operands))
+}
+
+pub fn load(word: u32, xlen64: bool) -> Decoded {
+    let (mnemonic, width) = match funct3(word) {
+        0b010 => ("flw", 32),
+        0b011 => ("fld", 64),
+        _ => return None,
+    };
+    ins(mnemonic, vec![f(rd(word), width), memory(rs1(word), i_immediate(word), width / 8, xlen64)])
+}
+
+pub fn store(word: u32, xlen64: bool) -> Decoded {
+    let (mnemonic, width) = match funct3(word) {
+        0b010 => ("fsw", 32),
+        0b011 => ("fsd", 64),
+        _ => return None,
+    };
+    let offset = sign_extend(funct7(word) << 5 | rd(word), 12);
+    ins(mnemonic, vec![f(rs2(word), width), memory(rs1(word), offset, width / 8, xlen64)])
+}
+
+// fmadd, fmsub, fnmsub and fnmadd
+pub fn fused(word: u32) -> Decoded {
+    let (width, suffix) = format_of(bits(word, 25, 2))?;
+    let operation = match bits(word, 2, 5) {
+        0b10000 => "fmadd",
+        0b10001 => "fmsub",
+        0b10010 => "fnmsub",
+        _ => "fnmadd",
+    };
+    let operands = vec![
+        f(rd(word), width),
+        f(rs1(word), width),
+        f(rs2(word), width),
+        f(bits(word, 27, 5), width),
+    ];
+    with_rounding(format!("{}.{}", operation, suffix), operands, word)
+}
+
+pub fn op_fp(word: u32, xlen64: bool) -> Decoded {
+    let (width, suffix) = format_of(bits(word, 25, 2))?;
+    let (rd, rs1, rs2) = (rd(word), rs1(word), rs2(word));
+    let (d, s1, s2) = (f(rd, width), f(rs1, width), f(rs2, width));
+    let named = |operation: &str| format!("{}.{}", operation, suffix);
+
+    match (bits(word, 27, 5), funct3(word)) {
+        (0b00000, _) => with_rounding(named("fadd"), vec![d, s1, s2], word),
+        (0b00001, _) => with_rounding(named("fsub"), vec![d, s1, s2], word),
+        (0b00010, _) => with_rounding(named("fmul"), vec![d, s1, s2], word),
+        (0b00011, _) => with_rounding(named("fdiv"), vec![d, s1, s2], word),
+        (0b01011, _) if rs2 == ZERO => with_rounding(named("fsqrt"), vec![d, s1], word),
+        (0b00100, 0b000) if rs1 == rs2 => Some((named("fmv"), vec![d, s1])),
+        (0b00100, 0b000) => Some((named("fsgnj"), vec![d, s1, s2])),
+        (0b00100, 0b001) if rs1 == rs2 => Some((named("fneg"), vec![d, s1])),
+        (0b00100, 0b001) => Some((named("fsgnjn"), vec![d, s1, s2])),
+        (0b00100, 0b010) if rs1 == rs2 => Some((named("fabs"), vec![d, s1])),
+        (0b00100, 0b010) => Some((named("fsgnjx"), vec![d, s1, s2])),
+        (0b00101, 0b000) => Some((named("fmin"), vec![d, s1, s2])),
+        (0b00101, 0b001) => Some((named("fmax"), vec![d, s1, s2])),
+        (0b01000, _) => {
+            // Conversion between the two precisions, rs2 holds the source format
+            let (source_width, source_suffix) = format_of(rs2)?;
+            if source_width == width {
+                return None;
+            }
+            with_rounding(format!("fcvt.{}.{}", suffix, source_suffix), vec![d, f(rs1, source_width)], word)
+        }
+        (0b10100, 0b010) => Some((named("feq"), vec![x(rd, xlen64), s1, s2])),
+        (0b10100, 0b001) => Some((named("flt"), vec![x(rd, xlen64), s1, s2])),
+        (0b10100, 0b000) => Some((named("fle"), vec![x(rd, xlen64), s1, s2])),
+        (0b11000, _) => {
+            let integer = integer_format(rs2, xlen64)?;
+            with_rounding(format!("fcvt.{}.{}", integer, suffix), vec![x(rd, xlen64), s1], word)
+        }
+        (0b11010, _) => {
+            let integer = integer_format(rs2, xlen64)?;
+            with_rounding(format!("fcvt.{}.{}", suffix, integer), vec![d, x(rs1, xlen64)], word)
+        }
+        (0b11100, 0b000) if rs2 == ZERO && (width == 32 || xlen64) => {
+            let name = if width == 32 { "fmv.x.w" } else { "fmv.x.d" };
+            ins(name, vec![x(rd, xlen64), s1])
+        }
+        (0b11100, 0b001) if rs2 == ZERO => Some((named("fclass"), vec![x(rd, xlen64), s1])),
+        (0b11110, 0b000) if rs2 == ZERO && (width == 32 || xlen64) => {
+            let name = if width == 32 { "fmv.w.x" } else { "fmv.d.x" };
+            ins(name, vec![d, x(rs1, xlen64)])
+        }
+        _ => None,
+    }
+}
+
+// Integer side of a conversion, selected by the rs2 field
+fn integer_format(field: u32, xlen64: bool) -> Option<&'static str> {
+    match field {
+        0 => Some("w"),
+        1 => Some("wu"),
+        2 if xlen64 => Some("l"),
+        3 if xlen64 => Some("lu"),
+        _ => None,
+    }
+}
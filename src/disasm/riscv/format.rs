@@ -0,0 +1,52 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand, Register, RegisterClass};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma without a space, upper
+// immediates and shift amounts are hex, everything else is decimal
+
+pub fn format(instruction: &Instruction) -> String {
+    let mnemonic = &instruction.mnemonic;
+    let hex = hex_immediates(mnemonic);
+    // Atomics only take a base register, written without an offset
+    let atomic = mnemonic.starts_with("lr.") || mnemonic.starts_with("sc.") || mnemonic.starts_with("amo");
+    let operands: Vec<String> = instruction.operands.iter().map(|x| operand(x, hex, atomic)).collect();
+
+    let mut res = mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(","));
+    }
+    res
+}
+
+fn hex_immediates(mnemonic: &str) -> bool {
+    matches!(mnemonic, "lui" | "auipc" | "slli" | "srli" | "srai" | "slliw" | "srliw" | "sraiw")
+}
+
+fn register(register: &Register) -> String {
+    if register.class == RegisterClass::Special && register.name.is_empty() {
+        // Non standard CSRs are shown by their number
+        return format!("0x{:x}", register.number);
+    }
+    register.name.to_string()
+}
+
+fn memory(mem: &MemoryOperand, atomic: bool) -> String {
+    let base = mem.base.as_ref().map(register).unwrap_or_default();
+    if atomic {
+        format!("({})", base)
+    } else {
+        format!("{}({})", mem.displacement, base)
+    }
+}
+
+fn operand(operand: &Operand, hex: bool, atomic: bool) -> String {
+    match operand {
+        Operand::Register(x) => register(x),
+        Operand::Immediate { value, .. } if hex => format!("0x{:x}", value),
+        Operand::Immediate { value, .. } => format!("{}", value),
+        Operand::Memory(mem) => memory(mem, atomic),
+        Operand::Address(address) => format!("0x{:x}", address),
+        Operand::Name(name) => name.to_string(),
+        other => format!("{:?}", other),
+    }
+}
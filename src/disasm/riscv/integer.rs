@@ -0,0 +1,215 @@
+use crate::disasm::Operand;
+
+use super::registers::{RA, ZERO};
+use super::{bit, bits, funct3, funct7, i_immediate, imm, ins, memory, rd, rs1, rs2, sign_extend, x, Decoded};
+
+// Predecessor and successor sets of fences, indexed by the 4 bit field
+const FENCE_SETS: [&str; 16] = [
+    "0", "w", "r", "rw", "o", "ow", "or", "orw",
+    "i", "iw", "ir", "irw", "io", "iow", "ior", "iorw",
+];
+
+// Address of a pc relative jump or branch, wrapped to the register width
+fn target(address: u64, offset: i64, xlen64: bool) -> Operand {
+    let target = address.wrapping_add(offset as u64);
+    Operand::Address(if xlen64 { target } else { target & 0xffff_ffff })
+}
+
+pub fn load(word: u32, xlen64: bool) -> Decoded {
+    let (mnemonic, size) = match funct3(word) {
+        0b000 => ("lb", 1),
+        0b001 => ("lh", 2),
+        0b010 => ("lw", 4),
+        0b011 if xlen64 => ("ld", 8),
+        0b100 => ("lbu", 1),
+        0b101 => ("lhu", 2),
+        0b110 if xlen64 => ("lwu", 4),
+        _ => return None,
+    };
+    ins(mnemonic, vec![x(rd(word), xlen64), memory(rs1(word), i_immediate(word), size, xlen64)])
+}
+
+pub fn store(word: u32, xlen64: bool) -> Decoded {
+    let (mnemonic, size) = match funct3(word) {
+        0b000 => ("sb", 1),
+        0b001 => ("sh", 2),
+        0b010 => ("sw", 4),
+        0b011 if xlen64 => ("sd", 8),
+        _ => return None,
+    };
+    let offset = sign_extend(funct7(word) << 5 | rd(word), 12);
+    ins(mnemonic, vec![x(rs2(word), xlen64), memory(rs1(word), offset, size, xlen64)])
+}
+
+pub fn fence(word: u32) -> Decoded {
+    match funct3(word) {
+        0b000 => {
+            let (mode, predecessor, successor) = (bits(word, 28, 4), bits(word, 24, 4), bits(word, 20, 4));
+            match (mode, predecessor, successor) {
+                (0b1000, 0b0011, 0b0011) => ins("fence.tso", vec![]),
+                (0b0000, 0b1111, 0b1111) => ins("fence", vec![]),
+                (0b0000, _, _) => ins(
+                    "fence",
+                    vec![Operand::Name(FENCE_SETS[predecessor as usize]), Operand::Name(FENCE_SETS[successor as usize])],
+                ),
+                _ => None,
+            }
+        }
+        0b001 => ins("fence.i", vec![]),
+        _ => None,
+    }
+}
+
+pub fn op_imm(word: u32, xlen64: bool) -> Decoded {
+    let (rd, rs1, value) = (rd(word), rs1(word), i_immediate(word));
+    let (d, s) = (x(rd, xlen64), x(rs1, xlen64));
+
+    match funct3(word) {
+        0b000 if rd == ZERO && rs1 == ZERO && value == 0 => ins("nop", vec![]),
+        0b000 if rs1 == ZERO => ins("li", vec![d, imm(value)]),
+        0b000 if value == 0 => ins("mv", vec![d, s]),
+        0b000 => ins("addi", vec![d, s, imm(value)]),
+        0b010 => ins("slti", vec![d, s, imm(value)]),
+        0b011 if value == 1 => ins("seqz", vec![d, s]),
+        0b011 => ins("sltiu", vec![d, s, imm(value)]),
+        0b100 if value == -1 => ins("not", vec![d, s]),
+        0b100 => ins("xori", vec![d, s, imm(value)]),
+        0b110 => ins("ori", vec![d, s, imm(value)]),
+        0b111 => ins("andi", vec![d, s, imm(value)]),
+        funct3 => {
+            // Shifts by a 5 bit amount on RV32 and 6 bit on RV64
+            let shamt = bits(word, 20, 6);
+            if !xlen64 && bit(word, 25) {
+                return None;
+            }
+            let mnemonic = match (funct3, bits(word, 26, 6)) {
+                (0b001, 0b000000) => "slli",
+                (0b101, 0b000000) => "srli",
+                (0b101, 0b010000) => "srai",
+                _ => return None,
+            };
+            ins(mnemonic, vec![d, s, imm(shamt as i64)])
+        }
+    }
+}
+
+pub fn op_imm_32(word: u32) -> Decoded {
+    let (d, s) = (x(rd(word), true), x(rs1(word), true));
+    let shamt = imm(bits(word, 20, 5) as i64);
+
+    match (funct3(word), funct7(word)) {
+        (0b000, _) if i_immediate(word) == 0 => ins("sext.w", vec![d, s]),
+        (0b000, _) => ins("addiw", vec![d, s, imm(i_immediate(word))]),
+        (0b001, 0b0000000) => ins("slliw", vec![d, s, shamt]),
+        (0b101, 0b0000000) => ins("srliw", vec![d, s, shamt]),
+        (0b101, 0b0100000) => ins("sraiw", vec![d, s, shamt]),
+        _ => None,
+    }
+}
+
+// lui and auipc
+pub fn upper(word: u32, xlen64: bool) -> Decoded {
+    let mnemonic = if bit(word, 5) { "lui" } else { "auipc" };
+    ins(mnemonic, vec![x(rd(word), xlen64), imm(bits(word, 12, 20) as i64)])
+}
+
+pub fn op(word: u32, xlen64: bool) -> Decoded {
+    let (rd, rs1, rs2) = (rd(word), rs1(word), rs2(word));
+    let (d, s1, s2) = (x(rd, xlen64), x(rs1, xlen64), x(rs2, xlen64));
+
+    let mnemonic = match (funct7(word), funct3(word)) {
+        (0b0000000, 0b000) if rs1 == ZERO => return ins("mv", vec![d, s2]),
+        (0b0000000, 0b000) => "add",
+        (0b0100000, 0b000) if rs1 == ZERO => return ins("neg", vec![d, s2]),
+        (0b0100000, 0b000) => "sub",
+        (0b0000000, 0b001) => "sll",
+        (0b0000000, 0b010) if rs2 == ZERO => return ins("sltz", vec![d, s1]),
+        (0b0000000, 0b010) if rs1 == ZERO => return ins("sgtz", vec![d, s2]),
+        (0b0000000, 0b010) => "slt",
+        (0b0000000, 0b011) if rs1 == ZERO => return ins("snez", vec![d, s2]),
+        (0b0000000, 0b011) => "sltu",
+        (0b0000000, 0b100) => "xor",
+        (0b0000000, 0b101) => "srl",
+        (0b0100000, 0b101) => "sra",
+        (0b0000000, 0b110) => "or",
+        (0b0000000, 0b111) => "and",
+        (0b0000001, funct3) => ["mul", "mulh", "mulhsu", "mulhu", "div", "divu", "rem", "remu"][funct3 as usize],
+        _ => return None,
+    };
+    ins(mnemonic, vec![d, s1, s2])
+}
+
+pub fn op_32(word: u32) -> Decoded {
+    let (rs1, d, s1, s2) = (rs1(word), x(rd(word), true), x(rs1(word), true), x(rs2(word), true));
+
+    let mnemonic = match (funct7(word), funct3(word)) {
+        (0b0000000, 0b000) => "addw",
+        (0b0100000, 0b000) if rs1 == ZERO => return ins("negw", vec![d, s2]),
+        (0b0100000, 0b000) => "subw",
+        (0b0000000, 0b001) => "sllw",
+        (0b0000000, 0b101) => "srlw",
+        (0b0100000, 0b101) => "sraw",
+        (0b0000001, 0b000) => "mulw",
+        (0b0000001, 0b100) => "divw",
+        (0b0000001, 0b101) => "divuw",
+        (0b0000001, 0b110) => "remw",
+        (0b0000001, 0b111) => "remuw",
+        _ => return None,
+    };
+    ins(mnemonic, vec![d, s1, s2])
+}
+
+pub fn branch(word: u32, address: u64, xlen64: bool) -> Decoded {
+    let offset = sign_extend(
+        (bit(word, 31) as u32) << 12 | (bit(word, 7) as u32) << 11 | bits(word, 25, 6) << 5 | bits(word, 8, 4) << 1,
+        13,
+    );
+    let (rs1, rs2) = (rs1(word), rs2(word));
+    let (s1, s2, target) = (x(rs1, xlen64), x(rs2, xlen64), target(address, offset, xlen64));
+
+    match funct3(word) {
+        0b000 if rs2 == ZERO => ins("beqz", vec![s1, target]),
+        0b000 => ins("beq", vec![s1, s2, target]),
+        0b001 if rs2 == ZERO => ins("bnez", vec![s1, target]),
+        0b001 => ins("bne", vec![s1, s2, target]),
+        0b100 if rs2 == ZERO => ins("bltz", vec![s1, target]),
+        0b100 if rs1 == ZERO => ins("bgtz", vec![s2, target]),
+        0b100 => ins("blt", vec![s1, s2, target]),
+        0b101 if rs2 == ZERO => ins("bgez", vec![s1, target]),
+        0b101 if rs1 == ZERO => ins("blez", vec![s2, target]),
+        0b101 => ins("bge", vec![s1, s2, target]),
+        0b110 => ins("bltu", vec![s1, s2, target]),
+        0b111 => ins("bgeu", vec![s1, s2, target]),
+        _ => None,
+    }
+}
+
+pub fn jal(word: u32, address: u64, xlen64: bool) -> Decoded {
+    let offset = sign_extend(
+        (bit(word, 31) as u32) << 20 | bits(word, 12, 8) << 12 | (bit(word, 20) as u32) << 11 | bits(word, 21, 10) << 1,
+        21,
+    );
+    let target = target(address, offset, xlen64);
+
+    match rd(word) {
+        ZERO => ins("j", vec![target]),
+        RA => ins("jal", vec![target]),
+        rd => ins("jal", vec![x(rd, xlen64), target]),
+    }
+}
+
+pub fn jalr(word: u32, xlen64: bool) -> Decoded {
+    if funct3(word) != 0 {
+        return None;
+    }
+    let (rd, rs1, offset) = (rd(word), rs1(word), i_immediate(word));
+
+    match (rd, rs1, offset) {
+        (ZERO, RA, 0) => ins("ret", vec![]),
+        (ZERO, _, 0) => ins("jr", vec![x(rs1, xlen64)]),
+        (ZERO, _, _) => ins("jr", vec![memory(rs1, offset, 0, xlen64)]),
+        (RA, _, 0) => ins("jalr", vec![x(rs1, xlen64)]),
+        (RA, _, _) => ins("jalr", vec![memory(rs1, offset, 0, xlen64)]),
+        _ => ins("jalr", vec![x(rd, xlen64), memory(rs1, offset, 0, xlen64)]),
+    }
+}
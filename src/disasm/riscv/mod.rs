@@ -0,0 +1,260 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, FlowKind, Instruction, MemoryOperand, Operand, RegisterClass};
+
+mod registers;
+use self::registers::{fpr, gpr};
+
+mod compressed;
+mod integer;
+mod system;
+mod atomic;
+mod float;
+
+mod format;
+pub use self::format::format;
+
+// Instructions are always little endian, whatever the endianness of the data
+const LENGTH: usize = 4;
+const COMPRESSED_LENGTH: usize = 2;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is reserved
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// Decoder for RV32 and RV64 with the IMAFDC extensions, Zicsr and Zifencei
+#[derive(Debug, Clone)]
+pub struct RiscVDecoder {
+    xlen64: bool,
+    // Whether the C extension is enabled, otherwise 16 bit encodings are invalid
+    compressed: bool,
+}
+
+impl RiscVDecoder {
+    pub fn new(xlen64: bool, compressed: bool) -> RiscVDecoder {
+        RiscVDecoder { xlen64, compressed }
+    }
+}
+
+impl Decoder for RiscVDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < COMPRESSED_LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let half = u16::from_le_bytes(bytes[..COMPRESSED_LENGTH].try_into().unwrap());
+
+        let (word, length) = if half & 3 != 3 {
+            if !self.compressed {
+                return Err(DecodeError::InvalidOpcode(half as u32));
+            }
+            if half == 0 {
+                // The all zero parcel is defined to be illegal
+                return Ok(self.instruction(address, COMPRESSED_LENGTH, ("unimp".to_string(), vec![])));
+            }
+            let word = compressed::expand(half, self.xlen64).ok_or(DecodeError::InvalidOpcode(half as u32))?;
+            (word, COMPRESSED_LENGTH)
+        } else if half & 0x1c == 0x1c {
+            // 48 bit and longer encodings, none of which are standard
+            return Err(DecodeError::InvalidOpcode(half as u32));
+        } else {
+            if bytes.len() < LENGTH {
+                return Err(DecodeError::EndOfInput);
+            }
+            (u32::from_le_bytes(bytes[..LENGTH].try_into().unwrap()), LENGTH)
+        };
+
+        let decoded = decode_word(word, address, self.xlen64).ok_or(DecodeError::InvalidOpcode(word))?;
+        Ok(self.instruction(address, length, decoded))
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        if self.compressed {
+            COMPRESSED_LENGTH
+        } else {
+            LENGTH
+        }
+    }
+}
+
+impl RiscVDecoder {
+    fn instruction(&self, address: u64, length: usize, (mnemonic, operands): (String, Vec<Operand>)) -> Instruction {
+        let mut instruction = Instruction::new(InstructionSet::RISC_V, address, length, String::new(), operands);
+        let (flow, conditional) = flow_of(&mnemonic, &instruction.operands);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.mnemonic = mnemonic;
+        instruction
+    }
+}
+
+fn decode_word(word: u32, address: u64, xlen64: bool) -> Decoded {
+    match bits(word, 0, 7) {
+        0b0000011 => integer::load(word, xlen64),
+        0b0000111 => float::load(word, xlen64),
+        0b0001111 => integer::fence(word),
+        0b0010011 => integer::op_imm(word, xlen64),
+        0b0010111 => integer::upper(word, xlen64),
+        0b0011011 if xlen64 => integer::op_imm_32(word),
+        0b0100011 => integer::store(word, xlen64),
+        0b0100111 => float::store(word, xlen64),
+        0b0101111 => atomic::atomic(word, xlen64),
+        0b0110011 => integer::op(word, xlen64),
+        0b0110111 => integer::upper(word, xlen64),
+        0b0111011 if xlen64 => integer::op_32(word),
+        0b1000011 | 0b1000111 | 0b1001011 | 0b1001111 => float::fused(word),
+        0b1010011 => float::op_fp(word, xlen64),
+        0b1100011 => integer::branch(word, address, xlen64),
+        0b1100111 => integer::jalr(word, xlen64),
+        0b1101111 => integer::jal(word, address, xlen64),
+        0b1110011 => system::system(word, xlen64),
+        _ => None,
+    }
+}
+
+fn flow_of(mnemonic: &str, operands: &[Operand]) -> (FlowKind, bool) {
+    // A jump which doesn't save the return address to a link register
+    let link = match operands.first() {
+        Some(Operand::Register(register)) => register.class == RegisterClass::General,
+        _ => true,
+    };
+
+    match mnemonic {
+        "j" | "jr" => (FlowKind::Jump, false),
+        "jal" | "jalr" if !link => (FlowKind::Jump, false),
+        "jal" | "jalr" => (FlowKind::Call, false),
+        "ret" | "mret" | "sret" | "uret" => (FlowKind::Return, false),
+        "ecall" => (FlowKind::Syscall, false),
+        "ebreak" | "unimp" => (FlowKind::Trap, false),
+        _ if mnemonic.starts_with('b') => (FlowKind::Jump, true),
+        _ => (FlowKind::Sequential, false),
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+fn rd(word: u32) -> u32 {
+    bits(word, 7, 5)
+}
+
+fn rs1(word: u32) -> u32 {
+    bits(word, 15, 5)
+}
+
+fn rs2(word: u32) -> u32 {
+    bits(word, 20, 5)
+}
+
+fn funct3(word: u32) -> u32 {
+    bits(word, 12, 3)
+}
+
+fn funct7(word: u32) -> u32 {
+    bits(word, 25, 7)
+}
+
+// Immediate of loads, jalr and register-immediate operations
+fn i_immediate(word: u32) -> i64 {
+    sign_extend(bits(word, 20, 12), 12)
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn x(number: u32, xlen64: bool) -> Operand {
+    Operand::Register(gpr(number, xlen64))
+}
+
+fn f(number: u32, bits: u16) -> Operand {
+    Operand::Register(fpr(number, bits))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+// A memory access of `size` bytes at base + displacement
+fn memory(base: u32, displacement: i64, size: u16, xlen64: bool) -> Operand {
+    let mut mem = MemoryOperand::new(size);
+    mem.base = Some(gpr(base, xlen64));
+    mem.displacement = displacement;
+    Operand::Memory(mem)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::RiscVDecoder;
+    use crate::disasm::{Decoder, DecodeError, FlowKind, Syntax};
+
+    // Decodes a run of instructions starting at 0x1000, mixing 16 and 32 bit lengths
+    fn listing(decoder: &RiscVDecoder, bytes: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let instruction = decoder.decode(&bytes[offset..], 0x1000 + offset as u64).unwrap();
+            lines.push(format!("{:x}: {}", instruction.address, instruction.render(Syntax::Intel)));
+            offset += instruction.length;
+        }
+        lines
+    }
+
+    #[test]
+    fn compressed_instructions_expand() {
+        let bytes = [
+            0x41, 0x11, 0x06, 0xe4, 0x15, 0x45, 0x2e, 0x85, 0x05, 0xc1, 0x4c, 0x41, 0x05, 0x25, 0x17, 0x15, 0x00, 0x00,
+            0xef, 0x00, 0x00, 0x10, 0xe3, 0x0c, 0xb5, 0xfe, 0x42, 0x65, 0x82, 0x80,
+        ];
+        let expected = [
+            "1000: addi sp,sp,-16",
+            "1002: sd ra,8(sp)",
+            "1004: li a0,5",
+            "1006: mv a0,a1",
+            "1008: beqz a0,0x1028",
+            "100a: lw a1,4(a0)",
+            "100c: addiw a0,a0,1",
+            "100e: auipc a0,0x1",
+            "1012: jal 0x1112",
+            "1016: beq a0,a1,0x100e",
+            "101a: ld a0,16(sp)",
+            "101c: ret",
+        ];
+        assert_eq!(listing(&RiscVDecoder::new(true, true), &bytes), expected);
+    }
+
+    #[test]
+    fn compressed_encodings_depend_on_xlen() {
+        let rv32 = RiscVDecoder::new(false, true);
+        // c.jal only exists on RV32, RV64 reuses it for c.addiw
+        assert_eq!(listing(&rv32, &[0x05, 0x25]), ["1000: jal 0x1620"]);
+        assert_eq!(listing(&RiscVDecoder::new(true, true), &[0x05, 0x25]), ["1000: addiw a0,a0,1"]);
+        // Where c.addiw with x0 is reserved
+        assert!(RiscVDecoder::new(true, true).decode(&[0x05, 0x20], 0).is_err());
+        let jal = rv32.decode(&[0x05, 0x20], 0x1000).unwrap();
+        assert_eq!((jal.length, jal.flow), (2, FlowKind::Call));
+
+        assert_eq!(RiscVDecoder::new(true, false).decode(&[0x41, 0x11], 0).unwrap_err(), DecodeError::InvalidOpcode(0x1141));
+        assert_eq!(listing(&rv32, &[0x00, 0x00]), ["1000: unimp"]);
+    }
+
+    #[test]
+    fn extensions() {
+        let bytes = [0x2f, 0xa5, 0x05, 0x14, 0x53, 0xf5, 0xc5, 0x02, 0x73, 0x00, 0x00, 0x00];
+        assert_eq!(
+            listing(&RiscVDecoder::new(true, false), &bytes),
+            ["1000: lr.w.aq a0,(a1)", "1004: fadd.d fa0,fa1,fa2", "1008: ecall"]
+        );
+    }
+}
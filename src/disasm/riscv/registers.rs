@@ -0,0 +1,71 @@
+use crate::disasm::{Register, RegisterClass};
+
+// ABI names, as printed by objdump
+const X: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+const F: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+    "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+    "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+pub const ZERO: u32 = 0;
+pub const RA: u32 = 1;
+pub const SP: u32 = 2;
+
+const CSRS: &[(u16, &str)] = &[
+    (0x000, "ustatus"), (0x004, "uie"), (0x005, "utvec"),
+    (0x040, "uscratch"), (0x041, "uepc"), (0x042, "ucause"), (0x043, "utval"), (0x044, "uip"),
+    (0x001, "fflags"), (0x002, "frm"), (0x003, "fcsr"),
+    (0xc00, "cycle"), (0xc01, "time"), (0xc02, "instret"),
+    (0xc03, "hpmcounter3"), (0xc04, "hpmcounter4"), (0xc05, "hpmcounter5"), (0xc06, "hpmcounter6"),
+    (0xc80, "cycleh"), (0xc81, "timeh"), (0xc82, "instreth"),
+    (0xc83, "hpmcounter3h"), (0xc84, "hpmcounter4h"), (0xc85, "hpmcounter5h"), (0xc86, "hpmcounter6h"),
+    (0x100, "sstatus"), (0x102, "sedeleg"), (0x103, "sideleg"), (0x104, "sie"), (0x105, "stvec"), (0x106, "scounteren"),
+    (0x140, "sscratch"), (0x141, "sepc"), (0x142, "scause"), (0x143, "stval"), (0x144, "sip"),
+    (0x180, "satp"),
+    (0xf11, "mvendorid"), (0xf12, "marchid"), (0xf13, "mimpid"), (0xf14, "mhartid"),
+    (0x300, "mstatus"), (0x301, "misa"), (0x302, "medeleg"), (0x303, "mideleg"), (0x304, "mie"), (0x305, "mtvec"),
+    (0x306, "mcounteren"), (0x310, "mstatush"), (0x320, "mcountinhibit"),
+    (0x340, "mscratch"), (0x341, "mepc"), (0x342, "mcause"), (0x343, "mtval"), (0x344, "mip"),
+    (0x34a, "mtinst"), (0x34b, "mtval2"),
+    (0x3a0, "pmpcfg0"), (0x3a1, "pmpcfg1"), (0x3a2, "pmpcfg2"), (0x3a3, "pmpcfg3"),
+    (0x3b0, "pmpaddr0"), (0x3b1, "pmpaddr1"), (0x3b2, "pmpaddr2"), (0x3b3, "pmpaddr3"),
+    (0x3b4, "pmpaddr4"), (0x3b5, "pmpaddr5"), (0x3b6, "pmpaddr6"), (0x3b7, "pmpaddr7"),
+    (0x3b8, "pmpaddr8"), (0x3b9, "pmpaddr9"), (0x3ba, "pmpaddr10"), (0x3bb, "pmpaddr11"),
+    (0x3bc, "pmpaddr12"), (0x3bd, "pmpaddr13"), (0x3be, "pmpaddr14"), (0x3bf, "pmpaddr15"),
+    (0xb00, "mcycle"), (0xb02, "minstret"),
+    (0xb03, "mhpmcounter3"), (0xb04, "mhpmcounter4"), (0xb05, "mhpmcounter5"), (0xb06, "mhpmcounter6"),
+    (0xb80, "mcycleh"), (0xb82, "minstreth"),
+    (0xb83, "mhpmcounter3h"), (0xb84, "mhpmcounter4h"), (0xb85, "mhpmcounter5h"), (0xb86, "mhpmcounter6h"),
+    (0x323, "mhpmevent3"), (0x324, "mhpmevent4"), (0x325, "mhpmevent5"), (0x326, "mhpmevent6"),
+    (0x7a0, "tselect"), (0x7a1, "tdata1"), (0x7a2, "tdata2"), (0x7a3, "tdata3"),
+    (0x7b0, "dcsr"), (0x7b1, "dpc"), (0x7b2, "dscratch0"), (0x7b3, "dscratch1"),
+];
+
+/// An integer register, x0 being hardwired to zero
+pub fn gpr(number: u32, xlen64: bool) -> Register {
+    let number = number & 31;
+    let bits = if xlen64 { 64 } else { 32 };
+    let class = if number == ZERO { RegisterClass::Zero } else { RegisterClass::General };
+    Register::new(class, number as u16, bits, X[number as usize])
+}
+
+/// A floating point register holding a value of `bits` bits
+pub fn fpr(number: u32, bits: u16) -> Register {
+    let number = number & 31;
+    Register::new(RegisterClass::Float, number as u16, bits, F[number as usize])
+}
+
+/// A control and status register, unnamed if it is not a standard one
+pub fn csr(number: u32) -> Register {
+    let number = (number & 0xfff) as u16;
+    let name = CSRS.iter().find(|x| x.0 == number).map(|x| x.1).unwrap_or("");
+    Register::new(RegisterClass::Special, number, 0, name)
+}
@@ -0,0 +1,103 @@
+use crate::disasm::Operand;
+
+use super::registers::{csr, ZERO};
+use super::{bits, funct3, funct7, imm, ins, rd, rs1, rs2, x, Decoded};
+
+const FFLAGS: u32 = 0x001;
+const FRM: u32 = 0x002;
+const FCSR: u32 = 0x003;
+
+// csrrw zero, cycle, zero, the canonical illegal instruction
+const UNIMP: u32 = 0xc000_1073;
+
+pub fn system(word: u32, xlen64: bool) -> Decoded {
+    match funct3(word) {
+        0b000 => privileged(word, xlen64),
+        0b100 => None,
+        _ => csr_access(word, xlen64),
+    }
+}
+
+fn privileged(word: u32, xlen64: bool) -> Decoded {
+    match word {
+        0x0000_0073 => return ins("ecall", vec![]),
+        0x0010_0073 => return ins("ebreak", vec![]),
+        0x0020_0073 => return ins("uret", vec![]),
+        0x1020_0073 => return ins("sret", vec![]),
+        0x3020_0073 => return ins("mret", vec![]),
+        0x1050_0073 => return ins("wfi", vec![]),
+        _ => {}
+    }
+
+    if funct7(word) != 0b0001001 || rd(word) != ZERO {
+        return None;
+    }
+    // sfence.vma, the optional operands are the address and the address space
+    let mut operands = Vec::new();
+    if rs1(word) != ZERO || rs2(word) != ZERO {
+        operands.push(x(rs1(word), xlen64));
+    }
+    if rs2(word) != ZERO {
+        operands.push(x(rs2(word), xlen64));
+    }
+    ins("sfence.vma", operands)
+}
+
+// Zicsr, including the aliases for reading counters and the floating point status
+fn csr_access(word: u32, xlen64: bool) -> Decoded {
+    if word == UNIMP {
+        return ins("unimp", vec![]);
+    }
+
+    let (rd, rs1, number) = (rd(word), rs1(word), bits(word, 20, 12));
+    let register = Operand::Register(csr(number));
+    let d = x(rd, xlen64);
+    // The immediate forms use the rs1 field as a 5 bit unsigned immediate
+    let source = if funct3(word) & 0b100 != 0 { imm(rs1 as i64) } else { x(rs1, xlen64) };
+
+    let float_alias = match (funct3(word), number) {
+        (0b001, FFLAGS) => Some("fsflags"),
+        (0b001, FRM) => Some("fsrm"),
+        (0b001, FCSR) => Some("fscsr"),
+        (0b101, FFLAGS) => Some("fsflagsi"),
+        (0b101, FRM) => Some("fsrmi"),
+        _ => None,
+    };
+    if let Some(mnemonic) = float_alias {
+        return match rd {
+            ZERO => ins(mnemonic, vec![source]),
+            _ => ins(mnemonic, vec![d, source]),
+        };
+    }
+
+    match (funct3(word), rd, rs1) {
+        (0b010, _, ZERO) => {
+            let reader = match number {
+                FFLAGS => Some("frflags"),
+                FRM => Some("frrm"),
+                FCSR => Some("frcsr"),
+                0xc00 => Some("rdcycle"),
+                0xc01 => Some("rdtime"),
+                0xc02 => Some("rdinstret"),
+                0xc80 if !xlen64 => Some("rdcycleh"),
+                0xc81 if !xlen64 => Some("rdtimeh"),
+                0xc82 if !xlen64 => Some("rdinstreth"),
+                _ => None,
+            };
+            match reader {
+                Some(mnemonic) => ins(mnemonic, vec![d]),
+                None => ins("csrr", vec![d, register]),
+            }
+        }
+        (0b001, ZERO, _) => ins("csrw", vec![register, source]),
+        (0b010, ZERO, _) => ins("csrs", vec![register, source]),
+        (0b011, ZERO, _) => ins("csrc", vec![register, source]),
+        (0b101, ZERO, _) => ins("csrwi", vec![register, source]),
+        (0b110, ZERO, _) => ins("csrsi", vec![register, source]),
+        (0b111, ZERO, _) => ins("csrci", vec![register, source]),
+        (funct3, _, _) => {
+            let mnemonic = ["", "csrrw", "csrrs", "csrrc", "", "csrrwi", "csrrsi", "csrrci"][funct3 as usize];
+            ins(mnemonic, vec![d, register, source])
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::disasm::{FlowKind, Operand};
+
+use super::registers::LINK;
+
+// Condition suffixes by mask, the bits of which stand for condition codes 0 to 3. Masks 0 and 15 have none.
+const CONDITIONS: [&str; 16] = ["", "o", "h", "nle", "l", "nhe", "lh", "ne", "e", "nlh", "he", "nl", "le", "nh", "no", ""];
+
+// Compare and branch only sets condition codes 0 to 2, and has suffixes for the combinations of them
+const COMPARISONS: [&str; 16] = ["", "", "h", "", "l", "", "lh", "", "e", "", "he", "", "le", "", "", ""];
+
+// The position of the mask among the operands, for the instructions that have extended mnemonics
+fn mask_position(mnemonic: &str) -> Option<usize> {
+    Some(match mnemonic {
+        "bcr" | "bc" | "brc" | "brcl" => 0,
+        "loc" | "locg" | "stoc" | "stocg" | "locr" | "locgr" | "lochi" | "locghi" => 2,
+        "crj" | "cgrj" | "clrj" | "clgrj" | "cij" | "cgij" | "clij" | "clgij" => 2,
+        _ => return None,
+    })
+}
+
+/// The extended mnemonic, which encodes the condition mask, and the mask itself. The mask operand is removed if
+/// the mnemonic includes it.
+pub fn extended(mnemonic: &str, operands: &mut Vec<Operand>) -> (String, Option<u32>) {
+    let position = match mask_position(mnemonic) {
+        Some(x) => x,
+        None => return (mnemonic.to_string(), None),
+    };
+    let mask = match operands.get(position) {
+        Some(Operand::Immediate { value, .. }) => *value as u32 & 15,
+        _ => return (mnemonic.to_string(), None),
+    };
+
+    let name = match mnemonic {
+        "bcr" if mask == 15 => "br".to_string(),
+        "bc" if mask == 15 => "b".to_string(),
+        "brc" if mask == 15 => "j".to_string(),
+        "brcl" if mask == 15 => "jg".to_string(),
+        _ if mask == 0 || mask == 15 => String::new(),
+        "bcr" => format!("b{}r", CONDITIONS[mask as usize]),
+        "bc" => format!("b{}", CONDITIONS[mask as usize]),
+        "brc" => format!("j{}", CONDITIONS[mask as usize]),
+        "brcl" => format!("jg{}", CONDITIONS[mask as usize]),
+        _ if mnemonic.ends_with('j') => match COMPARISONS[mask as usize] {
+            "" => String::new(),
+            x => format!("{}{}", mnemonic, x),
+        },
+        _ => format!("{}{}", mnemonic, CONDITIONS[mask as usize]),
+    };
+    if name.is_empty() {
+        return (mnemonic.to_string(), Some(mask));
+    }
+    operands.remove(position);
+    (name, Some(mask))
+}
+
+/// Control flow of an instruction, by its basic mnemonic and condition mask: its kind and whether it is conditional
+pub fn flow_of(mnemonic: &str, mask: Option<u32>, operands: &[Operand]) -> (FlowKind, bool) {
+    // The register forms don't branch when the register is 0
+    let register = operands.iter().rev().find_map(|x| match x {
+        Operand::Register(r) => Some(r.number as u32),
+        _ => None,
+    });
+    match mnemonic {
+        "bcr" if register == Some(0) => (FlowKind::Sequential, false),
+        // Returns go through the link register
+        "bcr" if register == Some(LINK) && mask != Some(0) => (FlowKind::Return, mask != Some(15)),
+        "bcr" | "bc" | "brc" | "brcl" => match mask {
+            Some(0) => (FlowKind::Sequential, false),
+            Some(15) => (FlowKind::Jump, false),
+            _ => (FlowKind::Jump, true),
+        },
+        "crj" | "cgrj" | "clrj" | "clgrj" | "cij" | "cgij" | "clij" | "clgij" => match mask {
+            Some(0) => (FlowKind::Sequential, false),
+            Some(14) | Some(15) => (FlowKind::Jump, false),
+            _ => (FlowKind::Jump, true),
+        },
+        "bctr" | "bctgr" | "balr" | "basr" if register == Some(0) => (FlowKind::Sequential, false),
+        "brct" | "brctg" | "bct" | "bctg" | "bctr" | "bctgr" | "bxh" | "bxle" => (FlowKind::Jump, true),
+        "brxh" | "brxle" | "brxhg" | "brxlg" => (FlowKind::Jump, true),
+        "bras" | "brasl" | "bas" | "basr" | "bal" | "balr" => (FlowKind::Call, false),
+        "svc" => (FlowKind::Syscall, false),
+        _ => (FlowKind::Sequential, false),
+    }
+}
@@ -0,0 +1,48 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma without a space, storage
+// operands are written as displacement(index,base), immediates are decimal
+
+pub fn format(instruction: &Instruction) -> String {
+    let length = storage_to_storage(&instruction.mnemonic);
+    let operands: Vec<String> =
+        instruction.operands.iter().enumerate().map(|(i, x)| operand(x, length && i == 0)).collect();
+
+    let mut res = instruction.mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(","));
+    }
+    res
+}
+
+// The operations whose first operand shows the length, as D1(L,B1)
+fn storage_to_storage(mnemonic: &str) -> bool {
+    matches!(mnemonic, "mvn" | "mvc" | "mvz" | "nc" | "clc" | "oc" | "xc" | "tr" | "trt")
+}
+
+fn memory(mem: &MemoryOperand, length: bool) -> String {
+    if let Some(target) = mem.target {
+        return format!("0x{:x}", target);
+    }
+    let base = mem.base.map(|x| x.name);
+    let inner = match (mem.index, base) {
+        (Some(index), Some(base)) => format!("{},{}", index.name, base),
+        // An index without a base is written with a base of 0
+        (Some(index), None) => format!("{},0", index.name),
+        _ if length => format!("{}{}", mem.size, base.map(|x| format!(",{}", x)).unwrap_or_default()),
+        (None, Some(base)) => base.to_string(),
+        (None, None) => return format!("{}", mem.displacement),
+    };
+    format!("{}({})", mem.displacement, inner)
+}
+
+fn operand(operand: &Operand, length: bool) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, .. } => format!("{}", value),
+        Operand::Memory(mem) => memory(mem, length),
+        Operand::Address(address) => format!("0x{:x}", address),
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,363 @@
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, Instruction, MemoryOperand, Operand};
+
+mod registers;
+use self::registers::{ar, fpr, gpr};
+
+mod opcodes;
+use self::opcodes::{Field, Format, Imm};
+
+mod branch;
+
+mod format;
+pub use self::format::format;
+
+/// Decoder for the problem state instructions of z/Architecture, the 64 bit mode of IBM Z. Instructions are 2, 4
+/// or 6 bytes long, and always big endian.
+#[derive(Debug, Clone)]
+pub struct S390Decoder;
+
+// The bits of an instruction, numbered from the most significant one as in the Principles of Operation
+#[derive(Debug, Clone, Copy)]
+struct Word(u64);
+
+impl Word {
+    fn new(bytes: &[u8]) -> Word {
+        let value = bytes.iter().fold(0u64, |acc, x| (acc << 8) | *x as u64);
+        Word(value << (8 * (6 - bytes.len())))
+    }
+
+    fn field(&self, start: u32, count: u32) -> u32 {
+        ((self.0 >> (48 - start - count)) & ((1 << count) - 1)) as u32
+    }
+}
+
+// What the decoding functions need besides the instruction word
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+    size: u16,
+}
+
+impl Context {
+    fn register(&self, field: Field, number: u32) -> Option<Operand> {
+        match field {
+            Field::Gpr => Some(Operand::Register(gpr(number))),
+            Field::Fpr => Some(Operand::Register(fpr(number))),
+            Field::Ar => Some(Operand::Register(ar(number))),
+            Field::Mask => Some(imm(number as i64)),
+            Field::Unused => None,
+        }
+    }
+
+    // D(X,B), register 0 standing for no register rather than %r0
+    fn address(&self, displacement: i64, index: u32, base: u32) -> Operand {
+        let mut mem = MemoryOperand::new(self.size);
+        mem.displacement = displacement;
+        if index != 0 {
+            mem.index = Some(gpr(index));
+        }
+        if base != 0 {
+            mem.base = Some(gpr(base));
+        }
+        Operand::Memory(mem)
+    }
+
+    // A pc relative operand, counted in halfwords. Branch targets are addresses, everything else a memory operand.
+    fn relative(&self, offset: i64, branch: bool) -> Operand {
+        let target = self.address.wrapping_add((offset << 1) as u64);
+        if branch {
+            return Operand::Address(target);
+        }
+        let mut mem = MemoryOperand::new(self.size);
+        mem.displacement = offset << 1;
+        mem.target = Some(target);
+        Operand::Memory(mem)
+    }
+
+    fn immediate(&self, kind: Imm, value: u32, bits: u32, branch: bool) -> Operand {
+        match kind {
+            Imm::Signed => imm(sign_extend(value, bits)),
+            Imm::Unsigned => imm(value as i64),
+            Imm::Relative => self.relative(sign_extend(value, bits), branch),
+        }
+    }
+}
+
+impl Decoder for S390Decoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.is_empty() {
+            return Err(DecodeError::EndOfInput);
+        }
+        // The two high bits of the first byte give the length
+        let length = match bytes[0] >> 6 {
+            0 => 2,
+            1 | 2 => 4,
+            _ => 6,
+        };
+        if bytes.len() < length {
+            return Err(DecodeError::EndOfInput);
+        }
+        let word = Word::new(&bytes[..length]);
+        let op1 = bytes[0];
+        let op2 = match opcodes::extension(op1) {
+            1 => bytes[1] & 15,
+            2 => bytes[1],
+            3 => bytes[5],
+            _ => 0,
+        };
+        let invalid = DecodeError::InvalidOpcode(word.field(0, 32));
+        let entry = opcodes::lookup(op1, op2).ok_or(invalid)?;
+
+        let context = Context { address, size: entry.size };
+        let branch = entry.mnemonic.starts_with("br") || entry.mnemonic.ends_with('j');
+        let mut operands = decode_operands(entry.format, word, &context, branch).ok_or(invalid)?;
+
+        // Even/odd register pairs are named by their even register
+        let odd = |n: usize| matches!(operands.get(n), Some(Operand::Register(r)) if r.number % 2 != 0);
+        if (entry.flags & opcodes::PAIR != 0 && odd(0)) || (entry.flags & opcodes::PAIR2 != 0 && odd(1)) {
+            return Err(invalid);
+        }
+
+        if entry.flags & opcodes::MASK_LAST != 0 {
+            operands.swap(1, 2);
+        }
+
+        let (mut mnemonic, mask) = branch::extended(entry.mnemonic, &mut operands);
+        // The forms with a fourth operand are named with an a suffix
+        if matches!(entry.format, Format::RRFe(..) | Format::RRFm(..)) && operands.len() == 4 {
+            mnemonic.push('a');
+        }
+        let mut instruction = Instruction::new(InstructionSet::S390, address, length, mnemonic, operands);
+        let (flow, conditional) = branch::flow_of(entry.mnemonic, mask, &instruction.operands);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        2
+    }
+}
+
+fn decode_operands(format: Format, w: Word, c: &Context, branch: bool) -> Option<Vec<Operand>> {
+    use self::Format::*;
+
+    let r1 = w.field(8, 4);
+    let r2 = w.field(12, 4);
+    // The base and 12 bit displacement of the second operand, and the 8 high bits extending it to 20 bits
+    let (b2, d2) = (w.field(16, 4), w.field(20, 12) as i64);
+    let long_displacement = sign_extend((w.field(32, 8) << 12) | w.field(20, 12), 20);
+
+    if !reserved_fields_clear(format, w) {
+        return None;
+    }
+
+    let mut operands = vec![];
+    let mut push = |x: Option<Operand>| operands.extend(x);
+    match format {
+        I => push(Some(imm(w.field(8, 8) as i64))),
+        RR(f1, f2) => {
+            push(c.register(f1, r1));
+            push(c.register(f2, r2));
+        }
+        RRE(f1, f2) => {
+            push(c.register(f1, w.field(24, 4)));
+            push(c.register(f2, w.field(28, 4)));
+        }
+        RRFa => {
+            push(c.register(Field::Gpr, w.field(24, 4)));
+            push(c.register(Field::Gpr, w.field(28, 4)));
+            push(c.register(Field::Gpr, w.field(16, 4)));
+        }
+        RRFc => {
+            push(c.register(Field::Gpr, w.field(24, 4)));
+            push(c.register(Field::Gpr, w.field(28, 4)));
+            push(c.register(Field::Mask, w.field(16, 4)));
+        }
+        // The floating point extension facility added M4, and made M3 optional where it used to be absent
+        RRFe(f1, f2) => {
+            push(c.register(f1, w.field(24, 4)));
+            push(c.register(Field::Mask, w.field(16, 4)));
+            push(c.register(f2, w.field(28, 4)));
+            if w.field(20, 4) != 0 {
+                push(c.register(Field::Mask, w.field(20, 4)));
+            }
+        }
+        RRFm(f1, f2) if w.field(16, 8) == 0 => {
+            push(c.register(f1, w.field(24, 4)));
+            push(c.register(f2, w.field(28, 4)));
+        }
+        RRFm(f1, f2) => {
+            push(c.register(f1, w.field(24, 4)));
+            push(c.register(Field::Mask, w.field(16, 4)));
+            push(c.register(f2, w.field(28, 4)));
+            push(c.register(Field::Mask, w.field(20, 4)));
+        }
+        RX(f1) | RXE(f1) => {
+            push(c.register(f1, r1));
+            push(Some(c.address(d2, r2, b2)));
+        }
+        RXY(f1) => {
+            push(c.register(f1, r1));
+            push(Some(c.address(long_displacement, r2, b2)));
+        }
+        RS(f1, f3) => {
+            push(c.register(f1, r1));
+            push(c.register(f3, r2));
+            push(Some(c.address(d2, 0, b2)));
+        }
+        RSY(f1, f3) => {
+            push(c.register(f1, r1));
+            push(c.register(f3, r2));
+            push(Some(c.address(long_displacement, 0, b2)));
+        }
+        RSI => {
+            push(c.register(Field::Gpr, r1));
+            push(c.register(Field::Gpr, r2));
+            push(Some(c.relative(sign_extend(w.field(16, 16), 16), true)));
+        }
+        S => push(Some(c.address(d2, 0, b2))),
+        RI(f1, kind) => {
+            push(c.register(f1, r1));
+            push(Some(c.immediate(kind, w.field(16, 16), 16, branch)));
+        }
+        RIL(f1, kind) => {
+            push(c.register(f1, r1));
+            push(Some(c.immediate(kind, w.field(16, 32), 32, branch)));
+        }
+        RIEb => {
+            push(c.register(Field::Gpr, r1));
+            push(c.register(Field::Gpr, r2));
+            push(c.register(Field::Mask, w.field(32, 4)));
+            push(Some(c.relative(sign_extend(w.field(16, 16), 16), true)));
+        }
+        RIEc(kind) => {
+            push(c.register(Field::Gpr, r1));
+            push(Some(c.immediate(kind, w.field(32, 8), 8, false)));
+            push(c.register(Field::Mask, r2));
+            push(Some(c.relative(sign_extend(w.field(16, 16), 16), true)));
+        }
+        RIEd(kind) => {
+            push(c.register(Field::Gpr, r1));
+            push(c.register(Field::Gpr, r2));
+            push(Some(c.immediate(kind, w.field(16, 16), 16, false)));
+        }
+        RIEf => {
+            push(c.register(Field::Gpr, r1));
+            push(c.register(Field::Gpr, r2));
+            push(Some(imm(w.field(16, 8) as i64)));
+            push(Some(imm(w.field(24, 8) as i64)));
+            push(Some(imm(w.field(32, 8) as i64)));
+        }
+        RIEg => {
+            push(c.register(Field::Gpr, r1));
+            push(Some(imm(sign_extend(w.field(16, 16), 16))));
+            push(c.register(Field::Mask, r2));
+        }
+        SI => {
+            push(Some(c.address(d2, 0, b2)));
+            push(Some(imm(w.field(8, 8) as i64)));
+        }
+        SIY(kind) => {
+            push(Some(c.address(long_displacement, 0, b2)));
+            push(Some(c.immediate(kind, w.field(8, 8), 8, false)));
+        }
+        SIL(kind) => {
+            push(Some(c.address(d2, 0, b2)));
+            push(Some(c.immediate(kind, w.field(32, 16), 16, false)));
+        }
+        SS => {
+            // The length field holds one less than the number of bytes
+            let length = w.field(8, 8) as u16 + 1;
+            let storage = Context { size: length, ..*c };
+            push(Some(storage.address(d2, 0, b2)));
+            push(Some(storage.address(w.field(36, 12) as i64, 0, w.field(32, 4))));
+        }
+    }
+    Some(operands)
+}
+
+// Fields that the format leaves unused must be zero
+fn reserved_fields_clear(format: Format, w: Word) -> bool {
+    use self::Format::*;
+
+    let unused = |field: Field, value: u32| field != Field::Unused || value == 0;
+    match format {
+        RR(f1, f2) => unused(f1, w.field(8, 4)) && unused(f2, w.field(12, 4)),
+        RRE(f1, f2) => w.field(16, 8) == 0 && unused(f1, w.field(24, 4)) && unused(f2, w.field(28, 4)),
+        RRFa | RRFc => w.field(20, 4) == 0,
+        RXE(_) => w.field(32, 8) == 0,
+        RS(_, f3) | RSY(_, f3) => unused(f3, w.field(12, 4)),
+        RIEb => w.field(36, 4) == 0,
+        RSI | RIEd(_) | RIEg => w.field(32, 8) == 0,
+        // The rotate amount is 6 bits
+        RIEf => w.field(32, 8) < 64,
+        _ => true,
+    }
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::S390Decoder;
+    use crate::disasm::{DecodeError, Decoder, FlowKind, Syntax};
+
+    fn decode_all(mut code: &[u8]) -> Vec<(usize, String)> {
+        let mut address = 0x1000;
+        let mut decoded = Vec::new();
+        while !code.is_empty() {
+            let instruction = S390Decoder.decode(code, address).unwrap();
+            decoded.push((instruction.length, instruction.render(Syntax::Att)));
+            address += instruction.length as u64;
+            code = &code[instruction.length..];
+        }
+        decoded
+    }
+
+    #[test]
+    fn lengths_come_from_the_opcode() {
+        let code = [
+            0xeb, 0x6f, 0xf0, 0x30, 0x00, 0x24, 0xa7, 0xfb, 0xff, 0x60, 0xb9, 0x04, 0x00, 0x23, 0x41, 0x23, 0x40, 0x08,
+            0xc0, 0x10, 0x00, 0x00, 0x00, 0x20, 0x0a, 0x01,
+        ];
+        let expected = [
+            (6, "stmg %r6,%r15,48(%r15)"),
+            (4, "aghi %r15,-160"),
+            (4, "lgr %r2,%r3"),
+            (4, "la %r2,8(%r3,%r4)"),
+            (6, "larl %r1,0x1052"),
+            (2, "svc 1"),
+        ];
+        let expected: Vec<(usize, String)> = expected.iter().map(|x| (x.0, x.1.to_string())).collect();
+        assert_eq!(decode_all(&code), expected);
+        // A six byte opcode with only two bytes left
+        assert_eq!(S390Decoder.decode(&[0xc0, 0xe5], 0).unwrap_err(), DecodeError::EndOfInput);
+    }
+
+    #[test]
+    fn branches() {
+        let cases: [(&[u8], &str, FlowKind, bool); 6] = [
+            (&[0xc0, 0xe5, 0x00, 0x00, 0x00, 0x80], "brasl %r14,0x1100", FlowKind::Call, false),
+            (&[0x0d, 0xe1], "basr %r14,%r1", FlowKind::Call, false),
+            (&[0x07, 0xfe], "br %r14", FlowKind::Return, false),
+            (&[0xa7, 0x84, 0x00, 0x10], "je 0x1020", FlowKind::Jump, true),
+            (&[0xa7, 0xf4, 0x00, 0x04], "j 0x1008", FlowKind::Jump, false),
+            (&[0xc0, 0xf4, 0x00, 0x00, 0x08, 0x00], "jg 0x2000", FlowKind::Jump, false),
+        ];
+        for &(code, text, flow, conditional) in cases.iter() {
+            let instruction = S390Decoder.decode(code, 0x1000).unwrap();
+            assert_eq!(instruction.render(Syntax::Att), text);
+            assert_eq!((instruction.flow, instruction.conditional), (flow, conditional), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,679 @@
+// Opcode tables, by instruction format. The format names follow the z/Architecture Principles of Operation.
+
+/// What a register field of an instruction holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Gpr,
+    Fpr,
+    Ar,
+    // A 4 bit mask, such as the condition mask of a branch
+    Mask,
+    // The field is ignored
+    Unused,
+}
+
+/// How an immediate field is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Imm {
+    Signed,
+    Unsigned,
+    // Number of halfwords relative to the instruction
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Format {
+    // svc: an 8 bit immediate
+    I,
+    // R1,R2 in two bytes
+    RR(Field, Field),
+    // R1,R2 in four bytes
+    RRE(Field, Field),
+    // R1,R2,R3 of the distinct operands facility
+    RRFa,
+    // R1,R2,M3 of load on condition
+    RRFc,
+    // R1,M3,R2 of the conversions with a rounding mode, and M4 for the forms that take it
+    RRFe(Field, Field),
+    // R1,R2 of the conversions whose rounding mode and M4 are optional
+    RRFm(Field, Field),
+    // R1,D2(X2,B2) with a 12 bit displacement
+    RX(Field),
+    // R1,D2(X2,B2) with a 12 bit displacement in six bytes
+    RXE(Field),
+    // R1,D2(X2,B2) with a 20 bit displacement
+    RXY(Field),
+    // R1,R3,D2(B2) or R1,M3,D2(B2), the second field being unused for shifts
+    RS(Field, Field),
+    // Same with a 20 bit displacement
+    RSY(Field, Field),
+    // R1,R3,RI2 of branch relative on index
+    RSI,
+    // D2(B2)
+    S,
+    // R1,I2 with a 16 bit immediate
+    RI(Field, Imm),
+    // R1,I2 with a 32 bit immediate
+    RIL(Field, Imm),
+    // R1,R2,M3,RI4 of compare and branch
+    RIEb,
+    // R1,I2,M3,RI4 of compare immediate and branch
+    RIEc(Imm),
+    // R1,R3,I2 of add immediate with distinct operands
+    RIEd(Imm),
+    // R1,R2,I3,I4,I5 of rotate then insert
+    RIEf,
+    // R1,I2,M3 of load halfword immediate on condition
+    RIEg,
+    // D1(B1),I2 with a 12 bit displacement
+    SI,
+    // D1(B1),I2 with a 20 bit displacement
+    SIY(Imm),
+    // D1(B1),I2 with a 16 bit immediate
+    SIL(Imm),
+    // D1(L,B1),D2(B2) of the storage to storage operations
+    SS,
+}
+
+// R1 is the even register of an even/odd pair
+pub const PAIR: u16 = 1;
+// R2 or R3 is the even register of a pair too
+pub const PAIR2: u16 = 2;
+// The M3 mask is written after the storage operand
+pub const MASK_LAST: u16 = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub mnemonic: &'static str,
+    pub format: Format,
+    // Size in bytes of the memory operand, 0 if it is only an address
+    pub size: u16,
+    pub flags: u16,
+}
+
+const fn e(mnemonic: &'static str, format: Format, size: u16) -> Option<Entry> {
+    Some(Entry { mnemonic, format, size, flags: 0 })
+}
+
+const fn ef(mnemonic: &'static str, format: Format, size: u16, flags: u16) -> Option<Entry> {
+    Some(Entry { mnemonic, format, size, flags })
+}
+
+use self::Field::*;
+use self::Format::*;
+use self::Imm::*;
+
+/// Where the second part of the opcode is, by the first byte: 0 for none, 1 for the low four bits of the second
+/// byte, 2 for the second byte and 3 for the last byte
+pub fn extension(op1: u8) -> u8 {
+    match op1 {
+        0xa5 | 0xa7 | 0xc0 | 0xc2 | 0xc4 | 0xc6 => 1,
+        0xb2 | 0xb3 | 0xb9 | 0xe5 => 2,
+        0xe3 | 0xeb | 0xec | 0xed => 3,
+        _ => 0,
+    }
+}
+
+pub fn lookup(op1: u8, op2: u8) -> Option<Entry> {
+    match op1 {
+        0xa5 => immediate_halfword(op2),
+        0xa7 => relative_immediate(op2),
+        0xc0 | 0xc2 | 0xc4 | 0xc6 => long_immediate(op1, op2),
+        0xb2 | 0xb3 => control_float(op1, op2),
+        0xb9 => register_extended(op2),
+        0xe3 => rxy(op2),
+        0xe5 => sil(op2),
+        0xeb => rsy(op2),
+        0xec => rie(op2),
+        0xed => float_extended(op2),
+        _ => one_byte(op1),
+    }
+}
+
+fn one_byte(op: u8) -> Option<Entry> {
+    match op {
+        0x05 => e("balr", RR(Gpr, Gpr), 0),
+        0x06 => e("bctr", RR(Gpr, Gpr), 0),
+        0x07 => e("bcr", RR(Mask, Gpr), 0),
+        0x0a => e("svc", I, 0),
+        0x0d => e("basr", RR(Gpr, Gpr), 0),
+        0x0e => ef("mvcl", RR(Gpr, Gpr), 0, PAIR | PAIR2),
+        0x0f => ef("clcl", RR(Gpr, Gpr), 0, PAIR | PAIR2),
+        0x10 => e("lpr", RR(Gpr, Gpr), 0),
+        0x11 => e("lnr", RR(Gpr, Gpr), 0),
+        0x12 => e("ltr", RR(Gpr, Gpr), 0),
+        0x13 => e("lcr", RR(Gpr, Gpr), 0),
+        0x14 => e("nr", RR(Gpr, Gpr), 0),
+        0x15 => e("clr", RR(Gpr, Gpr), 0),
+        0x16 => e("or", RR(Gpr, Gpr), 0),
+        0x17 => e("xr", RR(Gpr, Gpr), 0),
+        0x18 => e("lr", RR(Gpr, Gpr), 0),
+        0x19 => e("cr", RR(Gpr, Gpr), 0),
+        0x1a => e("ar", RR(Gpr, Gpr), 0),
+        0x1b => e("sr", RR(Gpr, Gpr), 0),
+        0x1c => ef("mr", RR(Gpr, Gpr), 0, PAIR),
+        0x1d => ef("dr", RR(Gpr, Gpr), 0, PAIR),
+        0x1e => e("alr", RR(Gpr, Gpr), 0),
+        0x1f => e("slr", RR(Gpr, Gpr), 0),
+        0x28 => e("ldr", RR(Fpr, Fpr), 0),
+        0x38 => e("ler", RR(Fpr, Fpr), 0),
+        0x40 => e("sth", RX(Gpr), 2),
+        0x41 => e("la", RX(Gpr), 0),
+        0x42 => e("stc", RX(Gpr), 1),
+        0x43 => e("ic", RX(Gpr), 1),
+        0x44 => e("ex", RX(Gpr), 0),
+        0x45 => e("bal", RX(Gpr), 0),
+        0x46 => e("bct", RX(Gpr), 0),
+        0x47 => e("bc", RX(Mask), 0),
+        0x48 => e("lh", RX(Gpr), 2),
+        0x49 => e("ch", RX(Gpr), 2),
+        0x4a => e("ah", RX(Gpr), 2),
+        0x4b => e("sh", RX(Gpr), 2),
+        0x4c => e("mh", RX(Gpr), 2),
+        0x4d => e("bas", RX(Gpr), 0),
+        0x4e => e("cvd", RX(Gpr), 8),
+        0x4f => e("cvb", RX(Gpr), 8),
+        0x50 => e("st", RX(Gpr), 4),
+        0x51 => e("lae", RX(Gpr), 0),
+        0x54 => e("n", RX(Gpr), 4),
+        0x55 => e("cl", RX(Gpr), 4),
+        0x56 => e("o", RX(Gpr), 4),
+        0x57 => e("x", RX(Gpr), 4),
+        0x58 => e("l", RX(Gpr), 4),
+        0x59 => e("c", RX(Gpr), 4),
+        0x5a => e("a", RX(Gpr), 4),
+        0x5b => e("s", RX(Gpr), 4),
+        0x5c => ef("m", RX(Gpr), 4, PAIR),
+        0x5d => ef("d", RX(Gpr), 4, PAIR),
+        0x5e => e("al", RX(Gpr), 4),
+        0x5f => e("sl", RX(Gpr), 4),
+        0x60 => e("std", RX(Fpr), 8),
+        0x68 => e("ld", RX(Fpr), 8),
+        0x70 => e("ste", RX(Fpr), 4),
+        0x71 => e("ms", RX(Gpr), 4),
+        0x78 => e("le", RX(Fpr), 4),
+        0x84 => e("brxh", RSI, 0),
+        0x85 => e("brxle", RSI, 0),
+        0x86 => e("bxh", RS(Gpr, Gpr), 0),
+        0x87 => e("bxle", RS(Gpr, Gpr), 0),
+        0x88 => e("srl", RS(Gpr, Unused), 0),
+        0x89 => e("sll", RS(Gpr, Unused), 0),
+        0x8a => e("sra", RS(Gpr, Unused), 0),
+        0x8b => e("sla", RS(Gpr, Unused), 0),
+        0x8c => ef("srdl", RS(Gpr, Unused), 0, PAIR),
+        0x8d => ef("sldl", RS(Gpr, Unused), 0, PAIR),
+        0x8e => ef("srda", RS(Gpr, Unused), 0, PAIR),
+        0x8f => ef("slda", RS(Gpr, Unused), 0, PAIR),
+        0x90 => e("stm", RS(Gpr, Gpr), 4),
+        0x91 => e("tm", SI, 1),
+        0x92 => e("mvi", SI, 1),
+        0x94 => e("ni", SI, 1),
+        0x95 => e("cli", SI, 1),
+        0x96 => e("oi", SI, 1),
+        0x97 => e("xi", SI, 1),
+        0x98 => e("lm", RS(Gpr, Gpr), 4),
+        0x9a => e("lam", RS(Ar, Ar), 4),
+        0x9b => e("stam", RS(Ar, Ar), 4),
+        0xa8 => ef("mvcle", RS(Gpr, Gpr), 0, PAIR | PAIR2),
+        0xa9 => ef("clcle", RS(Gpr, Gpr), 0, PAIR | PAIR2),
+        0xba => e("cs", RS(Gpr, Gpr), 4),
+        0xbb => ef("cds", RS(Gpr, Gpr), 8, PAIR | PAIR2),
+        0xbd => e("clm", RS(Gpr, Mask), 0),
+        0xbe => e("stcm", RS(Gpr, Mask), 0),
+        0xbf => e("icm", RS(Gpr, Mask), 0),
+        0xd1 => e("mvn", SS, 0),
+        0xd2 => e("mvc", SS, 0),
+        0xd3 => e("mvz", SS, 0),
+        0xd4 => e("nc", SS, 0),
+        0xd5 => e("clc", SS, 0),
+        0xd6 => e("oc", SS, 0),
+        0xd7 => e("xc", SS, 0),
+        0xdc => e("tr", SS, 0),
+        0xdd => e("trt", SS, 0),
+        _ => None,
+    }
+}
+
+// a5: insert, and, or and load logical of 16 bit parts of a register
+fn immediate_halfword(op: u8) -> Option<Entry> {
+    const NAMES: [&str; 16] = [
+        "iihh", "iihl", "iilh", "iill", "nihh", "nihl", "nilh", "nill",
+        "oihh", "oihl", "oilh", "oill", "llihh", "llihl", "llilh", "llill",
+    ];
+    e(NAMES[op as usize & 15], RI(Gpr, Unsigned), 0)
+}
+
+// a7: test under mask, relative branches and the 16 bit immediate arithmetic
+fn relative_immediate(op: u8) -> Option<Entry> {
+    match op {
+        0x0 => e("tmlh", RI(Gpr, Unsigned), 0),
+        0x1 => e("tmll", RI(Gpr, Unsigned), 0),
+        0x2 => e("tmhh", RI(Gpr, Unsigned), 0),
+        0x3 => e("tmhl", RI(Gpr, Unsigned), 0),
+        0x4 => e("brc", RI(Mask, Relative), 0),
+        0x5 => e("bras", RI(Gpr, Relative), 0),
+        0x6 => e("brct", RI(Gpr, Relative), 0),
+        0x7 => e("brctg", RI(Gpr, Relative), 0),
+        0x8 => e("lhi", RI(Gpr, Signed), 0),
+        0x9 => e("lghi", RI(Gpr, Signed), 0),
+        0xa => e("ahi", RI(Gpr, Signed), 0),
+        0xb => e("aghi", RI(Gpr, Signed), 0),
+        0xc => e("mhi", RI(Gpr, Signed), 0),
+        0xd => e("mghi", RI(Gpr, Signed), 0),
+        0xe => e("chi", RI(Gpr, Signed), 0),
+        0xf => e("cghi", RI(Gpr, Signed), 0),
+        _ => None,
+    }
+}
+
+// c0, c2, c4 and c6: 32 bit immediates, and the pc relative branches, loads and stores
+fn long_immediate(op1: u8, op2: u8) -> Option<Entry> {
+    match (op1, op2) {
+        (0xc0, 0x0) => e("larl", RIL(Gpr, Relative), 0),
+        (0xc0, 0x1) => e("lgfi", RIL(Gpr, Signed), 0),
+        (0xc0, 0x4) => e("brcl", RIL(Mask, Relative), 0),
+        (0xc0, 0x5) => e("brasl", RIL(Gpr, Relative), 0),
+        (0xc0, 0x6) => e("xihf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0x7) => e("xilf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0x8) => e("iihf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0x9) => e("iilf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xa) => e("nihf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xb) => e("nilf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xc) => e("oihf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xd) => e("oilf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xe) => e("llihf", RIL(Gpr, Unsigned), 0),
+        (0xc0, 0xf) => e("llilf", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0x0) => e("msgfi", RIL(Gpr, Signed), 0),
+        (0xc2, 0x1) => e("msfi", RIL(Gpr, Signed), 0),
+        (0xc2, 0x4) => e("slgfi", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0x5) => e("slfi", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0x8) => e("agfi", RIL(Gpr, Signed), 0),
+        (0xc2, 0x9) => e("afi", RIL(Gpr, Signed), 0),
+        (0xc2, 0xa) => e("algfi", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0xb) => e("alfi", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0xc) => e("cgfi", RIL(Gpr, Signed), 0),
+        (0xc2, 0xd) => e("cfi", RIL(Gpr, Signed), 0),
+        (0xc2, 0xe) => e("clgfi", RIL(Gpr, Unsigned), 0),
+        (0xc2, 0xf) => e("clfi", RIL(Gpr, Unsigned), 0),
+        (0xc4, 0x2) => e("llhrl", RIL(Gpr, Relative), 2),
+        (0xc4, 0x4) => e("lghrl", RIL(Gpr, Relative), 2),
+        (0xc4, 0x5) => e("lhrl", RIL(Gpr, Relative), 2),
+        (0xc4, 0x6) => e("llghrl", RIL(Gpr, Relative), 2),
+        (0xc4, 0x7) => e("sthrl", RIL(Gpr, Relative), 2),
+        (0xc4, 0x8) => e("lgrl", RIL(Gpr, Relative), 8),
+        (0xc4, 0xb) => e("stgrl", RIL(Gpr, Relative), 8),
+        (0xc4, 0xc) => e("lgfrl", RIL(Gpr, Relative), 4),
+        (0xc4, 0xd) => e("lrl", RIL(Gpr, Relative), 4),
+        (0xc4, 0xe) => e("llgfrl", RIL(Gpr, Relative), 4),
+        (0xc4, 0xf) => e("strl", RIL(Gpr, Relative), 4),
+        (0xc6, 0x0) => e("exrl", RIL(Gpr, Relative), 0),
+        (0xc6, 0x2) => e("pfdrl", RIL(Mask, Relative), 0),
+        (0xc6, 0x4) => e("cghrl", RIL(Gpr, Relative), 2),
+        (0xc6, 0x5) => e("chrl", RIL(Gpr, Relative), 2),
+        (0xc6, 0x6) => e("clghrl", RIL(Gpr, Relative), 2),
+        (0xc6, 0x7) => e("clhrl", RIL(Gpr, Relative), 2),
+        (0xc6, 0x8) => e("cgrl", RIL(Gpr, Relative), 8),
+        (0xc6, 0xa) => e("clgrl", RIL(Gpr, Relative), 8),
+        (0xc6, 0xc) => e("cgfrl", RIL(Gpr, Relative), 4),
+        (0xc6, 0xd) => e("crl", RIL(Gpr, Relative), 4),
+        (0xc6, 0xe) => e("clgfrl", RIL(Gpr, Relative), 4),
+        (0xc6, 0xf) => e("clrl", RIL(Gpr, Relative), 4),
+        _ => None,
+    }
+}
+
+// b2 and b3: the few unprivileged b2 instructions, and the binary floating point operations
+fn control_float(op1: u8, op2: u8) -> Option<Entry> {
+    match (op1, op2) {
+        (0xb2, 0x05) => e("stck", S, 8),
+        (0xb2, 0x22) => e("ipm", RRE(Gpr, Unused), 0),
+        (0xb2, 0x4e) => e("sar", RRE(Ar, Gpr), 0),
+        (0xb2, 0x4f) => e("ear", RRE(Gpr, Ar), 0),
+        (0xb2, 0x52) => e("msr", RRE(Gpr, Gpr), 0),
+        (0xb2, 0x78) => e("stcke", S, 16),
+        (0xb2, 0x7c) => e("stckf", S, 8),
+        (0xb2, 0xb0) => e("stfle", S, 0),
+        (0xb3, 0x00) => e("lpebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x01) => e("lnebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x02) => e("ltebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x03) => e("lcebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x04) => e("ldebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x09) => e("cebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x0a) => e("aebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x0b) => e("sebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x0d) => e("debr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x10) => e("lpdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x11) => e("lndbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x12) => e("ltdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x13) => e("lcdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x14) => e("sqebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x15) => e("sqdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x17) => e("meebr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x19) => e("cdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x1a) => e("adbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x1b) => e("sdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x1c) => e("mdbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x1d) => e("ddbr", RRE(Fpr, Fpr), 0),
+        (0xb3, 0x57) => e("fiebr", RRFe(Fpr, Fpr), 0),
+        (0xb3, 0x5f) => e("fidbr", RRFe(Fpr, Fpr), 0),
+        (0xb3, 0x44) => e("ledbr", RRFm(Fpr, Fpr), 0),
+        (0xb3, 0x74) => e("lzer", RRE(Fpr, Unused), 0),
+        (0xb3, 0x75) => e("lzdr", RRE(Fpr, Unused), 0),
+        (0xb3, 0x84) => e("sfpc", RRE(Gpr, Unused), 0),
+        (0xb3, 0x8c) => e("efpc", RRE(Gpr, Unused), 0),
+        (0xb3, 0x94) => e("cefbr", RRFm(Fpr, Gpr), 0),
+        (0xb3, 0x95) => e("cdfbr", RRFm(Fpr, Gpr), 0),
+        (0xb3, 0x98) => e("cfebr", RRFe(Gpr, Fpr), 0),
+        (0xb3, 0x99) => e("cfdbr", RRFe(Gpr, Fpr), 0),
+        (0xb3, 0xa4) => e("cegbr", RRFm(Fpr, Gpr), 0),
+        (0xb3, 0xa5) => e("cdgbr", RRFm(Fpr, Gpr), 0),
+        (0xb3, 0xa8) => e("cgebr", RRFe(Gpr, Fpr), 0),
+        (0xb3, 0xa9) => e("cgdbr", RRFe(Gpr, Fpr), 0),
+        (0xb3, 0xc1) => e("ldgr", RRE(Fpr, Gpr), 0),
+        (0xb3, 0xcd) => e("lgdr", RRE(Gpr, Fpr), 0),
+        _ => None,
+    }
+}
+
+// b9: register to register operations, most of them 64 bit
+fn register_extended(op: u8) -> Option<Entry> {
+    let mnemonic = match op {
+        0x00 => "lpgr",
+        0x01 => "lngr",
+        0x02 => "ltgr",
+        0x03 => "lcgr",
+        0x04 => "lgr",
+        0x06 => "lgbr",
+        0x07 => "lghr",
+        0x08 => "agr",
+        0x09 => "sgr",
+        0x0a => "algr",
+        0x0b => "slgr",
+        0x0c => "msgr",
+        0x0d => return ef("dsgr", RRE(Gpr, Gpr), 0, PAIR),
+        0x0f => "lrvgr",
+        0x10 => "lpgfr",
+        0x11 => "lngfr",
+        0x12 => "ltgfr",
+        0x13 => "lcgfr",
+        0x14 => "lgfr",
+        0x16 => "llgfr",
+        0x17 => "llgtr",
+        0x18 => "agfr",
+        0x19 => "sgfr",
+        0x1a => "algfr",
+        0x1b => "slgfr",
+        0x1c => "msgfr",
+        0x1d => return ef("dsgfr", RRE(Gpr, Gpr), 0, PAIR),
+        0x1f => "lrvr",
+        0x20 => "cgr",
+        0x21 => "clgr",
+        0x26 => "lbr",
+        0x27 => "lhr",
+        0x30 => "cgfr",
+        0x31 => "clgfr",
+        0x46 => "bctgr",
+        0x80 => "ngr",
+        0x81 => "ogr",
+        0x82 => "xgr",
+        0x83 => "flogr",
+        0x84 => "llgcr",
+        0x85 => "llghr",
+        0x86 => return ef("mlgr", RRE(Gpr, Gpr), 0, PAIR),
+        0x87 => return ef("dlgr", RRE(Gpr, Gpr), 0, PAIR),
+        0x88 => "alcgr",
+        0x89 => "slbgr",
+        0x94 => "llcr",
+        0x95 => "llhr",
+        0x96 => return ef("mlr", RRE(Gpr, Gpr), 0, PAIR),
+        0x97 => return ef("dlr", RRE(Gpr, Gpr), 0, PAIR),
+        0x98 => "alcr",
+        0x99 => "slbr",
+        0xe2 => return e("locgr", RRFc, 0),
+        0xf2 => return e("locr", RRFc, 0),
+        0xe4 => return e("ngrk", RRFa, 0),
+        0xe6 => return e("ogrk", RRFa, 0),
+        0xe7 => return e("xgrk", RRFa, 0),
+        0xe8 => return e("agrk", RRFa, 0),
+        0xe9 => return e("sgrk", RRFa, 0),
+        0xea => return e("algrk", RRFa, 0),
+        0xeb => return e("slgrk", RRFa, 0),
+        0xf4 => return e("nrk", RRFa, 0),
+        0xf6 => return e("ork", RRFa, 0),
+        0xf7 => return e("xrk", RRFa, 0),
+        0xf8 => return e("ark", RRFa, 0),
+        0xf9 => return e("srk", RRFa, 0),
+        0xfa => return e("alrk", RRFa, 0),
+        0xfb => return e("slrk", RRFa, 0),
+        _ => return None,
+    };
+    e(mnemonic, RRE(Gpr, Gpr), 0)
+}
+
+// e3: loads, stores and arithmetic with a 20 bit displacement
+fn rxy(op: u8) -> Option<Entry> {
+    // Multiply and divide work on an even/odd pair, and so do the quadword loads and stores
+    let flags = match op {
+        0x0d | 0x1d | 0x5c | 0x86 | 0x87 | 0x8e | 0x8f | 0x96 | 0x97 => PAIR,
+        _ => 0,
+    };
+    let (mnemonic, size) = match op {
+        0x02 => ("ltg", 8),
+        0x06 => ("cvby", 8),
+        0x04 => ("lg", 8),
+        0x08 => ("ag", 8),
+        0x09 => ("sg", 8),
+        0x0a => ("alg", 8),
+        0x0b => ("slg", 8),
+        0x0c => ("msg", 8),
+        0x0d => ("dsg", 8),
+        0x0f => ("lrvg", 8),
+        0x12 => ("lt", 4),
+        0x14 => ("lgf", 4),
+        0x15 => ("lgh", 2),
+        0x16 => ("llgf", 4),
+        0x17 => ("llgt", 4),
+        0x18 => ("agf", 4),
+        0x19 => ("sgf", 4),
+        0x1a => ("algf", 4),
+        0x1b => ("slgf", 4),
+        0x1c => ("msgf", 4),
+        0x1d => ("dsgf", 4),
+        0x1e => ("lrv", 4),
+        0x1f => ("lrvh", 2),
+        0x20 => ("cg", 8),
+        0x21 => ("clg", 8),
+        0x24 => ("stg", 8),
+        0x26 => ("cvdy", 8),
+        0x2f => ("strvg", 8),
+        0x30 => ("cgf", 4),
+        0x31 => ("clgf", 4),
+        0x32 => ("ltgf", 4),
+        0x34 => ("cgh", 2),
+        0x36 => return e("pfd", RXY(Mask), 0),
+        0x38 => ("agh", 2),
+        0x39 => ("sgh", 2),
+        0x3c => ("mgh", 2),
+        0x3e => ("strv", 4),
+        0x3f => ("strvh", 2),
+        0x46 => ("bctg", 0),
+        0x50 => ("sty", 4),
+        0x51 => ("msy", 4),
+        0x53 => ("msc", 4),
+        0x54 => ("ny", 4),
+        0x55 => ("cly", 4),
+        0x56 => ("oy", 4),
+        0x57 => ("xy", 4),
+        0x58 => ("ly", 4),
+        0x59 => ("cy", 4),
+        0x5a => ("ay", 4),
+        0x5b => ("sy", 4),
+        0x5c => ("mfy", 4),
+        0x5e => ("aly", 4),
+        0x5f => ("sly", 4),
+        0x70 => ("sthy", 2),
+        0x71 => ("lay", 0),
+        0x72 => ("stcy", 1),
+        0x73 => ("icy", 1),
+        0x75 => ("laey", 0),
+        0x76 => ("lb", 1),
+        0x77 => ("lgb", 1),
+        0x78 => ("lhy", 2),
+        0x79 => ("chy", 2),
+        0x7a => ("ahy", 2),
+        0x7b => ("shy", 2),
+        0x7c => ("mhy", 2),
+        0x80 => ("ng", 8),
+        0x81 => ("og", 8),
+        0x82 => ("xg", 8),
+        0x83 => ("msgc", 8),
+        0x86 => ("mlg", 8),
+        0x87 => ("dlg", 8),
+        0x88 => ("alcg", 8),
+        0x89 => ("slbg", 8),
+        0x8e => ("stpq", 16),
+        0x8f => ("lpq", 16),
+        0x90 => ("llgc", 1),
+        0x91 => ("llgh", 2),
+        0x94 => ("llc", 1),
+        0x95 => ("llh", 2),
+        0x96 => ("ml", 4),
+        0x97 => ("dl", 4),
+        0x98 => ("alc", 4),
+        0x99 => ("slb", 4),
+        0xca => ("lfh", 4),
+        0xcb => ("stfh", 4),
+        _ => return None,
+    };
+    ef(mnemonic, RXY(Gpr), size, flags)
+}
+
+// e5: storage and immediate operations with a 16 bit immediate
+fn sil(op: u8) -> Option<Entry> {
+    match op {
+        0x44 => e("mvhhi", SIL(Signed), 2),
+        0x48 => e("mvghi", SIL(Signed), 8),
+        0x4c => e("mvhi", SIL(Signed), 4),
+        0x54 => e("chhsi", SIL(Signed), 2),
+        0x55 => e("clhhsi", SIL(Unsigned), 2),
+        0x58 => e("cghsi", SIL(Signed), 8),
+        0x59 => e("clghsi", SIL(Unsigned), 8),
+        0x5c => e("chsi", SIL(Signed), 4),
+        0x5d => e("clfhsi", SIL(Unsigned), 4),
+        _ => None,
+    }
+}
+
+// eb: shifts, multiple loads and stores, atomics and storage immediates with a 20 bit displacement
+fn rsy(op: u8) -> Option<Entry> {
+    match op {
+        0x04 => e("lmg", RSY(Gpr, Gpr), 8),
+        0x0a => e("srag", RSY(Gpr, Gpr), 0),
+        0x0b => e("slag", RSY(Gpr, Gpr), 0),
+        0x0c => e("srlg", RSY(Gpr, Gpr), 0),
+        0x0d => e("sllg", RSY(Gpr, Gpr), 0),
+        0x14 => e("csy", RSY(Gpr, Gpr), 4),
+        0x1c => e("rllg", RSY(Gpr, Gpr), 0),
+        0x1d => e("rll", RSY(Gpr, Gpr), 0),
+        0x20 => e("clmh", RSY(Gpr, Mask), 0),
+        0x21 => e("clmy", RSY(Gpr, Mask), 0),
+        0x24 => e("stmg", RSY(Gpr, Gpr), 8),
+        0x26 => e("stmh", RSY(Gpr, Gpr), 4),
+        0x2c => e("stcmh", RSY(Gpr, Mask), 0),
+        0x2d => e("stcmy", RSY(Gpr, Mask), 0),
+        0x30 => e("csg", RSY(Gpr, Gpr), 8),
+        0x31 => ef("cdsy", RSY(Gpr, Gpr), 8, PAIR | PAIR2),
+        0x3e => ef("cdsg", RSY(Gpr, Gpr), 16, PAIR | PAIR2),
+        0x51 => e("tmy", SIY(Unsigned), 1),
+        0x52 => e("mviy", SIY(Unsigned), 1),
+        0x54 => e("niy", SIY(Unsigned), 1),
+        0x55 => e("cliy", SIY(Unsigned), 1),
+        0x56 => e("oiy", SIY(Unsigned), 1),
+        0x57 => e("xiy", SIY(Unsigned), 1),
+        0x6a => e("asi", SIY(Signed), 4),
+        0x6e => e("alsi", SIY(Signed), 4),
+        0x7a => e("agsi", SIY(Signed), 8),
+        0x7e => e("algsi", SIY(Signed), 8),
+        0x80 => e("icmh", RSY(Gpr, Mask), 0),
+        0x81 => e("icmy", RSY(Gpr, Mask), 0),
+        0x90 => e("stmy", RSY(Gpr, Gpr), 4),
+        0x96 => e("lmh", RSY(Gpr, Gpr), 4),
+        0x98 => e("lmy", RSY(Gpr, Gpr), 4),
+        0x9a => e("lamy", RSY(Ar, Ar), 4),
+        0x9b => e("stamy", RSY(Ar, Ar), 4),
+        0xdc => e("srak", RSY(Gpr, Gpr), 0),
+        0xdd => e("slak", RSY(Gpr, Gpr), 0),
+        0xde => e("srlk", RSY(Gpr, Gpr), 0),
+        0xdf => e("sllk", RSY(Gpr, Gpr), 0),
+        0xe2 => ef("locg", RSY(Gpr, Mask), 8, MASK_LAST),
+        0xe3 => ef("stocg", RSY(Gpr, Mask), 8, MASK_LAST),
+        0xe4 => e("lang", RSY(Gpr, Gpr), 8),
+        0xe6 => e("laog", RSY(Gpr, Gpr), 8),
+        0xe7 => e("laxg", RSY(Gpr, Gpr), 8),
+        0xe8 => e("laag", RSY(Gpr, Gpr), 8),
+        0xea => e("laalg", RSY(Gpr, Gpr), 8),
+        0xf2 => ef("loc", RSY(Gpr, Mask), 4, MASK_LAST),
+        0xf3 => ef("stoc", RSY(Gpr, Mask), 4, MASK_LAST),
+        0xf4 => e("lan", RSY(Gpr, Gpr), 4),
+        0xf6 => e("lao", RSY(Gpr, Gpr), 4),
+        0xf7 => e("lax", RSY(Gpr, Gpr), 4),
+        0xf8 => e("laa", RSY(Gpr, Gpr), 4),
+        0xfa => e("laal", RSY(Gpr, Gpr), 4),
+        _ => None,
+    }
+}
+
+// ec: rotate then insert, compare and branch, and the immediate forms with distinct operands
+fn rie(op: u8) -> Option<Entry> {
+    match op {
+        0x42 => e("lochi", RIEg, 0),
+        0x46 => e("locghi", RIEg, 0),
+        0x44 => e("brxhg", RSI, 0),
+        0x45 => e("brxlg", RSI, 0),
+        0x51 => e("risblg", RIEf, 0),
+        0x54 => e("rnsbg", RIEf, 0),
+        0x55 => e("risbg", RIEf, 0),
+        0x56 => e("rosbg", RIEf, 0),
+        0x57 => e("rxsbg", RIEf, 0),
+        0x59 => e("risbgn", RIEf, 0),
+        0x5d => e("risbhg", RIEf, 0),
+        0x64 => e("cgrj", RIEb, 0),
+        0x65 => e("clgrj", RIEb, 0),
+        0x76 => e("crj", RIEb, 0),
+        0x77 => e("clrj", RIEb, 0),
+        0x7c => e("cgij", RIEc(Signed), 0),
+        0x7d => e("clgij", RIEc(Unsigned), 0),
+        0x7e => e("cij", RIEc(Signed), 0),
+        0x7f => e("clij", RIEc(Unsigned), 0),
+        0xd8 => e("ahik", RIEd(Signed), 0),
+        0xd9 => e("aghik", RIEd(Signed), 0),
+        0xda => e("alhsik", RIEd(Signed), 0),
+        0xdb => e("alghsik", RIEd(Signed), 0),
+        _ => None,
+    }
+}
+
+// ed: binary floating point with a storage operand
+fn float_extended(op: u8) -> Option<Entry> {
+    match op {
+        0x04 => e("ldeb", RXE(Fpr), 4),
+        0x09 => e("ceb", RXE(Fpr), 4),
+        0x0a => e("aeb", RXE(Fpr), 4),
+        0x0b => e("seb", RXE(Fpr), 4),
+        0x0d => e("deb", RXE(Fpr), 4),
+        0x14 => e("sqeb", RXE(Fpr), 4),
+        0x15 => e("sqdb", RXE(Fpr), 8),
+        0x17 => e("meeb", RXE(Fpr), 4),
+        0x19 => e("cdb", RXE(Fpr), 8),
+        0x1a => e("adb", RXE(Fpr), 8),
+        0x1b => e("sdb", RXE(Fpr), 8),
+        0x1c => e("mdb", RXE(Fpr), 8),
+        0x1d => e("ddb", RXE(Fpr), 8),
+        0x64 => e("ley", RXY(Fpr), 4),
+        0x65 => e("ldy", RXY(Fpr), 8),
+        0x66 => e("stey", RXY(Fpr), 4),
+        0x67 => e("stdy", RXY(Fpr), 8),
+        _ => None,
+    }
+}
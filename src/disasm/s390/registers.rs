@@ -0,0 +1,35 @@
+use crate::disasm::{Register, RegisterClass};
+
+const R: [&str; 16] = [
+    "%r0", "%r1", "%r2", "%r3", "%r4", "%r5", "%r6", "%r7", "%r8", "%r9", "%r10", "%r11", "%r12", "%r13", "%r14", "%r15",
+];
+
+const F: [&str; 16] = [
+    "%f0", "%f1", "%f2", "%f3", "%f4", "%f5", "%f6", "%f7", "%f8", "%f9", "%f10", "%f11", "%f12", "%f13", "%f14", "%f15",
+];
+
+// Access registers, a0 and a1 hold the thread pointer
+const A: [&str; 16] = [
+    "%a0", "%a1", "%a2", "%a3", "%a4", "%a5", "%a6", "%a7", "%a8", "%a9", "%a10", "%a11", "%a12", "%a13", "%a14", "%a15",
+];
+
+// The link register of the calling convention
+pub const LINK: u32 = 14;
+
+/// A general purpose register, 64 bits wide in z/Architecture
+pub fn gpr(number: u32) -> Register {
+    let number = number & 15;
+    Register::new(RegisterClass::General, number as u16, 64, R[number as usize])
+}
+
+/// A floating point register
+pub fn fpr(number: u32) -> Register {
+    let number = number & 15;
+    Register::new(RegisterClass::Float, number as u16, 64, F[number as usize])
+}
+
+/// An access register
+pub fn ar(number: u32) -> Register {
+    let number = number & 15;
+    Register::new(RegisterClass::Special, number as u16, 32, A[number as usize])
+}
@@ -0,0 +1,116 @@
+use crate::disasm::{DelaySlot, FlowKind, Operand};
+
+use super::registers::{fcc, icc};
+use super::{bit, bits, imm, ins, rd, rs1, sign_extend, Context, Decoded};
+
+// Integer conditions of Bicc, BPcc, Tcc and MOVcc, by the cond field
+pub const CONDITIONS: [&str; 16] = ["n", "e", "le", "l", "leu", "cs", "neg", "vs", "a", "ne", "g", "ge", "gu", "cc", "pos", "vc"];
+
+// Floating point conditions of FBfcc, FBPfcc and FMOVcc
+pub const FLOAT_CONDITIONS: [&str; 16] = ["n", "ne", "lg", "ul", "l", "ug", "g", "u", "a", "e", "ue", "ge", "uge", "le", "ule", "o"];
+
+// Register conditions of BPr and MOVr, reserved encodings are empty
+pub const REGISTER_CONDITIONS: [&str; 8] = ["", "z", "lez", "lz", "", "nz", "gz", "gez"];
+
+// The annul suffix, and for SPARC V9 the static prediction
+fn suffixes(word: u32, predict: bool) -> String {
+    let mut res = String::new();
+    if bit(word, 29) {
+        res.push_str(",a");
+    }
+    if predict {
+        res.push_str(if bit(word, 19) { ",pt" } else { ",pn" });
+    }
+    res
+}
+
+/// Branches, sethi and unimp
+pub fn format2(word: u32, c: &Context) -> Decoded {
+    let cond = bits(word, 25, 4);
+    match bits(word, 22, 3) {
+        0 if rd(word) == 0 => ins("unimp", vec![imm(bits(word, 0, 22) as i64)]),
+        // BPcc, with a condition code register and a 19 bit displacement
+        1 if c.v9 => {
+            let register = match bits(word, 20, 2) {
+                0 => icc(false),
+                2 => icc(true),
+                _ => return None,
+            };
+            let mnemonic = format!("b{}{}", CONDITIONS[cond as usize], suffixes(word, true));
+            ins(&mnemonic, vec![Operand::Register(register), c.target(sign_extend(bits(word, 0, 19), 19))])
+        }
+        2 => {
+            let mnemonic = format!("b{}{}", CONDITIONS[cond as usize], suffixes(word, false));
+            ins(&mnemonic, vec![c.target(sign_extend(bits(word, 0, 22), 22))])
+        }
+        // BPr, branch on the contents of a register, with a 16 bit displacement split in two
+        3 if c.v9 && !bit(word, 28) => {
+            let condition = REGISTER_CONDITIONS[bits(word, 25, 3) as usize];
+            if condition.is_empty() {
+                return None;
+            }
+            let offset = sign_extend((bits(word, 20, 2) << 14) | bits(word, 0, 14), 16);
+            let mnemonic = format!("br{}{}", condition, suffixes(word, true));
+            ins(&mnemonic, vec![c.reg(rs1(word)), c.target(offset)])
+        }
+        4 if rd(word) == 0 && bits(word, 0, 22) == 0 => ins("nop", vec![]),
+        4 => ins("sethi", vec![imm((bits(word, 0, 22) as i64) << 10), c.reg(rd(word))]),
+        5 if c.v9 => {
+            let mnemonic = format!("fb{}{}", FLOAT_CONDITIONS[cond as usize], suffixes(word, true));
+            let register = Operand::Register(fcc(bits(word, 20, 2)));
+            ins(&mnemonic, vec![register, c.target(sign_extend(bits(word, 0, 19), 19))])
+        }
+        6 => {
+            let mnemonic = format!("fb{}{}", FLOAT_CONDITIONS[cond as usize], suffixes(word, false));
+            ins(&mnemonic, vec![c.target(sign_extend(bits(word, 0, 22), 22))])
+        }
+        _ => None,
+    }
+}
+
+// Whether `name` is a conditional branch or trap on one of the conditions in `table`
+fn condition<'a>(name: &'a str, prefix: &str, table: &[&str]) -> Option<&'a str> {
+    let condition = name.strip_prefix(prefix)?;
+    if table.contains(&condition) {
+        Some(condition)
+    } else {
+        None
+    }
+}
+
+pub fn flow_of(mnemonic: &str) -> (FlowKind, bool, DelaySlot) {
+    use DelaySlot::{Always, Taken};
+
+    // The annul bit cancels the delay slot of untaken conditional branches, and of ba and bn always
+    let mut parts = mnemonic.split(',');
+    let name = parts.next().unwrap_or_default();
+    let annul = parts.any(|x| x == "a");
+
+    let branch = condition(name, "br", &REGISTER_CONDITIONS)
+        .or_else(|| condition(name, "fb", &FLOAT_CONDITIONS))
+        .or_else(|| condition(name, "b", &CONDITIONS));
+    if let Some(condition) = branch {
+        return match (condition, annul) {
+            ("a", false) => (FlowKind::Jump, false, Always),
+            ("a", true) => (FlowKind::Jump, false, DelaySlot::None),
+            // Never taken, with the annul bit it skips the next instruction
+            ("n", _) => (FlowKind::Sequential, false, DelaySlot::None),
+            (_, false) => (FlowKind::Jump, true, Always),
+            (_, true) => (FlowKind::Jump, true, Taken),
+        };
+    }
+    if let Some(condition) = condition(name, "t", &CONDITIONS) {
+        return match condition {
+            "a" => (FlowKind::Syscall, false, DelaySlot::None),
+            "n" => (FlowKind::Sequential, false, DelaySlot::None),
+            _ => (FlowKind::Trap, true, DelaySlot::None),
+        };
+    }
+    match name {
+        "call" | "jmpl" => (FlowKind::Call, false, Always),
+        "jmp" => (FlowKind::Jump, false, Always),
+        "ret" | "retl" | "rett" | "return" => (FlowKind::Return, false, Always),
+        "done" | "retry" => (FlowKind::Return, false, DelaySlot::None),
+        _ => (FlowKind::Sequential, false, DelaySlot::None),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::disasm::Operand;
+
+use super::branch::{CONDITIONS, FLOAT_CONDITIONS, REGISTER_CONDITIONS};
+use super::registers::{fcc, fpr, icc};
+use super::{bit, bits, ins, op3, rd, rs1, rs2, Context, Decoded};
+
+// FPop1 operations by opf: name, width of the sources and of the result, and whether rs1 is a source
+fn operation(opf: u32, v9: bool) -> Option<(&'static str, u16, u16, bool)> {
+    Some(match opf {
+        0x01 => ("fmovs", 32, 32, false),
+        0x02 if v9 => ("fmovd", 64, 64, false),
+        0x03 if v9 => ("fmovq", 128, 128, false),
+        0x05 => ("fnegs", 32, 32, false),
+        0x06 if v9 => ("fnegd", 64, 64, false),
+        0x07 if v9 => ("fnegq", 128, 128, false),
+        0x09 => ("fabss", 32, 32, false),
+        0x0a if v9 => ("fabsd", 64, 64, false),
+        0x0b if v9 => ("fabsq", 128, 128, false),
+        0x29 => ("fsqrts", 32, 32, false),
+        0x2a => ("fsqrtd", 64, 64, false),
+        0x2b => ("fsqrtq", 128, 128, false),
+        0x41 => ("fadds", 32, 32, true),
+        0x42 => ("faddd", 64, 64, true),
+        0x43 => ("faddq", 128, 128, true),
+        0x45 => ("fsubs", 32, 32, true),
+        0x46 => ("fsubd", 64, 64, true),
+        0x47 => ("fsubq", 128, 128, true),
+        0x49 => ("fmuls", 32, 32, true),
+        0x4a => ("fmuld", 64, 64, true),
+        0x4b => ("fmulq", 128, 128, true),
+        0x4d => ("fdivs", 32, 32, true),
+        0x4e => ("fdivd", 64, 64, true),
+        0x4f => ("fdivq", 128, 128, true),
+        0x69 => ("fsmuld", 32, 64, true),
+        0x6e => ("fdmulq", 64, 128, true),
+        0x81 if v9 => ("fstox", 32, 64, false),
+        0x82 if v9 => ("fdtox", 64, 64, false),
+        0x83 if v9 => ("fqtox", 128, 64, false),
+        0x84 if v9 => ("fxtos", 64, 32, false),
+        0x88 if v9 => ("fxtod", 64, 64, false),
+        0x8c if v9 => ("fxtoq", 64, 128, false),
+        0xc4 => ("fitos", 32, 32, false),
+        0xc6 => ("fdtos", 64, 32, false),
+        0xc7 => ("fqtos", 128, 32, false),
+        0xc8 => ("fitod", 32, 64, false),
+        0xc9 => ("fstod", 32, 64, false),
+        0xcb => ("fqtod", 128, 64, false),
+        0xcc => ("fitoq", 32, 128, false),
+        0xcd => ("fstoq", 32, 128, false),
+        0xce => ("fdtoq", 64, 128, false),
+        0xd1 => ("fstoi", 32, 32, false),
+        0xd2 => ("fdtoi", 64, 32, false),
+        0xd3 => ("fqtoi", 128, 32, false),
+        _ => return None,
+    })
+}
+
+// Width of the registers of a move, by the two low bits of opf
+fn width(opf: u32) -> Option<u16> {
+    match opf & 3 {
+        1 => Some(32),
+        2 => Some(64),
+        3 => Some(128),
+        _ => None,
+    }
+}
+
+fn suffix(bits: u16) -> &'static str {
+    match bits {
+        32 => "s",
+        64 => "d",
+        _ => "q",
+    }
+}
+
+/// FPop1 and FPop2: arithmetic, conversions, comparisons and the SPARC V9 conditional moves
+pub fn fpop(word: u32, c: &Context) -> Decoded {
+    let opf = bits(word, 5, 9);
+    let (rd, rs1, rs2) = (rd(word), rs1(word), rs2(word));
+
+    if op3(word) == 0x34 {
+        let (name, source, result, binary) = operation(opf, c.v9)?;
+        let mut operands = vec![];
+        if binary {
+            operands.push(Operand::Register(fpr(rs1, source)));
+        } else if rs1 != 0 {
+            return None;
+        }
+        operands.push(Operand::Register(fpr(rs2, source)));
+        operands.push(Operand::Register(fpr(rd, result)));
+        return ins(name, operands);
+    }
+
+    match opf {
+        // Comparisons, which set %fcc0 or on SPARC V9 one of the four condition codes
+        0x51..=0x53 | 0x55..=0x57 => {
+            let size = width(opf)?;
+            let name = format!("fcmp{}{}", if opf & 4 != 0 { "e" } else { "" }, suffix(size));
+            let mut operands = vec![];
+            if c.v9 {
+                operands.push(Operand::Register(fcc(rd & 3)));
+            } else if rd != 0 {
+                return None;
+            }
+            operands.push(Operand::Register(fpr(rs1, size)));
+            operands.push(Operand::Register(fpr(rs2, size)));
+            ins(&name, operands)
+        }
+        _ if !c.v9 => None,
+        // FMOVr, on the contents of an integer register
+        _ if bits(opf, 2, 3) == 1 && !bit(word, 13) => {
+            let condition = REGISTER_CONDITIONS[bits(word, 10, 3) as usize];
+            let size = width(opf)?;
+            if condition.is_empty() {
+                return None;
+            }
+            let name = format!("fmovr{}{}", suffix(size), condition);
+            ins(&name, vec![c.reg(rs1), Operand::Register(fpr(rs2, size)), Operand::Register(fpr(rd, size))])
+        }
+        // FMOVcc, on an integer or floating point condition code
+        _ if bits(opf, 2, 4) == 0 && !bit(word, 18) => {
+            let size = width(opf)?;
+            let cond = bits(word, 14, 4) as usize;
+            let (condition, register) = match bits(opf, 6, 3) {
+                0..=3 => (FLOAT_CONDITIONS[cond], fcc(bits(opf, 6, 2))),
+                4 => (CONDITIONS[cond], icc(false)),
+                6 => (CONDITIONS[cond], icc(true)),
+                _ => return None,
+            };
+            let name = format!("fmov{}{}", suffix(size), condition);
+            let (source, destination) = (Operand::Register(fpr(rs2, size)), Operand::Register(fpr(rd, size)));
+            ins(&name, vec![Operand::Register(register), source, destination])
+        }
+        _ => None,
+    }
+}
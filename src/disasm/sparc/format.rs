@@ -0,0 +1,87 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma and a space, memory operands are
+// written as [ base + offset ], immediates above 9 are hex
+
+pub fn format(instruction: &Instruction) -> String {
+    let mnemonic = &instruction.mnemonic;
+    let brackets = !address_expression(mnemonic);
+
+    let mut res = mnemonic.clone();
+    if mnemonic == "membar" {
+        let masks: Vec<String> = instruction.operands.iter().map(|x| operand(x, brackets)).collect();
+        res.push(' ');
+        res.push_str(&masks.join(" | "));
+        return res;
+    }
+    if mnemonic == "sethi" {
+        if let Some(Operand::Immediate { value, .. }) = instruction.operands.first() {
+            return format!("sethi %hi(0x{:x}), {}", value, operand(&instruction.operands[1], brackets));
+        }
+    }
+
+    let mut previous: Option<&Operand> = None;
+    for x in &instruction.operands {
+        // The address space of an alternate load or store follows the address without a comma
+        let separator = match previous {
+            None => " ",
+            Some(Operand::Memory(_)) if alternate_space(mnemonic) => " ",
+            _ => ", ",
+        };
+        res.push_str(separator);
+        res.push_str(&operand(x, brackets));
+        previous = Some(x);
+    }
+    res
+}
+
+// Instructions whose address is not a memory access and so has no brackets
+fn address_expression(mnemonic: &str) -> bool {
+    match mnemonic {
+        "jmp" | "jmpl" | "call" | "rett" | "return" | "flush" => true,
+        // Traps take their number as rs1 + rs2 or rs1 + immediate
+        _ => mnemonic.starts_with('t') && !matches!(mnemonic, "tst" | "taddcc" | "tsubcc" | "taddcctv" | "tsubcctv"),
+    }
+}
+
+// The alternate space forms of the loads, stores and atomics end in an a
+fn alternate_space(mnemonic: &str) -> bool {
+    mnemonic.ends_with('a') && ["ld", "st", "swap", "cas", "prefetch"].iter().any(|x| mnemonic.starts_with(x))
+}
+
+fn number(value: i64) -> String {
+    if value > 9 {
+        format!("0x{:x}", value)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn memory(mem: &MemoryOperand, brackets: bool) -> String {
+    let mut parts = vec![];
+    if let Some(base) = mem.base {
+        parts.push(base.name.to_string());
+    }
+    if let Some(index) = mem.index {
+        parts.push(index.name.to_string());
+    }
+    if mem.displacement != 0 || parts.is_empty() {
+        parts.push(number(mem.displacement));
+    }
+    if brackets {
+        format!("[ {} ]", parts.join(" + "))
+    } else {
+        parts.join(" + ")
+    }
+}
+
+fn operand(operand: &Operand, brackets: bool) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, .. } => number(*value),
+        Operand::Memory(mem) => memory(mem, brackets),
+        Operand::Address(address) => format!("0x{:x}", address),
+        Operand::Name(name) => name.to_string(),
+        other => format!("{:?}", other),
+    }
+}
@@ -0,0 +1,220 @@
+use crate::disasm::{MemoryOperand, Operand};
+
+use super::branch::{CONDITIONS, FLOAT_CONDITIONS, REGISTER_CONDITIONS};
+use super::registers::{asr, fcc, gpr, icc, privileged, special, I7, O7};
+use super::{bit, bits, imm, ins, op3, rd, rs1, rs2, simm13, Context, Decoded};
+
+// Three operand arithmetic and logic: rs1, rs2 or simm13, rd
+fn operation(op3: u32, v9: bool) -> Option<&'static str> {
+    Some(match op3 {
+        0x00 => "add",
+        0x01 => "and",
+        0x02 => "or",
+        0x03 => "xor",
+        0x04 => "sub",
+        0x05 => "andn",
+        0x06 => "orn",
+        0x07 => "xnor",
+        0x08 => "addx",
+        0x09 if v9 => "mulx",
+        0x0a => "umul",
+        0x0b => "smul",
+        0x0c => "subx",
+        0x0d if v9 => "udivx",
+        0x0e => "udiv",
+        0x0f => "sdiv",
+        0x10 => "addcc",
+        0x11 => "andcc",
+        0x12 => "orcc",
+        0x13 => "xorcc",
+        0x14 => "subcc",
+        0x15 => "andncc",
+        0x16 => "orncc",
+        0x17 => "xnorcc",
+        0x18 => "addxcc",
+        0x1a => "umulcc",
+        0x1b => "smulcc",
+        0x1c => "subxcc",
+        0x1e => "udivcc",
+        0x1f => "sdivcc",
+        0x20 => "taddcc",
+        0x21 => "tsubcc",
+        0x22 => "taddcctv",
+        0x23 => "tsubcctv",
+        0x24 => "mulscc",
+        0x2d if v9 => "sdivx",
+        0x3c => "save",
+        0x3d => "restore",
+        _ => return None,
+    })
+}
+
+// The synthetic instructions GNU objdump shows instead of some of the plain operations
+fn synthetic(name: &str, word: u32, c: &Context) -> Decoded {
+    let (rd, rs1) = (rd(word), rs1(word));
+    let source = c.source(word, 13);
+    let zero_source = !bit(word, 13) && rs2(word) == 0;
+    match name {
+        "or" if rs1 == 0 && zero_source => ins("clr", vec![c.reg(rd)]),
+        "or" if rs1 == 0 => ins("mov", vec![source, c.reg(rd)]),
+        "or" if zero_source => ins("mov", vec![c.reg(rs1), c.reg(rd)]),
+        "subcc" if rd == 0 => ins("cmp", vec![c.reg(rs1), source]),
+        "orcc" if rd == 0 && rs1 == 0 => ins("tst", vec![source]),
+        "orcc" if rd == 0 && zero_source => ins("tst", vec![c.reg(rs1)]),
+        "xnor" if zero_source && rs1 == rd => ins("not", vec![c.reg(rd)]),
+        "xnor" if zero_source => ins("not", vec![c.reg(rs1), c.reg(rd)]),
+        "sub" if rs1 == 0 && !bit(word, 13) && rs2(word) == rd => ins("neg", vec![c.reg(rd)]),
+        "sub" if rs1 == 0 && !bit(word, 13) => ins("neg", vec![source, c.reg(rd)]),
+        "save" | "restore" if rd == 0 && rs1 == 0 && zero_source => ins(name, vec![]),
+        _ => None,
+    }
+}
+
+/// Format 3 instructions with op = 2, other than the floating point operations
+pub fn arithmetic(word: u32, c: &Context) -> Decoded {
+    let (op3, rd, rs1) = (op3(word), rd(word), rs1(word));
+    let immediate = bit(word, 13);
+
+    if let Some(name) = operation(op3, c.v9) {
+        // The register form has reserved bits between i and rs2
+        if !immediate && bits(word, 5, 8) != 0 {
+            return None;
+        }
+        return synthetic(name, word, c).or_else(|| ins(name, vec![c.reg(rs1), c.source(word, 13), c.reg(rd)]));
+    }
+
+    match op3 {
+        // Shifts, with a 6 bit count and the x bit selecting the 64 bit forms on SPARC V9
+        0x25..=0x27 => {
+            let extended = bit(word, 12);
+            if extended && !c.v9 {
+                return None;
+            }
+            let names = if extended { ["sllx", "srlx", "srax"] } else { ["sll", "srl", "sra"] };
+            let count = if extended { 6 } else { 5 };
+            let source = if immediate { imm(bits(word, 0, count) as i64) } else { c.reg(rs2(word)) };
+            ins(names[(op3 - 0x25) as usize], vec![c.reg(rs1), source, c.reg(rd)])
+        }
+        0x28 => read_state(word, c),
+        0x29 if !c.v9 => ins("rd", vec![Operand::Register(special("%psr")), c.reg(rd)]),
+        0x2a if !c.v9 => ins("rd", vec![Operand::Register(special("%wim")), c.reg(rd)]),
+        0x2a => {
+            let register = if rs1 == 31 { Some(special("%ver")) } else { privileged(rs1) };
+            ins("rdpr", vec![Operand::Register(register?), c.reg(rd)])
+        }
+        0x2b if !c.v9 => ins("rd", vec![Operand::Register(special("%tbr")), c.reg(rd)]),
+        0x2b if rd == 0 && rs1 == 0 && !immediate && bits(word, 0, 13) == 0 => ins("flushw", vec![]),
+        0x2c if c.v9 => move_on_condition(word, c),
+        0x2e if c.v9 && rs1 == 0 => ins("popc", vec![c.source(word, 13), c.reg(rd)]),
+        0x2f if c.v9 => {
+            let condition = REGISTER_CONDITIONS[bits(word, 10, 3) as usize];
+            if condition.is_empty() {
+                return None;
+            }
+            let name = format!("movr{}", condition);
+            ins(&name, vec![c.reg(rs1), c.source(word, 10), c.reg(rd)])
+        }
+        0x30 => {
+            // Writes rs1 xor the source to an ancillary state register
+            ins("wr", vec![c.reg(rs1), c.source(word, 13), Operand::Register(asr(rd, c.v9))])
+        }
+        0x31 if !c.v9 => ins("wr", vec![c.reg(rs1), c.source(word, 13), Operand::Register(special("%psr"))]),
+        0x31 if rs1 == 0 && !immediate && bits(word, 0, 13) == 0 => match rd {
+            0 => ins("saved", vec![]),
+            1 => ins("restored", vec![]),
+            _ => None,
+        },
+        0x32 if !c.v9 => ins("wr", vec![c.reg(rs1), c.source(word, 13), Operand::Register(special("%wim"))]),
+        0x32 => ins("wrpr", vec![c.reg(rs1), c.source(word, 13), Operand::Register(privileged(rd)?)]),
+        0x33 if !c.v9 => ins("wr", vec![c.reg(rs1), c.source(word, 13), Operand::Register(special("%tbr"))]),
+        0x38 => jump_and_link(word, c),
+        0x39 => ins(if c.v9 { "return" } else { "rett" }, vec![c.address(word, 0)]),
+        0x3a => trap(word, c),
+        0x3b => ins("flush", vec![c.address(word, 0)]),
+        0x3e if c.v9 && rs1 == 0 && !immediate && bits(word, 0, 13) == 0 => match rd {
+            0 => ins("done", vec![]),
+            1 => ins("retry", vec![]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// rd from %y and the other ancillary state registers, with stbar and membar in the %asr15 space
+fn read_state(word: u32, c: &Context) -> Decoded {
+    const MEMBAR: [&str; 7] = ["#LoadLoad", "#StoreLoad", "#LoadStore", "#StoreStore", "#Lookaside", "#MemIssue", "#Sync"];
+
+    let (rd, rs1) = (rd(word), rs1(word));
+    if rs1 == 15 && rd == 0 {
+        if !bit(word, 13) {
+            return ins("stbar", vec![]);
+        }
+        if c.v9 {
+            let mask = bits(word, 0, 7);
+            if mask == 0 {
+                return ins("membar", vec![imm(0)]);
+            }
+            let operands = (0..7).filter(|x| mask & (1 << x) != 0).map(|x| Operand::Name(MEMBAR[x])).collect();
+            return ins("membar", operands);
+        }
+    }
+    ins("rd", vec![Operand::Register(asr(rs1, c.v9)), c.reg(rd)])
+}
+
+// MOVcc of SPARC V9, on an integer or floating point condition code
+fn move_on_condition(word: u32, c: &Context) -> Decoded {
+    let cond = bits(word, 14, 4) as usize;
+    let (name, register) = if bit(word, 18) {
+        let register = match bits(word, 11, 2) {
+            0 => icc(false),
+            2 => icc(true),
+            _ => return None,
+        };
+        (format!("mov{}", CONDITIONS[cond]), register)
+    } else {
+        (format!("mov{}", FLOAT_CONDITIONS[cond]), fcc(bits(word, 11, 2)))
+    };
+    ins(&name, vec![Operand::Register(register), c.source(word, 11), c.reg(rd(word))])
+}
+
+// jmpl, with ret, retl, jmp and the indirect call as its common forms
+fn jump_and_link(word: u32, c: &Context) -> Decoded {
+    let (rd, rs1) = (rd(word), rs1(word));
+    let returns = bit(word, 13) && simm13(word) == 8;
+    match rd {
+        0 if returns && rs1 == I7 => ins("ret", vec![]),
+        0 if returns && rs1 == O7 => ins("retl", vec![]),
+        0 => ins("jmp", vec![c.address(word, 0)]),
+        O7 => ins("call", vec![c.address(word, 0)]),
+        _ => ins("jmpl", vec![c.address(word, 0), c.reg(rd)]),
+    }
+}
+
+// Ticc, a software trap with a 7 bit (8 bit on SPARC V9) trap number
+fn trap(word: u32, c: &Context) -> Decoded {
+    let cond = bits(word, 25, 4) as usize;
+    if bit(word, 29) {
+        return None;
+    }
+    let mut operands = vec![];
+    if c.v9 {
+        match bits(word, 11, 2) {
+            0 => {}
+            2 => operands.push(Operand::Register(icc(true))),
+            _ => return None,
+        }
+    }
+    let (rs1, number) = (rs1(word), bits(word, 0, if c.v9 { 8 } else { 7 }));
+    operands.push(match (rs1, bit(word, 13)) {
+        (0, true) => imm(number as i64),
+        (_, true) => {
+            let mut mem = MemoryOperand::new(0);
+            mem.base = Some(gpr(rs1, c.v9));
+            mem.displacement = number as i64;
+            Operand::Memory(mem)
+        }
+        (0, false) => c.reg(rs2(word)),
+        (_, false) => c.address(word, 0),
+    });
+    ins(&format!("t{}", CONDITIONS[cond]), operands)
+}
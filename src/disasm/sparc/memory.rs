@@ -0,0 +1,114 @@
+use crate::disasm::{MemoryOperand, Operand};
+
+use super::registers::{fpr, gpr, special};
+use super::{bit, bits, imm, ins, op3, rd, rs1, rs2, Context, Decoded};
+
+// The primary address space, which cas and casx use implicitly
+const ASI_PRIMARY: u32 = 0x80;
+
+// What a load or store transfers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Data {
+    General,
+    // A floating point register of the given width
+    Float(u16),
+    // The floating point state register, or the queue on SPARC V8
+    State(&'static str),
+}
+
+// Name, size in bytes, whether it stores and what it transfers, by op3 without the alternate space bit
+fn operation(op3: u32, v9: bool) -> Option<(&'static str, u16, bool, Data)> {
+    use self::Data::*;
+
+    Some(match op3 {
+        0x00 => ("ld", 4, false, General),
+        0x01 => ("ldub", 1, false, General),
+        0x02 => ("lduh", 2, false, General),
+        0x03 => ("ldd", 8, false, General),
+        0x04 => ("st", 4, true, General),
+        0x05 => ("stb", 1, true, General),
+        0x06 => ("sth", 2, true, General),
+        0x07 => ("std", 8, true, General),
+        0x08 if v9 => ("ldsw", 4, false, General),
+        0x09 => ("ldsb", 1, false, General),
+        0x0a => ("ldsh", 2, false, General),
+        0x0b if v9 => ("ldx", 8, false, General),
+        0x0d => ("ldstub", 1, false, General),
+        0x0e if v9 => ("stx", 8, true, General),
+        0x0f => ("swap", 4, false, General),
+        0x20 => ("ld", 4, false, Float(32)),
+        0x22 if v9 => ("ldq", 16, false, Float(128)),
+        0x23 => ("ldd", 8, false, Float(64)),
+        0x24 => ("st", 4, true, Float(32)),
+        0x26 if v9 => ("stq", 16, true, Float(128)),
+        0x26 => ("std", 8, true, State("%fq")),
+        0x27 => ("std", 8, true, Float(64)),
+        _ => return None,
+    })
+}
+
+/// Format 3 instructions with op = 3: loads, stores and the atomic operations
+pub fn load_store(word: u32, c: &Context) -> Decoded {
+    let (op3, rd) = (op3(word), rd(word));
+
+    // The alternate space is given by an immediate, or on SPARC V9 by %asi when the i bit is set
+    let space = || match bit(word, 13) {
+        true if c.v9 => Some(Operand::Register(special("%asi"))),
+        true => None,
+        false => Some(imm(bits(word, 5, 8) as i64)),
+    };
+
+    match op3 {
+        0x21 | 0x25 => {
+            let (name, size) = match (rd, c.v9) {
+                (0, _) => (if op3 == 0x21 { "ld" } else { "st" }, 4),
+                (1, true) => (if op3 == 0x21 { "ldx" } else { "stx" }, 8),
+                _ => return None,
+            };
+            let state = Operand::Register(special("%fsr"));
+            let address = c.address(word, size);
+            return if op3 == 0x21 { ins(name, vec![address, state]) } else { ins(name, vec![state, address]) };
+        }
+        0x2d if c.v9 => return ins("prefetch", vec![c.address(word, 0), imm(rd as i64)]),
+        0x3d if c.v9 => return ins("prefetcha", vec![c.address(word, 0), space()?, imm(rd as i64)]),
+        // Compare and swap on [rs1], with rs2 as the comparison value
+        0x3c | 0x3e if c.v9 => {
+            let mut mem = MemoryOperand::new(if op3 == 0x3c { 4 } else { 8 });
+            mem.base = Some(gpr(rs1(word), c.v9));
+            let (memory, compare) = (Operand::Memory(mem), c.reg(rs2(word)));
+            let name = if op3 == 0x3c { "cas" } else { "casx" };
+            if !bit(word, 13) && bits(word, 5, 8) == ASI_PRIMARY {
+                return ins(name, vec![memory, compare, c.reg(rd)]);
+            }
+            return ins(&format!("{}a", name), vec![memory, space()?, compare, c.reg(rd)]);
+        }
+        _ => {}
+    }
+
+    // The integer alternate space forms are op3 + 0x10, the floating point ones op3 + 0x10 on SPARC V9 only
+    let alternate = op3 & 0x10 != 0;
+    if alternate && op3 >= 0x30 && !c.v9 {
+        return None;
+    }
+    let (name, size, store, data) = operation(op3 & !0x10, c.v9)?;
+    if alternate && data == Data::State("%fq") {
+        return None;
+    }
+    let register = match data {
+        Data::General => c.reg(rd),
+        Data::Float(bits) => Operand::Register(fpr(rd, bits)),
+        Data::State(name) => Operand::Register(special(name)),
+    };
+    let name = if alternate { format!("{}a", name) } else { name.to_string() };
+
+    let mut operands = vec![c.address(word, size)];
+    if alternate {
+        operands.push(space()?);
+    }
+    if store {
+        operands.insert(0, register);
+    } else {
+        operands.push(register);
+    }
+    ins(&name, operands)
+}
@@ -0,0 +1,219 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, Instruction, MemoryOperand, Operand};
+
+mod registers;
+use self::registers::gpr;
+
+mod branch;
+mod integer;
+mod memory;
+mod float;
+
+mod format;
+pub use self::format::format;
+
+const LENGTH: usize = 4;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is reserved or not supported
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// Decoder for SPARC V8 and the 64 bit SPARC V9, with the FPU
+#[derive(Debug, Clone)]
+pub struct SparcDecoder {
+    big_endian: bool,
+    // SPARC V9 reassigned the coprocessor and some privileged encodings, and added 64 bit operations
+    v9: bool,
+}
+
+impl SparcDecoder {
+    pub fn new(big_endian: bool, v9: bool) -> SparcDecoder {
+        SparcDecoder { big_endian, v9 }
+    }
+}
+
+// What the decoding functions need besides the instruction word
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+    v9: bool,
+}
+
+impl Context {
+    fn reg(&self, number: u32) -> Operand {
+        Operand::Register(gpr(number, self.v9))
+    }
+
+    // The second source operand: rs2, or a signed immediate of `bits` bits when the i bit is set
+    fn source(&self, word: u32, bits: u32) -> Operand {
+        if bit(word, 13) {
+            imm(sign_extend(self::bits(word, 0, bits), bits))
+        } else {
+            self.reg(rs2(word))
+        }
+    }
+
+    // The address rs1 + rs2 or rs1 + simm13 of a load or store, leaving out %g0 unless it is all there is
+    fn address(&self, word: u32, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        let (rs1, rs2) = (rs1(word), rs2(word));
+        if bit(word, 13) {
+            mem.displacement = simm13(word);
+            if rs1 != 0 {
+                mem.base = Some(gpr(rs1, self.v9));
+            }
+        } else {
+            if rs1 != 0 || rs2 == 0 {
+                mem.base = Some(gpr(rs1, self.v9));
+            }
+            if rs2 != 0 {
+                mem.index = Some(gpr(rs2, self.v9));
+            }
+        }
+        Operand::Memory(mem)
+    }
+
+    // Target of a pc relative branch or call, counted in instructions
+    fn target(&self, offset: i64) -> Operand {
+        let target = self.address.wrapping_add((offset << 2) as u64);
+        Operand::Address(if self.v9 { target } else { target & 0xffff_ffff })
+    }
+}
+
+impl Decoder for SparcDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let raw: [u8; LENGTH] = bytes[..LENGTH].try_into().unwrap();
+        let word = if self.big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) };
+
+        let context = Context { address, v9: self.v9 };
+        let (mnemonic, operands) = decode_word(word, &context).ok_or(DecodeError::InvalidOpcode(word))?;
+
+        let mut instruction = Instruction::new(InstructionSet::SPARC, address, LENGTH, String::new(), operands);
+        let (flow, conditional, delay_slot) = branch::flow_of(&mnemonic);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.delay_slot = delay_slot;
+        instruction.mnemonic = mnemonic;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        LENGTH
+    }
+}
+
+fn decode_word(word: u32, c: &Context) -> Decoded {
+    match bits(word, 30, 2) {
+        0 => branch::format2(word, c),
+        1 => ins("call", vec![c.target(sign_extend(bits(word, 0, 30), 30))]),
+        2 => match op3(word) {
+            0x34 | 0x35 => float::fpop(word, c),
+            _ => integer::arithmetic(word, c),
+        },
+        _ => memory::load_store(word, c),
+    }
+}
+
+fn bits(word: u32, low: u32, count: u32) -> u32 {
+    (word >> low) & ((1 << count) - 1)
+}
+
+fn bit(word: u32, n: u32) -> bool {
+    word & (1 << n) != 0
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+fn rd(word: u32) -> u32 {
+    bits(word, 25, 5)
+}
+
+fn rs1(word: u32) -> u32 {
+    bits(word, 14, 5)
+}
+
+fn rs2(word: u32) -> u32 {
+    bits(word, 0, 5)
+}
+
+fn op3(word: u32) -> u32 {
+    bits(word, 19, 6)
+}
+
+fn simm13(word: u32) -> i64 {
+    sign_extend(bits(word, 0, 13), 13)
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparcDecoder;
+    use crate::disasm::{decoder_for, DelaySlot, Decoder, FlowKind, Syntax, Target};
+    use crate::endian::Endianness;
+    use crate::instruction_set::InstructionSet;
+
+    fn text(decoder: &dyn Decoder, word: u32, address: u64) -> String {
+        decoder.decode(&word.to_be_bytes(), address).unwrap().render(Syntax::Intel)
+    }
+
+    #[test]
+    fn delay_slots() {
+        let decoder = SparcDecoder::new(true, false);
+        let words: [u32; 8] = [0x40000040, 0x02800020, 0x22800020, 0x10800040, 0x81c7e008, 0x9fc04000, 0x91d02010, 0x01000000];
+        let expected = [
+            ("call 0x1100", FlowKind::Call, DelaySlot::Always),
+            ("be 0x1080", FlowKind::Jump, DelaySlot::Always),
+            // The annul bit of a conditional branch cancels the delay slot when the branch is not taken
+            ("be,a 0x1080", FlowKind::Jump, DelaySlot::Taken),
+            ("ba 0x1100", FlowKind::Jump, DelaySlot::Always),
+            ("ret", FlowKind::Return, DelaySlot::Always),
+            ("call %g1", FlowKind::Call, DelaySlot::Always),
+            ("ta 0x10", FlowKind::Syscall, DelaySlot::None),
+            ("nop", FlowKind::Sequential, DelaySlot::None),
+        ];
+        for (word, expected) in words.iter().zip(expected.iter()) {
+            let instruction = decoder.decode(&word.to_be_bytes(), 0x1000).unwrap();
+            assert_eq!((instruction.render(Syntax::Intel).as_str(), instruction.flow, instruction.delay_slot), *expected);
+        }
+    }
+
+    #[test]
+    fn operands() {
+        let decoder = SparcDecoder::new(true, false);
+        assert_eq!(text(&decoder, 0x9de3bfa0, 0), "save %sp, -96, %sp");
+        assert_eq!(text(&decoder, 0xd007bffc, 0), "ld [ %fp + -4 ], %o0");
+        assert_eq!(text(&decoder, 0x03048d15, 0), "sethi %hi(0x12345400), %g1");
+        assert_eq!(text(&decoder, 0x82106005, 0), "or %g1, 5, %g1");
+        assert_eq!(text(&decoder, 0x89a00842, 0), "faddd %f0, %f2, %f4");
+    }
+
+    #[test]
+    fn v9_instructions_in_v8plus_binaries() {
+        let v8 = SparcDecoder::new(true, false);
+        assert!(v8.decode(&0xd25a2008u32.to_be_bytes(), 0).is_err());
+
+        let target = Target::new(InstructionSet::SPARC32Plus, Endianness::BigEndian, 32);
+        let v8plus = decoder_for(&target).unwrap();
+        assert_eq!(text(&*v8plus, 0xd25a2008, 0), "ldx [ %o0 + 8 ], %o1");
+        assert_eq!(text(&*v8plus, 0xd2720000, 0), "stx %o1, [ %o0 ]");
+        // casa with the primary address space
+        assert_eq!(text(&*v8plus, 0xd5e21009, 0), "cas [ %o0 ], %o1, %o2");
+        assert_eq!(text(&*v8plus, 0x02ca0020, 0x1000), "brz,pt %o0, 0x1080");
+        assert_eq!(text(&*v8plus, 0x12680020, 0x1000), "bne,pt %xcc, 0x1080");
+    }
+}
@@ -0,0 +1,87 @@
+use crate::disasm::{Register, RegisterClass};
+
+// The global, out, local and in registers of the current window, o6 and i6 are the stack and frame pointers
+const R: [&str; 32] = [
+    "%g0", "%g1", "%g2", "%g3", "%g4", "%g5", "%g6", "%g7",
+    "%o0", "%o1", "%o2", "%o3", "%o4", "%o5", "%sp", "%o7",
+    "%l0", "%l1", "%l2", "%l3", "%l4", "%l5", "%l6", "%l7",
+    "%i0", "%i1", "%i2", "%i3", "%i4", "%i5", "%fp", "%i7",
+];
+
+const F: [&str; 64] = [
+    "%f0", "%f1", "%f2", "%f3", "%f4", "%f5", "%f6", "%f7", "%f8", "%f9", "%f10", "%f11", "%f12", "%f13", "%f14", "%f15",
+    "%f16", "%f17", "%f18", "%f19", "%f20", "%f21", "%f22", "%f23", "%f24", "%f25", "%f26", "%f27", "%f28", "%f29", "%f30", "%f31",
+    "%f32", "%f33", "%f34", "%f35", "%f36", "%f37", "%f38", "%f39", "%f40", "%f41", "%f42", "%f43", "%f44", "%f45", "%f46", "%f47",
+    "%f48", "%f49", "%f50", "%f51", "%f52", "%f53", "%f54", "%f55", "%f56", "%f57", "%f58", "%f59", "%f60", "%f61", "%f62", "%f63",
+];
+
+const FCC: [&str; 4] = ["%fcc0", "%fcc1", "%fcc2", "%fcc3"];
+
+// Ancillary state registers, named on SPARC V9 up to %fprs, %asr16 and up are implementation specific
+const ASR: [&str; 32] = [
+    "%y", "%asr1", "%asr2", "%asr3", "%asr4", "%asr5", "%asr6", "%asr7",
+    "%asr8", "%asr9", "%asr10", "%asr11", "%asr12", "%asr13", "%asr14", "%asr15",
+    "%asr16", "%asr17", "%asr18", "%asr19", "%asr20", "%asr21", "%asr22", "%asr23",
+    "%asr24", "%asr25", "%asr26", "%asr27", "%asr28", "%asr29", "%asr30", "%asr31",
+];
+
+// The privileged registers of SPARC V9, read with rdpr and written with wrpr
+const PR: [&str; 17] = [
+    "%tpc", "%tnpc", "%tstate", "%tt", "%tick", "%tba", "%pstate", "%tl",
+    "%pil", "%cwp", "%cansave", "%canrestore", "%cleanwin", "%otherwin", "%wstate", "%fq",
+    "%gl",
+];
+
+pub const ZERO: u32 = 0;
+// The link registers of call, from the caller's and the callee's point of view
+pub const O7: u32 = 15;
+pub const I7: u32 = 31;
+
+/// An integer register, %g0 reads as zero and ignores writes
+pub fn gpr(number: u32, v9: bool) -> Register {
+    let number = number & 31;
+    let class = if number == ZERO { RegisterClass::Zero } else { RegisterClass::General };
+    Register::new(class, number as u16, if v9 { 64 } else { 32 }, R[number as usize])
+}
+
+/// A floating point register holding `bits` bits. Double and quad registers above %f31 have the high bit of their
+/// number in the low bit of the field.
+pub fn fpr(field: u32, bits: u16) -> Register {
+    let number = if bits > 32 { (field & 0x1e) | ((field & 1) << 5) } else { field & 31 };
+    Register::new(RegisterClass::Float, number as u16, bits, F[number as usize])
+}
+
+/// One of the floating point condition codes of SPARC V9
+pub fn fcc(number: u32) -> Register {
+    let number = number & 3;
+    Register::new(RegisterClass::Flags, number as u16, 2, FCC[number as usize])
+}
+
+/// The 32 bit and 64 bit integer condition codes of SPARC V9
+pub fn icc(xcc: bool) -> Register {
+    if xcc {
+        Register::new(RegisterClass::Flags, 1, 4, "%xcc")
+    } else {
+        Register::new(RegisterClass::Flags, 0, 4, "%icc")
+    }
+}
+
+const ASR_V9: [&str; 7] = ["%y", "%asr1", "%ccr", "%asi", "%tick", "%pc", "%fprs"];
+
+/// An ancillary state register, %y being the first of them
+pub fn asr(number: u32, v9: bool) -> Register {
+    let number = number & 31;
+    let name = if v9 && number < 7 { ASR_V9[number as usize] } else { ASR[number as usize] };
+    Register::new(RegisterClass::Special, number as u16, 64, name)
+}
+
+/// A privileged register, None for reserved numbers
+pub fn privileged(number: u32) -> Option<Register> {
+    let name = *PR.get(number as usize)?;
+    Some(Register::new(RegisterClass::Special, number as u16, 64, name))
+}
+
+/// A register with no number of its own: %psr, %wim, %tbr, %fsr and the other state of SPARC V8
+pub fn special(name: &'static str) -> Register {
+    Register::new(RegisterClass::Special, 0, 32, name)
+}
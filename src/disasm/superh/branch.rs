@@ -0,0 +1,35 @@
+use crate::disasm::{DelaySlot, FlowKind};
+
+use super::{bits, ins, sign_extend, Context, Decoded};
+
+/// bt, bf and their delayed forms, which branch on the T bit
+pub fn conditional(word: u16, c: &Context) -> Decoded {
+    let mnemonic = match bits(word, 8, 4) {
+        0x9 => "bt",
+        0xb => "bf",
+        0xd => "bt/s",
+        0xf => "bf/s",
+        _ => return None,
+    };
+    ins(mnemonic, vec![c.target(sign_extend(bits(word, 0, 8), 8))])
+}
+
+/// bra and bsr, with a 12 bit displacement
+pub fn unconditional(word: u16, c: &Context) -> Decoded {
+    let mnemonic = if bits(word, 12, 4) == 0xa { "bra" } else { "bsr" };
+    ins(mnemonic, vec![c.target(sign_extend(bits(word, 0, 12), 12))])
+}
+
+/// Control flow of an instruction: its kind, whether it is conditional and its delay slot
+pub fn flow_of(mnemonic: &str) -> (FlowKind, bool, DelaySlot) {
+    match mnemonic {
+        "bt" | "bf" => (FlowKind::Jump, true, DelaySlot::None),
+        // The delayed conditional branches execute the next instruction either way
+        "bt/s" | "bf/s" => (FlowKind::Jump, true, DelaySlot::Always),
+        "bra" | "braf" | "jmp" => (FlowKind::Jump, false, DelaySlot::Always),
+        "bsr" | "bsrf" | "jsr" => (FlowKind::Call, false, DelaySlot::Always),
+        "rts" | "rte" => (FlowKind::Return, false, DelaySlot::Always),
+        "trapa" => (FlowKind::Syscall, false, DelaySlot::None),
+        _ => (FlowKind::Sequential, false, DelaySlot::None),
+    }
+}
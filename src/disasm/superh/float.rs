@@ -0,0 +1,64 @@
+use crate::disasm::IndexMode::{Offset, PostIndex, PreIndex};
+use crate::disasm::Operand;
+
+use super::registers::{dr, fr, fv, system, xmtrx};
+use super::{bits, ins, rm, rn, Context, Decoded};
+
+fn single(number: u32) -> Operand {
+    Operand::Register(fr(number))
+}
+
+fn fpul() -> Operand {
+    Operand::Register(system("fpul"))
+}
+
+/// The floating point instructions, all in the last opcode group
+pub fn fpu(word: u16, c: &Context) -> Decoded {
+    let (n, m) = (rn(word), rm(word));
+    let arithmetic = match bits(word, 0, 4) {
+        0x0 => "fadd",
+        0x1 => "fsub",
+        0x2 => "fmul",
+        0x3 => "fdiv",
+        0x4 => "fcmp/eq",
+        0x5 => "fcmp/gt",
+        0x6 => return ins("fmov.s", vec![c.indexed(m, 4), single(n)]),
+        0x7 => return ins("fmov.s", vec![single(m), c.indexed(n, 4)]),
+        0x8 => return ins("fmov.s", vec![c.at(m, 4, Offset), single(n)]),
+        0x9 => return ins("fmov.s", vec![c.at(m, 4, PostIndex), single(n)]),
+        0xa => return ins("fmov.s", vec![single(m), c.at(n, 4, Offset)]),
+        0xb => return ins("fmov.s", vec![single(m), c.at(n, 4, PreIndex)]),
+        0xc => "fmov",
+        0xd => return single_operand(word),
+        0xe => return ins("fmac", vec![single(0), single(m), single(n)]),
+        _ => return None,
+    };
+    ins(arithmetic, vec![single(m), single(n)])
+}
+
+// The instructions with an extended opcode in bits 4 to 7
+fn single_operand(word: u16) -> Decoded {
+    let n = rn(word);
+    match rm(word) {
+        0x0 => ins("fsts", vec![fpul(), single(n)]),
+        0x1 => ins("flds", vec![single(n), fpul()]),
+        0x2 => ins("float", vec![fpul(), single(n)]),
+        0x3 => ins("ftrc", vec![single(n), fpul()]),
+        0x4 => ins("fneg", vec![single(n)]),
+        0x5 => ins("fabs", vec![single(n)]),
+        0x6 => ins("fsqrt", vec![single(n)]),
+        0x8 => ins("fldi0", vec![single(n)]),
+        0x9 => ins("fldi1", vec![single(n)]),
+        // The conversions always work on a double precision register
+        0xa if n & 1 == 0 => ins("fcnvsd", vec![fpul(), Operand::Register(dr(n))]),
+        0xb if n & 1 == 0 => ins("fcnvds", vec![Operand::Register(dr(n)), fpul()]),
+        0xe => ins("fipr", vec![Operand::Register(fv(n << 2)), Operand::Register(fv(n))]),
+        0xf => match n {
+            0x3 => ins("fschg", vec![]),
+            0xb => ins("frchg", vec![]),
+            _ if n & 3 == 1 => ins("ftrv", vec![Operand::Register(xmtrx()), Operand::Register(fv(n))]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,43 @@
+use crate::disasm::{IndexMode, Instruction, MemoryOperand, Operand, RegisterClass};
+
+// Rendering follows the GNU objdump conventions: operands are separated by a comma without a space, immediates are
+// decimal and prefixed with #, pc relative loads show the address they read
+
+pub fn format(instruction: &Instruction) -> String {
+    let operands: Vec<String> = instruction.operands.iter().map(operand).collect();
+
+    let mut res = instruction.mnemonic.clone();
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands.join(","));
+    }
+    res
+}
+
+fn memory(mem: &MemoryOperand) -> String {
+    if let Some(target) = mem.target {
+        return format!("0x{:x}", target);
+    }
+    let base = mem.base.map(|x| x.name).unwrap_or_default();
+    if let Some(index) = mem.index {
+        return format!("@({},{})", index.name, base);
+    }
+    let control = mem.base.is_some_and(|x| x.class == RegisterClass::Control);
+    match mem.mode {
+        IndexMode::PreIndex => format!("@-{}", base),
+        IndexMode::PostIndex => format!("@{}+", base),
+        // @(disp,GBR) has no form without a displacement
+        IndexMode::Offset if mem.displacement != 0 || control => format!("@({},{})", mem.displacement, base),
+        IndexMode::Offset => format!("@{}", base),
+    }
+}
+
+fn operand(operand: &Operand) -> String {
+    match operand {
+        Operand::Register(x) => x.name.to_string(),
+        Operand::Immediate { value, .. } => format!("#{}", value),
+        Operand::Memory(mem) => memory(mem),
+        Operand::Address(address) => format!("0x{:x}", address),
+        other => format!("{:?}", other),
+    }
+}
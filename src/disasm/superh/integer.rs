@@ -0,0 +1,170 @@
+use crate::disasm::IndexMode::{Offset, PostIndex, PreIndex};
+
+use super::registers::R0;
+use super::{bits, imm, ins, rm, rn, sign_extend, Context, Decoded};
+
+// Size in bytes of the byte, word and longword forms, by the low two bits of the opcode
+fn size(word: u16) -> (u16, &'static str) {
+    match bits(word, 0, 2) {
+        0 => (1, "mov.b"),
+        1 => (2, "mov.w"),
+        _ => (4, "mov.l"),
+    }
+}
+
+/// The data transfers through @(R0,Rn) and the multiplications of the first opcode group
+pub fn group0(word: u16, c: &Context) -> Decoded {
+    let (n, m) = (rn(word), rm(word));
+    match bits(word, 0, 4) {
+        0x4..=0x6 => {
+            let (size, mnemonic) = size(word);
+            ins(mnemonic, vec![c.reg(m), c.indexed(n, size)])
+        }
+        0xc..=0xe => {
+            let (size, mnemonic) = size(word);
+            ins(mnemonic, vec![c.indexed(m, size), c.reg(n)])
+        }
+        0x7 => ins("mul.l", vec![c.reg(m), c.reg(n)]),
+        0xf => ins("mac.l", vec![c.at(m, 4, PostIndex), c.at(n, 4, PostIndex)]),
+        0x9 if m == 2 => ins("movt", vec![c.reg(n)]),
+        _ => None,
+    }
+}
+
+/// Shifts, rotates and the single register tests of the fourth opcode group
+pub fn group4(word: u16, c: &Context) -> Decoded {
+    let (n, m) = (rn(word), rm(word));
+    match bits(word, 0, 4) {
+        0xc => return ins("shad", vec![c.reg(m), c.reg(n)]),
+        0xd => return ins("shld", vec![c.reg(m), c.reg(n)]),
+        0xf => return ins("mac.w", vec![c.at(m, 2, PostIndex), c.at(n, 2, PostIndex)]),
+        _ => {}
+    }
+
+    let mnemonic = match bits(word, 0, 8) {
+        0x00 => "shll",
+        0x01 => "shlr",
+        0x04 => "rotl",
+        0x05 => "rotr",
+        0x08 => "shll2",
+        0x09 => "shlr2",
+        0x10 => "dt",
+        0x11 => "cmp/pz",
+        0x15 => "cmp/pl",
+        0x18 => "shll8",
+        0x19 => "shlr8",
+        0x1b => return ins("tas.b", vec![c.at(n, 1, Offset)]),
+        0x20 => "shal",
+        0x21 => "shar",
+        0x24 => "rotcl",
+        0x25 => "rotcr",
+        0x28 => "shll16",
+        0x29 => "shlr16",
+        _ => return None,
+    };
+    ins(mnemonic, vec![c.reg(n)])
+}
+
+// Two register operations of the second, third and sixth opcode groups, None for the loads and stores
+fn register_operation(word: u16) -> Option<&'static str> {
+    Some(match (bits(word, 12, 4), bits(word, 0, 4)) {
+        (0x2, 0x7) => "div0s",
+        (0x2, 0x8) => "tst",
+        (0x2, 0x9) => "and",
+        (0x2, 0xa) => "xor",
+        (0x2, 0xb) => "or",
+        (0x2, 0xc) => "cmp/str",
+        (0x2, 0xd) => "xtrct",
+        (0x2, 0xe) => "mulu.w",
+        (0x2, 0xf) => "muls.w",
+        (0x3, 0x0) => "cmp/eq",
+        (0x3, 0x2) => "cmp/hs",
+        (0x3, 0x3) => "cmp/ge",
+        (0x3, 0x4) => "div1",
+        (0x3, 0x5) => "dmulu.l",
+        (0x3, 0x6) => "cmp/hi",
+        (0x3, 0x7) => "cmp/gt",
+        (0x3, 0x8) => "sub",
+        (0x3, 0xa) => "subc",
+        (0x3, 0xb) => "subv",
+        (0x3, 0xc) => "add",
+        (0x3, 0xd) => "dmuls.l",
+        (0x3, 0xe) => "addc",
+        (0x3, 0xf) => "addv",
+        (0x6, 0x3) => "mov",
+        (0x6, 0x7) => "not",
+        (0x6, 0x8) => "swap.b",
+        (0x6, 0x9) => "swap.w",
+        (0x6, 0xa) => "negc",
+        (0x6, 0xb) => "neg",
+        (0x6, 0xc) => "extu.b",
+        (0x6, 0xd) => "extu.w",
+        (0x6, 0xe) => "exts.b",
+        (0x6, 0xf) => "exts.w",
+        _ => return None,
+    })
+}
+
+/// Data transfers, arithmetic and logic outside the first and fourth opcode groups
+pub fn operation(word: u16, c: &Context) -> Decoded {
+    let (n, m) = (rn(word), rm(word));
+    let disp4 = bits(word, 0, 4);
+    let disp8 = bits(word, 0, 8);
+    let imm8 = sign_extend(disp8, 8);
+
+    if let Some(mnemonic) = register_operation(word) {
+        return ins(mnemonic, vec![c.reg(m), c.reg(n)]);
+    }
+    match bits(word, 12, 4) {
+        0x1 => ins("mov.l", vec![c.reg(m), c.displacement(n, disp4, 4)]),
+        0x2 => {
+            let (size, mnemonic) = size(word);
+            match bits(word, 0, 4) {
+                0x0..=0x2 => ins(mnemonic, vec![c.reg(m), c.at(n, size, Offset)]),
+                0x4..=0x6 => ins(mnemonic, vec![c.reg(m), c.at(n, size, PreIndex)]),
+                _ => None,
+            }
+        }
+        0x5 => ins("mov.l", vec![c.displacement(m, disp4, 4), c.reg(n)]),
+        0x6 => {
+            let (size, mnemonic) = size(word);
+            match bits(word, 0, 4) {
+                0x0..=0x2 => ins(mnemonic, vec![c.at(m, size, Offset), c.reg(n)]),
+                0x4..=0x6 => ins(mnemonic, vec![c.at(m, size, PostIndex), c.reg(n)]),
+                _ => None,
+            }
+        }
+        0x7 => ins("add", vec![imm(imm8), c.reg(n)]),
+        0x8 => match n {
+            0x0 => ins("mov.b", vec![c.reg(R0), c.displacement(m, disp4, 1)]),
+            0x1 => ins("mov.w", vec![c.reg(R0), c.displacement(m, disp4, 2)]),
+            0x4 => ins("mov.b", vec![c.displacement(m, disp4, 1), c.reg(R0)]),
+            0x5 => ins("mov.w", vec![c.displacement(m, disp4, 2), c.reg(R0)]),
+            0x8 => ins("cmp/eq", vec![imm(imm8), c.reg(R0)]),
+            _ => None,
+        },
+        0x9 => ins("mov.w", vec![c.pc_relative(disp8, 2), c.reg(n)]),
+        0xc => {
+            let sizes = [1, 2, 4];
+            let gbr_mov = ["mov.b", "mov.w", "mov.l"];
+            match n {
+                0x0..=0x2 => ins(gbr_mov[n as usize], vec![c.reg(R0), c.gbr(Some(disp8), sizes[n as usize])]),
+                0x4..=0x6 => ins(gbr_mov[n as usize - 4], vec![c.gbr(Some(disp8), sizes[n as usize - 4]), c.reg(R0)]),
+                0x7 => ins("mova", vec![c.pc_relative(disp8, 0), c.reg(R0)]),
+                // The logical operations take an unsigned immediate
+                0x8 => ins("tst", vec![imm(disp8 as i64), c.reg(R0)]),
+                0x9 => ins("and", vec![imm(disp8 as i64), c.reg(R0)]),
+                0xa => ins("xor", vec![imm(disp8 as i64), c.reg(R0)]),
+                0xb => ins("or", vec![imm(disp8 as i64), c.reg(R0)]),
+                0xc => ins("tst.b", vec![imm(disp8 as i64), c.gbr(None, 1)]),
+                0xd => ins("and.b", vec![imm(disp8 as i64), c.gbr(None, 1)]),
+                0xe => ins("xor.b", vec![imm(disp8 as i64), c.gbr(None, 1)]),
+                0xf => ins("or.b", vec![imm(disp8 as i64), c.gbr(None, 1)]),
+                _ => None,
+            }
+        }
+        0xd => ins("mov.l", vec![c.pc_relative(disp8, 4), c.reg(n)]),
+        0xe => ins("mov", vec![imm(imm8), c.reg(n)]),
+        _ => None,
+    }
+}
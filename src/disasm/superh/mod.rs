@@ -0,0 +1,203 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, Instruction, IndexMode, MemoryOperand, Operand};
+
+mod registers;
+use self::registers::{control, gpr, R0};
+
+mod branch;
+mod integer;
+mod system;
+mod float;
+
+mod format;
+pub use self::format::format;
+
+const LENGTH: usize = 2;
+
+// Mnemonic and operands of a decoded instruction, None if the encoding is reserved or not supported
+type Decoded = Option<(String, Vec<Operand>)>;
+
+/// Decoder for the SH-4 instruction set, including its FPU. Floating point operations are shown on single
+/// precision registers, as whether they work on pairs depends on the FPSCR mode bits at run time.
+#[derive(Debug, Clone)]
+pub struct SuperHDecoder {
+    big_endian: bool,
+}
+
+impl SuperHDecoder {
+    pub fn new(big_endian: bool) -> SuperHDecoder {
+        SuperHDecoder { big_endian }
+    }
+}
+
+// What the decoding functions need besides the instruction word
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    address: u64,
+}
+
+impl Context {
+    fn reg(&self, number: u32) -> Operand {
+        Operand::Register(gpr(number))
+    }
+
+    // @Rn, @Rn+ and @-Rn
+    fn at(&self, number: u32, size: u16, mode: IndexMode) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(gpr(number));
+        mem.mode = mode;
+        Operand::Memory(mem)
+    }
+
+    // @(disp,Rn), the displacement being scaled by the size of the access
+    fn displacement(&self, number: u32, displacement: u32, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(gpr(number));
+        mem.displacement = (displacement * size as u32) as i64;
+        Operand::Memory(mem)
+    }
+
+    // @(R0,Rn)
+    fn indexed(&self, number: u32, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(gpr(number));
+        mem.index = Some(gpr(R0));
+        Operand::Memory(mem)
+    }
+
+    // @(disp,GBR) and @(R0,GBR)
+    fn gbr(&self, displacement: Option<u32>, size: u16) -> Operand {
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(control("gbr"));
+        match displacement {
+            Some(x) => mem.displacement = (x * size as u32) as i64,
+            None => mem.index = Some(gpr(R0)),
+        }
+        Operand::Memory(mem)
+    }
+
+    // @(disp,PC), relative to the instruction after the delay slot, and aligned down for longwords
+    fn pc_relative(&self, displacement: u32, size: u16) -> Operand {
+        let scale = if size == 0 { 4 } else { size as u64 };
+        let base = if scale == 4 { self.address & !3 } else { self.address };
+        let mut mem = MemoryOperand::new(size);
+        mem.base = Some(registers::pc());
+        mem.displacement = (displacement as u64 * scale) as i64;
+        mem.target = Some(base.wrapping_add(4).wrapping_add(displacement as u64 * scale) & 0xffff_ffff);
+        Operand::Memory(mem)
+    }
+
+    // Target of a branch, counted in instructions from the one after the delay slot
+    fn target(&self, offset: i64) -> Operand {
+        Operand::Address(self.address.wrapping_add(4).wrapping_add((offset << 1) as u64) & 0xffff_ffff)
+    }
+}
+
+impl Decoder for SuperHDecoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        if bytes.len() < LENGTH {
+            return Err(DecodeError::EndOfInput);
+        }
+        let raw: [u8; LENGTH] = bytes[..LENGTH].try_into().unwrap();
+        let word = if self.big_endian { u16::from_be_bytes(raw) } else { u16::from_le_bytes(raw) };
+
+        let context = Context { address };
+        let (mnemonic, operands) = decode_word(word, &context).ok_or(DecodeError::InvalidOpcode(word as u32))?;
+
+        let mut instruction = Instruction::new(InstructionSet::SuperH, address, LENGTH, String::new(), operands);
+        let (flow, conditional, delay_slot) = branch::flow_of(&mnemonic);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.delay_slot = delay_slot;
+        instruction.mnemonic = mnemonic;
+        Ok(instruction)
+    }
+
+    fn alignment(&self, _address: u64) -> usize {
+        LENGTH
+    }
+}
+
+fn decode_word(word: u16, c: &Context) -> Decoded {
+    match bits(word, 12, 4) {
+        0x0 => integer::group0(word, c).or_else(|| system::group0(word, c)),
+        0x4 => integer::group4(word, c).or_else(|| system::group4(word, c)),
+        0x8 if matches!(bits(word, 8, 4), 0x9 | 0xb | 0xd | 0xf) => branch::conditional(word, c),
+        0xa | 0xb => branch::unconditional(word, c),
+        0xc if bits(word, 8, 4) == 3 => system::trapa(word),
+        0xf => float::fpu(word, c),
+        _ => integer::operation(word, c),
+    }
+}
+
+fn bits(word: u16, low: u32, count: u32) -> u32 {
+    ((word as u32) >> low) & ((1 << count) - 1)
+}
+
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((value as i64) << shift) >> shift
+}
+
+// The destination register field
+fn rn(word: u16) -> u32 {
+    bits(word, 8, 4)
+}
+
+// The source register field
+fn rm(word: u16) -> u32 {
+    bits(word, 4, 4)
+}
+
+fn ins(mnemonic: &str, operands: Vec<Operand>) -> Decoded {
+    Some((mnemonic.to_string(), operands))
+}
+
+fn imm(value: i64) -> Operand {
+    Operand::Immediate { value, size: 4 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SuperHDecoder;
+    use crate::disasm::{DelaySlot, Decoder, FlowKind, Syntax};
+
+    // Joins the text of each 16 bit instruction of the code at 0x1000
+    fn disassemble(decoder: &SuperHDecoder, code: &[u8]) -> String {
+        let texts: Vec<String> = (0..code.len())
+            .step_by(2)
+            .map(|offset| decoder.decode(&code[offset..], 0x1000 + offset as u64).unwrap().render(Syntax::Intel))
+            .collect();
+        texts.join("; ")
+    }
+
+    #[test]
+    fn function() {
+        let code = [0xe6, 0x2f, 0x22, 0x4f, 0x12, 0xd1, 0x0b, 0x41, 0x01, 0xe0, 0x10, 0xc3, 0x0b, 0x00, 0x09, 0x00];
+        assert_eq!(
+            disassemble(&SuperHDecoder::new(false), &code),
+            "mov.l r14,@-r15; sts.l pr,@-r15; mov.l 0x1050,r1; jsr @r1; mov #1,r0; trapa #16; rts; nop"
+        );
+        let swapped: Vec<u8> = code.chunks(2).flat_map(|x| vec![x[1], x[0]]).collect();
+        assert_eq!(disassemble(&SuperHDecoder::new(true), &swapped), disassemble(&SuperHDecoder::new(false), &code));
+    }
+
+    #[test]
+    fn delay_slots() {
+        let decoder = SuperHDecoder::new(false);
+        let flow = |code: [u8; 2]| {
+            let instruction = decoder.decode(&code, 0x1000).unwrap();
+            (instruction.flow, instruction.conditional, instruction.delay_slot)
+        };
+        assert_eq!(flow([0x04, 0xa0]), (FlowKind::Jump, false, DelaySlot::Always));
+        assert_eq!(flow([0x04, 0xb0]), (FlowKind::Call, false, DelaySlot::Always));
+        assert_eq!(flow([0x0b, 0x00]), (FlowKind::Return, false, DelaySlot::Always));
+        // bt has no delay slot, bt/s always executes it
+        assert_eq!(flow([0x04, 0x89]), (FlowKind::Jump, true, DelaySlot::None));
+        assert_eq!(flow([0x04, 0x8d]), (FlowKind::Jump, true, DelaySlot::Always));
+        assert_eq!(disassemble(&decoder, &[0x04, 0x8d, 0x2b, 0x40]), "bt/s 0x100c; jmp @r0");
+    }
+}
@@ -0,0 +1,78 @@
+use crate::disasm::{Register, RegisterClass};
+
+const R: [&str; 16] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15",
+];
+
+// The other bank of r0 to r7, swapped in by SR.RB in privileged mode
+const BANK: [&str; 8] = ["r0_bank", "r1_bank", "r2_bank", "r3_bank", "r4_bank", "r5_bank", "r6_bank", "r7_bank"];
+
+const FR: [&str; 16] = [
+    "fr0", "fr1", "fr2", "fr3", "fr4", "fr5", "fr6", "fr7", "fr8", "fr9", "fr10", "fr11", "fr12", "fr13", "fr14", "fr15",
+];
+
+// Pairs of single precision registers
+const DR: [&str; 8] = ["dr0", "dr2", "dr4", "dr6", "dr8", "dr10", "dr12", "dr14"];
+
+// Groups of four single precision registers
+const FV: [&str; 4] = ["fv0", "fv4", "fv8", "fv12"];
+
+pub const R0: u32 = 0;
+
+/// A general purpose register
+pub fn gpr(number: u32) -> Register {
+    let number = number & 15;
+    Register::new(RegisterClass::General, number as u16, 32, R[number as usize])
+}
+
+/// A register of the inactive bank, numbered after the general purpose registers
+pub fn banked(number: u32) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::General, 16 + number as u16, 32, BANK[number as usize])
+}
+
+/// A single precision floating point register
+pub fn fr(number: u32) -> Register {
+    let number = number & 15;
+    Register::new(RegisterClass::Float, number as u16, 32, FR[number as usize])
+}
+
+/// A double precision register, made of two single precision ones starting at an even number
+pub fn dr(number: u32) -> Register {
+    let number = number & 14;
+    Register::new(RegisterClass::Float, number as u16, 64, DR[number as usize / 2])
+}
+
+/// A vector of four single precision registers starting at a multiple of four
+pub fn fv(number: u32) -> Register {
+    let number = number & 12;
+    Register::new(RegisterClass::Vector, number as u16, 128, FV[number as usize / 4])
+}
+
+/// The 4x4 matrix held in the single precision registers of the other bank
+pub fn xmtrx() -> Register {
+    Register::new(RegisterClass::Vector, 16, 512, "xmtrx")
+}
+
+// The status register, the base registers and the registers saved on exceptions
+const CONTROL: [&str; 7] = ["sr", "gbr", "vbr", "ssr", "spc", "sgr", "dbr"];
+
+// The multiply accumulator, procedure register and floating point communication registers
+const SYSTEM: [&str; 5] = ["mach", "macl", "pr", "fpul", "fpscr"];
+
+/// A control register by name
+pub fn control(name: &'static str) -> Register {
+    let number = CONTROL.iter().position(|x| *x == name).unwrap_or(0);
+    Register::new(RegisterClass::Control, number as u16, 32, name)
+}
+
+/// A system register by name
+pub fn system(name: &'static str) -> Register {
+    let number = SYSTEM.iter().position(|x| *x == name).unwrap_or(0);
+    Register::new(RegisterClass::Special, number as u16, 32, name)
+}
+
+/// The program counter, base of pc relative loads
+pub fn pc() -> Register {
+    Register::new(RegisterClass::ProgramCounter, 0, 32, "pc")
+}
@@ -0,0 +1,108 @@
+use crate::disasm::IndexMode::{Offset, PostIndex, PreIndex};
+use crate::disasm::{Operand, Register};
+
+use super::registers::{banked, control, system, R0};
+use super::{bits, imm, ins, rm, rn, Context, Decoded};
+
+// The register stc and ldc transfer by bits 4 to 7. sgr and dbr have encodings of their own.
+fn control_register(field: u32) -> Option<Register> {
+    Some(match field {
+        0x0 => control("sr"),
+        0x1 => control("gbr"),
+        0x2 => control("vbr"),
+        0x3 => control("ssr"),
+        0x4 => control("spc"),
+        0x8..=0xf => banked(field),
+        _ => return None,
+    })
+}
+
+// The register sts and lds transfer by bits 4 to 7
+fn system_register(field: u32) -> Option<Register> {
+    Some(match field {
+        0x0 => system("mach"),
+        0x1 => system("macl"),
+        0x2 => system("pr"),
+        0x5 => system("fpul"),
+        0x6 => system("fpscr"),
+        _ => return None,
+    })
+}
+
+/// Control instructions, stores of control and system registers and cache operations of the first opcode group
+pub fn group0(word: u16, c: &Context) -> Decoded {
+    let (n, field) = (rn(word), rm(word));
+    if n == 0 {
+        let mnemonic = match bits(word, 0, 8) {
+            0x08 => Some("clrt"),
+            0x09 => Some("nop"),
+            0x0b => Some("rts"),
+            0x18 => Some("sett"),
+            0x19 => Some("div0u"),
+            0x1b => Some("sleep"),
+            0x28 => Some("clrmac"),
+            0x2b => Some("rte"),
+            0x38 => Some("ldtlb"),
+            0x48 => Some("clrs"),
+            0x58 => Some("sets"),
+            _ => None,
+        };
+        if let Some(mnemonic) = mnemonic {
+            return ins(mnemonic, vec![]);
+        }
+    }
+
+    match bits(word, 0, 4) {
+        // stc sr,Rn to stc spc,Rn and the banked registers
+        0x2 => ins("stc", vec![Operand::Register(control_register(field)?), c.reg(n)]),
+        0xa => match field {
+            0x3 => ins("stc", vec![Operand::Register(control("sgr")), c.reg(n)]),
+            0xf => ins("stc", vec![Operand::Register(control("dbr")), c.reg(n)]),
+            _ => ins("sts", vec![Operand::Register(system_register(field)?), c.reg(n)]),
+        },
+        0x3 => match field {
+            0x0 => ins("bsrf", vec![c.reg(n)]),
+            0x2 => ins("braf", vec![c.reg(n)]),
+            0x8 => ins("pref", vec![c.at(n, 0, Offset)]),
+            0x9 => ins("ocbi", vec![c.at(n, 0, Offset)]),
+            0xa => ins("ocbp", vec![c.at(n, 0, Offset)]),
+            0xb => ins("ocbwb", vec![c.at(n, 0, Offset)]),
+            0xc => ins("movca.l", vec![c.reg(R0), c.at(n, 4, Offset)]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Loads and stores of control and system registers, and the register indirect jumps of the fourth opcode group
+pub fn group4(word: u16, c: &Context) -> Decoded {
+    let (n, field) = (rn(word), rm(word));
+    match bits(word, 0, 4) {
+        // Stores to @-Rn
+        0x2 => match field {
+            0x3 => ins("stc.l", vec![Operand::Register(control("sgr")), c.at(n, 4, PreIndex)]),
+            0xf => ins("stc.l", vec![Operand::Register(control("dbr")), c.at(n, 4, PreIndex)]),
+            _ => ins("sts.l", vec![Operand::Register(system_register(field)?), c.at(n, 4, PreIndex)]),
+        },
+        0x3 => ins("stc.l", vec![Operand::Register(control_register(field)?), c.at(n, 4, PreIndex)]),
+        // Loads from @Rm+
+        0x6 if field == 0xf => ins("ldc.l", vec![c.at(n, 4, PostIndex), Operand::Register(control("dbr"))]),
+        0x6 => ins("lds.l", vec![c.at(n, 4, PostIndex), Operand::Register(system_register(field)?)]),
+        0x7 => ins("ldc.l", vec![c.at(n, 4, PostIndex), Operand::Register(control_register(field)?)]),
+        0xa if field == 0xf => ins("ldc", vec![c.reg(n), Operand::Register(control("dbr"))]),
+        0xa => ins("lds", vec![c.reg(n), Operand::Register(system_register(field)?)]),
+        0xe => ins("ldc", vec![c.reg(n), Operand::Register(control_register(field)?)]),
+        // The target of jmp and jsr is written @Rm, like an address without a size
+        0xb => match field {
+            0x0 => ins("jsr", vec![c.at(n, 0, Offset)]),
+            0x2 => ins("jmp", vec![c.at(n, 0, Offset)]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// trapa #imm, the immediate being the trap number
+pub fn trapa(word: u16) -> Decoded {
+    ins("trapa", vec![imm(bits(word, 0, 8) as i64)])
+}
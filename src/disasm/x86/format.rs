@@ -0,0 +1,300 @@
+use crate::disasm::{Instruction, MemoryOperand, Operand, RegisterClass, Syntax, FlowKind};
+
+// Rendering follows the GNU objdump conventions for both syntaxes
+
+pub fn format(instruction: &Instruction, syntax: Syntax) -> String {
+    let (mnemonic, operands) = match syntax {
+        Syntax::Intel => (instruction.mnemonic.clone(), intel_operands(instruction)),
+        Syntax::Att => (att_mnemonic(instruction), att_operands(instruction)),
+    };
+
+    let mut res = mnemonic;
+    if !operands.is_empty() {
+        res.push(' ');
+        res.push_str(&operands);
+    }
+
+    let pc_relative = instruction.operands.iter().find_map(|x| match x {
+        Operand::Memory(mem) if mem.base.map(|x| x.class) == Some(RegisterClass::ProgramCounter) => mem.target,
+        _ => None,
+    });
+    if let Some(target) = pc_relative {
+        res.push_str(&format!("        # 0x{:x}", target));
+    }
+
+    res
+}
+
+fn hex_signed(value: i64) -> String {
+    if value < 0 {
+        format!("-0x{:x}", (value as i128).abs())
+    } else {
+        format!("0x{:x}", value)
+    }
+}
+
+fn masked(value: i64, size: u8) -> u64 {
+    if size >= 8 {
+        value as u64
+    } else {
+        value as u64 & ((1u64 << (size as u32 * 8)) - 1)
+    }
+}
+
+fn immediate(value: i64, size: u8) -> String {
+    if size == 6 {
+        // Far pointer, selector:offset
+        return format!("0x{:x}:0x{:x}", value >> 32, value & 0xFFFF_FFFF);
+    }
+    format!("0x{:x}", masked(value, size))
+}
+
+fn size_name(size: u16) -> Option<&'static str> {
+    match size {
+        1 => Some("BYTE"),
+        2 => Some("WORD"),
+        4 => Some("DWORD"),
+        6 => Some("FWORD"),
+        8 => Some("QWORD"),
+        10 => Some("TBYTE"),
+        16 => Some("XMMWORD"),
+        32 => Some("YMMWORD"),
+        64 => Some("ZMMWORD"),
+        _ => None,
+    }
+}
+
+fn intel_memory(mem: &MemoryOperand) -> String {
+    let mut res = String::new();
+    if let Some(size) = size_name(mem.size) {
+        res.push_str(size);
+        res.push_str(" PTR ");
+    }
+
+    match mem.segment {
+        Some(segment) => {
+            res.push_str(segment.name);
+            res.push(':');
+        }
+        None if mem.base.is_none() && mem.index.is_none() => res.push_str("ds:"),
+        None => {}
+    }
+
+    if mem.base.is_none() && mem.index.is_none() {
+        res.push_str(&format!("0x{:x}", mem.target.unwrap_or(mem.displacement as u64)));
+        return res;
+    }
+
+    res.push('[');
+    if let Some(base) = mem.base {
+        res.push_str(base.name);
+    }
+    if let Some(index) = mem.index {
+        if mem.base.is_some() {
+            res.push('+');
+        }
+        res.push_str(&format!("{}*{}", index.name, mem.scale));
+    }
+    if mem.displacement != 0 {
+        if mem.displacement > 0 {
+            res.push('+');
+        }
+        res.push_str(&hex_signed(mem.displacement));
+    }
+    res.push(']');
+    res
+}
+
+fn intel_operands(instruction: &Instruction) -> String {
+    let mut res = String::new();
+    for (i, operand) in instruction.operands.iter().enumerate() {
+        if i != 0 && !matches!(operand, Operand::Mask { .. }) {
+            res.push(',');
+        }
+        match operand {
+            Operand::Register(reg) => res.push_str(reg.name),
+            Operand::Immediate { value, size } => res.push_str(&immediate(*value, *size)),
+            Operand::Memory(mem) => res.push_str(&intel_memory(mem)),
+            Operand::Address(addr) => res.push_str(&format!("0x{:x}", addr)),
+            Operand::Mask { register, zeroing } => res.push_str(&mask(register.name, *zeroing)),
+            other => res.push_str(&format!("{:?}", other)),
+        }
+    }
+    res
+}
+
+fn mask(name: &str, zeroing: bool) -> String {
+    let mut res = String::new();
+    if !name.ends_with("k0") {
+        res.push_str(&format!("{{{}}}", name));
+    }
+    if zeroing {
+        res.push_str("{z}");
+    }
+    res
+}
+
+fn att_memory(mem: &MemoryOperand) -> String {
+    let mut res = String::new();
+    if let Some(segment) = mem.segment {
+        res.push('%');
+        res.push_str(segment.name);
+        res.push(':');
+    }
+
+    if mem.base.is_none() && mem.index.is_none() {
+        res.push_str(&format!("0x{:x}", mem.target.unwrap_or(mem.displacement as u64)));
+        return res;
+    }
+
+    if mem.displacement != 0 {
+        res.push_str(&hex_signed(mem.displacement));
+    }
+    res.push('(');
+    if let Some(base) = mem.base {
+        res.push('%');
+        res.push_str(base.name);
+    }
+    if let Some(index) = mem.index {
+        res.push_str(&format!(",%{},{}", index.name, mem.scale));
+    }
+    res.push(')');
+    res
+}
+
+fn att_register(name: &str) -> String {
+    match name.strip_prefix("st(") {
+        Some(rest) => format!("%st({}", rest),
+        None => format!("%{}", name),
+    }
+}
+
+fn att_operands(instruction: &Instruction) -> String {
+    let indirect = matches!(instruction.flow, FlowKind::Jump | FlowKind::Call)
+        && !instruction.operands.iter().any(|x| matches!(x, Operand::Address(_)));
+
+    let mut parts: Vec<String> = Vec::new();
+    for operand in instruction.operands.iter() {
+        let text = match operand {
+            Operand::Register(reg) => att_register(reg.name),
+            Operand::Immediate { value, size } => format!("${}", immediate(*value, *size)),
+            Operand::Memory(mem) => att_memory(mem),
+            Operand::Address(addr) => format!("0x{:x}", addr),
+            Operand::Mask { register, zeroing } => {
+                if let Some(last) = parts.last_mut() {
+                    last.push_str(&mask(&att_register(register.name), *zeroing));
+                }
+                continue;
+            }
+            other => format!("{:?}", other),
+        };
+        parts.push(if indirect { format!("*{}", text) } else { text });
+    }
+
+    parts.reverse();
+    parts.join(",")
+}
+
+fn suffix(size: u16) -> &'static str {
+    match size {
+        1 => "b",
+        2 => "w",
+        4 => "l",
+        8 => "q",
+        _ => "",
+    }
+}
+
+fn att_mnemonic(instruction: &Instruction) -> String {
+    let (prefix, mnemonic) = match instruction.mnemonic.rfind(' ') {
+        Some(idx) => instruction.mnemonic.split_at(idx + 1),
+        None => ("", &instruction.mnemonic[..]),
+    };
+
+    let register_size = |idx: usize| -> u16 {
+        match instruction.operands.get(idx) {
+            Some(Operand::Register(reg)) => reg.bits / 8,
+            Some(Operand::Memory(mem)) => mem.size,
+            _ => 0,
+        }
+    };
+
+    let renamed = match mnemonic {
+        "cbw" => Some("cbtw".to_string()),
+        "cwde" => Some("cwtl".to_string()),
+        "cdqe" => Some("cltq".to_string()),
+        "cwd" => Some("cwtd".to_string()),
+        "cdq" => Some("cltd".to_string()),
+        "cqo" => Some("cqto".to_string()),
+        "movsxd" => Some("movslq".to_string()),
+        "movzx" => Some(format!("movz{}{}", suffix(register_size(1)), suffix(register_size(0)))),
+        "movsx" => Some(format!("movs{}{}", suffix(register_size(1)), suffix(register_size(0)))),
+        _ => None,
+    };
+    if let Some(renamed) = renamed {
+        return format!("{}{}", prefix, renamed);
+    }
+
+    let memory_size = instruction.operands.iter().find_map(|x| match x {
+        Operand::Memory(mem) => Some(mem.size),
+        _ => None,
+    });
+    let has_register = instruction.operands.iter().any(|x| match x {
+        Operand::Register(reg) => reg.class != RegisterClass::Segment || instruction.operands.len() == 1,
+        _ => false,
+    });
+
+    // x87 memory operands encode the operand type in the suffix
+    if mnemonic.starts_with('f') && !has_register {
+        if let Some(size) = memory_size {
+            let integer = mnemonic.starts_with("fi");
+            let x87_suffix = match (integer, size) {
+                (false, 4) => "s",
+                (false, 8) => "l",
+                (false, 10) => "t",
+                (true, 2) => "s",
+                (true, 4) => "l",
+                (true, 8) => "ll",
+                _ => "",
+            };
+            return format!("{}{}{}", prefix, mnemonic, x87_suffix);
+        }
+    }
+
+    // The reversed operand order of AT&T swaps the meaning of fsub/fsubr and fdiv/fdivr when st(i) is the destination
+    if let [Operand::Register(_), Operand::Register(src)] = &instruction.operands[..] {
+        if src.class == RegisterClass::Float && src.number == 0 {
+            let swapped = match mnemonic {
+                "fsub" => Some("fsubr"),
+                "fsubr" => Some("fsub"),
+                "fsubp" => Some("fsubrp"),
+                "fsubrp" => Some("fsubp"),
+                "fdiv" => Some("fdivr"),
+                "fdivr" => Some("fdiv"),
+                "fdivp" => Some("fdivrp"),
+                "fdivrp" => Some("fdivp"),
+                _ => None,
+            };
+            if let Some(swapped) = swapped {
+                return format!("{}{}", prefix, swapped);
+            }
+        }
+    }
+
+    // Without a register operand the size is ambiguous for instructions that take any operand size
+    let has_immediate = instruction.operands.iter().any(|x| matches!(x, Operand::Immediate { .. }));
+    let sized = has_immediate || matches!(
+        mnemonic,
+        "inc" | "dec" | "neg" | "not" | "mul" | "imul" | "div" | "idiv" | "nop"
+            | "movs" | "cmps" | "stos" | "lods" | "scas" | "ins" | "outs"
+            | "rol" | "ror" | "rcl" | "rcr" | "shl" | "shr" | "sal" | "sar"
+    );
+    let branch = matches!(instruction.flow, FlowKind::Jump | FlowKind::Call | FlowKind::Return);
+    if !has_register && !branch && sized {
+        if let Some(size) = memory_size {
+            return format!("{}{}{}", prefix, mnemonic, suffix(size));
+        }
+    }
+
+    instruction.mnemonic.clone()
+}
@@ -0,0 +1,893 @@
+use std::convert::TryInto;
+
+use crate::instruction_set::InstructionSet;
+
+use super::{Decoder, DecodeError, FlowKind, Instruction, MemoryOperand, Operand, Register};
+
+mod registers;
+pub use self::registers::{gpr, RIP, EIP};
+
+mod opcodes;
+use self::opcodes::{Ctx, Entry, Op, Sz, D64, F64, I64, NO_V, NO_VEX};
+
+mod format;
+pub use self::format::format;
+
+const MAX_LENGTH: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Bits32,
+    Bits64,
+}
+
+#[derive(Debug, Clone)]
+pub struct X86Decoder {
+    mode: Mode,
+}
+
+impl X86Decoder {
+    pub fn new(mode: Mode) -> X86Decoder {
+        X86Decoder { mode }
+    }
+}
+
+impl Decoder for X86Decoder {
+    fn decode(&self, bytes: &[u8], address: u64) -> Result<Instruction, DecodeError> {
+        let bytes = &bytes[..bytes.len().min(MAX_LENGTH)];
+        let mut state = State::new(bytes, self.mode == Mode::Bits64);
+        let (base_mnemonic, mnemonic, mut operands) = state.decode()?;
+
+        let length = state.pos;
+        let next = address.wrapping_add(length as u64);
+        let address_mask = if state.long_mode { u64::MAX } else { 0xFFFF_FFFF };
+
+        // Relative targets can only be resolved once the length is known
+        for operand in operands.iter_mut() {
+            match operand {
+                Operand::Address(rel) => *operand = Operand::Address(next.wrapping_add(*rel) & address_mask),
+                Operand::Memory(mem) if mem.base == Some(RIP) || mem.base == Some(EIP) => {
+                    mem.target = Some(next.wrapping_add(mem.displacement as u64) & address_mask);
+                }
+                _ => {}
+            }
+        }
+
+        let instruction_set = match self.mode {
+            Mode::Bits32 => InstructionSet::X86,
+            Mode::Bits64 => InstructionSet::X86_64,
+        };
+        let mut instruction = Instruction::new(instruction_set, address, length, String::new(), operands);
+        let (flow, conditional) = flow_of(base_mnemonic);
+        instruction.flow = flow;
+        instruction.conditional = conditional;
+        instruction.mnemonic = state.prefix_string(base_mnemonic) + &mnemonic;
+
+        Ok(instruction)
+    }
+}
+
+fn flow_of(mnemonic: &str) -> (FlowKind, bool) {
+    match mnemonic {
+        "jmp" | "ljmp" => (FlowKind::Jump, false),
+        "call" | "lcall" => (FlowKind::Call, false),
+        "ret" | "retf" | "iret" | "iretw" | "iretq" | "sysret" | "sysexit" => (FlowKind::Return, false),
+        "loop" | "loope" | "loopne" | "xbegin" => (FlowKind::Jump, true),
+        "syscall" | "sysenter" | "int" | "int1" | "into" => (FlowKind::Syscall, false),
+        "hlt" | "ud0" | "ud1" | "ud2" | "int3" => (FlowKind::Trap, false),
+        _ if mnemonic.starts_with('j') => (FlowKind::Jump, true),
+        _ => (FlowKind::Sequential, false),
+    }
+}
+
+const SSE_PREDICATES: [&str; 32] = [
+    "eq", "lt", "le", "unord", "neq", "nlt", "nle", "ord",
+    "eq_uq", "nge", "ngt", "false", "neq_oq", "ge", "gt", "true",
+    "eq_os", "lt_oq", "le_oq", "unord_s", "neq_us", "nlt_uq", "nle_uq", "ord_s",
+    "eq_us", "nge_uq", "ngt_uq", "false_os", "neq_os", "ge_oq", "gt_oq", "true_us",
+];
+
+const INTEGER_PREDICATES: [Option<&str>; 8] = [Some("eq"), Some("lt"), Some("le"), None, Some("neq"), Some("nlt"), Some("nle"), None];
+
+/// Folds the predicate immediate of compare instructions into the mnemonic, e.g. cmpps with 1 becomes cmpltps
+fn comparison_predicate(mnemonic: &mut String, operands: &mut Vec<Operand>) {
+    let predicate = match operands.last() {
+        Some(Operand::Immediate { value, .. }) => *value as usize,
+        _ => return,
+    };
+
+    let (prefix, suffix) = match mnemonic.as_str() {
+        "cmpps" | "cmppd" | "cmpss" | "cmpsd" if predicate < 8 => ("cmp", &mnemonic[3..]),
+        "vcmpps" | "vcmppd" | "vcmpss" | "vcmpsd" if predicate < 32 => ("vcmp", &mnemonic[4..]),
+        _ if mnemonic.starts_with("vpcmp") && predicate < 8 => ("vpcmp", &mnemonic[5..]),
+        _ => return,
+    };
+
+    let name = if prefix == "vpcmp" {
+        match (suffix, INTEGER_PREDICATES[predicate]) {
+            ("b" | "w" | "d" | "q" | "ub" | "uw" | "ud" | "uq", Some(name)) => name,
+            _ => return,
+        }
+    } else {
+        SSE_PREDICATES[predicate]
+    };
+
+    *mnemonic = format!("{}{}{}", prefix, name, suffix);
+    operands.pop();
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Vex {
+    map: u8,
+    w: bool,
+    // VEX.vvvv (stored inverted in the encoding), including EVEX.V' as bit 4
+    vvvv: u8,
+    l: u8,
+    pp: u8,
+    evex: bool,
+    mask: u8,
+    zeroing: bool,
+    broadcast: bool,
+}
+
+struct State<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    long_mode: bool,
+
+    operand_size_prefix: bool,
+    address_size_prefix: bool,
+    lock: bool,
+    // Last F2 or F3 prefix
+    rep: Option<u8>,
+    segment: Option<u8>,
+    rex: Option<u8>,
+    vex: Option<Vex>,
+
+    opcode: u8,
+    map: u8,
+    modrm: Option<u8>,
+    entry_flags: u16,
+    operand_size: u16,
+    address_size: u16,
+}
+
+impl<'a> State<'a> {
+    fn new(bytes: &'a [u8], long_mode: bool) -> State<'a> {
+        State {
+            bytes,
+            pos: 0,
+            long_mode,
+            operand_size_prefix: false,
+            address_size_prefix: false,
+            lock: false,
+            rep: None,
+            segment: None,
+            rex: None,
+            vex: None,
+            opcode: 0,
+            map: 0,
+            modrm: None,
+            entry_flags: 0,
+            operand_size: 32,
+            address_size: 32,
+        }
+    }
+
+    fn peek(&self) -> Result<u8, DecodeError> {
+        self.bytes.get(self.pos).copied().ok_or_else(|| self.end_error())
+    }
+
+    fn end_error(&self) -> DecodeError {
+        if self.bytes.len() >= MAX_LENGTH {
+            DecodeError::TooLong
+        } else {
+            DecodeError::EndOfInput
+        }
+    }
+
+    fn read_n(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        if self.pos + n <= self.bytes.len() {
+            let start = self.pos;
+            self.pos += n;
+            Ok(&self.bytes[start..self.pos])
+        } else {
+            Err(self.end_error())
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_n(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_le_bytes(self.read_n(2)?.try_into().unwrap()))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_le_bytes(self.read_n(4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.read_n(8)?.try_into().unwrap()))
+    }
+
+    fn invalid(&self) -> DecodeError {
+        let opcode = match self.map {
+            0 => self.opcode as u32,
+            1 => 0x0F00 | self.opcode as u32,
+            2 => 0x0F3800 | self.opcode as u32,
+            _ => 0x0F3A00 | self.opcode as u32,
+        };
+        DecodeError::InvalidOpcode(opcode)
+    }
+
+    fn rex_bit(&self, bit: u8) -> u8 {
+        match self.rex {
+            Some(rex) => (rex >> bit) & 1,
+            None => 0,
+        }
+    }
+
+    // REX.W or VEX.W. Outside of long mode VEX.W still selects between instructions,
+    // but never makes general purpose operands 64 bit.
+    fn rex_w(&self) -> bool {
+        match self.vex {
+            Some(vex) => vex.w,
+            None => self.rex_bit(3) == 1,
+        }
+    }
+
+    // Returns the mnemonic of the table entry, the full mnemonic and the operands
+    fn decode(&mut self) -> Result<(&'static str, String, Vec<Operand>), DecodeError> {
+        self.read_prefixes()?;
+
+        let mut opcode = self.read_u8()?;
+        if self.is_vex_escape(opcode)? {
+            self.read_vex(opcode)?;
+            opcode = self.read_u8()?;
+        } else if opcode == 0x0F {
+            opcode = self.read_u8()?;
+            self.map = match opcode {
+                0x38 => 2,
+                0x3A => 3,
+                _ => 1,
+            };
+            if self.map != 1 {
+                opcode = self.read_u8()?;
+            }
+        }
+        self.opcode = opcode;
+
+        let has_modrm = match self.map {
+            0 => opcodes::has_modrm_one_byte(opcode),
+            1 => self.vex.is_some() && opcode != 0x77 || opcodes::has_modrm_two_byte(opcode),
+            _ => true,
+        };
+        if has_modrm {
+            self.modrm = Some(self.read_u8()?);
+        }
+
+        let entry = self.lookup()?;
+        self.entry_flags = entry.flags;
+        self.compute_sizes();
+
+        let operand_specs: Vec<Op> = entry
+            .operands
+            .iter()
+            .copied()
+            .filter(|x| self.vex.is_some() || !matches!(x, Op::H(_) | Op::L(_)))
+            .collect();
+
+        let memory_size = operand_specs
+            .iter()
+            .find_map(|x| match x {
+                Op::E(sz) | Op::M(sz) | Op::W(sz) | Op::Qm(sz) => Some(self.memory_bytes(*sz)),
+                _ => None,
+            })
+            .unwrap_or(0);
+        // EVEX broadcasts a single element from memory
+        let memory_size = match self.vex {
+            Some(vex) if vex.evex && vex.broadcast && memory_size > 8 => if vex.w { 8 } else { 4 },
+            _ => memory_size,
+        };
+        let memory = match self.modrm {
+            Some(modrm) if modrm >> 6 != 3 => Some(self.read_memory(modrm, memory_size)?),
+            _ => None,
+        };
+
+        let mut operands = Vec::with_capacity(operand_specs.len());
+        for (i, spec) in operand_specs.iter().enumerate() {
+            operands.push(self.operand(*spec, &memory)?);
+            if i == 0 {
+                if let Some(vex) = self.vex {
+                    if vex.evex && (vex.mask != 0 || vex.zeroing) {
+                        operands.push(Operand::Mask {
+                            register: registers::mask(vex.mask),
+                            zeroing: vex.zeroing,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut mnemonic = if self.vex.is_some() && entry.flags & NO_V == 0 {
+            format!("v{}", entry.mnemonic)
+        } else {
+            entry.mnemonic.to_string()
+        };
+        comparison_predicate(&mut mnemonic, &mut operands);
+
+        Ok((entry.mnemonic, mnemonic, operands))
+    }
+
+    fn read_prefixes(&mut self) -> Result<(), DecodeError> {
+        loop {
+            let byte = self.peek()?;
+            match byte {
+                0xF0 => self.lock = true,
+                0xF2 | 0xF3 => self.rep = Some(byte),
+                0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 => self.segment = Some(byte),
+                0x66 => self.operand_size_prefix = true,
+                0x67 => self.address_size_prefix = true,
+                0x40..=0x4F if self.long_mode => {
+                    self.rex = Some(byte);
+                    self.pos += 1;
+                    // REX has to come directly before the opcode, otherwise it is ignored
+                    match self.peek()? {
+                        0x40..=0x4F => continue,
+                        0xF0 | 0xF2 | 0xF3 | 0x26 | 0x2E | 0x36 | 0x3E | 0x64 | 0x65 | 0x66 | 0x67 => {
+                            self.rex = None;
+                            continue;
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                _ => return Ok(()),
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn is_vex_escape(&self, opcode: u8) -> Result<bool, DecodeError> {
+        if opcode != 0xC4 && opcode != 0xC5 && opcode != 0x62 {
+            return Ok(false);
+        }
+        // Outside of long mode these are les, lds and bound unless ModRM.mod is 3
+        if !self.long_mode && self.peek()? >> 6 != 3 {
+            return Ok(false);
+        }
+        if self.rex.is_some() || self.operand_size_prefix || self.rep.is_some() || self.lock {
+            return Err(DecodeError::InvalidOpcode(opcode as u32));
+        }
+        Ok(true)
+    }
+
+    fn read_vex(&mut self, escape: u8) -> Result<(), DecodeError> {
+        let mut vex = Vex::default();
+        let mut rex = 0x40;
+        match escape {
+            0xC5 => {
+                let b1 = self.read_u8()?;
+                rex |= ((!b1 >> 7) & 1) << 2;
+                vex.map = 1;
+                vex.vvvv = (!b1 >> 3) & 0xF;
+                vex.l = (b1 >> 2) & 1;
+                vex.pp = b1 & 3;
+            }
+            0xC4 => {
+                let b1 = self.read_u8()?;
+                let b2 = self.read_u8()?;
+                rex |= ((!b1 >> 5) & 7) | (((b2 >> 7) & 1) << 3);
+                vex.map = b1 & 0x1F;
+                vex.w = b2 >> 7 == 1;
+                vex.vvvv = (!b2 >> 3) & 0xF;
+                vex.l = (b2 >> 2) & 1;
+                vex.pp = b2 & 3;
+            }
+            _ => {
+                let p0 = self.read_u8()?;
+                let p1 = self.read_u8()?;
+                let p2 = self.read_u8()?;
+                if p1 & 4 == 0 {
+                    return Err(DecodeError::InvalidOpcode(0x62));
+                }
+                // R, X and B go into a fake REX, R' and V' are kept as bit 4 of reg and vvvv
+                rex |= (!p0 >> 5) & 7;
+                vex.map = p0 & 7;
+                vex.w = p1 >> 7 == 1;
+                vex.vvvv = ((!p1 >> 3) & 0xF) | (((!p2 >> 3) & 1) << 4);
+                vex.pp = p1 & 3;
+                vex.zeroing = p2 >> 7 == 1;
+                vex.l = (p2 >> 5) & 3;
+                vex.broadcast = (p2 >> 4) & 1 == 1;
+                vex.mask = p2 & 7;
+                vex.evex = true;
+                // EVEX.R' is stored inverted in bit 4 of P0
+                if (p0 >> 4) & 1 == 0 {
+                    rex |= 0x10;
+                }
+            }
+        }
+
+        if !self.long_mode {
+            // VEX.vvvv bit 3 and the inverted register extension bits are ignored outside long mode
+            vex.vvvv &= 7;
+            rex = 0x40;
+        }
+
+        if vex.map == 0 || vex.map > 3 {
+            return Err(DecodeError::InvalidOpcode(escape as u32));
+        }
+        self.map = vex.map;
+        self.rex = Some(rex);
+        self.vex = Some(vex);
+        Ok(())
+    }
+
+    fn mandatory_prefix(&self) -> u8 {
+        if let Some(vex) = self.vex {
+            return [0, 0x66, 0xF3, 0xF2][vex.pp as usize];
+        }
+        match self.rep {
+            Some(rep) => rep,
+            None if self.operand_size_prefix && self.map != 0 => 0x66,
+            None => 0,
+        }
+    }
+
+    fn lookup(&self) -> Result<Entry, DecodeError> {
+        let modrm = self.modrm.unwrap_or(0);
+        let ctx = Ctx {
+            long_mode: self.long_mode,
+            modrm_mod: modrm >> 6,
+            modrm_reg: (modrm >> 3) & 7,
+            modrm_rm: modrm & 7,
+            prefix: self.mandatory_prefix(),
+            rex_w: self.rex_w(),
+            rex_b: self.rex_bit(0) == 1,
+            address_size_prefix: self.address_size_prefix,
+            vex: self.vex.is_some(),
+            evex: self.vex.map(|x| x.evex).unwrap_or(false),
+            vex_l: self.vex.map(|x| x.l).unwrap_or(0),
+            operand_size: self.default_operand_size(),
+        };
+
+        let mut entry = None;
+        if ctx.evex {
+            entry = opcodes::evex_override(self.map, self.opcode, &ctx);
+        }
+        if entry.is_none() {
+            entry = match self.map {
+                0 => opcodes::one_byte(self.opcode, &ctx),
+                1 => opcodes::two_byte(self.opcode, &ctx),
+                2 => opcodes::three_byte_38(self.opcode, &ctx),
+                _ => opcodes::three_byte_3a(self.opcode, &ctx),
+            };
+        }
+
+        let entry = entry.ok_or_else(|| self.invalid())?;
+        if self.long_mode && entry.flags & I64 != 0 {
+            return Err(self.invalid());
+        }
+        if self.vex.is_some() && entry.flags & NO_VEX != 0 {
+            return Err(self.invalid());
+        }
+        Ok(entry)
+    }
+
+    // Operand size as far as it can be determined before the opcode table entry is known
+    fn default_operand_size(&self) -> u16 {
+        if self.rex_w() && self.long_mode {
+            64
+        } else if self.operand_size_prefix {
+            16
+        } else {
+            32
+        }
+    }
+
+    fn compute_sizes(&mut self) {
+        self.operand_size = self.default_operand_size();
+        if self.long_mode {
+            let default_64 = self.entry_flags & D64 != 0 && !self.operand_size_prefix;
+            if self.entry_flags & F64 != 0 || default_64 {
+                self.operand_size = 64;
+            }
+        }
+
+        self.address_size = match (self.long_mode, self.address_size_prefix) {
+            (true, false) => 64,
+            (true, true) => 32,
+            (false, false) => 32,
+            (false, true) => 16,
+        };
+    }
+
+    fn gpr_bits(&self, sz: Sz) -> u16 {
+        match sz {
+            Sz::Byte => 8,
+            Sz::Word => 16,
+            Sz::Dword | Sz::DwordByte | Sz::DwordWord => 32,
+            Sz::Qword => 64,
+            Sz::Oz => self.operand_size.min(32),
+            Sz::Oy => {
+                if self.rex_w() && self.long_mode {
+                    64
+                } else {
+                    32
+                }
+            }
+            _ => self.operand_size,
+        }
+    }
+
+    fn vector_length(&self) -> u16 {
+        128 << self.vex.map(|x| x.l).unwrap_or(0)
+    }
+
+    fn vector_bits(&self, sz: Sz) -> u16 {
+        match sz {
+            Sz::Vx => self.vector_length(),
+            Sz::Vh => (self.vector_length() / 2).max(128),
+            Sz::Ymm => 256,
+            _ => 128,
+        }
+    }
+
+    fn memory_bytes(&self, sz: Sz) -> u16 {
+        match sz {
+            Sz::Nil => 0,
+            Sz::Byte | Sz::DwordByte => 1,
+            Sz::Word | Sz::DwordWord => 2,
+            Sz::Dword => 4,
+            Sz::Qword => 8,
+            Sz::Tbyte => 10,
+            Sz::Far => 2 + self.operand_size / 8,
+            Sz::Ov | Sz::Oz | Sz::Oy => self.gpr_bits(sz) / 8,
+            Sz::Vx => self.vector_length() / 8,
+            Sz::Vh => self.vector_length() / 16,
+            Sz::Vq => self.vector_length() / 32,
+            Sz::Vo => self.vector_length() / 64,
+            Sz::Xmm => 16,
+            Sz::Ymm => 32,
+        }
+    }
+
+    fn segment_register(&self) -> Option<Register> {
+        self.segment.map(|x| match x {
+            0x26 => registers::segment(0),
+            0x2E => registers::segment(1),
+            0x36 => registers::segment(2),
+            0x3E => registers::segment(3),
+            0x64 => registers::segment(4),
+            _ => registers::segment(5),
+        })
+    }
+
+    fn read_memory(&mut self, modrm: u8, size: u16) -> Result<MemoryOperand, DecodeError> {
+        let mode = modrm >> 6;
+        let rm = modrm & 7;
+        let mut mem = MemoryOperand::new(size);
+        mem.segment = self.segment_register();
+
+        if self.address_size == 16 {
+            const BASES: [(Option<u8>, Option<u8>); 8] = [
+                (Some(3), Some(6)),
+                (Some(3), Some(7)),
+                (Some(5), Some(6)),
+                (Some(5), Some(7)),
+                (Some(6), None),
+                (Some(7), None),
+                (Some(5), None),
+                (Some(3), None),
+            ];
+            if mode == 0 && rm == 6 {
+                mem.displacement = self.read_u16()? as i16 as i64;
+            } else {
+                let (base, index) = BASES[rm as usize];
+                mem.base = base.map(|x| gpr(x, 16, false));
+                mem.index = index.map(|x| gpr(x, 16, false));
+                mem.displacement = match mode {
+                    1 => self.read_u8()? as i8 as i64,
+                    2 => self.read_u16()? as i16 as i64,
+                    _ => 0,
+                };
+            }
+            return Ok(mem);
+        }
+
+        let bits = self.address_size;
+        let mut disp_size = match mode {
+            1 => 1,
+            2 => 4,
+            _ => 0,
+        };
+
+        if rm == 4 {
+            let sib = self.read_u8()?;
+            let scale = sib >> 6;
+            let index = ((sib >> 3) & 7) | (self.rex_bit(1) << 3);
+            let base = (sib & 7) | (self.rex_bit(0) << 3);
+
+            if index != 4 {
+                mem.index = Some(gpr(index, bits, true));
+                mem.scale = 1 << scale;
+            }
+            if sib & 7 == 5 && mode == 0 {
+                disp_size = 4;
+            } else {
+                mem.base = Some(gpr(base, bits, true));
+            }
+        } else if rm == 5 && mode == 0 {
+            disp_size = 4;
+            if self.long_mode {
+                mem.base = Some(if bits == 64 { RIP } else { EIP });
+            }
+        } else {
+            mem.base = Some(gpr(rm | (self.rex_bit(0) << 3), bits, true));
+        }
+
+        mem.displacement = match disp_size {
+            1 => {
+                let disp = self.read_u8()? as i8 as i64;
+                // EVEX compresses 8 bit displacements by the size of the memory access
+                match self.vex {
+                    Some(vex) if vex.evex && size > 0 => disp * size as i64,
+                    _ => disp,
+                }
+            }
+            4 => self.read_i32()? as i64,
+            _ => 0,
+        };
+
+        if mem.base.is_none() && mem.index.is_none() {
+            let mask = if bits == 64 { u64::MAX } else { 0xFFFF_FFFF };
+            mem.target = Some(mem.displacement as u64 & mask);
+        }
+
+        Ok(mem)
+    }
+
+    fn modrm_reg(&self) -> u8 {
+        let modrm = self.modrm.unwrap_or(0);
+        ((modrm >> 3) & 7) | (self.rex_bit(2) << 3)
+    }
+
+    fn modrm_rm(&self) -> u8 {
+        self.modrm.unwrap_or(0) & 7 | (self.rex_bit(0) << 3)
+    }
+
+    fn is_register_form(&self) -> bool {
+        self.modrm.map(|x| x >> 6 == 3).unwrap_or(false)
+    }
+
+    // Index of a vector register in ModRM.reg, including EVEX.R'
+    fn vector_reg(&self) -> u8 {
+        self.modrm_reg() | (self.rex_bit(4) << 4)
+    }
+
+    // Index of a vector register in ModRM.rm, EVEX uses X as the fifth bit
+    fn vector_rm(&self) -> u8 {
+        match self.vex {
+            Some(vex) if vex.evex => self.modrm_rm() | (self.rex_bit(1) << 4),
+            _ => self.modrm_rm(),
+        }
+    }
+
+    fn immediate(&mut self, size: u16) -> Result<i64, DecodeError> {
+        Ok(match size {
+            8 => self.read_u8()? as i64,
+            16 => self.read_u16()? as i64,
+            32 => self.read_i32()? as i64,
+            _ => self.read_u64()? as i64,
+        })
+    }
+
+    fn operand(&mut self, spec: Op, memory: &Option<MemoryOperand>) -> Result<Operand, DecodeError> {
+        let rex = self.rex.is_some();
+        let memory_or = |reg: Register| -> Operand {
+            match memory {
+                Some(mem) => Operand::Memory(mem.clone()),
+                None => Operand::Register(reg),
+            }
+        };
+
+        Ok(match spec {
+            Op::E(sz) => memory_or(gpr(self.modrm_rm(), self.gpr_bits(sz), rex)),
+            Op::G(sz) => Operand::Register(gpr(self.modrm_reg(), self.gpr_bits(sz), rex)),
+            Op::M(_) => match memory {
+                Some(mem) => Operand::Memory(mem.clone()),
+                None => return Err(self.invalid()),
+            },
+            Op::R(sz) => {
+                if !self.is_register_form() {
+                    return Err(self.invalid());
+                }
+                Operand::Register(gpr(self.modrm_rm(), self.gpr_bits(sz), rex))
+            }
+            Op::I(sz) => {
+                let bits = match sz {
+                    Sz::Byte => 8,
+                    Sz::Word => 16,
+                    Sz::Oz => self.operand_size.min(32),
+                    _ => self.operand_size,
+                };
+                let value = self.immediate(bits)?;
+                let size = match sz {
+                    Sz::Byte | Sz::Word => bits,
+                    _ => self.operand_size,
+                };
+                Operand::Immediate { value, size: (size / 8) as u8 }
+            }
+            Op::Is => {
+                let value = self.read_u8()? as i8 as i64;
+                Operand::Immediate { value, size: (self.operand_size / 8) as u8 }
+            }
+            Op::J(sz) => {
+                let rel = match sz {
+                    Sz::Byte => self.read_u8()? as i8 as i64,
+                    _ if self.operand_size == 16 && !self.long_mode => self.read_u16()? as i16 as i64,
+                    _ => self.read_i32()? as i64,
+                };
+                // Made absolute once the instruction length is known
+                Operand::Address(rel as u64)
+            }
+            Op::O(sz) => {
+                let mut mem = MemoryOperand::new(self.gpr_bits(sz) / 8);
+                let address = match self.address_size {
+                    64 => self.read_u64()?,
+                    32 => self.read_i32()? as u32 as u64,
+                    _ => self.read_u16()? as u64,
+                };
+                mem.displacement = address as i64;
+                mem.target = Some(address);
+                mem.segment = self.segment_register();
+                Operand::Memory(mem)
+            }
+            Op::Acc(sz) => Operand::Register(gpr(0, self.gpr_bits(sz), rex)),
+            Op::Fix(n, sz) => Operand::Register(gpr(n, self.gpr_bits(sz), rex)),
+            Op::Zr(sz) => Operand::Register(gpr((self.opcode & 7) | (self.rex_bit(0) << 3), self.gpr_bits(sz), rex)),
+            Op::One => Operand::Immediate { value: 1, size: 1 },
+            Op::SegFix(n) => Operand::Register(registers::segment(n)),
+            Op::Sw => {
+                let reg = (self.modrm.unwrap_or(0) >> 3) & 7;
+                if reg > 5 {
+                    return Err(self.invalid());
+                }
+                Operand::Register(registers::segment(reg))
+            }
+            Op::Cr => Operand::Register(registers::control(self.modrm_reg())),
+            Op::Dr => Operand::Register(registers::debug(self.modrm_reg())),
+            Op::Xs(sz) | Op::Ys(sz) => {
+                let mut mem = MemoryOperand::new(self.gpr_bits(sz) / 8);
+                let (register, segment) = match spec {
+                    Op::Xs(_) => (6, self.segment_register().unwrap_or_else(|| registers::segment(3))),
+                    _ => (7, registers::segment(0)),
+                };
+                mem.base = Some(gpr(register, self.address_size, true));
+                mem.segment = Some(segment);
+                Operand::Memory(mem)
+            }
+            Op::V(sz) => Operand::Register(registers::vector(self.vector_reg(), self.vector_bits(sz))),
+            Op::W(sz) => memory_or(registers::vector(self.vector_rm(), self.vector_bits(sz))),
+            Op::U(sz) => {
+                if !self.is_register_form() {
+                    return Err(self.invalid());
+                }
+                Operand::Register(registers::vector(self.vector_rm(), self.vector_bits(sz)))
+            }
+            Op::H(sz) => {
+                let vvvv = self.vex.map(|x| x.vvvv).unwrap_or(0);
+                Operand::Register(registers::vector(vvvv, self.vector_bits(sz)))
+            }
+            Op::L(sz) => {
+                let imm = self.read_u8()?;
+                let number = if self.long_mode { imm >> 4 } else { (imm >> 4) & 7 };
+                Operand::Register(registers::vector(number, self.vector_bits(sz)))
+            }
+            Op::P => Operand::Register(registers::mmx(self.modrm_reg())),
+            Op::Qm(_) => memory_or(registers::mmx(self.modrm_rm())),
+            Op::Nm => {
+                if !self.is_register_form() {
+                    return Err(self.invalid());
+                }
+                Operand::Register(registers::mmx(self.modrm_rm()))
+            }
+            Op::By(sz) => {
+                let vvvv = self.vex.map(|x| x.vvvv).unwrap_or(0);
+                Operand::Register(gpr(vvvv, self.gpr_bits(sz), rex))
+            }
+            Op::K => Operand::Register(registers::mask(self.modrm_reg())),
+            Op::Kr => Operand::Register(registers::mask(self.modrm_rm())),
+            Op::Kh => Operand::Register(registers::mask(self.vex.map(|x| x.vvvv).unwrap_or(0))),
+            Op::St0 => Operand::Register(registers::ST0),
+            Op::Sti => Operand::Register(registers::st(self.modrm.unwrap_or(0) & 7)),
+            Op::Ap => {
+                // Rendered as selector:offset, the selector is kept in the upper bits
+                let offset = self.immediate(self.operand_size.min(32))? & 0xFFFF_FFFF;
+                let selector = self.read_u16()? as i64;
+                Operand::Immediate { value: (selector << 32) | offset, size: 6 }
+            }
+            Op::Xmm0 => Operand::Register(registers::vector(0, 128)),
+        })
+    }
+
+    fn prefix_string(&self, mnemonic: &str) -> String {
+        let mut prefix = String::new();
+        if self.lock {
+            prefix.push_str("lock ");
+        }
+        let string_op = self.map == 0 && matches!(self.opcode, 0x6C..=0x6F | 0xA4..=0xA7 | 0xAA..=0xAF);
+        let branch = matches!(flow_of(mnemonic).0, FlowKind::Jump | FlowKind::Call | FlowKind::Return);
+        match self.rep {
+            Some(0xF3) if string_op && matches!(mnemonic, "cmps" | "scas") => prefix.push_str("repz "),
+            Some(0xF3) if string_op => prefix.push_str("rep "),
+            Some(0xF2) if string_op => prefix.push_str("repnz "),
+            Some(0xF3) if mnemonic == "ret" => prefix.push_str("repz "),
+            Some(0xF2) if branch => prefix.push_str("bnd "),
+            _ => {}
+        }
+        let indirect = self.map == 0 && self.opcode == 0xFF;
+        if indirect && branch && self.segment == Some(0x3E) {
+            prefix.push_str("notrack ");
+        }
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, X86Decoder};
+    use crate::disasm::{Decoder, DecodeError, FlowKind, Syntax};
+
+    // Decodes one instruction at 0x1000 and renders it in both syntaxes
+    fn decode(mode: Mode, bytes: &[u8]) -> (usize, String, String) {
+        let instruction = X86Decoder::new(mode).decode(bytes, 0x1000).unwrap();
+        (instruction.length, instruction.render(Syntax::Intel), instruction.render(Syntax::Att))
+    }
+
+    #[test]
+    fn prefixes() {
+        let cases: &[(&[u8], &str, &str)] = &[
+            (&[0x48, 0x89, 0xe5], "mov rbp,rsp", "mov %rsp,%rbp"),
+            (&[0x66, 0x41, 0x89, 0xc8], "mov r8w,cx", "mov %cx,%r8w"),
+            (&[0x41, 0x50], "push r8", "push %r8"),
+            (&[0xf3, 0xab], "rep stos DWORD PTR es:[rdi],eax", "rep stos %eax,%es:(%rdi)"),
+            (&[0x66, 0xf3, 0xab], "rep stos WORD PTR es:[rdi],ax", "rep stos %ax,%es:(%rdi)"),
+            (&[0xf0, 0x48, 0x0f, 0xb1, 0x0f], "lock cmpxchg QWORD PTR [rdi],rcx", "lock cmpxchg %rcx,(%rdi)"),
+            (&[0x64, 0x48, 0x8b, 0x04, 0x25, 0x28, 0, 0, 0], "mov rax,QWORD PTR fs:0x28", "mov %fs:0x28,%rax"),
+            (&[0x67, 0x8b, 0x00], "mov eax,DWORD PTR [eax]", "mov (%eax),%eax"),
+            // 66 and f2 select the instruction rather than the operand size
+            (&[0x66, 0x0f, 0xef, 0xc0], "pxor xmm0,xmm0", "pxor %xmm0,%xmm0"),
+            (&[0xf2, 0x0f, 0x10, 0xc1], "movsd xmm0,xmm1", "movsd %xmm1,%xmm0"),
+            (&[0xc5, 0xf9, 0xef, 0xc0], "vpxor xmm0,xmm0,xmm0", "vpxor %xmm0,%xmm0,%xmm0"),
+        ];
+        for &(bytes, intel, att) in cases {
+            assert_eq!(decode(Mode::Bits64, bytes), (bytes.len(), intel.to_string(), att.to_string()), "{:02x?}", bytes);
+        }
+    }
+
+    #[test]
+    fn rex_only_counts_before_the_opcode() {
+        // The REX is followed by another prefix, so the store keeps its 32 bit size
+        let (length, intel, _) = decode(Mode::Bits64, &[0x48, 0xf3, 0xab]);
+        assert_eq!((length, intel.as_str()), (3, "rep stos DWORD PTR es:[rdi],eax"));
+        // And 40 is an increment outside of long mode
+        assert_eq!(decode(Mode::Bits32, &[0x40]), (1, "inc eax".to_string(), "inc %eax".to_string()));
+        assert_eq!(X86Decoder::new(Mode::Bits64).decode(&[0x40], 0).unwrap_err(), DecodeError::EndOfInput);
+    }
+
+    #[test]
+    fn control_flow() {
+        let decoder = X86Decoder::new(Mode::Bits64);
+        let flow = |bytes: &[u8]| {
+            let instruction = decoder.decode(bytes, 0x1000).unwrap();
+            (instruction.flow, instruction.conditional, instruction.render(Syntax::Intel))
+        };
+        assert_eq!(flow(&[0xe8, 0, 0, 0, 0]), (FlowKind::Call, false, "call 0x1005".to_string()));
+        assert_eq!(flow(&[0x74, 0x02]), (FlowKind::Jump, true, "je 0x1004".to_string()));
+        assert_eq!(flow(&[0xff, 0xe0]), (FlowKind::Jump, false, "jmp rax".to_string()));
+        assert_eq!(flow(&[0xc3]), (FlowKind::Return, false, "ret".to_string()));
+    }
+}
@@ -0,0 +1,1345 @@
+// Opcode tables, using the operand notation from the Intel manual (appendix A) where possible
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sz {
+    Nil,
+    Byte,
+    Word,
+    Dword,
+    Qword,
+    Tbyte,
+    // Operand size (16, 32 or 64)
+    Ov,
+    // Word for 16 bit operand size, otherwise dword
+    Oz,
+    // Dword, or qword with 64 bit operand size
+    Oy,
+    // Far pointer (m16:16, m16:32 or m16:64)
+    Far,
+    // Full vector length, half, quarter and eighth of it
+    Vx,
+    Vh,
+    Vq,
+    Vo,
+    Xmm,
+    Ymm,
+    // 32 bit register or memory of the given size
+    DwordByte,
+    DwordWord,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    // General purpose register or memory from ModRM.rm
+    E(Sz),
+    // General purpose register from ModRM.reg
+    G(Sz),
+    // Memory only ModRM.rm
+    M(Sz),
+    // Register only ModRM.rm
+    R(Sz),
+    // Immediate
+    I(Sz),
+    // 8 bit immediate, sign extended to the operand size
+    Is,
+    // Relative branch target
+    J(Sz),
+    // Memory offset (moffs)
+    O(Sz),
+    // al/ax/eax/rax
+    Acc(Sz),
+    // Fixed general purpose register
+    Fix(u8, Sz),
+    // Register from the low three bits of the opcode
+    Zr(Sz),
+    // Constant 1, for shifts
+    One,
+    // Fixed segment register
+    SegFix(u8),
+    // Segment register from ModRM.reg
+    Sw,
+    Cr,
+    Dr,
+    // String source (ds:rsi) and destination (es:rdi)
+    Xs(Sz),
+    Ys(Sz),
+    // Vector register from ModRM.reg, vector register or memory from ModRM.rm,
+    // vector register from ModRM.rm, vector register from VEX.vvvv, vector register from imm8[7:4]
+    V(Sz),
+    W(Sz),
+    U(Sz),
+    H(Sz),
+    L(Sz),
+    // MMX register from ModRM.reg, MMX register or memory, MMX register from ModRM.rm
+    P,
+    Qm(Sz),
+    Nm,
+    // General purpose register from VEX.vvvv
+    By(Sz),
+    // Opmask register from ModRM.reg, ModRM.rm and VEX.vvvv
+    K,
+    Kr,
+    Kh,
+    St0,
+    Sti,
+    // Direct far pointer (ptr16:16 / ptr16:32)
+    Ap,
+    // Implicit xmm0 (blendv)
+    Xmm0,
+}
+
+// Default operand size of 64 bit in long mode
+pub const D64: u16 = 1;
+// Operand size is always 64 bit in long mode
+pub const F64: u16 = 2;
+// Invalid in 64 bit mode
+pub const I64: u16 = 4;
+// The mnemonic is already complete when VEX encoded
+pub const NO_V: u16 = 8;
+// Only valid without a VEX prefix
+pub const NO_VEX: u16 = 16;
+
+use self::Op::*;
+use self::Sz::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Entry {
+    pub mnemonic: &'static str,
+    pub operands: &'static [Op],
+    pub flags: u16,
+}
+
+const fn e(mnemonic: &'static str, operands: &'static [Op]) -> Option<Entry> {
+    Some(Entry { mnemonic, operands, flags: 0 })
+}
+
+const fn ef(mnemonic: &'static str, operands: &'static [Op], flags: u16) -> Option<Entry> {
+    Some(Entry { mnemonic, operands, flags })
+}
+
+/// What the table lookup needs to know about the instruction decoded so far
+#[derive(Debug, Clone, Copy)]
+pub struct Ctx {
+    pub long_mode: bool,
+    pub modrm_mod: u8,
+    pub modrm_reg: u8,
+    pub modrm_rm: u8,
+    // 0, 0x66, 0xF3 or 0xF2
+    pub prefix: u8,
+    pub rex_w: bool,
+    pub rex_b: bool,
+    pub address_size_prefix: bool,
+    pub vex: bool,
+    pub evex: bool,
+    pub vex_l: u8,
+    pub operand_size: u16,
+}
+
+impl Ctx {
+    fn mem(&self) -> bool {
+        self.modrm_mod != 3
+    }
+}
+
+const CC: [&str; 16] = ["o", "no", "b", "ae", "e", "ne", "be", "a", "s", "ns", "p", "np", "l", "ge", "le", "g"];
+
+const JCC: [&str; 16] = ["jo", "jno", "jb", "jae", "je", "jne", "jbe", "ja", "js", "jns", "jp", "jnp", "jl", "jge", "jle", "jg"];
+const SETCC: [&str; 16] = [
+    "seto", "setno", "setb", "setae", "sete", "setne", "setbe", "seta",
+    "sets", "setns", "setp", "setnp", "setl", "setge", "setle", "setg",
+];
+const CMOVCC: [&str; 16] = [
+    "cmovo", "cmovno", "cmovb", "cmovae", "cmove", "cmovne", "cmovbe", "cmova",
+    "cmovs", "cmovns", "cmovp", "cmovnp", "cmovl", "cmovge", "cmovle", "cmovg",
+];
+
+#[allow(unused)]
+pub fn condition_code(n: u8) -> &'static str {
+    CC[(n & 0xF) as usize]
+}
+
+const ALU: [&str; 8] = ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"];
+// /6 is an undocumented alias of shl
+const SHIFT: [&str; 8] = ["rol", "ror", "rcl", "rcr", "shl", "shr", "shl", "sar"];
+
+/// Whether a one byte opcode is followed by a ModRM byte
+pub fn has_modrm_one_byte(op: u8) -> bool {
+    match op {
+        0x00..=0x3F => op & 7 < 4,
+        0x62 | 0x63 | 0x69 | 0x6B => true,
+        0x80..=0x8F => true,
+        0xC0 | 0xC1 | 0xC4..=0xC7 => true,
+        0xD0..=0xD3 => true,
+        0xD8..=0xDF => true,
+        0xF6 | 0xF7 | 0xFE | 0xFF => true,
+        _ => false,
+    }
+}
+
+/// Whether a two byte (0F xx) opcode is followed by a ModRM byte
+pub fn has_modrm_two_byte(op: u8) -> bool {
+    !matches!(
+        op,
+        0x04..=0x0C | 0x0E | 0x0F
+            | 0x30..=0x37 | 0x39 | 0x3B..=0x3F
+            | 0x77
+            | 0x80..=0x8F
+            | 0xA0..=0xA2 | 0xA8..=0xAA
+            | 0xC8..=0xCF
+    )
+}
+
+pub fn one_byte(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let reg = ctx.modrm_reg as usize;
+    // 64 bit absolute addresses are only encodable with these
+    let moffs_mov = if ctx.long_mode && !ctx.address_size_prefix { "movabs" } else { "mov" };
+    match op {
+        0x00..=0x3F if op & 7 < 6 => {
+            let mnemonic = ALU[(op >> 3) as usize];
+            match op & 7 {
+                0 => e(mnemonic, &[E(Byte), G(Byte)]),
+                1 => e(mnemonic, &[E(Ov), G(Ov)]),
+                2 => e(mnemonic, &[G(Byte), E(Byte)]),
+                3 => e(mnemonic, &[G(Ov), E(Ov)]),
+                4 => e(mnemonic, &[Acc(Byte), I(Byte)]),
+                _ => e(mnemonic, &[Acc(Ov), I(Oz)]),
+            }
+        }
+        0x06 => ef("push", &[SegFix(0)], I64),
+        0x07 => ef("pop", &[SegFix(0)], I64),
+        0x0E => ef("push", &[SegFix(1)], I64),
+        0x16 => ef("push", &[SegFix(2)], I64),
+        0x17 => ef("pop", &[SegFix(2)], I64),
+        0x1E => ef("push", &[SegFix(3)], I64),
+        0x1F => ef("pop", &[SegFix(3)], I64),
+        0x27 => ef("daa", &[], I64),
+        0x2F => ef("das", &[], I64),
+        0x37 => ef("aaa", &[], I64),
+        0x3F => ef("aas", &[], I64),
+        0x40..=0x47 => ef("inc", &[Zr(Ov)], I64),
+        0x48..=0x4F => ef("dec", &[Zr(Ov)], I64),
+        0x50..=0x57 => ef("push", &[Zr(Ov)], D64),
+        0x58..=0x5F => ef("pop", &[Zr(Ov)], D64),
+        0x60 => ef(if ctx.operand_size == 16 { "pushaw" } else { "pusha" }, &[], I64),
+        0x61 => ef(if ctx.operand_size == 16 { "popaw" } else { "popa" }, &[], I64),
+        0x62 if !ctx.long_mode && ctx.mem() => e("bound", &[G(Ov), M(Ov)]),
+        0x63 if ctx.long_mode => e("movsxd", &[G(Ov), E(Dword)]),
+        0x63 => e("arpl", &[E(Word), G(Word)]),
+        0x68 => ef("push", &[I(Oz)], D64),
+        0x69 => e("imul", &[G(Ov), E(Ov), I(Oz)]),
+        0x6A => ef("push", &[Is], D64),
+        0x6B => e("imul", &[G(Ov), E(Ov), Is]),
+        0x6C => e("ins", &[Ys(Byte), Fix(2, Word)]),
+        0x6D => e("ins", &[Ys(Oz), Fix(2, Word)]),
+        0x6E => e("outs", &[Fix(2, Word), Xs(Byte)]),
+        0x6F => e("outs", &[Fix(2, Word), Xs(Oz)]),
+        0x70..=0x7F => ef(JCC[(op & 0xF) as usize], &[J(Byte)], F64),
+        0x80 => e(ALU[reg], &[E(Byte), I(Byte)]),
+        0x81 => e(ALU[reg], &[E(Ov), I(Oz)]),
+        0x82 => ef(ALU[reg], &[E(Byte), I(Byte)], I64),
+        0x83 => e(ALU[reg], &[E(Ov), Is]),
+        0x84 => e("test", &[E(Byte), G(Byte)]),
+        0x85 => e("test", &[E(Ov), G(Ov)]),
+        0x86 => e("xchg", &[E(Byte), G(Byte)]),
+        0x87 => e("xchg", &[E(Ov), G(Ov)]),
+        0x88 => e("mov", &[E(Byte), G(Byte)]),
+        0x89 => e("mov", &[E(Ov), G(Ov)]),
+        0x8A => e("mov", &[G(Byte), E(Byte)]),
+        0x8B => e("mov", &[G(Ov), E(Ov)]),
+        0x8C if ctx.mem() => e("mov", &[M(Word), Sw]),
+        0x8C => e("mov", &[R(Ov), Sw]),
+        0x8D if ctx.mem() => e("lea", &[G(Ov), M(Nil)]),
+        0x8E => e("mov", &[Sw, E(Word)]),
+        0x8F if reg == 0 => ef("pop", &[E(Ov)], D64),
+        0x90 if ctx.rex_b => e("xchg", &[Zr(Ov), Acc(Ov)]),
+        0x90 if ctx.prefix == 0xF3 => e("pause", &[]),
+        0x90 if ctx.operand_size == 16 => e("xchg", &[Zr(Ov), Acc(Ov)]),
+        0x90 => e("nop", &[]),
+        0x91..=0x97 => e("xchg", &[Zr(Ov), Acc(Ov)]),
+        0x98 => match ctx.operand_size {
+            16 => e("cbw", &[]),
+            32 => e("cwde", &[]),
+            _ => e("cdqe", &[]),
+        },
+        0x99 => match ctx.operand_size {
+            16 => e("cwd", &[]),
+            32 => e("cdq", &[]),
+            _ => e("cqo", &[]),
+        },
+        0x9A => ef("call", &[Ap], I64),
+        0x9B => e("fwait", &[]),
+        0x9C => ef("pushf", &[], D64),
+        0x9D => ef("popf", &[], D64),
+        0x9E => e("sahf", &[]),
+        0x9F => e("lahf", &[]),
+        0xA0 => e(moffs_mov, &[Acc(Byte), O(Byte)]),
+        0xA1 => e(moffs_mov, &[Acc(Ov), O(Ov)]),
+        0xA2 => e(moffs_mov, &[O(Byte), Acc(Byte)]),
+        0xA3 => e(moffs_mov, &[O(Ov), Acc(Ov)]),
+        0xA4 => e("movs", &[Ys(Byte), Xs(Byte)]),
+        0xA5 => e("movs", &[Ys(Ov), Xs(Ov)]),
+        0xA6 => e("cmps", &[Xs(Byte), Ys(Byte)]),
+        0xA7 => e("cmps", &[Xs(Ov), Ys(Ov)]),
+        0xA8 => e("test", &[Acc(Byte), I(Byte)]),
+        0xA9 => e("test", &[Acc(Ov), I(Oz)]),
+        0xAA => e("stos", &[Ys(Byte), Acc(Byte)]),
+        0xAB => e("stos", &[Ys(Ov), Acc(Ov)]),
+        0xAC => e("lods", &[Acc(Byte), Xs(Byte)]),
+        0xAD => e("lods", &[Acc(Ov), Xs(Ov)]),
+        0xAE => e("scas", &[Acc(Byte), Ys(Byte)]),
+        0xAF => e("scas", &[Acc(Ov), Ys(Ov)]),
+        0xB0..=0xB7 => e("mov", &[Zr(Byte), I(Byte)]),
+        0xB8..=0xBF if ctx.operand_size == 64 => e("movabs", &[Zr(Ov), I(Ov)]),
+        0xB8..=0xBF => e("mov", &[Zr(Ov), I(Ov)]),
+        0xC0 => e(SHIFT[reg], &[E(Byte), I(Byte)]),
+        0xC1 => e(SHIFT[reg], &[E(Ov), I(Byte)]),
+        0xC2 => ef("ret", &[I(Word)], F64),
+        0xC3 => ef("ret", &[], F64),
+        0xC4 if ctx.mem() => ef("les", &[G(Oz), M(Far)], I64),
+        0xC5 if ctx.mem() => ef("lds", &[G(Oz), M(Far)], I64),
+        0xC6 if reg == 0 => e("mov", &[E(Byte), I(Byte)]),
+        0xC6 if ctx.modrm_mod == 3 && reg == 7 && ctx.modrm_rm == 0 => e("xabort", &[I(Byte)]),
+        0xC7 if reg == 0 => e("mov", &[E(Ov), I(Oz)]),
+        0xC7 if ctx.modrm_mod == 3 && reg == 7 && ctx.modrm_rm == 0 => e("xbegin", &[J(Oz)]),
+        0xC8 => ef("enter", &[I(Word), I(Byte)], D64),
+        0xC9 => ef("leave", &[], D64),
+        0xCA => e("retf", &[I(Word)]),
+        0xCB => e("retf", &[]),
+        0xCC => e("int3", &[]),
+        0xCD => e("int", &[I(Byte)]),
+        0xCE => ef("into", &[], I64),
+        0xCF => match ctx.operand_size {
+            16 => e("iretw", &[]),
+            32 => e("iret", &[]),
+            _ => e("iretq", &[]),
+        },
+        0xD0 => e(SHIFT[reg], &[E(Byte), One]),
+        0xD1 => e(SHIFT[reg], &[E(Ov), One]),
+        0xD2 => e(SHIFT[reg], &[E(Byte), Fix(1, Byte)]),
+        0xD3 => e(SHIFT[reg], &[E(Ov), Fix(1, Byte)]),
+        0xD4 => ef("aam", &[I(Byte)], I64),
+        0xD5 => ef("aad", &[I(Byte)], I64),
+        0xD7 => e("xlat", &[]),
+        0xD8..=0xDF => x87(op, ctx),
+        0xE0 => ef("loopne", &[J(Byte)], F64),
+        0xE1 => ef("loope", &[J(Byte)], F64),
+        0xE2 => ef("loop", &[J(Byte)], F64),
+        0xE3 if ctx.long_mode => ef("jrcxz", &[J(Byte)], F64),
+        0xE3 => e("jecxz", &[J(Byte)]),
+        0xE4 => e("in", &[Acc(Byte), I(Byte)]),
+        0xE5 => e("in", &[Acc(Oz), I(Byte)]),
+        0xE6 => e("out", &[I(Byte), Acc(Byte)]),
+        0xE7 => e("out", &[I(Byte), Acc(Oz)]),
+        0xE8 => ef("call", &[J(Oz)], F64),
+        0xE9 => ef("jmp", &[J(Oz)], F64),
+        0xEA => ef("jmp", &[Ap], I64),
+        0xEB => ef("jmp", &[J(Byte)], F64),
+        0xEC => e("in", &[Acc(Byte), Fix(2, Word)]),
+        0xED => e("in", &[Acc(Oz), Fix(2, Word)]),
+        0xEE => e("out", &[Fix(2, Word), Acc(Byte)]),
+        0xEF => e("out", &[Fix(2, Word), Acc(Oz)]),
+        0xF1 => e("int1", &[]),
+        0xF4 => e("hlt", &[]),
+        0xF5 => e("cmc", &[]),
+        0xF6 => match reg {
+            0 | 1 => e("test", &[E(Byte), I(Byte)]),
+            2 => e("not", &[E(Byte)]),
+            3 => e("neg", &[E(Byte)]),
+            4 => e("mul", &[E(Byte)]),
+            5 => e("imul", &[E(Byte)]),
+            6 => e("div", &[E(Byte)]),
+            _ => e("idiv", &[E(Byte)]),
+        },
+        0xF7 => match reg {
+            0 | 1 => e("test", &[E(Ov), I(Oz)]),
+            2 => e("not", &[E(Ov)]),
+            3 => e("neg", &[E(Ov)]),
+            4 => e("mul", &[E(Ov)]),
+            5 => e("imul", &[E(Ov)]),
+            6 => e("div", &[E(Ov)]),
+            _ => e("idiv", &[E(Ov)]),
+        },
+        0xF8 => e("clc", &[]),
+        0xF9 => e("stc", &[]),
+        0xFA => e("cli", &[]),
+        0xFB => e("sti", &[]),
+        0xFC => e("cld", &[]),
+        0xFD => e("std", &[]),
+        0xFE => match reg {
+            0 => e("inc", &[E(Byte)]),
+            1 => e("dec", &[E(Byte)]),
+            _ => None,
+        },
+        0xFF => match reg {
+            0 => e("inc", &[E(Ov)]),
+            1 => e("dec", &[E(Ov)]),
+            2 => ef("call", &[E(Ov)], F64),
+            3 if ctx.mem() => e("lcall", &[M(Far)]),
+            4 => ef("jmp", &[E(Ov)], F64),
+            5 if ctx.mem() => e("ljmp", &[M(Far)]),
+            6 => ef("push", &[E(Ov)], D64),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn x87(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let reg = ctx.modrm_reg;
+    if ctx.mem() {
+        return match (op, reg) {
+            (0xD8, _) => e(["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"][reg as usize], &[M(Dword)]),
+            (0xD9, 0) => e("fld", &[M(Dword)]),
+            (0xD9, 2) => e("fst", &[M(Dword)]),
+            (0xD9, 3) => e("fstp", &[M(Dword)]),
+            (0xD9, 4) => e("fldenv", &[M(Nil)]),
+            (0xD9, 5) => e("fldcw", &[M(Word)]),
+            (0xD9, 6) => e("fnstenv", &[M(Nil)]),
+            (0xD9, 7) => e("fnstcw", &[M(Word)]),
+            (0xDA, _) => e(["fiadd", "fimul", "ficom", "ficomp", "fisub", "fisubr", "fidiv", "fidivr"][reg as usize], &[M(Dword)]),
+            (0xDB, 0) => e("fild", &[M(Dword)]),
+            (0xDB, 1) => e("fisttp", &[M(Dword)]),
+            (0xDB, 2) => e("fist", &[M(Dword)]),
+            (0xDB, 3) => e("fistp", &[M(Dword)]),
+            (0xDB, 5) => e("fld", &[M(Tbyte)]),
+            (0xDB, 7) => e("fstp", &[M(Tbyte)]),
+            (0xDC, _) => e(["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"][reg as usize], &[M(Qword)]),
+            (0xDD, 0) => e("fld", &[M(Qword)]),
+            (0xDD, 1) => e("fisttp", &[M(Qword)]),
+            (0xDD, 2) => e("fst", &[M(Qword)]),
+            (0xDD, 3) => e("fstp", &[M(Qword)]),
+            (0xDD, 4) => e("frstor", &[M(Nil)]),
+            (0xDD, 6) => e("fnsave", &[M(Nil)]),
+            (0xDD, 7) => e("fnstsw", &[M(Word)]),
+            (0xDE, _) => e(["fiadd", "fimul", "ficom", "ficomp", "fisub", "fisubr", "fidiv", "fidivr"][reg as usize], &[M(Word)]),
+            (0xDF, 0) => e("fild", &[M(Word)]),
+            (0xDF, 1) => e("fisttp", &[M(Word)]),
+            (0xDF, 2) => e("fist", &[M(Word)]),
+            (0xDF, 3) => e("fistp", &[M(Word)]),
+            (0xDF, 4) => e("fbld", &[M(Tbyte)]),
+            (0xDF, 5) => e("fild", &[M(Qword)]),
+            (0xDF, 6) => e("fbstp", &[M(Tbyte)]),
+            (0xDF, 7) => e("fistp", &[M(Qword)]),
+            _ => None,
+        };
+    }
+
+    let rm = ctx.modrm_rm;
+    match (op, reg) {
+        (0xD8, _) => e(["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"][reg as usize], &[St0, Sti]),
+        (0xD9, 0) => e("fld", &[Sti]),
+        (0xD9, 1) => e("fxch", &[Sti]),
+        (0xD9, 2) if rm == 0 => e("fnop", &[]),
+        (0xD9, 4) => match rm {
+            0 => e("fchs", &[]),
+            1 => e("fabs", &[]),
+            4 => e("ftst", &[]),
+            5 => e("fxam", &[]),
+            _ => None,
+        },
+        (0xD9, 5) => match rm {
+            0 => e("fld1", &[]),
+            1 => e("fldl2t", &[]),
+            2 => e("fldl2e", &[]),
+            3 => e("fldpi", &[]),
+            4 => e("fldlg2", &[]),
+            5 => e("fldln2", &[]),
+            6 => e("fldz", &[]),
+            _ => None,
+        },
+        (0xD9, 6) => e(["f2xm1", "fyl2x", "fptan", "fpatan", "fxtract", "fprem1", "fdecstp", "fincstp"][rm as usize], &[]),
+        (0xD9, 7) => e(["fprem", "fyl2xp1", "fsqrt", "fsincos", "frndint", "fscale", "fsin", "fcos"][rm as usize], &[]),
+        (0xDA, 0) => e("fcmovb", &[St0, Sti]),
+        (0xDA, 1) => e("fcmove", &[St0, Sti]),
+        (0xDA, 2) => e("fcmovbe", &[St0, Sti]),
+        (0xDA, 3) => e("fcmovu", &[St0, Sti]),
+        (0xDA, 5) if rm == 1 => e("fucompp", &[]),
+        (0xDB, 0) => e("fcmovnb", &[St0, Sti]),
+        (0xDB, 1) => e("fcmovne", &[St0, Sti]),
+        (0xDB, 2) => e("fcmovnbe", &[St0, Sti]),
+        (0xDB, 3) => e("fcmovnu", &[St0, Sti]),
+        (0xDB, 4) if rm == 2 => e("fnclex", &[]),
+        (0xDB, 4) if rm == 3 => e("fninit", &[]),
+        (0xDB, 5) => e("fucomi", &[St0, Sti]),
+        (0xDB, 6) => e("fcomi", &[St0, Sti]),
+        (0xDC, _) => e(["fadd", "fmul", "fcom", "fcomp", "fsubr", "fsub", "fdivr", "fdiv"][reg as usize], &[Sti, St0]),
+        (0xDD, 0) => e("ffree", &[Sti]),
+        (0xDD, 2) => e("fst", &[Sti]),
+        (0xDD, 3) => e("fstp", &[Sti]),
+        (0xDD, 4) => e("fucom", &[Sti]),
+        (0xDD, 5) => e("fucomp", &[Sti]),
+        (0xDE, 3) if rm == 1 => e("fcompp", &[]),
+        (0xDE, 0) => e("faddp", &[Sti, St0]),
+        (0xDE, 1) => e("fmulp", &[Sti, St0]),
+        (0xDE, 4) => e("fsubrp", &[Sti, St0]),
+        (0xDE, 5) => e("fsubp", &[Sti, St0]),
+        (0xDE, 6) => e("fdivrp", &[Sti, St0]),
+        (0xDE, 7) => e("fdivp", &[Sti, St0]),
+        (0xDF, 4) if rm == 0 => e("fnstsw", &[Fix(0, Word)]),
+        (0xDF, 5) => e("fucomip", &[St0, Sti]),
+        (0xDF, 6) => e("fcomip", &[St0, Sti]),
+        _ => None,
+    }
+}
+
+pub fn two_byte(op: u8, ctx: &Ctx) -> Option<Entry> {
+    if ctx.vex {
+        return match op {
+            0x41..=0x4B | 0x90..=0x99 => opmask(op, ctx),
+            0x77 if ctx.vex_l == 0 => ef("vzeroupper", &[], NO_V),
+            0x77 => ef("vzeroall", &[], NO_V),
+            _ => sse(op, ctx),
+        };
+    }
+
+    let reg = ctx.modrm_reg;
+    let mem = ctx.mem();
+    match op {
+        0x00 => match reg {
+            0 if mem => e("sldt", &[M(Word)]),
+            0 => e("sldt", &[R(Ov)]),
+            1 if mem => e("str", &[M(Word)]),
+            1 => e("str", &[R(Ov)]),
+            2 => e("lldt", &[E(Word)]),
+            3 => e("ltr", &[E(Word)]),
+            4 => e("verr", &[E(Word)]),
+            5 => e("verw", &[E(Word)]),
+            _ => None,
+        },
+        0x01 if mem => match reg {
+            0 => e("sgdt", &[M(Nil)]),
+            1 => e("sidt", &[M(Nil)]),
+            2 => e("lgdt", &[M(Nil)]),
+            3 => e("lidt", &[M(Nil)]),
+            4 => e("smsw", &[M(Word)]),
+            6 => e("lmsw", &[E(Word)]),
+            7 => e("invlpg", &[M(Byte)]),
+            _ => None,
+        },
+        0x01 => match (reg, ctx.modrm_rm) {
+            (0, 1) => e("vmcall", &[]),
+            (0, 2) => e("vmlaunch", &[]),
+            (0, 3) => e("vmresume", &[]),
+            (0, 4) => e("vmxoff", &[]),
+            (1, 0) => e("monitor", &[]),
+            (1, 1) => e("mwait", &[]),
+            (1, 2) => e("clac", &[]),
+            (1, 3) => e("stac", &[]),
+            (1, 7) => e("encls", &[]),
+            (2, 0) => e("xgetbv", &[]),
+            (2, 1) => e("xsetbv", &[]),
+            (2, 4) => e("vmfunc", &[]),
+            (2, 5) => e("xend", &[]),
+            (2, 6) => e("xtest", &[]),
+            (2, 7) => e("enclu", &[]),
+            (4, _) => e("smsw", &[R(Ov)]),
+            (5, 6) => e("rdpkru", &[]),
+            (5, 7) => e("wrpkru", &[]),
+            (6, _) => e("lmsw", &[R(Word)]),
+            (7, 0) if ctx.long_mode => e("swapgs", &[]),
+            (7, 1) => e("rdtscp", &[]),
+            _ => None,
+        },
+        0x02 => e("lar", &[G(Ov), E(Word)]),
+        0x03 => e("lsl", &[G(Ov), E(Word)]),
+        0x05 => e("syscall", &[]),
+        0x06 => e("clts", &[]),
+        0x07 => e("sysret", &[]),
+        0x08 => e("invd", &[]),
+        0x09 => e("wbinvd", &[]),
+        0x0B => e("ud2", &[]),
+        0x0D if mem => e(if reg == 1 { "prefetchw" } else { "prefetch" }, &[M(Byte)]),
+        0x18 if mem && reg < 4 => e(["prefetchnta", "prefetcht0", "prefetcht1", "prefetcht2"][reg as usize], &[M(Byte)]),
+        0x1E if ctx.prefix == 0xF3 && ctx.modrm_mod == 3 && reg == 7 && ctx.modrm_rm == 2 => e("endbr64", &[]),
+        0x1E if ctx.prefix == 0xF3 && ctx.modrm_mod == 3 && reg == 7 && ctx.modrm_rm == 3 => e("endbr32", &[]),
+        0x1E if ctx.prefix == 0xF3 && ctx.modrm_mod == 3 && reg == 1 => e(if ctx.rex_w { "rdsspq" } else { "rdsspd" }, &[R(Oy)]),
+        0x18..=0x1F => e("nop", &[E(Ov)]),
+        0x20 => ef("mov", &[R(Ov), Cr], F64),
+        0x21 => ef("mov", &[R(Ov), Dr], F64),
+        0x22 => ef("mov", &[Cr, R(Ov)], F64),
+        0x23 => ef("mov", &[Dr, R(Ov)], F64),
+        0x30 => e("wrmsr", &[]),
+        0x31 => e("rdtsc", &[]),
+        0x32 => e("rdmsr", &[]),
+        0x33 => e("rdpmc", &[]),
+        0x34 => e("sysenter", &[]),
+        0x35 => e("sysexit", &[]),
+        0x37 => e("getsec", &[]),
+        0x40..=0x4F => e(CMOVCC[(op & 0xF) as usize], &[G(Ov), E(Ov)]),
+        0x80..=0x8F => ef(JCC[(op & 0xF) as usize], &[J(Oz)], F64),
+        0x90..=0x9F => e(SETCC[(op & 0xF) as usize], &[E(Byte)]),
+        0xA0 => ef("push", &[SegFix(4)], D64),
+        0xA1 => ef("pop", &[SegFix(4)], D64),
+        0xA2 => e("cpuid", &[]),
+        0xA3 => e("bt", &[E(Ov), G(Ov)]),
+        0xA4 => e("shld", &[E(Ov), G(Ov), I(Byte)]),
+        0xA5 => e("shld", &[E(Ov), G(Ov), Fix(1, Byte)]),
+        0xA8 => ef("push", &[SegFix(5)], D64),
+        0xA9 => ef("pop", &[SegFix(5)], D64),
+        0xAA => e("rsm", &[]),
+        0xAB => e("bts", &[E(Ov), G(Ov)]),
+        0xAC => e("shrd", &[E(Ov), G(Ov), I(Byte)]),
+        0xAD => e("shrd", &[E(Ov), G(Ov), Fix(1, Byte)]),
+        0xAE if mem => match (ctx.prefix, reg) {
+            (0, 0) => e(if ctx.rex_w { "fxsave64" } else { "fxsave" }, &[M(Nil)]),
+            (0, 1) => e(if ctx.rex_w { "fxrstor64" } else { "fxrstor" }, &[M(Nil)]),
+            (0, 2) => e("ldmxcsr", &[M(Dword)]),
+            (0, 3) => e("stmxcsr", &[M(Dword)]),
+            (0, 4) => e(if ctx.rex_w { "xsave64" } else { "xsave" }, &[M(Nil)]),
+            (0, 5) => e(if ctx.rex_w { "xrstor64" } else { "xrstor" }, &[M(Nil)]),
+            (0, 6) => e(if ctx.rex_w { "xsaveopt64" } else { "xsaveopt" }, &[M(Nil)]),
+            (0, 7) => e("clflush", &[M(Byte)]),
+            (0x66, 6) => e("clwb", &[M(Byte)]),
+            (0x66, 7) => e("clflushopt", &[M(Byte)]),
+            _ => None,
+        },
+        0xAE => match (ctx.prefix, reg) {
+            (0, 5) => e("lfence", &[]),
+            (0, 6) => e("mfence", &[]),
+            (0, 7) => e("sfence", &[]),
+            (0xF3, 0) => e("rdfsbase", &[R(Oy)]),
+            (0xF3, 1) => e("rdgsbase", &[R(Oy)]),
+            (0xF3, 2) => e("wrfsbase", &[R(Oy)]),
+            (0xF3, 3) => e("wrgsbase", &[R(Oy)]),
+            (0xF3, 5) => e(if ctx.rex_w { "incsspq" } else { "incsspd" }, &[R(Oy)]),
+            _ => None,
+        },
+        0xAF => e("imul", &[G(Ov), E(Ov)]),
+        0xB0 => e("cmpxchg", &[E(Byte), G(Byte)]),
+        0xB1 => e("cmpxchg", &[E(Ov), G(Ov)]),
+        0xB2 if mem => e("lss", &[G(Ov), M(Far)]),
+        0xB3 => e("btr", &[E(Ov), G(Ov)]),
+        0xB4 if mem => e("lfs", &[G(Ov), M(Far)]),
+        0xB5 if mem => e("lgs", &[G(Ov), M(Far)]),
+        0xB6 => e("movzx", &[G(Ov), E(Byte)]),
+        0xB7 => e("movzx", &[G(Ov), E(Word)]),
+        0xB8 if ctx.prefix == 0xF3 => e("popcnt", &[G(Ov), E(Ov)]),
+        0xB9 => e("ud1", &[G(Ov), E(Ov)]),
+        0xBA if reg >= 4 => e(["bt", "bts", "btr", "btc"][(reg - 4) as usize], &[E(Ov), I(Byte)]),
+        0xBB => e("btc", &[E(Ov), G(Ov)]),
+        0xBC if ctx.prefix == 0xF3 => e("tzcnt", &[G(Ov), E(Ov)]),
+        0xBC => e("bsf", &[G(Ov), E(Ov)]),
+        0xBD if ctx.prefix == 0xF3 => e("lzcnt", &[G(Ov), E(Ov)]),
+        0xBD => e("bsr", &[G(Ov), E(Ov)]),
+        0xBE => e("movsx", &[G(Ov), E(Byte)]),
+        0xBF => e("movsx", &[G(Ov), E(Word)]),
+        0xC0 => e("xadd", &[E(Byte), G(Byte)]),
+        0xC1 => e("xadd", &[E(Ov), G(Ov)]),
+        0xC3 if mem => e("movnti", &[M(Oy), G(Oy)]),
+        0xC7 if mem => match reg {
+            1 if ctx.rex_w => e("cmpxchg16b", &[M(Xmm)]),
+            1 => e("cmpxchg8b", &[M(Qword)]),
+            6 if ctx.prefix == 0x66 => e("vmclear", &[M(Qword)]),
+            6 if ctx.prefix == 0xF3 => e("vmxon", &[M(Qword)]),
+            6 => e("vmptrld", &[M(Qword)]),
+            7 => e("vmptrst", &[M(Qword)]),
+            _ => None,
+        },
+        0xC7 => match reg {
+            6 => e("rdrand", &[R(Ov)]),
+            7 if ctx.prefix == 0xF3 => e("rdpid", &[R(Qword)]),
+            7 => e("rdseed", &[R(Ov)]),
+            _ => None,
+        },
+        0xC8..=0xCF => e("bswap", &[Zr(Oy)]),
+        0xFF => e("ud0", &[G(Ov), E(Ov)]),
+        _ => sse(op, ctx),
+    }
+}
+
+// AVX-512 opmask instructions, VEX encoded in the two byte map
+fn opmask(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let reg = ctx.modrm_mod == 3;
+
+    // Moves between opmask and general purpose registers select the width with F2 and W,
+    // where W is ignored outside of long mode
+    match (op, ctx.prefix, ctx.rex_w && ctx.long_mode) {
+        (0x92, 0, _) if reg => return ef("kmovw", &[K, R(Dword)], NO_V),
+        (0x92, 0x66, _) if reg => return ef("kmovb", &[K, R(Dword)], NO_V),
+        (0x92, 0xF2, false) if reg => return ef("kmovd", &[K, R(Dword)], NO_V),
+        (0x92, 0xF2, true) if reg => return ef("kmovq", &[K, R(Qword)], NO_V),
+        (0x93, 0, _) if reg => return ef("kmovw", &[G(Dword), Kr], NO_V),
+        (0x93, 0x66, _) if reg => return ef("kmovb", &[G(Dword), Kr], NO_V),
+        (0x93, 0xF2, false) if reg => return ef("kmovd", &[G(Dword), Kr], NO_V),
+        (0x93, 0xF2, true) if reg => return ef("kmovq", &[G(Qword), Kr], NO_V),
+        _ => {}
+    }
+
+    // Everything else uses no prefix for w/q and 66 for b/d
+    let width = match (ctx.prefix, ctx.rex_w) {
+        (0, false) => 0,
+        (0, true) => 1,
+        (0x66, false) => 2,
+        (0x66, true) => 3,
+        _ => return None,
+    };
+    let pick = |names: [&'static str; 4]| names[width];
+    match op {
+        0x41 if reg => ef(pick(["kandw", "kandq", "kandb", "kandd"]), &[K, Kh, Kr], NO_V),
+        0x42 if reg => ef(pick(["kandnw", "kandnq", "kandnb", "kandnd"]), &[K, Kh, Kr], NO_V),
+        0x44 if reg => ef(pick(["knotw", "knotq", "knotb", "knotd"]), &[K, Kr], NO_V),
+        0x45 if reg => ef(pick(["korw", "korq", "korb", "kord"]), &[K, Kh, Kr], NO_V),
+        0x46 if reg => ef(pick(["kxnorw", "kxnorq", "kxnorb", "kxnord"]), &[K, Kh, Kr], NO_V),
+        0x47 if reg => ef(pick(["kxorw", "kxorq", "kxorb", "kxord"]), &[K, Kh, Kr], NO_V),
+        0x4A if reg => ef(pick(["kaddw", "kaddq", "kaddb", "kaddd"]), &[K, Kh, Kr], NO_V),
+        0x4B if reg && width != 3 => ef(pick(["kunpckwd", "kunpckdq", "kunpckbw", ""]), &[K, Kh, Kr], NO_V),
+        0x90 if reg => ef(pick(["kmovw", "kmovq", "kmovb", "kmovd"]), &[K, Kr], NO_V),
+        0x90 => ef(pick(["kmovw", "kmovq", "kmovb", "kmovd"]), &[K, M(Nil)], NO_V),
+        0x91 if !reg => ef(pick(["kmovw", "kmovq", "kmovb", "kmovd"]), &[M(Nil), K], NO_V),
+        0x98 if reg => ef(pick(["kortestw", "kortestq", "kortestb", "kortestd"]), &[K, Kr], NO_V),
+        0x99 if reg => ef(pick(["ktestw", "ktestq", "ktestb", "ktestd"]), &[K, Kr], NO_V),
+        _ => None,
+    }
+}
+
+const MMX_ARITH: [Op; 2] = [P, Qm(Qword)];
+const VEC_ARITH: [Op; 3] = [V(Vx), H(Vx), W(Vx)];
+
+/// SSE and AVX instructions in the two byte map. Operands are given in their VEX form,
+/// the decoder drops the H operand for legacy encodings.
+fn sse(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let p = ctx.prefix;
+    let mem = ctx.mem();
+    let reg = ctx.modrm_reg;
+
+    // Integer instructions which exist both as MMX (no prefix) and SSE2 (66) versions
+    let mmx_or_sse = |name: &'static str| -> Option<Entry> {
+        match p {
+            0 => ef(name, &MMX_ARITH, NO_VEX),
+            0x66 => e(name, &VEC_ARITH),
+            _ => None,
+        }
+    };
+    // Shifts where the count is in an xmm register
+    let shift_by_xmm = |name: &'static str| -> Option<Entry> {
+        match p {
+            0 => ef(name, &MMX_ARITH, NO_VEX),
+            0x66 => e(name, &[V(Vx), H(Vx), W(Xmm)]),
+            _ => None,
+        }
+    };
+    // Floating point arithmetic in the ps/pd/ss/sd flavours
+    let fp = |names: [&'static str; 4]| -> Option<Entry> {
+        match p {
+            0 => e(names[0], &VEC_ARITH),
+            0x66 => e(names[1], &VEC_ARITH),
+            0xF3 => e(names[2], &[V(Xmm), H(Xmm), W(Dword)]),
+            _ => e(names[3], &[V(Xmm), H(Xmm), W(Qword)]),
+        }
+    };
+
+    match op {
+        0x10 => match p {
+            0 => e("movups", &[V(Vx), W(Vx)]),
+            0x66 => e("movupd", &[V(Vx), W(Vx)]),
+            0xF3 if mem => e("movss", &[V(Xmm), M(Dword)]),
+            0xF3 => e("movss", &[V(Xmm), H(Xmm), U(Xmm)]),
+            _ if mem => e("movsd", &[V(Xmm), M(Qword)]),
+            _ => e("movsd", &[V(Xmm), H(Xmm), U(Xmm)]),
+        },
+        0x11 => match p {
+            0 => e("movups", &[W(Vx), V(Vx)]),
+            0x66 => e("movupd", &[W(Vx), V(Vx)]),
+            0xF3 if mem => e("movss", &[M(Dword), V(Xmm)]),
+            0xF3 => e("movss", &[U(Xmm), H(Xmm), V(Xmm)]),
+            _ if mem => e("movsd", &[M(Qword), V(Xmm)]),
+            _ => e("movsd", &[U(Xmm), H(Xmm), V(Xmm)]),
+        },
+        0x12 => match p {
+            0 if mem => e("movlps", &[V(Xmm), H(Xmm), M(Qword)]),
+            0 => e("movhlps", &[V(Xmm), H(Xmm), U(Xmm)]),
+            0x66 if mem => e("movlpd", &[V(Xmm), H(Xmm), M(Qword)]),
+            0xF3 => e("movsldup", &[V(Vx), W(Vx)]),
+            0xF2 if ctx.vex_l == 0 => e("movddup", &[V(Xmm), W(Qword)]),
+            0xF2 => e("movddup", &[V(Vx), W(Vx)]),
+            _ => None,
+        },
+        0x13 if mem => match p {
+            0 => e("movlps", &[M(Qword), V(Xmm)]),
+            0x66 => e("movlpd", &[M(Qword), V(Xmm)]),
+            _ => None,
+        },
+        0x14 => match p {
+            0 => e("unpcklps", &VEC_ARITH),
+            0x66 => e("unpcklpd", &VEC_ARITH),
+            _ => None,
+        },
+        0x15 => match p {
+            0 => e("unpckhps", &VEC_ARITH),
+            0x66 => e("unpckhpd", &VEC_ARITH),
+            _ => None,
+        },
+        0x16 => match p {
+            0 if mem => e("movhps", &[V(Xmm), H(Xmm), M(Qword)]),
+            0 => e("movlhps", &[V(Xmm), H(Xmm), U(Xmm)]),
+            0x66 if mem => e("movhpd", &[V(Xmm), H(Xmm), M(Qword)]),
+            0xF3 => e("movshdup", &[V(Vx), W(Vx)]),
+            _ => None,
+        },
+        0x17 if mem => match p {
+            0 => e("movhps", &[M(Qword), V(Xmm)]),
+            0x66 => e("movhpd", &[M(Qword), V(Xmm)]),
+            _ => None,
+        },
+        0x28 => match p {
+            0 => e("movaps", &[V(Vx), W(Vx)]),
+            0x66 => e("movapd", &[V(Vx), W(Vx)]),
+            _ => None,
+        },
+        0x29 => match p {
+            0 => e("movaps", &[W(Vx), V(Vx)]),
+            0x66 => e("movapd", &[W(Vx), V(Vx)]),
+            _ => None,
+        },
+        0x2A => match p {
+            0 => ef("cvtpi2ps", &[V(Xmm), Qm(Qword)], NO_VEX),
+            0x66 => ef("cvtpi2pd", &[V(Xmm), Qm(Qword)], NO_VEX),
+            0xF3 => e("cvtsi2ss", &[V(Xmm), H(Xmm), E(Oy)]),
+            _ => e("cvtsi2sd", &[V(Xmm), H(Xmm), E(Oy)]),
+        },
+        0x2B if mem => match p {
+            0 => e("movntps", &[M(Vx), V(Vx)]),
+            0x66 => e("movntpd", &[M(Vx), V(Vx)]),
+            _ => None,
+        },
+        0x2C => match p {
+            0 => ef("cvttps2pi", &[P, W(Qword)], NO_VEX),
+            0x66 => ef("cvttpd2pi", &[P, W(Xmm)], NO_VEX),
+            0xF3 => e("cvttss2si", &[G(Oy), W(Dword)]),
+            _ => e("cvttsd2si", &[G(Oy), W(Qword)]),
+        },
+        0x2D => match p {
+            0 => ef("cvtps2pi", &[P, W(Qword)], NO_VEX),
+            0x66 => ef("cvtpd2pi", &[P, W(Xmm)], NO_VEX),
+            0xF3 => e("cvtss2si", &[G(Oy), W(Dword)]),
+            _ => e("cvtsd2si", &[G(Oy), W(Qword)]),
+        },
+        0x2E => match p {
+            0 => e("ucomiss", &[V(Xmm), W(Dword)]),
+            0x66 => e("ucomisd", &[V(Xmm), W(Qword)]),
+            _ => None,
+        },
+        0x2F => match p {
+            0 => e("comiss", &[V(Xmm), W(Dword)]),
+            0x66 => e("comisd", &[V(Xmm), W(Qword)]),
+            _ => None,
+        },
+        0x50 if !mem => match p {
+            0 => e("movmskps", &[G(Dword), U(Vx)]),
+            0x66 => e("movmskpd", &[G(Dword), U(Vx)]),
+            _ => None,
+        },
+        0x51 => match p {
+            0 => e("sqrtps", &[V(Vx), W(Vx)]),
+            0x66 => e("sqrtpd", &[V(Vx), W(Vx)]),
+            0xF3 => e("sqrtss", &[V(Xmm), H(Xmm), W(Dword)]),
+            _ => e("sqrtsd", &[V(Xmm), H(Xmm), W(Qword)]),
+        },
+        0x52 => match p {
+            0 => e("rsqrtps", &[V(Vx), W(Vx)]),
+            0xF3 => e("rsqrtss", &[V(Xmm), H(Xmm), W(Dword)]),
+            _ => None,
+        },
+        0x53 => match p {
+            0 => e("rcpps", &[V(Vx), W(Vx)]),
+            0xF3 => e("rcpss", &[V(Xmm), H(Xmm), W(Dword)]),
+            _ => None,
+        },
+        0x54 => fp(["andps", "andpd", "", ""]).filter(|_| p == 0 || p == 0x66),
+        0x55 => fp(["andnps", "andnpd", "", ""]).filter(|_| p == 0 || p == 0x66),
+        0x56 => fp(["orps", "orpd", "", ""]).filter(|_| p == 0 || p == 0x66),
+        0x57 => fp(["xorps", "xorpd", "", ""]).filter(|_| p == 0 || p == 0x66),
+        0x58 => fp(["addps", "addpd", "addss", "addsd"]),
+        0x59 => fp(["mulps", "mulpd", "mulss", "mulsd"]),
+        0x5A => match p {
+            0 => e("cvtps2pd", &[V(Vx), W(Vh)]),
+            0x66 => e("cvtpd2ps", &[V(Vh), W(Vx)]),
+            0xF3 => e("cvtss2sd", &[V(Xmm), H(Xmm), W(Dword)]),
+            _ => e("cvtsd2ss", &[V(Xmm), H(Xmm), W(Qword)]),
+        },
+        0x5B => match p {
+            0 => e("cvtdq2ps", &[V(Vx), W(Vx)]),
+            0x66 => e("cvtps2dq", &[V(Vx), W(Vx)]),
+            0xF3 => e("cvttps2dq", &[V(Vx), W(Vx)]),
+            _ => None,
+        },
+        0x5C => fp(["subps", "subpd", "subss", "subsd"]),
+        0x5D => fp(["minps", "minpd", "minss", "minsd"]),
+        0x5E => fp(["divps", "divpd", "divss", "divsd"]),
+        0x5F => fp(["maxps", "maxpd", "maxss", "maxsd"]),
+        0x60 => mmx_or_sse("punpcklbw"),
+        0x61 => mmx_or_sse("punpcklwd"),
+        0x62 => mmx_or_sse("punpckldq"),
+        0x63 => mmx_or_sse("packsswb"),
+        0x64 => mmx_or_sse("pcmpgtb"),
+        0x65 => mmx_or_sse("pcmpgtw"),
+        0x66 => mmx_or_sse("pcmpgtd"),
+        0x67 => mmx_or_sse("packuswb"),
+        0x68 => mmx_or_sse("punpckhbw"),
+        0x69 => mmx_or_sse("punpckhwd"),
+        0x6A => mmx_or_sse("punpckhdq"),
+        0x6B => mmx_or_sse("packssdw"),
+        0x6C if p == 0x66 => e("punpcklqdq", &VEC_ARITH),
+        0x6D if p == 0x66 => e("punpckhqdq", &VEC_ARITH),
+        0x6E => match p {
+            0 => ef(if ctx.rex_w && ctx.long_mode { "movq" } else { "movd" }, &[P, E(Oy)], NO_VEX),
+            0x66 => e(if ctx.rex_w && ctx.long_mode { "movq" } else { "movd" }, &[V(Xmm), E(Oy)]),
+            _ => None,
+        },
+        0x6F => match p {
+            0 => ef("movq", &[P, Qm(Qword)], NO_VEX),
+            0x66 => e("movdqa", &[V(Vx), W(Vx)]),
+            0xF3 => e("movdqu", &[V(Vx), W(Vx)]),
+            _ => None,
+        },
+        0x70 => match p {
+            0 => ef("pshufw", &[P, Qm(Qword), I(Byte)], NO_VEX),
+            0x66 => e("pshufd", &[V(Vx), W(Vx), I(Byte)]),
+            0xF3 => e("pshufhw", &[V(Vx), W(Vx), I(Byte)]),
+            _ => e("pshuflw", &[V(Vx), W(Vx), I(Byte)]),
+        },
+        0x71..=0x73 if !mem => {
+            let name = match (op, reg) {
+                (0x71, 2) => "psrlw",
+                (0x71, 4) => "psraw",
+                (0x71, 6) => "psllw",
+                (0x72, 2) => "psrld",
+                (0x72, 4) => "psrad",
+                (0x72, 6) => "pslld",
+                (0x73, 2) => "psrlq",
+                (0x73, 3) if p == 0x66 => "psrldq",
+                (0x73, 6) => "psllq",
+                (0x73, 7) if p == 0x66 => "pslldq",
+                _ => return None,
+            };
+            match p {
+                0 => ef(name, &[Nm, I(Byte)], NO_VEX),
+                0x66 => e(name, &[H(Vx), U(Vx), I(Byte)]),
+                _ => None,
+            }
+        }
+        0x74 => mmx_or_sse("pcmpeqb"),
+        0x75 => mmx_or_sse("pcmpeqw"),
+        0x76 => mmx_or_sse("pcmpeqd"),
+        0x77 if p == 0 => e("emms", &[]),
+        0x7C => match p {
+            0x66 => e("haddpd", &VEC_ARITH),
+            0xF2 => e("haddps", &VEC_ARITH),
+            _ => None,
+        },
+        0x7D => match p {
+            0x66 => e("hsubpd", &VEC_ARITH),
+            0xF2 => e("hsubps", &VEC_ARITH),
+            _ => None,
+        },
+        0x7E => match p {
+            0 => ef(if ctx.rex_w && ctx.long_mode { "movq" } else { "movd" }, &[E(Oy), P], NO_VEX),
+            0x66 => e(if ctx.rex_w && ctx.long_mode { "movq" } else { "movd" }, &[E(Oy), V(Xmm)]),
+            0xF3 => e("movq", &[V(Xmm), W(Qword)]),
+            _ => None,
+        },
+        0x7F => match p {
+            0 => ef("movq", &[Qm(Qword), P], NO_VEX),
+            0x66 => e("movdqa", &[W(Vx), V(Vx)]),
+            0xF3 => e("movdqu", &[W(Vx), V(Vx)]),
+            _ => None,
+        },
+        0xC2 => match p {
+            0 => e("cmpps", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+            0x66 => e("cmppd", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+            0xF3 => e("cmpss", &[V(Xmm), H(Xmm), W(Dword), I(Byte)]),
+            _ => e("cmpsd", &[V(Xmm), H(Xmm), W(Qword), I(Byte)]),
+        },
+        0xC4 => match p {
+            0 => ef("pinsrw", &[P, E(DwordWord), I(Byte)], NO_VEX),
+            0x66 => e("pinsrw", &[V(Xmm), H(Xmm), E(DwordWord), I(Byte)]),
+            _ => None,
+        },
+        0xC5 if !mem => match p {
+            0 => ef("pextrw", &[G(Dword), Nm, I(Byte)], NO_VEX),
+            0x66 => e("pextrw", &[G(Dword), U(Xmm), I(Byte)]),
+            _ => None,
+        },
+        0xC6 => match p {
+            0 => e("shufps", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+            0x66 => e("shufpd", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+            _ => None,
+        },
+        0xD0 => match p {
+            0x66 => e("addsubpd", &VEC_ARITH),
+            0xF2 => e("addsubps", &VEC_ARITH),
+            _ => None,
+        },
+        0xD1 => shift_by_xmm("psrlw"),
+        0xD2 => shift_by_xmm("psrld"),
+        0xD3 => shift_by_xmm("psrlq"),
+        0xD4 => mmx_or_sse("paddq"),
+        0xD5 => mmx_or_sse("pmullw"),
+        0xD6 => match p {
+            0x66 => e("movq", &[W(Qword), V(Xmm)]),
+            0xF3 if !mem => ef("movq2dq", &[V(Xmm), Nm], NO_VEX),
+            0xF2 if !mem => ef("movdq2q", &[P, U(Xmm)], NO_VEX),
+            _ => None,
+        },
+        0xD7 if !mem => match p {
+            0 => ef("pmovmskb", &[G(Dword), Nm], NO_VEX),
+            0x66 => e("pmovmskb", &[G(Dword), U(Vx)]),
+            _ => None,
+        },
+        0xD8 => mmx_or_sse("psubusb"),
+        0xD9 => mmx_or_sse("psubusw"),
+        0xDA => mmx_or_sse("pminub"),
+        0xDB => mmx_or_sse("pand"),
+        0xDC => mmx_or_sse("paddusb"),
+        0xDD => mmx_or_sse("paddusw"),
+        0xDE => mmx_or_sse("pmaxub"),
+        0xDF => mmx_or_sse("pandn"),
+        0xE0 => mmx_or_sse("pavgb"),
+        0xE1 => shift_by_xmm("psraw"),
+        0xE2 => shift_by_xmm("psrad"),
+        0xE3 => mmx_or_sse("pavgw"),
+        0xE4 => mmx_or_sse("pmulhuw"),
+        0xE5 => mmx_or_sse("pmulhw"),
+        0xE6 => match p {
+            0x66 => e("cvttpd2dq", &[V(Vh), W(Vx)]),
+            0xF3 => e("cvtdq2pd", &[V(Vx), W(Vh)]),
+            0xF2 => e("cvtpd2dq", &[V(Vh), W(Vx)]),
+            _ => None,
+        },
+        0xE7 if mem => match p {
+            0 => ef("movntq", &[M(Qword), P], NO_VEX),
+            0x66 => e("movntdq", &[M(Vx), V(Vx)]),
+            _ => None,
+        },
+        0xE8 => mmx_or_sse("psubsb"),
+        0xE9 => mmx_or_sse("psubsw"),
+        0xEA => mmx_or_sse("pminsw"),
+        0xEB => mmx_or_sse("por"),
+        0xEC => mmx_or_sse("paddsb"),
+        0xED => mmx_or_sse("paddsw"),
+        0xEE => mmx_or_sse("pmaxsw"),
+        0xEF => mmx_or_sse("pxor"),
+        0xF0 if mem && p == 0xF2 => e("lddqu", &[V(Vx), M(Vx)]),
+        0xF1 => shift_by_xmm("psllw"),
+        0xF2 => shift_by_xmm("pslld"),
+        0xF3 => shift_by_xmm("psllq"),
+        0xF4 => mmx_or_sse("pmuludq"),
+        0xF5 => mmx_or_sse("pmaddwd"),
+        0xF6 => mmx_or_sse("psadbw"),
+        0xF7 if !mem => match p {
+            0 => ef("maskmovq", &[P, Nm], NO_VEX),
+            0x66 => e("maskmovdqu", &[V(Xmm), U(Xmm)]),
+            _ => None,
+        },
+        0xF8 => mmx_or_sse("psubb"),
+        0xF9 => mmx_or_sse("psubw"),
+        0xFA => mmx_or_sse("psubd"),
+        0xFB => mmx_or_sse("psubq"),
+        0xFC => mmx_or_sse("paddb"),
+        0xFD => mmx_or_sse("paddw"),
+        0xFE => mmx_or_sse("paddd"),
+        _ => None,
+    }
+}
+
+const FMA: [[&str; 2]; 30] = [
+    ["vfmaddsub132ps", "vfmaddsub132pd"],
+    ["vfmsubadd132ps", "vfmsubadd132pd"],
+    ["vfmadd132ps", "vfmadd132pd"],
+    ["vfmadd132ss", "vfmadd132sd"],
+    ["vfmsub132ps", "vfmsub132pd"],
+    ["vfmsub132ss", "vfmsub132sd"],
+    ["vfnmadd132ps", "vfnmadd132pd"],
+    ["vfnmadd132ss", "vfnmadd132sd"],
+    ["vfnmsub132ps", "vfnmsub132pd"],
+    ["vfnmsub132ss", "vfnmsub132sd"],
+    ["vfmaddsub213ps", "vfmaddsub213pd"],
+    ["vfmsubadd213ps", "vfmsubadd213pd"],
+    ["vfmadd213ps", "vfmadd213pd"],
+    ["vfmadd213ss", "vfmadd213sd"],
+    ["vfmsub213ps", "vfmsub213pd"],
+    ["vfmsub213ss", "vfmsub213sd"],
+    ["vfnmadd213ps", "vfnmadd213pd"],
+    ["vfnmadd213ss", "vfnmadd213sd"],
+    ["vfnmsub213ps", "vfnmsub213pd"],
+    ["vfnmsub213ss", "vfnmsub213sd"],
+    ["vfmaddsub231ps", "vfmaddsub231pd"],
+    ["vfmsubadd231ps", "vfmsubadd231pd"],
+    ["vfmadd231ps", "vfmadd231pd"],
+    ["vfmadd231ss", "vfmadd231sd"],
+    ["vfmsub231ps", "vfmsub231pd"],
+    ["vfmsub231ss", "vfmsub231sd"],
+    ["vfnmadd231ps", "vfnmadd231pd"],
+    ["vfnmadd231ss", "vfnmadd231sd"],
+    ["vfnmsub231ps", "vfnmsub231pd"],
+    ["vfnmsub231ss", "vfnmsub231sd"],
+];
+
+pub fn three_byte_38(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let p = ctx.prefix;
+    let mem = ctx.mem();
+
+    // SSSE3 instructions with both MMX and xmm versions
+    let ssse3 = |name: &'static str, unary: bool| -> Option<Entry> {
+        match (p, unary) {
+            (0, _) => ef(name, &MMX_ARITH, NO_VEX),
+            (0x66, false) => e(name, &VEC_ARITH),
+            (0x66, true) => e(name, &[V(Vx), W(Vx)]),
+            _ => None,
+        }
+    };
+    let sse4 = |name: &'static str| -> Option<Entry> {
+        match p {
+            0x66 => e(name, &VEC_ARITH),
+            _ => None,
+        }
+    };
+    let extend = |name: &'static str, size: Sz| -> Option<Entry> {
+        match (p, size) {
+            (0x66, Vh) => e(name, &[V(Vx), W(Vh)]),
+            (0x66, Vq) => e(name, &[V(Vx), W(Vq)]),
+            (0x66, _) => e(name, &[V(Vx), W(Vo)]),
+            _ => None,
+        }
+    };
+
+    if ctx.vex && p == 0x66 {
+        // FMA: 0x96-0x9F, 0xA6-0xAF and 0xB6-0xBF, alternating packed and scalar from 0x98 on
+        if let 0x96..=0x9F | 0xA6..=0xAF | 0xB6..=0xBF = op {
+            let idx = ((op >> 4) - 0x9) as usize * 10 + (op & 0xF) as usize - 6;
+            let mnemonic = FMA[idx][ctx.rex_w as usize];
+            let scalar = op & 0xF >= 8 && op & 1 == 1;
+            return match (scalar, ctx.rex_w) {
+                (false, _) => ef(mnemonic, &VEC_ARITH, NO_V),
+                (true, false) => ef(mnemonic, &[V(Xmm), H(Xmm), W(Dword)], NO_V),
+                (true, true) => ef(mnemonic, &[V(Xmm), H(Xmm), W(Qword)], NO_V),
+            };
+        }
+
+        let vex_only = match op {
+            0x0C => ef("vpermilps", &VEC_ARITH, NO_V),
+            0x0D => ef("vpermilpd", &VEC_ARITH, NO_V),
+            0x0E => ef("vtestps", &[V(Vx), W(Vx)], NO_V),
+            0x0F => ef("vtestpd", &[V(Vx), W(Vx)], NO_V),
+            0x13 => ef("vcvtph2ps", &[V(Vx), W(Vh)], NO_V),
+            0x16 => ef("vpermps", &VEC_ARITH, NO_V),
+            0x18 => ef("vbroadcastss", &[V(Vx), W(Dword)], NO_V),
+            0x19 => ef("vbroadcastsd", &[V(Vx), W(Qword)], NO_V),
+            0x1A if mem => ef("vbroadcastf128", &[V(Vx), M(Xmm)], NO_V),
+            0x2C if mem => ef("vmaskmovps", &[V(Vx), H(Vx), M(Vx)], NO_V),
+            0x2D if mem => ef("vmaskmovpd", &[V(Vx), H(Vx), M(Vx)], NO_V),
+            0x2E if mem => ef("vmaskmovps", &[M(Vx), H(Vx), V(Vx)], NO_V),
+            0x2F if mem => ef("vmaskmovpd", &[M(Vx), H(Vx), V(Vx)], NO_V),
+            0x36 => ef("vpermd", &VEC_ARITH, NO_V),
+            0x45 => ef(if ctx.rex_w { "vpsrlvq" } else { "vpsrlvd" }, &VEC_ARITH, NO_V),
+            0x46 => ef("vpsravd", &VEC_ARITH, NO_V),
+            0x47 => ef(if ctx.rex_w { "vpsllvq" } else { "vpsllvd" }, &VEC_ARITH, NO_V),
+            0x58 => ef("vpbroadcastd", &[V(Vx), W(Dword)], NO_V),
+            0x59 => ef("vpbroadcastq", &[V(Vx), W(Qword)], NO_V),
+            0x5A if mem => ef("vbroadcasti128", &[V(Vx), M(Xmm)], NO_V),
+            0x78 => ef("vpbroadcastb", &[V(Vx), W(Byte)], NO_V),
+            0x79 => ef("vpbroadcastw", &[V(Vx), W(Word)], NO_V),
+            0x8C if mem => ef(if ctx.rex_w { "vpmaskmovq" } else { "vpmaskmovd" }, &[V(Vx), H(Vx), M(Vx)], NO_V),
+            0x8E if mem => ef(if ctx.rex_w { "vpmaskmovq" } else { "vpmaskmovd" }, &[M(Vx), H(Vx), V(Vx)], NO_V),
+            _ => None,
+        };
+        if vex_only.is_some() {
+            return vex_only;
+        }
+    }
+
+    if ctx.vex && !ctx.evex {
+        // BMI1/BMI2 instructions on general purpose registers
+        let bmi = match (op, p) {
+            (0xF2, 0) => ef("andn", &[G(Oy), By(Oy), E(Oy)], NO_V),
+            (0xF3, 0) => match ctx.modrm_reg {
+                1 => ef("blsr", &[By(Oy), E(Oy)], NO_V),
+                2 => ef("blsmsk", &[By(Oy), E(Oy)], NO_V),
+                3 => ef("blsi", &[By(Oy), E(Oy)], NO_V),
+                _ => None,
+            },
+            (0xF5, 0) => ef("bzhi", &[G(Oy), E(Oy), By(Oy)], NO_V),
+            (0xF5, 0xF3) => ef("pext", &[G(Oy), By(Oy), E(Oy)], NO_V),
+            (0xF5, 0xF2) => ef("pdep", &[G(Oy), By(Oy), E(Oy)], NO_V),
+            (0xF6, 0xF2) => ef("mulx", &[G(Oy), By(Oy), E(Oy)], NO_V),
+            (0xF7, 0) => ef("bextr", &[G(Oy), E(Oy), By(Oy)], NO_V),
+            (0xF7, 0x66) => ef("shlx", &[G(Oy), E(Oy), By(Oy)], NO_V),
+            (0xF7, 0xF3) => ef("sarx", &[G(Oy), E(Oy), By(Oy)], NO_V),
+            (0xF7, 0xF2) => ef("shrx", &[G(Oy), E(Oy), By(Oy)], NO_V),
+            _ => None,
+        };
+        if bmi.is_some() {
+            return bmi;
+        }
+    }
+
+    match op {
+        0x00 => ssse3("pshufb", false),
+        0x01 => ssse3("phaddw", false),
+        0x02 => ssse3("phaddd", false),
+        0x03 => ssse3("phaddsw", false),
+        0x04 => ssse3("pmaddubsw", false),
+        0x05 => ssse3("phsubw", false),
+        0x06 => ssse3("phsubd", false),
+        0x07 => ssse3("phsubsw", false),
+        0x08 => ssse3("psignb", false),
+        0x09 => ssse3("psignw", false),
+        0x0A => ssse3("psignd", false),
+        0x0B => ssse3("pmulhrsw", false),
+        0x10 if p == 0x66 => ef("pblendvb", &[V(Xmm), W(Xmm), Xmm0], NO_VEX),
+        0x14 if p == 0x66 => ef("blendvps", &[V(Xmm), W(Xmm), Xmm0], NO_VEX),
+        0x15 if p == 0x66 => ef("blendvpd", &[V(Xmm), W(Xmm), Xmm0], NO_VEX),
+        0x17 if p == 0x66 => e("ptest", &[V(Vx), W(Vx)]),
+        0x1C => ssse3("pabsb", true),
+        0x1D => ssse3("pabsw", true),
+        0x1E => ssse3("pabsd", true),
+        0x20 => extend("pmovsxbw", Vh),
+        0x21 => extend("pmovsxbd", Vq),
+        0x22 => extend("pmovsxbq", Vo),
+        0x23 => extend("pmovsxwd", Vh),
+        0x24 => extend("pmovsxwq", Vq),
+        0x25 => extend("pmovsxdq", Vh),
+        0x28 => sse4("pmuldq"),
+        0x29 => sse4("pcmpeqq"),
+        0x2A if mem && p == 0x66 => e("movntdqa", &[V(Vx), M(Vx)]),
+        0x2B => sse4("packusdw"),
+        0x30 => extend("pmovzxbw", Vh),
+        0x31 => extend("pmovzxbd", Vq),
+        0x32 => extend("pmovzxbq", Vo),
+        0x33 => extend("pmovzxwd", Vh),
+        0x34 => extend("pmovzxwq", Vq),
+        0x35 => extend("pmovzxdq", Vh),
+        0x37 => sse4("pcmpgtq"),
+        0x38 => sse4("pminsb"),
+        0x39 => sse4("pminsd"),
+        0x3A => sse4("pminuw"),
+        0x3B => sse4("pminud"),
+        0x3C => sse4("pmaxsb"),
+        0x3D => sse4("pmaxsd"),
+        0x3E => sse4("pmaxuw"),
+        0x3F => sse4("pmaxud"),
+        0x40 => sse4("pmulld"),
+        0x41 if p == 0x66 => e("phminposuw", &[V(Xmm), W(Xmm)]),
+        0xDB if p == 0x66 => e("aesimc", &[V(Xmm), W(Xmm)]),
+        0xDC => sse4("aesenc"),
+        0xDD => sse4("aesenclast"),
+        0xDE => sse4("aesdec"),
+        0xDF => sse4("aesdeclast"),
+        0xF0 | 0xF1 if ctx.vex => None,
+        0xF0 if p == 0xF2 => e("crc32", &[G(Oy), E(Byte)]),
+        0xF1 if p == 0xF2 => e("crc32", &[G(Oy), E(Ov)]),
+        0xF0 if mem => e("movbe", &[G(Ov), M(Ov)]),
+        0xF1 if mem => e("movbe", &[M(Ov), G(Ov)]),
+        _ => None,
+    }
+}
+
+pub fn three_byte_3a(op: u8, ctx: &Ctx) -> Option<Entry> {
+    let p = ctx.prefix;
+    if p != 0x66 {
+        return match op {
+            0x0F if p == 0 => ef("palignr", &[P, Qm(Qword), I(Byte)], NO_VEX),
+            0xF0 if p == 0xF2 && ctx.vex => ef("rorx", &[G(Oy), E(Oy), I(Byte)], NO_V),
+            _ => None,
+        };
+    }
+
+    if ctx.vex {
+        let vex_only = match op {
+            0x00 => ef("vpermq", &[V(Vx), W(Vx), I(Byte)], NO_V),
+            0x01 => ef("vpermpd", &[V(Vx), W(Vx), I(Byte)], NO_V),
+            0x02 => ef("vpblendd", &[V(Vx), H(Vx), W(Vx), I(Byte)], NO_V),
+            0x04 => ef("vpermilps", &[V(Vx), W(Vx), I(Byte)], NO_V),
+            0x05 => ef("vpermilpd", &[V(Vx), W(Vx), I(Byte)], NO_V),
+            0x06 => ef("vperm2f128", &[V(Vx), H(Vx), W(Vx), I(Byte)], NO_V),
+            0x18 => ef("vinsertf128", &[V(Vx), H(Vx), W(Xmm), I(Byte)], NO_V),
+            0x19 => ef("vextractf128", &[W(Xmm), V(Vx), I(Byte)], NO_V),
+            0x1D => ef("vcvtps2ph", &[W(Vh), V(Vx), I(Byte)], NO_V),
+            0x38 => ef("vinserti128", &[V(Vx), H(Vx), W(Xmm), I(Byte)], NO_V),
+            0x39 => ef("vextracti128", &[W(Xmm), V(Vx), I(Byte)], NO_V),
+            0x46 => ef("vperm2i128", &[V(Vx), H(Vx), W(Vx), I(Byte)], NO_V),
+            0x4A => ef("vblendvps", &[V(Vx), H(Vx), W(Vx), L(Vx)], NO_V),
+            0x4B => ef("vblendvpd", &[V(Vx), H(Vx), W(Vx), L(Vx)], NO_V),
+            0x4C => ef("vpblendvb", &[V(Vx), H(Vx), W(Vx), L(Vx)], NO_V),
+            _ => None,
+        };
+        if vex_only.is_some() {
+            return vex_only;
+        }
+    }
+
+    match op {
+        0x08 => e("roundps", &[V(Vx), W(Vx), I(Byte)]),
+        0x09 => e("roundpd", &[V(Vx), W(Vx), I(Byte)]),
+        0x0A => e("roundss", &[V(Xmm), H(Xmm), W(Dword), I(Byte)]),
+        0x0B => e("roundsd", &[V(Xmm), H(Xmm), W(Qword), I(Byte)]),
+        0x0C => e("blendps", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x0D => e("blendpd", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x0E => e("pblendw", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x0F => e("palignr", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x14 => e("pextrb", &[E(DwordByte), V(Xmm), I(Byte)]),
+        0x15 => e("pextrw", &[E(DwordWord), V(Xmm), I(Byte)]),
+        0x16 => e(if ctx.rex_w && ctx.long_mode { "pextrq" } else { "pextrd" }, &[E(Oy), V(Xmm), I(Byte)]),
+        0x17 => e("extractps", &[E(Dword), V(Xmm), I(Byte)]),
+        0x20 => e("pinsrb", &[V(Xmm), H(Xmm), E(DwordByte), I(Byte)]),
+        0x21 => e("insertps", &[V(Xmm), H(Xmm), W(Dword), I(Byte)]),
+        0x22 => e(if ctx.rex_w && ctx.long_mode { "pinsrq" } else { "pinsrd" }, &[V(Xmm), H(Xmm), E(Oy), I(Byte)]),
+        0x40 => e("dpps", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x41 => e("dppd", &[V(Xmm), H(Xmm), W(Xmm), I(Byte)]),
+        0x42 => e("mpsadbw", &[V(Vx), H(Vx), W(Vx), I(Byte)]),
+        0x44 => e("pclmulqdq", &[V(Xmm), H(Xmm), W(Xmm), I(Byte)]),
+        0x60 => e("pcmpestrm", &[V(Xmm), W(Xmm), I(Byte)]),
+        0x61 => e("pcmpestri", &[V(Xmm), W(Xmm), I(Byte)]),
+        0x62 => e("pcmpistrm", &[V(Xmm), W(Xmm), I(Byte)]),
+        0x63 => e("pcmpistri", &[V(Xmm), W(Xmm), I(Byte)]),
+        0xDF => e("aeskeygenassist", &[V(Xmm), W(Xmm), I(Byte)]),
+        _ => None,
+    }
+}
+
+/// EVEX encodings whose mnemonic or operands differ from their VEX counterpart
+pub fn evex_override(map: u8, op: u8, ctx: &Ctx) -> Option<Entry> {
+    let p = ctx.prefix;
+    let w = ctx.rex_w;
+    let pick = |d: &'static str, q: &'static str| if w { q } else { d };
+    match (map, op, p) {
+        (1, 0x6F, 0x66) => ef(pick("vmovdqa32", "vmovdqa64"), &[V(Vx), W(Vx)], NO_V),
+        (1, 0x6F, 0xF3) => ef(pick("vmovdqu32", "vmovdqu64"), &[V(Vx), W(Vx)], NO_V),
+        (1, 0x6F, 0xF2) => ef(pick("vmovdqu8", "vmovdqu16"), &[V(Vx), W(Vx)], NO_V),
+        (1, 0x7F, 0x66) => ef(pick("vmovdqa32", "vmovdqa64"), &[W(Vx), V(Vx)], NO_V),
+        (1, 0x7F, 0xF3) => ef(pick("vmovdqu32", "vmovdqu64"), &[W(Vx), V(Vx)], NO_V),
+        (1, 0x7F, 0xF2) => ef(pick("vmovdqu8", "vmovdqu16"), &[W(Vx), V(Vx)], NO_V),
+        (1, 0xDB, 0x66) => ef(pick("vpandd", "vpandq"), &VEC_ARITH, NO_V),
+        (1, 0xDF, 0x66) => ef(pick("vpandnd", "vpandnq"), &VEC_ARITH, NO_V),
+        (1, 0xEB, 0x66) => ef(pick("vpord", "vporq"), &VEC_ARITH, NO_V),
+        (1, 0xEF, 0x66) => ef(pick("vpxord", "vpxorq"), &VEC_ARITH, NO_V),
+        (1, 0x74, 0x66) => ef("vpcmpeqb", &[K, H(Vx), W(Vx)], NO_V),
+        (1, 0x75, 0x66) => ef("vpcmpeqw", &[K, H(Vx), W(Vx)], NO_V),
+        (1, 0x76, 0x66) => ef("vpcmpeqd", &[K, H(Vx), W(Vx)], NO_V),
+        (1, 0x64, 0x66) => ef("vpcmpgtb", &[K, H(Vx), W(Vx)], NO_V),
+        (1, 0x65, 0x66) => ef("vpcmpgtw", &[K, H(Vx), W(Vx)], NO_V),
+        (1, 0x66, 0x66) => ef("vpcmpgtd", &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x29, 0x66) => ef("vpcmpeqq", &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x26, 0x66) => ef(pick("vptestmb", "vptestmw"), &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x26, 0xF3) => ef(pick("vptestnmb", "vptestnmw"), &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x27, 0x66) => ef(pick("vptestmd", "vptestmq"), &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x27, 0xF3) => ef(pick("vptestnmd", "vptestnmq"), &[K, H(Vx), W(Vx)], NO_V),
+        (2, 0x64, 0x66) => ef(pick("vpblendmd", "vpblendmq"), &VEC_ARITH, NO_V),
+        (2, 0x66, 0x66) => ef(pick("vpblendmb", "vpblendmw"), &VEC_ARITH, NO_V),
+        (2, 0x7A, 0x66) => ef("vpbroadcastb", &[V(Vx), R(Dword)], NO_V),
+        (2, 0x7B, 0x66) => ef("vpbroadcastw", &[V(Vx), R(Dword)], NO_V),
+        (2, 0x7C, 0x66) => ef(pick("vpbroadcastd", "vpbroadcastq"), &[V(Vx), R(Oy)], NO_V),
+        (2, 0x3F, 0x66) => ef(pick("vpmaxud", "vpmaxuq"), &VEC_ARITH, NO_V),
+        (2, 0x3B, 0x66) => ef(pick("vpminud", "vpminuq"), &VEC_ARITH, NO_V),
+        (3, 0x1E, 0x66) => ef(pick("vpcmpud", "vpcmpuq"), &[K, H(Vx), W(Vx), I(Byte)], NO_V),
+        (3, 0x1F, 0x66) => ef(pick("vpcmpd", "vpcmpq"), &[K, H(Vx), W(Vx), I(Byte)], NO_V),
+        (3, 0x3E, 0x66) => ef(pick("vpcmpub", "vpcmpuw"), &[K, H(Vx), W(Vx), I(Byte)], NO_V),
+        (3, 0x3F, 0x66) => ef(pick("vpcmpb", "vpcmpw"), &[K, H(Vx), W(Vx), I(Byte)], NO_V),
+        (3, 0x25, 0x66) => ef(pick("vpternlogd", "vpternlogq"), &[V(Vx), H(Vx), W(Vx), I(Byte)], NO_V),
+        (3, 0x18, 0x66) | (3, 0x38, 0x66) => ef(pick("vinserti32x4", "vinserti64x2"), &[V(Vx), H(Vx), W(Xmm), I(Byte)], NO_V),
+        (3, 0x19, 0x66) | (3, 0x39, 0x66) => ef(pick("vextracti32x4", "vextracti64x2"), &[W(Xmm), V(Vx), I(Byte)], NO_V),
+        (3, 0x3A, 0x66) => ef(pick("vinserti32x8", "vinserti64x4"), &[V(Vx), H(Vx), W(Ymm), I(Byte)], NO_V),
+        (3, 0x3B, 0x66) => ef(pick("vextracti32x8", "vextracti64x4"), &[W(Ymm), V(Vx), I(Byte)], NO_V),
+        _ => None,
+    }
+}
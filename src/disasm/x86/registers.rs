@@ -0,0 +1,127 @@
+use crate::disasm::{Register, RegisterClass};
+
+const GPR64: [&str; 16] = [
+    "rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi",
+    "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15",
+];
+
+const GPR32: [&str; 16] = [
+    "eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi",
+    "r8d", "r9d", "r10d", "r11d", "r12d", "r13d", "r14d", "r15d",
+];
+
+const GPR16: [&str; 16] = [
+    "ax", "cx", "dx", "bx", "sp", "bp", "si", "di",
+    "r8w", "r9w", "r10w", "r11w", "r12w", "r13w", "r14w", "r15w",
+];
+
+const GPR8: [&str; 16] = [
+    "al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil",
+    "r8b", "r9b", "r10b", "r11b", "r12b", "r13b", "r14b", "r15b",
+];
+
+const GPR8_HIGH: [&str; 4] = ["ah", "ch", "dh", "bh"];
+
+const SEGMENT: [&str; 8] = ["es", "cs", "ss", "ds", "fs", "gs", "?", "?"];
+
+const XMM: [&str; 32] = [
+    "xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7",
+    "xmm8", "xmm9", "xmm10", "xmm11", "xmm12", "xmm13", "xmm14", "xmm15",
+    "xmm16", "xmm17", "xmm18", "xmm19", "xmm20", "xmm21", "xmm22", "xmm23",
+    "xmm24", "xmm25", "xmm26", "xmm27", "xmm28", "xmm29", "xmm30", "xmm31",
+];
+
+const YMM: [&str; 32] = [
+    "ymm0", "ymm1", "ymm2", "ymm3", "ymm4", "ymm5", "ymm6", "ymm7",
+    "ymm8", "ymm9", "ymm10", "ymm11", "ymm12", "ymm13", "ymm14", "ymm15",
+    "ymm16", "ymm17", "ymm18", "ymm19", "ymm20", "ymm21", "ymm22", "ymm23",
+    "ymm24", "ymm25", "ymm26", "ymm27", "ymm28", "ymm29", "ymm30", "ymm31",
+];
+
+const ZMM: [&str; 32] = [
+    "zmm0", "zmm1", "zmm2", "zmm3", "zmm4", "zmm5", "zmm6", "zmm7",
+    "zmm8", "zmm9", "zmm10", "zmm11", "zmm12", "zmm13", "zmm14", "zmm15",
+    "zmm16", "zmm17", "zmm18", "zmm19", "zmm20", "zmm21", "zmm22", "zmm23",
+    "zmm24", "zmm25", "zmm26", "zmm27", "zmm28", "zmm29", "zmm30", "zmm31",
+];
+
+const MMX: [&str; 8] = ["mm0", "mm1", "mm2", "mm3", "mm4", "mm5", "mm6", "mm7"];
+
+const MASK: [&str; 8] = ["k0", "k1", "k2", "k3", "k4", "k5", "k6", "k7"];
+
+const ST: [&str; 8] = ["st(0)", "st(1)", "st(2)", "st(3)", "st(4)", "st(5)", "st(6)", "st(7)"];
+
+const CONTROL: [&str; 16] = [
+    "cr0", "cr1", "cr2", "cr3", "cr4", "cr5", "cr6", "cr7",
+    "cr8", "cr9", "cr10", "cr11", "cr12", "cr13", "cr14", "cr15",
+];
+
+const DEBUG: [&str; 16] = [
+    "db0", "db1", "db2", "db3", "db4", "db5", "db6", "db7",
+    "db8", "db9", "db10", "db11", "db12", "db13", "db14", "db15",
+];
+
+// The implicit top of the x87 stack
+pub const ST0: Register = Register::new(RegisterClass::Float, 0, 80, "st");
+
+pub const RIP: Register = Register::new(RegisterClass::ProgramCounter, 0, 64, "rip");
+pub const EIP: Register = Register::new(RegisterClass::ProgramCounter, 0, 32, "eip");
+
+/// A general purpose register. `rex` selects spl/bpl/sil/dil instead of ah/ch/dh/bh for byte registers.
+pub fn gpr(number: u8, bits: u16, rex: bool) -> Register {
+    let number = number & 0xF;
+    match bits {
+        8 if !rex && (4..8).contains(&number) => Register {
+            class: RegisterClass::General,
+            number: (number - 4) as u16,
+            bits: 8,
+            offset: 8,
+            name: GPR8_HIGH[(number - 4) as usize],
+        },
+        8 => Register::new(RegisterClass::General, number as u16, 8, GPR8[number as usize]),
+        16 => Register::new(RegisterClass::General, number as u16, 16, GPR16[number as usize]),
+        32 => Register::new(RegisterClass::General, number as u16, 32, GPR32[number as usize]),
+        _ => Register::new(RegisterClass::General, number as u16, 64, GPR64[number as usize]),
+    }
+}
+
+pub fn segment(number: u8) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::Segment, number as u16, 16, SEGMENT[number as usize])
+}
+
+/// A vector register, `bits` is 128, 256 or 512
+pub fn vector(number: u8, bits: u16) -> Register {
+    let number = number & 0x1F;
+    let name = match bits {
+        512 => ZMM[number as usize],
+        256 => YMM[number as usize],
+        _ => XMM[number as usize],
+    };
+    Register::new(RegisterClass::Vector, number as u16, bits, name)
+}
+
+pub fn mmx(number: u8) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::Mmx, number as u16, 64, MMX[number as usize])
+}
+
+pub fn mask(number: u8) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::Mask, number as u16, 64, MASK[number as usize])
+}
+
+pub fn st(number: u8) -> Register {
+    let number = number & 7;
+    Register::new(RegisterClass::Float, number as u16, 80, ST[number as usize])
+}
+
+pub fn control(number: u8) -> Register {
+    let number = number & 0xF;
+    Register::new(RegisterClass::Control, number as u16, 64, CONTROL[number as usize])
+}
+
+pub fn debug(number: u8) -> Register {
+    let number = number & 0xF;
+    Register::new(RegisterClass::Debug, number as u16, 64, DEBUG[number as usize])
+}
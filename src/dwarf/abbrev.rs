@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use super::constants::DW_FORM_IMPLICIT_CONST;
+use super::{DwarfError, Reader};
+
+/// How an attribute of the DIEs using an abbreviation is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeSpec {
+    // DW_AT_*
+    pub name: u64,
+    // DW_FORM_*
+    pub form: u64,
+    // The value itself for DW_FORM_implicit_const, which has nothing in the DIE
+    pub implicit_const: i64,
+}
+
+/// The shape of the DIEs with a given code, from .debug_abbrev
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abbreviation {
+    pub code: u64,
+    // DW_TAG_*
+    pub tag: u64,
+    pub children: bool,
+    pub attributes: Vec<AttributeSpec>,
+}
+
+/// The abbreviation table at `offset` in .debug_abbrev, by code
+pub fn parse_abbreviations(reader: &Reader<'_>, offset: u64) -> Result<BTreeMap<u64, Abbreviation>, DwarfError> {
+    let mut reader = reader.clone();
+    reader.seek(offset as usize);
+    let mut res = BTreeMap::new();
+    loop {
+        let code = reader.uleb128()?;
+        if code == 0 {
+            return Ok(res);
+        }
+        let tag = reader.uleb128()?;
+        let children = reader.u8()? != 0;
+        let mut attributes = Vec::new();
+        loop {
+            let name = reader.uleb128()?;
+            let form = reader.uleb128()?;
+            if name == 0 && form == 0 {
+                break;
+            }
+            let implicit_const = if form == DW_FORM_IMPLICIT_CONST { reader.sleb128()? } else { 0 };
+            attributes.push(AttributeSpec { name, form, implicit_const });
+        }
+        res.insert(code, Abbreviation { code, tag, children, attributes });
+    }
+}
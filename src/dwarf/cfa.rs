@@ -0,0 +1,416 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::frame::{Cie, Fde, FrameTable};
+use super::{Bases, DwarfError, Reader};
+
+// Call frame instructions (DW_CFA_*). The first three keep their operand in the low 6 bits.
+const DW_CFA_ADVANCE_LOC: u8 = 0x40;
+const DW_CFA_OFFSET: u8 = 0x80;
+const DW_CFA_RESTORE: u8 = 0xc0;
+const DW_CFA_NOP: u8 = 0x00;
+const DW_CFA_SET_LOC: u8 = 0x01;
+const DW_CFA_ADVANCE_LOC1: u8 = 0x02;
+const DW_CFA_ADVANCE_LOC2: u8 = 0x03;
+const DW_CFA_ADVANCE_LOC4: u8 = 0x04;
+const DW_CFA_OFFSET_EXTENDED: u8 = 0x05;
+const DW_CFA_RESTORE_EXTENDED: u8 = 0x06;
+const DW_CFA_UNDEFINED: u8 = 0x07;
+const DW_CFA_SAME_VALUE: u8 = 0x08;
+const DW_CFA_REGISTER: u8 = 0x09;
+const DW_CFA_REMEMBER_STATE: u8 = 0x0a;
+const DW_CFA_RESTORE_STATE: u8 = 0x0b;
+const DW_CFA_DEF_CFA: u8 = 0x0c;
+const DW_CFA_DEF_CFA_REGISTER: u8 = 0x0d;
+const DW_CFA_DEF_CFA_OFFSET: u8 = 0x0e;
+const DW_CFA_DEF_CFA_EXPRESSION: u8 = 0x0f;
+const DW_CFA_EXPRESSION: u8 = 0x10;
+const DW_CFA_OFFSET_EXTENDED_SF: u8 = 0x11;
+const DW_CFA_DEF_CFA_SF: u8 = 0x12;
+const DW_CFA_DEF_CFA_OFFSET_SF: u8 = 0x13;
+const DW_CFA_VAL_OFFSET: u8 = 0x14;
+const DW_CFA_VAL_OFFSET_SF: u8 = 0x15;
+const DW_CFA_VAL_EXPRESSION: u8 = 0x16;
+// SPARC register window save, and the return address signing state of AArch64 pointer authentication
+const DW_CFA_GNU_WINDOW_SAVE: u8 = 0x2d;
+const DW_CFA_GNU_ARGS_SIZE: u8 = 0x2e;
+const DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED: u8 = 0x2f;
+
+/// How to compute the canonical frame address: the value of the stack pointer at the call that entered the frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfaRule {
+    RegisterOffset(u64, i64),
+    Expression(Vec<u8>),
+}
+
+/// Where the value a register had in the caller is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegisterRule {
+    // The value is lost
+    Undefined,
+    SameValue,
+    // Saved at CFA + offset
+    Offset(i64),
+    // Is CFA + offset
+    ValOffset(i64),
+    // In another register
+    Register(u64),
+    // Saved at the address the expression computes, with the CFA pushed first
+    Expression(Vec<u8>),
+    // Is what the expression computes
+    ValExpression(Vec<u8>),
+}
+
+/// The rules of the code from `start` up to `end`, a row of the table the call frame instructions describe.
+/// Registers without a rule keep their value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwindRow {
+    pub start: u64,
+    pub end: u64,
+    pub cfa: CfaRule,
+    pub registers: BTreeMap<u64, RegisterRule>,
+    // Bytes of arguments pushed on the stack for a call, from DW_CFA_GNU_args_size
+    pub args_size: u64,
+}
+
+impl UnwindRow {
+    /// How far the stack pointer is below the CFA, when the CFA is given relative to it
+    pub fn stack_height(&self, stack_pointer: u64) -> Option<i64> {
+        match self.cfa {
+            CfaRule::RegisterOffset(register, offset) if register == stack_pointer => Some(offset),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.start && address < self.end
+    }
+}
+
+impl fmt::Display for CfaRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfaRule::RegisterOffset(register, offset) => write!(f, "reg{}{:+}", register, offset),
+            CfaRule::Expression(x) => write!(f, "expression of {} bytes", x.len()),
+        }
+    }
+}
+
+impl fmt::Display for RegisterRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegisterRule::Undefined => write!(f, "undefined"),
+            RegisterRule::SameValue => write!(f, "same"),
+            RegisterRule::Offset(x) => write!(f, "[cfa{:+}]", x),
+            RegisterRule::ValOffset(x) => write!(f, "cfa{:+}", x),
+            RegisterRule::Register(x) => write!(f, "reg{}", x),
+            RegisterRule::Expression(x) => write!(f, "[expression of {} bytes]", x.len()),
+            RegisterRule::ValExpression(x) => write!(f, "expression of {} bytes", x.len()),
+        }
+    }
+}
+
+// The state of the instructions being run
+struct Machine<'a> {
+    cie: &'a Cie,
+    row: UnwindRow,
+    // The rules after the CIE instructions, which DW_CFA_restore goes back to
+    initial: Option<UnwindRow>,
+    remembered: Vec<UnwindRow>,
+    rows: Vec<UnwindRow>,
+}
+
+impl Machine<'_> {
+    fn run(&mut self, instructions: &[u8]) -> Result<(), DwarfError> {
+        let cie = self.cie;
+        let mut reader = Reader::new(instructions, cie.endianness, cie.address_size);
+        let code_alignment = cie.code_alignment;
+        let data_alignment = cie.data_alignment;
+        while !reader.is_at_end() {
+            let byte = reader.u8()?;
+            let operand = (byte & 0x3f) as u64;
+            match byte & 0xc0 {
+                DW_CFA_ADVANCE_LOC => {
+                    self.advance(self.row.start.wrapping_add(operand * code_alignment));
+                    continue;
+                }
+                DW_CFA_OFFSET => {
+                    let offset = reader.uleb128()? as i64 * data_alignment;
+                    self.row.registers.insert(operand, RegisterRule::Offset(offset));
+                    continue;
+                }
+                DW_CFA_RESTORE => {
+                    self.restore(operand);
+                    continue;
+                }
+                _ => {}
+            }
+            match byte {
+                DW_CFA_NOP | DW_CFA_GNU_WINDOW_SAVE => {}
+                // Only the format of the FDE encoding is known here, pointers relative to the instruction are off
+                DW_CFA_SET_LOC => {
+                    let address = reader.encoded_pointer(cie.fde_encoding & 0x0f, &Bases::default())?;
+                    self.advance(address);
+                }
+                DW_CFA_ADVANCE_LOC1 => {
+                    let delta = reader.u8()? as u64;
+                    self.advance(self.row.start.wrapping_add(delta * code_alignment));
+                }
+                DW_CFA_ADVANCE_LOC2 => {
+                    let delta = reader.u16()? as u64;
+                    self.advance(self.row.start.wrapping_add(delta * code_alignment));
+                }
+                DW_CFA_ADVANCE_LOC4 => {
+                    let delta = reader.u32()? as u64;
+                    self.advance(self.row.start.wrapping_add(delta * code_alignment));
+                }
+                DW_CFA_OFFSET_EXTENDED => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.uleb128()? as i64 * data_alignment;
+                    self.row.registers.insert(register, RegisterRule::Offset(offset));
+                }
+                DW_CFA_OFFSET_EXTENDED_SF => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.sleb128()? * data_alignment;
+                    self.row.registers.insert(register, RegisterRule::Offset(offset));
+                }
+                DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED => {
+                    let register = reader.uleb128()?;
+                    let offset = -(reader.uleb128()? as i64) * data_alignment;
+                    self.row.registers.insert(register, RegisterRule::Offset(offset));
+                }
+                DW_CFA_VAL_OFFSET => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.uleb128()? as i64 * data_alignment;
+                    self.row.registers.insert(register, RegisterRule::ValOffset(offset));
+                }
+                DW_CFA_VAL_OFFSET_SF => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.sleb128()? * data_alignment;
+                    self.row.registers.insert(register, RegisterRule::ValOffset(offset));
+                }
+                DW_CFA_RESTORE_EXTENDED => {
+                    let register = reader.uleb128()?;
+                    self.restore(register);
+                }
+                DW_CFA_UNDEFINED => {
+                    let register = reader.uleb128()?;
+                    self.row.registers.insert(register, RegisterRule::Undefined);
+                }
+                DW_CFA_SAME_VALUE => {
+                    let register = reader.uleb128()?;
+                    self.row.registers.insert(register, RegisterRule::SameValue);
+                }
+                DW_CFA_REGISTER => {
+                    let register = reader.uleb128()?;
+                    let other = reader.uleb128()?;
+                    self.row.registers.insert(register, RegisterRule::Register(other));
+                }
+                DW_CFA_EXPRESSION | DW_CFA_VAL_EXPRESSION => {
+                    let register = reader.uleb128()?;
+                    let length = reader.uleb128()? as usize;
+                    let bytes = reader.bytes(length)?.to_vec();
+                    let rule = match byte {
+                        DW_CFA_EXPRESSION => RegisterRule::Expression(bytes),
+                        _ => RegisterRule::ValExpression(bytes),
+                    };
+                    self.row.registers.insert(register, rule);
+                }
+                DW_CFA_REMEMBER_STATE => self.remembered.push(self.row.clone()),
+                DW_CFA_RESTORE_STATE => {
+                    // The location stays, only the rules come back
+                    if let Some(state) = self.remembered.pop() {
+                        self.row = UnwindRow { start: self.row.start, end: self.row.end, ..state };
+                    }
+                }
+                DW_CFA_DEF_CFA => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.uleb128()? as i64;
+                    self.row.cfa = CfaRule::RegisterOffset(register, offset);
+                }
+                DW_CFA_DEF_CFA_SF => {
+                    let register = reader.uleb128()?;
+                    let offset = reader.sleb128()? * data_alignment;
+                    self.row.cfa = CfaRule::RegisterOffset(register, offset);
+                }
+                DW_CFA_DEF_CFA_REGISTER => {
+                    let register = reader.uleb128()?;
+                    if let CfaRule::RegisterOffset(_, offset) = self.row.cfa {
+                        self.row.cfa = CfaRule::RegisterOffset(register, offset);
+                    }
+                }
+                DW_CFA_DEF_CFA_OFFSET => {
+                    let offset = reader.uleb128()? as i64;
+                    if let CfaRule::RegisterOffset(register, _) = self.row.cfa {
+                        self.row.cfa = CfaRule::RegisterOffset(register, offset);
+                    }
+                }
+                DW_CFA_DEF_CFA_OFFSET_SF => {
+                    let offset = reader.sleb128()? * data_alignment;
+                    if let CfaRule::RegisterOffset(register, _) = self.row.cfa {
+                        self.row.cfa = CfaRule::RegisterOffset(register, offset);
+                    }
+                }
+                DW_CFA_DEF_CFA_EXPRESSION => {
+                    let length = reader.uleb128()? as usize;
+                    self.row.cfa = CfaRule::Expression(reader.bytes(length)?.to_vec());
+                }
+                DW_CFA_GNU_ARGS_SIZE => self.row.args_size = reader.uleb128()?,
+                x => return Err(DwarfError::UnknownCfaInstruction(x)),
+            }
+        }
+        Ok(())
+    }
+
+    // Ends the current row at `address`. The CIE instructions have no code to cover.
+    fn advance(&mut self, address: u64) {
+        if self.initial.is_some() && address > self.row.start {
+            self.rows.push(UnwindRow { end: address, ..self.row.clone() });
+            self.row.start = address;
+        }
+    }
+
+    fn restore(&mut self, register: u64) {
+        match self.initial.as_ref().and_then(|x| x.registers.get(&register)) {
+            Some(rule) => {
+                self.row.registers.insert(register, rule.clone());
+            }
+            None => {
+                self.row.registers.remove(&register);
+            }
+        }
+    }
+}
+
+impl FrameTable {
+    /// The rows of the unwind table of an FDE, from its start to its end
+    pub fn rows(&self, fde: &Fde) -> Result<Vec<UnwindRow>, DwarfError> {
+        let cie = self.cies.get(&fde.cie).ok_or(DwarfError::InvalidCiePointer(fde.offset))?;
+        let row = UnwindRow {
+            start: fde.start,
+            end: fde.end(),
+            cfa: CfaRule::RegisterOffset(0, 0),
+            registers: BTreeMap::new(),
+            args_size: 0,
+        };
+        let mut machine = Machine { cie, row, initial: None, remembered: Vec::new(), rows: Vec::new() };
+        machine.run(&cie.instructions)?;
+        machine.initial = Some(machine.row.clone());
+        machine.run(&fde.instructions)?;
+        let mut rows = machine.rows;
+        if machine.row.start < fde.end() {
+            rows.push(UnwindRow { end: fde.end(), ..machine.row });
+        }
+        Ok(rows)
+    }
+
+    /// The rules at an address
+    pub fn row_at(&self, address: u64) -> Option<UnwindRow> {
+        let fde = self.fde_for(address)?;
+        self.rows(fde).ok()?.into_iter().find(|x| x.contains(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{DW_CFA_DEF_CFA, DW_CFA_DEF_CFA_OFFSET, DW_CFA_DEF_CFA_REGISTER, DW_CFA_REMEMBER_STATE, DW_CFA_RESTORE_STATE};
+    use crate::dwarf::{Bases, FrameKind, FrameTable, Frame, Reader, Unwinder};
+    use crate::endian::Endianness;
+
+    const RBP: u8 = 6;
+    const RSP: u8 = 7;
+    const RA: u8 = 16;
+
+    // The .eh_frame gcc emits for a function at 0x1000 that keeps rbp as its frame pointer:
+    //
+    //     1000: push rbp
+    //     1001: mov rbp, rsp
+    //     1004: ...
+    //     1013: pop rbp
+    //     1014: ret
+    fn eh_frame() -> Vec<u8> {
+        let mut section = vec![0x14, 0, 0, 0, 0, 0, 0, 0, 1, b'z', b'R', 0, 1, 0x78, RA, 1, 0x1b];
+        section.extend_from_slice(&[DW_CFA_DEF_CFA, RSP, 8, 0x80 | RA, 1, 0, 0]);
+        // The FDE at 0x18, whose start is relative to where it is read from, 0x2020 in the section at 0x2000
+        section.extend_from_slice(&[0x1c, 0, 0, 0, 0x1c, 0, 0, 0]);
+        section.extend_from_slice(&(0x1000i32 - 0x2020).to_le_bytes());
+        section.extend_from_slice(&0x15u32.to_le_bytes());
+        section.push(0);
+        section.extend_from_slice(&[0x41, DW_CFA_DEF_CFA_OFFSET, 16, 0x80 | RBP, 2]);
+        section.extend_from_slice(&[0x43, DW_CFA_DEF_CFA_REGISTER, RBP]);
+        section.extend_from_slice(&[0x50, DW_CFA_DEF_CFA, RSP, 8, 0, 0, 0]);
+        section.extend_from_slice(&[0, 0, 0, 0]);
+        section
+    }
+
+    fn parse(section: &[u8], kind: FrameKind, address: u64) -> FrameTable {
+        let reader = Reader::new(section, Endianness::LittleEndian, 8);
+        FrameTable::parse(&reader, kind, &Bases { section: address, ..Bases::default() }).unwrap()
+    }
+
+    // A row as its range, CFA rule and register rules
+    fn rows(table: &FrameTable) -> Vec<String> {
+        let rows = table.rows(&table.fdes[0]).unwrap();
+        rows.iter()
+            .map(|row| {
+                let registers: Vec<String> = row.registers.iter().map(|(number, rule)| format!("r{}={}", number, rule)).collect();
+                format!("{:x}-{:x} cfa={} {}", row.start, row.end, row.cfa, registers.join(" ")).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn eh_frame_rows() {
+        let table = parse(&eh_frame(), FrameKind::EhFrame, 0x2000);
+        assert_eq!(table.fdes.len(), 1);
+        assert_eq!((table.fdes[0].start, table.fdes[0].end()), (0x1000, 0x1015));
+        assert_eq!(
+            rows(&table),
+            [
+                "1000-1001 cfa=reg7+8 r16=[cfa-8]",
+                "1001-1004 cfa=reg7+16 r6=[cfa-16] r16=[cfa-8]",
+                "1004-1014 cfa=reg6+16 r6=[cfa-16] r16=[cfa-8]",
+                "1014-1015 cfa=reg7+8 r6=[cfa-16] r16=[cfa-8]",
+            ]
+        );
+        assert_eq!(table.row_at(0x1002).and_then(|x| x.stack_height(RSP as u64)), Some(16));
+        assert_eq!(table.row_at(0x1010).and_then(|x| x.stack_height(RSP as u64)), None);
+        assert!(table.row_at(0x1015).is_none());
+    }
+
+    #[test]
+    fn debug_frame_remember_and_restore() {
+        // A version 1 CIE, whose id is all ones, and an FDE with absolute addresses
+        let mut section = vec![0x0c, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 1, 0, 1, 0x78, RA];
+        section.extend_from_slice(&[DW_CFA_DEF_CFA, RSP, 8]);
+        section.extend_from_slice(&[0x20, 0, 0, 0, 0, 0, 0, 0]);
+        section.extend_from_slice(&0x1000u64.to_le_bytes());
+        section.extend_from_slice(&0x10u64.to_le_bytes());
+        section.extend_from_slice(&[0x41, DW_CFA_DEF_CFA_OFFSET, 16, DW_CFA_REMEMBER_STATE]);
+        section.extend_from_slice(&[0x41, DW_CFA_DEF_CFA_OFFSET, 8, 0x41, DW_CFA_RESTORE_STATE]);
+        section.extend_from_slice(&[0, 0, 0]);
+
+        let table = parse(&section, FrameKind::DebugFrame, 0);
+        assert_eq!(
+            rows(&table),
+            ["1000-1001 cfa=reg7+8", "1001-1002 cfa=reg7+16", "1002-1003 cfa=reg7+8", "1003-1010 cfa=reg7+16"]
+        );
+    }
+
+    #[test]
+    fn unwind_through_the_frame_pointer() {
+        let table = parse(&eh_frame(), FrameKind::EhFrame, 0x2000);
+        let unwinder = Unwinder::new(vec![&table], RSP as u64, Endianness::LittleEndian, 8);
+        // The saved rbp and the return address
+        let stack: BTreeMap<u64, u64> = [(0x7ff0, 0x8010), (0x7ff8, 0x4242)].iter().copied().collect();
+        let memory = |address: u64, _size: u8| stack.get(&address).copied();
+
+        let registers: BTreeMap<u64, u64> = [(RBP as u64, 0x7ff0), (RSP as u64, 0x7fe0)].iter().copied().collect();
+        let frames = unwinder.backtrace(0x1008, &registers, &memory, 8);
+        assert_eq!(frames.len(), 2);
+        let caller: BTreeMap<u64, u64> = [(RBP as u64, 0x8010), (RSP as u64, 0x8000), (RA as u64, 0x4242)].iter().copied().collect();
+        assert_eq!(frames[1], Frame { pc: 0x4242, registers: caller });
+    }
+}
@@ -0,0 +1,185 @@
+// The numbers of the DWARF standard that .debug_info, .debug_line and the lists it refers to are made of
+
+// Tags of the debugging information entries (DW_TAG_*)
+pub const DW_TAG_ARRAY_TYPE: u64 = 0x01;
+pub const DW_TAG_CLASS_TYPE: u64 = 0x02;
+pub const DW_TAG_ENUMERATION_TYPE: u64 = 0x04;
+pub const DW_TAG_FORMAL_PARAMETER: u64 = 0x05;
+pub const DW_TAG_LEXICAL_BLOCK: u64 = 0x0b;
+pub const DW_TAG_MEMBER: u64 = 0x0d;
+pub const DW_TAG_POINTER_TYPE: u64 = 0x0f;
+pub const DW_TAG_REFERENCE_TYPE: u64 = 0x10;
+pub const DW_TAG_COMPILE_UNIT: u64 = 0x11;
+pub const DW_TAG_STRUCTURE_TYPE: u64 = 0x13;
+pub const DW_TAG_SUBROUTINE_TYPE: u64 = 0x15;
+pub const DW_TAG_TYPEDEF: u64 = 0x16;
+pub const DW_TAG_UNION_TYPE: u64 = 0x17;
+pub const DW_TAG_UNSPECIFIED_PARAMETERS: u64 = 0x18;
+pub const DW_TAG_PTR_TO_MEMBER_TYPE: u64 = 0x1f;
+pub const DW_TAG_SUBRANGE_TYPE: u64 = 0x21;
+pub const DW_TAG_BASE_TYPE: u64 = 0x24;
+pub const DW_TAG_CONST_TYPE: u64 = 0x26;
+pub const DW_TAG_ENUMERATOR: u64 = 0x28;
+pub const DW_TAG_SUBPROGRAM: u64 = 0x2e;
+pub const DW_TAG_VARIABLE: u64 = 0x34;
+pub const DW_TAG_VOLATILE_TYPE: u64 = 0x35;
+pub const DW_TAG_RESTRICT_TYPE: u64 = 0x37;
+pub const DW_TAG_NAMESPACE: u64 = 0x39;
+pub const DW_TAG_UNSPECIFIED_TYPE: u64 = 0x3b;
+pub const DW_TAG_PARTIAL_UNIT: u64 = 0x3c;
+pub const DW_TAG_RVALUE_REFERENCE_TYPE: u64 = 0x42;
+pub const DW_TAG_ATOMIC_TYPE: u64 = 0x47;
+pub const DW_TAG_SKELETON_UNIT: u64 = 0x4a;
+
+// Attributes (DW_AT_*)
+pub const DW_AT_LOCATION: u64 = 0x02;
+pub const DW_AT_NAME: u64 = 0x03;
+pub const DW_AT_BYTE_SIZE: u64 = 0x0b;
+pub const DW_AT_BIT_SIZE: u64 = 0x0d;
+pub const DW_AT_STMT_LIST: u64 = 0x10;
+pub const DW_AT_LOW_PC: u64 = 0x11;
+pub const DW_AT_HIGH_PC: u64 = 0x12;
+pub const DW_AT_COMP_DIR: u64 = 0x1b;
+pub const DW_AT_CONST_VALUE: u64 = 0x1c;
+pub const DW_AT_UPPER_BOUND: u64 = 0x2f;
+pub const DW_AT_ABSTRACT_ORIGIN: u64 = 0x31;
+pub const DW_AT_COUNT: u64 = 0x37;
+pub const DW_AT_DATA_MEMBER_LOCATION: u64 = 0x38;
+pub const DW_AT_DECL_FILE: u64 = 0x3a;
+pub const DW_AT_DECL_LINE: u64 = 0x3b;
+pub const DW_AT_DECLARATION: u64 = 0x3c;
+pub const DW_AT_ENCODING: u64 = 0x3e;
+pub const DW_AT_EXTERNAL: u64 = 0x3f;
+pub const DW_AT_FRAME_BASE: u64 = 0x40;
+pub const DW_AT_SPECIFICATION: u64 = 0x47;
+pub const DW_AT_TYPE: u64 = 0x49;
+pub const DW_AT_RANGES: u64 = 0x55;
+pub const DW_AT_DATA_BIT_OFFSET: u64 = 0x6b;
+pub const DW_AT_LINKAGE_NAME: u64 = 0x6e;
+pub const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+pub const DW_AT_ADDR_BASE: u64 = 0x73;
+pub const DW_AT_RNGLISTS_BASE: u64 = 0x74;
+pub const DW_AT_LOCLISTS_BASE: u64 = 0x8c;
+pub const DW_AT_MIPS_LINKAGE_NAME: u64 = 0x2007;
+pub const DW_AT_GNU_ADDR_BASE: u64 = 0x2133;
+
+// Forms of the attribute values (DW_FORM_*)
+pub const DW_FORM_ADDR: u64 = 0x01;
+pub const DW_FORM_BLOCK2: u64 = 0x03;
+pub const DW_FORM_BLOCK4: u64 = 0x04;
+pub const DW_FORM_DATA2: u64 = 0x05;
+pub const DW_FORM_DATA4: u64 = 0x06;
+pub const DW_FORM_DATA8: u64 = 0x07;
+pub const DW_FORM_STRING: u64 = 0x08;
+pub const DW_FORM_BLOCK: u64 = 0x09;
+pub const DW_FORM_BLOCK1: u64 = 0x0a;
+pub const DW_FORM_DATA1: u64 = 0x0b;
+pub const DW_FORM_FLAG: u64 = 0x0c;
+pub const DW_FORM_SDATA: u64 = 0x0d;
+pub const DW_FORM_STRP: u64 = 0x0e;
+pub const DW_FORM_UDATA: u64 = 0x0f;
+pub const DW_FORM_REF_ADDR: u64 = 0x10;
+pub const DW_FORM_REF1: u64 = 0x11;
+pub const DW_FORM_REF2: u64 = 0x12;
+pub const DW_FORM_REF4: u64 = 0x13;
+pub const DW_FORM_REF8: u64 = 0x14;
+pub const DW_FORM_REF_UDATA: u64 = 0x15;
+pub const DW_FORM_INDIRECT: u64 = 0x16;
+pub const DW_FORM_SEC_OFFSET: u64 = 0x17;
+pub const DW_FORM_EXPRLOC: u64 = 0x18;
+pub const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+pub const DW_FORM_STRX: u64 = 0x1a;
+pub const DW_FORM_ADDRX: u64 = 0x1b;
+pub const DW_FORM_REF_SUP4: u64 = 0x1c;
+pub const DW_FORM_STRP_SUP: u64 = 0x1d;
+pub const DW_FORM_DATA16: u64 = 0x1e;
+pub const DW_FORM_LINE_STRP: u64 = 0x1f;
+pub const DW_FORM_REF_SIG8: u64 = 0x20;
+pub const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+pub const DW_FORM_LOCLISTX: u64 = 0x22;
+pub const DW_FORM_RNGLISTX: u64 = 0x23;
+pub const DW_FORM_REF_SUP8: u64 = 0x24;
+pub const DW_FORM_STRX1: u64 = 0x25;
+pub const DW_FORM_STRX2: u64 = 0x26;
+pub const DW_FORM_STRX3: u64 = 0x27;
+pub const DW_FORM_STRX4: u64 = 0x28;
+pub const DW_FORM_ADDRX1: u64 = 0x29;
+pub const DW_FORM_ADDRX2: u64 = 0x2a;
+pub const DW_FORM_ADDRX3: u64 = 0x2b;
+pub const DW_FORM_ADDRX4: u64 = 0x2c;
+pub const DW_FORM_GNU_ADDR_INDEX: u64 = 0x1f01;
+pub const DW_FORM_GNU_STR_INDEX: u64 = 0x1f02;
+pub const DW_FORM_GNU_REF_ALT: u64 = 0x1f20;
+pub const DW_FORM_GNU_STRP_ALT: u64 = 0x1f21;
+
+// Encodings of base types (DW_ATE_*)
+pub const DW_ATE_BOOLEAN: u64 = 0x02;
+pub const DW_ATE_FLOAT: u64 = 0x04;
+pub const DW_ATE_SIGNED: u64 = 0x05;
+pub const DW_ATE_SIGNED_CHAR: u64 = 0x06;
+pub const DW_ATE_UNSIGNED: u64 = 0x07;
+pub const DW_ATE_UNSIGNED_CHAR: u64 = 0x08;
+
+// Unit types of DWARF 5 unit headers (DW_UT_*)
+pub const DW_UT_TYPE: u8 = 0x02;
+pub const DW_UT_SKELETON: u8 = 0x04;
+pub const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+pub const DW_UT_SPLIT_TYPE: u8 = 0x06;
+
+// Entries of the range lists of DWARF 5 (DW_RLE_*)
+pub const DW_RLE_END_OF_LIST: u8 = 0x00;
+pub const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_RLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+pub const DW_RLE_START_END: u8 = 0x06;
+pub const DW_RLE_START_LENGTH: u8 = 0x07;
+
+// Entries of the location lists of DWARF 5 (DW_LLE_*)
+pub const DW_LLE_END_OF_LIST: u8 = 0x00;
+pub const DW_LLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_LLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_LLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_LLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_LLE_DEFAULT_LOCATION: u8 = 0x05;
+pub const DW_LLE_BASE_ADDRESS: u8 = 0x06;
+pub const DW_LLE_START_END: u8 = 0x07;
+pub const DW_LLE_START_LENGTH: u8 = 0x08;
+pub const DW_LLE_GNU_VIEW_PAIR: u8 = 0x09;
+
+// Operations of location expressions (DW_OP_*)
+pub const DW_OP_ADDR: u8 = 0x03;
+pub const DW_OP_REG0: u8 = 0x50;
+pub const DW_OP_REG31: u8 = 0x6f;
+pub const DW_OP_BREG0: u8 = 0x70;
+pub const DW_OP_BREG31: u8 = 0x8f;
+pub const DW_OP_REGX: u8 = 0x90;
+pub const DW_OP_FBREG: u8 = 0x91;
+pub const DW_OP_BREGX: u8 = 0x92;
+pub const DW_OP_CALL_FRAME_CFA: u8 = 0x9c;
+pub const DW_OP_ADDRX: u8 = 0xa1;
+pub const DW_OP_GNU_ADDR_INDEX: u8 = 0xfb;
+
+// Standard opcodes of the line number program (DW_LNS_*)
+pub const DW_LNS_COPY: u8 = 0x01;
+pub const DW_LNS_ADVANCE_PC: u8 = 0x02;
+pub const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+pub const DW_LNS_SET_FILE: u8 = 0x04;
+pub const DW_LNS_SET_COLUMN: u8 = 0x05;
+pub const DW_LNS_NEGATE_STMT: u8 = 0x06;
+pub const DW_LNS_SET_BASIC_BLOCK: u8 = 0x07;
+pub const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+pub const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+pub const DW_LNS_SET_PROLOGUE_END: u8 = 0x0a;
+pub const DW_LNS_SET_EPILOGUE_BEGIN: u8 = 0x0b;
+pub const DW_LNS_SET_ISA: u8 = 0x0c;
+
+// Extended opcodes of the line number program (DW_LNE_*)
+pub const DW_LNE_END_SEQUENCE: u8 = 0x01;
+pub const DW_LNE_SET_ADDRESS: u8 = 0x02;
+pub const DW_LNE_DEFINE_FILE: u8 = 0x03;
+
+// What the entries of the DWARF 5 directory and file tables hold (DW_LNCT_*)
+pub const DW_LNCT_PATH: u64 = 0x1;
+pub const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
@@ -0,0 +1,692 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::constants::*;
+use super::info::{parse_units, AttributeValue, DebugSections, Die, Location, Unit};
+use super::line::LineProgram;
+use super::{DwarfError, Reader};
+
+/// A function with code, from a DW_TAG_subprogram
+#[derive(Debug, Clone, Default)]
+pub struct FunctionRecord {
+    // Offset of the DIE
+    pub offset: u64,
+    pub name: Option<String>,
+    // The mangled name, for languages that have one
+    pub linkage_name: Option<String>,
+    pub ranges: Vec<(u64, u64)>,
+    // Offset of the type DIE, None for void
+    pub return_type: Option<u64>,
+    pub parameters: Vec<VariableRecord>,
+    // Also takes a variable number of arguments
+    pub variadic: bool,
+    // Locals of the function and of the blocks in it
+    pub variables: Vec<VariableRecord>,
+    // What DW_OP_fbreg locations are relative to
+    pub frame_base: Option<Location>,
+    pub external: bool,
+    pub file: Option<String>,
+    pub line: Option<u64>,
+}
+
+impl FunctionRecord {
+    /// The lowest address of the function, where it usually starts
+    pub fn start(&self) -> Option<u64> {
+        self.ranges.iter().map(|x| x.0).min()
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        self.ranges.iter().any(|x| address >= x.0 && address < x.1)
+    }
+}
+
+/// A variable or parameter
+#[derive(Debug, Clone, Default)]
+pub struct VariableRecord {
+    pub offset: u64,
+    pub name: Option<String>,
+    pub ty: Option<u64>,
+    pub location: Option<Location>,
+    pub external: bool,
+    pub file: Option<String>,
+    pub line: Option<u64>,
+}
+
+/// A member of a structure or union, at a byte offset or a bit offset for bit fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: Option<String>,
+    pub ty: Option<u64>,
+    pub offset: u64,
+    pub bit_offset: Option<u64>,
+    pub bit_size: Option<u64>,
+}
+
+/// What a type is. References to other types are offsets of their DIEs, None for void.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeKind {
+    // DW_ATE_* encoding
+    Base(u64),
+    Pointer(Option<u64>),
+    Reference(Option<u64>),
+    Const(Option<u64>),
+    Volatile(Option<u64>),
+    Restrict(Option<u64>),
+    Atomic(Option<u64>),
+    Typedef(Option<u64>),
+    Struct(Vec<Member>),
+    Class(Vec<Member>),
+    Union(Vec<Member>),
+    Enum(Vec<(String, i64)>),
+    // The element type and the number of elements of each dimension, None when unknown
+    Array(Option<u64>, Vec<Option<u64>>),
+    Function { returns: Option<u64>, parameters: Vec<Option<u64>>, variadic: bool },
+    // void, decltype(nullptr) and types this module doesn't know
+    Unspecified,
+}
+
+/// A type of the program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeRecord {
+    pub offset: u64,
+    pub name: Option<String>,
+    pub size: Option<u64>,
+    pub kind: TypeKind,
+}
+
+/// What .debug_info and .debug_line say about a program
+#[derive(Debug, Clone, Default)]
+pub struct DebugInfo {
+    pub units: Vec<Unit>,
+    // The programs of the units, in their order
+    pub lines: Vec<LineProgram>,
+    pub functions: Vec<FunctionRecord>,
+    // Globals and static variables
+    pub variables: Vec<VariableRecord>,
+    // By offset of their DIE
+    pub types: BTreeMap<u64, TypeRecord>,
+    // Unit and DIE index of each DIE offset
+    index: HashMap<u64, (usize, usize)>,
+}
+
+impl DebugInfo {
+    /// Parses the units of .debug_info, their line programs and the records of their DIEs. The reader gives the
+    /// byte order and the address size to use for units that don't give theirs.
+    pub fn parse(sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<DebugInfo, DwarfError> {
+        let reader = Reader::new(sections.info, reader.endianness, reader.address_size);
+        let mut info = DebugInfo { units: parse_units(sections, &reader)?, ..DebugInfo::default() };
+        for (i, unit) in info.units.iter().enumerate() {
+            for (j, die) in unit.dies.iter().enumerate() {
+                info.index.insert(die.offset, (i, j));
+            }
+        }
+
+        let mut lines = Vec::new();
+        for unit in info.units.iter() {
+            let root = match unit.root() {
+                Some(x) => x,
+                None => continue,
+            };
+            // Type units share the program of their compilation unit
+            let offset = match root.attribute(DW_AT_STMT_LIST).and_then(|x| x.unsigned()) {
+                Some(x) if !lines.iter().any(|y: &LineProgram| y.offset == x) => x,
+                _ => continue,
+            };
+            let comp_dir = root.attribute(DW_AT_COMP_DIR).and_then(|x| x.string());
+            if let Ok(program) = LineProgram::parse(sections, &reader, offset, comp_dir, root.name()) {
+                lines.push(program);
+            }
+        }
+        info.lines = lines;
+
+        let mut functions = Vec::new();
+        let mut variables = Vec::new();
+        let mut types = BTreeMap::new();
+        for unit in info.units.iter() {
+            let mut reader = reader.clone();
+            reader.address_size = unit.address_size;
+            let program = unit
+                .root()
+                .and_then(|x| x.attribute(DW_AT_STMT_LIST))
+                .and_then(|x| x.unsigned())
+                .and_then(|x| info.lines.iter().find(|y| y.offset == x));
+            let records = Records { info: &info, unit, sections, reader: &reader, program };
+            for die in unit.dies.iter() {
+                match die.tag {
+                    DW_TAG_SUBPROGRAM => {
+                        if let Some(x) = records.function(die) {
+                            functions.push(x);
+                        }
+                    }
+                    DW_TAG_VARIABLE if records.is_global(die) => {
+                        if let Some(x) = records.variable(die) {
+                            variables.push(x);
+                        }
+                    }
+                    _ => {
+                        if let Some(x) = records.ty(die) {
+                            types.insert(die.offset, x);
+                        }
+                    }
+                }
+            }
+        }
+        functions.sort_by_key(|x| x.start());
+        info.functions = functions;
+        info.variables = variables;
+        info.types = types;
+        Ok(info)
+    }
+
+    /// The DIE at an offset of .debug_info
+    pub fn die(&self, offset: u64) -> Option<&Die> {
+        let (unit, die) = *self.index.get(&offset)?;
+        Some(&self.units[unit].dies[die])
+    }
+
+    /// The function whose code holds an address
+    pub fn function_at(&self, address: u64) -> Option<&FunctionRecord> {
+        self.functions.iter().find(|x| x.contains(address))
+    }
+
+    /// The source file and line of the code at an address
+    pub fn line_for(&self, address: u64) -> Option<(String, u64)> {
+        self.lines.iter().find_map(|program| {
+            let row = program.row_for(address)?;
+            Some((program.file(row.file)?, row.line))
+        })
+    }
+
+    /// A type as C writes it
+    pub fn type_name(&self, ty: Option<u64>) -> String {
+        self.declaration(ty, "")
+    }
+
+    /// The C declaration of `name` with a type, such as `char *argv[]`
+    pub fn declaration(&self, ty: Option<u64>, name: &str) -> String {
+        self.declarator(ty, name.to_string(), 0).trim().to_string()
+    }
+
+    // Wraps the declarator `inner` in the type, from the outside in as C reads it
+    fn declarator(&self, ty: Option<u64>, inner: String, depth: usize) -> String {
+        let record = match ty.and_then(|x| self.types.get(&x)) {
+            Some(x) if depth < 32 => x,
+            Some(_) => return format!("... {}", inner),
+            None if ty.is_some() => return format!("? {}", inner),
+            None => return format!("void {}", inner),
+        };
+        // Pointers to arrays and functions need parentheses
+        let wrapped = |inner: String, target: Option<u64>| match target.and_then(|x| self.types.get(&x)).map(|x| &x.kind) {
+            Some(TypeKind::Array(..)) | Some(TypeKind::Function { .. }) => format!("({})", inner),
+            _ => inner,
+        };
+        match &record.kind {
+            TypeKind::Pointer(x) => self.declarator(*x, wrapped(format!("*{}", inner), *x), depth + 1),
+            TypeKind::Reference(x) => self.declarator(*x, wrapped(format!("&{}", inner), *x), depth + 1),
+            TypeKind::Const(x) | TypeKind::Volatile(x) | TypeKind::Restrict(x) | TypeKind::Atomic(x) => {
+                let qualifier = match record.kind {
+                    TypeKind::Const(_) => "const",
+                    TypeKind::Volatile(_) => "volatile",
+                    TypeKind::Restrict(_) => "restrict",
+                    _ => "_Atomic",
+                };
+                // Qualified pointers put the qualifier after the *, other types before the type
+                match x.and_then(|x| self.types.get(&x)).map(|x| &x.kind) {
+                    Some(TypeKind::Pointer(_)) | Some(TypeKind::Reference(_)) => {
+                        self.declarator(*x, format!("{} {}", qualifier, inner), depth + 1)
+                    }
+                    _ => format!("{} {}", qualifier, self.declarator(*x, inner, depth + 1)),
+                }
+            }
+            TypeKind::Array(element, counts) => {
+                let mut inner = inner;
+                for count in counts.iter() {
+                    match count {
+                        Some(x) => inner.push_str(&format!("[{}]", x)),
+                        None => inner.push_str("[]"),
+                    }
+                }
+                self.declarator(*element, inner, depth + 1)
+            }
+            TypeKind::Function { returns, parameters, variadic } => {
+                let mut parameters: Vec<String> = parameters.iter().map(|x| self.declaration(*x, "")).collect();
+                if *variadic {
+                    parameters.push("...".to_string());
+                }
+                if parameters.is_empty() {
+                    parameters.push("void".to_string());
+                }
+                self.declarator(*returns, format!("{}({})", inner, parameters.join(", ")), depth + 1)
+            }
+            _ => {
+                let name = self.plain_name(record);
+                format!("{} {}", name, inner)
+            }
+        }
+    }
+
+    // The name of a type that isn't built from another
+    fn plain_name(&self, record: &TypeRecord) -> String {
+        let name = record.name.as_deref().unwrap_or("<anonymous>");
+        match record.kind {
+            TypeKind::Struct(_) => format!("struct {}", name),
+            TypeKind::Union(_) => format!("union {}", name),
+            TypeKind::Enum(_) => format!("enum {}", name),
+            TypeKind::Unspecified if record.name.is_none() => "void".to_string(),
+            _ => name.to_string(),
+        }
+    }
+
+    /// The C prototype of a function, such as `int main(int argc, char **argv)`
+    pub fn prototype(&self, function: &FunctionRecord) -> String {
+        let mut parameters: Vec<String> =
+            function.parameters.iter().map(|x| self.declaration(x.ty, x.name.as_deref().unwrap_or(""))).collect();
+        if function.variadic {
+            parameters.push("...".to_string());
+        }
+        if parameters.is_empty() {
+            parameters.push("void".to_string());
+        }
+        let name = function.name.as_deref().unwrap_or("?");
+        self.declaration(function.return_type, &format!("{}({})", name, parameters.join(", ")))
+    }
+}
+
+// Builds the records of the DIEs of a unit
+struct Records<'a> {
+    info: &'a DebugInfo,
+    unit: &'a Unit,
+    sections: &'a DebugSections<'a>,
+    reader: &'a Reader<'a>,
+    program: Option<&'a LineProgram>,
+}
+
+impl Records<'_> {
+    // An attribute of the DIE or of the DIEs it completes: the declaration of a definition, or the abstract instance
+    // of an inlined or out-of-line copy
+    fn attribute<'b>(&'b self, die: &'b Die, name: u64) -> Option<&'b AttributeValue> {
+        let mut die = die;
+        for _ in 0..8 {
+            if let Some(x) = die.attribute(name) {
+                return Some(x);
+            }
+            let origin = die.reference(DW_AT_SPECIFICATION).or_else(|| die.reference(DW_AT_ABSTRACT_ORIGIN))?;
+            die = self.info.die(origin)?;
+        }
+        None
+    }
+
+    fn string(&self, die: &Die, name: u64) -> Option<String> {
+        self.attribute(die, name).and_then(|x| x.string()).map(|x| x.to_string())
+    }
+
+    fn reference(&self, die: &Die, name: u64) -> Option<u64> {
+        match self.attribute(die, name) {
+            Some(AttributeValue::Reference(x)) => Some(*x),
+            _ => None,
+        }
+    }
+
+    fn unsigned(&self, die: &Die, name: u64) -> Option<u64> {
+        self.attribute(die, name).and_then(|x| x.unsigned())
+    }
+
+    fn flag(&self, die: &Die, name: u64) -> bool {
+        self.unsigned(die, name).unwrap_or(0) != 0
+    }
+
+    fn children<'b>(&'b self, die: &'b Die) -> impl Iterator<Item = &'b Die> {
+        die.children.iter().map(move |x| &self.unit.dies[*x])
+    }
+
+    // The source file and line of the declaration. Files are numbered in the line program of the unit.
+    fn declared(&self, die: &Die) -> (Option<String>, Option<u64>) {
+        let file = self.unsigned(die, DW_AT_DECL_FILE).and_then(|x| self.program?.file(x));
+        (file, self.unsigned(die, DW_AT_DECL_LINE))
+    }
+
+    fn location(&self, die: &Die, name: u64) -> Option<Location> {
+        let value = die.attribute(name)?;
+        self.unit.location(value, self.sections, self.reader)
+    }
+
+    fn function(&self, die: &Die) -> Option<FunctionRecord> {
+        // Declarations and abstract instances of inline functions have no code
+        let ranges = self.unit.ranges(die, self.sections, self.reader);
+        if ranges.is_empty() {
+            return None;
+        }
+        let (file, line) = self.declared(die);
+        let mut res = FunctionRecord {
+            offset: die.offset,
+            name: self.string(die, DW_AT_NAME),
+            linkage_name: self.string(die, DW_AT_LINKAGE_NAME).or_else(|| self.string(die, DW_AT_MIPS_LINKAGE_NAME)),
+            ranges,
+            return_type: self.reference(die, DW_AT_TYPE),
+            frame_base: self.location(die, DW_AT_FRAME_BASE),
+            external: self.flag(die, DW_AT_EXTERNAL),
+            file,
+            line,
+            ..FunctionRecord::default()
+        };
+        // The locals can be in nested blocks. Inlined calls have their own, which are not the function's.
+        let mut work: Vec<&Die> = self.children(die).collect();
+        while let Some(child) = work.pop() {
+            match child.tag {
+                DW_TAG_FORMAL_PARAMETER => res.parameters.extend(self.variable(child)),
+                DW_TAG_UNSPECIFIED_PARAMETERS => res.variadic = true,
+                DW_TAG_VARIABLE => res.variables.extend(self.variable(child)),
+                DW_TAG_LEXICAL_BLOCK => work.extend(self.children(child)),
+                _ => {}
+            }
+        }
+        res.parameters.sort_by_key(|x| x.offset);
+        res.variables.sort_by_key(|x| x.offset);
+        Some(res)
+    }
+
+    // A variable at the top of the unit or a namespace, and statics of functions
+    fn is_global(&self, die: &Die) -> bool {
+        if die.attribute(DW_AT_LOCATION).is_none() {
+            return false;
+        }
+        match die.parent.map(|x| self.unit.dies[x].tag) {
+            Some(DW_TAG_COMPILE_UNIT) | Some(DW_TAG_PARTIAL_UNIT) | Some(DW_TAG_SKELETON_UNIT) | Some(DW_TAG_NAMESPACE) => true,
+            _ => matches!(self.location(die, DW_AT_LOCATION), Some(Location::Address(_))),
+        }
+    }
+
+    fn variable(&self, die: &Die) -> Option<VariableRecord> {
+        if die.flag(DW_AT_DECLARATION) {
+            return None;
+        }
+        let (file, line) = self.declared(die);
+        Some(VariableRecord {
+            offset: die.offset,
+            name: self.string(die, DW_AT_NAME),
+            ty: self.reference(die, DW_AT_TYPE),
+            location: self.location(die, DW_AT_LOCATION),
+            external: self.flag(die, DW_AT_EXTERNAL),
+            file,
+            line,
+        })
+    }
+
+    fn ty(&self, die: &Die) -> Option<TypeRecord> {
+        let target = die.reference(DW_AT_TYPE);
+        let kind = match die.tag {
+            DW_TAG_BASE_TYPE => TypeKind::Base(die.attribute(DW_AT_ENCODING).and_then(|x| x.unsigned()).unwrap_or(0)),
+            DW_TAG_POINTER_TYPE | DW_TAG_PTR_TO_MEMBER_TYPE => TypeKind::Pointer(target),
+            DW_TAG_REFERENCE_TYPE | DW_TAG_RVALUE_REFERENCE_TYPE => TypeKind::Reference(target),
+            DW_TAG_CONST_TYPE => TypeKind::Const(target),
+            DW_TAG_VOLATILE_TYPE => TypeKind::Volatile(target),
+            DW_TAG_RESTRICT_TYPE => TypeKind::Restrict(target),
+            DW_TAG_ATOMIC_TYPE => TypeKind::Atomic(target),
+            DW_TAG_TYPEDEF => TypeKind::Typedef(target),
+            DW_TAG_STRUCTURE_TYPE => TypeKind::Struct(self.members(die)),
+            DW_TAG_CLASS_TYPE => TypeKind::Class(self.members(die)),
+            DW_TAG_UNION_TYPE => TypeKind::Union(self.members(die)),
+            DW_TAG_ENUMERATION_TYPE => TypeKind::Enum(
+                self.children(die)
+                    .filter(|x| x.tag == DW_TAG_ENUMERATOR)
+                    .map(|x| {
+                        let value = match x.attribute(DW_AT_CONST_VALUE) {
+                            Some(AttributeValue::Signed(x)) => *x,
+                            Some(x) => x.unsigned().unwrap_or(0) as i64,
+                            None => 0,
+                        };
+                        (x.name().unwrap_or("").to_string(), value)
+                    })
+                    .collect(),
+            ),
+            DW_TAG_ARRAY_TYPE => {
+                let counts = self.children(die).filter(|x| x.tag == DW_TAG_SUBRANGE_TYPE).map(|x| self.count(x)).collect();
+                TypeKind::Array(target, counts)
+            }
+            DW_TAG_SUBROUTINE_TYPE => TypeKind::Function {
+                returns: target,
+                parameters: self.children(die).filter(|x| x.tag == DW_TAG_FORMAL_PARAMETER).map(|x| x.reference(DW_AT_TYPE)).collect(),
+                variadic: self.children(die).any(|x| x.tag == DW_TAG_UNSPECIFIED_PARAMETERS),
+            },
+            DW_TAG_UNSPECIFIED_TYPE => TypeKind::Unspecified,
+            _ => return None,
+        };
+        Some(TypeRecord {
+            offset: die.offset,
+            name: die.name().map(|x| x.to_string()),
+            size: die.attribute(DW_AT_BYTE_SIZE).and_then(|x| x.unsigned()),
+            kind,
+        })
+    }
+
+    // The number of elements of an array dimension
+    fn count(&self, subrange: &Die) -> Option<u64> {
+        if let Some(x) = subrange.attribute(DW_AT_COUNT).and_then(|x| x.unsigned()) {
+            return Some(x);
+        }
+        // Flexible array members have -1 or no upper bound. C arrays start at 0.
+        match subrange.attribute(DW_AT_UPPER_BOUND)? {
+            AttributeValue::Signed(x) if *x < 0 => None,
+            AttributeValue::Unsigned(x) if *x == u32::MAX as u64 || *x == u64::MAX => None,
+            x => x.unsigned().map(|x| x + 1),
+        }
+    }
+
+    fn members(&self, die: &Die) -> Vec<Member> {
+        self.children(die)
+            .filter(|x| x.tag == DW_TAG_MEMBER)
+            .map(|x| {
+                let bit_offset = x.attribute(DW_AT_DATA_BIT_OFFSET).and_then(|x| x.unsigned());
+                // The offset is usually a constant, old compilers gave a DW_OP_plus_uconst expression
+                let offset = match x.attribute(DW_AT_DATA_MEMBER_LOCATION) {
+                    Some(AttributeValue::Block(bytes)) if bytes.first() == Some(&0x23) => {
+                        let mut reader = Reader::new(&bytes[1..], self.reader.endianness, self.reader.address_size);
+                        reader.uleb128().unwrap_or(0)
+                    }
+                    Some(x) => x.unsigned().unwrap_or(0),
+                    None => bit_offset.map_or(0, |x| x / 8),
+                };
+                Member {
+                    name: x.name().map(|x| x.to_string()),
+                    ty: x.reference(DW_AT_TYPE),
+                    offset,
+                    bit_offset,
+                    bit_size: x.attribute(DW_AT_BIT_SIZE).and_then(|x| x.unsigned()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::constants::*;
+    use super::{DebugInfo, Member, TypeKind};
+    use crate::dwarf::{DebugSections, Location, Reader};
+    use crate::endian::Endianness;
+
+    const DW_CHILDREN_YES: u8 = 1;
+
+    fn uleb(out: &mut Vec<u8>, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    // An abbreviation: its code, tag, whether it has children and the (attribute, form) of its DIEs
+    fn abbreviation(out: &mut Vec<u8>, code: u64, tag: u64, children: u8, attributes: &[(u64, u64)]) {
+        uleb(out, code);
+        uleb(out, tag);
+        out.push(children);
+        for &(name, form) in attributes {
+            uleb(out, name);
+            uleb(out, form);
+        }
+        out.extend_from_slice(&[0, 0]);
+    }
+
+    // The sections of this program, compiled for x86-64 as DWARF 4:
+    //
+    //     struct point { int x; int y; };
+    //     int counter;                    // at 0x4000
+    //     int main(char **argv) { ... }   // from 0x1000 to 0x1020, lines 10 and 11 of /src/main.c
+    struct Fixture {
+        info: Vec<u8>,
+        abbrev: Vec<u8>,
+        str: Vec<u8>,
+        line: Vec<u8>,
+    }
+
+    fn fixture() -> Fixture {
+        let mut abbrev = Vec::new();
+        let cu = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_COMP_DIR, DW_FORM_STRING), (DW_AT_LOW_PC, DW_FORM_ADDR),
+                  (DW_AT_HIGH_PC, DW_FORM_DATA4), (DW_AT_STMT_LIST, DW_FORM_SEC_OFFSET)];
+        abbreviation(&mut abbrev, 1, DW_TAG_COMPILE_UNIT, DW_CHILDREN_YES, &cu);
+        let base = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_BYTE_SIZE, DW_FORM_DATA1), (DW_AT_ENCODING, DW_FORM_DATA1)];
+        abbreviation(&mut abbrev, 2, DW_TAG_BASE_TYPE, 0, &base);
+        abbreviation(&mut abbrev, 3, DW_TAG_POINTER_TYPE, 0, &[(DW_AT_BYTE_SIZE, DW_FORM_DATA1), (DW_AT_TYPE, DW_FORM_REF4)]);
+        let subprogram = [(DW_AT_EXTERNAL, DW_FORM_FLAG_PRESENT), (DW_AT_NAME, DW_FORM_STRP), (DW_AT_TYPE, DW_FORM_REF4),
+                          (DW_AT_LOW_PC, DW_FORM_ADDR), (DW_AT_HIGH_PC, DW_FORM_DATA4), (DW_AT_FRAME_BASE, DW_FORM_EXPRLOC)];
+        abbreviation(&mut abbrev, 4, DW_TAG_SUBPROGRAM, DW_CHILDREN_YES, &subprogram);
+        let parameter = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_TYPE, DW_FORM_REF4), (DW_AT_LOCATION, DW_FORM_EXPRLOC)];
+        abbreviation(&mut abbrev, 5, DW_TAG_FORMAL_PARAMETER, 0, &parameter);
+        let variable = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_TYPE, DW_FORM_REF4), (DW_AT_EXTERNAL, DW_FORM_FLAG_PRESENT),
+                        (DW_AT_LOCATION, DW_FORM_EXPRLOC)];
+        abbreviation(&mut abbrev, 6, DW_TAG_VARIABLE, 0, &variable);
+        let structure = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_BYTE_SIZE, DW_FORM_DATA1)];
+        abbreviation(&mut abbrev, 7, DW_TAG_STRUCTURE_TYPE, DW_CHILDREN_YES, &structure);
+        let member = [(DW_AT_NAME, DW_FORM_STRING), (DW_AT_TYPE, DW_FORM_REF4), (DW_AT_DATA_MEMBER_LOCATION, DW_FORM_DATA1)];
+        abbreviation(&mut abbrev, 8, DW_TAG_MEMBER, 0, &member);
+        abbrev.push(0);
+
+        // Unit header, the length is filled in at the end. References are from the start of the unit, which is the
+        // start of the section.
+        let mut info = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0, 8];
+        info.push(1);
+        info.extend_from_slice(b"main.c\0/src\0");
+        info.extend_from_slice(&0x1000u64.to_le_bytes());
+        info.extend_from_slice(&0x20u32.to_le_bytes());
+        info.extend_from_slice(&0u32.to_le_bytes());
+        let int = info.len() as u32;
+        info.extend_from_slice(&[2, b'i', b'n', b't', 0, 4, DW_ATE_SIGNED as u8]);
+        let char = info.len() as u32;
+        info.extend_from_slice(&[2, b'c', b'h', b'a', b'r', 0, 1, DW_ATE_SIGNED_CHAR as u8]);
+        let char_pointer = info.len() as u32;
+        info.extend_from_slice(&[3, 8]);
+        info.extend_from_slice(&char.to_le_bytes());
+        let char_pointer_pointer = info.len() as u32;
+        info.extend_from_slice(&[3, 8]);
+        info.extend_from_slice(&char_pointer.to_le_bytes());
+
+        info.push(4);
+        info.extend_from_slice(&0u32.to_le_bytes());
+        info.extend_from_slice(&int.to_le_bytes());
+        info.extend_from_slice(&0x1000u64.to_le_bytes());
+        info.extend_from_slice(&0x20u32.to_le_bytes());
+        info.extend_from_slice(&[1, DW_OP_CALL_FRAME_CFA]);
+        info.push(5);
+        info.extend_from_slice(b"argv\0");
+        info.extend_from_slice(&char_pointer_pointer.to_le_bytes());
+        // fbreg -24
+        info.extend_from_slice(&[2, DW_OP_FBREG, 0x68]);
+        info.push(0);
+
+        info.push(6);
+        info.extend_from_slice(b"counter\0");
+        info.extend_from_slice(&int.to_le_bytes());
+        info.extend_from_slice(&[9, DW_OP_ADDR]);
+        info.extend_from_slice(&0x4000u64.to_le_bytes());
+
+        info.push(7);
+        info.extend_from_slice(b"point\0");
+        info.push(8);
+        for (name, offset) in [(&b"x\0"[..], 0), (&b"y\0"[..], 4)].iter() {
+            info.push(8);
+            info.extend_from_slice(name);
+            info.extend_from_slice(&int.to_le_bytes());
+            info.push(*offset);
+        }
+        info.push(0);
+        info.push(0);
+        let length = (info.len() - 4) as u32;
+        info[..4].copy_from_slice(&length.to_le_bytes());
+
+        let mut line = vec![0, 0, 0, 0, 4, 0, 0, 0, 0, 0];
+        // Minimum instruction length, maximum operations, default is_stmt, line base, line range, opcode base and
+        // the lengths of the standard opcodes
+        line.extend_from_slice(&[1, 1, 1, (-5i8) as u8, 14, 13, 0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+        // No include directories, then the file
+        line.push(0);
+        line.extend_from_slice(b"main.c\0\0\0\0\0");
+        let header_length = (line.len() - 10) as u32;
+        line[6..10].copy_from_slice(&header_length.to_le_bytes());
+        line.extend_from_slice(&[0, 9, DW_LNE_SET_ADDRESS]);
+        line.extend_from_slice(&0x1000u64.to_le_bytes());
+        line.extend_from_slice(&[DW_LNS_ADVANCE_LINE, 9, DW_LNS_COPY]);
+        // Special opcode: 8 bytes and 1 line further
+        line.push((1 + 5) + 14 * 8 + 13);
+        line.extend_from_slice(&[DW_LNS_ADVANCE_PC, 0x18, 0, 1, DW_LNE_END_SEQUENCE]);
+        let length = (line.len() - 4) as u32;
+        line[..4].copy_from_slice(&length.to_le_bytes());
+
+        Fixture { info, abbrev, str: b"main\0".to_vec(), line }
+    }
+
+    fn parse(fixture: &Fixture) -> DebugInfo {
+        let sections = DebugSections {
+            info: &fixture.info,
+            abbrev: &fixture.abbrev,
+            str: &fixture.str,
+            line: &fixture.line,
+            ..DebugSections::default()
+        };
+        DebugInfo::parse(&sections, &Reader::new(&[], Endianness::LittleEndian, 8)).unwrap()
+    }
+
+    #[test]
+    fn functions_and_variables() {
+        let info = parse(&fixture());
+        assert_eq!(info.functions.len(), 1);
+        let main = &info.functions[0];
+        assert_eq!(main.name.as_deref(), Some("main"));
+        assert_eq!(main.ranges, [(0x1000, 0x1020)]);
+        assert_eq!(main.frame_base, Some(Location::CallFrameCfa));
+        assert_eq!(main.parameters[0].location, Some(Location::FrameOffset(-24)));
+        assert_eq!(info.prototype(main), "int main(char **argv)");
+        assert_eq!(info.function_at(0x101f).map(|x| x.offset), Some(main.offset));
+        assert!(info.function_at(0x1020).is_none());
+
+        assert_eq!(info.variables.len(), 1);
+        assert_eq!(info.variables[0].name.as_deref(), Some("counter"));
+        assert_eq!(info.variables[0].location, Some(Location::Address(0x4000)));
+        assert_eq!(info.declaration(info.variables[0].ty, "counter"), "int counter");
+    }
+
+    #[test]
+    fn types() {
+        let info = parse(&fixture());
+        let point = info.types.values().find(|x| x.name.as_deref() == Some("point")).unwrap();
+        assert_eq!(point.size, Some(8));
+        let int = info.types.values().find(|x| x.name.as_deref() == Some("int")).unwrap();
+        assert_eq!(int.kind, TypeKind::Base(DW_ATE_SIGNED));
+        let member = |name: &str, offset| {
+            Member { name: Some(name.to_string()), ty: Some(int.offset), offset, bit_offset: None, bit_size: None }
+        };
+        assert_eq!(point.kind, TypeKind::Struct(vec![member("x", 0), member("y", 4)]));
+        assert_eq!(info.type_name(Some(point.offset)), "struct point");
+    }
+
+    #[test]
+    fn lines() {
+        let info = parse(&fixture());
+        assert_eq!(info.line_for(0x1000), Some(("/src/main.c".to_string(), 10)));
+        assert_eq!(info.line_for(0x1007), Some(("/src/main.c".to_string(), 10)));
+        assert_eq!(info.line_for(0x1008), Some(("/src/main.c".to_string(), 11)));
+        assert_eq!(info.line_for(0x1020), None);
+    }
+}
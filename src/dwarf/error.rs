@@ -0,0 +1,22 @@
+use crate::error::GenericParseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DwarfError {
+    Generic(GenericParseError),
+    UnknownPointerEncoding(u8),
+    UnknownCieVersion(u8),
+    // Offset of the FDE whose CIE pointer doesn't lead to a CIE
+    InvalidCiePointer(u64),
+    // Of a unit header or a line number program
+    UnknownVersion(u16),
+    UnknownForm(u64),
+    // Code of a DIE that the abbreviations of its unit don't have
+    UnknownAbbreviation(u64),
+    UnknownCfaInstruction(u8),
+}
+
+impl From<GenericParseError> for DwarfError {
+    fn from(err: GenericParseError) -> Self {
+        DwarfError::Generic(err)
+    }
+}
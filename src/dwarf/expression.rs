@@ -0,0 +1,173 @@
+use std::convert::TryInto;
+
+use super::constants::{DW_OP_ADDR, DW_OP_BREG0, DW_OP_BREG31, DW_OP_BREGX};
+use super::Reader;
+
+// Operations of DWARF expressions (DW_OP_*) that compute values, beyond the ones that name places
+const DW_OP_DEREF: u8 = 0x06;
+const DW_OP_CONST1U: u8 = 0x08;
+const DW_OP_CONST1S: u8 = 0x09;
+const DW_OP_CONST2U: u8 = 0x0a;
+const DW_OP_CONST2S: u8 = 0x0b;
+const DW_OP_CONST4U: u8 = 0x0c;
+const DW_OP_CONST4S: u8 = 0x0d;
+const DW_OP_CONST8U: u8 = 0x0e;
+const DW_OP_CONST8S: u8 = 0x0f;
+const DW_OP_CONSTU: u8 = 0x10;
+const DW_OP_CONSTS: u8 = 0x11;
+const DW_OP_DUP: u8 = 0x12;
+const DW_OP_DROP: u8 = 0x13;
+const DW_OP_OVER: u8 = 0x14;
+const DW_OP_PICK: u8 = 0x15;
+const DW_OP_SWAP: u8 = 0x16;
+const DW_OP_ROT: u8 = 0x17;
+const DW_OP_ABS: u8 = 0x19;
+const DW_OP_AND: u8 = 0x1a;
+const DW_OP_DIV: u8 = 0x1b;
+const DW_OP_MINUS: u8 = 0x1c;
+const DW_OP_MOD: u8 = 0x1d;
+const DW_OP_MUL: u8 = 0x1e;
+const DW_OP_NEG: u8 = 0x1f;
+const DW_OP_NOT: u8 = 0x20;
+const DW_OP_OR: u8 = 0x21;
+const DW_OP_PLUS: u8 = 0x22;
+const DW_OP_PLUS_UCONST: u8 = 0x23;
+const DW_OP_SHL: u8 = 0x24;
+const DW_OP_SHR: u8 = 0x25;
+const DW_OP_SHRA: u8 = 0x26;
+const DW_OP_XOR: u8 = 0x27;
+const DW_OP_BRA: u8 = 0x28;
+const DW_OP_EQ: u8 = 0x29;
+const DW_OP_GE: u8 = 0x2a;
+const DW_OP_GT: u8 = 0x2b;
+const DW_OP_LE: u8 = 0x2c;
+const DW_OP_LT: u8 = 0x2d;
+const DW_OP_NE: u8 = 0x2e;
+const DW_OP_SKIP: u8 = 0x2f;
+const DW_OP_LIT0: u8 = 0x30;
+const DW_OP_LIT31: u8 = 0x4f;
+const DW_OP_DEREF_SIZE: u8 = 0x94;
+const DW_OP_NOP: u8 = 0x96;
+
+// Operations run before giving up on an expression that loops
+const MAX_STEPS: usize = 1000;
+
+/// Runs a DWARF expression on a stack holding `initial`, giving the value on top at the end. Registers and memory
+/// are read through the closures, as DWARF numbered registers and values of a given size. Expressions using
+/// operations other than arithmetic, registers and memory give None.
+pub fn evaluate(
+    bytes: &[u8],
+    reader: &Reader<'_>,
+    initial: &[u64],
+    register: &dyn Fn(u64) -> Option<u64>,
+    memory: &dyn Fn(u64, u8) -> Option<u64>,
+) -> Option<u64> {
+    let mut expr = Reader::new(bytes, reader.endianness, reader.address_size);
+    let mut stack: Vec<u64> = initial.to_vec();
+    let mask = match reader.address_size {
+        4 => 0xffff_ffff,
+        _ => u64::MAX,
+    };
+    // Values are as wide as addresses, signed operations sign extend from there
+    let signed = |x: u64| match reader.address_size {
+        4 => x as u32 as i32 as i64,
+        _ => x as i64,
+    };
+    for _ in 0..MAX_STEPS {
+        if expr.is_at_end() {
+            return stack.pop();
+        }
+        let op = expr.u8().ok()?;
+        let value = match op {
+            DW_OP_ADDR => expr.address().ok()?,
+            DW_OP_LIT0..=DW_OP_LIT31 => (op - DW_OP_LIT0) as u64,
+            DW_OP_CONST1U => expr.u8().ok()? as u64,
+            DW_OP_CONST1S => expr.u8().ok()? as i8 as u64,
+            DW_OP_CONST2U => expr.u16().ok()? as u64,
+            DW_OP_CONST2S => expr.u16().ok()? as i16 as u64,
+            DW_OP_CONST4U => expr.u32().ok()? as u64,
+            DW_OP_CONST4S => expr.u32().ok()? as i32 as u64,
+            DW_OP_CONST8U | DW_OP_CONST8S => expr.u64().ok()?,
+            DW_OP_CONSTU => expr.uleb128().ok()?,
+            DW_OP_CONSTS => expr.sleb128().ok()? as u64,
+            DW_OP_BREG0..=DW_OP_BREG31 => {
+                let offset = expr.sleb128().ok()?;
+                register((op - DW_OP_BREG0) as u64)?.wrapping_add(offset as u64)
+            }
+            DW_OP_BREGX => {
+                let number = expr.uleb128().ok()?;
+                register(number)?.wrapping_add(expr.sleb128().ok()? as u64)
+            }
+            DW_OP_DUP => *stack.last()?,
+            DW_OP_OVER => *stack.get(stack.len().checked_sub(2)?)?,
+            DW_OP_PICK => {
+                let index = expr.u8().ok()? as usize;
+                *stack.get(stack.len().checked_sub(index + 1)?)?
+            }
+            DW_OP_DROP => {
+                stack.pop()?;
+                continue;
+            }
+            DW_OP_SWAP => {
+                let len = stack.len();
+                if len < 2 {
+                    return None;
+                }
+                stack.swap(len - 1, len - 2);
+                continue;
+            }
+            DW_OP_ROT => {
+                let len = stack.len();
+                if len < 3 {
+                    return None;
+                }
+                stack[len - 3..].rotate_right(1);
+                continue;
+            }
+            DW_OP_DEREF => memory(stack.pop()?, reader.address_size)?,
+            DW_OP_DEREF_SIZE => {
+                let size = expr.u8().ok()?;
+                memory(stack.pop()?, size)?
+            }
+            DW_OP_ABS => signed(stack.pop()?).unsigned_abs(),
+            DW_OP_NEG => signed(stack.pop()?).wrapping_neg() as u64,
+            DW_OP_NOT => !stack.pop()?,
+            DW_OP_PLUS_UCONST => stack.pop()?.wrapping_add(expr.uleb128().ok()?),
+            DW_OP_AND | DW_OP_DIV | DW_OP_MINUS | DW_OP_MOD | DW_OP_MUL | DW_OP_OR | DW_OP_PLUS | DW_OP_SHL | DW_OP_SHR
+            | DW_OP_SHRA | DW_OP_XOR | DW_OP_EQ | DW_OP_GE | DW_OP_GT | DW_OP_LE | DW_OP_LT | DW_OP_NE => {
+                let b = stack.pop()?;
+                let a = stack.pop()?;
+                match op {
+                    DW_OP_AND => a & b,
+                    DW_OP_DIV => signed(a).checked_div(signed(b))? as u64,
+                    DW_OP_MINUS => a.wrapping_sub(b),
+                    DW_OP_MOD => (a & mask).checked_rem(b & mask)?,
+                    DW_OP_MUL => a.wrapping_mul(b),
+                    DW_OP_OR => a | b,
+                    DW_OP_PLUS => a.wrapping_add(b),
+                    DW_OP_SHL => a.checked_shl(b as u32).unwrap_or(0),
+                    DW_OP_SHR => (a & mask).checked_shr(b as u32).unwrap_or(0),
+                    DW_OP_SHRA => (signed(a) >> b.min(63)) as u64,
+                    DW_OP_XOR => a ^ b,
+                    DW_OP_EQ => (signed(a) == signed(b)) as u64,
+                    DW_OP_GE => (signed(a) >= signed(b)) as u64,
+                    DW_OP_GT => (signed(a) > signed(b)) as u64,
+                    DW_OP_LE => (signed(a) <= signed(b)) as u64,
+                    DW_OP_LT => (signed(a) < signed(b)) as u64,
+                    _ => (signed(a) != signed(b)) as u64,
+                }
+            }
+            DW_OP_SKIP | DW_OP_BRA => {
+                let offset = expr.u16().ok()? as i16 as isize;
+                if op == DW_OP_SKIP || stack.pop()? != 0 {
+                    expr.seek((expr.offset() as isize + offset).try_into().ok()?);
+                }
+                continue;
+            }
+            DW_OP_NOP => continue,
+            _ => return None,
+        };
+        stack.push(value & mask);
+    }
+    None
+}
@@ -0,0 +1,228 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+
+use crate::endian::Endianness;
+
+use super::reader::{Bases, Reader, DW_EH_PE_OMIT};
+use super::DwarfError;
+
+/// Which section the call frame information comes from. They differ in how CIEs are told apart from FDEs
+/// and how FDEs refer to their CIE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum FrameKind {
+    EhFrame,
+    DebugFrame,
+}
+
+/// Common Information Entry: what the FDEs of a compilation unit share
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct Cie {
+    // Offset in the section
+    pub offset: u64,
+    pub version: u8,
+    pub augmentation: String,
+    pub address_size: u8,
+    pub code_alignment: u64,
+    pub data_alignment: i64,
+    pub return_register: u64,
+    // DW_EH_PE_* encodings of the addresses in the FDEs and of their LSDA pointer
+    pub fde_encoding: u8,
+    pub lsda_encoding: u8,
+    pub personality: Option<u64>,
+    // The frames are signal handler frames, whose return address is not after a call
+    pub signal_frame: bool,
+    pub instructions: Vec<u8>,
+    // Byte order of the operands of the instructions
+    pub endianness: Endianness,
+}
+
+/// Frame Description Entry: how to unwind the code of one function
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct Fde {
+    pub offset: u64,
+    // Offset of the CIE in the section
+    pub cie: u64,
+    pub start: u64,
+    pub length: u64,
+    // Language specific data, the exception tables for C++
+    pub lsda: Option<u64>,
+    pub instructions: Vec<u8>,
+}
+
+impl Fde {
+    pub fn end(&self) -> u64 {
+        self.start.wrapping_add(self.length)
+    }
+}
+
+/// The entries of a .eh_frame or .debug_frame section
+#[derive(Debug, Clone, Default)]
+pub struct FrameTable {
+    // By offset
+    pub cies: BTreeMap<u64, Cie>,
+    pub fdes: Vec<Fde>,
+}
+
+impl FrameTable {
+    /// Parses `reader`, the contents of a section of the given kind. The bases give what pointers in it are
+    /// relative to.
+    pub fn parse(reader: &Reader<'_>, kind: FrameKind, bases: &Bases) -> Result<FrameTable, DwarfError> {
+        let mut table = FrameTable::default();
+        let mut reader = reader.clone();
+        reader.seek(0);
+        while !reader.is_at_end() {
+            let offset = reader.offset() as u64;
+            let (length, dwarf64) = match reader.u32()? {
+                0xffff_ffff => (reader.u64()?, true),
+                x => (x as u64, false),
+            };
+            // A zero length entry terminates .eh_frame
+            if length == 0 {
+                if kind == FrameKind::EhFrame {
+                    break;
+                }
+                continue;
+            }
+            let end = reader.offset() + length as usize;
+            let id_offset = reader.offset() as u64;
+            let id = if dwarf64 { reader.u64()? } else { reader.u32()? as u64 };
+
+            let is_cie = match kind {
+                FrameKind::EhFrame => id == 0,
+                FrameKind::DebugFrame => id == 0xffff_ffff || id == u64::MAX,
+            };
+            if is_cie {
+                if let Entry::Vacant(entry) = table.cies.entry(offset) {
+                    entry.insert(parse_cie(&mut reader, offset, end, bases)?);
+                }
+            } else {
+                // .eh_frame points back relative to the pointer, .debug_frame gives the offset in the section
+                let cie_offset = match kind {
+                    FrameKind::EhFrame => id_offset.wrapping_sub(id),
+                    FrameKind::DebugFrame => id,
+                };
+                if let Entry::Vacant(entry) = table.cies.entry(cie_offset) {
+                    entry.insert(parse_cie_at(&reader, cie_offset, bases).map_err(|_| DwarfError::InvalidCiePointer(offset))?);
+                }
+                let fde = parse_fde(&mut reader, offset, end, &table.cies[&cie_offset], kind, bases)?;
+                table.fdes.push(fde);
+            }
+            reader.seek(end);
+        }
+        Ok(table)
+    }
+
+    /// The FDE covering an address
+    pub fn fde_for(&self, address: u64) -> Option<&Fde> {
+        self.fdes.iter().find(|x| address >= x.start && address < x.end())
+    }
+}
+
+// The CIE at an offset that wasn't reached yet, which .debug_frame allows
+fn parse_cie_at(reader: &Reader<'_>, offset: u64, bases: &Bases) -> Result<Cie, DwarfError> {
+    let mut reader = reader.clone();
+    reader.seek(offset as usize);
+    let (length, dwarf64) = match reader.u32()? {
+        0xffff_ffff => (reader.u64()?, true),
+        x => (x as u64, false),
+    };
+    let end = reader.offset() + length as usize;
+    if dwarf64 {
+        reader.u64()?;
+    } else {
+        reader.u32()?;
+    }
+    parse_cie(&mut reader, offset, end, bases)
+}
+
+// From after the CIE id
+fn parse_cie(reader: &mut Reader<'_>, offset: u64, end: usize, bases: &Bases) -> Result<Cie, DwarfError> {
+    let version = reader.u8()?;
+    if !matches!(version, 1 | 3 | 4) {
+        return Err(DwarfError::UnknownCieVersion(version));
+    }
+    let augmentation = String::from_utf8_lossy(reader.cstr()?).into_owned();
+    // Old GCC put the address of the exception table right in the CIE
+    if augmentation.contains("eh") {
+        reader.address()?;
+    }
+    let mut address_size = reader.address_size;
+    if version >= 4 {
+        address_size = reader.u8()?;
+        // Segment selector size
+        reader.u8()?;
+    }
+    let code_alignment = reader.uleb128()?;
+    let data_alignment = reader.sleb128()?;
+    let return_register = match version {
+        1 => reader.u8()? as u64,
+        _ => reader.uleb128()?,
+    };
+
+    let mut cie = Cie {
+        offset,
+        version,
+        augmentation: augmentation.clone(),
+        address_size,
+        code_alignment,
+        data_alignment,
+        return_register,
+        fde_encoding: 0,
+        lsda_encoding: DW_EH_PE_OMIT,
+        personality: None,
+        signal_frame: false,
+        instructions: Vec::new(),
+        endianness: reader.endianness,
+    };
+
+    // The augmentation data is only understood if the string starts with z, which gives its length
+    if augmentation.starts_with('z') {
+        let length = reader.uleb128()? as usize;
+        let data_end = reader.offset() + length;
+        for c in augmentation.chars().skip(1) {
+            match c {
+                'L' => cie.lsda_encoding = reader.u8()?,
+                'R' => cie.fde_encoding = reader.u8()?,
+                'P' => {
+                    let encoding = reader.u8()?;
+                    cie.personality = Some(reader.encoded_pointer(encoding, bases)?);
+                }
+                'S' => cie.signal_frame = true,
+                // Characters without data, such as B for AArch64 BTI and G for MTE tagged frames
+                _ => {}
+            }
+        }
+        reader.seek(data_end);
+    }
+    cie.instructions = reader.bytes(end.saturating_sub(reader.offset()))?.to_vec();
+    Ok(cie)
+}
+
+// From after the CIE pointer
+fn parse_fde(reader: &mut Reader<'_>, offset: u64, end: usize, cie: &Cie, kind: FrameKind, bases: &Bases) -> Result<Fde, DwarfError> {
+    let (start, length) = match kind {
+        FrameKind::EhFrame => {
+            let start = reader.encoded_pointer(cie.fde_encoding, bases)?;
+            // The length is a plain number in the same format
+            let length = reader.encoded_pointer(cie.fde_encoding & 0x0f, bases)?;
+            (start, length)
+        }
+        FrameKind::DebugFrame => (reader.sized(cie.address_size)?, reader.sized(cie.address_size)?),
+    };
+
+    let mut lsda = None;
+    if cie.augmentation.starts_with('z') {
+        let length = reader.uleb128()? as usize;
+        let data_end = reader.offset() + length;
+        if cie.lsda_encoding != DW_EH_PE_OMIT {
+            let bases = Bases { function: Some(start), ..*bases };
+            lsda = Some(reader.encoded_pointer(cie.lsda_encoding, &bases)?);
+        }
+        reader.seek(data_end);
+    }
+    let instructions = reader.bytes(end.saturating_sub(reader.offset()))?.to_vec();
+    Ok(Fde { offset, cie: cie.offset, start, length, lsda, instructions })
+}
@@ -0,0 +1,49 @@
+use super::reader::{Bases, Reader, DW_EH_PE_OMIT};
+use super::DwarfError;
+
+/// .eh_frame_hdr: where .eh_frame is, and a table of the FDEs sorted by the start of their function for unwinders
+/// to search
+#[derive(Debug, Clone, Default)]
+pub struct EhFrameHeader {
+    pub version: u8,
+    // Address of .eh_frame
+    pub eh_frame: u64,
+    // Start of the function and address of its FDE, by start
+    pub table: Vec<(u64, u64)>,
+}
+
+impl EhFrameHeader {
+    /// Parses the header, which is at `address`. Pointers in it can be relative to that.
+    pub fn parse(reader: &Reader<'_>, address: u64) -> Result<EhFrameHeader, DwarfError> {
+        let mut reader = reader.clone();
+        reader.seek(0);
+        let bases = Bases { section: address, data: Some(address), ..Bases::default() };
+        let version = reader.u8()?;
+        if version != 1 {
+            return Err(DwarfError::UnknownVersion(version as u16));
+        }
+        let eh_frame_encoding = reader.u8()?;
+        let count_encoding = reader.u8()?;
+        let table_encoding = reader.u8()?;
+        let eh_frame = reader.encoded_pointer(eh_frame_encoding, &bases)?;
+        let mut header = EhFrameHeader { version, eh_frame, table: Vec::new() };
+        // Without a count or a table, unwinders go through .eh_frame themselves
+        if count_encoding == DW_EH_PE_OMIT || table_encoding == DW_EH_PE_OMIT {
+            return Ok(header);
+        }
+        let count = reader.encoded_pointer(count_encoding, &bases)?;
+        for _ in 0..count {
+            let start = reader.encoded_pointer(table_encoding, &bases)?;
+            let fde = reader.encoded_pointer(table_encoding, &bases)?;
+            header.table.push((start, fde));
+        }
+        Ok(header)
+    }
+
+    /// The address of the FDE of the function holding `address`, if it is in the table. The FDE tells where the
+    /// function ends.
+    pub fn fde_for(&self, address: u64) -> Option<u64> {
+        let index = self.table.partition_point(|x| x.0 <= address);
+        Some(self.table.get(index.checked_sub(1)?)?.1)
+    }
+}
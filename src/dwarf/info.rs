@@ -0,0 +1,632 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::endian::Endianness;
+
+use super::abbrev::{parse_abbreviations, Abbreviation, AttributeSpec};
+use super::constants::*;
+use super::{DwarfError, Reader};
+
+/// The debug sections of a binary, empty when it doesn't have them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugSections<'a> {
+    pub info: &'a [u8],
+    pub abbrev: &'a [u8],
+    pub str: &'a [u8],
+    pub line_str: &'a [u8],
+    pub str_offsets: &'a [u8],
+    pub addr: &'a [u8],
+    pub line: &'a [u8],
+    // Range and location lists before DWARF 5
+    pub ranges: &'a [u8],
+    pub loc: &'a [u8],
+    pub rnglists: &'a [u8],
+    pub loclists: &'a [u8],
+}
+
+/// The value of an attribute, as far as its form tells. Data forms give Unsigned even for attributes whose value is
+/// signed; what a value means depends on the attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue {
+    Address(u64),
+    Unsigned(u64),
+    Signed(i64),
+    Flag(bool),
+    String(String),
+    // Blocks, location expressions and 16 byte constants
+    Block(Vec<u8>),
+    // Offset of a DIE in .debug_info
+    Reference(u64),
+    // Offset in .debug_line, a range list or location list section, or the supplementary file
+    SectionOffset(u64),
+    // Signature of the type unit holding a type
+    Signature(u64),
+    // Indices into the tables of the unit, which only its root DIE gives. They are resolved once it is read.
+    StringIndex(u64),
+    AddressIndex(u64),
+    RangeListIndex(u64),
+    LocationListIndex(u64),
+}
+
+impl AttributeValue {
+    /// The value of a constant, flag or section offset. DWARF 2 and 3 give offsets as plain data.
+    pub fn unsigned(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::Unsigned(x) | AttributeValue::SectionOffset(x) => Some(x),
+            AttributeValue::Signed(x) => Some(x as u64),
+            AttributeValue::Flag(x) => Some(x as u64),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self) -> Option<&str> {
+        match self {
+            AttributeValue::String(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// A debugging information entry
+#[derive(Debug, Clone)]
+pub struct Die {
+    // Offset in .debug_info
+    pub offset: u64,
+    // DW_TAG_*
+    pub tag: u64,
+    // By DW_AT_* name
+    pub attributes: Vec<(u64, AttributeValue)>,
+    // Indices in the DIEs of the unit
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl Die {
+    pub fn attribute(&self, name: u64) -> Option<&AttributeValue> {
+        self.attributes.iter().find(|x| x.0 == name).map(|x| &x.1)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.attribute(DW_AT_NAME).and_then(|x| x.string())
+    }
+
+    /// The DIE an attribute refers to, by offset
+    pub fn reference(&self, name: u64) -> Option<u64> {
+        match self.attribute(name) {
+            Some(AttributeValue::Reference(x)) => Some(*x),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self, name: u64) -> bool {
+        self.attribute(name).and_then(|x| x.unsigned()).unwrap_or(0) != 0
+    }
+}
+
+/// Where the value of a variable lives, from its location expression. Expressions that do more than name a place
+/// are kept as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Address(u64),
+    // DWARF register number, which depends on the architecture
+    Register(u64),
+    // Relative to the frame base of the function
+    FrameOffset(i64),
+    RegisterOffset(u64, i64),
+    // The canonical frame address, what frame bases usually are
+    CallFrameCfa,
+    Expression(Vec<u8>),
+    // A location per range of addresses, from a location list
+    List(Vec<LocationRange>),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Address(x) => write!(f, "0x{:x}", x),
+            Location::Register(x) => write!(f, "reg{}", x),
+            Location::FrameOffset(x) => write!(f, "fbreg{:+}", x),
+            Location::RegisterOffset(register, x) => write!(f, "breg{}{:+}", register, x),
+            Location::CallFrameCfa => write!(f, "cfa"),
+            Location::Expression(x) => write!(f, "expression of {} bytes", x.len()),
+            Location::List(x) => write!(f, "list of {} locations", x.len()),
+        }
+    }
+}
+
+/// Where a variable is while the code between `start` and `end` runs. `start` and `end` are both zero for the
+/// default location of a list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationRange {
+    pub start: u64,
+    pub end: u64,
+    pub location: Location,
+}
+
+/// A unit of .debug_info, usually a compilation unit, with the tree of its DIEs
+#[derive(Debug, Clone)]
+pub struct Unit {
+    pub offset: u64,
+    pub version: u16,
+    // DW_UT_*, or what the unit is for DWARF before 5
+    pub unit_type: u8,
+    pub dwarf64: bool,
+    pub address_size: u8,
+    pub abbrev_offset: u64,
+    // Where the tables of the unit start in .debug_str_offsets, .debug_addr, .debug_rnglists and .debug_loclists
+    pub str_offsets_base: u64,
+    pub addr_base: u64,
+    pub rnglists_base: u64,
+    pub loclists_base: u64,
+    // The low_pc of the root, which the range and location lists are relative to
+    pub base_address: u64,
+    // In order, the root first
+    pub dies: Vec<Die>,
+}
+
+impl Unit {
+    pub fn root(&self) -> Option<&Die> {
+        self.dies.first()
+    }
+
+    fn offset_size(&self) -> u64 {
+        if self.dwarf64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// The address ranges of a DIE, from low_pc and high_pc or a range list. Range list indices are resolved to
+    /// offsets once the unit is read.
+    pub fn ranges(&self, die: &Die, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Vec<(u64, u64)> {
+        if let Some(AttributeValue::Address(low)) = die.attribute(DW_AT_LOW_PC) {
+            let high = match die.attribute(DW_AT_HIGH_PC) {
+                Some(AttributeValue::Address(x)) => *x,
+                // Since DWARF 4 high_pc can be the size
+                Some(x) => low.wrapping_add(x.unsigned().unwrap_or(0)),
+                None => low.wrapping_add(1),
+            };
+            return vec![(*low, high)];
+        }
+        let res = match die.attribute(DW_AT_RANGES) {
+            Some(x) => match x.unsigned() {
+                Some(offset) if self.version >= 5 => self.range_list(offset, sections, reader),
+                Some(offset) => self.old_range_list(offset, sections, reader),
+                None => Ok(Vec::new()),
+            },
+            None => Ok(Vec::new()),
+        };
+        res.unwrap_or_default()
+    }
+
+    // .debug_ranges: pairs of addresses relative to the base, ended by zeros
+    fn old_range_list(&self, offset: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<Vec<(u64, u64)>, DwarfError> {
+        let mut reader = Reader::new(sections.ranges, reader.endianness, self.address_size);
+        reader.seek(offset as usize);
+        let mut base = self.base_address;
+        let mut res = Vec::new();
+        loop {
+            let start = reader.address()?;
+            let end = reader.address()?;
+            if start == 0 && end == 0 {
+                return Ok(res);
+            }
+            if start == self.max_address() {
+                base = end;
+            } else if start != end {
+                res.push((base.wrapping_add(start), base.wrapping_add(end)));
+            }
+        }
+    }
+
+    // .debug_rnglists, at an offset in the section
+    fn range_list(&self, offset: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<Vec<(u64, u64)>, DwarfError> {
+        let mut reader = Reader::new(sections.rnglists, reader.endianness, self.address_size);
+        reader.seek(offset as usize);
+        let mut base = self.base_address;
+        let mut res = Vec::new();
+        loop {
+            let (start, end) = match reader.u8()? {
+                DW_RLE_END_OF_LIST => return Ok(res),
+                DW_RLE_BASE_ADDRESSX => {
+                    base = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    continue;
+                }
+                DW_RLE_BASE_ADDRESS => {
+                    base = reader.address()?;
+                    continue;
+                }
+                DW_RLE_STARTX_ENDX => {
+                    let start = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    (start, self.indexed_address(reader.uleb128()?, sections, &reader)?)
+                }
+                DW_RLE_STARTX_LENGTH => {
+                    let start = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    (start, start.wrapping_add(reader.uleb128()?))
+                }
+                DW_RLE_OFFSET_PAIR => {
+                    let start = base.wrapping_add(reader.uleb128()?);
+                    (start, base.wrapping_add(reader.uleb128()?))
+                }
+                DW_RLE_START_END => (reader.address()?, reader.address()?),
+                DW_RLE_START_LENGTH => {
+                    let start = reader.address()?;
+                    (start, start.wrapping_add(reader.uleb128()?))
+                }
+                x => return Err(DwarfError::UnknownForm(x as u64)),
+            };
+            if start != end {
+                res.push((start, end));
+            }
+        }
+    }
+
+    /// Where the variable or parameter of a DIE is
+    pub fn location(&self, value: &AttributeValue, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Option<Location> {
+        match value {
+            AttributeValue::Block(x) => Some(self.expression(x, sections, reader)),
+            x => match x.unsigned() {
+                Some(offset) if self.version >= 5 => self.location_list(offset, sections, reader).ok(),
+                Some(offset) => self.old_location_list(offset, sections, reader).ok(),
+                None => None,
+            },
+        }
+    }
+
+    // The place an expression names, if that is all it does
+    fn expression(&self, bytes: &[u8], sections: &DebugSections<'_>, reader: &Reader<'_>) -> Location {
+        let whole = Location::Expression(bytes.to_vec());
+        let mut expr = Reader::new(bytes, reader.endianness, self.address_size);
+        let res = match expr.u8() {
+            Ok(DW_OP_ADDR) => expr.address().map(Location::Address),
+            Ok(x @ DW_OP_REG0..=DW_OP_REG31) => Ok(Location::Register((x - DW_OP_REG0) as u64)),
+            Ok(x @ DW_OP_BREG0..=DW_OP_BREG31) => expr.sleb128().map(|offset| Location::RegisterOffset((x - DW_OP_BREG0) as u64, offset)),
+            Ok(DW_OP_REGX) => expr.uleb128().map(Location::Register),
+            Ok(DW_OP_FBREG) => expr.sleb128().map(Location::FrameOffset),
+            Ok(DW_OP_BREGX) => expr.uleb128().and_then(|register| Ok(Location::RegisterOffset(register, expr.sleb128()?))),
+            Ok(DW_OP_CALL_FRAME_CFA) => Ok(Location::CallFrameCfa),
+            Ok(DW_OP_ADDRX) | Ok(DW_OP_GNU_ADDR_INDEX) => {
+                expr.uleb128().and_then(|x| self.indexed_address(x, sections, reader)).map(Location::Address)
+            }
+            _ => return whole,
+        };
+        match res {
+            Ok(x) if expr.is_at_end() => x,
+            _ => whole,
+        }
+    }
+
+    // .debug_loc: pairs of addresses relative to the base, each followed by an expression
+    fn old_location_list(&self, offset: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<Location, DwarfError> {
+        let mut reader = Reader::new(sections.loc, reader.endianness, self.address_size);
+        reader.seek(offset as usize);
+        let mut base = self.base_address;
+        let mut res = Vec::new();
+        loop {
+            let start = reader.address()?;
+            let end = reader.address()?;
+            if start == 0 && end == 0 {
+                return Ok(Location::List(res));
+            }
+            if start == self.max_address() {
+                base = end;
+                continue;
+            }
+            let length = reader.u16()? as usize;
+            let location = self.expression(reader.bytes(length)?, sections, &reader);
+            res.push(LocationRange { start: base.wrapping_add(start), end: base.wrapping_add(end), location });
+        }
+    }
+
+    // .debug_loclists, at an offset in the section
+    fn location_list(&self, offset: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<Location, DwarfError> {
+        let mut reader = Reader::new(sections.loclists, reader.endianness, self.address_size);
+        reader.seek(offset as usize);
+        let mut base = self.base_address;
+        let mut res = Vec::new();
+        loop {
+            let (start, end) = match reader.u8()? {
+                DW_LLE_END_OF_LIST => return Ok(Location::List(res)),
+                DW_LLE_BASE_ADDRESSX => {
+                    base = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    continue;
+                }
+                DW_LLE_BASE_ADDRESS => {
+                    base = reader.address()?;
+                    continue;
+                }
+                // GCC gives the views of the next entry, which only debuggers stepping through care about
+                DW_LLE_GNU_VIEW_PAIR => {
+                    reader.uleb128()?;
+                    reader.uleb128()?;
+                    continue;
+                }
+                DW_LLE_STARTX_ENDX => {
+                    let start = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    (start, self.indexed_address(reader.uleb128()?, sections, &reader)?)
+                }
+                DW_LLE_STARTX_LENGTH => {
+                    let start = self.indexed_address(reader.uleb128()?, sections, &reader)?;
+                    (start, start.wrapping_add(reader.uleb128()?))
+                }
+                DW_LLE_OFFSET_PAIR => {
+                    let start = base.wrapping_add(reader.uleb128()?);
+                    (start, base.wrapping_add(reader.uleb128()?))
+                }
+                DW_LLE_DEFAULT_LOCATION => (0, 0),
+                DW_LLE_START_END => (reader.address()?, reader.address()?),
+                DW_LLE_START_LENGTH => {
+                    let start = reader.address()?;
+                    (start, start.wrapping_add(reader.uleb128()?))
+                }
+                x => return Err(DwarfError::UnknownForm(x as u64)),
+            };
+            let length = reader.uleb128()? as usize;
+            let location = self.expression(reader.bytes(length)?, sections, &reader);
+            res.push(LocationRange { start, end, location });
+        }
+    }
+
+    fn max_address(&self) -> u64 {
+        match self.address_size {
+            4 => 0xffff_ffff,
+            _ => u64::MAX,
+        }
+    }
+
+    // An entry of the unit's table in .debug_addr
+    fn indexed_address(&self, index: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<u64, DwarfError> {
+        let mut reader = Reader::new(sections.addr, reader.endianness, self.address_size);
+        reader.seek((self.addr_base + index * self.address_size as u64) as usize);
+        reader.address()
+    }
+
+    // An entry of the unit's table in .debug_str_offsets
+    fn indexed_string(&self, index: u64, sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<String, DwarfError> {
+        let mut offsets = Reader::new(sections.str_offsets, reader.endianness, self.address_size);
+        offsets.seek((self.str_offsets_base + index * self.offset_size()) as usize);
+        let offset = offsets.section_offset(self.dwarf64)?;
+        string_at(sections.str, offset, reader)
+    }
+
+    // The offset a range or location list index stands for, from the offset table at the base
+    fn indexed_list(&self, index: u64, base: u64, section: &[u8], reader: &Reader<'_>) -> Result<u64, DwarfError> {
+        let mut offsets = Reader::new(section, reader.endianness, self.address_size);
+        offsets.seek((base + index * self.offset_size()) as usize);
+        Ok(base + offsets.section_offset(self.dwarf64)?)
+    }
+}
+
+/// The NUL terminated string at an offset in a string section
+pub(super) fn string_at(section: &[u8], offset: u64, reader: &Reader<'_>) -> Result<String, DwarfError> {
+    let mut reader = Reader::new(section, reader.endianness, reader.address_size);
+    reader.seek(offset as usize);
+    Ok(String::from_utf8_lossy(reader.cstr()?).into_owned())
+}
+
+/// Parses all the units of .debug_info
+pub fn parse_units(sections: &DebugSections<'_>, reader: &Reader<'_>) -> Result<Vec<Unit>, DwarfError> {
+    let abbrev_reader = Reader::new(sections.abbrev, reader.endianness, reader.address_size);
+    let mut abbreviations: BTreeMap<u64, BTreeMap<u64, Abbreviation>> = BTreeMap::new();
+    let mut reader = reader.clone();
+    reader.seek(0);
+    let mut res = Vec::new();
+    while !reader.is_at_end() {
+        let offset = reader.offset() as u64;
+        let (length, dwarf64) = reader.initial_length()?;
+        let end = reader.offset() + length as usize;
+        let version = reader.u16()?;
+        let (unit_type, address_size, abbrev_offset) = match version {
+            2..=4 => {
+                let abbrev_offset = reader.section_offset(dwarf64)?;
+                (0, reader.u8()?, abbrev_offset)
+            }
+            5 => {
+                let unit_type = reader.u8()?;
+                let address_size = reader.u8()?;
+                (unit_type, address_size, reader.section_offset(dwarf64)?)
+            }
+            x => return Err(DwarfError::UnknownVersion(x)),
+        };
+        match unit_type {
+            DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => {
+                // DWO id
+                reader.u64()?;
+            }
+            DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+                // Type signature and offset
+                reader.u64()?;
+                reader.section_offset(dwarf64)?;
+            }
+            _ => {}
+        }
+        if let Entry::Vacant(entry) = abbreviations.entry(abbrev_offset) {
+            entry.insert(parse_abbreviations(&abbrev_reader, abbrev_offset)?);
+        }
+
+        // The tables of DWARF 5 start after a header, where the bases point when the root doesn't give them
+        let header_size = if dwarf64 { 16 } else { 8 };
+        let mut unit = Unit {
+            offset,
+            version,
+            unit_type,
+            dwarf64,
+            address_size,
+            abbrev_offset,
+            str_offsets_base: header_size,
+            addr_base: header_size,
+            rnglists_base: header_size + if dwarf64 { 4 } else { 0 },
+            loclists_base: header_size + if dwarf64 { 4 } else { 0 },
+            base_address: 0,
+            dies: Vec::new(),
+        };
+        let mut unit_reader = reader.clone();
+        unit_reader.address_size = address_size;
+        parse_dies(&mut unit_reader, end, &mut unit, &abbreviations[&abbrev_offset], sections)?;
+        resolve(&mut unit, sections, &unit_reader);
+        res.push(unit);
+        reader.seek(end);
+    }
+    Ok(res)
+}
+
+fn parse_dies(
+    reader: &mut Reader<'_>,
+    end: usize,
+    unit: &mut Unit,
+    abbreviations: &BTreeMap<u64, Abbreviation>,
+    sections: &DebugSections<'_>,
+) -> Result<(), DwarfError> {
+    // The DIEs whose children come next
+    let mut parents: Vec<usize> = Vec::new();
+    while reader.offset() < end {
+        let offset = reader.offset() as u64;
+        let code = reader.uleb128()?;
+        if code == 0 {
+            parents.pop();
+            continue;
+        }
+        let abbreviation = abbreviations.get(&code).ok_or(DwarfError::UnknownAbbreviation(code))?;
+        let mut attributes = Vec::with_capacity(abbreviation.attributes.len());
+        for spec in abbreviation.attributes.iter() {
+            attributes.push((spec.name, attribute(reader, spec, spec.form, unit, sections)?));
+        }
+        let index = unit.dies.len();
+        let parent = parents.last().copied();
+        if let Some(parent) = parent {
+            unit.dies[parent].children.push(index);
+        }
+        unit.dies.push(Die { offset, tag: abbreviation.tag, attributes, parent, children: Vec::new() });
+        if abbreviation.children {
+            parents.push(index);
+        }
+    }
+    Ok(())
+}
+
+fn attribute(
+    reader: &mut Reader<'_>,
+    spec: &AttributeSpec,
+    form: u64,
+    unit: &Unit,
+    sections: &DebugSections<'_>,
+) -> Result<AttributeValue, DwarfError> {
+    let dwarf64 = unit.dwarf64;
+    Ok(match form {
+        DW_FORM_ADDR => AttributeValue::Address(reader.address()?),
+        DW_FORM_DATA1 => AttributeValue::Unsigned(reader.u8()? as u64),
+        DW_FORM_DATA2 => AttributeValue::Unsigned(reader.u16()? as u64),
+        DW_FORM_DATA4 => AttributeValue::Unsigned(reader.u32()? as u64),
+        DW_FORM_DATA8 => AttributeValue::Unsigned(reader.u64()?),
+        DW_FORM_UDATA => AttributeValue::Unsigned(reader.uleb128()?),
+        DW_FORM_SDATA => AttributeValue::Signed(reader.sleb128()?),
+        DW_FORM_IMPLICIT_CONST => AttributeValue::Signed(spec.implicit_const),
+        DW_FORM_FLAG => AttributeValue::Flag(reader.u8()? != 0),
+        DW_FORM_FLAG_PRESENT => AttributeValue::Flag(true),
+        DW_FORM_STRING => AttributeValue::String(String::from_utf8_lossy(reader.cstr()?).into_owned()),
+        DW_FORM_STRP => AttributeValue::String(string_at(sections.str, reader.section_offset(dwarf64)?, reader)?),
+        DW_FORM_LINE_STRP => AttributeValue::String(string_at(sections.line_str, reader.section_offset(dwarf64)?, reader)?),
+        DW_FORM_STRP_SUP | DW_FORM_GNU_STRP_ALT | DW_FORM_GNU_REF_ALT => AttributeValue::SectionOffset(reader.section_offset(dwarf64)?),
+        DW_FORM_STRX | DW_FORM_GNU_STR_INDEX => AttributeValue::StringIndex(reader.uleb128()?),
+        DW_FORM_STRX1 => AttributeValue::StringIndex(reader.u8()? as u64),
+        DW_FORM_STRX2 => AttributeValue::StringIndex(reader.u16()? as u64),
+        DW_FORM_STRX3 => AttributeValue::StringIndex(u24(reader)?),
+        DW_FORM_STRX4 => AttributeValue::StringIndex(reader.u32()? as u64),
+        DW_FORM_ADDRX | DW_FORM_GNU_ADDR_INDEX => AttributeValue::AddressIndex(reader.uleb128()?),
+        DW_FORM_ADDRX1 => AttributeValue::AddressIndex(reader.u8()? as u64),
+        DW_FORM_ADDRX2 => AttributeValue::AddressIndex(reader.u16()? as u64),
+        DW_FORM_ADDRX3 => AttributeValue::AddressIndex(u24(reader)?),
+        DW_FORM_ADDRX4 => AttributeValue::AddressIndex(reader.u32()? as u64),
+        DW_FORM_BLOCK1 => {
+            let length = reader.u8()? as usize;
+            AttributeValue::Block(reader.bytes(length)?.to_vec())
+        }
+        DW_FORM_BLOCK2 => {
+            let length = reader.u16()? as usize;
+            AttributeValue::Block(reader.bytes(length)?.to_vec())
+        }
+        DW_FORM_BLOCK4 => {
+            let length = reader.u32()? as usize;
+            AttributeValue::Block(reader.bytes(length)?.to_vec())
+        }
+        DW_FORM_BLOCK | DW_FORM_EXPRLOC => {
+            let length = reader.uleb128()? as usize;
+            AttributeValue::Block(reader.bytes(length)?.to_vec())
+        }
+        DW_FORM_DATA16 => AttributeValue::Block(reader.bytes(16)?.to_vec()),
+        // References within the unit are relative to its start
+        DW_FORM_REF1 => AttributeValue::Reference(unit.offset + reader.u8()? as u64),
+        DW_FORM_REF2 => AttributeValue::Reference(unit.offset + reader.u16()? as u64),
+        DW_FORM_REF4 => AttributeValue::Reference(unit.offset + reader.u32()? as u64),
+        DW_FORM_REF8 => AttributeValue::Reference(unit.offset + reader.u64()?),
+        DW_FORM_REF_UDATA => AttributeValue::Reference(unit.offset + reader.uleb128()?),
+        // DWARF 2 gave these the size of an address
+        DW_FORM_REF_ADDR if unit.version == 2 => AttributeValue::Reference(reader.address()?),
+        DW_FORM_REF_ADDR => AttributeValue::Reference(reader.section_offset(dwarf64)?),
+        DW_FORM_REF_SUP4 => AttributeValue::SectionOffset(reader.u32()? as u64),
+        DW_FORM_REF_SUP8 => AttributeValue::SectionOffset(reader.u64()?),
+        DW_FORM_REF_SIG8 => AttributeValue::Signature(reader.u64()?),
+        DW_FORM_SEC_OFFSET => AttributeValue::SectionOffset(reader.section_offset(dwarf64)?),
+        DW_FORM_LOCLISTX => AttributeValue::LocationListIndex(reader.uleb128()?),
+        DW_FORM_RNGLISTX => AttributeValue::RangeListIndex(reader.uleb128()?),
+        DW_FORM_INDIRECT => {
+            let form = reader.uleb128()?;
+            return attribute(reader, spec, form, unit, sections);
+        }
+        x => return Err(DwarfError::UnknownForm(x)),
+    })
+}
+
+fn u24(reader: &mut Reader<'_>) -> Result<u64, DwarfError> {
+    let bytes = reader.bytes(3)?;
+    Ok(match reader.endianness {
+        Endianness::LittleEndian => bytes[0] as u64 | (bytes[1] as u64) << 8 | (bytes[2] as u64) << 16,
+        Endianness::BigEndian => bytes[2] as u64 | (bytes[1] as u64) << 8 | (bytes[0] as u64) << 16,
+    })
+}
+
+// Takes the bases from the root and replaces the indices with what they stand for. Indices that lead nowhere are
+// left as they are.
+fn resolve(unit: &mut Unit, sections: &DebugSections<'_>, reader: &Reader<'_>) {
+    if let Some(root) = unit.dies.first() {
+        let base = |name| root.attribute(name).and_then(|x| x.unsigned());
+        if let Some(x) = base(DW_AT_STR_OFFSETS_BASE) {
+            unit.str_offsets_base = x;
+        }
+        if let Some(x) = base(DW_AT_ADDR_BASE).or_else(|| base(DW_AT_GNU_ADDR_BASE)) {
+            unit.addr_base = x;
+        }
+        if let Some(x) = base(DW_AT_RNGLISTS_BASE) {
+            unit.rnglists_base = x;
+        }
+        if let Some(x) = base(DW_AT_LOCLISTS_BASE) {
+            unit.loclists_base = x;
+        }
+    }
+
+    let mut dies = std::mem::take(&mut unit.dies);
+    for die in dies.iter_mut() {
+        for (_, value) in die.attributes.iter_mut() {
+            let resolved = match *value {
+                AttributeValue::StringIndex(x) => unit.indexed_string(x, sections, reader).map(AttributeValue::String),
+                AttributeValue::AddressIndex(x) => unit.indexed_address(x, sections, reader).map(AttributeValue::Address),
+                AttributeValue::RangeListIndex(x) => {
+                    unit.indexed_list(x, unit.rnglists_base, sections.rnglists, reader).map(AttributeValue::SectionOffset)
+                }
+                AttributeValue::LocationListIndex(x) => {
+                    unit.indexed_list(x, unit.loclists_base, sections.loclists, reader).map(AttributeValue::SectionOffset)
+                }
+                _ => continue,
+            };
+            if let Ok(x) = resolved {
+                *value = x;
+            }
+        }
+    }
+    if let Some(AttributeValue::Address(x)) = dies.first().and_then(|x| x.attribute(DW_AT_LOW_PC)) {
+        unit.base_address = *x;
+    }
+    unit.dies = dies;
+}
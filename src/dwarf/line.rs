@@ -0,0 +1,246 @@
+use super::constants::*;
+use super::info::{string_at, AttributeValue, DebugSections};
+use super::{DwarfError, Reader};
+
+/// A source file of a line number program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    pub path: String,
+    // Index in the directories of the program
+    pub directory: u64,
+}
+
+/// A row of the line table: the code from `address` up to the next row comes from this place in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    // Index in the files of the program
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+    // A place to put a breakpoint on the line
+    pub is_stmt: bool,
+    // The first address after a sequence, which belongs to no line
+    pub end_sequence: bool,
+}
+
+/// A line number program of .debug_line, run into its rows
+#[derive(Debug, Clone, Default)]
+pub struct LineProgram {
+    pub offset: u64,
+    pub version: u16,
+    // Indexed the DWARF 5 way: the directory and file of the compilation are the first ones for all versions
+    pub directories: Vec<String>,
+    pub files: Vec<FileEntry>,
+    pub rows: Vec<LineRow>,
+}
+
+impl LineProgram {
+    /// Runs the program at `offset` in .debug_line. The directory and name of the compilation unit stand for the
+    /// entries 0 of the tables, which versions before 5 don't have.
+    pub fn parse(
+        sections: &DebugSections<'_>,
+        reader: &Reader<'_>,
+        offset: u64,
+        comp_dir: Option<&str>,
+        name: Option<&str>,
+    ) -> Result<LineProgram, DwarfError> {
+        let mut reader = Reader::new(sections.line, reader.endianness, reader.address_size);
+        reader.seek(offset as usize);
+        let (length, dwarf64) = reader.initial_length()?;
+        let end = reader.offset() + length as usize;
+        let version = reader.u16()?;
+        if !(2..=5).contains(&version) {
+            return Err(DwarfError::UnknownVersion(version));
+        }
+        if version >= 5 {
+            reader.address_size = reader.u8()?;
+            // Segment selector size
+            reader.u8()?;
+        }
+        let header_length = reader.section_offset(dwarf64)?;
+        let program_start = reader.offset() + header_length as usize;
+        let minimum_instruction_length = reader.u8()? as u64;
+        if version >= 4 {
+            // Maximum operations per instruction, which only VLIW architectures have more than one of
+            reader.u8()?;
+        }
+        let default_is_stmt = reader.u8()? != 0;
+        let line_base = reader.u8()? as i8 as i64;
+        let line_range = reader.u8()?.max(1) as u64;
+        let opcode_base = reader.u8()?;
+        let standard_opcode_lengths = reader.bytes(opcode_base.saturating_sub(1) as usize)?.to_vec();
+
+        let mut program = LineProgram { offset, version, ..LineProgram::default() };
+        if version >= 5 {
+            for fields in entries(&mut reader, dwarf64, sections)? {
+                program.directories.push(fields.path);
+            }
+            for fields in entries(&mut reader, dwarf64, sections)? {
+                program.files.push(FileEntry { path: fields.path, directory: fields.directory });
+            }
+        } else {
+            program.directories.push(comp_dir.unwrap_or("").to_string());
+            program.files.push(FileEntry { path: name.unwrap_or("").to_string(), directory: 0 });
+            loop {
+                let directory = reader.cstr()?;
+                if directory.is_empty() {
+                    break;
+                }
+                program.directories.push(String::from_utf8_lossy(directory).into_owned());
+            }
+            loop {
+                let path = reader.cstr()?;
+                if path.is_empty() {
+                    break;
+                }
+                program.files.push(old_file_entry(&mut reader, path)?);
+            }
+        }
+
+        reader.seek(program_start);
+        // The registers of the state machine, which each row is a copy of
+        let initial = LineRow { address: 0, file: 1, line: 1, column: 0, is_stmt: default_is_stmt, end_sequence: false };
+        let mut state = initial;
+        // The address a special opcode or DW_LNS_const_add_pc adds
+        let advance = |opcode: u8| (opcode.wrapping_sub(opcode_base) as u64 / line_range) * minimum_instruction_length;
+        while reader.offset() < end {
+            let opcode = reader.u8()?;
+            if opcode >= opcode_base {
+                let adjusted = (opcode - opcode_base) as u64;
+                state.address = state.address.wrapping_add(advance(opcode));
+                state.line = (state.line as i64 + line_base + (adjusted % line_range) as i64) as u64;
+                program.rows.push(state);
+                continue;
+            }
+            match opcode {
+                0 => {
+                    let length = reader.uleb128()? as usize;
+                    let next = reader.offset() + length;
+                    match reader.u8()? {
+                        DW_LNE_END_SEQUENCE => {
+                            state.end_sequence = true;
+                            program.rows.push(state);
+                            state = initial;
+                        }
+                        DW_LNE_SET_ADDRESS => state.address = reader.sized(length.saturating_sub(1) as u8)?,
+                        DW_LNE_DEFINE_FILE => {
+                            let path = reader.cstr()?;
+                            program.files.push(old_file_entry(&mut reader, path)?);
+                        }
+                        // Discriminators and vendor opcodes
+                        _ => {}
+                    }
+                    reader.seek(next);
+                }
+                DW_LNS_COPY => program.rows.push(state),
+                DW_LNS_ADVANCE_PC => {
+                    let operand = reader.uleb128()?;
+                    state.address = state.address.wrapping_add(operand * minimum_instruction_length);
+                }
+                DW_LNS_ADVANCE_LINE => state.line = (state.line as i64).wrapping_add(reader.sleb128()?) as u64,
+                DW_LNS_SET_FILE => state.file = reader.uleb128()?,
+                DW_LNS_SET_COLUMN => state.column = reader.uleb128()?,
+                DW_LNS_NEGATE_STMT => state.is_stmt = !state.is_stmt,
+                DW_LNS_CONST_ADD_PC => state.address = state.address.wrapping_add(advance(255)),
+                DW_LNS_FIXED_ADVANCE_PC => state.address = state.address.wrapping_add(reader.u16()? as u64),
+                DW_LNS_SET_ISA => {
+                    reader.uleb128()?;
+                }
+                DW_LNS_SET_BASIC_BLOCK | DW_LNS_SET_PROLOGUE_END | DW_LNS_SET_EPILOGUE_BEGIN => {}
+                // Opcodes of later versions, skipped by the number of operands the header gives
+                _ => {
+                    for _ in 0..standard_opcode_lengths[opcode as usize - 1] {
+                        reader.uleb128()?;
+                    }
+                }
+            }
+        }
+        Ok(program)
+    }
+
+    /// The path of a file, with its directory unless it is absolute
+    pub fn file(&self, index: u64) -> Option<String> {
+        let file = self.files.get(index as usize)?;
+        let directory = match self.directories.get(file.directory as usize) {
+            Some(x) if !file.path.starts_with('/') && !x.is_empty() => x,
+            _ => return Some(file.path.clone()),
+        };
+        Some(format!("{}/{}", directory.trim_end_matches('/'), file.path))
+    }
+
+    /// The row covering an address
+    pub fn row_for(&self, address: u64) -> Option<&LineRow> {
+        // Rows are sorted within a sequence, but sequences come in any order
+        let mut res: Option<&LineRow> = None;
+        for (row, next) in self.rows.iter().zip(self.rows.iter().skip(1)) {
+            let covers = !row.end_sequence && row.address <= address && address < next.address;
+            if covers && res.is_none_or(|x| x.address <= row.address) {
+                res = Some(row);
+            }
+        }
+        res
+    }
+}
+
+// What the files of DWARF 4 and before give after their path
+fn old_file_entry(reader: &mut Reader<'_>, path: &[u8]) -> Result<FileEntry, DwarfError> {
+    let directory = reader.uleb128()?;
+    // Modification time and length
+    reader.uleb128()?;
+    reader.uleb128()?;
+    Ok(FileEntry { path: String::from_utf8_lossy(path).into_owned(), directory })
+}
+
+#[derive(Debug, Clone, Default)]
+struct EntryFields {
+    path: String,
+    directory: u64,
+}
+
+// A DWARF 5 directory or file table: the format of the entries then the entries
+fn entries(reader: &mut Reader<'_>, dwarf64: bool, sections: &DebugSections<'_>) -> Result<Vec<EntryFields>, DwarfError> {
+    let format_count = reader.u8()?;
+    let mut format = Vec::new();
+    for _ in 0..format_count {
+        format.push((reader.uleb128()?, reader.uleb128()?));
+    }
+    let count = reader.uleb128()?;
+    let mut res = Vec::new();
+    for _ in 0..count {
+        let mut fields = EntryFields::default();
+        for (content, form) in format.iter() {
+            let value = entry_value(reader, *form, dwarf64, sections)?;
+            match *content {
+                DW_LNCT_PATH => fields.path = value.string().unwrap_or("").to_string(),
+                DW_LNCT_DIRECTORY_INDEX => fields.directory = value.unsigned().unwrap_or(0),
+                // Timestamps, sizes, MD5 sums and vendor contents
+                _ => {}
+            }
+        }
+        res.push(fields);
+    }
+    Ok(res)
+}
+
+// The forms the entries of the tables use. Strings by index need the bases of a unit and are left empty.
+fn entry_value(reader: &mut Reader<'_>, form: u64, dwarf64: bool, sections: &DebugSections<'_>) -> Result<AttributeValue, DwarfError> {
+    Ok(match form {
+        DW_FORM_STRING => AttributeValue::String(String::from_utf8_lossy(reader.cstr()?).into_owned()),
+        DW_FORM_LINE_STRP => AttributeValue::String(string_at(sections.line_str, reader.section_offset(dwarf64)?, reader)?),
+        DW_FORM_STRP => AttributeValue::String(string_at(sections.str, reader.section_offset(dwarf64)?, reader)?),
+        DW_FORM_STRP_SUP => AttributeValue::SectionOffset(reader.section_offset(dwarf64)?),
+        DW_FORM_STRX | DW_FORM_UDATA => AttributeValue::Unsigned(reader.uleb128()?),
+        DW_FORM_STRX1 | DW_FORM_DATA1 => AttributeValue::Unsigned(reader.u8()? as u64),
+        DW_FORM_STRX2 | DW_FORM_DATA2 => AttributeValue::Unsigned(reader.u16()? as u64),
+        DW_FORM_STRX3 => AttributeValue::Block(reader.bytes(3)?.to_vec()),
+        DW_FORM_STRX4 | DW_FORM_DATA4 => AttributeValue::Unsigned(reader.u32()? as u64),
+        DW_FORM_DATA8 => AttributeValue::Unsigned(reader.u64()?),
+        DW_FORM_DATA16 => AttributeValue::Block(reader.bytes(16)?.to_vec()),
+        DW_FORM_BLOCK => {
+            let length = reader.uleb128()? as usize;
+            AttributeValue::Block(reader.bytes(length)?.to_vec())
+        }
+        x => return Err(DwarfError::UnknownForm(x)),
+    })
+}
@@ -0,0 +1,47 @@
+use super::reader::{Bases, Reader, DW_EH_PE_OMIT};
+use super::DwarfError;
+
+/// A range of a function whose exceptions land somewhere, from the call-site table of an LSDA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub struct CallSite {
+    pub start: u64,
+    pub length: u64,
+    // Where an exception thrown in the range continues, None if it keeps unwinding
+    pub landing_pad: Option<u64>,
+    // 1 + offset into the action table, 0 for cleanups only
+    pub action: u64,
+}
+
+/// Parses the call-site table of the language specific data area at the reader's position, as found in
+/// .gcc_except_table. Offsets in it are relative to the start of the function.
+pub fn parse_lsda(reader: &mut Reader<'_>, function_start: u64, bases: &Bases) -> Result<Vec<CallSite>, DwarfError> {
+    let bases = Bases { function: Some(function_start), ..*bases };
+    let landing_pad_start = match reader.u8()? {
+        DW_EH_PE_OMIT => function_start,
+        encoding => reader.encoded_pointer(encoding, &bases)?,
+    };
+    // Types of the catch clauses, which are not needed to know where exceptions land
+    if reader.u8()? != DW_EH_PE_OMIT {
+        reader.uleb128()?;
+    }
+    let encoding = reader.u8()?;
+    let length = reader.uleb128()? as usize;
+    let end = reader.offset() + length;
+
+    let mut res = Vec::new();
+    while reader.offset() < end {
+        // The values are plain offsets, only their format counts
+        let start = reader.encoded_pointer(encoding & 0x0f, &bases)?;
+        let length = reader.encoded_pointer(encoding & 0x0f, &bases)?;
+        let landing_pad = reader.encoded_pointer(encoding & 0x0f, &bases)?;
+        let action = reader.uleb128()?;
+        res.push(CallSite {
+            start: function_start.wrapping_add(start),
+            length,
+            landing_pad: Some(landing_pad).filter(|x| *x != 0).map(|x| landing_pad_start.wrapping_add(x)),
+            action,
+        });
+    }
+    Ok(res)
+}
@@ -0,0 +1,37 @@
+mod error;
+pub use self::error::DwarfError;
+
+mod reader;
+pub use self::reader::{Bases, Reader};
+
+mod frame;
+pub use self::frame::{Cie, Fde, FrameKind, FrameTable};
+
+mod lsda;
+pub use self::lsda::{parse_lsda, CallSite};
+
+pub mod constants;
+
+mod abbrev;
+pub use self::abbrev::{Abbreviation, AttributeSpec};
+
+mod info;
+pub use self::info::{AttributeValue, DebugSections, Die, Location, LocationRange, Unit};
+
+mod line;
+pub use self::line::{FileEntry, LineProgram, LineRow};
+
+mod debuginfo;
+pub use self::debuginfo::{DebugInfo, FunctionRecord, Member, TypeKind, TypeRecord, VariableRecord};
+
+mod cfa;
+pub use self::cfa::{CfaRule, RegisterRule, UnwindRow};
+
+mod expression;
+pub use self::expression::evaluate;
+
+mod header;
+pub use self::header::EhFrameHeader;
+
+mod unwind;
+pub use self::unwind::{register_name, stack_pointer, Frame, Registers, Unwinder};
@@ -0,0 +1,192 @@
+use crate::endian::Endianness;
+use crate::error::GenericParseError;
+use crate::parsable_file::ParsableFile;
+
+use super::DwarfError;
+
+// Pointer encodings of .eh_frame (DW_EH_PE_*), the format in the low nibble and how to apply it above
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+const DW_EH_PE_ABSPTR: u8 = 0x00;
+const DW_EH_PE_ULEB128: u8 = 0x01;
+const DW_EH_PE_UDATA2: u8 = 0x02;
+const DW_EH_PE_UDATA4: u8 = 0x03;
+const DW_EH_PE_UDATA8: u8 = 0x04;
+const DW_EH_PE_SLEB128: u8 = 0x09;
+const DW_EH_PE_SDATA2: u8 = 0x0a;
+const DW_EH_PE_SDATA4: u8 = 0x0b;
+const DW_EH_PE_SDATA8: u8 = 0x0c;
+const DW_EH_PE_PCREL: u8 = 0x10;
+const DW_EH_PE_TEXTREL: u8 = 0x20;
+const DW_EH_PE_DATAREL: u8 = 0x30;
+const DW_EH_PE_FUNCREL: u8 = 0x40;
+const DW_EH_PE_ALIGNED: u8 = 0x50;
+
+/// Addresses encoded pointers can be relative to
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bases {
+    // Address of the start of the section being read
+    pub section: u64,
+    pub text: Option<u64>,
+    pub data: Option<u64>,
+    // Start of the function, for pointers in FDEs
+    pub function: Option<u64>,
+}
+
+/// Cursor over the contents of a debug section
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    file: ParsableFile<'a>,
+    len: usize,
+    pub endianness: Endianness,
+    pub address_size: u8,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8], endianness: Endianness, address_size: u8) -> Reader<'a> {
+        Reader { file: ParsableFile::new(data), len: data.len(), endianness, address_size }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.file.get_cursor()
+    }
+
+    pub fn seek(&mut self, offset: usize) {
+        self.file.move_to(offset);
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.offset() >= self.len
+    }
+
+    pub fn bytes(&mut self, n: usize) -> Result<&'a [u8], DwarfError> {
+        Ok(self.file.read_n_bytes(n)?)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DwarfError> {
+        Ok(self.endianness.read_u8(&mut self.file)?)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, DwarfError> {
+        Ok(self.endianness.read_u16(&mut self.file)?)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DwarfError> {
+        Ok(self.endianness.read_u32(&mut self.file)?)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DwarfError> {
+        Ok(self.endianness.read_u64(&mut self.file)?)
+    }
+
+    pub fn uleb128(&mut self) -> Result<u64, DwarfError> {
+        let mut res = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                res |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(res);
+            }
+        }
+    }
+
+    pub fn sleb128(&mut self) -> Result<i64, DwarfError> {
+        let mut res = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                res |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    res |= -1i64 << shift;
+                }
+                return Ok(res);
+            }
+        }
+    }
+
+    /// An unsigned value of `size` bytes
+    pub fn sized(&mut self, size: u8) -> Result<u64, DwarfError> {
+        match size {
+            1 => Ok(self.u8()? as u64),
+            2 => Ok(self.u16()? as u64),
+            4 => Ok(self.u32()? as u64),
+            8 => self.u64(),
+            _ => Err(GenericParseError::PtrTooLarge(size as u64).into()),
+        }
+    }
+
+    pub fn address(&mut self) -> Result<u64, DwarfError> {
+        self.sized(self.address_size)
+    }
+
+    /// The length that starts units and entries, and whether it makes them use the 64-bit format
+    pub fn initial_length(&mut self) -> Result<(u64, bool), DwarfError> {
+        Ok(match self.u32()? {
+            0xffff_ffff => (self.u64()?, true),
+            x => (x as u64, false),
+        })
+    }
+
+    /// An offset into another section, 8 bytes in the 64-bit format
+    pub fn section_offset(&mut self, dwarf64: bool) -> Result<u64, DwarfError> {
+        if dwarf64 {
+            self.u64()
+        } else {
+            Ok(self.u32()? as u64)
+        }
+    }
+
+    /// A NUL terminated string, without the NUL
+    pub fn cstr(&mut self) -> Result<&'a [u8], DwarfError> {
+        let start = self.offset();
+        while self.u8()? != 0 {}
+        let end = self.offset();
+        self.seek(start);
+        let res = self.bytes(end - start - 1)?;
+        self.seek(end);
+        Ok(res)
+    }
+
+    /// A pointer in one of the DW_EH_PE_* encodings. Indirect pointers (0x80) give the address of the pointer, as
+    /// only the memory of the process holds its value.
+    pub fn encoded_pointer(&mut self, encoding: u8, bases: &Bases) -> Result<u64, DwarfError> {
+        let here = bases.section.wrapping_add(self.offset() as u64);
+        if encoding & 0x70 == DW_EH_PE_ALIGNED {
+            let size = self.address_size as usize;
+            self.seek(self.offset().div_ceil(size) * size);
+        }
+        let value = match encoding & 0x0f {
+            DW_EH_PE_ABSPTR => self.address()?,
+            DW_EH_PE_ULEB128 => self.uleb128()?,
+            DW_EH_PE_UDATA2 => self.u16()? as u64,
+            DW_EH_PE_UDATA4 => self.u32()? as u64,
+            DW_EH_PE_UDATA8 => self.u64()?,
+            DW_EH_PE_SLEB128 => self.sleb128()? as u64,
+            DW_EH_PE_SDATA2 => self.u16()? as i16 as u64,
+            DW_EH_PE_SDATA4 => self.u32()? as i32 as u64,
+            DW_EH_PE_SDATA8 => self.u64()?,
+            _ => return Err(DwarfError::UnknownPointerEncoding(encoding)),
+        };
+        let base = match encoding & 0x70 {
+            DW_EH_PE_ABSPTR | DW_EH_PE_ALIGNED => Some(0),
+            DW_EH_PE_PCREL => Some(here),
+            DW_EH_PE_TEXTREL => bases.text,
+            DW_EH_PE_DATAREL => bases.data,
+            DW_EH_PE_FUNCREL => bases.function,
+            _ => None,
+        };
+        let base = base.ok_or(DwarfError::UnknownPointerEncoding(encoding))?;
+        let value = base.wrapping_add(value);
+        Ok(match self.address_size {
+            4 => value & 0xffff_ffff,
+            _ => value,
+        })
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+
+use crate::endian::Endianness;
+use crate::instruction_set::InstructionSet;
+
+use super::cfa::{CfaRule, RegisterRule, UnwindRow};
+use super::expression::evaluate;
+use super::frame::{Cie, FrameTable};
+use super::Reader;
+
+/// Values of registers by DWARF number
+pub type Registers = BTreeMap<u64, u64>;
+
+/// A frame of an unwound stack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub pc: u64,
+    // The registers as far as they are known when the frame runs at pc
+    pub registers: Registers,
+}
+
+/// The DWARF number of the stack pointer, which the CFA of most code is relative to
+pub fn stack_pointer(instruction_set: InstructionSet) -> Option<u64> {
+    match instruction_set {
+        InstructionSet::X86 => Some(4),
+        InstructionSet::X86_64 => Some(7),
+        InstructionSet::AArch64 => Some(31),
+        InstructionSet::ARM => Some(13),
+        InstructionSet::RISC_V => Some(2),
+        InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => Some(29),
+        InstructionSet::PowerPC | InstructionSet::PowerPC64 => Some(1),
+        InstructionSet::SPARC | InstructionSet::SPARC32Plus | InstructionSet::SPARC_V9 => Some(14),
+        InstructionSet::S390 => Some(15),
+        InstructionSet::LoongArch => Some(3),
+        _ => None,
+    }
+}
+
+/// The name of a register by its DWARF number, rN for the ones this doesn't know
+pub fn register_name(instruction_set: InstructionSet, number: u64) -> String {
+    const X86: [&str; 9] = ["eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi", "eip"];
+    const X86_64: [&str; 17] =
+        ["rax", "rdx", "rcx", "rbx", "rsi", "rdi", "rbp", "rsp", "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15", "rip"];
+    const RISC_V: [&str; 32] = [
+        "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "s2", "s3", "s4",
+        "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+    ];
+    let n = number as usize;
+    match instruction_set {
+        InstructionSet::X86 if n < X86.len() => X86[n].to_string(),
+        InstructionSet::X86_64 if n < X86_64.len() => X86_64[n].to_string(),
+        InstructionSet::X86_64 if (17..33).contains(&n) => format!("xmm{}", n - 17),
+        InstructionSet::AArch64 if n < 31 => format!("x{}", n),
+        InstructionSet::AArch64 if n == 31 => "sp".to_string(),
+        InstructionSet::AArch64 if (64..96).contains(&n) => format!("v{}", n - 64),
+        InstructionSet::ARM if n == 13 => "sp".to_string(),
+        InstructionSet::ARM if n == 14 => "lr".to_string(),
+        InstructionSet::ARM if n == 15 => "pc".to_string(),
+        InstructionSet::ARM if n < 13 => format!("r{}", n),
+        InstructionSet::ARM if (256..288).contains(&n) => format!("d{}", n - 256),
+        InstructionSet::RISC_V if n < RISC_V.len() => RISC_V[n].to_string(),
+        InstructionSet::RISC_V if (32..64).contains(&n) => format!("f{}", n - 32),
+        InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE if n < 32 => format!("${}", n),
+        InstructionSet::PowerPC | InstructionSet::PowerPC64 if n == 65 => "lr".to_string(),
+        _ => format!("r{}", n),
+    }
+}
+
+/// Unwinds stacks with the rules of call frame tables. Registers and memory come from outside, such as a core file or
+/// a debugger, so stacks can be unwound offline.
+#[derive(Debug, Clone)]
+pub struct Unwinder<'a> {
+    // Searched in order
+    tables: Vec<&'a FrameTable>,
+    stack_pointer: u64,
+    endianness: Endianness,
+    address_size: u8,
+}
+
+impl<'a> Unwinder<'a> {
+    pub fn new(tables: Vec<&'a FrameTable>, stack_pointer: u64, endianness: Endianness, address_size: u8) -> Unwinder<'a> {
+        Unwinder { tables, stack_pointer, endianness, address_size }
+    }
+
+    /// The rules at an address, with the CIE they come from
+    pub fn row_at(&self, address: u64) -> Option<(UnwindRow, &'a Cie)> {
+        self.tables.iter().find_map(|table| {
+            let fde = table.fde_for(address)?;
+            let cie = table.cies.get(&fde.cie)?;
+            let row = table.rows(fde).ok()?.into_iter().find(|x| x.contains(address))?;
+            Some((row, cie))
+        })
+    }
+
+    /// The frame of the caller. Frames other than the innermost one are at a return address, which can be past
+    /// the end of the calling function, so their rules are looked up an instruction earlier. Memory is read as values
+    /// of a given size at an address.
+    pub fn step(&self, frame: &Frame, innermost: bool, memory: &dyn Fn(u64, u8) -> Option<u64>) -> Option<Frame> {
+        let lookup = if innermost { frame.pc } else { frame.pc.wrapping_sub(1) };
+        let (mut row, cie) = self.row_at(lookup)?;
+        if !innermost && cie.signal_frame {
+            row = self.row_at(frame.pc)?.0;
+        }
+        let reader = Reader::new(&[], self.endianness, self.address_size);
+        let register = |x: u64| frame.registers.get(&x).copied();
+        let cfa = match &row.cfa {
+            CfaRule::RegisterOffset(number, offset) => register(*number)?.wrapping_add(*offset as u64),
+            CfaRule::Expression(bytes) => evaluate(bytes, &reader, &[], &register, memory)?,
+        };
+
+        let mut registers = frame.registers.clone();
+        for (number, rule) in row.registers.iter() {
+            let value = match rule {
+                RegisterRule::Undefined => None,
+                RegisterRule::SameValue => register(*number),
+                RegisterRule::Offset(x) => memory(cfa.wrapping_add(*x as u64), self.address_size),
+                RegisterRule::ValOffset(x) => Some(cfa.wrapping_add(*x as u64)),
+                RegisterRule::Register(x) => register(*x),
+                RegisterRule::Expression(bytes) => {
+                    evaluate(bytes, &reader, &[cfa], &register, memory).and_then(|x| memory(x, self.address_size))
+                }
+                RegisterRule::ValExpression(bytes) => evaluate(bytes, &reader, &[cfa], &register, memory),
+            };
+            match value {
+                Some(x) => registers.insert(*number, x),
+                None => registers.remove(number),
+            };
+        }
+        registers.insert(self.stack_pointer, cfa);
+        // An undefined return address marks the outermost frame
+        let pc = *registers.get(&cie.return_register).filter(|x| **x != 0)?;
+        Some(Frame { pc, registers })
+    }
+
+    /// The frames from the one running at `pc` out to the outermost one the rules reach, at most `limit` of them
+    pub fn backtrace(&self, pc: u64, registers: &Registers, memory: &dyn Fn(u64, u8) -> Option<u64>, limit: usize) -> Vec<Frame> {
+        let mut frames = vec![Frame { pc, registers: registers.clone() }];
+        while frames.len() < limit {
+            let frame = frames.last().unwrap();
+            let caller = match self.step(frame, frames.len() == 1, memory) {
+                Some(x) => x,
+                None => break,
+            };
+            // A caller whose stack is not above its callee's means the rules or the memory are wrong
+            let sp = |x: &Frame| x.registers.get(&self.stack_pointer).copied();
+            if sp(&caller) <= sp(frame) {
+                break;
+            }
+            frames.push(caller);
+        }
+        frames
+    }
+}
@@ -1,21 +1,227 @@
 use crate::instruction_set::InstructionSet;
-use super::error::ElfParseError;
 
-pub fn instruction_set_from_u16(value: u16) -> Result<InstructionSet, ElfParseError> {
+pub fn instruction_set_from_u16(value: u16) -> InstructionSet {
     use InstructionSet::*;
     match value {
-        0x00 => Ok(NotSpecified),
-        0x02 => Ok(SPARC),
-        0x03 => Ok(X86),
-        0x08 => Ok(MIPS),
-        0x14 => Ok(PowerPC),
-        0x16 => Ok(S390),
-        0x28 => Ok(ARM),
-        0x2A => Ok(SuperH),
-        0x32 => Ok(IA_64),
-        0x3E => Ok(X86_64),
-        0xB7 => Ok(AArch64),
-        0xF3 => Ok(RISC_V),
-        _ => Err(ElfParseError::UnknownInstructionSet(value)),
+        0x00 => NotSpecified,
+        0x01 => M32,
+        0x02 => SPARC,
+        0x03 => X86,
+        0x04 => M68K,
+        0x05 => M88K,
+        0x06 => IAMCU,
+        0x07 => I860,
+        0x08 => MIPS,
+        0x09 => S370,
+        0x0A => MIPS_RS3_LE,
+        0x0F => PA_RISC,
+        0x11 => VPP500,
+        0x12 => SPARC32Plus,
+        0x13 => I960,
+        0x14 => PowerPC,
+        0x15 => PowerPC64,
+        0x16 => S390,
+        0x17 => SPU,
+        0x24 => V800,
+        0x25 => FR20,
+        0x26 => RH32,
+        0x27 => RCE,
+        0x28 => ARM,
+        0x29 => Alpha,
+        0x2A => SuperH,
+        0x2B => SPARC_V9,
+        0x2C => TriCore,
+        0x2D => ARC,
+        0x2E => H8_300,
+        0x2F => H8_300H,
+        0x30 => H8S,
+        0x31 => H8_500,
+        0x32 => IA_64,
+        0x33 => MIPS_X,
+        0x34 => ColdFire,
+        0x35 => M68HC12,
+        0x36 => MMA,
+        0x37 => PCP,
+        0x38 => NCPU,
+        0x39 => NDR1,
+        0x3A => StarCore,
+        0x3B => ME16,
+        0x3C => ST100,
+        0x3D => TinyJ,
+        0x3E => X86_64,
+        0x3F => PDSP,
+        0x40 => PDP10,
+        0x41 => PDP11,
+        0x42 => FX66,
+        0x43 => ST9Plus,
+        0x44 => ST7,
+        0x45 => M68HC16,
+        0x46 => M68HC11,
+        0x47 => M68HC08,
+        0x48 => M68HC05,
+        0x49 => SVX,
+        0x4A => ST19,
+        0x4B => VAX,
+        0x4C => CRIS,
+        0x4D => Javelin,
+        0x4E => FirePath,
+        0x4F => ZSP,
+        0x50 => MMIX,
+        0x51 => HUANY,
+        0x52 => Prism,
+        0x53 => AVR,
+        0x54 => FR30,
+        0x55 => D10V,
+        0x56 => D30V,
+        0x57 => V850,
+        0x58 => M32R,
+        0x59 => MN10300,
+        0x5A => MN10200,
+        0x5B => PicoJava,
+        0x5C => OpenRISC,
+        0x5D => ARCompact,
+        0x5E => Xtensa,
+        0x5F => VideoCore,
+        0x60 => TMM_GPP,
+        0x61 => NS32K,
+        0x62 => TPC,
+        0x63 => SNP1K,
+        0x64 => ST200,
+        0x65 => IP2K,
+        0x66 => MAX,
+        0x67 => CompactRISC,
+        0x68 => F2MC16,
+        0x69 => MSP430,
+        0x6A => Blackfin,
+        0x6B => SE_C33,
+        0x6C => SEP,
+        0x6D => ARCA,
+        0x6E => UniCore,
+        0x6F => Excess,
+        0x70 => DXP,
+        0x71 => Nios2,
+        0x72 => CRX,
+        0x73 => XGATE,
+        0x74 => C166,
+        0x75 => M16C,
+        0x76 => DSPIC30F,
+        0x77 => CE,
+        0x78 => M32C,
+        0x83 => TSK3000,
+        0x84 => RS08,
+        0x85 => SHARC,
+        0x86 => ECOG2,
+        0x87 => Score7,
+        0x88 => DSP24,
+        0x89 => VideoCore3,
+        0x8A => LatticeMico32,
+        0x8B => SE_C17,
+        0x8C => TI_C6000,
+        0x8D => TI_C2000,
+        0x8E => TI_C5500,
+        0x8F => TI_ARP32,
+        0x90 => TI_PRU,
+        0xA0 => MMDSP_Plus,
+        0xA1 => Cypress_M8C,
+        0xA2 => R32C,
+        0xA3 => TriMedia,
+        0xA4 => Hexagon,
+        0xA5 => I8051,
+        0xA6 => STxP7x,
+        0xA7 => NDS32,
+        0xA8 => ECOG1X,
+        0xA9 => MAXQ30,
+        0xAA => XIMO16,
+        0xAB => Manik,
+        0xAC => CrayNV2,
+        0xAD => RX,
+        0xAE => MetaG,
+        0xAF => Elbrus,
+        0xB0 => ECOG16,
+        0xB1 => CR16,
+        0xB2 => ETPU,
+        0xB3 => SLE9X,
+        0xB4 => L10M,
+        0xB5 => K10M,
+        0xB7 => AArch64,
+        0xB9 => AVR32,
+        0xBA => STM8,
+        0xBB => Tile64,
+        0xBC => TilePro,
+        0xBD => MicroBlaze,
+        0xBE => CUDA,
+        0xBF => TileGx,
+        0xC0 => CloudShield,
+        0xC1 => CoreA_1st,
+        0xC2 => CoreA_2nd,
+        0xC3 => ARCv2,
+        0xC4 => Open8,
+        0xC5 => RL78,
+        0xC6 => VideoCore5,
+        0xC7 => R78KOR,
+        0xC8 => DSC56800EX,
+        0xC9 => BA1,
+        0xCA => BA2,
+        0xCB => XCore,
+        0xCC => PIC,
+        0xCD => IntelGT,
+        0xD2 => KM32,
+        0xD3 => KMX32,
+        0xD4 => KMX16,
+        0xD5 => KMX8,
+        0xD6 => KVARC,
+        0xD7 => CDP,
+        0xD8 => COGE,
+        0xD9 => Cool,
+        0xDA => NORC,
+        0xDB => Kalimba,
+        0xDC => Z80,
+        0xDD => Visium,
+        0xDE => FT32,
+        0xDF => Moxie,
+        0xE0 => AMDGPU,
+        0xF3 => RISC_V,
+        0xF4 => Lanai,
+        0xF7 => BPF,
+        0xFB => VE,
+        0xFC => CSKY,
+        0x102 => LoongArch,
+        // The number Linux used for Alpha before one was assigned
+        0x9026 => Alpha,
+        _ => Other(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::instruction_set_from_u16;
+    use crate::disasm::{decoder_for, Target};
+    use crate::instruction_set::InstructionSet;
+
+    #[test]
+    fn machines() {
+        assert_eq!(instruction_set_from_u16(0x3e), InstructionSet::X86_64);
+        assert_eq!(instruction_set_from_u16(0xb7), InstructionSet::AArch64);
+        assert_eq!(instruction_set_from_u16(0x102), InstructionSet::LoongArch);
+        assert_eq!(instruction_set_from_u16(0x9026), InstructionSet::Alpha);
+        assert_eq!(instruction_set_from_u16(0xffff), InstructionSet::Other(0xffff));
+        assert_eq!(InstructionSet::Other(0xffff).metadata().name, "Unknown machine");
+
+        // Each assigned number names its own machine, except the old number of Alpha
+        let mut seen = Vec::new();
+        for value in 0..=0x102 {
+            let instruction_set = instruction_set_from_u16(value);
+            if instruction_set == InstructionSet::Other(value) {
+                continue;
+            }
+            assert!(!seen.contains(&instruction_set), "{:#x} {:?}", value, instruction_set);
+            seen.push(instruction_set);
+
+            // The metadata says which of them have a decoder
+            let metadata = instruction_set.metadata();
+            assert_ne!(metadata.name, "Unknown machine");
+            let target = Target::new(instruction_set, metadata.endianness, metadata.pointer_size * 8);
+            assert_eq!(decoder_for(&target).is_some(), metadata.disassemble, "{:?}", instruction_set);
+        }
     }
 }
@@ -0,0 +1,194 @@
+use crate::instruction_set::InstructionSet;
+
+// RISC-V e_flags bits
+const EF_RISCV_RVC: u32 = 0x0001;
+const EF_RISCV_FLOAT_ABI: u32 = 0x0006;
+const EF_RISCV_RVE: u32 = 0x0008;
+const EF_RISCV_TSO: u32 = 0x0010;
+
+// ARM e_flags bits
+const EF_ARM_EABIMASK: u32 = 0xff00_0000;
+const EF_ARM_BE8: u32 = 0x0080_0000;
+const EF_ARM_ABI_FLOAT_HARD: u32 = 0x0000_0400;
+const EF_ARM_ABI_FLOAT_SOFT: u32 = 0x0000_0200;
+
+// MIPS e_flags bits
+const EF_MIPS_NOREORDER: u32 = 0x0000_0001;
+const EF_MIPS_PIC: u32 = 0x0000_0002;
+const EF_MIPS_CPIC: u32 = 0x0000_0004;
+const EF_MIPS_ABI2: u32 = 0x0000_0020;
+const EF_MIPS_FP64: u32 = 0x0000_0200;
+const EF_MIPS_NAN2008: u32 = 0x0000_0400;
+const EF_MIPS_ABI: u32 = 0x0000_f000;
+const EF_MIPS_MICROMIPS: u32 = 0x0200_0000;
+const EF_MIPS_ARCH_ASE_M16: u32 = 0x0400_0000;
+const EF_MIPS_ARCH: u32 = 0xf000_0000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub struct ArmFlags {
+    // Version of the ARM EABI the binary conforms to, 0 for old GNU binaries
+    pub eabi_version: u8,
+    // Instructions are little endian in a big endian binary
+    pub be8: bool,
+    pub hard_float: bool,
+    pub soft_float: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum RiscVFloatABI {
+    Soft,
+    Single,
+    Double,
+    Quad,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub struct RiscVFlags {
+    // The C extension is used, so instructions can be 16 bits
+    pub compressed: bool,
+    pub float_abi: RiscVFloatABI,
+    // RV32E, only 16 integer registers
+    pub embedded: bool,
+    // Total store ordering memory model
+    pub tso: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum MipsArch {
+    Mips1,
+    Mips2,
+    Mips3,
+    Mips4,
+    Mips5,
+    Mips32,
+    Mips64,
+    Mips32r2,
+    Mips64r2,
+    Mips32r6,
+    Mips64r6,
+    Unknown(u8),
+}
+
+impl MipsArch {
+    /// Whether the 64 bit instructions are part of the architecture
+    #[allow(unused)]
+    pub fn is_64bit(self) -> bool {
+        use MipsArch::*;
+        matches!(self, Mips3 | Mips4 | Mips5 | Mips64 | Mips64r2 | Mips64r6)
+    }
+
+    /// Release 6 removed and reassigned many of the older encodings
+    pub fn is_release6(self) -> bool {
+        matches!(self, MipsArch::Mips32r6 | MipsArch::Mips64r6)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum MipsAbi {
+    // No ABI given, o32 for 32 bit files and n64 for 64 bit ones unless the ABI2 bit is set
+    Unspecified,
+    O32,
+    O64,
+    Eabi32,
+    Eabi64,
+    Unknown(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub struct MipsFlags {
+    pub arch: MipsArch,
+    pub abi: MipsAbi,
+    // n32, 64 bit registers with 32 bit pointers
+    pub abi2: bool,
+    // The assembler didn't fill delay slots itself
+    pub noreorder: bool,
+    // Position independent code, calls go through $t9 and the .got
+    pub pic: bool,
+    pub cpic: bool,
+    // 64 bit floating point registers in a 32 bit ABI
+    pub fp64: bool,
+    pub nan2008: bool,
+    pub micromips: bool,
+    pub mips16: bool,
+}
+
+/// The processor specific e_flags of the header, interpreted according to the instruction set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(unused)]
+pub enum ElfFlags {
+    Arm(ArmFlags),
+    Mips(MipsFlags),
+    RiscV(RiscVFlags),
+    // Flags of instruction sets we don't interpret
+    Other(u32),
+}
+
+impl ElfFlags {
+    pub fn from_u32(instruction_set: InstructionSet, value: u32) -> ElfFlags {
+        match instruction_set {
+            InstructionSet::ARM => ElfFlags::Arm(ArmFlags {
+                eabi_version: ((value & EF_ARM_EABIMASK) >> 24) as u8,
+                be8: value & EF_ARM_BE8 != 0,
+                hard_float: value & EF_ARM_ABI_FLOAT_HARD != 0,
+                soft_float: value & EF_ARM_ABI_FLOAT_SOFT != 0,
+            }),
+            InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => {
+                let arch = match (value & EF_MIPS_ARCH) >> 28 {
+                    0x0 => MipsArch::Mips1,
+                    0x1 => MipsArch::Mips2,
+                    0x2 => MipsArch::Mips3,
+                    0x3 => MipsArch::Mips4,
+                    0x4 => MipsArch::Mips5,
+                    0x5 => MipsArch::Mips32,
+                    0x6 => MipsArch::Mips64,
+                    0x7 => MipsArch::Mips32r2,
+                    0x8 => MipsArch::Mips64r2,
+                    0x9 => MipsArch::Mips32r6,
+                    0xa => MipsArch::Mips64r6,
+                    other => MipsArch::Unknown(other as u8),
+                };
+                let abi = match (value & EF_MIPS_ABI) >> 12 {
+                    0 => MipsAbi::Unspecified,
+                    1 => MipsAbi::O32,
+                    2 => MipsAbi::O64,
+                    3 => MipsAbi::Eabi32,
+                    4 => MipsAbi::Eabi64,
+                    other => MipsAbi::Unknown(other as u8),
+                };
+                ElfFlags::Mips(MipsFlags {
+                    arch,
+                    abi,
+                    abi2: value & EF_MIPS_ABI2 != 0,
+                    noreorder: value & EF_MIPS_NOREORDER != 0,
+                    pic: value & EF_MIPS_PIC != 0,
+                    cpic: value & EF_MIPS_CPIC != 0,
+                    fp64: value & EF_MIPS_FP64 != 0,
+                    nan2008: value & EF_MIPS_NAN2008 != 0,
+                    micromips: value & EF_MIPS_MICROMIPS != 0,
+                    mips16: value & EF_MIPS_ARCH_ASE_M16 != 0,
+                })
+            }
+            InstructionSet::RISC_V => {
+                let float_abi = match (value & EF_RISCV_FLOAT_ABI) >> 1 {
+                    0 => RiscVFloatABI::Soft,
+                    1 => RiscVFloatABI::Single,
+                    2 => RiscVFloatABI::Double,
+                    _ => RiscVFloatABI::Quad,
+                };
+                ElfFlags::RiscV(RiscVFlags {
+                    compressed: value & EF_RISCV_RVC != 0,
+                    float_abi,
+                    embedded: value & EF_RISCV_RVE != 0,
+                    tso: value & EF_RISCV_TSO != 0,
+                })
+            }
+            _ => ElfFlags::Other(value),
+        }
+    }
+}
@@ -8,10 +8,12 @@ use crate::parsable_file::ParsableFile;
 use super::elf_bitwidth::ElfBitwidth;
 use super::osabi::OsABI;
 use super::elf_instruction_set::instruction_set_from_u16;
-use super::ElfParseError;
+use super::{ElfParseError, ParseMode};
 use super::object_type::ObjectType;
+use super::flags::ElfFlags;
 
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct Header<B: ElfBitwidth> {
     _bitwidth: PhantomData<B>,
     pub endianness: Endianness,
@@ -23,6 +25,7 @@ pub struct Header<B: ElfBitwidth> {
     pub entry_offset: <B as Bitwidth>::Ptr,
     pub program_header_offset: <B as Bitwidth>::Ptr,
     pub section_header_offset: <B as Bitwidth>::Ptr,
+    pub flags: ElfFlags,
     pub program_header_entry_size: u16,
     pub section_header_entry_size: u16,
     pub program_header_n_entries: u16,
@@ -31,7 +34,7 @@ pub struct Header<B: ElfBitwidth> {
 }
 
 impl <B: ElfBitwidth> Header<B> {
-    pub fn parse(inp: &mut ParsableFile<'_>) -> Result<Header<B>, ElfParseError> {
+    pub fn parse(inp: &mut ParsableFile<'_>, mode: ParseMode, warnings: &mut Vec<ElfParseError>) -> Result<Header<B>, ElfParseError> {
         let bitwidth_marker = inp.read_n_bytes(1)?[0];
         if bitwidth_marker != B::MARKER {
             return Err(ElfParseError::WrongBitwidth(bitwidth_marker))
@@ -48,16 +51,25 @@ impl <B: ElfBitwidth> Header<B> {
 
         inp.skip_n_bytes(1)?;
 
-        let abi = OsABI::from_u8(endianness.read_u8(inp)?)?;
+        let abi = OsABI::from_u8(endianness.read_u8(inp)?);
+        if let OsABI::Unknown(value) = abi {
+            mode.unknown(ElfParseError::UnknownOsABI([value]), warnings)?;
+        }
 
         let abi_version = endianness.read_u8(inp)?;
 
         // Skip padding
         inp.skip_n_bytes(7)?;
 
-        let object_type = ObjectType::from_u16(endianness.read_u16(inp)?)?;
+        let object_type = ObjectType::from_u16(endianness.read_u16(inp)?);
+        if let ObjectType::Unknown(value) = object_type {
+            mode.unknown(ElfParseError::UnknownObjectType(value), warnings)?;
+        }
 
-        let instruction_set = instruction_set_from_u16(endianness.read_u16(inp)?)?;
+        let instruction_set = instruction_set_from_u16(endianness.read_u16(inp)?);
+        if let InstructionSet::Other(value) = instruction_set {
+            mode.unknown(ElfParseError::UnknownInstructionSet(value), warnings)?;
+        }
 
         // Maybe check e_version?
         inp.skip_n_bytes(4)?;
@@ -68,8 +80,7 @@ impl <B: ElfBitwidth> Header<B> {
 
         let section_header_offset = <B as Bitwidth>::Ptr::read(endianness, inp)?;
 
-        // Skip e_flags
-        inp.skip_n_bytes(4)?;
+        let flags = ElfFlags::from_u32(instruction_set, endianness.read_u32(inp)?);
 
         // Skip e_ehsize
         inp.skip_n_bytes(2)?;
@@ -94,6 +105,7 @@ impl <B: ElfBitwidth> Header<B> {
             entry_offset,
             program_header_offset,
             section_header_offset,
+            flags,
             program_header_entry_size,
             section_header_entry_size,
             program_header_n_entries,
@@ -7,20 +7,25 @@ mod error;
 pub use self::error::ElfParseError;
 
 mod elf_bitwidth;
-use self::elf_bitwidth::ElfBitwidth;
+pub use self::elf_bitwidth::ElfBitwidth;
 
 mod osabi;
+pub use self::osabi::OsABI;
 mod elf_instruction_set;
 mod object_type;
+pub use self::object_type::ObjectType;
+
+mod flags;
+pub use self::flags::ElfFlags;
 
 mod header;
 use self::header::Header;
 
 mod program_header;
-use self::program_header::ProgramHeader;
+pub use self::program_header::{ProgramHeader, ProgramHeaderType};
 
 mod section_header;
-use self::section_header::{SectionHeader, SectionHeaderType};
+pub use self::section_header::{SectionHeader, SectionHeaderType};
 
 mod symbol;
 use self::symbol::Symbol;
@@ -28,14 +33,40 @@ use self::symbol::Symbol;
 mod reloc;
 use reloc::Relocation;
 
+mod reloc_type;
+pub use self::reloc_type::relocation_type_name;
+
 const ELF_MAGIC: [u8; 4] = [0x7F, 0x45, 0x4c, 0x46];
 
+/// What the parser does with values it doesn't know, such as a vendor OS ABI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseMode {
+    // Fail on the first one, which is what validators want
+    Strict,
+    // Keep them as unknown variants, and record the error as a warning
+    Lenient,
+}
+
+impl ParseMode {
+    fn unknown(self, error: ElfParseError, warnings: &mut Vec<ElfParseError>) -> Result<(), ElfParseError> {
+        match self {
+            ParseMode::Strict => Err(error),
+            ParseMode::Lenient => {
+                warnings.push(error);
+                Ok(())
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct Elf<B: ElfBitwidth> {
     pub header: Header<B>,
     pub program_headers: Vec<ProgramHeader<B>>,
     pub section_headers: Vec<SectionHeader<B>>,
+    // What lenient parsing let through
+    pub warnings: Vec<ElfParseError>,
 }
 
 impl <B: ElfBitwidth> ParsedExecutable for Elf<B> {
@@ -46,13 +77,14 @@ impl <B: ElfBitwidth> ParsedExecutable for Elf<B> {
 
 
 impl <B: ElfBitwidth> Elf<B> {
-    pub fn parse(inp: &mut ParsableFile<'_>) -> Result<Elf<B>, ElfParseError> {
+    pub fn parse(inp: &mut ParsableFile<'_>, mode: ParseMode) -> Result<Elf<B>, ElfParseError> {
         let magic = inp.read_n_bytes(4)?;
-        if magic != &ELF_MAGIC {
+        if magic != ELF_MAGIC {
             return Err(ElfParseError::WrongMagic(magic.to_vec()))
         }
 
-        let header = Header::<B>::parse(inp)?;
+        let mut warnings = Vec::new();
+        let header = Header::<B>::parse(inp, mode, &mut warnings)?;
 
         // Read program headers
         let mut program_headers = Vec::with_capacity(header.program_header_n_entries as usize);
@@ -82,6 +114,7 @@ impl <B: ElfBitwidth> Elf<B> {
             header,
             program_headers,
             section_headers,
+            warnings,
         })
     }
 
@@ -91,6 +124,13 @@ impl <B: ElfBitwidth> Elf<B> {
             .position(|x| x.type_ == SectionHeaderType::SymTab)
     }
 
+    /// The symbols the dynamic linker sees, which stripped binaries keep
+    pub fn dynsym_index(&self) -> Option<usize> {
+        self.section_headers
+            .iter()
+            .position(|x| x.type_ == SectionHeaderType::DynSym)
+    }
+
     #[inline(always)]
     fn symtab_entry_size(&self) -> usize {
         let ptr_size = <B as Bitwidth>::Ptr::N_BYTES;
@@ -164,3 +204,66 @@ impl <B: ElfBitwidth> Elf<B> {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::{Elf, ElfParseError, OsABI, ParseMode};
+    use crate::bits::SixtyfourBit;
+    use crate::error::GenericParseError;
+    use crate::instruction_set::InstructionSet;
+    use crate::parsable_file::ParsableFile;
+
+    // The header of a little endian 64 bit executable without program or section headers
+    fn header(os_abi: u8, machine: u16) -> Vec<u8> {
+        let mut data = vec![0x7f, b'E', b'L', b'F', 2, 1, 1, os_abi, 0, 0, 0, 0, 0, 0, 0, 0];
+        data.extend(2u16.to_le_bytes());
+        data.extend(machine.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend([0; 24]);
+        data.extend(0u32.to_le_bytes());
+        data.extend([64, 0, 56, 0, 0, 0, 64, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    fn parse(data: &[u8], mode: ParseMode) -> Result<Elf<SixtyfourBit>, ElfParseError> {
+        Elf::<SixtyfourBit>::parse(&mut ParsableFile::new(data), mode)
+    }
+
+    #[test]
+    fn unknown_values() {
+        let elf = parse(&header(3, 0x3e), ParseMode::Strict).unwrap();
+        assert_eq!((elf.header.abi, elf.header.instruction_set), (OsABI::Linux, InstructionSet::X86_64));
+        assert!(elf.warnings.is_empty());
+
+        // Strict parsing fails on the first unknown value, lenient parsing keeps them all
+        let data = header(0x42, 0x7777);
+        assert_eq!(parse(&data, ParseMode::Strict).unwrap_err(), ElfParseError::UnknownOsABI([0x42]));
+        let elf = parse(&data, ParseMode::Lenient).unwrap();
+        assert_eq!((elf.header.abi, elf.header.instruction_set), (OsABI::Unknown(0x42), InstructionSet::Other(0x7777)));
+        assert_eq!(elf.warnings, vec![ElfParseError::UnknownOsABI([0x42]), ElfParseError::UnknownInstructionSet(0x7777)]);
+    }
+
+    #[test]
+    fn malformed() {
+        // Leniency is about values, what can't be read fails either way
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let data = header(0, 0x3e);
+            assert_eq!(parse(&data[..40], mode).unwrap_err(), ElfParseError::Generic(GenericParseError::EndOfFead));
+            assert_eq!(parse(&data[..2], mode).unwrap_err(), ElfParseError::Generic(GenericParseError::EndOfFead));
+            assert_eq!(parse(b"MZ\x90\x00", mode).unwrap_err(), ElfParseError::WrongMagic(b"MZ\x90\x00".to_vec()));
+
+            let mut data = header(0, 0x3e);
+            data[4] = 1;
+            assert_eq!(parse(&data, mode).unwrap_err(), ElfParseError::WrongBitwidth(1));
+            data[4] = 2;
+            data[5] = 3;
+            assert_eq!(parse(&data, mode).unwrap_err(), ElfParseError::WrongEndianness(3));
+
+            // A section header table past the end of the file
+            let mut data = header(0, 0x3e);
+            data[40] = 0xff;
+            data[60] = 1;
+            assert!(matches!(parse(&data, mode), Err(ElfParseError::Generic(_))));
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use super::ElfParseError;
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(unused)]
 pub enum ObjectType {
@@ -12,23 +10,24 @@ pub enum ObjectType {
     HiOs,
     LoProc,
     HiProc,
+    Unknown(u16),
 }
 
 impl ObjectType {
-    pub fn from_u16(value: u16) -> Result<ObjectType, ElfParseError> {
+    pub fn from_u16(value: u16) -> ObjectType {
         use ObjectType::*;
 
         match value {
-            0 => Ok(None),
-            1 => Ok(Rel),
-            2 => Ok(Exec),
-            3 => Ok(Dyn),
-            4 => Ok(Core),
-            0xfe00 => Ok(LoOs),
-            0xfeff => Ok(HiOs),
-            0xff00 => Ok(LoProc),
-            0xffff => Ok(HiProc),
-            _ => Err(ElfParseError::UnknownObjectType(value)),
+            0 => None,
+            1 => Rel,
+            2 => Exec,
+            3 => Dyn,
+            4 => Core,
+            0xfe00 => LoOs,
+            0xfeff => HiOs,
+            0xff00 => LoProc,
+            0xffff => HiProc,
+            _ => Unknown(value),
         }
     }
 }
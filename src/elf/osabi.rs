@@ -1,7 +1,5 @@
-use super::error::ElfParseError;
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(unused, non_camel_case_types)]
+#[allow(unused, non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum OsABI {
     System_V,
     HP_UX,
@@ -21,32 +19,39 @@ pub enum OsABI {
     Fenix_OS,
     CloudABI,
     OpenVOS,
+    ARM_AEABI,
+    ARM,
+    Standalone,
+    Unknown(u8),
 }
 
 impl OsABI {
-    pub fn from_u8(value: u8) -> Result<OsABI, ElfParseError> {
+    pub fn from_u8(value: u8) -> OsABI {
         use OsABI::*;
 
         match value {
-            0x00 => Ok(System_V),
-            0x01 => Ok(HP_UX),
-            0x02 => Ok(NetBSD),
-            0x03 => Ok(Linux),
-            0x04 => Ok(GNU_Hurd),
-            0x06 => Ok(Solaris),
-            0x07 => Ok(AIX),
-            0x08 => Ok(IRIX),
-            0x09 => Ok(FreeBSD),
-            0x0A => Ok(Tru64),
-            0x0B => Ok(Novell_Modesto),
-            0x0C => Ok(OpenBSD),
-            0x0D => Ok(OpenVMS),
-            0x0E => Ok(NonStop_Kernel),
-            0x0F => Ok(AROS),
-            0x10 => Ok(Fenix_OS),
-            0x11 => Ok(CloudABI),
-            0x12 => Ok(OpenVOS),
-            _ => Err(ElfParseError::UnknownOsABI([value])),
+            0x00 => System_V,
+            0x01 => HP_UX,
+            0x02 => NetBSD,
+            0x03 => Linux,
+            0x04 => GNU_Hurd,
+            0x06 => Solaris,
+            0x07 => AIX,
+            0x08 => IRIX,
+            0x09 => FreeBSD,
+            0x0A => Tru64,
+            0x0B => Novell_Modesto,
+            0x0C => OpenBSD,
+            0x0D => OpenVMS,
+            0x0E => NonStop_Kernel,
+            0x0F => AROS,
+            0x10 => Fenix_OS,
+            0x11 => CloudABI,
+            0x12 => OpenVOS,
+            0x40 => ARM_AEABI,
+            0x61 => ARM,
+            0xFF => Standalone,
+            _ => Unknown(value),
         }
     }
 }
@@ -8,6 +8,7 @@ use super::elf_bitwidth::ElfBitwidth;
 use super::ElfParseError;
 
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct ProgramHeader<B: ElfBitwidth> {
     _bitwidth: PhantomData<B>,
 
@@ -79,6 +80,8 @@ pub enum ProgramHeaderType {
     Phdr,
     Tls,
     Loos,
+    // Where .eh_frame_hdr is, for unwinders to find without section headers
+    GnuEhFrame,
     Hios,
     LoProc,
     HiProc,
@@ -98,6 +101,7 @@ impl ProgramHeaderType {
             0x00000006 => Phdr,
             0x00000007 => Tls,
             0x60000000 => Loos,
+            0x6474e550 => GnuEhFrame,
             0x6FFFFFFF => Hios,
             0x70000000 => LoProc,
             0x7FFFFFFF => HiProc,
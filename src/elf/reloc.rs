@@ -11,6 +11,7 @@ use super::Symbol;
 use super::Elf;
 
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct Relocation<B: ElfBitwidth> {
     _bitwidth: PhantomData<B>,
 
@@ -32,7 +33,7 @@ impl <B: ElfBitwidth> Relocation<B> {
             if <B as Bitwidth>::Ptr::N_BYTES == 4 {
                 Some(endianness.read_i32(inp)? as i64)
             } else {
-                Some(endianness.read_i64(inp)? as i64)
+                Some(endianness.read_i64(inp)?)
             }
         } else {
             None
@@ -46,6 +47,24 @@ impl <B: ElfBitwidth> Relocation<B> {
         })
     }
 
+    pub fn address(&self) -> u64 {
+        self.virtual_address.to_u64()
+    }
+
+    /// The relocation type, whose meaning depends on the instruction set
+    pub fn get_type(&self) -> u32 {
+        let info = self.info.to_u64();
+        if <B as Bitwidth>::Ptr::N_BYTES == 4 {
+            (info & 0xff) as u32
+        } else {
+            (info & 0xffff_ffff) as u32
+        }
+    }
+
+    pub fn addend(&self) -> Option<i64> {
+        self.addend
+    }
+
     pub fn get_symbol_idx(&self) -> usize {
         let info = self.info.to_u64();
         if <B as Bitwidth>::Ptr::N_BYTES == 4 {
@@ -58,7 +77,7 @@ impl <B: ElfBitwidth> Relocation<B> {
     pub fn get_symbol(&self, bytes: &mut ParsableFile<'_>, elf: &Elf<B>, reloc_table: &SectionHeader<B>) -> Result<Symbol<B>, ElfParseError> {
         let symbols = elf.symbols(bytes, reloc_table.link)?;
 
-        symbols.get(self.get_symbol_idx()).ok_or(ElfParseError::InvalidSymbolReference(self.get_symbol_idx())).map(|x| x.clone())
+        symbols.get(self.get_symbol_idx()).ok_or(ElfParseError::InvalidSymbolReference(self.get_symbol_idx())).cloned()
     }
 
 }
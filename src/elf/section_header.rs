@@ -8,14 +8,21 @@ use super::elf_bitwidth::ElfBitwidth;
 use super::ElfParseError;
 use super::Elf;
 
+// Bits of sh_flags
+pub const SHF_WRITE: u64 = 0x1;
+pub const SHF_ALLOC: u64 = 0x2;
+pub const SHF_EXECINSTR: u64 = 0x4;
+pub const SHF_MERGE: u64 = 0x10;
+pub const SHF_STRINGS: u64 = 0x20;
+
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct SectionHeader<B: ElfBitwidth> {
     _bitwidth: PhantomData<B>,
 
     pub shstrtab_offset: usize,
     pub type_: SectionHeaderType,
-    // TODO: Add this
-    // pub flags: SectionHeaderFlags,
+    pub flags: u64,
     pub virtual_address: <B as Bitwidth>::Ptr,
     pub file_offset: <B as Bitwidth>::Ptr,
     pub size: <B as Bitwidth>::Ptr,
@@ -31,8 +38,7 @@ impl <B: ElfBitwidth> SectionHeader<B> {
 
         let type_ = SectionHeaderType::from_u32(endianness.read_u32(inp)?);
 
-        // TODO: sh_flags
-        <B as Bitwidth>::Ptr::read(endianness, inp)?;
+        let flags = <B as Bitwidth>::Ptr::read(endianness, inp)?.to_u64();
 
         let virtual_address = <B as Bitwidth>::Ptr::read(endianness, inp)?;
 
@@ -54,6 +60,7 @@ impl <B: ElfBitwidth> SectionHeader<B> {
             _bitwidth: PhantomData,
             shstrtab_offset,
             type_,
+            flags,
             virtual_address,
             file_offset,
             size,
@@ -63,6 +70,24 @@ impl <B: ElfBitwidth> SectionHeader<B> {
         })
     }
 
+    /// Whether the section is part of the memory image of the program
+    pub fn is_allocated(&self) -> bool {
+        self.flags & SHF_ALLOC != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.flags & SHF_EXECINSTR != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.flags & SHF_WRITE != 0
+    }
+
+    /// Whether the section holds null terminated strings the linker may merge, with characters of entry_size bytes
+    pub fn is_merged_strings(&self) -> bool {
+        self.flags & (SHF_MERGE | SHF_STRINGS) == SHF_MERGE | SHF_STRINGS
+    }
+
     pub fn get_content<'a>(&self, bytes: &mut ParsableFile<'a>) -> Result<&'a [u8], ElfParseError> {
         let mut bytes_r = bytes.clone();
         bytes_r.move_to(self.file_offset.to_usize()?);
@@ -9,7 +9,16 @@ use super::ElfParseError;
 use super::SectionHeader;
 use super::Elf;
 
+// Symbol type in the low nibble of st_info
+const STT_FUNC: u8 = 2;
+const STT_SECTION: u8 = 3;
+const STT_FILE: u8 = 4;
+
+// Symbol binding in the high nibble of st_info
+const STB_LOCAL: u8 = 0;
+
 #[derive(Debug, Clone)]
+#[allow(unused)]
 pub struct Symbol<B: ElfBitwidth> {
     _bitwidth: PhantomData<B>,
 
@@ -71,6 +80,33 @@ impl <B: ElfBitwidth> Symbol<B> {
         }
     }
 
+    pub fn address(&self) -> u64 {
+        self.value.to_u64()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size.to_u64()
+    }
+
+    pub fn is_function(&self) -> bool {
+        self.info & 0xf == STT_FUNC
+    }
+
+    /// Whether the symbol names a section or source file rather than something in the program
+    pub fn is_section_or_file(&self) -> bool {
+        matches!(self.info & 0xf, STT_SECTION | STT_FILE)
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.info >> 4 != STB_LOCAL
+    }
+
+    /// Index of the section the symbol is defined in, 0 for undefined symbols. Values from 0xff00 on are special
+    /// (absolute, common).
+    pub fn section_index(&self) -> usize {
+        self.section_header_index
+    }
+
     pub fn get_name<'a>(&self, bytes: &mut ParsableFile<'a>, elf: &Elf<B>, symbol_table: &SectionHeader<B>) -> Result<Option<&'a [u8]>, ElfParseError> {
         let strtab_header = &elf.section_headers[symbol_table.link];
 
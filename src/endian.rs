@@ -3,7 +3,7 @@ use std::convert::TryInto;
 use crate::error::GenericParseError;
 use crate::parsable_file::ParsableFile;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Endianness {
     LittleEndian,
     BigEndian,
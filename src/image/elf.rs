@@ -0,0 +1,213 @@
+use crate::bits::PtrType;
+use crate::disasm::{self, Target};
+use crate::elf::{Elf, ElfBitwidth, ElfFlags, ElfParseError, ObjectType, ProgramHeaderType, SectionHeaderType};
+use crate::endian::Endianness;
+use crate::instruction_set::InstructionSet;
+use crate::parsable_file::ParsableFile;
+
+use super::{Image, Relocation, Section, Segment, SegmentKind, Symbol};
+
+// Section indices from here on are special (absolute, common, ...) rather than sections
+const SHN_LORESERVE: usize = 0xff00;
+
+impl Image {
+    pub fn from_elf<B: ElfBitwidth>(elf: &Elf<B>, contents: &mut ParsableFile<'_>) -> Result<Image, ElfParseError> {
+        let mut sections = Vec::new();
+        for (index, header) in elf.section_headers.iter().enumerate() {
+            let name = String::from_utf8_lossy(header.get_name(contents, elf)?).into_owned();
+            let data = match header.type_ {
+                SectionHeaderType::NoBits | SectionHeaderType::Null => Vec::new(),
+                _ => header.get_content(contents)?.to_vec(),
+            };
+            sections.push(Section {
+                index,
+                name,
+                address: header.virtual_address.to_u64(),
+                size: header.size.to_u64(),
+                entry_size: header.entry_size.to_u64(),
+                data,
+                allocated: header.is_allocated(),
+                executable: header.is_executable(),
+                writable: header.is_writable(),
+                merged_strings: header.is_merged_strings(),
+            });
+        }
+
+        let mut segments = Vec::new();
+        for header in elf.program_headers.iter() {
+            let kind = match header.type_ {
+                ProgramHeaderType::Load => SegmentKind::Load,
+                ProgramHeaderType::GnuEhFrame => SegmentKind::EhFrameHeader,
+                _ => SegmentKind::Other,
+            };
+            let data = match kind {
+                SegmentKind::Other => Vec::new(),
+                _ => header.get_content(contents)?.to_vec(),
+            };
+            segments.push(Segment {
+                kind,
+                address: header.virtual_address.to_u64(),
+                memory_size: header.memory_size.to_u64(),
+                data,
+            });
+        }
+
+        let arm = elf.header.instruction_set == InstructionSet::ARM;
+        let mut symbols = Vec::new();
+        for (table, dynamic) in [(elf.symtab_index(), false), (elf.dynsym_index(), true)] {
+            let table = match table {
+                Some(x) => x,
+                None => continue,
+            };
+            for symbol in elf.symbols(contents, table)? {
+                if symbol.is_section_or_file() {
+                    continue;
+                }
+                let name = symbol.get_name(contents, elf, &elf.section_headers[table])?.unwrap_or_default();
+                let index = symbol.section_index();
+                let mut address = symbol.address();
+                // The low bit of Thumb function addresses selects the mode, it isn't part of the address
+                if arm && symbol.is_function() {
+                    address &= !1;
+                }
+                symbols.push(Symbol {
+                    name: String::from_utf8_lossy(name).into_owned(),
+                    address,
+                    size: symbol.size(),
+                    function: symbol.is_function(),
+                    global: symbol.is_global(),
+                    section: Some(index).filter(|x| *x != 0 && *x < SHN_LORESERVE),
+                    dynamic,
+                    mangled: None,
+                });
+            }
+        }
+
+        let mut relocations = Vec::new();
+        for table_index in elf.reloc_tables_inds() {
+            let table = &elf.section_headers[table_index];
+            let name = table.get_name(contents, elf)?;
+            let plt = name == b".rela.plt" || name == b".rel.plt";
+            let section = Some(table.info as usize).filter(|x| *x != 0 && *x < sections.len());
+            // Offsets in object files are relative to the section they apply to
+            let base = match (elf.header.object_type, section) {
+                (ObjectType::Rel, Some(x)) => sections[x].address,
+                _ => 0,
+            };
+            // The symbol table of the relocations, if they have one
+            let symtab = match elf.section_headers.get(table.link) {
+                Some(x) if matches!(x.type_, SectionHeaderType::SymTab | SectionHeaderType::DynSym) => Some(x),
+                _ => None,
+            };
+            let table_symbols = match symtab {
+                Some(_) => elf.symbols(contents, table.link)?,
+                None => Vec::new(),
+            };
+            for relocation in elf.relocations(contents, table_index)? {
+                let symbol = match (relocation.get_symbol_idx(), symtab) {
+                    (0, _) | (_, None) => None,
+                    (index, Some(symtab)) => {
+                        let symbol = table_symbols.get(index).ok_or(ElfParseError::InvalidSymbolReference(index))?;
+                        let name = symbol.get_name(contents, elf, symtab)?.unwrap_or_default();
+                        match name {
+                            // Relocations against sections are named after them
+                            b"" => sections.get(symbol.section_index()).map(|x| x.name.clone()),
+                            _ => Some(String::from_utf8_lossy(name).into_owned()),
+                        }
+                    }
+                };
+                relocations.push(Relocation {
+                    address: base.wrapping_add(relocation.address()),
+                    kind: relocation.get_type(),
+                    symbol,
+                    addend: relocation.addend().unwrap_or(0),
+                    section,
+                    plt,
+                    mangled: None,
+                });
+            }
+        }
+
+        let target = target(elf, contents)?;
+        Ok(Image {
+            target,
+            entry: elf.header.entry_offset.to_u64(),
+            relocatable: elf.header.object_type == ObjectType::Rel,
+            sections,
+            segments,
+            symbols,
+            relocations,
+        })
+    }
+}
+
+// What the decoder needs to know, from the header flags and the symbols
+fn target<B: ElfBitwidth>(elf: &Elf<B>, contents: &mut ParsableFile<'_>) -> Result<Target, ElfParseError> {
+    let mut target = Target::new(elf.header.instruction_set, elf.header.endianness, (B::Ptr::N_BYTES * 8) as u8);
+    target.os_abi = elf.header.abi;
+    if let ElfFlags::RiscV(flags) = elf.header.flags {
+        target.compressed = flags.compressed;
+    }
+    if let ElfFlags::Arm(flags) = elf.header.flags {
+        // BE8 was introduced with version 4 of the EABI, the bit means something else in older binaries
+        target.be8 = flags.be8 && flags.eabi_version >= 4;
+        // Binaries flagged with neither float ABI are taken to use the VFP registers
+        target.soft_float = flags.soft_float && !flags.hard_float;
+
+        // Mapping symbols and Thumb function addresses tell where A32, T32 and data are
+        if let Some(idx) = elf.symtab_index() {
+            let mut symbols = Vec::new();
+            for symbol in elf.symbols(contents, idx)? {
+                let name = symbol.get_name(contents, elf, &elf.section_headers[idx])?.unwrap_or_default();
+                symbols.push((symbol.address(), name, symbol.is_function()));
+            }
+            target.arm_regions = disasm::arm::mode_regions(&symbols);
+        }
+        if elf.header.entry_offset.to_u64() & 1 != 0 {
+            target.arm_default_mode = disasm::arm::ArmMode::Thumb;
+        }
+    }
+    if let ElfFlags::Mips(flags) = elf.header.flags {
+        target.mips64 = target.bits == 64 || flags.abi2;
+        target.release6 = flags.arch.is_release6();
+        target.gp = mips_gp(elf, contents)?;
+    }
+    Ok(target)
+}
+
+// $gp of a MIPS binary: the _gp symbol if there is one, otherwise the value recorded in .reginfo or the
+// conventional 0x7ff0 bytes into the .got
+fn mips_gp<B: ElfBitwidth>(elf: &Elf<B>, contents: &mut ParsableFile<'_>) -> Result<Option<u64>, ElfParseError> {
+    if let Some(idx) = elf.symtab_index() {
+        for symbol in elf.symbols(contents, idx)? {
+            if symbol.get_name(contents, elf, &elf.section_headers[idx])? == Some(b"_gp") {
+                return Ok(Some(symbol.address()));
+            }
+        }
+    }
+
+    for section_header in elf.section_headers.iter() {
+        let name = section_header.get_name(contents, elf)?;
+        if name == b".reginfo" {
+            // ri_gp_value follows the general and coprocessor register masks
+            let content = section_header.get_content(contents)?;
+            if let Some(raw) = content.get(20..24) {
+                let raw = [raw[0], raw[1], raw[2], raw[3]];
+                let gp = match elf.header.endianness {
+                    Endianness::BigEndian => u32::from_be_bytes(raw),
+                    _ => u32::from_le_bytes(raw),
+                };
+                if gp != 0 {
+                    return Ok(Some(gp as u64));
+                }
+            }
+        }
+    }
+
+    for section_header in elf.section_headers.iter() {
+        if section_header.get_name(contents, elf)? == b".got" {
+            return Ok(Some(section_header.virtual_address.to_u64() + 0x7ff0));
+        }
+    }
+    Ok(None)
+}
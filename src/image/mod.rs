@@ -0,0 +1,165 @@
+use crate::demangle;
+use crate::disasm::Target;
+
+mod elf;
+
+/// A section of the binary, with its contents if it has any in the file
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct Section {
+    // Index in the section header table, which symbols and relocations refer to
+    pub index: usize,
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    // Size of the entries of tables, 0 if the section isn't one
+    pub entry_size: u64,
+    pub data: Vec<u8>,
+    // Part of the memory image of the program
+    pub allocated: bool,
+    pub executable: bool,
+    pub writable: bool,
+    // Null terminated strings with characters of entry_size bytes (SHF_MERGE and SHF_STRINGS)
+    pub merged_strings: bool,
+}
+
+impl Section {
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.address && address - self.address < self.size
+    }
+}
+
+/// What a segment of the program headers is for, as far as the analyses care
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Load,
+    // PT_GNU_EH_FRAME, the .eh_frame_hdr section
+    EhFrameHeader,
+    Other,
+}
+
+/// A segment of the program headers, with the contents the file has for it
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub address: u64,
+    pub memory_size: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    // Without the Thumb bit on ARM
+    pub address: u64,
+    pub size: u64,
+    pub function: bool,
+    pub global: bool,
+    // Section the symbol is defined in, None for undefined and absolute symbols
+    pub section: Option<usize>,
+    // From the dynamic symbol table rather than the full one
+    pub dynamic: bool,
+    // The name in the symbol table, when `name` is its demangled form
+    pub mangled: Option<String>,
+}
+
+impl Symbol {
+    /// The name the linker knows the symbol by
+    pub fn linkage_name(&self) -> &str {
+        self.mangled.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(unused)]
+pub struct Relocation {
+    // Where the relocation is applied
+    pub address: u64,
+    // Architecture specific type
+    pub kind: u32,
+    pub symbol: Option<String>,
+    pub addend: i64,
+    // Section the relocation is applied to, if the table says
+    pub section: Option<usize>,
+    // From the table of relocations the PLT resolves lazily (JMPREL)
+    pub plt: bool,
+    // The name of the symbol in the symbol table, when `symbol` is its demangled form
+    pub mangled: Option<String>,
+}
+
+impl Relocation {
+    /// The name the linker knows the symbol by
+    pub fn linkage_name(&self) -> Option<&str> {
+        self.mangled.as_deref().or(self.symbol.as_deref())
+    }
+}
+
+/// What the analyses need to know about an executable, independent of its format
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub target: Target,
+    pub entry: u64,
+    // Object files, whose sections all start at address 0 until they are linked
+    pub relocatable: bool,
+    pub sections: Vec<Section>,
+    // Only the loaded segments and the one of .eh_frame_hdr have contents
+    pub segments: Vec<Segment>,
+    pub symbols: Vec<Symbol>,
+    pub relocations: Vec<Relocation>,
+}
+
+impl Image {
+    pub fn section_by_name(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|x| x.name == name)
+    }
+
+    /// The allocated section containing an address. Sections with contents win over .bss and the like.
+    pub fn section_at(&self, address: u64) -> Option<&Section> {
+        let sections: Vec<&Section> = self.sections.iter().filter(|x| x.allocated && x.contains(address)).collect();
+        sections.iter().find(|x| !x.data.is_empty()).or_else(|| sections.first()).copied()
+    }
+
+    /// The contents of the file from an address to the end of its section, or of its segment when the section headers
+    /// don't say
+    pub fn data_at(&self, address: u64) -> Option<&[u8]> {
+        if let Some(section) = self.section_at(address).filter(|x| !x.data.is_empty()) {
+            return section.data.get((address - section.address) as usize..);
+        }
+        let segment = self.segments.iter().find(|x| {
+            x.kind == SegmentKind::Load && address >= x.address && address - x.address < x.data.len() as u64
+        })?;
+        segment.data.get((address - segment.address) as usize..)
+    }
+
+    pub fn is_code(&self, address: u64) -> bool {
+        self.section_at(address).is_some_and(|x| x.executable)
+    }
+
+    /// The executable sections, as (address, contents)
+    pub fn code(&self) -> Vec<(u64, &[u8])> {
+        self.sections.iter().filter(|x| x.executable && x.allocated).map(|x| (x.address, &x.data[..])).collect()
+    }
+
+    /// The function symbol with the given name, demangled or not
+    pub fn function_named(&self, name: &str) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|x| (x.name == name || x.mangled.as_deref() == Some(name)) && x.section.is_some())
+            .max_by_key(|x| x.function)
+    }
+
+    /// Names symbols, and relocations by their symbols, after what the mangled names say, C++ and Rust ones. The
+    /// names from the symbol table stay in `mangled`.
+    pub fn demangle(&mut self) {
+        for symbol in self.symbols.iter_mut().filter(|x| x.mangled.is_none()) {
+            if let Some(demangled) = demangle::demangle(&symbol.name) {
+                symbol.mangled = Some(std::mem::replace(&mut symbol.name, demangled.to_string()));
+            }
+        }
+        for relocation in self.relocations.iter_mut().filter(|x| x.mangled.is_none()) {
+            if let Some(demangled) = relocation.symbol.as_deref().and_then(demangle::demangle) {
+                relocation.mangled = relocation.symbol.replace(demangled.to_string());
+            }
+        }
+    }
+}
@@ -1,17 +1,399 @@
+use crate::endian::Endianness;
+
+/// The machine of a binary, one variant per entry of the ELF EM_* registry
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(unused, non_camel_case_types)]
+#[allow(unused, non_camel_case_types, clippy::upper_case_acronyms)]
 pub enum InstructionSet {
     NotSpecified,
+    M32,
     SPARC,
     X86,
+    M68K,
+    M88K,
+    IAMCU,
+    I860,
     MIPS,
+    S370,
+    MIPS_RS3_LE,
+    PA_RISC,
+    VPP500,
+    SPARC32Plus,
+    I960,
     PowerPC,
+    PowerPC64,
     S390,
+    SPU,
+    V800,
+    FR20,
+    RH32,
+    RCE,
     ARM,
+    Alpha,
     SuperH,
+    SPARC_V9,
+    TriCore,
+    ARC,
+    H8_300,
+    H8_300H,
+    H8S,
+    H8_500,
     IA_64,
+    MIPS_X,
+    ColdFire,
+    M68HC12,
+    MMA,
+    PCP,
+    NCPU,
+    NDR1,
+    StarCore,
+    ME16,
+    ST100,
+    TinyJ,
     X86_64,
+    PDSP,
+    PDP10,
+    PDP11,
+    FX66,
+    ST9Plus,
+    ST7,
+    M68HC16,
+    M68HC11,
+    M68HC08,
+    M68HC05,
+    SVX,
+    ST19,
+    VAX,
+    CRIS,
+    Javelin,
+    FirePath,
+    ZSP,
+    MMIX,
+    HUANY,
+    Prism,
+    AVR,
+    FR30,
+    D10V,
+    D30V,
+    V850,
+    M32R,
+    MN10300,
+    MN10200,
+    PicoJava,
+    OpenRISC,
+    ARCompact,
+    Xtensa,
+    VideoCore,
+    TMM_GPP,
+    NS32K,
+    TPC,
+    SNP1K,
+    ST200,
+    IP2K,
+    MAX,
+    CompactRISC,
+    F2MC16,
+    MSP430,
+    Blackfin,
+    SE_C33,
+    SEP,
+    ARCA,
+    UniCore,
+    Excess,
+    DXP,
+    Nios2,
+    CRX,
+    XGATE,
+    C166,
+    M16C,
+    DSPIC30F,
+    CE,
+    M32C,
+    TSK3000,
+    RS08,
+    SHARC,
+    ECOG2,
+    Score7,
+    DSP24,
+    VideoCore3,
+    LatticeMico32,
+    SE_C17,
+    TI_C6000,
+    TI_C2000,
+    TI_C5500,
+    TI_ARP32,
+    TI_PRU,
+    MMDSP_Plus,
+    Cypress_M8C,
+    R32C,
+    TriMedia,
+    Hexagon,
+    I8051,
+    STxP7x,
+    NDS32,
+    ECOG1X,
+    MAXQ30,
+    XIMO16,
+    Manik,
+    CrayNV2,
+    RX,
+    MetaG,
+    Elbrus,
+    ECOG16,
+    CR16,
+    ETPU,
+    SLE9X,
+    L10M,
+    K10M,
     AArch64,
+    AVR32,
+    STM8,
+    Tile64,
+    TilePro,
+    MicroBlaze,
+    CUDA,
+    TileGx,
+    CloudShield,
+    CoreA_1st,
+    CoreA_2nd,
+    ARCv2,
+    Open8,
+    RL78,
+    VideoCore5,
+    R78KOR,
+    DSC56800EX,
+    BA1,
+    BA2,
+    XCore,
+    PIC,
+    IntelGT,
+    KM32,
+    KMX32,
+    KMX16,
+    KMX8,
+    KVARC,
+    CDP,
+    COGE,
+    Cool,
+    NORC,
+    Kalimba,
+    Z80,
+    Visium,
+    FT32,
+    Moxie,
+    AMDGPU,
     RISC_V,
+    Lanai,
+    BPF,
+    VE,
+    CSKY,
+    LoongArch,
+    // A machine missing from the registry, by its number
+    Other(u16),
+}
+
+/// What is known about an instruction set without looking at a binary. Pointer size and endianness are those of
+/// its most common ABI, binaries say which ones they actually use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    pub name: &'static str,
+    // In bytes
+    pub pointer_size: u8,
+    pub endianness: Endianness,
+    // decster has a decoder for it
+    pub disassemble: bool,
 }
 
+impl InstructionSet {
+    pub fn metadata(&self) -> Metadata {
+        use InstructionSet::*;
+        use Endianness::{BigEndian as Big, LittleEndian as Little};
+
+        let (name, pointer_size, endianness, disassemble) = match self {
+            NotSpecified => ("No machine", 8, Little, false),
+            M32 => ("AT&T WE 32100", 4, Big, false),
+            SPARC => ("SPARC", 4, Big, true),
+            X86 => ("Intel 80386", 4, Little, true),
+            M68K => ("Motorola 68000", 4, Big, false),
+            M88K => ("Motorola 88000", 4, Big, false),
+            IAMCU => ("Intel MCU", 4, Little, false),
+            I860 => ("Intel 80860", 4, Little, false),
+            MIPS => ("MIPS", 4, Big, true),
+            S370 => ("IBM System/370", 4, Big, false),
+            MIPS_RS3_LE => ("MIPS R3000 little endian", 4, Little, true),
+            PA_RISC => ("HP PA-RISC", 4, Big, false),
+            VPP500 => ("Fujitsu VPP500", 4, Big, false),
+            SPARC32Plus => ("SPARC V8+", 4, Big, true),
+            I960 => ("Intel 80960", 4, Little, false),
+            PowerPC => ("PowerPC", 4, Big, true),
+            PowerPC64 => ("PowerPC 64 bit", 8, Big, true),
+            S390 => ("IBM S/390 and z/Architecture", 8, Big, true),
+            SPU => ("IBM Cell SPU", 4, Big, false),
+            V800 => ("NEC V800", 4, Little, false),
+            FR20 => ("Fujitsu FR20", 4, Big, false),
+            RH32 => ("TRW RH-32", 4, Big, false),
+            RCE => ("Motorola RCE", 4, Big, false),
+            ARM => ("ARM", 4, Little, true),
+            Alpha => ("Digital Alpha", 8, Little, false),
+            SuperH => ("Hitachi SuperH", 4, Little, true),
+            SPARC_V9 => ("SPARC V9", 8, Big, true),
+            TriCore => ("Siemens TriCore", 4, Little, false),
+            ARC => ("Argonaut RISC Core", 4, Little, false),
+            H8_300 => ("Hitachi H8/300", 2, Big, false),
+            H8_300H => ("Hitachi H8/300H", 4, Big, false),
+            H8S => ("Hitachi H8S", 4, Big, false),
+            H8_500 => ("Hitachi H8/500", 2, Big, false),
+            IA_64 => ("Intel Itanium", 8, Little, false),
+            MIPS_X => ("Stanford MIPS-X", 4, Big, false),
+            ColdFire => ("Motorola ColdFire", 4, Big, false),
+            M68HC12 => ("Motorola M68HC12", 2, Big, false),
+            MMA => ("Fujitsu MMA", 4, Big, false),
+            PCP => ("Siemens PCP", 4, Little, false),
+            NCPU => ("Sony nCPU", 4, Little, false),
+            NDR1 => ("Denso NDR1", 4, Little, false),
+            StarCore => ("Motorola StarCore", 4, Little, false),
+            ME16 => ("Toyota ME16", 2, Little, false),
+            ST100 => ("STMicroelectronics ST100", 4, Little, false),
+            TinyJ => ("Advanced Logic TinyJ", 4, Little, false),
+            X86_64 => ("AMD x86-64", 8, Little, true),
+            PDSP => ("Sony DSP", 4, Little, false),
+            PDP10 => ("Digital PDP-10", 8, Big, false),
+            PDP11 => ("Digital PDP-11", 2, Little, false),
+            FX66 => ("Siemens FX66", 2, Little, false),
+            ST9Plus => ("STMicroelectronics ST9+", 2, Big, false),
+            ST7 => ("STMicroelectronics ST7", 2, Big, false),
+            M68HC16 => ("Motorola MC68HC16", 2, Big, false),
+            M68HC11 => ("Motorola MC68HC11", 2, Big, false),
+            M68HC08 => ("Motorola MC68HC08", 2, Big, false),
+            M68HC05 => ("Motorola MC68HC05", 2, Big, false),
+            SVX => ("Silicon Graphics SVx", 8, Big, false),
+            ST19 => ("STMicroelectronics ST19", 2, Big, false),
+            VAX => ("Digital VAX", 4, Little, false),
+            CRIS => ("Axis CRIS", 4, Little, false),
+            Javelin => ("Infineon Javelin", 4, Little, false),
+            FirePath => ("Element 14 FirePath", 8, Little, false),
+            ZSP => ("LSI Logic ZSP", 2, Little, false),
+            MMIX => ("Knuth MMIX", 8, Big, false),
+            HUANY => ("Harvard machine independent", 4, Little, false),
+            Prism => ("SiTera Prism", 4, Little, false),
+            AVR => ("Atmel AVR", 2, Little, false),
+            FR30 => ("Fujitsu FR30", 4, Big, false),
+            D10V => ("Mitsubishi D10V", 2, Big, false),
+            D30V => ("Mitsubishi D30V", 4, Big, false),
+            V850 => ("NEC V850", 4, Little, false),
+            M32R => ("Mitsubishi M32R", 4, Big, false),
+            MN10300 => ("Matsushita MN10300", 4, Little, false),
+            MN10200 => ("Matsushita MN10200", 4, Little, false),
+            PicoJava => ("picoJava", 4, Big, false),
+            OpenRISC => ("OpenRISC", 4, Big, false),
+            ARCompact => ("ARC ARCompact", 4, Little, false),
+            Xtensa => ("Tensilica Xtensa", 4, Little, false),
+            VideoCore => ("Alphamosaic VideoCore", 4, Little, false),
+            TMM_GPP => ("Thomson Multimedia GPP", 4, Little, false),
+            NS32K => ("National Semiconductor 32000", 4, Little, false),
+            TPC => ("Tenor Network TPC", 4, Little, false),
+            SNP1K => ("Trebia SNP 1000", 4, Little, false),
+            ST200 => ("STMicroelectronics ST200", 4, Little, false),
+            IP2K => ("Ubicom IP2xxx", 2, Big, false),
+            MAX => ("MAX", 4, Little, false),
+            CompactRISC => ("National Semiconductor CompactRISC", 4, Little, false),
+            F2MC16 => ("Fujitsu F2MC16", 2, Little, false),
+            MSP430 => ("Texas Instruments MSP430", 2, Little, false),
+            Blackfin => ("Analog Devices Blackfin", 4, Little, false),
+            SE_C33 => ("Seiko Epson S1C33", 4, Little, false),
+            SEP => ("Sharp embedded microprocessor", 4, Little, false),
+            ARCA => ("Arca RISC", 4, Little, false),
+            UniCore => ("PKU-Unity UniCore", 4, Little, false),
+            Excess => ("eXcess", 4, Big, false),
+            DXP => ("Icera Deep Execution Processor", 4, Little, false),
+            Nios2 => ("Altera Nios II", 4, Little, false),
+            CRX => ("National Semiconductor CompactRISC CRX", 4, Little, false),
+            XGATE => ("Motorola XGATE", 2, Big, false),
+            C166 => ("Infineon C16x", 2, Little, false),
+            M16C => ("Renesas M16C", 2, Little, false),
+            DSPIC30F => ("Microchip dsPIC30F", 2, Little, false),
+            CE => ("Freescale Communication Engine", 4, Big, false),
+            M32C => ("Renesas M32C", 4, Little, false),
+            TSK3000 => ("Altium TSK3000", 4, Little, false),
+            RS08 => ("Freescale RS08", 2, Big, false),
+            SHARC => ("Analog Devices SHARC", 4, Little, false),
+            ECOG2 => ("Cyan eCOG2", 2, Little, false),
+            Score7 => ("Sunplus S+core7", 4, Little, false),
+            DSP24 => ("New Japan Radio DSP24", 4, Little, false),
+            VideoCore3 => ("Broadcom VideoCore III", 4, Little, false),
+            LatticeMico32 => ("Lattice Mico32", 4, Big, false),
+            SE_C17 => ("Seiko Epson C17", 2, Little, false),
+            TI_C6000 => ("Texas Instruments TMS320C6000", 4, Little, false),
+            TI_C2000 => ("Texas Instruments TMS320C2000", 4, Little, false),
+            TI_C5500 => ("Texas Instruments TMS320C55x", 4, Little, false),
+            TI_ARP32 => ("Texas Instruments ARP32", 4, Little, false),
+            TI_PRU => ("Texas Instruments PRU", 4, Little, false),
+            MMDSP_Plus => ("STMicroelectronics MMDSP+", 8, Little, false),
+            Cypress_M8C => ("Cypress M8C", 2, Big, false),
+            R32C => ("Renesas R32C", 4, Little, false),
+            TriMedia => ("NXP TriMedia", 4, Little, false),
+            Hexagon => ("Qualcomm Hexagon", 4, Little, false),
+            I8051 => ("Intel 8051", 2, Big, false),
+            STxP7x => ("STMicroelectronics STxP7x", 4, Little, false),
+            NDS32 => ("Andes NDS32", 4, Little, false),
+            ECOG1X => ("Cyan eCOG1X", 2, Little, false),
+            MAXQ30 => ("Dallas MAXQ30", 2, Little, false),
+            XIMO16 => ("New Japan Radio XIMO16", 2, Little, false),
+            Manik => ("M2000 Manik", 4, Little, false),
+            CrayNV2 => ("Cray NV2", 8, Big, false),
+            RX => ("Renesas RX", 4, Little, false),
+            MetaG => ("Imagination META", 4, Little, false),
+            Elbrus => ("MCST Elbrus", 8, Little, false),
+            ECOG16 => ("Cyan eCOG16", 2, Little, false),
+            CR16 => ("National Semiconductor CompactRISC CR16", 4, Little, false),
+            ETPU => ("Freescale eTPU", 4, Big, false),
+            SLE9X => ("Infineon SLE9X", 2, Little, false),
+            L10M => ("Intel L10M", 8, Little, false),
+            K10M => ("Intel K10M", 8, Little, false),
+            AArch64 => ("ARM AArch64", 8, Little, true),
+            AVR32 => ("Atmel AVR32", 4, Big, false),
+            STM8 => ("STMicroelectronics STM8", 2, Big, false),
+            Tile64 => ("Tilera TILE64", 4, Little, false),
+            TilePro => ("Tilera TILEPro", 4, Little, false),
+            MicroBlaze => ("Xilinx MicroBlaze", 4, Big, false),
+            CUDA => ("NVIDIA CUDA", 8, Little, false),
+            TileGx => ("Tilera TILE-Gx", 8, Little, false),
+            CloudShield => ("CloudShield", 4, Little, false),
+            CoreA_1st => ("KIPO-KAIST Core-A 1st generation", 4, Little, false),
+            CoreA_2nd => ("KIPO-KAIST Core-A 2nd generation", 4, Little, false),
+            ARCv2 => ("Synopsys ARCv2", 4, Little, false),
+            Open8 => ("Open8", 2, Little, false),
+            RL78 => ("Renesas RL78", 2, Little, false),
+            VideoCore5 => ("Broadcom VideoCore V", 4, Little, false),
+            R78KOR => ("Renesas 78KOR", 2, Little, false),
+            DSC56800EX => ("Freescale 56800EX", 2, Little, false),
+            BA1 => ("Beyond BA1", 4, Little, false),
+            BA2 => ("Beyond BA2", 4, Little, false),
+            XCore => ("XMOS xCORE", 4, Little, false),
+            PIC => ("Microchip 8 bit PIC", 2, Little, false),
+            IntelGT => ("Intel Graphics Technology", 8, Little, false),
+            KM32 => ("KM211 KM32", 4, Little, false),
+            KMX32 => ("KM211 KMX32", 4, Little, false),
+            KMX16 => ("KM211 KMX16", 2, Little, false),
+            KMX8 => ("KM211 KMX8", 2, Little, false),
+            KVARC => ("KM211 KVARC", 4, Little, false),
+            CDP => ("Paneve CDP", 4, Little, false),
+            COGE => ("Cognitive Smart Memory Processor", 4, Little, false),
+            Cool => ("Bluechip CoolEngine", 4, Little, false),
+            NORC => ("Nanoradio Optimized RISC", 4, Little, false),
+            Kalimba => ("CSR Kalimba", 4, Little, false),
+            Z80 => ("Zilog Z80", 2, Little, false),
+            Visium => ("VISIUMcore", 4, Big, false),
+            FT32 => ("FTDI FT32", 4, Little, false),
+            Moxie => ("Moxie", 4, Big, false),
+            AMDGPU => ("AMD GPU", 8, Little, false),
+            RISC_V => ("RISC-V", 8, Little, true),
+            Lanai => ("Lanai", 4, Big, false),
+            BPF => ("Linux BPF", 8, Little, false),
+            VE => ("NEC SX-Aurora VE", 8, Little, false),
+            CSKY => ("C-SKY", 4, Little, false),
+            LoongArch => ("LoongArch", 8, Little, false),
+            Other(_) => ("Unknown machine", 4, Little, false),
+        };
+        Metadata { name, pointer_size, endianness, disassemble }
+    }
+}
@@ -0,0 +1,728 @@
+use crate::disasm::aarch64::{gpr_sp, vector, CONDITIONS};
+use crate::disasm::{FlowKind, IndexMode, Instruction, MemoryOperand, Operand, Register, RegisterClass, ShiftKind};
+use crate::endian::Endianness;
+
+use super::builder::{
+    add, add_overflow, and, eq, extract, insert, is_zero, mentions, ne, negative, not, or, sub, sub_overflow, ult, xor,
+    Builder,
+};
+use super::statement::{BinaryOp, Destination, Expr, Stmt, Type, Var};
+use super::Lifter;
+
+const N: Var = Var::flag("n");
+const Z: Var = Var::flag("z");
+const C: Var = Var::flag("c");
+const V: Var = Var::flag("v");
+
+/// Lifts A64 instructions
+///
+/// The general purpose registers are the 64 bit x0 to x30 and sp: reading a w register truncates it and writing
+/// one clears the upper half. The SIMD and floating point registers are the 128 bit v0 to v31, which scalar writes
+/// clear above the scalar. The flags are n, z, c and v.
+pub struct AArch64Lifter {
+    endianness: Endianness,
+}
+
+impl AArch64Lifter {
+    pub fn new(endianness: Endianness) -> AArch64Lifter {
+        AArch64Lifter { endianness }
+    }
+}
+
+impl Lifter for AArch64Lifter {
+    fn lift(&self, instruction: &Instruction) -> Vec<Stmt> {
+        let mut lifter = InstructionLifter { b: Builder::new(self.endianness), instruction };
+        lifter.lift();
+        lifter.b.statements
+    }
+
+    fn stack_pointer(&self) -> Var {
+        Var::register("sp", Type::Int(64))
+    }
+}
+
+// The whole register a general purpose, SIMD or floating point register is part of
+fn full_register(register: &Register) -> Option<Var> {
+    match register.class {
+        RegisterClass::General => Some(Var::register(gpr_sp(register.number as u32, true).name, Type::Int(64))),
+        RegisterClass::Vector => Some(Var::register(vector(register.number as u32).name, Type::Int(128))),
+        RegisterClass::Zero => None,
+        _ => Some(Var::register(register.name, Type::Int(register.bits))),
+    }
+}
+
+struct InstructionLifter<'a> {
+    b: Builder,
+    instruction: &'a Instruction,
+}
+
+impl<'a> InstructionLifter<'a> {
+    fn width(&self, operand: &Operand) -> u16 {
+        match operand {
+            Operand::Register(register) => register.bits,
+            Operand::Vector { element_bits, lane: Some(_), .. } => *element_bits,
+            Operand::Vector { register, .. } => register.bits,
+            Operand::Memory(mem) => mem.size * 8,
+            _ => 64,
+        }
+    }
+
+    fn read_register(&self, register: &Register) -> Expr {
+        match full_register(register) {
+            None => Expr::int(0, register.bits),
+            Some(full) if full.ty.bits() == register.bits => Expr::var(full),
+            Some(full) => Expr::var(full).resize(register.bits, false),
+        }
+    }
+
+    fn write_register(&mut self, register: &Register, value: Expr) {
+        if let Some(full) = full_register(register) {
+            let value = value.resize(register.bits, false).resize(full.ty.bits(), false);
+            self.b.assign(full, value);
+        }
+    }
+
+    // The value of an operand, immediates are `bits` wide
+    fn read(&self, operand: &Operand, bits: u16) -> Option<Expr> {
+        Some(match operand {
+            Operand::Register(register) => self.read_register(register),
+            Operand::Immediate { value, .. } => Expr::int(*value as u64, bits),
+            Operand::Address(address) => Expr::int(*address, 64),
+            Operand::Vector { register, element_bits, lane: Some(lane), .. } => {
+                extract(self.read_register(register), element_bits * *lane as u16, *element_bits)
+            }
+            Operand::Vector { register, elements, element_bits, .. } => {
+                let value = self.read_register(register);
+                match *elements as u16 * element_bits {
+                    64 => value.resize(64, false),
+                    _ => value,
+                }
+            }
+            Operand::Memory(mem) => {
+                let address = self.address(mem).0;
+                self.b.load(address, mem.size * 8)
+            }
+            _ => return None,
+        })
+    }
+
+    fn write(&mut self, operand: &Operand, value: Expr) {
+        match operand {
+            Operand::Register(register) => self.write_register(register, value),
+            Operand::Vector { register, element_bits, lane: Some(lane), .. } => {
+                let old = self.read_register(register);
+                let value = insert(old, value.resize(*element_bits, false), element_bits * *lane as u16);
+                self.write_register(register, value);
+            }
+            Operand::Vector { register, .. } => self.write_register(register, value),
+            _ => {}
+        }
+    }
+
+    // Operand `i` with the shift or extension following it applied, as `bits` wide
+    fn shifted(&self, operands: &[Operand], i: usize, bits: u16) -> Option<Expr> {
+        let value = self.read(operands.get(i)?, bits)?;
+        Some(match operands.get(i + 1) {
+            Some(Operand::Shift { kind, amount }) => self.shift(value, *kind, *amount as u64, bits),
+            _ => value.resize(bits, false),
+        })
+    }
+
+    fn shift(&self, value: Expr, kind: ShiftKind, amount: u64, bits: u16) -> Expr {
+        let (from, signed) = match kind {
+            ShiftKind::Uxtb => (8, false),
+            ShiftKind::Uxth => (16, false),
+            ShiftKind::Uxtw => (32, false),
+            ShiftKind::Sxtb => (8, true),
+            ShiftKind::Sxth => (16, true),
+            ShiftKind::Sxtw => (32, true),
+            ShiftKind::Uxtx | ShiftKind::Sxtx => (64, false),
+            _ => {
+                if amount == 0 {
+                    return value.resize(bits, false);
+                }
+                let op = match kind {
+                    ShiftKind::Lsr => BinaryOp::LShr,
+                    ShiftKind::Asr => BinaryOp::AShr,
+                    ShiftKind::Ror => BinaryOp::RotR,
+                    _ => BinaryOp::Shl,
+                };
+                let value = value.resize(bits, false);
+                return Expr::binary(op, value, Expr::int(amount, bits));
+            }
+        };
+        let value = value.resize(from.min(bits), false).resize(bits, signed);
+        if amount == 0 {
+            value
+        } else {
+            Expr::binary(BinaryOp::Shl, value, Expr::int(amount, bits))
+        }
+    }
+
+    // The address a memory operand accesses, and the value to write back to its base register
+    fn address(&self, mem: &MemoryOperand) -> (Expr, Option<(Register, Expr)>) {
+        if let Some(target) = mem.target {
+            return (Expr::int(target, 64), None);
+        }
+        let base = mem.base.map_or(Expr::int(0, 64), |x| self.read_register(&x));
+        let offset = match mem.index {
+            Some(index) => {
+                let value = self.read_register(&index);
+                let amount = mem.scale.trailing_zeros() as u64;
+                Some(self.shift(value, mem.extend.unwrap_or(ShiftKind::Lsl), amount, 64))
+            }
+            None if mem.displacement != 0 => Some(Expr::int(mem.displacement as u64, 64)),
+            None => None,
+        };
+        let indexed = match offset {
+            Some(_) if mem.displacement < 0 && mem.index.is_none() => {
+                sub(base.clone(), Expr::int(mem.displacement.unsigned_abs(), 64))
+            }
+            Some(offset) => add(base.clone(), offset),
+            None => base.clone(),
+        };
+        match (mem.mode, mem.base) {
+            (IndexMode::PreIndex, Some(register)) => (indexed.clone(), Some((register, indexed))),
+            (IndexMode::PostIndex, Some(register)) => (base, Some((register, indexed))),
+            _ => (indexed, None),
+        }
+    }
+
+    // The condition with the given index of CONDITIONS
+    fn condition(&self, condition: u8) -> Expr {
+        let flag = |x: Var| Expr::var(x);
+        let less = || xor(flag(N), flag(V));
+        let positive = match CONDITIONS[(condition & 15) as usize] {
+            "eq" | "ne" => flag(Z),
+            "cs" | "cc" => flag(C),
+            "mi" | "pl" => flag(N),
+            "vs" | "vc" => flag(V),
+            "hi" | "ls" => and(flag(C), not(flag(Z))),
+            "ge" | "lt" => not(less()),
+            "gt" | "le" => and(not(flag(Z)), not(less())),
+            _ => return Expr::bool(true),
+        };
+        // Odd conditions are the negation of the even one before them
+        if condition & 1 == 1 {
+            not(positive)
+        } else {
+            positive
+        }
+    }
+
+    fn nzcv(&mut self, n: Expr, z: Expr, c: Expr, v: Expr) {
+        self.b.assign(N, n);
+        self.b.assign(Z, z);
+        self.b.assign(C, c);
+        self.b.assign(V, v);
+    }
+
+    fn lift(&mut self) {
+        let instruction = self.instruction;
+        let mnemonic = instruction.mnemonic.as_str();
+        let operands = &instruction.operands[..];
+        let width = operands.first().map_or(64, |x| self.width(x));
+
+        match (mnemonic, operands) {
+            ("mov", [d, _, ..]) => {
+                if let Some(value) = self.shifted(operands, 1, width) {
+                    self.write(d, value);
+                }
+            }
+            ("movz" | "movn", [d, ..]) => {
+                let Some(value) = self.shifted(operands, 1, width) else { return };
+                let value = if mnemonic == "movn" { not(value) } else { value };
+                self.write(d, value);
+            }
+            ("movk", [d, Operand::Immediate { value, .. }, rest @ ..]) => {
+                let amount = match rest {
+                    [Operand::Shift { amount, .. }] => *amount as u16,
+                    _ => 0,
+                };
+                let Some(old) = self.read(d, width) else { return };
+                self.write(d, insert(old, Expr::int(*value as u64, 16), amount));
+            }
+            ("add" | "adds" | "sub" | "subs", [d, ..]) => {
+                let (Some(a), Some(c)) = (self.read(&operands[1], width), self.shifted(operands, 2, width)) else { return };
+                self.add_sub(Some(d), a, c, mnemonic.starts_with("sub"), mnemonic.ends_with('s'));
+            }
+            ("cmp" | "cmn", [_, ..]) => {
+                let (Some(a), Some(c)) = (self.read(&operands[0], width), self.shifted(operands, 1, width)) else { return };
+                self.add_sub(None, a, c, mnemonic == "cmp", true);
+            }
+            ("neg" | "negs", [d, ..]) => {
+                let Some(c) = self.shifted(operands, 1, width) else { return };
+                self.add_sub(Some(d), Expr::int(0, width), c, true, mnemonic == "negs");
+            }
+            ("adc" | "adcs" | "sbc" | "sbcs", [d, rn, rm]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.read(rm, width)) else { return };
+                self.carry(d, a, c, mnemonic.starts_with("sbc"), mnemonic.ends_with('s'));
+            }
+            ("ngc" | "ngcs", [d, rm]) => {
+                let Some(c) = self.read(rm, width) else { return };
+                self.carry(d, Expr::int(0, width), c, true, mnemonic == "ngcs");
+            }
+            ("and" | "ands" | "orr" | "eor" | "bic" | "bics" | "orn" | "eon", [d, rn, ..]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.shifted(operands, 2, width)) else { return };
+                let c = if matches!(mnemonic, "bic" | "bics" | "orn" | "eon") { not(c) } else { c };
+                let result = match mnemonic {
+                    "orr" | "orn" => or(a, c),
+                    "eor" | "eon" => xor(a, c),
+                    _ => and(a, c),
+                };
+                if mnemonic.ends_with('s') {
+                    let result = self.b.temp(result);
+                    self.logic_flags(&result);
+                    self.write(d, result);
+                } else {
+                    self.write(d, result);
+                }
+            }
+            ("tst", [rn, ..]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.shifted(operands, 1, width)) else { return };
+                let result = self.b.temp(and(a, c));
+                self.logic_flags(&result);
+            }
+            ("mvn", [d, ..]) => {
+                if let Some(value) = self.shifted(operands, 1, width) {
+                    self.write(d, not(value));
+                }
+            }
+            ("lsl" | "lsr" | "asr" | "ror", [d, rn, amount]) => {
+                let Some(a) = self.read(rn, width) else { return };
+                let op = match mnemonic {
+                    "lsl" => BinaryOp::Shl,
+                    "lsr" => BinaryOp::LShr,
+                    "asr" => BinaryOp::AShr,
+                    _ => BinaryOp::RotR,
+                };
+                // Shifts by a register use it modulo the width
+                let amount = match amount {
+                    Operand::Immediate { value, .. } => Expr::int(*value as u64, width),
+                    _ => match self.read(amount, width) {
+                        Some(x) => and(x, Expr::int(width as u64 - 1, width)),
+                        None => return,
+                    },
+                };
+                self.write(d, Expr::binary(op, a, amount));
+            }
+            ("ubfx" | "sbfx" | "ubfiz" | "sbfiz" | "bfi" | "bfxil", [d, rn, lsb, field]) => {
+                let (Some(a), Some(lsb), Some(field)) = (self.read(rn, width), immediate(lsb), immediate(field)) else {
+                    return;
+                };
+                let (lsb, field) = (lsb as u16, field as u16);
+                if lsb + field > width || field == 0 {
+                    return self.fallback(mnemonic);
+                }
+                let value = match mnemonic {
+                    "ubfx" => extract(a, lsb, field).resize(width, false),
+                    "sbfx" => extract(a, lsb, field).resize(width, true),
+                    "ubfiz" => self.shift(a.resize(field, false), ShiftKind::Lsl, lsb as u64, width),
+                    "sbfiz" => self.shift(a.resize(field, false).resize(width, true), ShiftKind::Lsl, lsb as u64, width),
+                    "bfi" => match self.read(d, width) {
+                        Some(old) => insert(old, a.resize(field, false), lsb),
+                        None => return,
+                    },
+                    _ => match self.read(d, width) {
+                        Some(old) => insert(old, extract(a, lsb, field), 0),
+                        None => return,
+                    },
+                };
+                self.write(d, value);
+            }
+            ("bfc", [d, lsb, field]) => {
+                let (Some(old), Some(lsb), Some(field)) = (self.read(d, width), immediate(lsb), immediate(field)) else {
+                    return;
+                };
+                self.write(d, insert(old, Expr::int(0, field as u16), lsb as u16));
+            }
+            ("sxtb" | "sxth" | "sxtw" | "uxtb" | "uxth", [d, rn]) => {
+                let bits = match &mnemonic[3..] {
+                    "b" => 8,
+                    "h" => 16,
+                    _ => 32,
+                };
+                if let Some(a) = self.read(rn, bits) {
+                    self.write(d, a.resize(bits, false).resize(width, mnemonic.starts_with('s')));
+                }
+            }
+            ("extr", [d, rn, rm, lsb]) => {
+                let (Some(high), Some(low), Some(lsb)) = (self.read(rn, width), self.read(rm, width), immediate(lsb)) else {
+                    return;
+                };
+                let value = if lsb == 0 {
+                    low
+                } else {
+                    or(
+                        Expr::binary(BinaryOp::LShr, low, Expr::int(lsb, width)),
+                        Expr::binary(BinaryOp::Shl, high, Expr::int(width as u64 - lsb, width)),
+                    )
+                };
+                self.write(d, value);
+            }
+            ("mul" | "mneg" | "madd" | "msub", [d, rn, rm, rest @ ..]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.read(rm, width)) else { return };
+                let product = Expr::binary(BinaryOp::Mul, a, c);
+                let value = match (mnemonic, rest.first().and_then(|x| self.read(x, width))) {
+                    ("madd", Some(accumulator)) => add(accumulator, product),
+                    ("msub", Some(accumulator)) => sub(accumulator, product),
+                    ("mneg", _) => sub(Expr::int(0, width), product),
+                    _ => product,
+                };
+                self.write(d, value);
+            }
+            (
+                "smull" | "umull" | "smnegl" | "umnegl" | "smaddl" | "umaddl" | "smsubl" | "umsubl",
+                [d, rn, rm, rest @ ..],
+            ) => {
+                let signed = mnemonic.starts_with('s');
+                let (Some(a), Some(c)) = (self.read(rn, 32), self.read(rm, 32)) else { return };
+                let product = Expr::binary(BinaryOp::Mul, a.resize(64, signed), c.resize(64, signed));
+                let value = match (&mnemonic[1..], rest.first().and_then(|x| self.read(x, 64))) {
+                    ("maddl", Some(accumulator)) => add(accumulator, product),
+                    ("msubl", Some(accumulator)) => sub(accumulator, product),
+                    ("mnegl", _) => sub(Expr::int(0, 64), product),
+                    _ => product,
+                };
+                self.write(d, value);
+            }
+            ("smulh" | "umulh", [d, rn, rm]) => {
+                let signed = mnemonic == "smulh";
+                let (Some(a), Some(c)) = (self.read(rn, 64), self.read(rm, 64)) else { return };
+                let product = Expr::binary(BinaryOp::Mul, a.resize(128, signed), c.resize(128, signed));
+                self.write(d, extract(product, 64, 64));
+            }
+            ("udiv" | "sdiv", [d, rn, rm]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.read(rm, width)) else { return };
+                let op = if mnemonic == "udiv" { BinaryOp::UDiv } else { BinaryOp::SDiv };
+                // Dividing by zero gives zero
+                let c = self.b.temp(c);
+                let quotient = Expr::binary(op, a, c.clone());
+                self.write(d, Expr::select(is_zero(c), Expr::int(0, width), quotient));
+            }
+            ("adr" | "adrp", [d, Operand::Address(address)]) => self.write(d, Expr::int(*address, 64)),
+            ("csel" | "csinc" | "csinv" | "csneg", [d, rn, rm, Operand::Condition(condition)]) => {
+                let (Some(a), Some(c)) = (self.read(rn, width), self.read(rm, width)) else { return };
+                let otherwise = match mnemonic {
+                    "csinc" => add(c, Expr::int(1, width)),
+                    "csinv" => not(c),
+                    "csneg" => sub(Expr::int(0, width), c),
+                    _ => c,
+                };
+                self.write(d, Expr::select(self.condition(*condition), a, otherwise));
+            }
+            ("cset" | "csetm", [d, Operand::Condition(condition)]) => {
+                let one = if mnemonic == "cset" { 1 } else { u64::MAX };
+                let value = Expr::select(self.condition(*condition), Expr::int(one, width), Expr::int(0, width));
+                self.write(d, value);
+            }
+            ("cinc" | "cinv" | "cneg", [d, rn, Operand::Condition(condition)]) => {
+                let Some(a) = self.read(rn, width) else { return };
+                let changed = match mnemonic {
+                    "cinc" => add(a.clone(), Expr::int(1, width)),
+                    "cinv" => not(a.clone()),
+                    _ => sub(Expr::int(0, width), a.clone()),
+                };
+                self.write(d, Expr::select(self.condition(*condition), changed, a));
+            }
+            ("ccmp" | "ccmn", [rn, second, Operand::Immediate { value: flags, .. }, Operand::Condition(condition)]) => {
+                let width = self.width(rn);
+                let (Some(a), Some(c)) = (self.read(rn, width), self.read(second, width)) else { return };
+                let holds = self.b.temp(self.condition(*condition));
+                let result = if mnemonic == "ccmp" { sub(a.clone(), c.clone()) } else { add(a.clone(), c.clone()) };
+                let result = self.b.temp(result);
+                let (carry, overflow) = if mnemonic == "ccmp" {
+                    (not(ult(a.clone(), c.clone())), sub_overflow(&a, &c, &result))
+                } else {
+                    (ult(result.clone(), a.clone()), add_overflow(&a, &c, &result))
+                };
+                let given = |bit: u32| Expr::bool(flags & (1 << bit) != 0);
+                let select = |value: Expr, bit: u32| Expr::select(holds.clone(), value, given(bit));
+                self.nzcv(
+                    select(negative(result.clone()), 3),
+                    select(is_zero(result), 2),
+                    select(carry, 1),
+                    select(overflow, 0),
+                );
+            }
+            ("mrs", [d, Operand::Register(register)]) => {
+                let value = Expr::var(Var::register(register.name, Type::Int(64)));
+                self.write(d, value);
+            }
+            ("msr", [Operand::Register(register), s]) if register.class == RegisterClass::Special => {
+                if let Some(value) = self.read(s, 64) {
+                    self.b.assign(Var::register(register.name, Type::Int(64)), value.resize(64, false));
+                }
+            }
+            ("fmov", [d, Operand::Float(value)]) => {
+                let bits = self.width(d);
+                let value = match bits {
+                    64 => *value,
+                    32 => (f64::from_bits(*value) as f32).to_bits() as u64,
+                    _ => return self.fallback(mnemonic),
+                };
+                self.write(d, Expr::int(value, bits));
+            }
+            ("fmov", [d @ Operand::Register(_), s @ Operand::Register(_)]) => {
+                if let Some(value) = self.read(s, width) {
+                    self.write(d, value);
+                }
+            }
+            ("b", _) => {
+                let destination = self.destination(operands.first());
+                self.b.jump(destination);
+            }
+            ("bl", _) => {
+                let destination = self.destination(operands.first());
+                self.b.push(Stmt::call(destination));
+            }
+            ("cbz" | "cbnz", [rt, _]) => {
+                let Some(value) = self.read(rt, width) else { return };
+                let condition = if mnemonic == "cbz" { is_zero(value) } else { ne(value, Expr::int(0, width)) };
+                let target = self.destination(None);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            ("tbz" | "tbnz", [rt, bit, _]) => {
+                let (Some(value), Some(bit)) = (self.read(rt, width), immediate(bit)) else { return };
+                let set = ne(and(value, Expr::int(1 << bit, width)), Expr::int(0, width));
+                let condition = if mnemonic == "tbz" { not(set) } else { set };
+                let target = self.destination(None);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            _ if mnemonic.starts_with("b.") => {
+                let index = CONDITIONS.iter().position(|x| *x == &mnemonic[2..]).unwrap_or(14);
+                let condition = self.condition(index as u8);
+                let target = self.destination(None);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            _ if instruction.flow == FlowKind::Return => self.b.push(Stmt::Return),
+            // br, blr and their authenticating forms
+            _ if instruction.flow == FlowKind::Jump => {
+                let destination = self.destination(operands.first());
+                self.b.jump(destination);
+            }
+            _ if instruction.flow == FlowKind::Call => {
+                let destination = self.destination(operands.first());
+                self.b.push(Stmt::call(destination));
+            }
+            _ if instruction.flow == FlowKind::Trap => self.b.push(Stmt::Trap),
+            _ if instruction.flow == FlowKind::Syscall => self.b.push(Stmt::syscall()),
+            _ if is_hint(mnemonic) => {}
+            _ if operands.iter().any(|x| matches!(x, Operand::Memory(_))) && is_plain_access(mnemonic) => {
+                self.memory(mnemonic)
+            }
+            _ => self.fallback(mnemonic),
+        }
+    }
+
+    fn destination(&self, operand: Option<&Operand>) -> Destination {
+        match self.instruction.branch_target() {
+            Some(target) => Destination::Direct(target),
+            None => match operand.and_then(|x| self.read(x, 64)) {
+                Some(x) => Destination::Indirect(x),
+                None => Destination::Indirect(Expr::var(Var::register("x30", Type::Int(64)))),
+            },
+        }
+    }
+
+    // add, sub and their flag setting forms, and cmp, cmn and neg
+    fn add_sub(&mut self, d: Option<&Operand>, a: Expr, c: Expr, subtract: bool, set_flags: bool) {
+        let result = if subtract { sub(a.clone(), c.clone()) } else { add(a.clone(), c.clone()) };
+        if !set_flags {
+            if let Some(d) = d {
+                self.write(d, result);
+            }
+            return;
+        }
+        let result = self.b.temp(if subtract { sub(a.clone(), c.clone()) } else { add(a.clone(), c.clone()) });
+        // The carry of a subtraction is set when it doesn't borrow
+        let (carry, overflow) = if subtract {
+            (not(ult(a.clone(), c.clone())), sub_overflow(&a, &c, &result))
+        } else {
+            (ult(result.clone(), a.clone()), add_overflow(&a, &c, &result))
+        };
+        self.nzcv(negative(result.clone()), is_zero(result.clone()), carry, overflow);
+        if let Some(d) = d {
+            self.write(d, result);
+        }
+    }
+
+    // adc, sbc and their flag setting forms
+    fn carry(&mut self, d: &Operand, a: Expr, c: Expr, subtract: bool, set_flags: bool) {
+        let width = a.ty().bits();
+        let carry = Expr::var(C).resize(width, false);
+        // a - b - !c is a + ~b + c
+        let result = if subtract {
+            sub(sub(a.clone(), c.clone()), xor(carry, Expr::int(1, width)))
+        } else {
+            add(add(a.clone(), c.clone()), carry)
+        };
+        let result = self.b.temp(result);
+        if set_flags {
+            let (carry, overflow) = if subtract {
+                let carry = and(not(ult(a.clone(), c.clone())), or(Expr::var(C), ne(a.clone(), c.clone())));
+                (carry, sub_overflow(&a, &c, &result))
+            } else {
+                let carry = or(ult(result.clone(), a.clone()), and(Expr::var(C), eq(result.clone(), a.clone())));
+                (carry, add_overflow(&a, &c, &result))
+            };
+            self.nzcv(negative(result.clone()), is_zero(result.clone()), carry, overflow);
+        }
+        self.write(d, result);
+    }
+
+    fn logic_flags(&mut self, result: &Expr) {
+        self.nzcv(negative(result.clone()), is_zero(result.clone()), Expr::bool(false), Expr::bool(false));
+    }
+
+    // Loads and stores of one or two registers, with their exclusive, acquire and release forms
+    fn memory(&mut self, mnemonic: &str) {
+        let operands = &self.instruction.operands;
+        let Some(position) = operands.iter().position(|x| matches!(x, Operand::Memory(_))) else { return };
+        let Operand::Memory(mem) = &operands[position] else { return };
+        let load = mnemonic.starts_with("ld");
+        // Exclusive stores write their status to the first register
+        let exclusive_store = !load && mnemonic.contains('x');
+        let registers = if exclusive_store { &operands[1..position] } else { &operands[..position] };
+        if registers.is_empty() {
+            return self.fallback(mnemonic);
+        }
+        let bits = mem.size * 8 / registers.len() as u16;
+        let signed = load && ["sb", "sh", "sw"].iter().any(|x| mnemonic.ends_with(x));
+
+        let (address, writeback) = self.address(mem);
+        // Pairs use the address twice and their first load may overwrite the base register
+        let overwritten = load && registers.iter().any(|x| match x {
+            Operand::Register(register) => full_register(register).is_some_and(|x| mentions(&address, x)),
+            _ => false,
+        });
+        let needed = registers.len() > 1 && (overwritten || !matches!(address, Expr::Var(_) | Expr::Const { .. }));
+        let temp = if needed { Some(self.b.temp(address.clone())) } else { None };
+        let writeback = writeback.map(|(register, value)| match &temp {
+            Some(temp) if value == address => (register, temp.clone()),
+            _ => (register, value),
+        });
+        let address = temp.unwrap_or(address);
+        for (i, register) in registers.iter().enumerate() {
+            let offset = i as u64 * bits as u64 / 8;
+            let address = if i == 0 { address.clone() } else { add(address.clone(), Expr::int(offset, 64)) };
+            if load {
+                let value = self.b.load(address, bits);
+                let width = self.width(register);
+                self.write(register, value.resize(width, signed));
+            } else if let Some(value) = self.read(register, bits) {
+                self.b.store(address, value.resize(bits, false));
+            }
+        }
+        if exclusive_store {
+            let status = operands[0].clone();
+            self.write(&status, Expr::intrinsic("exclusive_monitor", vec![], Type::Int(32)));
+        }
+        if let Some((register, value)) = writeback {
+            self.write_register(&register, value);
+        }
+    }
+
+    // Instructions without a model: the first operand, which is the destination of most of them, is assigned an
+    // intrinsic of all the operands. Floating point comparisons set the flags.
+    fn fallback(&mut self, mnemonic: &str) {
+        let operands = &self.instruction.operands;
+        let args: Vec<Expr> = operands.iter().filter_map(|x| self.read(x, self.width(x))).collect();
+        if mnemonic.starts_with("fcmp") || mnemonic.starts_with("fccmp") {
+            for flag in [N, Z, C, V] {
+                self.b.assign(flag, Expr::intrinsic(mnemonic, args.clone(), Type::Bool));
+            }
+            return;
+        }
+        match operands.first() {
+            Some(d @ (Operand::Register(_) | Operand::Vector { .. })) if !writes_nothing(mnemonic) => {
+                let value = Expr::intrinsic(mnemonic, args, Type::Int(self.width(d)));
+                let d = d.clone();
+                self.write(&d, value);
+            }
+            _ => self.b.intrinsic(mnemonic, args),
+        }
+    }
+}
+
+fn immediate(operand: &Operand) -> Option<u64> {
+    match operand {
+        Operand::Immediate { value, .. } => Some(*value as u64),
+        _ => None,
+    }
+}
+
+// Hints and barriers, which don't change registers or memory as far as the IR is concerned
+fn is_hint(mnemonic: &str) -> bool {
+    matches!(
+        mnemonic,
+        "nop" | "yield" | "wfe" | "wfi" | "sev" | "sevl" | "dmb" | "dsb" | "isb" | "sb" | "csdb" | "esb" | "psb" | "tsb"
+            | "bti" | "hint" | "clrex" | "prfm" | "prfum" | "ssbb" | "pssbb"
+    ) || ["pac", "aut", "xpac"].iter().any(|x| mnemonic.starts_with(x))
+}
+
+// Loads and stores that only move registers to and from memory, as opposed to the atomic read-modify-write
+// instructions and the vector structure loads
+fn is_plain_access(mnemonic: &str) -> bool {
+    let stem = ["ldr", "ldur", "ldtr", "ldp", "ldnp", "ldxr", "ldxp", "ldaxr", "ldaxp", "ldar", "ldapr", "ldapur", "ldlar"]
+        .iter()
+        .chain(["str", "stur", "sttr", "stp", "stnp", "stxr", "stxp", "stlxr", "stlxp", "stlr", "stlur", "stllr"].iter())
+        .filter(|x| mnemonic.starts_with(*x))
+        .max_by_key(|x| x.len());
+    stem.is_some_and(|x| ["", "b", "h", "sb", "sh", "sw"].contains(&&mnemonic[x.len()..]))
+}
+
+// Instructions whose first operand is only read
+fn writes_nothing(mnemonic: &str) -> bool {
+    mnemonic.starts_with("st") || matches!(mnemonic, "msr" | "sys" | "at" | "dc" | "ic" | "tlbi" | "cmpp")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AArch64Lifter;
+    use crate::disasm::aarch64::AArch64Decoder;
+    use crate::disasm::Decoder;
+    use crate::endian::Endianness;
+    use crate::ir::Lifter;
+
+    fn lift(word: u32) -> Vec<String> {
+        let instruction = AArch64Decoder.decode(&word.to_le_bytes(), 0x1000).unwrap();
+        AArch64Lifter::new(Endianness::LittleEndian).lift(&instruction).iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn arithmetic() {
+        // add x0, x1, x2; add x0, x1, w2, uxtw #2
+        assert_eq!(lift(0x8b020020), ["x0 = x1 + x2"]);
+        assert_eq!(lift(0x8b224820), ["x0 = x1 + (zext64(trunc32(x2)) << 2)"]);
+        // cmp x0, #1: the carry is set when there's no borrow
+        assert_eq!(lift(0xf100041f), [
+            "t0 = x0 - 1",
+            "n = t0 <s 0",
+            "z = t0 == 0",
+            "c = !(x0 <u 1)",
+            "v = ((x0 ^ 1) & (x0 ^ t0)) <s 0",
+        ]);
+        // csel w0, w1, w2, ge
+        assert_eq!(lift(0x1a82a020), ["x0 = zext64(!(n ^ v) ? trunc32(x1) : trunc32(x2))"]);
+        // adrp x0, 0x13000
+        assert_eq!(lift(0xd0000080), ["x0 = 0x13000"]);
+    }
+
+    #[test]
+    fn memory() {
+        // ldr w0, [x1, #8]; str x1, [sp, #-16]!; stp x29, x30, [sp, #-16]!
+        assert_eq!(lift(0xb9400820), ["x0 = zext64(mem32[x1 + 8])"]);
+        assert_eq!(lift(0xf81f0fe1), ["mem64[sp - 0x10] = x1", "sp = sp - 0x10"]);
+        assert_eq!(lift(0xa9bf7bfd), ["t0 = sp - 0x10", "mem64[t0] = x29", "mem64[t0 + 8] = x30", "sp = t0"]);
+    }
+
+    #[test]
+    fn control_flow() {
+        // cbz x0, 0x1008; b.eq 0x1008; bl 0x1010; ret
+        assert_eq!(lift(0xb4000040), ["if x0 == 0 jump 0x1008"]);
+        assert_eq!(lift(0x54000040), ["if z jump 0x1008"]);
+        assert_eq!(lift(0x94000004), ["call 0x1010"]);
+        assert_eq!(lift(0xd65f03c0), ["return"]);
+    }
+}
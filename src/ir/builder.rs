@@ -0,0 +1,158 @@
+use crate::endian::Endianness;
+
+use super::statement::{BinaryOp, CompareOp, Destination, Expr, Stmt, Type, UnaryOp, Var};
+
+// Collects the statements of one instruction. Temporaries are numbered from 0 for each instruction, functions
+// renumber them.
+pub(super) struct Builder {
+    pub statements: Vec<Stmt>,
+    next_temp: u32,
+    pub endianness: Endianness,
+}
+
+impl Builder {
+    pub fn new(endianness: Endianness) -> Builder {
+        Builder { statements: Vec::new(), next_temp: 0, endianness }
+    }
+
+    pub fn push(&mut self, stmt: Stmt) {
+        self.statements.push(stmt);
+    }
+
+    pub fn assign(&mut self, var: Var, value: Expr) {
+        self.push(Stmt::Assign { var, value });
+    }
+
+    // Evaluates `value` into a new temporary, so that later statements see it as it was
+    pub fn temp(&mut self, value: Expr) -> Expr {
+        // Constants and variables that aren't assigned again can be used as they are, but which are isn't known here
+        if let Expr::Const { .. } = value {
+            return value;
+        }
+        let var = Var::temp(self.next_temp, value.ty());
+        self.next_temp += 1;
+        self.assign(var, value);
+        Expr::Var(var)
+    }
+
+    pub fn load(&self, address: Expr, bits: u16) -> Expr {
+        Expr::load(address, Type::Int(bits), self.endianness)
+    }
+
+    pub fn store(&mut self, address: Expr, value: Expr) {
+        let endianness = self.endianness;
+        self.push(Stmt::Store { address, value, endianness });
+    }
+
+    pub fn jump(&mut self, destination: Destination) {
+        self.push(Stmt::Jump(destination));
+    }
+
+    pub fn intrinsic(&mut self, name: &str, args: Vec<Expr>) {
+        self.push(Stmt::Intrinsic { name: name.to_string(), args });
+    }
+}
+
+// Shorthands for building expressions in the lifters
+
+pub(super) fn add(left: Expr, right: Expr) -> Expr {
+    Expr::binary(BinaryOp::Add, left, right)
+}
+
+pub(super) fn sub(left: Expr, right: Expr) -> Expr {
+    Expr::binary(BinaryOp::Sub, left, right)
+}
+
+pub(super) fn and(left: Expr, right: Expr) -> Expr {
+    Expr::binary(BinaryOp::And, left, right)
+}
+
+pub(super) fn or(left: Expr, right: Expr) -> Expr {
+    Expr::binary(BinaryOp::Or, left, right)
+}
+
+pub(super) fn xor(left: Expr, right: Expr) -> Expr {
+    Expr::binary(BinaryOp::Xor, left, right)
+}
+
+pub(super) fn not(value: Expr) -> Expr {
+    Expr::unary(UnaryOp::Not, value)
+}
+
+pub(super) fn eq(left: Expr, right: Expr) -> Expr {
+    Expr::compare(CompareOp::Eq, left, right)
+}
+
+pub(super) fn ne(left: Expr, right: Expr) -> Expr {
+    Expr::compare(CompareOp::Ne, left, right)
+}
+
+pub(super) fn ult(left: Expr, right: Expr) -> Expr {
+    Expr::compare(CompareOp::ULt, left, right)
+}
+
+pub(super) fn slt(left: Expr, right: Expr) -> Expr {
+    Expr::compare(CompareOp::SLt, left, right)
+}
+
+// Whether an expression reads a variable
+pub(super) fn mentions(expr: &Expr, var: Var) -> bool {
+    let mut found = false;
+    expr.for_each_var(&mut |x| found |= x.storage == var.storage);
+    found
+}
+
+// A constant of the same type as `like`
+pub(super) fn constant(value: u64, like: &Expr) -> Expr {
+    match like.ty() {
+        Type::Bool => Expr::bool(value != 0),
+        Type::Int(bits) => Expr::int(value, bits),
+    }
+}
+
+// Whether the value is negative as a signed integer
+pub(super) fn negative(value: Expr) -> Expr {
+    let zero = constant(0, &value);
+    slt(value, zero)
+}
+
+pub(super) fn is_zero(value: Expr) -> Expr {
+    let zero = constant(0, &value);
+    eq(value, zero)
+}
+
+// Signed overflow of left + right giving result: both operands have the same sign and the result the other
+pub(super) fn add_overflow(left: &Expr, right: &Expr, result: &Expr) -> Expr {
+    negative(and(xor(result.clone(), left.clone()), xor(result.clone(), right.clone())))
+}
+
+// Signed overflow of left - right giving result: the operands have different signs and the result has the sign
+// of the right one
+pub(super) fn sub_overflow(left: &Expr, right: &Expr, result: &Expr) -> Expr {
+    negative(and(xor(left.clone(), right.clone()), xor(left.clone(), result.clone())))
+}
+
+// The bits `offset..offset + bits` of a value
+pub(super) fn extract(value: Expr, offset: u16, bits: u16) -> Expr {
+    let shifted = if offset == 0 {
+        value
+    } else {
+        let ty = value.ty();
+        Expr::binary(BinaryOp::LShr, value, Expr::int(offset as u64, ty.bits()))
+    };
+    shifted.resize(bits, false)
+}
+
+// `value` with the bits `offset..offset + part.ty().bits()` replaced by `part`
+pub(super) fn insert(value: Expr, part: Expr, offset: u16) -> Expr {
+    let bits = value.ty().bits();
+    let part_bits = part.ty().bits();
+    let mask = if part_bits >= 64 { u64::MAX } else { ((1u64 << part_bits) - 1) << offset };
+    // Constants are zero extended, so the kept bits of values wider than 64 bits are given by the complement
+    let kept = if bits > 64 { and(value, not(Expr::int(mask, bits))) } else { and(value, Expr::int(!mask, bits)) };
+    let mut part = part.resize(bits, false);
+    if offset != 0 {
+        part = Expr::binary(BinaryOp::Shl, part, Expr::int(offset as u64, bits));
+    }
+    or(kept, part)
+}
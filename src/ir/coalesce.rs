@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::analysis::{BlockId, Edge, EdgeKind};
+
+use super::function::{Block, Function};
+use super::statement::{Expr, Stmt, Storage, Var};
+
+// Variables whose values are needed at the same time, by variable
+type Interference = BTreeMap<Var, BTreeSet<Var>>;
+
+impl Function {
+    /// Takes a function out of SSA form. A phi and its sources are coalesced into one variable where their values
+    /// are never needed at the same time, the sources that can't be get copied into the phi on the edge from their
+    /// block. The copies go at the end of the block, or in a new block on the edge when the block branches. The
+    /// variables coalesced are renamed to the first of them, as the map returned gives.
+    ///
+    /// Phis of the entry block getting the values of the exit block, which a function looping back to its start
+    /// has, are dropped along with all the other phis.
+    pub fn leave_ssa(&mut self) -> BTreeMap<Var, Var> {
+        let interference = self.interference();
+
+        // Classes of variables by the first of them, and the class of each variable
+        let mut classes: BTreeMap<Var, Vec<Var>> = BTreeMap::new();
+        let mut class: BTreeMap<Var, Var> = BTreeMap::new();
+        let mut copies: BTreeMap<(BlockId, BlockId), Vec<(Var, Expr)>> = BTreeMap::new();
+        for (block, (_, stmt)) in self.blocks.iter().enumerate().flat_map(|(i, x)| x.statements.iter().map(move |x| (i, x))) {
+            let Stmt::Phi { var, sources } = stmt else { continue };
+            for (predecessor, value) in sources.iter() {
+                if *predecessor == self.exit {
+                    continue;
+                }
+                let coalesced = match value {
+                    Expr::Var(source) if source.ty == var.ty => {
+                        let (a, b) = (find(&class, var), find(&class, source));
+                        let members = |x: &Var| classes.get(x).cloned().unwrap_or_else(|| vec![*x]);
+                        let (left, right) = (members(&a), members(&b));
+                        let interferes = left.iter().any(|x| {
+                            interference.get(x).is_some_and(|others| right.iter().any(|y| others.contains(y)))
+                        });
+                        if a != b && !interferes {
+                            let (first, second) = if key(&a) <= key(&b) { (a, b) } else { (b, a) };
+                            let mut merged = members(&first);
+                            merged.extend(members(&second));
+                            for x in merged.iter() {
+                                class.insert(*x, first);
+                            }
+                            classes.remove(&second);
+                            classes.insert(first, merged);
+                        }
+                        a == b || !interferes
+                    }
+                    _ => false,
+                };
+                if !coalesced {
+                    copies.entry((*predecessor, block)).or_default().push((*var, value.clone()));
+                }
+            }
+        }
+
+        let rename = |var: &mut Var| *var = find(&class, var);
+        let mut next_temp = 0;
+        for (_, stmt) in self.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+            for var in stmt.definitions_mut() {
+                if let Storage::Temp(id) = var.storage {
+                    next_temp = next_temp.max(id + 1);
+                }
+                rename(var);
+            }
+            for expr in stmt.expressions_mut() {
+                expr.for_each_var_mut(&mut |x| rename(x));
+            }
+        }
+        for block in self.blocks.iter_mut() {
+            block.statements.retain(|x| !matches!(x.1, Stmt::Phi { .. }));
+        }
+
+        for ((from, to), copies) in copies {
+            let copies: Vec<(Var, Expr)> = copies
+                .into_iter()
+                .map(|(mut var, mut value)| {
+                    rename(&mut var);
+                    value.for_each_var_mut(&mut |x| rename(x));
+                    (var, value)
+                })
+                .collect();
+            let copies = sequentialize(copies, &mut next_temp);
+            if copies.is_empty() {
+                continue;
+            }
+            let mut successors = self.successors(from);
+            successors.dedup();
+            if successors == [to] {
+                let start = self.blocks[to].start;
+                let statements = &mut self.blocks[from].statements;
+                let address = statements.last().map_or(start, |x| x.0);
+                let at = match statements.last() {
+                    Some((_, Stmt::Jump(_) | Stmt::Branch { .. } | Stmt::Return)) => statements.len() - 1,
+                    _ => statements.len(),
+                };
+                statements.splice(at..at, copies.into_iter().map(|(var, value)| (address, Stmt::Assign { var, value })));
+                continue;
+            }
+            let split = self.blocks.len();
+            let address = self.blocks[to].start;
+            let statements = copies.into_iter().map(|(var, value)| (address, Stmt::Assign { var, value })).collect();
+            self.blocks.push(Block { start: address, statements });
+            for edge in self.edges.iter_mut().filter(|x| x.from == from && x.to == to) {
+                edge.to = split;
+            }
+            self.edges.push(Edge { from: split, to, kind: EdgeKind::Unconditional });
+        }
+        class
+    }
+
+    // Which variables are live at the definition of each other, from the variables live at the end of each block.
+    // The sources of phis are read at the end of the blocks they come from, the variables read before being
+    // assigned at the start of the entry block.
+    fn interference(&self) -> Interference {
+        let blocks = self.blocks.len();
+        let mut used: Vec<BTreeSet<Var>> = vec![BTreeSet::new(); blocks];
+        let mut defined: Vec<BTreeSet<Var>> = vec![BTreeSet::new(); blocks];
+        let mut phi_sources: BTreeMap<(BlockId, BlockId), BTreeSet<Var>> = BTreeMap::new();
+        for (block, statements) in self.blocks.iter().enumerate().map(|(i, x)| (i, &x.statements)) {
+            for (_, stmt) in statements.iter() {
+                if let Stmt::Phi { sources, .. } = stmt {
+                    for (predecessor, value) in sources.iter() {
+                        let live = phi_sources.entry((*predecessor, block)).or_default();
+                        value.for_each_var(&mut |x| {
+                            live.insert(*x);
+                        });
+                    }
+                } else {
+                    stmt.for_each_use(&mut |x| {
+                        if !defined[block].contains(x) {
+                            used[block].insert(*x);
+                        }
+                    });
+                }
+                defined[block].extend(stmt.definitions().into_iter().copied());
+            }
+        }
+
+        let mut live_in: Vec<BTreeSet<Var>> = vec![BTreeSet::new(); blocks];
+        let mut live_out: Vec<BTreeSet<Var>> = vec![BTreeSet::new(); blocks];
+        let order = self.reverse_postorder();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in order.iter().rev().copied() {
+                let mut out = BTreeSet::new();
+                for successor in self.successors(block) {
+                    out.extend(live_in[successor].iter().copied());
+                    out.extend(phi_sources.get(&(block, successor)).into_iter().flatten().copied());
+                }
+                let mut live: BTreeSet<Var> = out.difference(&defined[block]).copied().collect();
+                live.extend(used[block].iter().copied());
+                if live != live_in[block] || out != live_out[block] {
+                    live_in[block] = live;
+                    live_out[block] = out;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut res = Interference::new();
+        let mut interfere = |a: &Var, b: &Var| {
+            if a != b {
+                res.entry(*a).or_default().insert(*b);
+                res.entry(*b).or_default().insert(*a);
+            }
+        };
+        for block in order.iter().copied() {
+            let mut live = live_out[block].clone();
+            let mut phis = Vec::new();
+            for (_, stmt) in self.blocks[block].statements.iter().rev() {
+                if let Stmt::Phi { var, .. } = stmt {
+                    phis.push(*var);
+                    continue;
+                }
+                let definitions = stmt.definitions();
+                for var in definitions.iter() {
+                    live.iter().for_each(|x| interfere(var, x));
+                }
+                for var in definitions {
+                    live.remove(var);
+                }
+                stmt.for_each_use(&mut |x| {
+                    live.insert(*x);
+                });
+            }
+            // The phis are all assigned at once when the block starts
+            for var in phis.iter() {
+                live.iter().for_each(|x| interfere(var, x));
+                phis.iter().for_each(|x| interfere(var, x));
+            }
+            if block == self.entry {
+                for var in live.iter() {
+                    live.iter().for_each(|x| interfere(var, x));
+                }
+            }
+        }
+        res
+    }
+}
+
+fn find(class: &BTreeMap<Var, Var>, var: &Var) -> Var {
+    class.get(var).copied().unwrap_or(*var)
+}
+
+// The variable a class is named after: what a register holds on entry, otherwise the earliest version
+fn key(var: &Var) -> (u32, Var) {
+    (var.version, *var)
+}
+
+// Copies done at once as copies done one after the other: a copy goes before those overwriting what it reads.
+// Copies reading each other in a cycle go through a new temporary.
+fn sequentialize(copies: Vec<(Var, Expr)>, next_temp: &mut u32) -> Vec<(Var, Expr)> {
+    let mut pending: Vec<(Var, Expr)> = copies.into_iter().filter(|x| x.1 != Expr::var(x.0)).collect();
+    let reads = |expr: &Expr, var: &Var| {
+        let mut res = false;
+        expr.for_each_var(&mut |x| res |= x == var);
+        res
+    };
+    let mut res = Vec::new();
+    while !pending.is_empty() {
+        let free = (0..pending.len())
+            .find(|i| pending.iter().enumerate().all(|(j, x)| j == *i || !reads(&x.1, &pending[*i].0)));
+        match free {
+            Some(i) => res.push(pending.remove(i)),
+            None => {
+                let var = pending[0].0;
+                let temp = Var::temp(*next_temp, var.ty);
+                *next_temp += 1;
+                res.push((temp, Expr::var(var)));
+                for (_, value) in pending.iter_mut() {
+                    value.substitute(&mut |x| if *x == var { Some(Expr::var(temp)) } else { None });
+                }
+            }
+        }
+    }
+    res
+}
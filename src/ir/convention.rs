@@ -0,0 +1,484 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::disasm::Target;
+use crate::elf::OsABI;
+use crate::instruction_set::InstructionSet;
+
+use super::frame::StackFrame;
+use super::function::Function;
+use super::ssa::{CallEffects, DefUse, Location};
+use super::statement::{Destination, Expr, Stmt, Storage, Type, Var};
+
+/// A calling convention, by the name of the ABI defining it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Abi {
+    SysV64,
+    Cdecl,
+    Stdcall,
+    Fastcall,
+    Aapcs64,
+    Aapcs32,
+    // AAPCS with the base procedure call standard only, as soft-float binaries use: floating point values are passed
+    // in r0-r3 and on the stack like integers
+    Aapcs32Soft,
+    // The ARM ABI before EABI: the same registers, but floating point values are passed as integers
+    Apcs,
+    RiscV32,
+    RiscV64,
+    MipsO32,
+    MipsN64,
+}
+
+impl Abi {
+    pub const ALL: [Abi; 12] = [
+        Abi::SysV64,
+        Abi::Cdecl,
+        Abi::Stdcall,
+        Abi::Fastcall,
+        Abi::Aapcs64,
+        Abi::Aapcs32,
+        Abi::Aapcs32Soft,
+        Abi::Apcs,
+        Abi::RiscV32,
+        Abi::RiscV64,
+        Abi::MipsO32,
+        Abi::MipsN64,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Abi::SysV64 => "sysv64",
+            Abi::Cdecl => "cdecl",
+            Abi::Stdcall => "stdcall",
+            Abi::Fastcall => "fastcall",
+            Abi::Aapcs64 => "aapcs64",
+            Abi::Aapcs32 => "aapcs32",
+            Abi::Aapcs32Soft => "aapcs32-soft",
+            Abi::Apcs => "apcs",
+            Abi::RiscV32 => "riscv32",
+            Abi::RiscV64 => "riscv64",
+            Abi::MipsO32 => "o32",
+            Abi::MipsN64 => "n64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Abi> {
+        Abi::ALL.iter().copied().find(|x| x.name() == name)
+    }
+
+    /// The convention functions of the target follow unless told otherwise. The ELF OS ABIs all share the System V
+    /// conventions of the processor, except for the ARM ABI predating EABI. ARM binaries flagged as soft-float pass
+    /// floating point values in the integer registers. MIPS n32 passes arguments like n64.
+    pub fn detect(target: &Target) -> Option<Abi> {
+        match target.instruction_set {
+            InstructionSet::X86_64 => Some(Abi::SysV64),
+            InstructionSet::X86 => Some(Abi::Cdecl),
+            InstructionSet::AArch64 => Some(Abi::Aapcs64),
+            InstructionSet::ARM if target.os_abi == OsABI::ARM => Some(Abi::Apcs),
+            InstructionSet::ARM if target.soft_float => Some(Abi::Aapcs32Soft),
+            InstructionSet::ARM => Some(Abi::Aapcs32),
+            InstructionSet::RISC_V if target.bits == 64 => Some(Abi::RiscV64),
+            InstructionSet::RISC_V => Some(Abi::RiscV32),
+            InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE if target.bits == 64 || target.mips64 => Some(Abi::MipsN64),
+            InstructionSet::MIPS | InstructionSet::MIPS_RS3_LE => Some(Abi::MipsO32),
+            _ => None,
+        }
+    }
+
+    pub fn convention(self) -> CallingConvention {
+        match self {
+            Abi::SysV64 => CallingConvention {
+                abi: self,
+                stack_pointer: int("rsp", 64),
+                arguments: ints(&["rdi", "rsi", "rdx", "rcx", "r8", "r9"], 64),
+                float_arguments: ints(&XMM[..8], 128),
+                results: ints(&["rax", "rdx"], 64),
+                float_results: ints(&XMM[..2], 128),
+                callee_saved: ints(&["rbx", "rbp", "r12", "r13", "r14", "r15", "fs_base", "gs_base"], 64),
+                return_address: None,
+                stack_arguments: 8,
+                slot_size: 8,
+                callee_pops: false,
+                red_zone: 128,
+            },
+            Abi::Cdecl | Abi::Stdcall | Abi::Fastcall => CallingConvention {
+                abi: self,
+                stack_pointer: int("esp", 32),
+                arguments: if self == Abi::Fastcall { ints(&["ecx", "edx"], 32) } else { Vec::new() },
+                float_arguments: Vec::new(),
+                results: ints(&["eax", "edx"], 32),
+                float_results: Vec::new(),
+                callee_saved: ints(&["ebx", "esi", "edi", "ebp", "fs_base", "gs_base"], 32),
+                return_address: None,
+                stack_arguments: 4,
+                slot_size: 4,
+                callee_pops: self != Abi::Cdecl,
+                red_zone: 0,
+            },
+            Abi::Aapcs64 => CallingConvention {
+                abi: self,
+                stack_pointer: int("sp", 64),
+                arguments: ints(&X[..8], 64),
+                float_arguments: ints(&V[..8], 128),
+                results: ints(&X[..2], 64),
+                float_results: ints(&V[..4], 128),
+                callee_saved: ints(&X[19..30], 64),
+                return_address: Some(int("x30", 64)),
+                stack_arguments: 0,
+                slot_size: 8,
+                callee_pops: false,
+                red_zone: 0,
+            },
+            Abi::Aapcs32 | Abi::Aapcs32Soft | Abi::Apcs => CallingConvention {
+                abi: self,
+                stack_pointer: int("sp", 32),
+                arguments: ints(&["r0", "r1", "r2", "r3"], 32),
+                float_arguments: if self == Abi::Aapcs32 { ints(&D[..8], 64) } else { Vec::new() },
+                results: ints(&["r0", "r1"], 32),
+                float_results: if self == Abi::Aapcs32 { ints(&D[..1], 64) } else { Vec::new() },
+                callee_saved: ints(&["r4", "r5", "r6", "r7", "r8", "r9", "sl", "fp"], 32),
+                return_address: Some(int("lr", 32)),
+                stack_arguments: 0,
+                slot_size: 4,
+                callee_pops: false,
+                red_zone: 0,
+            },
+            Abi::RiscV32 | Abi::RiscV64 => {
+                let bits = if self == Abi::RiscV64 { 64 } else { 32 };
+                CallingConvention {
+                    abi: self,
+                    stack_pointer: int("sp", bits),
+                    arguments: ints(&["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"], bits),
+                    float_arguments: ints(&["fa0", "fa1", "fa2", "fa3", "fa4", "fa5", "fa6", "fa7"], 64),
+                    results: ints(&["a0", "a1"], bits),
+                    float_results: ints(&["fa0", "fa1"], 64),
+                    callee_saved: ints(&["s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11"], bits),
+                    return_address: Some(int("ra", bits)),
+                    stack_arguments: 0,
+                    slot_size: bits as u64 / 8,
+                    callee_pops: false,
+                    red_zone: 0,
+                }
+            }
+            // o32 reserves stack slots for the four argument registers, n64 doesn't
+            Abi::MipsO32 => CallingConvention {
+                abi: self,
+                stack_pointer: int("sp", 32),
+                arguments: ints(&["a0", "a1", "a2", "a3"], 32),
+                float_arguments: ints(&["$f12", "$f14"], 64),
+                results: ints(&["v0", "v1"], 32),
+                float_results: ints(&["$f0"], 64),
+                callee_saved: ints(&["s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8"], 32),
+                return_address: Some(int("ra", 32)),
+                stack_arguments: 16,
+                slot_size: 4,
+                callee_pops: false,
+                red_zone: 0,
+            },
+            Abi::MipsN64 => CallingConvention {
+                abi: self,
+                stack_pointer: int("sp", 64),
+                arguments: ints(&["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"], 64),
+                float_arguments: ints(&["$f12", "$f13", "$f14", "$f15", "$f16", "$f17", "$f18", "$f19"], 64),
+                results: ints(&["v0", "v1"], 64),
+                float_results: ints(&["$f0", "$f2"], 64),
+                callee_saved: ints(&["s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "gp"], 64),
+                return_address: Some(int("ra", 64)),
+                stack_arguments: 0,
+                slot_size: 8,
+                callee_pops: false,
+                red_zone: 0,
+            },
+        }
+    }
+}
+
+const XMM: [&str; 8] = ["xmm0", "xmm1", "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7"];
+const V: [&str; 8] = ["v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7"];
+const D: [&str; 8] = ["d0", "d1", "d2", "d3", "d4", "d5", "d6", "d7"];
+#[rustfmt::skip]
+const X: [&str; 31] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14", "x15",
+    "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28", "x29", "x30",
+];
+
+fn int(name: &'static str, bits: u16) -> Var {
+    Var::register(name, Type::Int(bits))
+}
+
+fn ints(names: &[&'static str], bits: u16) -> Vec<Var> {
+    names.iter().map(|x| int(x, bits)).collect()
+}
+
+/// Where a calling convention passes arguments and results, and what calls preserve. Registers are named as the
+/// lifters name them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallingConvention {
+    pub abi: Abi,
+    pub stack_pointer: Var,
+    // Integer and pointer arguments, in order
+    pub arguments: Vec<Var>,
+    pub float_arguments: Vec<Var>,
+    pub results: Vec<Var>,
+    pub float_results: Vec<Var>,
+    // Registers a call leaves as they were, besides the stack pointer. On x86 these include the bases of the thread
+    // local segments.
+    pub callee_saved: Vec<Var>,
+    // The register holding the return address on entry, if it isn't pushed on the stack by the call
+    pub return_address: Option<Var>,
+    /// Offset from the stack pointer at entry of the first argument passed on the stack
+    pub stack_arguments: u64,
+    /// Size of a stack argument slot in bytes
+    pub slot_size: u64,
+    /// The function removes its stack arguments when it returns
+    pub callee_pops: bool,
+    /// Bytes below the stack pointer the function may use without moving it
+    pub red_zone: u64,
+}
+
+impl CallEffects {
+    /// Calls following the convention: they read the argument registers, and write the result registers and those
+    /// they don't preserve which the function mentions. Floating point registers only count if it mentions them.
+    pub fn for_convention(function: &Function, convention: &CallingConvention) -> CallEffects {
+        let registers: BTreeSet<Var> = function.registers().into_iter().collect();
+        let mentioned = |vars: &[Var]| -> Vec<Var> { vars.iter().copied().filter(|x| registers.contains(x)).collect() };
+        let float_arguments = mentioned(&convention.float_arguments);
+        let float_results = mentioned(&convention.float_results);
+
+        let arguments: Vec<Var> = convention.arguments.iter().copied().chain(float_arguments).collect();
+        let outputs: Vec<Var> = convention.results.iter().copied().chain(float_results).collect();
+        let preserved: BTreeSet<_> = convention.callee_saved.iter().map(|x| x.storage).collect();
+        let mut results: BTreeSet<Var> = registers
+            .into_iter()
+            .filter(|x| x.storage != convention.stack_pointer.storage && !preserved.contains(&x.storage))
+            .collect();
+        results.extend(outputs.iter().copied());
+        CallEffects { arguments, results: results.into_iter().collect(), outputs }
+    }
+}
+
+/// A parameter of a function: a register or a slot of the stack, by its offset from the stack pointer at entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Parameter {
+    Register(Var),
+    Stack { offset: i64, bits: u16 },
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Parameter::Register(var) => write!(f, "{}", var),
+            Parameter::Stack { offset, .. } if *offset < 0 => write!(f, "stack-{}", offset.unsigned_abs()),
+            Parameter::Stack { offset, .. } => write!(f, "stack+{}", offset),
+        }
+    }
+}
+
+/// What a function takes and returns
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Prototype {
+    pub abi: Abi,
+    pub parameters: Vec<Parameter>,
+    pub returns: Vec<Var>,
+}
+
+impl fmt::Display for Prototype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (", self.abi.name())?;
+        for (i, parameter) in self.parameters.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { "" } else { ", " }, parameter)?;
+        }
+        write!(f, ")")?;
+        match self.returns.as_slice() {
+            [] => write!(f, " -> void"),
+            returns => {
+                write!(f, " ->")?;
+                for (i, var) in returns.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, var)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Prototype {
+    /// The prototype of a function in SSA form built with effects for the convention. Parameters are the argument
+    /// registers read before being assigned, up to the last one of each class, and the stack arguments read. Once
+    /// arguments are read from the stack all of the integer registers are taken to be. Passing a register on to a
+    /// call only makes it a parameter if the callee is known to take it, as `taken` tells for the target of a call
+    /// by the integer and floating point registers it takes. The function returns the first result register if what
+    /// it leaves there isn't what it was called with, and what it returns there is read.
+    pub fn recover(
+        function: &Function,
+        convention: &CallingConvention,
+        frame: &StackFrame,
+        taken: &dyn Fn(&Destination) -> Option<(usize, usize)>,
+    ) -> Prototype {
+        let definitions = DefUse::build(function);
+        let returns = [convention.results.first(), convention.float_results.first()]
+            .iter()
+            .flatten()
+            .copied()
+            .find(|x| is_returned(function, &definitions, x))
+            .map(|x| vec![*x])
+            .unwrap_or_default();
+        let returned = returns.first().map(|x| x.storage);
+        let mut used = Used { function, definitions: &definitions, convention, returned, taken, visiting: BTreeSet::new() };
+
+        // Integers only go on the stack once the registers are taken
+        let stack_arguments: Vec<Parameter> =
+            frame.arguments().filter(|x| x.read).map(|x| Parameter::Stack { offset: x.offset, bits: x.bits }).collect();
+        let mut parameters = Vec::new();
+        for (i, class) in [&convention.arguments, &convention.float_arguments].iter().enumerate() {
+            let last = match i == 0 && !stack_arguments.is_empty() {
+                true => class.len().checked_sub(1),
+                false => class.iter().rposition(|x| used.is_used(&Var { version: 0, ..*x })),
+            };
+            parameters.extend(class[..last.map_or(0, |x| x + 1)].iter().map(|x| Parameter::Register(*x)));
+        }
+        parameters.extend(stack_arguments);
+        Prototype { abi: convention.abi, parameters, returns }
+    }
+}
+
+// Whether a variable is read by anything but calls that aren't known to take it as an argument and the phis of the
+// exit block for registers not returned, or by phis whose values are
+struct Used<'a> {
+    function: &'a Function,
+    definitions: &'a DefUse,
+    convention: &'a CallingConvention,
+    returned: Option<Storage>,
+    taken: &'a dyn Fn(&Destination) -> Option<(usize, usize)>,
+    visiting: BTreeSet<Var>,
+}
+
+impl<'a> Used<'a> {
+    fn is_used(&mut self, var: &Var) -> bool {
+        // A phi reached again around a loop adds nothing
+        if !self.visiting.insert(*var) {
+            return false;
+        }
+        let function = self.function;
+        let res = self.definitions.uses(var).iter().any(|location| match function.statement(*location) {
+            Stmt::Call { target: Destination::Indirect(target), .. } if mentions(target, var) => true,
+            Stmt::Call { target, arguments, .. } => self.is_argument(target, arguments, var),
+            Stmt::Syscall { .. } => false,
+            Stmt::Phi { var: phi, .. } if location.block == function.exit => Some(phi.storage) == self.returned,
+            Stmt::Phi { var: phi, .. } => self.is_used(&phi.clone()),
+            _ => true,
+        });
+        self.visiting.remove(var);
+        res
+    }
+
+    // Whether a call passes the variable in a register its callee takes. The arguments of calls in SSA form are the
+    // integer argument registers of the convention followed by the floating point ones.
+    fn is_argument(&self, target: &Destination, arguments: &[Expr], var: &Var) -> bool {
+        let Some((ints, floats)) = (self.taken)(target) else { return false };
+        let integers = self.convention.arguments.len();
+        arguments.iter().enumerate().any(|(i, x)| {
+            mentions(x, var)
+                && match i.checked_sub(integers) {
+                    None => i < ints,
+                    Some(i) => i < floats,
+                }
+        })
+    }
+}
+
+fn mentions(expr: &Expr, var: &Var) -> bool {
+    let mut res = false;
+    expr.for_each_var(&mut |x| res |= x == var);
+    res
+}
+
+// Whether the register's phi in the exit block gets something else than the value the function was called with
+fn is_returned(function: &Function, definitions: &DefUse, register: &Var) -> bool {
+    let phi = function.blocks[function.exit].statements.iter().find_map(|(_, stmt)| match stmt {
+        Stmt::Phi { var, sources } if var.storage == register.storage => Some(sources),
+        _ => None,
+    });
+    let Some(sources) = phi else { return false };
+    let entry = Var { version: 0, ..*register };
+    let mut seen = BTreeSet::new();
+    let mut work: Vec<&Expr> = sources.iter().map(|x| &x.1).collect();
+    while let Some(expr) = work.pop() {
+        let Expr::Var(var) = expr else { return true };
+        if *var == entry || !seen.insert(*var) {
+            continue;
+        }
+        let location: Option<Location> = definitions.definition(var);
+        match location.map(|x| function.statement(x)) {
+            Some(Stmt::Assign { value, .. }) => work.push(value),
+            Some(Stmt::Phi { sources, .. }) => work.extend(sources.iter().map(|x| &x.1)),
+            _ => return true,
+        }
+    }
+    false
+}
+
+/// The calling conventions and prototypes of functions: detected for the target, unless overridden for a function
+/// by its start address
+#[derive(Debug, Clone, Default)]
+pub struct Conventions {
+    pub default: Option<Abi>,
+    abis: BTreeMap<u64, Abi>,
+    prototypes: BTreeMap<u64, Prototype>,
+}
+
+impl Conventions {
+    pub fn new(target: &Target) -> Conventions {
+        Conventions { default: Abi::detect(target), ..Conventions::default() }
+    }
+
+    pub fn set_abi(&mut self, start: u64, abi: Abi) {
+        self.abis.insert(start, abi);
+    }
+
+    pub fn set_prototype(&mut self, start: u64, prototype: Prototype) {
+        self.abis.insert(start, prototype.abi);
+        self.prototypes.insert(start, prototype);
+    }
+
+    /// The ABI of the function starting at `start`
+    pub fn abi(&self, start: u64) -> Option<Abi> {
+        self.abis.get(&start).copied().or(self.default)
+    }
+
+    /// The prototype of the function starting at `start`, as given or else recovered from the function in SSA form,
+    /// with `taken` telling the argument registers of the functions it calls
+    pub fn prototype(&self, start: u64, function: &Function, taken: &dyn Fn(&Destination) -> Option<(usize, usize)>) -> Option<Prototype> {
+        if let Some(prototype) = self.prototypes.get(&start) {
+            return Some(prototype.clone());
+        }
+        let convention = self.abi(start)?.convention();
+        let frame = StackFrame::analyze(function, &convention);
+        Some(Prototype::recover(function, &convention, &frame, taken))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Endianness;
+
+    #[test]
+    fn arm_float_abi() {
+        let mut target = Target::new(InstructionSet::ARM, Endianness::LittleEndian, 32);
+        let hard = Abi::detect(&target).unwrap().convention();
+        assert_eq!(hard.abi, Abi::Aapcs32);
+        assert_eq!(hard.float_arguments.iter().map(|x| x.to_string()).collect::<Vec<_>>(), &D[..8]);
+
+        target.soft_float = true;
+        let soft = Abi::detect(&target).unwrap().convention();
+        assert_eq!(soft.abi, Abi::Aapcs32Soft);
+        assert!(soft.float_arguments.is_empty() && soft.float_results.is_empty());
+        assert_eq!(soft.arguments, hard.arguments);
+        assert_eq!(Abi::from_name("aapcs32-soft"), Some(Abi::Aapcs32Soft));
+
+        target.os_abi = OsABI::ARM;
+        assert_eq!(Abi::detect(&target), Some(Abi::Apcs));
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::convention::CallingConvention;
+use super::function::Function;
+use super::statement::{BinaryOp, Expr, Stmt, Var};
+
+/// A slot of the stack the function reads or writes, by its offset from the stack pointer at entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StackSlot {
+    pub offset: i64,
+    pub bits: u16,
+    pub read: bool,
+    pub written: bool,
+}
+
+/// The stack frame of a function in SSA form, from tracking the stack pointer: which variables point into the
+/// stack, and where
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    offsets: BTreeMap<Var, i64>,
+    stack_pointer: Var,
+    // Offset of the first argument passed on the stack
+    stack_arguments: i64,
+    /// The slots accessed, by offset and size
+    pub slots: Vec<StackSlot>,
+    /// How far below its value at entry the stack pointer goes
+    pub depth: u64,
+    /// The stack pointer is moved by amounts not known statically, to allocate or realign the stack
+    pub dynamic: bool,
+}
+
+// What is known of a variable: it's the stack pointer at entry plus some offset, or it isn't known to be
+type Offset = Option<i64>;
+
+impl StackFrame {
+    pub fn analyze(function: &Function, convention: &CallingConvention) -> StackFrame {
+        let mut frame = StackFrame {
+            offsets: BTreeMap::new(),
+            stack_pointer: Var { version: 0, ..convention.stack_pointer },
+            stack_arguments: convention.stack_arguments as i64,
+            slots: Vec::new(),
+            depth: 0,
+            dynamic: false,
+        };
+
+        // Variables missing from `values` aren't assigned by anything seen yet
+        let mut values: BTreeMap<Var, Offset> = BTreeMap::new();
+        let order = function.reverse_postorder();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (_, stmt) in order.iter().flat_map(|x| function.blocks[*x].statements.iter()) {
+                let value = match stmt {
+                    Stmt::Assign { value, .. } => frame.evaluate(value, &values),
+                    Stmt::Phi { sources, .. } => {
+                        let known: BTreeSet<Offset> = sources
+                            .iter()
+                            .filter(|x| !matches!(&x.1, Expr::Var(var) if var.version != 0 && !values.contains_key(var)))
+                            .map(|x| frame.evaluate(&x.1, &values))
+                            .collect();
+                        match known.len() {
+                            0 => continue,
+                            1 => known.into_iter().next().unwrap(),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                for var in stmt.definitions() {
+                    let old = values.get(var).copied();
+                    // Once a variable is found to vary it stays so
+                    let new = match old {
+                        Some(old) if old != value => None,
+                        _ => value,
+                    };
+                    if old != Some(new) {
+                        values.insert(*var, new);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        frame.offsets = values.iter().filter_map(|(var, offset)| Some((*var, (*offset)?))).collect();
+
+        let storage = frame.stack_pointer.storage;
+        for (_, offset) in values.iter().filter(|x| x.0.storage == storage) {
+            match offset {
+                Some(offset) => frame.depth = frame.depth.max(offset.saturating_neg().max(0) as u64),
+                None => frame.dynamic = true,
+            }
+        }
+
+        let mut slots: BTreeMap<(i64, u16), StackSlot> = BTreeMap::new();
+        let mut access = |frame: &StackFrame, address: &Expr, bits: u16, write: bool| {
+            if let Some(offset) = frame.offset(address) {
+                let slot = slots.entry((offset, bits)).or_insert(StackSlot { offset, bits, read: false, written: false });
+                slot.read |= !write;
+                slot.written |= write;
+            }
+        };
+        for (_, stmt) in order.iter().flat_map(|x| function.blocks[*x].statements.iter()) {
+            if let Stmt::Store { address, value, .. } = stmt {
+                access(&frame, address, value.ty().bits(), true);
+            }
+            for expr in stmt.expressions() {
+                for_each_load(expr, &mut |address, bits| access(&frame, address, bits, false));
+            }
+        }
+        frame.slots = slots.into_values().collect();
+        frame
+    }
+
+    // The offset an expression has from the stack pointer at entry, given those of the variables
+    fn evaluate(&self, expr: &Expr, values: &BTreeMap<Var, Offset>) -> Offset {
+        match expr {
+            Expr::Var(var) if *var == self.stack_pointer => Some(0),
+            Expr::Var(var) => values.get(var).copied().flatten(),
+            Expr::Binary(op, left, right) => self.evaluate(left, values)?.checked_add(step(*op, right)?),
+            _ => None,
+        }
+    }
+
+    /// The offset from the stack pointer at entry an expression has, if it points into the stack
+    pub fn offset(&self, expr: &Expr) -> Option<i64> {
+        match expr {
+            Expr::Var(var) if *var == self.stack_pointer => Some(0),
+            Expr::Var(var) => self.offsets.get(var).copied(),
+            Expr::Binary(op, left, right) => self.offset(left)?.checked_add(step(*op, right)?),
+            _ => None,
+        }
+    }
+
+    /// The slots below the stack pointer at entry: locals, spills and saved registers
+    pub fn locals(&self) -> impl Iterator<Item = &StackSlot> {
+        self.slots.iter().filter(|x| x.offset < 0)
+    }
+
+    /// The slots holding arguments passed on the stack
+    pub fn arguments(&self) -> impl Iterator<Item = &StackSlot> {
+        let start = self.stack_arguments;
+        self.slots.iter().filter(move |x| x.offset >= start)
+    }
+}
+
+// How much adding or subtracting a constant moves a pointer. Constants narrower than 64 bits are offsets of the
+// same width.
+fn step(op: BinaryOp, amount: &Expr) -> Option<i64> {
+    let bits = amount.ty().bits();
+    let value = amount.constant()?;
+    let value = if bits >= 64 { value as i64 } else { ((value << (64 - bits)) as i64) >> (64 - bits) };
+    match op {
+        BinaryOp::Add => Some(value),
+        BinaryOp::Sub => value.checked_neg(),
+        _ => None,
+    }
+}
+
+// Calls `f` with the address and size of every load in the expression
+pub(super) fn for_each_load(expr: &Expr, f: &mut dyn FnMut(&Expr, u16)) {
+    match expr {
+        Expr::Const { .. } | Expr::Var(_) => {}
+        Expr::Load { address, ty, .. } => {
+            f(address, ty.bits());
+            for_each_load(address, f);
+        }
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => for_each_load(value, f),
+        Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+            for_each_load(left, f);
+            for_each_load(right, f);
+        }
+        Expr::Select { condition, then, otherwise } => {
+            for_each_load(condition, f);
+            for_each_load(then, f);
+            for_each_load(otherwise, f);
+        }
+        Expr::Intrinsic { args, .. } => args.iter().for_each(|x| for_each_load(x, f)),
+    }
+}
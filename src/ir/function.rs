@@ -0,0 +1,90 @@
+use std::fmt;
+
+use crate::analysis::{BlockId, Cfg, Edge};
+
+use super::statement::{Stmt, Storage};
+use super::Lifter;
+
+/// The statements of a block of the control flow graph, with the address of the instruction each comes from
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub start: u64,
+    pub statements: Vec<(u64, Stmt)>,
+}
+
+/// A function in the intermediate representation. Blocks and edges are those of the control flow graph it is
+/// lifted from, including the exit block without statements.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub entry: BlockId,
+    pub exit: BlockId,
+    pub blocks: Vec<Block>,
+    pub edges: Vec<Edge>,
+}
+
+impl Function {
+    /// Lifts the instructions of the blocks of `cfg`. Temporaries are renumbered to be unique in the function.
+    pub fn lift(cfg: &Cfg, lifter: &dyn Lifter) -> Function {
+        let mut next_temp = 0;
+        let mut blocks = Vec::with_capacity(cfg.blocks.len());
+        for block in cfg.blocks.iter() {
+            let mut statements = Vec::new();
+            for instruction in block.instructions.iter() {
+                let lifted = lifter.lift(instruction);
+                let mut count = 0;
+                for mut stmt in lifted {
+                    let mut renumber = |storage: &mut Storage| {
+                        if let Storage::Temp(id) = storage {
+                            count = count.max(*id + 1);
+                            *id += next_temp;
+                        }
+                    };
+                    for var in stmt.definitions_mut() {
+                        renumber(&mut var.storage);
+                    }
+                    for expr in stmt.expressions_mut() {
+                        expr.for_each_var_mut(&mut |x| renumber(&mut x.storage));
+                    }
+                    statements.push((instruction.address, stmt));
+                }
+                next_temp += count;
+            }
+            blocks.push(Block { start: block.start, statements });
+        }
+        Function { entry: cfg.entry, exit: cfg.exit, blocks, edges: cfg.edges.clone() }
+    }
+
+    pub fn successors(&self, block: BlockId) -> Vec<BlockId> {
+        self.edges.iter().filter(|x| x.from == block).map(|x| x.to).collect()
+    }
+
+    pub fn predecessors(&self, block: BlockId) -> Vec<BlockId> {
+        self.edges.iter().filter(|x| x.to == block).map(|x| x.from).collect()
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, block) in self.blocks.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            // In SSA form the exit block has the phis of the outputs
+            if i == self.exit {
+                writeln!(f, "Block {} (exit)", i)?;
+                for (_, stmt) in block.statements.iter() {
+                    writeln!(f, "    {:8}  {}", "", stmt)?;
+                }
+                continue;
+            }
+            writeln!(f, "Block {} 0x{:x}{}", i, block.start, if i == self.entry { " (entry)" } else { "" })?;
+            for (address, stmt) in block.statements.iter() {
+                writeln!(f, "    {:08x}  {}", address, stmt)?;
+            }
+            for edge in self.edges.iter().filter(|x| x.from == i) {
+                writeln!(f, "    -> {} {}", edge.to, edge.kind)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+/// The prototype of a function of the C library or the C runtime, with the types as written in C
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub name: &'static str,
+    pub returns: &'static str,
+    pub parameters: &'static [&'static str],
+    // Takes more arguments after the parameters, like printf
+    pub variadic: bool,
+    // Never returns to the caller
+    pub noreturn: bool,
+}
+
+const fn function(name: &'static str, returns: &'static str, parameters: &'static [&'static str]) -> Signature {
+    Signature { name, returns, parameters, variadic: false, noreturn: false }
+}
+
+impl Signature {
+    const fn variadic(self) -> Signature {
+        Signature { variadic: true, ..self }
+    }
+
+    const fn noreturn(self) -> Signature {
+        Signature { noreturn: true, ..self }
+    }
+}
+
+const STRING: &str = "const char *";
+const FILE: &str = "FILE *";
+
+#[rustfmt::skip]
+const SIGNATURES: &[Signature] = &[
+    // stdio.h
+    function("puts", "int", &[STRING]),
+    function("printf", "int", &[STRING]).variadic(),
+    function("fprintf", "int", &[FILE, STRING]).variadic(),
+    function("dprintf", "int", &["int", STRING]).variadic(),
+    function("sprintf", "int", &["char *", STRING]).variadic(),
+    function("snprintf", "int", &["char *", "size_t", STRING]).variadic(),
+    function("__printf_chk", "int", &["int", STRING]).variadic(),
+    function("__fprintf_chk", "int", &[FILE, "int", STRING]).variadic(),
+    function("__sprintf_chk", "int", &["char *", "int", "size_t", STRING]).variadic(),
+    function("__snprintf_chk", "int", &["char *", "size_t", "int", "size_t", STRING]).variadic(),
+    function("scanf", "int", &[STRING]).variadic(),
+    function("fscanf", "int", &[FILE, STRING]).variadic(),
+    function("sscanf", "int", &[STRING, STRING]).variadic(),
+    function("__isoc99_scanf", "int", &[STRING]).variadic(),
+    function("__isoc99_fscanf", "int", &[FILE, STRING]).variadic(),
+    function("__isoc99_sscanf", "int", &[STRING, STRING]).variadic(),
+    function("fgets", "char *", &["char *", "int", FILE]),
+    function("fputs", "int", &[STRING, FILE]),
+    function("fputc", "int", &["int", FILE]),
+    function("putc", "int", &["int", FILE]),
+    function("putchar", "int", &["int"]),
+    function("fgetc", "int", &[FILE]),
+    function("getc", "int", &[FILE]),
+    function("getchar", "int", &[]),
+    function("ungetc", "int", &["int", FILE]),
+    function("fopen", FILE, &[STRING, STRING]),
+    function("fdopen", FILE, &["int", STRING]),
+    function("fclose", "int", &[FILE]),
+    function("fflush", "int", &[FILE]),
+    function("fread", "size_t", &["void *", "size_t", "size_t", FILE]),
+    function("fwrite", "size_t", &["const void *", "size_t", "size_t", FILE]),
+    function("fseek", "int", &[FILE, "long", "int"]),
+    function("ftell", "long", &[FILE]),
+    function("rewind", "void", &[FILE]),
+    function("feof", "int", &[FILE]),
+    function("ferror", "int", &[FILE]),
+    function("fileno", "int", &[FILE]),
+    function("setvbuf", "int", &[FILE, "char *", "int", "size_t"]),
+    function("perror", "void", &[STRING]),
+    function("remove", "int", &[STRING]),
+    function("rename", "int", &[STRING, STRING]),
+    // stdlib.h
+    function("malloc", "void *", &["size_t"]),
+    function("calloc", "void *", &["size_t", "size_t"]),
+    function("realloc", "void *", &["void *", "size_t"]),
+    function("free", "void", &["void *"]),
+    function("atoi", "int", &[STRING]),
+    function("atol", "long", &[STRING]),
+    function("strtol", "long", &[STRING, "char **", "int"]),
+    function("strtoul", "unsigned long", &[STRING, "char **", "int"]),
+    function("strtoll", "long long", &[STRING, "char **", "int"]),
+    function("strtoull", "unsigned long long", &[STRING, "char **", "int"]),
+    function("getenv", "char *", &[STRING]),
+    function("setenv", "int", &[STRING, STRING, "int"]),
+    function("system", "int", &[STRING]),
+    function("rand", "int", &[]),
+    function("srand", "void", &["unsigned int"]),
+    function("qsort", "void", &["void *", "size_t", "size_t", "int (*)(const void *, const void *)"]),
+    function("bsearch", "void *", &["const void *", "const void *", "size_t", "size_t",
+                                    "int (*)(const void *, const void *)"]),
+    function("abs", "int", &["int"]),
+    function("labs", "long", &["long"]),
+    function("atexit", "int", &["void (*)(void)"]),
+    function("exit", "void", &["int"]).noreturn(),
+    function("_exit", "void", &["int"]).noreturn(),
+    function("_Exit", "void", &["int"]).noreturn(),
+    function("abort", "void", &[]).noreturn(),
+    // string.h
+    function("strlen", "size_t", &[STRING]),
+    function("strnlen", "size_t", &[STRING, "size_t"]),
+    function("strcmp", "int", &[STRING, STRING]),
+    function("strncmp", "int", &[STRING, STRING, "size_t"]),
+    function("strcasecmp", "int", &[STRING, STRING]),
+    function("strncasecmp", "int", &[STRING, STRING, "size_t"]),
+    function("strcpy", "char *", &["char *", STRING]),
+    function("strncpy", "char *", &["char *", STRING, "size_t"]),
+    function("strcat", "char *", &["char *", STRING]),
+    function("strncat", "char *", &["char *", STRING, "size_t"]),
+    function("strchr", "char *", &[STRING, "int"]),
+    function("strrchr", "char *", &[STRING, "int"]),
+    function("strstr", "char *", &[STRING, STRING]),
+    function("strdup", "char *", &[STRING]),
+    function("strndup", "char *", &[STRING, "size_t"]),
+    function("strtok", "char *", &["char *", STRING]),
+    function("strerror", "char *", &["int"]),
+    function("memcpy", "void *", &["void *", "const void *", "size_t"]),
+    function("memmove", "void *", &["void *", "const void *", "size_t"]),
+    function("memset", "void *", &["void *", "int", "size_t"]),
+    function("memcmp", "int", &["const void *", "const void *", "size_t"]),
+    function("memchr", "void *", &["const void *", "int", "size_t"]),
+    function("__memcpy_chk", "void *", &["void *", "const void *", "size_t", "size_t"]),
+    function("__memset_chk", "void *", &["void *", "int", "size_t", "size_t"]),
+    function("__strcpy_chk", "char *", &["char *", STRING, "size_t"]),
+    // ctype.h
+    function("toupper", "int", &["int"]),
+    function("tolower", "int", &["int"]),
+    function("__ctype_b_loc", "const unsigned short **", &[]),
+    // unistd.h and fcntl.h
+    function("open", "int", &[STRING, "int"]).variadic(),
+    function("close", "int", &["int"]),
+    function("read", "ssize_t", &["int", "void *", "size_t"]),
+    function("write", "ssize_t", &["int", "const void *", "size_t"]),
+    function("lseek", "off_t", &["int", "off_t", "int"]),
+    function("unlink", "int", &[STRING]),
+    function("sleep", "unsigned int", &["unsigned int"]),
+    function("usleep", "int", &["unsigned int"]),
+    function("getpid", "pid_t", &[]),
+    function("fork", "pid_t", &[]),
+    function("execve", "int", &[STRING, "char *const *", "char *const *"]),
+    function("time", "time_t", &["time_t *"]),
+    // setjmp.h, errno and pthread
+    function("setjmp", "int", &["struct __jmp_buf_tag *"]),
+    function("_setjmp", "int", &["struct __jmp_buf_tag *"]),
+    function("longjmp", "void", &["struct __jmp_buf_tag *", "int"]).noreturn(),
+    function("__longjmp_chk", "void", &["struct __jmp_buf_tag *", "int"]).noreturn(),
+    function("__errno_location", "int *", &[]),
+    function("pthread_exit", "void", &["void *"]).noreturn(),
+    // err.h
+    function("err", "void", &["int", STRING]).variadic().noreturn(),
+    function("errx", "void", &["int", STRING]).variadic().noreturn(),
+    function("warn", "void", &[STRING]).variadic(),
+    function("warnx", "void", &[STRING]).variadic(),
+    // The C runtime, the stack protector, assertions and C++ exceptions
+    function("__libc_start_main", "int", &["int (*)(int, char **, char **)", "int", "char **", "void (*)(void)",
+                                           "void (*)(void)", "void (*)(void)", "void *"]).noreturn(),
+    function("__cxa_atexit", "int", &["void (*)(void *)", "void *", "void *"]),
+    function("__cxa_finalize", "void", &["void *"]),
+    function("__stack_chk_fail", "void", &[]).noreturn(),
+    function("__chk_fail", "void", &[]).noreturn(),
+    function("__assert_fail", "void", &[STRING, STRING, "unsigned int", STRING]).noreturn(),
+    function("__cxa_throw", "void", &["void *", "void *", "void (*)(void *)"]).noreturn(),
+    function("__cxa_rethrow", "void", &[]).noreturn(),
+    function("__cxa_allocate_exception", "void *", &["size_t"]),
+    function("__cxa_begin_catch", "void *", &["void *"]),
+    function("__cxa_end_catch", "void", &[]),
+    function("_Unwind_Resume", "void", &["void *"]).noreturn(),
+];
+
+/// The signature of the library function with the given name. Symbol versions and the @plt of PLT stubs are
+/// ignored.
+pub fn library_function(name: &str) -> Option<&'static Signature> {
+    let name = name.split('@').next().unwrap_or(name);
+    SIGNATURES.iter().find(|x| x.name == name)
+}
@@ -0,0 +1,55 @@
+mod statement;
+pub use self::statement::{BinaryOp, CastOp, CompareOp, Destination, Expr, Stmt, Storage, Type, UnaryOp, Var};
+
+mod builder;
+
+mod x86;
+pub use self::x86::X86Lifter;
+
+mod aarch64;
+pub use self::aarch64::AArch64Lifter;
+
+mod function;
+pub use self::function::{Block, Function};
+
+mod ssa;
+pub use self::ssa::{CallEffects, DefUse, Location};
+
+mod simplify;
+
+mod optimize;
+
+mod coalesce;
+
+mod range;
+pub use self::range::{Range, RangeAnalysis};
+
+mod frame;
+pub use self::frame::{StackFrame, StackSlot};
+
+mod convention;
+pub use self::convention::{Abi, CallingConvention, Conventions, Parameter, Prototype};
+
+mod library;
+pub use self::library::{library_function, Signature};
+
+use crate::disasm::{Instruction, Target};
+use crate::instruction_set::InstructionSet;
+
+/// Translates instructions into statements of the intermediate representation
+pub trait Lifter {
+    /// The statements executing the instruction, in order. Temporaries are numbered from 0 for each instruction.
+    fn lift(&self, instruction: &Instruction) -> Vec<Stmt>;
+
+    /// The register holding the stack pointer
+    fn stack_pointer(&self) -> Var;
+}
+
+pub fn lifter_for(target: &Target) -> Option<Box<dyn Lifter>> {
+    match target.instruction_set {
+        InstructionSet::X86 => Some(Box::new(X86Lifter::new(32))),
+        InstructionSet::X86_64 => Some(Box::new(X86Lifter::new(64))),
+        InstructionSet::AArch64 => Some(Box::new(AArch64Lifter::new(target.endianness))),
+        _ => None,
+    }
+}
@@ -0,0 +1,640 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::analysis::{BlockId, EdgeKind};
+
+use super::function::Function;
+use super::ssa::{DefUse, Location};
+use super::statement::{BinaryOp, CastOp, CompareOp, Expr, Stmt, Storage, Type, Var};
+
+// What constant propagation knows of a variable: nothing yet, because nothing assigning it was found to run, a
+// constant, or that it varies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lattice {
+    Unknown,
+    Constant(u64),
+    Varying,
+}
+
+impl Lattice {
+    fn meet(self, other: Lattice) -> Lattice {
+        match (self, other) {
+            (Lattice::Unknown, x) | (x, Lattice::Unknown) => x,
+            (Lattice::Constant(a), Lattice::Constant(b)) if a == b => self,
+            _ => Lattice::Varying,
+        }
+    }
+}
+
+// Edges by their ends and kind
+type Edges = BTreeSet<(BlockId, BlockId, EdgeKind)>;
+
+impl Function {
+    /// Sparse conditional constant propagation, on a function in SSA form: variables found to always hold the
+    /// same value are replaced by it and every expression is simplified. Branches with a constant condition
+    /// become jumps or go away, along with the edges and the blocks that can't run any more. Block ids don't
+    /// change, blocks that can't run are left empty.
+    pub fn propagate_constants(&mut self) {
+        let (values, executable) = self.constants();
+
+        let mut reachable: BTreeSet<BlockId> = executable.iter().map(|x| x.1).collect();
+        reachable.insert(self.entry);
+        for (i, block) in self.blocks.iter_mut().enumerate() {
+            if !reachable.contains(&i) {
+                block.statements.clear();
+                continue;
+            }
+            for (_, stmt) in block.statements.iter_mut() {
+                for expr in stmt.expressions_mut() {
+                    expr.substitute(&mut |var| match values.get(var) {
+                        Some(Lattice::Constant(value)) => Some(Expr::Const { value: *value, ty: var.ty }),
+                        _ => None,
+                    });
+                    *expr = std::mem::replace(expr, Expr::bool(false)).simplify();
+                }
+            }
+            // Branches that always go the same way
+            let condition = match block.statements.last() {
+                Some((_, Stmt::Branch { condition, .. })) => condition.constant(),
+                _ => None,
+            };
+            match condition {
+                Some(0) => {
+                    block.statements.pop();
+                }
+                Some(_) => {
+                    let (address, stmt) = block.statements.pop().unwrap();
+                    let Stmt::Branch { target, .. } = stmt else { unreachable!() };
+                    block.statements.push((address, Stmt::Jump(target)));
+                }
+                None => {}
+            }
+        }
+
+        self.edges.retain(|x| executable.contains(&(x.from, x.to, x.kind)));
+        for edge in self.edges.iter_mut() {
+            let taken = match &self.blocks[edge.from].statements.last() {
+                Some((_, Stmt::Branch { .. })) => continue,
+                Some((_, Stmt::Jump(_))) => true,
+                _ => false,
+            };
+            edge.kind = match (edge.kind, taken) {
+                (EdgeKind::True, true) => EdgeKind::Unconditional,
+                (EdgeKind::False, false) => EdgeKind::Fallthrough,
+                (kind, _) => kind,
+            };
+        }
+        self.remove_phi_sources();
+    }
+
+    // The constants and the edges that can be taken
+    fn constants(&self) -> (BTreeMap<Var, Lattice>, Edges) {
+        let mut values: BTreeMap<Var, Lattice> = BTreeMap::new();
+        let mut executable = Edges::new();
+        let mut arriving: BTreeSet<(BlockId, BlockId)> = BTreeSet::new();
+        let mut running: BTreeSet<BlockId> = BTreeSet::new();
+        running.insert(self.entry);
+        let order = self.reverse_postorder();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in order.iter().copied() {
+                if !running.contains(&block) {
+                    continue;
+                }
+                let mut condition = None;
+                for (_, stmt) in self.blocks[block].statements.iter() {
+                    let value = match stmt {
+                        Stmt::Assign { value, .. } => evaluate(value, &values),
+                        // Sources from blocks that can't run yet don't count
+                        Stmt::Phi { sources, .. } => sources
+                            .iter()
+                            .filter(|x| x.0 == self.exit || arriving.contains(&(x.0, block)))
+                            .fold(Lattice::Unknown, |res, x| res.meet(evaluate(&x.1, &values))),
+                        Stmt::Branch { condition: x, .. } => {
+                            condition = Some(evaluate(x, &values));
+                            continue;
+                        }
+                        _ => Lattice::Varying,
+                    };
+                    for var in stmt.definitions() {
+                        let old = values.get(var).copied().unwrap_or(Lattice::Unknown);
+                        let new = old.meet(value);
+                        if new != old {
+                            values.insert(*var, new);
+                            changed = true;
+                        }
+                    }
+                }
+                for edge in self.edges.iter().filter(|x| x.from == block) {
+                    let taken = match (edge.kind, condition) {
+                        (EdgeKind::True | EdgeKind::False, Some(Lattice::Unknown)) => false,
+                        (EdgeKind::True, Some(Lattice::Constant(x))) => x != 0,
+                        (EdgeKind::False, Some(Lattice::Constant(x))) => x == 0,
+                        _ => true,
+                    };
+                    if taken && executable.insert((edge.from, edge.to, edge.kind)) {
+                        arriving.insert((edge.from, edge.to));
+                        running.insert(edge.to);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        (values, executable)
+    }
+
+    /// Copy propagation, on a function in SSA form: variables assigned another variable or a constant are
+    /// replaced by it, as are phis all of whose sources are the same. The assignments are left for dead code
+    /// elimination.
+    pub fn propagate_copies(&mut self) {
+        let mut copies: BTreeMap<Var, Expr> = BTreeMap::new();
+        for (_, stmt) in self.blocks.iter().flat_map(|x| x.statements.iter()) {
+            match stmt {
+                Stmt::Assign { var, value: value @ (Expr::Var(_) | Expr::Const { .. }) } => {
+                    copies.insert(*var, value.clone());
+                }
+                Stmt::Phi { var, sources } => {
+                    let mut others = sources.iter().map(|x| &x.1).filter(|x| **x != Expr::var(*var));
+                    if let Some(first) = others.next() {
+                        if matches!(first, Expr::Var(_) | Expr::Const { .. }) && others.all(|x| x == first) {
+                            copies.insert(*var, first.clone());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Copies of copies, until a variable that isn't a copy. Phis copying each other around a loop stop where
+        // they started.
+        let resolve = |var: &Var| {
+            let mut current = copies.get(var)?;
+            for _ in 0..copies.len() {
+                match current {
+                    Expr::Var(next) if next == var => return None,
+                    Expr::Var(next) if copies.contains_key(next) => current = &copies[next],
+                    _ => break,
+                }
+            }
+            Some(current.clone())
+        };
+        let resolved: BTreeMap<Var, Expr> = copies.keys().filter_map(|x| Some((*x, resolve(x)?))).collect();
+        for (_, stmt) in self.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+            for expr in stmt.expressions_mut() {
+                expr.substitute(&mut |var| resolved.get(var).cloned());
+            }
+        }
+    }
+
+    /// Dead code elimination, on a function in SSA form: removes the assignments and phis whose values nothing
+    /// uses, and the unused results of calls. Loads are taken to have no side effects. The phis of the exit block
+    /// stay: they are what the function leaves for its caller.
+    pub fn eliminate_dead_code(&mut self) {
+        let definitions = DefUse::build(self);
+        let is_root = |location: Location, stmt: &Stmt| match stmt {
+            Stmt::Assign { .. } => false,
+            Stmt::Phi { .. } => location.block == self.exit,
+            _ => true,
+        };
+
+        let mut live: BTreeSet<Location> = BTreeSet::new();
+        let mut work: Vec<Location> = Vec::new();
+        for (block, statements) in self.blocks.iter().enumerate().map(|(i, x)| (i, &x.statements)) {
+            for (index, (_, stmt)) in statements.iter().enumerate() {
+                let location = Location { block, index };
+                if is_root(location, stmt) {
+                    live.insert(location);
+                    work.push(location);
+                }
+            }
+        }
+        let mut used: BTreeSet<Var> = BTreeSet::new();
+        while let Some(location) = work.pop() {
+            self.statement(location).for_each_use(&mut |var| {
+                if !used.insert(*var) {
+                    return;
+                }
+                if let Some(definition) = definitions.definition(var) {
+                    if live.insert(definition) {
+                        work.push(definition);
+                    }
+                }
+            });
+        }
+
+        for (block, statements) in self.blocks.iter_mut().enumerate().map(|(i, x)| (i, &mut x.statements)) {
+            let mut index = 0;
+            statements.retain_mut(|(_, stmt)| {
+                let keep = live.contains(&Location { block, index });
+                index += 1;
+                if let Stmt::Call { results, .. } | Stmt::Syscall { results, .. } = stmt {
+                    results.retain(|x| used.contains(x));
+                }
+                keep
+            });
+        }
+    }
+
+    /// Rewrites conditions tested through flags, on a function in SSA form, as comparisons of the values the flags
+    /// come from where they can be: the flags read are replaced by what they were assigned, and the temporaries
+    /// those read by theirs, unless loads are involved. Conditions for which the flags don't combine into a
+    /// comparison, such as overflow checks, are left as they were. The flags no longer read are left for dead code
+    /// elimination.
+    pub fn fold_conditions(&mut self) {
+        let definitions = DefUse::build(self);
+        let mut values: BTreeMap<Var, Expr> = BTreeMap::new();
+        for var in definitions.variables() {
+            let expandable = var.ty == Type::Bool || matches!(var.storage, Storage::Temp(_));
+            match definitions.value(self, var) {
+                Some(value) if expandable && !value.has_load() => {
+                    values.insert(*var, value.clone());
+                }
+                _ => {}
+            }
+        }
+
+        for (_, stmt) in self.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+            if let Stmt::Phi { .. } = stmt {
+                continue;
+            }
+            for expr in stmt.expressions_mut() {
+                fold_flags(expr, &values);
+            }
+        }
+    }
+
+    /// Narrows the variables only part of which is ever read, on a function in SSA form: a 64 bit register whose
+    /// uses all truncate it, or sums and bitwise operations of it, to 32 bits becomes a 32 bit variable, assigned
+    /// the truncated value. Phis are narrowed along with their sources. The phis of the exit block keep their width.
+    pub fn narrow_variables(&mut self) {
+        let mut phis: BTreeMap<Var, Vec<Var>> = BTreeMap::new();
+        for (block, (_, stmt)) in self.blocks.iter().enumerate().flat_map(|(i, x)| x.statements.iter().map(move |x| (i, x))) {
+            if let (Stmt::Phi { var, sources }, false) = (stmt, block == self.exit) {
+                for source in sources.iter().filter_map(|x| if let Expr::Var(source) = &x.1 { Some(source) } else { None }) {
+                    phis.entry(*source).or_default().push(*var);
+                }
+            }
+        }
+
+        // The bits read of each variable, growing until nothing changes. Phis read as much of their sources as is
+        // read of them.
+        let mut read: BTreeMap<Var, u16> = BTreeMap::new();
+        let mut direct: BTreeMap<Var, u16> = BTreeMap::new();
+        for (block, (_, stmt)) in self.blocks.iter().enumerate().flat_map(|(i, x)| x.statements.iter().map(move |x| (i, x))) {
+            let mut note = |var: &Var, bits: u16| {
+                let entry = direct.entry(*var).or_default();
+                *entry = (*entry).max(bits);
+            };
+            match stmt {
+                Stmt::Phi { var, sources } => {
+                    for (_, source) in sources.iter() {
+                        match source {
+                            Expr::Var(x) if block != self.exit => note(x, 0),
+                            Expr::Var(x) => note(x, x.ty.bits()),
+                            _ => source.for_each_var(&mut |x| note(x, x.ty.bits())),
+                        }
+                        note(var, 0);
+                    }
+                }
+                _ => {
+                    for expr in stmt.expressions() {
+                        bits_read(expr, &mut note);
+                    }
+                }
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (var, bits) in direct.iter() {
+                let through = phis.get(var).into_iter().flatten().map(|x| read.get(x).copied().unwrap_or(0)).max();
+                let bits = (*bits).max(through.unwrap_or(0));
+                if read.get(var).copied().unwrap_or(0) != bits {
+                    read.insert(*var, bits);
+                    changed = true;
+                }
+            }
+        }
+        let narrowed: BTreeMap<Var, Var> = read
+            .iter()
+            .filter(|(var, bits)| matches!(var.ty, Type::Int(x) if **bits < x) && [8, 16, 32].contains(*bits))
+            .map(|(var, bits)| (*var, Var { ty: Type::Int(*bits), ..*var }))
+            .collect();
+        if narrowed.is_empty() {
+            return;
+        }
+
+        for (_, stmt) in self.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+            let narrow = |var: &Var| narrowed.get(var).copied().unwrap_or(*var);
+            match stmt {
+                Stmt::Phi { var, sources } => {
+                    let ty = narrow(var).ty;
+                    for (_, source) in sources.iter_mut() {
+                        let value = std::mem::replace(source, Expr::bool(false));
+                        *source = truncated(value, &narrowed).resize(ty.bits(), false).simplify();
+                    }
+                }
+                Stmt::Assign { var, value } if narrowed.contains_key(var) => {
+                    let old = std::mem::replace(value, Expr::bool(false));
+                    *value = truncated(old, &narrowed).resize(narrow(var).ty.bits(), false).simplify();
+                }
+                _ => {
+                    for expr in stmt.expressions_mut() {
+                        let old = std::mem::replace(expr, Expr::bool(false));
+                        *expr = truncated(old, &narrowed).simplify();
+                    }
+                }
+            }
+            for var in stmt.definitions_mut() {
+                *var = narrow(var);
+            }
+        }
+    }
+
+    // Removes the sources of phis whose edges are gone
+    fn remove_phi_sources(&mut self) {
+        let edges: BTreeSet<(BlockId, BlockId)> = self.edges.iter().map(|x| (x.from, x.to)).collect();
+        let (entry, exit) = (self.entry, self.exit);
+        for (block, statements) in self.blocks.iter_mut().enumerate().map(|(i, x)| (i, &mut x.statements)) {
+            for (_, stmt) in statements.iter_mut() {
+                if let Stmt::Phi { sources, .. } = stmt {
+                    sources.retain(|x| edges.contains(&(x.0, block)) || (block == entry && x.0 == exit));
+                }
+            }
+        }
+    }
+}
+
+// The value of an expression given what is known of the variables it reads
+fn evaluate(expr: &Expr, values: &BTreeMap<Var, Lattice>) -> Lattice {
+    let mut unknown = false;
+    let mut substituted = expr.clone();
+    substituted.substitute(&mut |var| match values.get(var) {
+        Some(Lattice::Constant(value)) => Some(Expr::Const { value: *value, ty: var.ty }),
+        // Registers read before being assigned vary
+        None if var.version == 0 && matches!(var.storage, Storage::Register(_)) => None,
+        Some(Lattice::Varying) => None,
+        _ => {
+            unknown = true;
+            None
+        }
+    });
+    match substituted.simplify() {
+        Expr::Const { value, .. } => Lattice::Constant(value),
+        _ if unknown => Lattice::Unknown,
+        _ => Lattice::Varying,
+    }
+}
+
+// Replaces the flags in the expression by the comparisons they stand for, unless they don't make one. Conditions
+// combining flags are folded as a whole first: the overflow flag alone doesn't make a comparison, but with the sign
+// flag it does.
+fn fold_flags(expr: &mut Expr, values: &BTreeMap<Var, Expr>) {
+    let mut flags = false;
+    expr.for_each_var(&mut |x| flags |= x.ty == Type::Bool && values.contains_key(x));
+    if flags && expr.ty() == Type::Bool {
+        let mut folded = expr.clone();
+        // The definitions come before their uses, a chain of them can't loop
+        for _ in 0..values.len() {
+            let mut expanded = false;
+            folded.substitute(&mut |x| {
+                let value = values.get(x)?;
+                expanded = true;
+                Some(value.clone())
+            });
+            if !expanded {
+                break;
+            }
+        }
+        let folded = folded.simplify();
+        if !has_overflow(&folded) {
+            *expr = folded;
+            return;
+        }
+    }
+    match expr {
+        Expr::Const { .. } | Expr::Var(_) => {}
+        Expr::Load { address, .. } => fold_flags(address, values),
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => fold_flags(value, values),
+        Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+            fold_flags(left, values);
+            fold_flags(right, values);
+        }
+        Expr::Select { condition, then, otherwise } => {
+            fold_flags(condition, values);
+            fold_flags(then, values);
+            fold_flags(otherwise, values);
+        }
+        Expr::Intrinsic { args, .. } => args.iter_mut().for_each(|x| fold_flags(x, values)),
+    }
+    if let Expr::Unary(..) | Expr::Binary(..) | Expr::Compare(..) = expr {
+        *expr = std::mem::replace(expr, Expr::bool(false)).simplify();
+    }
+}
+
+// Whether the expression still has the computation of an overflow or parity flag, which reads worse than the flag
+fn has_overflow(expr: &Expr) -> bool {
+    match expr {
+        Expr::Compare(CompareOp::SLt, left, _) if matches!(**left, Expr::Binary(BinaryOp::And, ..)) => true,
+        Expr::Intrinsic { name, .. } if name == "parity" => true,
+        Expr::Const { .. } | Expr::Var(_) => false,
+        Expr::Load { address, .. } => has_overflow(address),
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => has_overflow(value),
+        Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => has_overflow(left) || has_overflow(right),
+        Expr::Select { condition, then, otherwise } => {
+            has_overflow(condition) || has_overflow(then) || has_overflow(otherwise)
+        }
+        Expr::Intrinsic { args, .. } => args.iter().any(has_overflow),
+    }
+}
+
+// Calls `f` with the variables the expression reads and how many of their low bits it does
+fn bits_read(expr: &Expr, f: &mut dyn FnMut(&Var, u16)) {
+    match expr {
+        Expr::Cast(CastOp::Truncate, value, ty) => low_bits_read(value, ty.bits(), f),
+        Expr::Var(var) => f(var, var.ty.bits()),
+        Expr::Const { .. } => {}
+        Expr::Load { address, .. } => bits_read(address, f),
+        Expr::Unary(_, value) | Expr::Cast(_, value, _) => bits_read(value, f),
+        Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+            bits_read(left, f);
+            bits_read(right, f);
+        }
+        Expr::Select { condition, then, otherwise } => {
+            bits_read(condition, f);
+            bits_read(then, f);
+            bits_read(otherwise, f);
+        }
+        Expr::Intrinsic { args, .. } => args.iter().for_each(|x| bits_read(x, f)),
+    }
+}
+
+// The bits read of the variables of an expression of which only the low bits are. Those of sums, products and
+// bitwise operations only depend on the low bits of their operands.
+fn low_bits_read(expr: &Expr, bits: u16, f: &mut dyn FnMut(&Var, u16)) {
+    match expr {
+        Expr::Var(var) => f(var, bits.min(var.ty.bits())),
+        Expr::Binary(op, left, right) if keeps_low_bits(*op) => {
+            low_bits_read(left, bits, f);
+            low_bits_read(right, bits, f);
+        }
+        Expr::Unary(_, value) => low_bits_read(value, bits, f),
+        Expr::Cast(CastOp::Truncate, value, ty) => low_bits_read(value, bits.min(ty.bits()), f),
+        expr => bits_read(expr, f),
+    }
+}
+
+fn keeps_low_bits(op: BinaryOp) -> bool {
+    matches!(op, BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::And | BinaryOp::Or | BinaryOp::Xor)
+}
+
+// The expression reading the narrowed variables: truncations of them become the variable itself, and truncations
+// of operations on them the operations on the truncated operands
+fn truncated(expr: Expr, narrowed: &BTreeMap<Var, Var>) -> Expr {
+    let reads = |expr: &Expr| {
+        let mut res = false;
+        expr.for_each_var(&mut |x| res |= narrowed.contains_key(x));
+        res
+    };
+    match expr {
+        Expr::Cast(CastOp::Truncate, value, ty) => match *value {
+            Expr::Var(var) if narrowed.contains_key(&var) => Expr::var(narrowed[&var]).resize(ty.bits(), false),
+            Expr::Binary(op, left, right) if keeps_low_bits(op) && (reads(&left) || reads(&right)) => {
+                let left = truncated(Expr::cast(CastOp::Truncate, *left, ty), narrowed);
+                Expr::binary(op, left, truncated(Expr::cast(CastOp::Truncate, *right, ty), narrowed))
+            }
+            Expr::Unary(op, value) if reads(&value) => Expr::unary(op, truncated(Expr::cast(CastOp::Truncate, *value, ty), narrowed)),
+            value => Expr::cast(CastOp::Truncate, truncated(value, narrowed), ty),
+        },
+        Expr::Var(var) => Expr::var(narrowed.get(&var).copied().unwrap_or(var)),
+        Expr::Const { .. } => expr,
+        Expr::Load { address, ty, endianness } => Expr::load(truncated(*address, narrowed), ty, endianness),
+        Expr::Unary(op, value) => Expr::unary(op, truncated(*value, narrowed)),
+        Expr::Cast(op, value, ty) => Expr::cast(op, truncated(*value, narrowed), ty),
+        Expr::Binary(op, left, right) => Expr::binary(op, truncated(*left, narrowed), truncated(*right, narrowed)),
+        Expr::Compare(op, left, right) => Expr::compare(op, truncated(*left, narrowed), truncated(*right, narrowed)),
+        Expr::Select { condition, then, otherwise } => Expr::select(
+            truncated(*condition, narrowed),
+            truncated(*then, narrowed),
+            truncated(*otherwise, narrowed),
+        ),
+        Expr::Intrinsic { name, args, ty } => {
+            Expr::Intrinsic { name, args: args.into_iter().map(|x| truncated(x, narrowed)).collect(), ty }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::analysis::Cfg;
+    use crate::disasm::x86::{Mode, X86Decoder};
+    use crate::disasm::Decoder;
+    use crate::ir::{CallEffects, CastOp, DefUse, Expr, Function, Range, RangeAnalysis, Type, Var, X86Lifter};
+
+    const RAX: Var = Var::register("rax", Type::Int(64));
+
+    // Lifts the x86-64 code at 0x1000 into SSA form, with rax as the output
+    fn lift(code: &[u8]) -> Function {
+        let decoder = X86Decoder::new(Mode::Bits64);
+        let mut instructions = BTreeMap::new();
+        let mut offset = 0;
+        while offset < code.len() {
+            let instruction = decoder.decode(&code[offset..], 0x1000 + offset as u64).unwrap();
+            offset += instruction.length;
+            instructions.insert(instruction.address, instruction);
+        }
+        let mut function = Function::lift(&Cfg::new(0x1000, &instructions, &[], &[]), &X86Lifter::new(64));
+        function.to_ssa(&CallEffects { arguments: Vec::new(), results: Vec::new(), outputs: vec![RAX] });
+        function
+    }
+
+    // Lifts the code and runs the passes the decompiler starts with
+    fn optimize(code: &[u8]) -> Function {
+        let mut function = lift(code);
+        function.propagate_constants();
+        function.propagate_copies();
+        function.eliminate_dead_code();
+        function
+    }
+
+    fn statements(function: &Function, block: usize) -> Vec<String> {
+        function.blocks[block].statements.iter().map(|x| x.1.to_string()).collect()
+    }
+
+    #[test]
+    fn ssa() {
+        // xor eax, eax; l: add eax, edi; dec edi; jne l; ret
+        let function = lift(&[0x31, 0xc0, 0x01, 0xf8, 0xff, 0xcf, 0x75, 0xfa, 0xc3]);
+        let loop_statements = statements(&function, 1);
+        assert_eq!(loop_statements[..2], ["rax_2 = phi(0: rax_1, 1: rax_3)", "rdi_1 = phi(0: rdi, 1: rdi_2)"]);
+        assert_eq!(loop_statements[2], "t0 = trunc32(rax_2) + trunc32(rdi_1)");
+        assert_eq!(loop_statements.iter().filter(|x| x.starts_with("rax_3 = ") || x.starts_with("rdi_2 = ")).count(), 2);
+        assert_eq!(statements(&function, 3), ["rax_4 = phi(2: rax_3)"]);
+    }
+
+    #[test]
+    fn constants() {
+        // mov eax, 5; cmp eax, 5; je l; mov eax, 1; l: ret
+        let function = optimize(&[0xb8, 5, 0, 0, 0, 0x83, 0xf8, 5, 0x74, 0x05, 0xb8, 1, 0, 0, 0, 0xc3]);
+        assert_eq!(statements(&function, 0), ["jump 0x100f"]);
+        assert!(statements(&function, 1).is_empty());
+        assert_eq!(function.predecessors(2), [0]);
+        assert_eq!(statements(&function, 3), ["rax_4 = phi(2: 5)"]);
+    }
+
+    #[test]
+    fn copies_and_dead_code() {
+        // mov rax, rdi; mov rcx, rax; lea rax, [rcx + 1]; ret
+        let function = optimize(&[0x48, 0x89, 0xf8, 0x48, 0x89, 0xc1, 0x48, 0x8d, 0x41, 0x01, 0xc3]);
+        assert_eq!(statements(&function, 0), ["rax_2 = rdi + 1", "return"]);
+        assert_eq!(statements(&function, 1), ["rax_3 = phi(0: rax_2)"]);
+    }
+
+    #[test]
+    fn flags() {
+        let condition = |code: &[u8]| {
+            let mut function = optimize(code);
+            function.fold_conditions();
+            function.eliminate_dead_code();
+            statements(&function, 0)
+        };
+        // cmp edi, 0x3d; jle
+        assert_eq!(condition(&[0x83, 0xff, 0x3d, 0x7e, 0x01, 0xc3, 0xcc]), ["if trunc32(rdi) <=s 0x3d jump 0x1006"]);
+        // cmp edi, esi; jl
+        assert_eq!(condition(&[0x39, 0xf7, 0x7c, 0x01, 0xc3, 0xcc]), ["if trunc32(rdi) <s trunc32(rsi) jump 0x1005"]);
+        // cmp edi, esi; setl al
+        assert_eq!(
+            condition(&[0x39, 0xf7, 0x0f, 0x9c, 0xc0, 0xc3]),
+            ["rax_1 = (rax & 0xffffffffffffff00) | zext64(trunc32(rdi) <s trunc32(rsi))", "return"]
+        );
+        // dec edi; jne
+        assert_eq!(condition(&[0xff, 0xcf, 0x75, 0x01, 0xc3, 0xcc]), ["if trunc32(rdi) != 1 jump 0x1005"]);
+        // add edi, esi; jo: the overflow check stays one
+        assert_eq!(
+            condition(&[0x01, 0xf7, 0x70, 0x01, 0xc3, 0xcc]),
+            ["t0 = trunc32(rdi) + trunc32(rsi)", "of_1 = ((t0 ^ trunc32(rdi)) & (t0 ^ trunc32(rsi))) <s 0", "if of_1 jump 0x1005"]
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        // and edi, 7; mov eax, edi; ret
+        let function = optimize(&[0x83, 0xe7, 0x07, 0x89, 0xf8, 0xc3]);
+        let ranges = RangeAnalysis::new(&function);
+        let output = *DefUse::build(&function).variables().find(|x| x.storage == RAX.storage && x.version == 2).unwrap();
+        assert_eq!(ranges.range(&output), Range { low: 0, high: 7, stride: 1 });
+        assert_eq!(ranges.range(&output).count(), 8);
+
+        // cmp edi, 3; ja l; mov eax, edi; ret; l: ret, with the comparison folded: edi is at most 3 where the
+        // branch isn't taken, and at least 4 where it is
+        let mut function = optimize(&[0x83, 0xff, 0x03, 0x77, 0x03, 0x89, 0xf8, 0xc3, 0xc3]);
+        function.fold_conditions();
+        function.eliminate_dead_code();
+        let ranges = RangeAnalysis::new(&function);
+        let edi = Expr::cast(CastOp::Truncate, Expr::var(Var::register("rdi", Type::Int(64))), Type::Int(32));
+        assert_eq!(ranges.at(1, &edi), Range { low: 0, high: 3, stride: 1 });
+        assert_eq!(ranges.at(2, &edi), Range { low: 4, high: 0xffff_ffff, stride: 1 });
+    }
+}
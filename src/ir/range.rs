@@ -0,0 +1,519 @@
+use std::collections::BTreeMap;
+
+use crate::analysis::{BlockId, DominatorTree, EdgeKind};
+
+use super::function::Function;
+use super::ssa::DefUse;
+use super::statement::{BinaryOp, CastOp, CompareOp, Destination, Expr, Stmt, Type, UnaryOp, Var};
+
+/// A strided interval: the unsigned values low, low + stride, ... up to high. Single values have stride 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub low: u64,
+    pub high: u64,
+    pub stride: u64,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Range {
+    pub fn constant(value: u64) -> Range {
+        Range { low: value, high: value, stride: 0 }
+    }
+
+    /// Every value of the type. Values wider than 64 bits are taken to be unknown, as if they were 64 bits wide.
+    pub fn full(ty: Type) -> Range {
+        Range { low: 0, high: ty.mask(), stride: 1 }
+    }
+
+    fn new(low: u64, high: u64, stride: u64) -> Range {
+        if low == high {
+            Range::constant(low)
+        } else {
+            Range { low, high, stride: stride.max(1) }
+        }
+    }
+
+    pub fn is_full(&self, ty: Type) -> bool {
+        *self == Range::full(ty)
+    }
+
+    pub fn as_constant(&self) -> Option<u64> {
+        if self.low == self.high {
+            Some(self.low)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        value >= self.low && value <= self.high && (self.stride == 0 || (value - self.low).is_multiple_of(self.stride))
+    }
+
+    /// The number of values, saturating
+    pub fn count(&self) -> u64 {
+        match self.stride {
+            0 => 1,
+            stride => ((self.high - self.low) / stride).saturating_add(1),
+        }
+    }
+
+    /// The values in increasing order
+    pub fn values(&self) -> impl Iterator<Item = u64> {
+        let Range { low, stride, .. } = *self;
+        (0..self.count()).map(move |i| low + i * stride)
+    }
+
+    /// The smallest range holding both
+    pub fn join(self, other: Range) -> Range {
+        let stride = gcd(gcd(self.stride, other.stride), self.low.abs_diff(other.low));
+        Range::new(self.low.min(other.low), self.high.max(other.high), stride)
+    }
+
+    /// A range holding the values of both, if there are any. It may hold more when the strides differ.
+    pub fn intersect(self, other: Range) -> Option<Range> {
+        if self.stride == 0 || other.stride == 0 {
+            let (single, range) = if self.stride == 0 { (self, other) } else { (other, self) };
+            return if range.contains(single.low) { Some(single) } else { None };
+        }
+        // Aligned on the values of the range with the larger stride
+        let base = if self.stride >= other.stride { self } else { other };
+        let (low, high) = (self.low.max(other.low), self.high.min(other.high));
+        if low > high {
+            return None;
+        }
+        let low = low + (base.stride - (low - base.low) % base.stride) % base.stride;
+        let high = high - (high - base.low) % base.stride;
+        if low > high {
+            return None;
+        }
+        Some(Range::new(low, high, base.stride))
+    }
+
+    // Values of up to 64 bits given as wider integers, wrapping at `bits` when all of them wrap the same way
+    fn wrapped(low: u128, high: u128, stride: u64, ty: Type) -> Range {
+        let modulus = ty.mask() as u128 + 1;
+        if ty.bits() > 64 || high - low > ty.mask() as u128 {
+            return Range::full(ty);
+        }
+        let (low, high) = (low % modulus, high % modulus);
+        if low > high {
+            return Range::full(ty);
+        }
+        Range::new(low as u64, high as u64, stride)
+    }
+
+    fn add(self, other: Range, ty: Type) -> Range {
+        let (low, high) = (self.low as u128 + other.low as u128, self.high as u128 + other.high as u128);
+        Range::wrapped(low, high, gcd(self.stride, other.stride), ty)
+    }
+
+    fn negate(self, ty: Type) -> Range {
+        let modulus = ty.mask() as u128 + 1;
+        match self.low {
+            0 if self.high == 0 => self,
+            // 0 stays 0 while the rest wraps
+            0 => Range::full(ty),
+            low => Range::wrapped(modulus - self.high as u128, modulus - low as u128, self.stride, ty),
+        }
+    }
+
+    fn multiply(self, other: Range, ty: Type) -> Range {
+        let (low, high) = (self.low as u128 * other.low as u128, self.high as u128 * other.high as u128);
+        if high > ty.mask() as u128 {
+            return Range::full(ty);
+        }
+        let stride = match (self.as_constant(), other.as_constant()) {
+            (_, Some(factor)) => self.stride * factor,
+            (Some(factor), _) => other.stride * factor,
+            _ => 1,
+        };
+        Range::new(low as u64, high as u64, stride)
+    }
+}
+
+// Bits up to the highest one set
+fn smear(value: u64) -> u64 {
+    match value {
+        0 => 0,
+        value => u64::MAX >> value.leading_zeros(),
+    }
+}
+
+// A binary operation on ranges of the operands
+fn binary(op: BinaryOp, a: Range, b: Range, ty: Type) -> Range {
+    if let (Some(x), Some(y)) = (a.as_constant(), b.as_constant()) {
+        let amount = match op {
+            BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr | BinaryOp::RotL | BinaryOp::RotR => Type::Int(64),
+            _ => ty,
+        };
+        let folded = Expr::binary(op, Expr::int(x, ty.bits()), Expr::int(y, amount.bits())).simplify();
+        if let Expr::Const { value, .. } = folded {
+            return Range::constant(value);
+        }
+    }
+    if ty.bits() > 64 {
+        return Range::full(ty);
+    }
+    let shift = b.as_constant().filter(|x| *x < ty.bits() as u64);
+    match op {
+        BinaryOp::Add => a.add(b, ty),
+        BinaryOp::Sub => a.add(b.negate(ty), ty),
+        BinaryOp::Mul => a.multiply(b, ty),
+        BinaryOp::Shl => match shift {
+            Some(amount) => a.multiply(Range::constant(1 << amount), ty),
+            None => Range::full(ty),
+        },
+        BinaryOp::LShr => match shift {
+            Some(amount) => {
+                let stride = if a.stride.is_multiple_of(1 << amount) { a.stride >> amount } else { 1 };
+                Range::new(a.low >> amount, a.high >> amount, stride)
+            }
+            None => Range::new(0, a.high, 1),
+        },
+        // Shifting a value that isn't negative to the right is the same either way
+        BinaryOp::AShr if a.high <= ty.mask() >> 1 => binary(BinaryOp::LShr, a, b, ty),
+        BinaryOp::And => {
+            let high = a.high.min(b.high);
+            // Masking with the low bits keeps the values that fit
+            match b.as_constant() {
+                Some(mask) if a.high <= mask && smear(mask) == mask => a,
+                _ => Range::new(0, high, 1),
+            }
+        }
+        BinaryOp::Or | BinaryOp::Xor => Range::new(0, smear(a.high | b.high), 1),
+        BinaryOp::UDiv => match b.as_constant() {
+            Some(divisor) if divisor > 0 => Range::new(a.low / divisor, a.high / divisor, 1),
+            _ => Range::new(0, a.high, 1),
+        },
+        BinaryOp::URem => match b.as_constant() {
+            Some(divisor) if divisor > a.high => a,
+            Some(divisor) if divisor > 0 => Range::new(0, divisor - 1, 1),
+            _ => Range::new(0, a.high, 1),
+        },
+        _ => Range::full(ty),
+    }
+}
+
+fn cast(op: CastOp, value: Range, from: Type, to: Type) -> Range {
+    if from.bits() > 64 || to.bits() > 64 {
+        return Range::full(to);
+    }
+    let sign = from.mask() >> 1;
+    match op {
+        CastOp::ZeroExtend => value,
+        CastOp::SignExtend if value.high <= sign || from == Type::Bool => value,
+        // Negative values move up by the same amount
+        CastOp::SignExtend if value.low > sign => {
+            let offset = to.mask() - from.mask();
+            Range::new(value.low + offset, value.high + offset, value.stride)
+        }
+        CastOp::SignExtend => Range::full(to),
+        CastOp::Truncate if value.high <= to.mask() => value,
+        CastOp::Truncate if value.low & !to.mask() == value.high & !to.mask() => {
+            Range::new(value.low & to.mask(), value.high & to.mask(), value.stride)
+        }
+        CastOp::Truncate => Range::full(to),
+    }
+}
+
+// How deep the analysis looks into the definitions of variables and the conditions of branches
+const DEPTH: usize = 12;
+
+// Times a variable may widen before it is given up on
+const WIDENINGS: u32 = 4;
+
+/// Value-set analysis of a function in SSA form: the values each variable may hold, as strided intervals. Ranges
+/// are computed once for the whole function, and narrowed at a block by the conditions of the branches that must
+/// have been taken to get there, which bounds the indices of jump tables.
+pub struct RangeAnalysis<'a> {
+    function: &'a Function,
+    definitions: DefUse,
+    dominators: DominatorTree,
+    ranges: BTreeMap<Var, Range>,
+}
+
+// What the conditions of the branches taken tell of expressions
+type Facts = Vec<(Expr, Range)>;
+
+impl<'a> RangeAnalysis<'a> {
+    pub fn new(function: &'a Function) -> RangeAnalysis<'a> {
+        let mut res = RangeAnalysis {
+            function,
+            definitions: DefUse::build(function),
+            dominators: function.dominators(),
+            ranges: BTreeMap::new(),
+        };
+        let order = function.reverse_postorder();
+        let mut widened: BTreeMap<Var, u32> = BTreeMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (_, stmt) in order.iter().flat_map(|x| function.blocks[*x].statements.iter()) {
+                let range = match stmt {
+                    Stmt::Assign { value, .. } => res.value(value, &Vec::new(), 0),
+                    // Sources assigned later, around a loop, don't count yet
+                    Stmt::Phi { sources, .. } => {
+                        let known = sources.iter().map(|x| &x.1).filter(|x| match x {
+                            Expr::Var(v) => res.definitions.definition(v).is_none() || res.ranges.contains_key(v),
+                            _ => true,
+                        });
+                        match known.map(|x| res.value(x, &Vec::new(), 0)).reduce(Range::join) {
+                            Some(range) => range,
+                            None => continue,
+                        }
+                    }
+                    _ => {
+                        for var in stmt.definitions() {
+                            res.ranges.insert(*var, Range::full(var.ty));
+                        }
+                        continue;
+                    }
+                };
+                let var = *stmt.definitions()[0];
+                let old = res.ranges.get(&var).copied();
+                // Every cycle goes through a phi: phis only grow, and the upper bounds that keep moving go to the
+                // largest value of the next narrower integer type, then of the type
+                let mut new = range;
+                if let (Some(old), Stmt::Phi { .. }) = (old, stmt) {
+                    new = old.join(range);
+                    if new != old {
+                        let count = widened.entry(var).or_default();
+                        *count += 1;
+                        if *count > WIDENINGS {
+                            let low = if new.low < old.low { 0 } else { new.low };
+                            let threshold = [0xff, 0xffff, 0xffff_ffff].iter().copied().find(|x| *x >= new.high);
+                            let high = match threshold {
+                                Some(threshold) if new.high > old.high && threshold < var.ty.mask() => threshold,
+                                _ if new.high > old.high => var.ty.mask(),
+                                _ => new.high,
+                            };
+                            new = Range::new(low, high, 1);
+                        }
+                    }
+                }
+                if old != Some(new) {
+                    res.ranges.insert(var, new);
+                    changed = true;
+                }
+            }
+        }
+        res
+    }
+
+    /// The values the variable may hold anywhere in the function
+    pub fn range(&self, var: &Var) -> Range {
+        self.ranges.get(var).copied().unwrap_or_else(|| Range::full(var.ty))
+    }
+
+    /// The values the expression may have anywhere in the function
+    pub fn evaluate(&self, expr: &Expr) -> Range {
+        self.value(expr, &Vec::new(), 0)
+    }
+
+    /// The values the expression may have in `block`, given the branches taken to get there
+    pub fn at(&self, block: BlockId, expr: &Expr) -> Range {
+        let mut facts = Facts::new();
+        let mut current = block;
+        loop {
+            let mut predecessors = self.function.predecessors(current);
+            predecessors.sort_unstable();
+            predecessors.dedup();
+            if let [predecessor] = predecessors[..] {
+                let kinds: Vec<EdgeKind> =
+                    self.function.edges.iter().filter(|x| x.from == predecessor && x.to == current).map(|x| x.kind).collect();
+                let condition = match self.function.blocks[predecessor].statements.last() {
+                    Some((_, Stmt::Branch { condition, .. })) => Some(condition),
+                    _ => None,
+                };
+                if let (Some(condition), [kind @ (EdgeKind::True | EdgeKind::False)]) = (condition, &kinds[..]) {
+                    self.assume(condition, *kind == EdgeKind::True, &mut facts, 0);
+                }
+            }
+            match self.dominators.idom[current] {
+                Some(idom) => current = idom,
+                None => break,
+            }
+        }
+        self.value(expr, &facts, DEPTH)
+    }
+
+    /// Where the indirect jump ending the block may go, given the branches taken to get there
+    pub fn jump_targets(&self, block: BlockId) -> Option<Range> {
+        match self.function.blocks[block].statements.last() {
+            Some((_, Stmt::Jump(Destination::Indirect(target)))) => Some(self.at(block, target)),
+            _ => None,
+        }
+    }
+
+    // The range of an expression, looking into the definitions of the variables up to `depth` deep
+    fn value(&self, expr: &Expr, facts: &Facts, depth: usize) -> Range {
+        let ty = expr.ty();
+        let range = match expr {
+            Expr::Const { value, .. } => Range::constant(*value),
+            Expr::Var(var) => {
+                let range = self.range(var);
+                match self.definitions.value(self.function, var) {
+                    Some(value) if depth > 0 && !facts.is_empty() => {
+                        range.intersect(self.value(value, facts, depth - 1)).unwrap_or(range)
+                    }
+                    _ => range,
+                }
+            }
+            Expr::Load { .. } | Expr::Intrinsic { .. } => Range::full(ty),
+            Expr::Unary(op, value) => {
+                let value = self.value(value, facts, depth);
+                match op {
+                    UnaryOp::Neg => value.negate(ty),
+                    // The complement counts down
+                    UnaryOp::Not => Range::new(ty.mask() - value.high, ty.mask() - value.low, value.stride),
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                binary(*op, self.value(left, facts, depth), self.value(right, facts, depth), ty)
+            }
+            Expr::Compare(..) => match expr.clone().simplify() {
+                Expr::Const { value, .. } => Range::constant(value),
+                _ => Range::full(ty),
+            },
+            Expr::Cast(op, value, to) => cast(*op, self.value(value, facts, depth), value.ty(), *to),
+            Expr::Select { condition, then, otherwise } => match self.value(condition, facts, depth).as_constant() {
+                Some(0) => self.value(otherwise, facts, depth),
+                Some(_) => self.value(then, facts, depth),
+                None => self.value(then, facts, depth).join(self.value(otherwise, facts, depth)),
+            },
+        };
+        match facts.iter().find(|x| x.0 == *expr) {
+            Some((_, fact)) => range.intersect(*fact).unwrap_or(range),
+            None => range,
+        }
+    }
+
+    // Adds what holds when `condition` is `truth`
+    fn assume(&self, condition: &Expr, truth: bool, facts: &mut Facts, depth: usize) {
+        if depth > DEPTH {
+            return;
+        }
+        match condition {
+            Expr::Unary(UnaryOp::Not, value) => self.assume(value, !truth, facts, depth + 1),
+            Expr::Binary(op @ (BinaryOp::And | BinaryOp::Or), left, right) => {
+                if (*op == BinaryOp::And) == truth {
+                    self.assume(left, truth, facts, depth + 1);
+                    self.assume(right, truth, facts, depth + 1);
+                    return;
+                }
+                // Either one holds: what is known of the same expressions either way
+                let (mut either, mut or) = (Facts::new(), Facts::new());
+                self.assume(left, truth, &mut either, depth + 1);
+                self.assume(right, truth, &mut or, depth + 1);
+                for (expr, range) in either {
+                    if let Some((_, other)) = or.iter().find(|x| x.0 == expr) {
+                        add_fact(facts, expr, range.join(*other));
+                    }
+                }
+            }
+            Expr::Var(var) => {
+                add_fact(facts, condition.clone(), Range::constant(truth as u64));
+                if let Some(value) = self.definitions.value(self.function, var) {
+                    if !value.has_load() {
+                        self.assume(value, truth, facts, depth + 1);
+                    }
+                }
+            }
+            Expr::Compare(op, left, right) => {
+                let (a, b) = (self.evaluate(left).as_constant(), self.evaluate(right).as_constant());
+                let (value, constant, op, swapped) = match (a, b) {
+                    (_, Some(constant)) => (left, constant, *op, false),
+                    (Some(constant), None) => (right, constant, *op, true),
+                    _ => return,
+                };
+                if let Some(range) = comparison(op, swapped, truth, constant, value.ty()) {
+                    self.constrain(value, range, facts, depth + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Adds that `expr` is in `range`, and what follows for the values it is computed from
+    fn constrain(&self, expr: &Expr, range: Range, facts: &mut Facts, depth: usize) {
+        if depth > DEPTH || expr.has_load() {
+            return;
+        }
+        add_fact(facts, expr.clone(), range);
+        let ty = expr.ty();
+        match expr {
+            Expr::Var(var) => {
+                if let Some(value) = self.definitions.value(self.function, var) {
+                    self.constrain(value, range, facts, depth + 1);
+                }
+            }
+            // x + c in the range is x in it moved back by c, if it doesn't wrap around partly
+            Expr::Binary(op @ (BinaryOp::Add | BinaryOp::Sub), value, offset) if ty.bits() <= 64 => {
+                let Some(offset) = offset.constant() else { return };
+                let offset = if *op == BinaryOp::Add { Range::constant(offset).negate(ty) } else { Range::constant(offset) };
+                let moved = range.add(offset, ty);
+                if !moved.is_full(ty) {
+                    self.constrain(value, moved, facts, depth + 1);
+                }
+            }
+            Expr::Cast(CastOp::ZeroExtend, value, _) => {
+                if let Some(range) = range.intersect(Range::full(value.ty())) {
+                    self.constrain(value, range, facts, depth + 1);
+                }
+            }
+            Expr::Cast(CastOp::SignExtend, value, _) if range.high <= value.ty().mask() >> 1 => {
+                self.constrain(value, range, facts, depth + 1)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn add_fact(facts: &mut Facts, expr: Expr, range: Range) {
+    match facts.iter_mut().find(|x| x.0 == expr) {
+        // Contradicting facts mean the block can't be reached, the first is kept
+        Some((_, known)) => *known = known.intersect(range).unwrap_or(*known),
+        None => facts.push((expr, range)),
+    }
+}
+
+// The values of x for which `x op constant`, or `constant op x` when swapped, is `truth`, if they are a range
+fn comparison(op: CompareOp, swapped: bool, truth: bool, constant: u64, ty: Type) -> Option<Range> {
+    if ty.bits() > 64 {
+        return None;
+    }
+    let mask = ty.mask();
+    let (less, equal) = match op {
+        CompareOp::Eq if truth => return Some(Range::constant(constant)),
+        CompareOp::Ne if !truth => return Some(Range::constant(constant)),
+        CompareOp::Eq | CompareOp::Ne => return None,
+        CompareOp::ULt | CompareOp::SLt => (true, false),
+        CompareOp::ULe | CompareOp::SLe => (true, true),
+    };
+    // As x < c, x <= c, x > c or x >= c
+    let less = less != swapped;
+    let (less, equal) = if truth { (less, equal) } else { (!less, !equal) };
+
+    // Flipping the sign bit orders signed values as unsigned ones
+    let flip = if matches!(op, CompareOp::SLt | CompareOp::SLe) { (mask >> 1) + 1 } else { 0 };
+    let constant = constant ^ flip;
+    let (low, high) = match (less, equal) {
+        (true, true) => (0, constant),
+        (true, false) => (0, constant.checked_sub(1)?),
+        (false, true) => (constant, mask),
+        (false, false) if constant == mask => return None,
+        (false, false) => (constant + 1, mask),
+    };
+    // Signed bounds are ranges when they don't cross from the negative values to the others
+    if flip != 0 && (low < flip) != (high < flip) {
+        return None;
+    }
+    Some(Range::new(low ^ flip, high ^ flip, 1))
+}
@@ -0,0 +1,302 @@
+use super::statement::{BinaryOp, CastOp, CompareOp, Expr, Type, UnaryOp};
+
+impl Expr {
+    /// Folds constants and removes operations that don't change the value, bottom up. Values wider than 64 bits
+    /// are only folded by the identities, not computed.
+    pub fn simplify(self) -> Expr {
+        match self {
+            Expr::Const { .. } | Expr::Var(_) => self,
+            Expr::Load { address, ty, endianness } => Expr::load(address.simplify(), ty, endianness),
+            Expr::Unary(op, value) => unary(op, value.simplify()),
+            Expr::Binary(op, left, right) => binary(op, left.simplify(), right.simplify()),
+            Expr::Compare(op, left, right) => compare(op, left.simplify(), right.simplify()),
+            Expr::Cast(op, value, ty) => cast(op, value.simplify(), ty),
+            Expr::Select { condition, then, otherwise } => {
+                let (then, otherwise) = (then.simplify(), otherwise.simplify());
+                match condition.simplify() {
+                    Expr::Const { value, .. } => {
+                        if value != 0 {
+                            then
+                        } else {
+                            otherwise
+                        }
+                    }
+                    _ if then == otherwise => then,
+                    condition => Expr::select(condition, then, otherwise),
+                }
+            }
+            Expr::Intrinsic { name, args, ty } => {
+                Expr::Intrinsic { name, args: args.into_iter().map(|x| x.simplify()).collect(), ty }
+            }
+        }
+    }
+}
+
+// The value as a signed integer of `bits`
+fn signed(value: u64, bits: u16) -> i64 {
+    if bits >= 64 {
+        value as i64
+    } else {
+        ((value << (64 - bits)) as i64) >> (64 - bits)
+    }
+}
+
+fn constant(value: u64, ty: Type) -> Expr {
+    Expr::Const { value: value & ty.mask(), ty }
+}
+
+fn unary(op: UnaryOp, value: Expr) -> Expr {
+    let ty = value.ty();
+    match (op, value) {
+        (UnaryOp::Neg, Expr::Const { value, .. }) if ty.bits() <= 64 => constant(value.wrapping_neg(), ty),
+        (UnaryOp::Not, Expr::Const { value, .. }) if ty.bits() <= 64 => constant(!value, ty),
+        (op, Expr::Unary(inner, value)) if inner == op => *value,
+        // Negated comparisons are the opposite comparisons
+        (UnaryOp::Not, Expr::Compare(op, left, right)) => match op {
+            CompareOp::Eq => Expr::Compare(CompareOp::Ne, left, right),
+            CompareOp::Ne => Expr::Compare(CompareOp::Eq, left, right),
+            CompareOp::ULt => Expr::Compare(CompareOp::ULe, right, left),
+            CompareOp::ULe => Expr::Compare(CompareOp::ULt, right, left),
+            CompareOp::SLt => Expr::Compare(CompareOp::SLe, right, left),
+            CompareOp::SLe => Expr::Compare(CompareOp::SLt, right, left),
+        },
+        (op, value) => Expr::unary(op, value),
+    }
+}
+
+fn is_commutative(op: BinaryOp) -> bool {
+    matches!(op, BinaryOp::Add | BinaryOp::Mul | BinaryOp::And | BinaryOp::Or | BinaryOp::Xor)
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    let ty = left.ty();
+    let bits = ty.bits();
+    // Constants go to the right of commutative operations
+    let (left, right) = match (&left, &right) {
+        (Expr::Const { .. }, Expr::Const { .. }) => (left, right),
+        (Expr::Const { .. }, _) if is_commutative(op) => (right, left),
+        _ => (left, right),
+    };
+
+    if let (Some(a), Some(b)) = (left.constant(), right.constant()) {
+        if let Some(value) = evaluate(op, a, b, bits) {
+            return constant(value, ty);
+        }
+    }
+
+    let all_ones = bits <= 64 && right.constant() == Some(ty.mask());
+    match (op, right.constant()) {
+        (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Or | BinaryOp::Xor, Some(0)) => return left,
+        (BinaryOp::Shl | BinaryOp::LShr | BinaryOp::AShr | BinaryOp::RotL | BinaryOp::RotR, Some(0)) => return left,
+        (BinaryOp::Mul | BinaryOp::UDiv | BinaryOp::SDiv, Some(1)) => return left,
+        (BinaryOp::And | BinaryOp::Mul, Some(0)) => return right,
+        (BinaryOp::And, _) if all_ones => return left,
+        (BinaryOp::Or, _) if all_ones => return right,
+        _ => {}
+    }
+    if let Some(res) = comparison(op, &left, &right).or_else(|| comparison(op, &right, &left)) {
+        return res;
+    }
+    if left == right {
+        match op {
+            BinaryOp::And | BinaryOp::Or => return left,
+            BinaryOp::Xor | BinaryOp::Sub if bits <= 64 => return constant(0, ty),
+            _ => {}
+        }
+    }
+
+    // Offsets add up: (x + a) - b is x + (a - b)
+    if let (BinaryOp::Add | BinaryOp::Sub, Some(b), Expr::Binary(inner, x, a)) = (op, right.constant(), &left) {
+        if let (BinaryOp::Add | BinaryOp::Sub, Some(a), true) = (*inner, a.constant(), bits <= 64) {
+            let a = if *inner == BinaryOp::Add { a } else { a.wrapping_neg() };
+            let b = if op == BinaryOp::Add { b } else { b.wrapping_neg() };
+            let offset = a.wrapping_add(b) & ty.mask();
+            // Negative offsets are subtracted
+            return if signed(offset, bits) < 0 {
+                binary(BinaryOp::Sub, (**x).clone(), constant(offset.wrapping_neg(), ty))
+            } else {
+                binary(BinaryOp::Add, (**x).clone(), constant(offset, ty))
+            };
+        }
+    }
+    Expr::binary(op, left, right)
+}
+
+// The comparison flags combine into, when they come from comparing the same values: signed less than is sf ^ of
+// after a subtraction, and less or equal is that or equality. On AArch64 greater than is the opposite comparison
+// and not equal.
+fn comparison(op: BinaryOp, left: &Expr, right: &Expr) -> Option<Expr> {
+    let (Expr::Compare(a, x, y), Expr::Compare(b, z, w)) = (left, right) else { return None };
+    let same = (x == z && y == w) || (x == w && y == z);
+    match (op, *a, *b) {
+        (BinaryOp::Xor, CompareOp::SLt, CompareOp::SLt) if is_zero(y) && is_zero(w) => {
+            let Expr::Binary(BinaryOp::Sub, p, q) = &**x else { return None };
+            let overflow = Expr::binary(
+                BinaryOp::And,
+                Expr::binary(BinaryOp::Xor, (**p).clone(), (**q).clone()),
+                Expr::binary(BinaryOp::Xor, (**p).clone(), (**x).clone()),
+            );
+            (**z == overflow.simplify()).then(|| Expr::Compare(CompareOp::SLt, p.clone(), q.clone()))
+        }
+        (BinaryOp::Or, CompareOp::Eq, CompareOp::SLt | CompareOp::ULt) if same => {
+            let op = if *b == CompareOp::SLt { CompareOp::SLe } else { CompareOp::ULe };
+            Some(Expr::Compare(op, z.clone(), w.clone()))
+        }
+        (BinaryOp::And, CompareOp::Ne, CompareOp::SLe | CompareOp::ULe) if same => {
+            let op = if *b == CompareOp::SLe { CompareOp::SLt } else { CompareOp::ULt };
+            Some(Expr::Compare(op, z.clone(), w.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn is_zero(expr: &Expr) -> bool {
+    expr.constant() == Some(0)
+}
+
+// The operation on constants, unless it divides by zero or is too wide. Shift amounts may be of another type.
+fn evaluate(op: BinaryOp, a: u64, b: u64, bits: u16) -> Option<u64> {
+    if bits > 64 {
+        return match op {
+            BinaryOp::And => Some(a & b),
+            BinaryOp::Or => Some(a | b),
+            BinaryOp::Xor => Some(a ^ b),
+            _ => None,
+        };
+    }
+    let mask = Type::Int(bits).mask();
+    let shift = |amount: u64| if amount >= bits as u64 { None } else { Some(amount as u32) };
+    let res = match op {
+        BinaryOp::Add => a.wrapping_add(b),
+        BinaryOp::Sub => a.wrapping_sub(b),
+        BinaryOp::Mul => a.wrapping_mul(b),
+        BinaryOp::UDiv => a.checked_div(b)?,
+        BinaryOp::URem => a.checked_rem(b)?,
+        BinaryOp::SDiv | BinaryOp::SRem if b == 0 => return None,
+        // Dividing the lowest value by -1 overflows back to it
+        BinaryOp::SDiv => signed(a, bits).checked_div(signed(b, bits)).unwrap_or(i64::MIN) as u64,
+        BinaryOp::SRem => signed(a, bits).checked_rem(signed(b, bits)).unwrap_or(0) as u64,
+        BinaryOp::And => a & b,
+        BinaryOp::Or => a | b,
+        BinaryOp::Xor => a ^ b,
+        BinaryOp::Shl => shift(b).map_or(0, |x| a << x),
+        BinaryOp::LShr => shift(b).map_or(0, |x| a >> x),
+        BinaryOp::AShr => {
+            let value = signed(a, bits);
+            shift(b).map_or(value >> 63, |x| value >> x) as u64
+        }
+        BinaryOp::RotL | BinaryOp::RotR => {
+            let amount = (b % bits as u64) as u32;
+            let left = if op == BinaryOp::RotL { amount } else { (bits as u32 - amount) % bits as u32 };
+            if left == 0 {
+                a
+            } else {
+                (a << left | a >> (bits as u32 - left)) & mask
+            }
+        }
+    };
+    Some(res & mask)
+}
+
+fn compare(op: CompareOp, left: Expr, right: Expr) -> Expr {
+    let bits = left.ty().bits();
+    if let (Some(a), Some(b), true) = (left.constant(), right.constant(), bits <= 64) {
+        let res = match op {
+            CompareOp::Eq => a == b,
+            CompareOp::Ne => a != b,
+            CompareOp::ULt => a < b,
+            CompareOp::ULe => a <= b,
+            CompareOp::SLt => signed(a, bits) < signed(b, bits),
+            CompareOp::SLe => signed(a, bits) <= signed(b, bits),
+        };
+        return Expr::bool(res);
+    }
+    if left == right {
+        return Expr::bool(matches!(op, CompareOp::Eq | CompareOp::ULe | CompareOp::SLe));
+    }
+    match (op, right.constant(), left.ty()) {
+        // Flags compared with constants are the flag or its negation
+        (CompareOp::Eq, Some(value), Type::Bool) | (CompareOp::Ne, Some(value), Type::Bool) => {
+            if (op == CompareOp::Eq) == (value != 0) {
+                left
+            } else {
+                unary(UnaryOp::Not, left)
+            }
+        }
+        // Nothing is below 0
+        (CompareOp::ULt, Some(0), _) => Expr::bool(false),
+        // A difference or exclusive or is 0 when the operands are equal
+        (CompareOp::Eq | CompareOp::Ne, Some(0), _) => match left {
+            Expr::Binary(BinaryOp::Sub | BinaryOp::Xor, x, y) => compare(op, *x, *y),
+            left => Expr::compare(op, left, right),
+        },
+        _ => Expr::compare(op, left, right),
+    }
+}
+
+fn cast(op: CastOp, value: Expr, ty: Type) -> Expr {
+    let from = value.ty();
+    if from == ty {
+        return value;
+    }
+    if let (Expr::Const { value, .. }, Type::Bool) = (&value, ty) {
+        return Expr::bool(value & 1 != 0);
+    }
+    if let Expr::Const { .. } = value {
+        // Folded by resize, unless sign extending a negative value past 64 bits
+        let folded = value.clone().resize(ty.bits(), op == CastOp::SignExtend);
+        if let Expr::Const { .. } = folded {
+            return folded;
+        }
+        return Expr::cast(op, value, ty);
+    }
+    match (op, value) {
+        // Extending twice the same way is extending once, as is zero extending a value extended by zero
+        (CastOp::ZeroExtend, Expr::Cast(CastOp::ZeroExtend, inner, _))
+        | (CastOp::SignExtend, Expr::Cast(CastOp::SignExtend, inner, _))
+        | (CastOp::Truncate, Expr::Cast(CastOp::Truncate, inner, _)) => cast(op, *inner, ty),
+        // Truncating an extended value keeps what was extended or part of it
+        (CastOp::Truncate, Expr::Cast(inner_op @ (CastOp::ZeroExtend | CastOp::SignExtend), inner, _)) => {
+            let inner_bits = inner.ty().bits();
+            if inner_bits >= ty.bits() {
+                cast(CastOp::Truncate, *inner, ty)
+            } else {
+                cast(inner_op, *inner, ty)
+            }
+        }
+        // Truncating the low bits merged into a value, as by setcc, keeps only what was merged
+        (CastOp::Truncate, Expr::Binary(BinaryOp::Or, left, right)) => {
+            let cleared = |x: &Expr| match x {
+                Expr::Binary(BinaryOp::And, _, mask) => mask.constant().is_some_and(|x| x & ty.mask() == 0),
+                _ => false,
+            };
+            match (cleared(&left), cleared(&right)) {
+                (true, _) => cast(CastOp::Truncate, *right, ty),
+                (_, true) => cast(CastOp::Truncate, *left, ty),
+                _ => Expr::cast(CastOp::Truncate, Expr::Binary(BinaryOp::Or, left, right), ty),
+            }
+        }
+        (op, value) => Expr::cast(op, value, ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{BinaryOp, CastOp, CompareOp, Expr, Type, Var};
+
+    #[test]
+    fn truncated_merges() {
+        let rax = Expr::var(Var::register("rax", Type::Int(64)));
+        let condition = Expr::compare(CompareOp::SLt, Expr::var(Var::register("rdi", Type::Int(64))), Expr::int(0, 64));
+        let merge = |mask: u64| {
+            let kept = Expr::binary(BinaryOp::And, rax.clone(), Expr::int(mask, 64));
+            Expr::binary(BinaryOp::Or, kept, Expr::cast(CastOp::ZeroExtend, condition.clone(), Type::Int(64)))
+        };
+        // setl al, then reading al: only the flag is left
+        let low = Expr::cast(CastOp::Truncate, merge(0xffff_ffff_ffff_ff00), Type::Int(8)).simplify();
+        assert_eq!(low, Expr::cast(CastOp::ZeroExtend, condition.clone(), Type::Int(8)));
+        // Reading eax keeps bits of rax
+        let wide = Expr::cast(CastOp::Truncate, merge(0xffff_ffff_ffff_ff00), Type::Int(32)).simplify();
+        assert_eq!(wide.to_string(), "trunc32((rax & 0xffffffffffffff00) | zext64(rdi <s 0))");
+    }
+}
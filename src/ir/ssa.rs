@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::analysis::{dominator_tree, reverse_postorder, BlockId, DominatorTree};
+
+use super::function::Function;
+use super::statement::{Expr, Stmt, Storage, Type, Var};
+
+/// What calls and system calls read and write, and which registers the caller sees once the function is left.
+/// The lifters leave all of these implicit.
+#[derive(Debug, Clone, Default)]
+pub struct CallEffects {
+    pub arguments: Vec<Var>,
+    pub results: Vec<Var>,
+    // Live when the function returns or jumps out of it, given by phis in the exit block
+    pub outputs: Vec<Var>,
+}
+
+impl CallEffects {
+    /// Without a calling convention: calls read every integer register the function mentions and write all of the
+    /// registers and flags but the stack pointer
+    pub fn conservative(function: &Function, stack_pointer: Var) -> CallEffects {
+        let registers = function.registers();
+        let integers: Vec<Var> = registers.iter().copied().filter(|x| x.ty != Type::Bool).collect();
+        let results = registers.into_iter().filter(|x| x.storage != stack_pointer.storage).collect();
+        CallEffects { arguments: integers.clone(), results, outputs: integers }
+    }
+}
+
+/// Where a statement is: its block and its index in the block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Location {
+    pub block: BlockId,
+    pub index: usize,
+}
+
+/// Def-use chains of a function in SSA form. Registers of version 0 hold what they did when the function was
+/// entered and have no definition.
+#[derive(Debug, Clone, Default)]
+pub struct DefUse {
+    definitions: BTreeMap<Var, Location>,
+    uses: BTreeMap<Var, Vec<Location>>,
+}
+
+impl DefUse {
+    pub fn build(function: &Function) -> DefUse {
+        let mut res = DefUse::default();
+        for (block, statements) in function.blocks.iter().enumerate().map(|(i, x)| (i, &x.statements)) {
+            for (index, (_, stmt)) in statements.iter().enumerate() {
+                let location = Location { block, index };
+                for var in stmt.definitions() {
+                    res.definitions.insert(*var, location);
+                }
+                stmt.for_each_use(&mut |var| {
+                    let uses = res.uses.entry(*var).or_default();
+                    // A statement reading a variable twice is one use
+                    if uses.last() != Some(&location) {
+                        uses.push(location);
+                    }
+                });
+            }
+        }
+        res
+    }
+
+    /// Where the variable is assigned
+    pub fn definition(&self, var: &Var) -> Option<Location> {
+        self.definitions.get(var).copied()
+    }
+
+    /// The statements reading the variable, in order
+    pub fn uses(&self, var: &Var) -> &[Location] {
+        self.uses.get(var).map(|x| x.as_slice()).unwrap_or(&[])
+    }
+
+    /// The value assigned to the variable, unless a phi or a call gives it
+    pub fn value<'a>(&self, function: &'a Function, var: &Var) -> Option<&'a Expr> {
+        match function.statement(self.definition(var)?) {
+            Stmt::Assign { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The variables defined, in order
+    pub fn variables(&self) -> impl Iterator<Item = &Var> {
+        self.definitions.keys()
+    }
+}
+
+impl Function {
+    pub fn statement(&self, location: Location) -> &Stmt {
+        &self.blocks[location.block].statements[location.index].1
+    }
+
+    pub fn dominators(&self) -> DominatorTree {
+        dominator_tree(self.blocks.len(), self.entry, |x| self.successors(x), |x| self.predecessors(x))
+    }
+
+    /// The blocks reachable from the entry, each before its successors except along back edges
+    pub fn reverse_postorder(&self) -> Vec<BlockId> {
+        reverse_postorder(self.blocks.len(), self.entry, |x| self.successors(x))
+    }
+
+    /// The registers and flags the statements read or write, as version 0
+    pub fn registers(&self) -> Vec<Var> {
+        let mut res = BTreeSet::new();
+        let mut add = |var: &Var| {
+            if let Storage::Register(_) = var.storage {
+                res.insert(Var { version: 0, ..*var });
+            }
+        };
+        for (_, stmt) in self.blocks.iter().flat_map(|x| x.statements.iter()) {
+            stmt.definitions().into_iter().for_each(&mut add);
+            stmt.for_each_use(&mut add);
+        }
+        res.into_iter().collect()
+    }
+
+    /// Converts the function to SSA form: calls get the arguments and results `effects` gives, phis are placed
+    /// where definitions meet and every assignment of a register gets its own version. Temporaries are assigned
+    /// once already and keep version 0, as do the registers read before the function assigns them.
+    ///
+    /// The phis of the exit block give the outputs, from every block leaving the function. When something jumps
+    /// back to the entry block its phis also get the values the function starts with, as coming from the exit
+    /// block. Blocks the entry doesn't reach are left as they are.
+    pub fn to_ssa(&mut self, effects: &CallEffects) {
+        for (_, stmt) in self.blocks.iter_mut().flat_map(|x| x.statements.iter_mut()) {
+            if let Stmt::Call { arguments, results, .. } | Stmt::Syscall { arguments, results } = stmt {
+                *arguments = effects.arguments.iter().map(|x| Expr::var(*x)).collect();
+                *results = effects.results.clone();
+            }
+        }
+
+        let dominators = self.dominators();
+        let reachable = |x: BlockId| x == self.entry || dominators.idom[x].is_some();
+        let predecessors: Vec<Vec<BlockId>> = (0..self.blocks.len())
+            .map(|block| {
+                let mut res: Vec<BlockId> = self.predecessors(block).into_iter().filter(|x| reachable(*x)).collect();
+                res.sort_unstable();
+                res.dedup();
+                res
+            })
+            .collect();
+        let frontiers = dominance_frontiers(&dominators, &predecessors);
+
+        // Only registers read in another block than the one assigning them need phis
+        let mut types: BTreeMap<Storage, Type> = BTreeMap::new();
+        let mut assigned_in: BTreeMap<Storage, BTreeSet<BlockId>> = BTreeMap::new();
+        let mut live_across: BTreeSet<Storage> = effects.outputs.iter().map(|x| x.storage).collect();
+        for (i, block) in self.blocks.iter().enumerate().filter(|x| reachable(x.0)) {
+            let mut assigned = BTreeSet::new();
+            for (_, stmt) in block.statements.iter() {
+                stmt.for_each_use(&mut |var| {
+                    if !assigned.contains(&var.storage) {
+                        live_across.insert(var.storage);
+                    }
+                });
+                for var in stmt.definitions() {
+                    types.insert(var.storage, var.ty);
+                    assigned.insert(var.storage);
+                    assigned_in.entry(var.storage).or_default().insert(i);
+                }
+            }
+        }
+
+        let mut phis: Vec<Vec<Var>> = vec![Vec::new(); self.blocks.len()];
+        if !predecessors[self.exit].is_empty() {
+            phis[self.exit] = effects.outputs.iter().map(|x| Var { version: 0, ..*x }).collect();
+        }
+        for (storage, blocks) in assigned_in.iter() {
+            if !live_across.contains(storage) || matches!(storage, Storage::Temp(_)) {
+                continue;
+            }
+            let var = Var { storage: *storage, ty: types[storage], version: 0 };
+            let mut work: Vec<BlockId> = blocks.iter().copied().collect();
+            let mut queued: BTreeSet<BlockId> = blocks.clone();
+            while let Some(block) = work.pop() {
+                for frontier in frontiers[block].iter().copied() {
+                    if phis[frontier].iter().any(|x| x.storage == *storage) {
+                        continue;
+                    }
+                    phis[frontier].push(var);
+                    if queued.insert(frontier) {
+                        work.push(frontier);
+                    }
+                }
+            }
+        }
+        for (block, vars) in phis.into_iter().enumerate() {
+            let address = self.blocks[block].start;
+            let mut sources = predecessors[block].clone();
+            if block == self.entry && !sources.is_empty() {
+                sources.push(self.exit);
+            }
+            let statements = vars.into_iter().map(|var| {
+                let sources = sources.iter().map(|x| (*x, Expr::var(var))).collect();
+                (address, Stmt::Phi { var, sources })
+            });
+            self.blocks[block].statements.splice(0..0, statements);
+        }
+
+        self.rename(&dominators);
+    }
+
+    // Gives every definition of a register a new version, walking the dominator tree so that each use sees the
+    // definition dominating it
+    fn rename(&mut self, dominators: &DominatorTree) {
+        let mut children: Vec<Vec<BlockId>> = vec![Vec::new(); self.blocks.len()];
+        for (block, idom) in dominators.idom.iter().enumerate() {
+            if let Some(idom) = idom {
+                children[*idom].push(block);
+            }
+        }
+        let mut versions: BTreeMap<Storage, u32> = BTreeMap::new();
+        let mut current: BTreeMap<Storage, Vec<u32>> = BTreeMap::new();
+        let renamed = |current: &BTreeMap<Storage, Vec<u32>>, var: &mut Var| {
+            if let Storage::Register(_) = var.storage {
+                var.version = current.get(&var.storage).and_then(|x| x.last().copied()).unwrap_or(0);
+            }
+        };
+
+        // Entering a block pushes the versions it defines, leaving it pops them again
+        let mut stack: Vec<(BlockId, bool)> = vec![(self.entry, true)];
+        let mut defined: Vec<Vec<Storage>> = vec![Vec::new(); self.blocks.len()];
+        while let Some((block, entering)) = stack.pop() {
+            if !entering {
+                for storage in defined[block].iter() {
+                    current.get_mut(storage).unwrap().pop();
+                }
+                continue;
+            }
+            for (_, stmt) in self.blocks[block].statements.iter_mut() {
+                if !matches!(stmt, Stmt::Phi { .. }) {
+                    for expr in stmt.expressions_mut() {
+                        expr.for_each_var_mut(&mut |var| renamed(&current, var));
+                    }
+                }
+                for var in stmt.definitions_mut() {
+                    if let Storage::Register(_) = var.storage {
+                        let version = versions.entry(var.storage).or_default();
+                        *version += 1;
+                        var.version = *version;
+                        current.entry(var.storage).or_default().push(*version);
+                        defined[block].push(var.storage);
+                    }
+                }
+            }
+            // The sources from the exit block into the entry block keep version 0
+            let mut successors = self.successors(block);
+            successors.sort_unstable();
+            successors.dedup();
+            for successor in successors {
+                for (_, stmt) in self.blocks[successor].statements.iter_mut() {
+                    let Stmt::Phi { sources, .. } = stmt else { break };
+                    for (_, value) in sources.iter_mut().filter(|x| x.0 == block) {
+                        value.for_each_var_mut(&mut |var| renamed(&current, var));
+                    }
+                }
+            }
+            stack.push((block, false));
+            stack.extend(children[block].iter().rev().map(|x| (*x, true)));
+        }
+    }
+}
+
+// The blocks where the dominance of each block ends: those it doesn't strictly dominate but dominates a
+// predecessor of
+fn dominance_frontiers(dominators: &DominatorTree, predecessors: &[Vec<BlockId>]) -> Vec<BTreeSet<BlockId>> {
+    let mut res = vec![BTreeSet::new(); predecessors.len()];
+    for (block, predecessors) in predecessors.iter().enumerate() {
+        if predecessors.len() < 2 {
+            continue;
+        }
+        let idom = dominators.idom[block];
+        for predecessor in predecessors.iter().copied() {
+            let mut runner = Some(predecessor);
+            while let Some(x) = runner {
+                if Some(x) == idom {
+                    break;
+                }
+                res[x].insert(block);
+                runner = dominators.idom[x];
+            }
+        }
+    }
+    res
+}
@@ -0,0 +1,596 @@
+use std::fmt;
+
+use crate::analysis::BlockId;
+use crate::endian::Endianness;
+
+/// Type of a value: a flag, or an integer of some number of bits which the operations interpret as signed or
+/// unsigned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Type {
+    Bool,
+    Int(u16),
+}
+
+impl Type {
+    pub fn bits(self) -> u16 {
+        match self {
+            Type::Bool => 1,
+            Type::Int(bits) => bits,
+        }
+    }
+
+    // All the bits of a value of the type set, for up to 64 bits
+    pub fn mask(self) -> u64 {
+        match self.bits() {
+            bits if bits >= 64 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+}
+
+/// Where a variable lives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Storage {
+    // A whole architectural register or flag. Partial registers are reads and writes of parts of it.
+    Register(&'static str),
+    // A value computed within an instruction
+    Temp(u32),
+}
+
+/// A variable, assigned by statements. The version tells the assignments of a variable apart in SSA form, it is
+/// 0 otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var {
+    pub storage: Storage,
+    pub ty: Type,
+    pub version: u32,
+}
+
+impl Var {
+    pub const fn register(name: &'static str, ty: Type) -> Var {
+        Var { storage: Storage::Register(name), ty, version: 0 }
+    }
+
+    pub const fn flag(name: &'static str) -> Var {
+        Var::register(name, Type::Bool)
+    }
+
+    pub const fn temp(id: u32, ty: Type) -> Var {
+        Var { storage: Storage::Temp(id), ty, version: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnaryOp {
+    Neg,
+    // Bitwise for integers, logical for flags
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    // Bitwise for integers, logical for flags
+    And,
+    Or,
+    Xor,
+    Shl,
+    LShr,
+    AShr,
+    RotL,
+    RotR,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    ULt,
+    ULe,
+    SLt,
+    SLe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastOp {
+    ZeroExtend,
+    SignExtend,
+    // Keeps the low bits
+    Truncate,
+}
+
+/// A side effect free computation, except for loads which may fault
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    // Values wider than 64 bits are zero extended
+    Const { value: u64, ty: Type },
+    Var(Var),
+    Load { address: Box<Expr>, ty: Type, endianness: Endianness },
+    Unary(UnaryOp, Box<Expr>),
+    // Both operands have the same type, except for the amount of shifts and rotations
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Compare(CompareOp, Box<Expr>, Box<Expr>),
+    Cast(CastOp, Box<Expr>, Type),
+    Select { condition: Box<Expr>, then: Box<Expr>, otherwise: Box<Expr> },
+    // An operation the IR doesn't model, such as those of vector instructions
+    Intrinsic { name: String, args: Vec<Expr>, ty: Type },
+}
+
+impl Expr {
+    /// An integer constant, truncated to `bits`
+    pub fn int(value: u64, bits: u16) -> Expr {
+        let ty = Type::Int(bits);
+        Expr::Const { value: value & ty.mask(), ty }
+    }
+
+    pub fn bool(value: bool) -> Expr {
+        Expr::Const { value: value as u64, ty: Type::Bool }
+    }
+
+    pub fn var(var: Var) -> Expr {
+        Expr::Var(var)
+    }
+
+    pub fn load(address: Expr, ty: Type, endianness: Endianness) -> Expr {
+        Expr::Load { address: Box::new(address), ty, endianness }
+    }
+
+    pub fn unary(op: UnaryOp, value: Expr) -> Expr {
+        Expr::Unary(op, Box::new(value))
+    }
+
+    pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr::Binary(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn compare(op: CompareOp, left: Expr, right: Expr) -> Expr {
+        Expr::Compare(op, Box::new(left), Box::new(right))
+    }
+
+    pub fn cast(op: CastOp, value: Expr, ty: Type) -> Expr {
+        Expr::Cast(op, Box::new(value), ty)
+    }
+
+    pub fn select(condition: Expr, then: Expr, otherwise: Expr) -> Expr {
+        Expr::Select { condition: Box::new(condition), then: Box::new(then), otherwise: Box::new(otherwise) }
+    }
+
+    pub fn intrinsic(name: &str, args: Vec<Expr>, ty: Type) -> Expr {
+        Expr::Intrinsic { name: name.to_string(), args, ty }
+    }
+
+    pub fn ty(&self) -> Type {
+        match self {
+            Expr::Const { ty, .. } | Expr::Load { ty, .. } | Expr::Cast(_, _, ty) | Expr::Intrinsic { ty, .. } => *ty,
+            Expr::Var(var) => var.ty,
+            Expr::Unary(_, value) => value.ty(),
+            Expr::Binary(_, left, _) => left.ty(),
+            Expr::Compare(..) => Type::Bool,
+            Expr::Select { then, .. } => then.ty(),
+        }
+    }
+
+    /// The value as an integer of `bits`, truncated or extended. Flags become 0 or 1.
+    pub fn resize(self, bits: u16, signed: bool) -> Expr {
+        let from = self.ty();
+        if from == Type::Int(bits) {
+            return self;
+        }
+        // Constants are extended right away, as long as they still fit
+        if let Expr::Const { value, .. } = self {
+            let negative = from.bits() > 0 && from.bits() <= 64 && value >> (from.bits() - 1) & 1 == 1;
+            if !(signed && negative && from.bits() < bits) {
+                return Expr::int(value, bits);
+            }
+            if bits <= 64 {
+                return Expr::int(value | !from.mask(), bits);
+            }
+        }
+        let op = if from.bits() > bits {
+            CastOp::Truncate
+        } else if signed && from != Type::Bool {
+            CastOp::SignExtend
+        } else {
+            CastOp::ZeroExtend
+        };
+        Expr::cast(op, self, Type::Int(bits))
+    }
+
+    /// The constant value, if the expression is one
+    pub fn constant(&self) -> Option<u64> {
+        match self {
+            Expr::Const { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with every variable read by the expression
+    pub fn for_each_var(&self, f: &mut dyn FnMut(&Var)) {
+        match self {
+            Expr::Const { .. } => {}
+            Expr::Var(var) => f(var),
+            Expr::Load { address, .. } => address.for_each_var(f),
+            Expr::Unary(_, value) | Expr::Cast(_, value, _) => value.for_each_var(f),
+            Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+                left.for_each_var(f);
+                right.for_each_var(f);
+            }
+            Expr::Select { condition, then, otherwise } => {
+                condition.for_each_var(f);
+                then.for_each_var(f);
+                otherwise.for_each_var(f);
+            }
+            Expr::Intrinsic { args, .. } => args.iter().for_each(|x| x.for_each_var(f)),
+        }
+    }
+
+    pub fn for_each_var_mut(&mut self, f: &mut dyn FnMut(&mut Var)) {
+        match self {
+            Expr::Const { .. } => {}
+            Expr::Var(var) => f(var),
+            Expr::Load { address, .. } => address.for_each_var_mut(f),
+            Expr::Unary(_, value) | Expr::Cast(_, value, _) => value.for_each_var_mut(f),
+            Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+                left.for_each_var_mut(f);
+                right.for_each_var_mut(f);
+            }
+            Expr::Select { condition, then, otherwise } => {
+                condition.for_each_var_mut(f);
+                then.for_each_var_mut(f);
+                otherwise.for_each_var_mut(f);
+            }
+            Expr::Intrinsic { args, .. } => args.iter_mut().for_each(|x| x.for_each_var_mut(f)),
+        }
+    }
+
+    /// Replaces the variables for which `f` gives an expression
+    pub fn substitute(&mut self, f: &mut dyn FnMut(&Var) -> Option<Expr>) {
+        match self {
+            Expr::Const { .. } => {}
+            Expr::Var(var) => {
+                if let Some(value) = f(var) {
+                    *self = value;
+                }
+            }
+            Expr::Load { address, .. } => address.substitute(f),
+            Expr::Unary(_, value) | Expr::Cast(_, value, _) => value.substitute(f),
+            Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => {
+                left.substitute(f);
+                right.substitute(f);
+            }
+            Expr::Select { condition, then, otherwise } => {
+                condition.substitute(f);
+                then.substitute(f);
+                otherwise.substitute(f);
+            }
+            Expr::Intrinsic { args, .. } => args.iter_mut().for_each(|x| x.substitute(f)),
+        }
+    }
+
+    /// Whether the expression reads memory
+    pub fn has_load(&self) -> bool {
+        match self {
+            Expr::Const { .. } | Expr::Var(_) => false,
+            Expr::Load { .. } => true,
+            Expr::Unary(_, value) | Expr::Cast(_, value, _) => value.has_load(),
+            Expr::Binary(_, left, right) | Expr::Compare(_, left, right) => left.has_load() || right.has_load(),
+            Expr::Select { condition, then, otherwise } => condition.has_load() || then.has_load() || otherwise.has_load(),
+            Expr::Intrinsic { args, .. } => args.iter().any(|x| x.has_load()),
+        }
+    }
+}
+
+/// Where a jump or call goes
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Destination {
+    Direct(u64),
+    Indirect(Expr),
+}
+
+/// A statement, executed in order within a block. Calls leave the stack pointer as it was before them and returns
+/// don't move it either: pushing and popping the return address is part of them.
+///
+/// The lifters leave the arguments and results of calls and system calls empty, SSA construction fills them in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Stmt {
+    Assign { var: Var, value: Expr },
+    // At the start of a block in SSA form: the value coming from each predecessor
+    Phi { var: Var, sources: Vec<(BlockId, Expr)> },
+    // The size of the access is the size of the value
+    Store { address: Expr, value: Expr, endianness: Endianness },
+    Jump(Destination),
+    // Execution continues with the next instruction if the condition is false
+    Branch { condition: Expr, target: Destination },
+    Call { target: Destination, arguments: Vec<Expr>, results: Vec<Var> },
+    Return,
+    Syscall { arguments: Vec<Expr>, results: Vec<Var> },
+    // Execution doesn't continue
+    Trap,
+    // A side effect the IR doesn't model
+    Intrinsic { name: String, args: Vec<Expr> },
+}
+
+impl Stmt {
+    pub fn call(target: Destination) -> Stmt {
+        Stmt::Call { target, arguments: Vec::new(), results: Vec::new() }
+    }
+
+    pub fn syscall() -> Stmt {
+        Stmt::Syscall { arguments: Vec::new(), results: Vec::new() }
+    }
+
+    /// The variables the statement assigns
+    pub fn definitions(&self) -> Vec<&Var> {
+        match self {
+            Stmt::Assign { var, .. } | Stmt::Phi { var, .. } => vec![var],
+            Stmt::Call { results, .. } | Stmt::Syscall { results, .. } => results.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn definitions_mut(&mut self) -> Vec<&mut Var> {
+        match self {
+            Stmt::Assign { var, .. } | Stmt::Phi { var, .. } => vec![var],
+            Stmt::Call { results, .. } | Stmt::Syscall { results, .. } => results.iter_mut().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The expressions the statement evaluates
+    pub fn expressions(&self) -> Vec<&Expr> {
+        match self {
+            Stmt::Assign { value, .. } => vec![value],
+            Stmt::Phi { sources, .. } => sources.iter().map(|x| &x.1).collect(),
+            Stmt::Store { address, value, .. } => vec![address, value],
+            Stmt::Jump(destination) => match destination {
+                Destination::Direct(_) => vec![],
+                Destination::Indirect(x) => vec![x],
+            },
+            Stmt::Call { target, arguments, .. } => {
+                let mut res: Vec<&Expr> = match target {
+                    Destination::Direct(_) => vec![],
+                    Destination::Indirect(x) => vec![x],
+                };
+                res.extend(arguments.iter());
+                res
+            }
+            Stmt::Branch { condition, target } => match target {
+                Destination::Direct(_) => vec![condition],
+                Destination::Indirect(x) => vec![condition, x],
+            },
+            Stmt::Return | Stmt::Trap => vec![],
+            Stmt::Syscall { arguments: args, .. } | Stmt::Intrinsic { args, .. } => args.iter().collect(),
+        }
+    }
+
+    pub fn expressions_mut(&mut self) -> Vec<&mut Expr> {
+        match self {
+            Stmt::Assign { value, .. } => vec![value],
+            Stmt::Phi { sources, .. } => sources.iter_mut().map(|x| &mut x.1).collect(),
+            Stmt::Store { address, value, .. } => vec![address, value],
+            Stmt::Jump(destination) => match destination {
+                Destination::Direct(_) => vec![],
+                Destination::Indirect(x) => vec![x],
+            },
+            Stmt::Call { target, arguments, .. } => {
+                let mut res: Vec<&mut Expr> = match target {
+                    Destination::Direct(_) => vec![],
+                    Destination::Indirect(x) => vec![x],
+                };
+                res.extend(arguments.iter_mut());
+                res
+            }
+            Stmt::Branch { condition, target } => match target {
+                Destination::Direct(_) => vec![condition],
+                Destination::Indirect(x) => vec![condition, x],
+            },
+            Stmt::Return | Stmt::Trap => vec![],
+            Stmt::Syscall { arguments: args, .. } | Stmt::Intrinsic { args, .. } => args.iter_mut().collect(),
+        }
+    }
+
+    /// Calls `f` with every variable the statement reads
+    pub fn for_each_use(&self, f: &mut dyn FnMut(&Var)) {
+        for expr in self.expressions() {
+            expr.for_each_var(f);
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Bool => write!(f, "bool"),
+            Type::Int(bits) => write!(f, "i{}", bits),
+        }
+    }
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.storage {
+            Storage::Register(name) => write!(f, "{}", name)?,
+            Storage::Temp(id) => write!(f, "t{}", id)?,
+        }
+        if self.version != 0 {
+            write!(f, "_{}", self.version)?;
+        }
+        Ok(())
+    }
+}
+
+impl BinaryOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::UDiv => "/u",
+            BinaryOp::SDiv => "/s",
+            BinaryOp::URem => "%u",
+            BinaryOp::SRem => "%s",
+            BinaryOp::And => "&",
+            BinaryOp::Or => "|",
+            BinaryOp::Xor => "^",
+            BinaryOp::Shl => "<<",
+            BinaryOp::LShr => ">>u",
+            BinaryOp::AShr => ">>s",
+            BinaryOp::RotL => "rotl",
+            BinaryOp::RotR => "rotr",
+        }
+    }
+}
+
+impl CompareOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::ULt => "<u",
+            CompareOp::ULe => "<=u",
+            CompareOp::SLt => "<s",
+            CompareOp::SLe => "<=s",
+        }
+    }
+}
+
+impl Expr {
+    // Operations nested in others are parenthesized
+    fn write(&self, f: &mut fmt::Formatter<'_>, nested: bool) -> fmt::Result {
+        let (open, close) = if nested { ("(", ")") } else { ("", "") };
+        match self {
+            Expr::Const { value, ty: Type::Bool } => write!(f, "{}", *value != 0),
+            Expr::Const { value, .. } if *value < 10 => write!(f, "{}", value),
+            Expr::Const { value, .. } => write!(f, "0x{:x}", value),
+            Expr::Var(var) => write!(f, "{}", var),
+            Expr::Load { address, ty, endianness } => {
+                let order = if *endianness == Endianness::BigEndian { "be" } else { "" };
+                write!(f, "mem{}{}[", ty.bits(), order)?;
+                address.write(f, false)?;
+                write!(f, "]")
+            }
+            Expr::Unary(op, value) => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not if value.ty() == Type::Bool => "!",
+                    UnaryOp::Not => "~",
+                };
+                write!(f, "{}", symbol)?;
+                value.write(f, true)
+            }
+            Expr::Binary(op, left, right) => {
+                write!(f, "{}", open)?;
+                left.write(f, true)?;
+                write!(f, " {} ", op.symbol())?;
+                right.write(f, true)?;
+                write!(f, "{}", close)
+            }
+            Expr::Compare(op, left, right) => {
+                write!(f, "{}", open)?;
+                left.write(f, true)?;
+                write!(f, " {} ", op.symbol())?;
+                right.write(f, true)?;
+                write!(f, "{}", close)
+            }
+            Expr::Cast(op, value, ty) => {
+                let name = match op {
+                    CastOp::ZeroExtend => "zext",
+                    CastOp::SignExtend => "sext",
+                    CastOp::Truncate => "trunc",
+                };
+                write!(f, "{}{}(", name, ty.bits())?;
+                value.write(f, false)?;
+                write!(f, ")")
+            }
+            Expr::Select { condition, then, otherwise } => {
+                write!(f, "{}", open)?;
+                condition.write(f, true)?;
+                write!(f, " ? ")?;
+                then.write(f, true)?;
+                write!(f, " : ")?;
+                otherwise.write(f, true)?;
+                write!(f, "{}", close)
+            }
+            Expr::Intrinsic { name, args, .. } => write_call(f, name, args),
+        }
+    }
+}
+
+fn write_call(f: &mut fmt::Formatter<'_>, name: &str, args: &[Expr]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        arg.write(f, false)?;
+    }
+    write!(f, ")")
+}
+
+fn write_results(f: &mut fmt::Formatter<'_>, results: &[Var]) -> fmt::Result {
+    for (i, var) in results.iter().enumerate() {
+        write!(f, "{}{}", var, if i + 1 == results.len() { " = " } else { ", " })?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+impl fmt::Display for Destination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Destination::Direct(address) => write!(f, "0x{:x}", address),
+            Destination::Indirect(expr) => write!(f, "[{}]", expr),
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stmt::Assign { var, value } => write!(f, "{} = {}", var, value),
+            Stmt::Store { address, value, endianness } => {
+                let order = if *endianness == Endianness::BigEndian { "be" } else { "" };
+                write!(f, "mem{}{}[{}] = {}", value.ty().bits(), order, address, value)
+            }
+            Stmt::Jump(destination) => write!(f, "jump {}", destination),
+            Stmt::Branch { condition, target } => write!(f, "if {} jump {}", condition, target),
+            Stmt::Phi { var, sources } => {
+                write!(f, "{} = phi(", var)?;
+                for (i, (block, value)) in sources.iter().enumerate() {
+                    write!(f, "{}{}: {}", if i > 0 { ", " } else { "" }, block, value)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::Call { target, arguments, results } => {
+                write_results(f, results)?;
+                write!(f, "call {}", target)?;
+                if !arguments.is_empty() {
+                    write_call(f, "", arguments)?;
+                }
+                Ok(())
+            }
+            Stmt::Return => write!(f, "return"),
+            Stmt::Syscall { arguments, results } => {
+                write_results(f, results)?;
+                write!(f, "syscall")?;
+                if !arguments.is_empty() {
+                    write_call(f, "", arguments)?;
+                }
+                Ok(())
+            }
+            Stmt::Trap => write!(f, "trap"),
+            Stmt::Intrinsic { name, args } => write_call(f, name, args),
+        }
+    }
+}
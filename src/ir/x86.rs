@@ -0,0 +1,925 @@
+use crate::disasm::x86::gpr;
+use crate::disasm::{FlowKind, Instruction, MemoryOperand, Operand, Register, RegisterClass};
+use crate::endian::Endianness;
+
+use super::builder::{
+    add, add_overflow, and, constant, eq, extract, insert, is_zero, mentions, ne, negative, not, or, sub, sub_overflow,
+    ult, xor, Builder,
+};
+use super::statement::{BinaryOp, Destination, Expr, Stmt, Type, Var};
+use super::Lifter;
+
+const CF: Var = Var::flag("cf");
+const PF: Var = Var::flag("pf");
+const ZF: Var = Var::flag("zf");
+const SF: Var = Var::flag("sf");
+const OF: Var = Var::flag("of");
+const DF: Var = Var::flag("df");
+
+/// Lifts i386 and x86-64 instructions
+///
+/// Partial registers are parts of the full general purpose registers: writing a 32 bit register clears the upper
+/// half in 64 bit mode, writing the 8 and 16 bit ones keeps the rest. The flags are cf, pf, zf, sf, of and df, the
+/// auxiliary carry isn't modelled. The vector registers of each size are separate variables, and the string
+/// instructions assume the direction flag is clear, as the ABIs require at calls.
+pub struct X86Lifter {
+    // Width of addresses and general purpose registers
+    bits: u16,
+}
+
+impl X86Lifter {
+    pub fn new(bits: u16) -> X86Lifter {
+        X86Lifter { bits }
+    }
+}
+
+impl Lifter for X86Lifter {
+    fn lift(&self, instruction: &Instruction) -> Vec<Stmt> {
+        let mut lifter = InstructionLifter { b: Builder::new(Endianness::LittleEndian), bits: self.bits, instruction };
+        lifter.lift();
+        lifter.b.statements
+    }
+
+    fn stack_pointer(&self) -> Var {
+        full_register(4, self.bits)
+    }
+}
+
+fn full_register(number: u16, bits: u16) -> Var {
+    Var::register(gpr(number as u8, bits, true).name, Type::Int(bits))
+}
+
+struct InstructionLifter<'a> {
+    b: Builder,
+    bits: u16,
+    instruction: &'a Instruction,
+}
+
+impl<'a> InstructionLifter<'a> {
+    fn sp(&self) -> Var {
+        full_register(4, self.bits)
+    }
+
+    fn reg(&self, number: u16) -> Var {
+        full_register(number, self.bits)
+    }
+
+    // The general purpose register `number` viewed as `bits` wide
+    fn gpr(&self, number: u16, bits: u16) -> Operand {
+        Operand::Register(gpr(number as u8, bits, true))
+    }
+
+    // Width of an operand in bits, None for operands without a size
+    fn width(&self, operand: &Operand) -> Option<u16> {
+        match operand {
+            Operand::Register(register) => Some(register.bits),
+            Operand::Memory(mem) if mem.size != 0 => Some(mem.size * 8),
+            Operand::Immediate { size, .. } => Some(*size as u16 * 8),
+            _ => None,
+        }
+    }
+
+    fn read_register(&self, register: &Register) -> Expr {
+        match register.class {
+            RegisterClass::General => {
+                let full = Expr::var(self.reg(register.number));
+                if register.bits == self.bits {
+                    full
+                } else {
+                    extract(full, register.offset, register.bits)
+                }
+            }
+            RegisterClass::ProgramCounter => Expr::int(self.instruction.next_address(), register.bits),
+            _ => Expr::var(Var::register(register.name, Type::Int(register.bits))),
+        }
+    }
+
+    // The variable a register is part of, and its value once `value` is written to the register
+    fn merge(&self, register: &Register, value: Expr) -> (Var, Expr) {
+        let value = value.resize(register.bits, false);
+        match register.class {
+            RegisterClass::General => {
+                let full = self.reg(register.number);
+                let value = if register.bits == self.bits || register.bits == 32 {
+                    value.resize(self.bits, false)
+                } else {
+                    insert(Expr::var(full), value, register.offset)
+                };
+                (full, value)
+            }
+            _ => (Var::register(register.name, Type::Int(register.bits)), value),
+        }
+    }
+
+    fn write_register(&mut self, register: &Register, value: Expr) {
+        let (var, value) = self.merge(register, value);
+        self.b.assign(var, value);
+    }
+
+    fn address(&self, mem: &MemoryOperand) -> Expr {
+        let relative = mem.base.is_none_or(|x| x.class == RegisterClass::ProgramCounter);
+        let mut address = match mem.target {
+            Some(target) if relative && mem.index.is_none() => Expr::int(target, self.bits),
+            _ => {
+                // An address size prefix computes the address with 32 bit registers
+                let bits = mem.base.or(mem.index).map_or(self.bits, |x| x.bits);
+                let mut address = mem.base.map(|x| self.read_register(&x));
+                if let Some(index) = mem.index {
+                    let mut index = self.read_register(&index);
+                    if mem.scale > 1 {
+                        index = Expr::binary(BinaryOp::Mul, index, Expr::int(mem.scale as u64, bits));
+                    }
+                    address = Some(address.map_or(index.clone(), |x| add(x, index)));
+                }
+                let address = match address {
+                    Some(x) if mem.displacement == 0 => x,
+                    Some(x) if mem.displacement < 0 => sub(x, Expr::int(mem.displacement.unsigned_abs(), bits)),
+                    Some(x) => add(x, Expr::int(mem.displacement as u64, bits)),
+                    None => Expr::int(mem.displacement as u64, bits),
+                };
+                address.resize(self.bits, false)
+            }
+        };
+        // Thread local storage is at the base of fs or gs
+        if let Some(segment) = mem.segment.filter(|x| x.name == "fs" || x.name == "gs") {
+            let base = if segment.name == "fs" { "fs_base" } else { "gs_base" };
+            address = add(Expr::var(Var::register(base, Type::Int(self.bits))), address);
+        }
+        address
+    }
+
+    // The value of an operand. Immediates are extended to `bits`, the width of the other operands.
+    fn read(&self, operand: &Operand, bits: u16) -> Option<Expr> {
+        Some(match operand {
+            Operand::Register(register) => self.read_register(register),
+            Operand::Immediate { value, .. } => Expr::int(*value as u64, bits),
+            Operand::Memory(mem) if mem.size == 0 => self.address(mem),
+            Operand::Memory(mem) => self.b.load(self.address(mem), mem.size * 8),
+            Operand::Address(address) => Expr::int(*address, self.bits),
+            _ => return None,
+        })
+    }
+
+    // The value of an operand, evaluated once if it is read from memory since it is used in several places
+    fn read_once(&mut self, operand: &Operand, bits: u16) -> Option<Expr> {
+        let value = self.read(operand, bits)?;
+        Some(if value.has_load() { self.b.temp(value) } else { value })
+    }
+
+    fn write(&mut self, operand: &Operand, value: Expr) {
+        match operand {
+            Operand::Register(register) => self.write_register(register, value),
+            Operand::Memory(mem) => {
+                let value = value.resize(mem.size * 8, false);
+                let address = self.address(mem);
+                self.b.store(address, value);
+            }
+            _ => {}
+        }
+    }
+
+    // zf, sf and pf from a result
+    fn result_flags(&mut self, result: &Expr) {
+        self.b.assign(ZF, is_zero(result.clone()));
+        self.b.assign(SF, negative(result.clone()));
+        let low = result.clone().resize(8, false);
+        self.b.assign(PF, Expr::intrinsic("parity", vec![low], Type::Bool));
+    }
+
+    fn flags(&mut self, cf: Expr, of: Expr, result: &Expr) {
+        self.b.assign(CF, cf);
+        self.b.assign(OF, of);
+        self.result_flags(result);
+    }
+
+    // The condition of jcc, setcc and cmovcc, from the suffix of the mnemonic
+    fn condition(&self, suffix: &str) -> Option<Expr> {
+        let flag = |x: Var| Expr::var(x);
+        let less = || xor(flag(SF), flag(OF));
+        Some(match suffix {
+            "o" => flag(OF),
+            "no" => not(flag(OF)),
+            "b" | "c" | "nae" => flag(CF),
+            "ae" | "nb" | "nc" => not(flag(CF)),
+            "e" | "z" => flag(ZF),
+            "ne" | "nz" => not(flag(ZF)),
+            "be" | "na" => or(flag(CF), flag(ZF)),
+            "a" | "nbe" => not(or(flag(CF), flag(ZF))),
+            "s" => flag(SF),
+            "ns" => not(flag(SF)),
+            "p" | "pe" => flag(PF),
+            "np" | "po" => not(flag(PF)),
+            "l" | "nge" => less(),
+            "ge" | "nl" => not(less()),
+            "le" | "ng" => or(flag(ZF), less()),
+            "g" | "nle" => not(or(flag(ZF), less())),
+            _ => return None,
+        })
+    }
+
+    fn destination(&self, operand: Option<&Operand>) -> Destination {
+        match self.instruction.branch_target() {
+            Some(target) => Destination::Direct(target),
+            None => match operand.and_then(|x| self.read(x, self.bits)) {
+                Some(x) => Destination::Indirect(x.resize(self.bits, false)),
+                None => Destination::Indirect(Expr::intrinsic("unknown", vec![], Type::Int(self.bits))),
+            },
+        }
+    }
+
+    fn push(&mut self, value: Expr) {
+        let size = value.ty().bits() / 8;
+        let value = if value.has_load() || mentions(&value, self.sp()) { self.b.temp(value) } else { value };
+        let sp = self.sp();
+        self.b.assign(sp, sub(Expr::var(sp), Expr::int(size as u64, self.bits)));
+        self.b.store(Expr::var(sp), value);
+    }
+
+    fn pop(&mut self, bits: u16) -> Expr {
+        let sp = self.sp();
+        let value = self.b.temp(self.b.load(Expr::var(sp), bits));
+        self.b.assign(sp, add(Expr::var(sp), Expr::int(bits as u64 / 8, self.bits)));
+        value
+    }
+
+    fn lift(&mut self) {
+        let instruction = self.instruction;
+        // Prefixes are part of the mnemonic
+        let mut words: Vec<&str> = instruction.mnemonic.split(' ').collect();
+        let mnemonic = words.pop().unwrap_or_default();
+        let repeat = words.iter().find(|x| x.starts_with("rep")).copied();
+        let operands = &instruction.operands[..];
+        let first = operands.first();
+        let width = first.and_then(|x| self.width(x)).unwrap_or(self.bits);
+
+        match (mnemonic, operands) {
+            ("mov" | "movabs", [d, s]) => {
+                if let Some(value) = self.read(s, width) {
+                    self.write(d, value);
+                }
+            }
+            ("movzx" | "movsx" | "movsxd", [d, s]) => {
+                if let Some(value) = self.read(s, width) {
+                    self.write(d, value.resize(width, mnemonic != "movzx"));
+                }
+            }
+            ("lea", [d, Operand::Memory(mem)]) => {
+                let address = self.address(mem);
+                self.write(d, address);
+            }
+            ("xchg", [a, b]) => {
+                if a == b {
+                    // The 32 bit form still clears the upper half
+                    let value = self.read(a, width).unwrap_or_else(|| Expr::int(0, width));
+                    if width == 32 && self.bits == 64 {
+                        self.write(a, value);
+                    }
+                    return;
+                }
+                if let (Some(x), Some(y)) = (self.read(a, width), self.read(b, width)) {
+                    let x = self.b.temp(x);
+                    self.write(a, y);
+                    self.write(b, x);
+                }
+            }
+            ("add" | "sub" | "cmp" | "adc" | "sbb", [d, s]) => self.arithmetic(mnemonic, d, s, width),
+            ("and" | "or" | "xor" | "test", [d, s]) => self.logic(mnemonic, d, s, width),
+            ("inc" | "dec", [d]) => {
+                let Some(a) = self.read_once(d, width) else { return };
+                let one = constant(1, &a);
+                let result = if mnemonic == "inc" { add(a.clone(), one.clone()) } else { sub(a.clone(), one.clone()) };
+                let result = self.b.temp(result);
+                let of = if mnemonic == "inc" { add_overflow(&a, &one, &result) } else { sub_overflow(&a, &one, &result) };
+                self.b.assign(OF, of);
+                self.result_flags(&result);
+                self.write(d, result);
+            }
+            ("neg", [d]) => {
+                let Some(a) = self.read_once(d, width) else { return };
+                let zero = constant(0, &a);
+                let result = self.b.temp(sub(zero.clone(), a.clone()));
+                self.flags(ne(a.clone(), zero.clone()), sub_overflow(&zero, &a, &result), &result);
+                self.write(d, result);
+            }
+            ("not", [d]) => {
+                if let Some(a) = self.read(d, width) {
+                    self.write(d, not(a));
+                }
+            }
+            ("shl" | "sal" | "shr" | "sar" | "rol" | "ror", [d, count]) => self.shift(mnemonic, d, count, width),
+            ("shl" | "sal" | "shr" | "sar" | "rol" | "ror", [d]) => {
+                self.shift(mnemonic, d, &Operand::Immediate { value: 1, size: 1 }, width)
+            }
+            ("shld" | "shrd", [d, s, count]) => self.double_shift(mnemonic, d, s, count, width),
+            ("mul" | "imul", [s]) => self.multiply(mnemonic == "imul", s, width),
+            ("imul", [d, s]) => self.signed_multiply(d, d, s, width),
+            ("imul", [d, s, factor]) => self.signed_multiply(d, s, factor, width),
+            ("div" | "idiv", [s]) => self.divide(mnemonic == "idiv", s, width),
+            ("cbw" | "cwde" | "cdqe", []) => {
+                let bits = match mnemonic {
+                    "cbw" => 8,
+                    "cwde" => 16,
+                    _ => 32,
+                };
+                let value = self.read_register(&gpr(0, bits, true)).resize(bits * 2, true);
+                self.write(&self.gpr(0, bits * 2), value);
+            }
+            ("cwd" | "cdq" | "cqo", []) => {
+                let bits = match mnemonic {
+                    "cwd" => 16,
+                    "cdq" => 32,
+                    _ => 64,
+                };
+                let a = self.read_register(&gpr(0, bits, true));
+                let sign = Expr::binary(BinaryOp::AShr, a, Expr::int(bits as u64 - 1, bits));
+                self.write(&self.gpr(2, bits), sign);
+            }
+            ("push", [s]) => {
+                // Immediates and segment registers are pushed as whole stack slots
+                let bits = match s {
+                    Operand::Register(x) if x.class == RegisterClass::General => x.bits,
+                    Operand::Memory(mem) => mem.size * 8,
+                    _ => self.bits,
+                };
+                if let Some(value) = self.read(s, bits) {
+                    self.push(value.resize(bits, false));
+                }
+            }
+            ("pop", [d]) => {
+                let bits = match d {
+                    Operand::Register(x) if x.class == RegisterClass::General => x.bits,
+                    Operand::Memory(mem) => mem.size * 8,
+                    _ => self.bits,
+                };
+                let value = self.pop(bits);
+                self.write(d, value);
+            }
+            ("leave", []) => {
+                let sp = self.sp();
+                self.b.assign(sp, Expr::var(self.reg(5)));
+                let value = self.pop(self.bits);
+                let bp = self.reg(5);
+                self.b.assign(bp, value);
+            }
+            ("call", _) => {
+                let destination = self.destination(first);
+                self.b.push(Stmt::call(destination));
+            }
+            ("jmp", _) => {
+                let destination = self.destination(first);
+                self.b.jump(destination);
+            }
+            ("ret", _) => {
+                // The return address is popped by the return, only the extra bytes are released here
+                if let Some(Operand::Immediate { value, .. }) = first {
+                    let sp = self.sp();
+                    self.b.assign(sp, add(Expr::var(sp), Expr::int(*value as u64, self.bits)));
+                }
+                self.b.push(Stmt::Return);
+            }
+            ("loop" | "loope" | "loopne", _) => {
+                let counter = self.reg(1);
+                self.b.assign(counter, sub(Expr::var(counter), Expr::int(1, self.bits)));
+                let mut condition = ne(Expr::var(counter), Expr::int(0, self.bits));
+                if mnemonic == "loope" {
+                    condition = and(condition, Expr::var(ZF));
+                } else if mnemonic == "loopne" {
+                    condition = and(condition, not(Expr::var(ZF)));
+                }
+                let target = self.destination(first);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            ("jcxz" | "jecxz" | "jrcxz", _) => {
+                let bits = match mnemonic {
+                    "jcxz" => 16,
+                    "jecxz" => 32,
+                    _ => 64,
+                };
+                let condition = is_zero(self.read_register(&gpr(1, bits, true)));
+                let target = self.destination(first);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            _ if instruction.flow == FlowKind::Jump && mnemonic.starts_with('j') => {
+                let Some(condition) = self.condition(&mnemonic[1..]) else { return self.fallback(mnemonic) };
+                let target = self.destination(first);
+                self.b.push(Stmt::Branch { condition, target });
+            }
+            _ if mnemonic.starts_with("cmov") => {
+                let (Some(condition), [d, s]) = (self.condition(&mnemonic[4..]), operands) else {
+                    return self.fallback(mnemonic);
+                };
+                if let (Some(x), Some(y)) = (self.read(s, width), self.read(d, width)) {
+                    self.write(d, Expr::select(condition, x, y));
+                }
+            }
+            _ if mnemonic.starts_with("set") && operands.len() == 1 => {
+                let Some(condition) = self.condition(&mnemonic[3..]) else { return self.fallback(mnemonic) };
+                self.write(&operands[0], condition.resize(8, false));
+            }
+            ("bt" | "bts" | "btr" | "btc", [d, bit]) => {
+                let Some(a) = self.read_once(d, width) else { return };
+                let Some(index) = self.read(bit, width) else { return };
+                let index = and(index.resize(width, false), Expr::int(width as u64 - 1, width));
+                let index = self.b.temp(index);
+                let mask = Expr::binary(BinaryOp::Shl, Expr::int(1, width), index);
+                self.b.assign(CF, ne(and(a.clone(), mask.clone()), Expr::int(0, width)));
+                let value = match mnemonic {
+                    "bts" => or(a, mask),
+                    "btr" => and(a, not(mask)),
+                    "btc" => xor(a, mask),
+                    _ => return,
+                };
+                self.write(d, value);
+            }
+            ("bswap", [d]) => {
+                if let Some(a) = self.read(d, width) {
+                    self.write(d, Expr::intrinsic("bswap", vec![a], Type::Int(width)));
+                }
+            }
+            ("cmpxchg", [d, s]) => {
+                let accumulator = self.gpr(0, width);
+                let (Some(old), Some(new), Some(expected)) =
+                    (self.read_once(d, width), self.read(s, width), self.read(&accumulator, width))
+                else {
+                    return;
+                };
+                let result = self.b.temp(sub(expected.clone(), old.clone()));
+                self.flags(ult(expected.clone(), old.clone()), sub_overflow(&expected, &old, &result), &result);
+                self.write(d, Expr::select(Expr::var(ZF), new, old.clone()));
+                // The accumulator is only written when the values differ
+                let (full, changed) = self.merge(&gpr(0, width, true), old);
+                self.b.assign(full, Expr::select(Expr::var(ZF), Expr::var(full), changed));
+            }
+            ("xadd", [d, s]) => {
+                let (Some(a), Some(c)) = (self.read_once(d, width), self.read(s, width)) else { return };
+                let c = self.b.temp(c);
+                let result = self.b.temp(add(a.clone(), c.clone()));
+                self.flags(ult(result.clone(), a.clone()), add_overflow(&a, &c, &result), &result);
+                self.write(s, a);
+                self.write(d, result);
+            }
+            ("clc" | "stc" | "cmc", []) => {
+                let value = match mnemonic {
+                    "clc" => Expr::bool(false),
+                    "stc" => Expr::bool(true),
+                    _ => not(Expr::var(CF)),
+                };
+                self.b.assign(CF, value);
+            }
+            ("cld" | "std", []) => self.b.assign(DF, Expr::bool(mnemonic == "std")),
+            ("stos" | "lods" | "movs" | "cmps" | "scas", _) => self.string(mnemonic, repeat, width),
+            ("cpuid", []) => {
+                self.b.intrinsic("cpuid", vec![]);
+                for number in 0..4 {
+                    self.clobber(self.reg(number));
+                }
+            }
+            ("rdtsc" | "rdtscp", []) => {
+                self.b.intrinsic(mnemonic, vec![]);
+                self.clobber(self.reg(0));
+                self.clobber(self.reg(2));
+            }
+            // Zeroing idioms of the vector registers
+            ("pxor" | "xorps" | "xorpd" | "vpxor" | "vxorps" | "vxorpd", [.., x, y]) if x == y => {
+                self.write(&operands[0], Expr::int(0, width));
+            }
+            ("pand" | "andps" | "andpd" | "por" | "orps" | "orpd" | "pxor" | "xorps" | "xorpd", [d, s]) => {
+                let (Some(a), Some(c)) = (self.read(d, width), self.read(s, width)) else { return };
+                let value = match mnemonic {
+                    "pand" | "andps" | "andpd" => and(a, c),
+                    "por" | "orps" | "orpd" => or(a, c),
+                    _ => xor(a, c),
+                };
+                self.write(d, value);
+            }
+            ("movd" | "movq", [d, s]) => {
+                if let Some(value) = self.read(s, width) {
+                    let bits = self.width(s).unwrap_or(width).min(width);
+                    self.write(d, value.resize(bits, false).resize(width, false));
+                }
+            }
+            ("movaps" | "movups" | "movapd" | "movupd" | "movdqa" | "movdqu", [d, s]) => {
+                if let Some(value) = self.read(s, width) {
+                    self.write(d, value);
+                }
+            }
+            ("movss" | "movsd", [d, s]) => {
+                let bits = if mnemonic == "movss" { 32 } else { 64 };
+                let Some(value) = self.read(s, width) else { return };
+                let value = value.resize(bits, false);
+                match (d, s) {
+                    // Between registers only the low element is copied
+                    (Operand::Register(_), Operand::Register(_)) => {
+                        if let Some(old) = self.read(d, width) {
+                            self.write(d, insert(old, value, 0));
+                        }
+                    }
+                    _ => self.write(d, value.resize(width, false)),
+                }
+            }
+            (
+                "nop" | "endbr64" | "endbr32" | "pause" | "lfence" | "mfence" | "sfence" | "prefetchnta" | "prefetcht0"
+                | "prefetcht1" | "prefetcht2" | "prefetchw",
+                _,
+            ) => {}
+            _ if instruction.flow == FlowKind::Trap => self.b.push(Stmt::Trap),
+            _ if instruction.flow == FlowKind::Syscall => self.b.push(Stmt::syscall()),
+            _ => self.fallback(mnemonic),
+        }
+    }
+
+    // add, sub, cmp, adc and sbb
+    fn arithmetic(&mut self, mnemonic: &str, d: &Operand, s: &Operand, width: u16) {
+        // Subtracting a register from itself gives 0 whatever it holds
+        if mnemonic == "sub" && d == s && matches!(d, Operand::Register(_)) {
+            let zero = Expr::int(0, width);
+            self.flags(Expr::bool(false), Expr::bool(false), &zero);
+            self.write(d, zero);
+            return;
+        }
+        let (Some(a), Some(c)) = (self.read_once(d, width), self.read_once(s, width)) else { return };
+        let carry = Expr::var(CF).resize(width, false);
+        let result = match mnemonic {
+            "add" => add(a.clone(), c.clone()),
+            "adc" => add(add(a.clone(), c.clone()), carry),
+            "sbb" => sub(sub(a.clone(), c.clone()), carry),
+            _ => sub(a.clone(), c.clone()),
+        };
+        let result = self.b.temp(result);
+        let (cf, of) = match mnemonic {
+            "add" => (ult(result.clone(), a.clone()), add_overflow(&a, &c, &result)),
+            "adc" => {
+                let wrapped = or(ult(result.clone(), a.clone()), and(Expr::var(CF), eq(result.clone(), a.clone())));
+                (wrapped, add_overflow(&a, &c, &result))
+            }
+            "sbb" => {
+                let borrow = or(ult(a.clone(), c.clone()), and(Expr::var(CF), eq(a.clone(), c.clone())));
+                (borrow, sub_overflow(&a, &c, &result))
+            }
+            _ => (ult(a.clone(), c.clone()), sub_overflow(&a, &c, &result)),
+        };
+        self.flags(cf, of, &result);
+        if mnemonic != "cmp" {
+            self.write(d, result);
+        }
+    }
+
+    // and, or, xor and test, which clear cf and of
+    fn logic(&mut self, mnemonic: &str, d: &Operand, s: &Operand, width: u16) {
+        let result = if mnemonic == "xor" && d == s && matches!(d, Operand::Register(_)) {
+            Expr::int(0, width)
+        } else {
+            let (Some(a), Some(c)) = (self.read(d, width), self.read(s, width)) else { return };
+            let result = match mnemonic {
+                "or" => or(a, c),
+                "xor" => xor(a, c),
+                _ => and(a, c),
+            };
+            self.b.temp(result)
+        };
+        self.flags(Expr::bool(false), Expr::bool(false), &result);
+        if mnemonic != "test" {
+            self.write(d, result);
+        }
+    }
+
+    // Shifts and rotations by an immediate or cl. The count is masked to 5 bits, or 6 for 64 bit operands, and the
+    // flags are left alone when it is 0.
+    fn shift(&mut self, mnemonic: &str, d: &Operand, count: &Operand, width: u16) {
+        let Some(a) = self.read_once(d, width) else { return };
+        let mask = if width == 64 { 63 } else { 31 };
+        let op = match mnemonic {
+            "shl" | "sal" => BinaryOp::Shl,
+            "shr" => BinaryOp::LShr,
+            "sar" => BinaryOp::AShr,
+            "rol" => BinaryOp::RotL,
+            _ => BinaryOp::RotR,
+        };
+        let bit = |value: Expr, index: Expr| {
+            ne(and(Expr::binary(BinaryOp::LShr, value, index), Expr::int(1, width)), Expr::int(0, width))
+        };
+        let msb = |value: Expr| negative(value);
+
+        if let Operand::Immediate { value, .. } = count {
+            let n = *value as u64 & mask;
+            if n == 0 {
+                return;
+            }
+            let result = self.b.temp(Expr::binary(op, a.clone(), Expr::int(n, width)));
+            let cf = match op {
+                BinaryOp::Shl => bit(a.clone(), Expr::int(width as u64 - n, width)),
+                BinaryOp::LShr | BinaryOp::AShr => bit(a.clone(), Expr::int(n - 1, width)),
+                BinaryOp::RotL => bit(result.clone(), Expr::int(0, width)),
+                _ => msb(result.clone()),
+            };
+            self.b.assign(CF, cf);
+            // The overflow flag is only defined for single bit shifts
+            if n == 1 {
+                let of = match op {
+                    BinaryOp::Shl | BinaryOp::RotL => xor(msb(result.clone()), Expr::var(CF)),
+                    BinaryOp::LShr => msb(a.clone()),
+                    BinaryOp::AShr => Expr::bool(false),
+                    _ => xor(msb(result.clone()), bit(result.clone(), Expr::int(width as u64 - 2, width))),
+                };
+                self.b.assign(OF, of);
+            }
+            if !matches!(op, BinaryOp::RotL | BinaryOp::RotR) {
+                self.result_flags(&result);
+            }
+            self.write(d, result);
+            return;
+        }
+
+        let Some(n) = self.read(count, width) else { return };
+        let n = self.b.temp(and(n.resize(width, false), Expr::int(mask, width)));
+        let result = self.b.temp(Expr::binary(op, a.clone(), n.clone()));
+        let changed = ne(n.clone(), Expr::int(0, width));
+        let cf = match op {
+            BinaryOp::Shl => bit(a.clone(), sub(Expr::int(width as u64, width), n.clone())),
+            BinaryOp::LShr | BinaryOp::AShr => bit(a.clone(), sub(n.clone(), Expr::int(1, width))),
+            BinaryOp::RotL => bit(result.clone(), Expr::int(0, width)),
+            _ => msb(result.clone()),
+        };
+        self.b.assign(CF, Expr::select(changed.clone(), cf, Expr::var(CF)));
+        if !matches!(op, BinaryOp::RotL | BinaryOp::RotR) {
+            for (flag, value) in [(ZF, is_zero(result.clone())), (SF, negative(result.clone()))] {
+                self.b.assign(flag, Expr::select(changed.clone(), value, Expr::var(flag)));
+            }
+            let parity = Expr::intrinsic("parity", vec![result.clone().resize(8, false)], Type::Bool);
+            self.b.assign(PF, Expr::select(changed, parity, Expr::var(PF)));
+        }
+        self.write(d, result);
+    }
+
+    // shld and shrd shift in the bits of a second register
+    fn double_shift(&mut self, mnemonic: &str, d: &Operand, s: &Operand, count: &Operand, width: u16) {
+        let (Some(a), Some(c)) = (self.read_once(d, width), self.read(s, width)) else { return };
+        let n = match count {
+            Operand::Immediate { value, .. } => *value as u64 & if width == 64 { 63 } else { 31 },
+            _ => return self.fallback(mnemonic),
+        };
+        if n == 0 || n >= width as u64 {
+            return;
+        }
+        let (first, second) =
+            if mnemonic == "shld" { (BinaryOp::Shl, BinaryOp::LShr) } else { (BinaryOp::LShr, BinaryOp::Shl) };
+        let result = or(
+            Expr::binary(first, a.clone(), Expr::int(n, width)),
+            Expr::binary(second, c, Expr::int(width as u64 - n, width)),
+        );
+        let result = self.b.temp(result);
+        let index = if mnemonic == "shld" { width as u64 - n } else { n - 1 };
+        let cf = ne(and(Expr::binary(BinaryOp::LShr, a, Expr::int(index, width)), Expr::int(1, width)), Expr::int(0, width));
+        self.b.assign(CF, cf);
+        self.result_flags(&result);
+        self.write(d, result);
+    }
+
+    // One operand mul and imul, which multiply the accumulator into the accumulator and the data register
+    fn multiply(&mut self, signed: bool, s: &Operand, width: u16) {
+        let Some(c) = self.read(s, width) else { return };
+        let a = self.read_register(&gpr(0, width, true));
+        let product = Expr::binary(BinaryOp::Mul, a.resize(width * 2, signed), c.resize(width * 2, signed));
+        let product = self.b.temp(product);
+        let low = self.b.temp(product.clone().resize(width, false));
+        let overflow = if signed {
+            ne(product.clone(), low.clone().resize(width * 2, true))
+        } else {
+            ne(extract(product.clone(), width, width), Expr::int(0, width))
+        };
+        self.b.assign(CF, overflow);
+        self.b.assign(OF, Expr::var(CF));
+        if width == 8 {
+            self.write(&self.gpr(0, 16), product);
+        } else {
+            self.write(&self.gpr(0, width), low);
+            self.write(&self.gpr(2, width), extract(product, width, width));
+        }
+    }
+
+    // imul with two or three operands, which keeps the low half of the product
+    fn signed_multiply(&mut self, d: &Operand, s: &Operand, factor: &Operand, width: u16) {
+        let (Some(a), Some(c)) = (self.read(s, width), self.read(factor, width)) else { return };
+        let product = Expr::binary(BinaryOp::Mul, a.resize(width * 2, true), c.resize(width * 2, true));
+        let product = self.b.temp(product);
+        let low = self.b.temp(product.clone().resize(width, false));
+        self.b.assign(CF, ne(product, low.clone().resize(width * 2, true)));
+        self.b.assign(OF, Expr::var(CF));
+        self.write(d, low);
+    }
+
+    // div and idiv divide the accumulator and data register by the operand, into the quotient in the accumulator and
+    // the remainder in the data register. Division by zero and quotients that don't fit trap, which isn't modelled.
+    fn divide(&mut self, signed: bool, s: &Operand, width: u16) {
+        let Some(divisor) = self.read(s, width) else { return };
+        let dividend = if width == 8 {
+            self.read_register(&gpr(0, 16, true))
+        } else {
+            let high = self.read_register(&gpr(2, width, true)).resize(width * 2, false);
+            let high = Expr::binary(BinaryOp::Shl, high, Expr::int(width as u64, width * 2));
+            or(high, self.read_register(&gpr(0, width, true)).resize(width * 2, false))
+        };
+        let dividend = self.b.temp(dividend);
+        let divisor = self.b.temp(divisor.resize(width * 2, signed));
+        let (divide, remainder) = if signed { (BinaryOp::SDiv, BinaryOp::SRem) } else { (BinaryOp::UDiv, BinaryOp::URem) };
+        let quotient = self.b.temp(Expr::binary(divide, dividend.clone(), divisor.clone()).resize(width, false));
+        let remainder = self.b.temp(Expr::binary(remainder, dividend, divisor).resize(width, false));
+        if width == 8 {
+            self.write(&Operand::Register(gpr(4, 8, false)), remainder);
+            self.write(&self.gpr(0, 8), quotient);
+        } else {
+            self.write(&self.gpr(0, width), quotient);
+            self.write(&self.gpr(2, width), remainder);
+        }
+    }
+
+    // The string instructions, on rsi and rdi. Repeated ones become an intrinsic over rcx elements.
+    fn string(&mut self, mnemonic: &str, repeat: Option<&str>, width: u16) {
+        let operands = &self.instruction.operands;
+        let bits = operands.iter().find_map(|x| match x {
+            Operand::Memory(mem) => Some(mem.size * 8),
+            _ => None,
+        });
+        let bits = bits.unwrap_or(width);
+        let source = self.reg(6);
+        let destination = self.reg(7);
+        let counter = self.reg(1);
+        let step = Expr::int(bits as u64 / 8, self.bits);
+        let uses_source = matches!(mnemonic, "lods" | "movs" | "cmps");
+        let uses_destination = matches!(mnemonic, "stos" | "movs" | "cmps" | "scas");
+
+        if let Some(repeat) = repeat {
+            let mut args = Vec::new();
+            if uses_destination {
+                args.push(Expr::var(destination));
+            }
+            if uses_source {
+                args.push(Expr::var(source));
+            }
+            args.push(Expr::var(counter));
+            if matches!(mnemonic, "stos" | "scas") {
+                args.push(self.read_register(&gpr(0, bits, true)));
+            }
+            self.b.intrinsic(&format!("{} {}{}", repeat, mnemonic, bits / 8), args);
+            if matches!(mnemonic, "cmps" | "scas") {
+                // Where the comparison stops isn't known
+                for var in [source, destination, counter] {
+                    self.clobber(var);
+                }
+                for flag in [CF, PF, ZF, SF, OF] {
+                    self.clobber(flag);
+                }
+                return;
+            }
+            let size = Expr::binary(BinaryOp::Mul, Expr::var(counter), step);
+            let size = self.b.temp(size);
+            if uses_source {
+                self.b.assign(source, add(Expr::var(source), size.clone()));
+            }
+            if uses_destination {
+                self.b.assign(destination, add(Expr::var(destination), size));
+            }
+            if mnemonic == "lods" {
+                self.clobber(self.reg(0));
+            }
+            self.b.assign(counter, Expr::int(0, self.bits));
+            return;
+        }
+
+        let accumulator = self.gpr(0, bits);
+        match mnemonic {
+            "stos" => {
+                let value = self.read(&accumulator, bits).unwrap_or_else(|| Expr::int(0, bits));
+                self.b.store(Expr::var(destination), value);
+            }
+            "lods" => {
+                let value = self.b.load(Expr::var(source), bits);
+                self.write(&accumulator, value);
+            }
+            "movs" => {
+                let value = self.b.temp(self.b.load(Expr::var(source), bits));
+                self.b.store(Expr::var(destination), value);
+            }
+            _ => {
+                let a = if mnemonic == "cmps" {
+                    self.b.temp(self.b.load(Expr::var(source), bits))
+                } else {
+                    self.read(&accumulator, bits).unwrap_or_else(|| Expr::int(0, bits))
+                };
+                let c = self.b.temp(self.b.load(Expr::var(destination), bits));
+                let result = self.b.temp(sub(a.clone(), c.clone()));
+                self.flags(ult(a.clone(), c.clone()), sub_overflow(&a, &c, &result), &result);
+            }
+        }
+        if uses_source {
+            self.b.assign(source, add(Expr::var(source), step.clone()));
+        }
+        if uses_destination {
+            self.b.assign(destination, add(Expr::var(destination), step));
+        }
+    }
+
+    // A variable given a value the IR doesn't compute
+    fn clobber(&mut self, var: Var) {
+        self.b.assign(var, Expr::intrinsic("undefined", vec![], var.ty));
+    }
+
+    // Instructions without a model: the first operand, which is the destination of most of them, is assigned an
+    // intrinsic of all the operands. Comparisons of floating point and vector values only set the flags.
+    fn fallback(&mut self, mnemonic: &str) {
+        let operands = &self.instruction.operands;
+        let args: Vec<Expr> = operands.iter().filter_map(|x| self.read(x, self.width(x).unwrap_or(self.bits))).collect();
+        let compare = ["comis", "ucomis", "vcomis", "vucomis", "ptest", "vptest"].iter().any(|x| mnemonic.starts_with(x));
+        if compare {
+            for flag in [CF, PF, ZF] {
+                self.b.assign(flag, Expr::intrinsic(mnemonic, args.clone(), Type::Bool));
+            }
+            self.b.assign(SF, Expr::bool(false));
+            self.b.assign(OF, Expr::bool(false));
+            return;
+        }
+        match operands.first() {
+            Some(d @ (Operand::Register(_) | Operand::Memory(_))) if self.width(d).is_some() => {
+                let width = self.width(d).unwrap_or(self.bits);
+                let value = Expr::intrinsic(mnemonic, args, Type::Int(width));
+                let d = d.clone();
+                self.write(&d, value);
+            }
+            _ => self.b.intrinsic(mnemonic, args),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::X86Lifter;
+    use crate::disasm::x86::{Mode, X86Decoder};
+    use crate::disasm::Decoder;
+    use crate::ir::Lifter;
+
+    fn lift(code: &[u8]) -> Vec<String> {
+        let instruction = X86Decoder::new(Mode::Bits64).decode(code, 0x1000).unwrap();
+        assert_eq!(instruction.length, code.len());
+        X86Lifter::new(64).lift(&instruction).iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn arithmetic() {
+        // add eax, edi: 32 bit results clear the upper half
+        assert_eq!(lift(&[0x01, 0xf8]), [
+            "t0 = trunc32(rax) + trunc32(rdi)",
+            "cf = t0 <u trunc32(rax)",
+            "of = ((t0 ^ trunc32(rax)) & (t0 ^ trunc32(rdi))) <s 0",
+            "zf = t0 == 0",
+            "sf = t0 <s 0",
+            "pf = parity(trunc8(t0))",
+            "rax = zext64(t0)",
+        ]);
+        // cmp rdi, rsi only sets the flags
+        assert_eq!(lift(&[0x48, 0x39, 0xf7]), [
+            "t0 = rdi - rsi",
+            "cf = rdi <u rsi",
+            "of = ((rdi ^ rsi) & (rdi ^ t0)) <s 0",
+            "zf = t0 == 0",
+            "sf = t0 <s 0",
+            "pf = parity(trunc8(t0))",
+        ]);
+        // shr rax, 3: the carry is the last bit shifted out
+        assert_eq!(lift(&[0x48, 0xc1, 0xe8, 0x03]), [
+            "t0 = rax >>u 3",
+            "cf = ((rax >>u 2) & 1) != 0",
+            "zf = t0 == 0",
+            "sf = t0 <s 0",
+            "pf = parity(trunc8(t0))",
+            "rax = t0",
+        ]);
+        // div esi divides edx:eax
+        assert_eq!(lift(&[0xf7, 0xf6]), [
+            "t0 = (zext64(trunc32(rdx)) << 0x20) | zext64(trunc32(rax))",
+            "t1 = zext64(trunc32(rsi))",
+            "t2 = trunc32(t0 /u t1)",
+            "t3 = trunc32(t0 %u t1)",
+            "rax = zext64(t2)",
+            "rdx = zext64(t3)",
+        ]);
+        // cmovl eax, esi
+        assert_eq!(lift(&[0x0f, 0x4c, 0xc6]), ["rax = zext64((sf ^ of) ? trunc32(rsi) : trunc32(rax))"]);
+    }
+
+    #[test]
+    fn memory() {
+        // mov [rdi + 8], rsi; movzx eax, byte [rdi]; lea rax, [rdi + rsi * 4 + 0x10]; push rbp
+        assert_eq!(lift(&[0x48, 0x89, 0x77, 0x08]), ["mem64[rdi + 8] = rsi"]);
+        assert_eq!(lift(&[0x0f, 0xb6, 0x07]), ["rax = zext64(zext32(mem8[rdi]))"]);
+        assert_eq!(lift(&[0x48, 0x8d, 0x44, 0xb7, 0x10]), ["rax = (rdi + (rsi * 4)) + 0x10"]);
+        assert_eq!(lift(&[0x55]), ["rsp = rsp - 8", "mem64[rsp] = rbp"]);
+    }
+
+    #[test]
+    fn control_flow() {
+        // call 0x1010; jne 0x1012; ret
+        assert_eq!(lift(&[0xe8, 0x0b, 0, 0, 0]), ["call 0x1010"]);
+        assert_eq!(lift(&[0x75, 0x10]), ["if !zf jump 0x1012"]);
+        assert_eq!(lift(&[0xc3]), ["return"]);
+    }
+}
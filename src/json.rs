@@ -0,0 +1,23 @@
+/// A JSON string literal, quotes included
+pub fn string(value: &str) -> String {
+    let mut res = String::with_capacity(value.len() + 2);
+    res.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+/// An address, as a string in hexadecimal since JSON numbers lose precision above 2^53
+pub fn address(value: u64) -> String {
+    format!("\"0x{:x}\"", value)
+}
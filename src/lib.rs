@@ -0,0 +1,28 @@
+//! Parsing, disassembly and analysis of executables. The decster binary is a command line interface to it.
+
+pub mod common;
+pub mod json;
+pub mod bits;
+pub mod endian;
+
+pub mod error;
+
+pub mod instruction_set;
+
+pub mod disasm;
+
+pub mod elf;
+
+pub mod parsable_file;
+
+pub mod dwarf;
+
+pub mod demangle;
+
+pub mod image;
+
+pub mod analysis;
+
+pub mod ir;
+
+pub mod decompile;
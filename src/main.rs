@@ -2,22 +2,41 @@ use std::env::args;
 use std::fs::File;
 use std::io::Read;
 
-mod common;
-mod bits;
-mod endian;
+use decster::bits::{self, PtrType};
+use decster::parsable_file::ParsableFile;
+use decster::{analysis, common, disasm, elf, image};
 
-mod error;
-
-mod instruction_set;
-
-mod elf;
-
-mod parsable_file;
-use parsable_file::ParsableFile;
+mod commands;
 
 fn main() {
-    let path = args().skip(1).next();
-    let path = path.unwrap_or("example_binaries/hello_elf.bin".to_string());
+    let arguments: Vec<String> = args().skip(1).collect();
+    let command = match arguments.first().map(|x| x.as_str()) {
+        Some("decompile") => Some(commands::decompile::run as fn(&[String]) -> Result<(), String>),
+        Some("disasm") => Some(commands::disasm::run as fn(&[String]) -> Result<(), String>),
+        Some("dwarf") => Some(commands::dwarf::run as fn(&[String]) -> Result<(), String>),
+        Some("callgraph") => Some(commands::callgraph::run as fn(&[String]) -> Result<(), String>),
+        Some("cfg") => Some(commands::cfg::run as fn(&[String]) -> Result<(), String>),
+        Some("cfi") => Some(commands::cfi::run as fn(&[String]) -> Result<(), String>),
+        Some("functions") => Some(commands::functions::run as fn(&[String]) -> Result<(), String>),
+        Some("ir") => Some(commands::ir::run as fn(&[String]) -> Result<(), String>),
+        Some("plt") => Some(commands::plt::run as fn(&[String]) -> Result<(), String>),
+        Some("strings") => Some(commands::strings::run as fn(&[String]) -> Result<(), String>),
+        Some("xrefs") => Some(commands::xrefs::run as fn(&[String]) -> Result<(), String>),
+        _ => None,
+    };
+    if let Some(command) = command {
+        if let Err(e) = command(&arguments[1..]) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let path = args().skip(1).find(|x| !x.starts_with("--"));
+    let path = path.unwrap_or_else(|| "example_binaries/hello_elf.bin".to_string());
+    let syntax = if args().any(|x| x == "--att") { disasm::Syntax::Att } else { disasm::Syntax::Intel };
+    // Unknown values in the headers are only warned about, unless asked to be strict
+    let mode = if args().any(|x| x == "--strict") { elf::ParseMode::Strict } else { elf::ParseMode::Lenient };
 
     let mut file = if let Ok(file) = File::open(path.clone()) {
         file
@@ -31,13 +50,23 @@ fn main() {
         eprintln!("Could not read file {}, error {}", path, e);
         return;
     }
-    let contents = ParsableFile::new(&*contents);
+    let contents = ParsableFile::new(&contents);
 
-    let mut parsed = None;
-    match handle_elf_64bit(contents) {
+    let mut parsed: Option<Box<dyn common::ParsedExecutable>> = None;
+    match handle_elf::<bits::SixtyfourBit>(contents.clone(), syntax, mode) {
         Ok(res) => {
             parsed = Some(Box::new(res));
         }
+        Err(elf::ElfParseError::WrongBitwidth(_)) => {
+            match handle_elf::<bits::ThirtytwoBit>(contents, syntax, mode) {
+                Ok(res) => {
+                    parsed = Some(Box::new(res));
+                }
+                Err(e) => {
+                    eprintln!("Error parsing ELF {}: {:?}", path, e);
+                }
+            }
+        }
         Err(e) => {
             eprintln!("Error parsing ELF {}: {:?}", path, e);
         }
@@ -50,14 +79,17 @@ fn main() {
     }
 }
 
-fn handle_elf_64bit(mut contents: ParsableFile<'_>) -> Result<impl common::ParsedExecutable, elf::ElfParseError> {
-    let elf = elf::Elf::<bits::SixtyfourBit>::parse(&mut contents)?;
+fn handle_elf<B: elf::ElfBitwidth>(mut contents: ParsableFile<'_>, syntax: disasm::Syntax, mode: elf::ParseMode) -> Result<elf::Elf<B>, elf::ElfParseError> {
+    let elf = elf::Elf::<B>::parse(&mut contents, mode)?;
+    for warning in elf.warnings.iter() {
+        eprintln!("Warning: {:?}", warning);
+    }
     println!("Parsed elf: {:#X?}", elf);
 
     for (i, section_header) in elf.section_headers.iter().enumerate() {
         println!("Section header #{:X}: {:X?}", i, section_header);
         println!("Name: {:?}", String::from_utf8_lossy(section_header.get_name(&mut contents, &elf)?));
-        if section_header.size < 32 {
+        if section_header.size.to_u64() < 32 {
             println!("Content: {:?}", String::from_utf8_lossy(section_header.get_content(&mut contents)?));
         }
         println!();
@@ -95,9 +127,81 @@ fn handle_elf_64bit(mut contents: ParsableFile<'_>) -> Result<impl common::Parse
         }
     }
 
+    let image = image::Image::from_elf(&elf, &mut contents)?;
+
+    match disasm::decoder_for(&image.target) {
+        Some(decoder) => {
+            // Without symbols, the functions have to be found in the code
+            if elf.symtab_index().is_none() {
+                println!("---\n");
+                println!("Discovered functions:");
+                for function in analysis::discover_functions(&image, &*decoder) {
+                    println!("{:x}..{:x} {:?} {}", function.start, function.end, function.confidence(), function.display_name());
+                }
+            }
+
+            for section_header in elf.section_headers.iter() {
+                if section_header.get_name(&mut contents, &elf)? != b".text" {
+                    continue;
+                }
+
+                println!("---\n");
+                println!("Disassembly of .text:");
+                let content = section_header.get_content(&mut contents)?;
+                disassemble(&*decoder, content, section_header.virtual_address.to_u64(), syntax);
+            }
+        }
+        None => {
+            eprintln!("No disassembler for {}", elf.header.instruction_set.metadata().name);
+        }
+    }
+
     Ok(elf)
 }
 
+fn disassemble(decoder: &dyn disasm::Decoder, bytes: &[u8], address: u64, syntax: disasm::Syntax) {
+    let lines = analysis::linear_sweep(decoder, bytes, address);
+
+    // adrp pairs only give the address they compute when taken together
+    let instructions: Vec<disasm::Instruction> = lines
+        .iter()
+        .filter_map(|x| match x {
+            analysis::Line::Instruction(instruction) => Some(instruction.clone()),
+            _ => None,
+        })
+        .collect();
+    let pairs = disasm::aarch64::address_pairs(&instructions);
+
+    // Instructions in a delay slot execute before the branch, they are indented to show it
+    let mut in_delay_slot = false;
+    for line in lines {
+        match line {
+            analysis::Line::Instruction(instruction) => {
+                let mut text = instruction.render(syntax);
+                if let Some(pair) = pairs.iter().find(|x| x.address == instruction.address) {
+                    text.push_str(&format!("        // 0x{:x}", pair.target));
+                }
+                let indent = if in_delay_slot { " " } else { "" };
+                in_delay_slot = instruction.delay_slot != disasm::DelaySlot::None;
+                println!("{:8x}: {}{}", instruction.address, indent, text);
+            }
+            analysis::Line::Bad { address, error, .. } => {
+                in_delay_slot = false;
+                println!("{:8x}: (bad) {:?}", address, error);
+            }
+            analysis::Line::Data { .. } => {}
+        }
+    }
+}
+
 fn process_generic_parsed(x: &dyn common::ParsedExecutable) {
-    println!("Instruction set: {:?}", x.get_instruction_set());
+    let metadata = x.get_instruction_set().metadata();
+    println!(
+        "Instruction set: {:?} ({}), {} byte pointers and {:?} by default{}",
+        x.get_instruction_set(),
+        metadata.name,
+        metadata.pointer_size,
+        metadata.endianness,
+        if metadata.disassemble { "" } else { ", no disassembler" },
+    );
 }
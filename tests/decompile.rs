@@ -0,0 +1,27 @@
+//! The pseudocode of the example binaries, against the one in tests/golden. After a change to the decompiler
+//! changes it on purpose, the files are written again with `decster decompile main example_binaries/NAME.bin`.
+
+use std::path::Path;
+use std::process::Command;
+
+fn check(binary: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO_BIN_EXE_decster"))
+        .args(["decompile", "main"])
+        .arg(root.join("example_binaries").join(format!("{}.bin", binary)))
+        .output()
+        .expect("decster runs");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let golden = std::fs::read_to_string(root.join("tests/golden").join(format!("{}.c", binary))).expect("golden file");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), golden);
+}
+
+#[test]
+fn hello_elf() {
+    check("hello_elf");
+}
+
+#[test]
+fn hello_elf_optimized() {
+    check("hello_elf_optimized");
+}
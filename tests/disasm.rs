@@ -0,0 +1,50 @@
+//! `decster disasm` on the example binaries: how functions and ranges are selected, and the arguments it refuses.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn disasm(args: &[&str]) -> Output {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    Command::new(env!("CARGO_BIN_EXE_decster"))
+        .arg("disasm")
+        .args(args)
+        .arg(root.join("example_binaries/hello_elf.bin"))
+        .output()
+        .expect("decster runs")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn function_by_name_and_address() {
+    let by_name = stdout(&disasm(&["--function", "main"]));
+    assert!(by_name.contains("000000000000077a <main>:"), "{}", by_name);
+    assert!(by_name.contains("     77a: push rbp"), "{}", by_name);
+    // Only main is listed
+    assert!(!by_name.contains("<frame_dummy>:") && !by_name.contains("<__libc_csu_init>:"), "{}", by_name);
+    assert_eq!(stdout(&disasm(&["--function", "0x77a"])), by_name);
+}
+
+#[test]
+fn range() {
+    let text = stdout(&disasm(&["--range", "0x77a..0x77e"]));
+    let lines: Vec<&str> = text.lines().filter(|x| x.starts_with("     77")).collect();
+    assert_eq!(lines, ["     77a: push rbp", "     77b: mov rbp,rsp"]);
+}
+
+#[test]
+fn bad_arguments() {
+    for args in [&["--range", "0x7f0..0x780"][..], &["--function", "0x123"], &["--function", "main", "--range", "0x0..0x1"]] {
+        let output = disasm(args);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+    }
+    // A second file is not silently ignored
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = disasm(&[root.join("example_binaries/hello_elf_optimized.bin").to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Usage: decster disasm"));
+}
@@ -0,0 +1,15 @@
+int32_t main(void)
+{
+    char local_58[72];
+    int64_t local_10;
+
+    local_10 = *(int64_t *)(fs_base + 40);
+    puts("Hello world!");
+    puts("What's your name?");
+    fgets(local_58, 64, stdin);
+    __printf_chk(1, "Hello, %s!", local_58);
+    if (local_10 != *(int64_t *)(fs_base + 40)) {
+        __stack_chk_fail();
+    }
+    return 0;
+}